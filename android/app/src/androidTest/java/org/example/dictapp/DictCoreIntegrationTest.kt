@@ -1,10 +1,12 @@
 package org.example.dictapp
 
 import android.content.Context
+import android.database.sqlite.SQLiteDatabase
 import androidx.test.ext.junit.runners.AndroidJUnit4
 import androidx.test.platform.app.InstrumentationRegistry
 import com.google.common.truth.Truth.assertThat
 import org.junit.After
+import org.junit.Assert.assertThrows
 import org.junit.Before
 import org.junit.Test
 import org.junit.runner.RunWith
@@ -308,4 +310,22 @@ class DictCoreIntegrationTest {
         assertThat(definition.pronunciations).isInstanceOf(List::class.java)
         assertThat(definition.translations).isInstanceOf(List::class.java)
     }
+
+    // ========================================================================
+    // Error Tests
+    // ========================================================================
+
+    @Test
+    fun search_corruptIndex_throwsInsteadOfEmpty() {
+        // Garbage in the full-text index's segments
+        SQLiteDatabase.openDatabase(testDbPath!!, null, SQLiteDatabase.OPEN_READWRITE).use { db ->
+            db.execSQL("UPDATE words_fts_data SET block = X'00ff00ff' WHERE id > 10")
+        }
+        DictCore.init(testDbPath!!)
+
+        val error = assertThrows(DictException::class.java) {
+            DictCore.searchParsed("hello world", 50)
+        }
+        assertThat(error.code).isEqualTo(DictCore.ERROR_FTS_CORRUPT)
+    }
 }
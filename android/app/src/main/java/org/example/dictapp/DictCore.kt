@@ -35,6 +35,16 @@ object DictCore {
     const val ERROR_NOT_INITIALIZED = 4
     const val ERROR_SEARCH_FAILED = 5
     const val ERROR_JSON_FAILED = 6
+    const val ERROR_DOWNLOAD_FAILED = 7
+    const val ERROR_CHECKSUM_MISMATCH = 8
+    const val ERROR_FTS_CORRUPT = 9
+    const val ERROR_INTERRUPTED = 10
+    const val ERROR_INVALID_QUERY = 11
+
+    // Formats for renderDefinition
+    const val RENDER_HTML = 0
+    const val RENDER_MARKDOWN = 1
+    const val RENDER_PLAIN_TEXT = 2
 
     private val gson = Gson()
 
@@ -52,13 +62,34 @@ object DictCore {
      */
     external fun init(dbPath: String): Int
 
+    /**
+     * Read the database's hot indexes into the page cache, so the first
+     * search is fast. Call after [init], e.g. during the splash screen.
+     *
+     * @return Error code (SUCCESS = 0 on success)
+     */
+    external fun warmUp(): Int
+
+    /**
+     * Stop the query running on the dictionary, e.g. a search for text the
+     * user has since typed over. Call from another thread than the one
+     * searching; the interrupted search throws [DictException] with
+     * [ERROR_INTERRUPTED].
+     *
+     * @return Error code (SUCCESS = 0 on success)
+     */
+    external fun interrupt(): Int
+
     /**
      * Search for words matching the query.
      *
      * @param query Search query string
      * @param limit Maximum number of results to return
      * @param offset Number of results to skip (for pagination)
-     * @return JSON string containing array of SearchResult, or null on error
+     * @return JSON string containing array of SearchResult, or null if the
+     *   dictionary is not initialized
+     * @throws DictException if the search fails, with [ERROR_FTS_CORRUPT],
+     *   [ERROR_INTERRUPTED], [ERROR_INVALID_QUERY] or [ERROR_SEARCH_FAILED]
      */
     external fun search(query: String, limit: Int, offset: Int): String?
 
@@ -70,6 +101,167 @@ object DictCore {
      */
     external fun getDefinition(wordId: Long): String?
 
+    /**
+     * Get synonyms and antonyms of a word, grouped by sense.
+     *
+     * @param word Headword to look up
+     * @param lang Language code of the word (e.g. "en")
+     * @return JSON string containing array of sense groups, or null on error
+     */
+    external fun thesaurus(word: String, lang: String): String?
+
+    /**
+     * Complete a typed prefix to headwords, most frequent first.
+     *
+     * @param prefix Text typed so far
+     * @param n Maximum number of completions
+     * @return Headwords starting with the prefix, or null on error
+     */
+    external fun complete(prefix: String, n: Int): Array<String>?
+
+    /**
+     * Initialize the dictionary database with a configuration.
+     *
+     * @param dbPath Absolute path to the SQLite database file
+     * @param configJson JSON `DictConfig` (search tiers, fuzzy matching, ranking)
+     * @return Error code (SUCCESS = 0 on success)
+     */
+    external fun initWithConfig(dbPath: String, configJson: String): Int
+
+    /**
+     * Render the full definition of a word.
+     *
+     * @param wordId The unique ID of the word
+     * @param format RENDER_HTML, RENDER_MARKDOWN or RENDER_PLAIN_TEXT (for TalkBack)
+     * @param themeJson JSON `ThemeOptions`, or null for the default theme
+     * @return Rendered definition, or null if not found/error
+     */
+    external fun renderDefinition(wordId: Long, format: Int, themeJson: String?): String?
+
+    /**
+     * Download the latest database for a language and install it at
+     * [destPath]. Blocks until done, so call it off the main thread; an
+     * interrupted download resumes on the next call.
+     *
+     * @param baseUrl URL the databases and their manifests are published under
+     * @param language Language of the database (e.g. "english")
+     * @param destPath Absolute path to install the database at
+     * @param listener Notified of progress on the calling thread, or null
+     * @return Error code (SUCCESS = 0 on success)
+     */
+    external fun downloadDatabase(
+        baseUrl: String,
+        language: String,
+        destPath: String,
+        listener: DownloadListener?
+    ): Int
+
+    /**
+     * Check whether a newer database than the open one has been published.
+     *
+     * @param manifestUrl URL of the language's `{language}-latest.json`
+     * @return JSON `AvailableUpdate`, "null" if the database is current, or null on error
+     */
+    external fun checkUpdate(manifestUrl: String): String?
+
+    /**
+     * Strip content from the open dictionary and vacuum it.
+     *
+     * @param optionsJson JSON `CompactOptions` naming what to strip
+     * @return JSON `CompactStats`, or null on error
+     */
+    external fun compact(optionsJson: String): String?
+
+    /**
+     * Get an entry of the user database.
+     *
+     * @param wordId ID of the entry, as returned in a user search result
+     * @return JSON string containing FullDefinition, or null if not found/error
+     */
+    external fun getUserDefinition(wordId: Long): String?
+
+    /**
+     * Report what the SQLite library supports, e.g. whether FTS5 is available.
+     *
+     * @return JSON `Capabilities`, or null if not initialized
+     */
+    external fun capabilities(): String?
+
+    /**
+     * Check whether a dictionary database can be used by this build, without
+     * opening it as the dictionary.
+     *
+     * @param dbPath Absolute path to the SQLite database file
+     * @return JSON `CompatibilityReport`, or null if the file could not be read
+     */
+    external fun checkDb(dbPath: String): String?
+
+    /**
+     * Load the entries expected to be opened next (e.g. the top search
+     * results), so opening them is fast.
+     *
+     * @param wordIds IDs of the words to load
+     * @return Error code (SUCCESS = 0 on success)
+     */
+    external fun prefetchDefinitions(wordIds: LongArray): Int
+
+    /**
+     * Get candidate translations between two languages through English.
+     *
+     * @param word Word in the source language
+     * @param fromLang Language code of the word
+     * @param toLang Language code to translate into
+     * @return JSON string containing array of candidates, or null on error
+     */
+    external fun translatePivot(word: String, fromLang: String, toLang: String): String?
+
+    /**
+     * Find text in the senses of one entry, for find-in-page.
+     *
+     * @param wordId The unique ID of the word
+     * @param needle Text to look for
+     * @return JSON string containing array of matches, or null on error
+     */
+    external fun findInEntry(wordId: Long, needle: String): String?
+
+    /**
+     * Open the user database, which holds bookmarks.
+     *
+     * @param path Absolute path to the user database file (created if missing)
+     * @return Error code (SUCCESS = 0 on success)
+     */
+    external fun openUserData(path: String): Int
+
+    /**
+     * Bookmark an entry, or one of its senses.
+     *
+     * @param wordId The unique ID of the word
+     * @param word Headword of the entry
+     * @param definitionId ID of the sense, or 0 for the whole entry
+     * @param note Note to keep with the bookmark, or null
+     * @param at Unix timestamp of the bookmark
+     * @return Error code (SUCCESS = 0 on success)
+     */
+    external fun addBookmark(wordId: Long, word: String, definitionId: Long, note: String?, at: Long): Int
+
+    /**
+     * Remove the bookmark of an entry, or of one of its senses.
+     *
+     * @param wordId The unique ID of the word
+     * @param definitionId ID of the sense, or 0 for the whole entry
+     * @return Error code (SUCCESS = 0 on success)
+     */
+    external fun removeBookmark(wordId: Long, definitionId: Long): Int
+
+    /**
+     * Get the most recent bookmarks, or the bookmarks of one entry.
+     *
+     * @param wordId The unique ID of the word, or 0 for the most recent bookmarks
+     * @param limit Maximum number of recent bookmarks
+     * @return JSON string containing array of bookmarks, or null on error
+     */
+    external fun getBookmarks(wordId: Long, limit: Int): String?
+
     /**
      * Close the dictionary and free resources.
      */
@@ -81,7 +273,9 @@ object DictCore {
      * @param query Search query string
      * @param limit Maximum number of results
      * @param offset Number of results to skip (for pagination)
-     * @return List of SearchResult objects
+     * @return List of SearchResult objects, empty if nothing matched
+     * @throws DictException if the search fails, so a damaged index or an
+     *   interrupted search is not mistaken for no results
      */
     fun searchParsed(query: String, limit: Int = 50, offset: Int = 0): List<SearchResult> {
         val json = search(query, limit, offset)
@@ -128,6 +322,34 @@ object DictCore {
     private const val TAG = "DictCore"
 }
 
+/**
+ * A failed native call, thrown by [DictCore.search].
+ *
+ * @property code The error code, one of the DictCore ERROR_ constants
+ */
+class DictException(val code: Int, message: String) : Exception(message)
+
+/**
+ * Progress of [DictCore.downloadDatabase].
+ */
+interface DownloadListener {
+    /**
+     * Called as data arrives and while verifying and decompressing.
+     *
+     * @param phase PHASE_DOWNLOADING, PHASE_VERIFYING or PHASE_DECOMPRESSING
+     * @param bytesDone Bytes processed so far in this phase
+     * @param bytesTotal Bytes to process in this phase (compressed size while
+     *   downloading and verifying, decompressed size while decompressing)
+     */
+    fun onProgress(phase: Int, bytesDone: Long, bytesTotal: Long)
+
+    companion object {
+        const val PHASE_DOWNLOADING = 0
+        const val PHASE_VERIFYING = 1
+        const val PHASE_DECOMPRESSING = 2
+    }
+}
+
 /**
  * Search result entry for display in results list.
  */
@@ -8,7 +8,10 @@
 //! - Definition load: < 20ms
 
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
-use dict_core::{get_definition, import_jsonl, init, search};
+use dict_core::{
+    get_definition, import_jsonl, import_jsonl_with_options, init, search, FtsProfile,
+    ImportOptions,
+};
 use std::io::Write;
 use std::time::Duration;
 
@@ -42,6 +45,14 @@ const SAMPLE_ENTRIES: &[&str] = &[
 
 /// Create a test database with realistic data using JSONL import
 fn create_test_db(word_count: usize) -> (tempfile::TempDir, String) {
+    create_test_db_with_options(word_count, &ImportOptions::default())
+}
+
+/// Create a test database, importing with `options`
+fn create_test_db_with_options(
+    word_count: usize,
+    options: &ImportOptions,
+) -> (tempfile::TempDir, String) {
     let dir = tempfile::tempdir().unwrap();
     let db_path = dir.path().join("bench.db");
     let jsonl_path = dir.path().join("data.jsonl");
@@ -63,9 +74,10 @@ fn create_test_db(word_count: usize) -> (tempfile::TempDir, String) {
     drop(file);
 
     // Import the JSONL data
-    import_jsonl(
+    import_jsonl_with_options(
         db_path.to_str().unwrap(),
         jsonl_path.to_str().unwrap(),
+        options,
         |_, _| {},
     )
     .unwrap();
@@ -164,6 +176,46 @@ fn bench_fts_scaling(c: &mut Criterion) {
     group.finish();
 }
 
+/// Search with each full-text index layout, printing how big the index is
+fn bench_fts_profile(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fts_profile");
+    group.measurement_time(Duration::from_secs(10));
+
+    for profile in [FtsProfile::Full, FtsProfile::Compact] {
+        let options = ImportOptions {
+            fts: profile,
+            ..ImportOptions::default()
+        };
+        let (_dir, db_path) = create_test_db_with_options(10000, &options);
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        let index_bytes: i64 = conn
+            .query_row("SELECT SUM(length(block)) FROM words_fts_data", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        let file_bytes = std::fs::metadata(&db_path).unwrap().len();
+        println!(
+            "fts_profile/{}: index {} bytes, database {} bytes",
+            profile.name(),
+            index_bytes,
+            file_bytes
+        );
+
+        let handle = init(&db_path).unwrap();
+        for (name, query) in [("prefix_short", "hel"), ("multi_word", "hello world")] {
+            group.bench_with_input(
+                BenchmarkId::new(profile.name(), name),
+                &query,
+                |b, query| {
+                    b.iter(|| search(black_box(&handle), black_box(query), 50));
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
 // ============================================================================
 // Fuzzy Search (Levenshtein) Benchmarks
 // ============================================================================
@@ -404,6 +456,7 @@ criterion_group!(
     bench_startup,
     bench_fts_search,
     bench_fts_scaling,
+    bench_fts_profile,
     bench_levenshtein,
     bench_definition_loading,
     bench_import,
@@ -7,11 +7,19 @@ CREATE TABLE IF NOT EXISTS words (
     word TEXT NOT NULL,
     pos TEXT NOT NULL,
     language TEXT NOT NULL,
-    etymology_num INTEGER DEFAULT 0
+    lang_code TEXT NOT NULL DEFAULT '',
+    etymology_num INTEGER DEFAULT 0,
+    frequency INTEGER,  -- corpus count from an external frequency list, NULL if unknown
+    search_key TEXT,  -- normalize::search_key of the headword
+    anagram_key TEXT  -- games::anagram_key of the headword, NULL if not playable
 );
 
 CREATE INDEX IF NOT EXISTS idx_words_word ON words(word);
 CREATE INDEX IF NOT EXISTS idx_words_language ON words(language);
+-- Anagram and rack lookups (games)
+CREATE INDEX IF NOT EXISTS idx_words_anagram_key ON words(anagram_key);
+-- Alphabetical browsing by language (get_words_by_language)
+CREATE INDEX IF NOT EXISTS idx_words_language_word ON words(language, word COLLATE NOCASE);
 
 -- Full-text search using FTS5
 CREATE VIRTUAL TABLE IF NOT EXISTS words_fts USING fts5(
@@ -41,6 +49,10 @@ CREATE TABLE IF NOT EXISTS definitions (
     definition TEXT NOT NULL,
     examples TEXT,  -- JSON array
     tags TEXT,      -- JSON array
+    form_of TEXT,   -- headword a "plural of cat" style gloss points at
+    form_of_id INTEGER,  -- its word id, resolved after import
+    source_id INTEGER,   -- source of senses merged in from another dictionary, NULL for the entry's own
+    sense_id TEXT,       -- stable id of the sense across rebuilds (kaikki's, or a hash)
     FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE
 );
 
@@ -53,10 +65,96 @@ CREATE TABLE IF NOT EXISTS pronunciations (
     ipa TEXT,
     audio_url TEXT,
     accent TEXT,
+    rhyme TEXT,  -- ipa::rhyme_key of the IPA, NULL if it has no vowel
+    ipa_key TEXT,  -- ipa::search_key of the IPA
     FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE
 );
 
 CREATE INDEX IF NOT EXISTS idx_pronunciations_word_id ON pronunciations(word_id);
+-- Lookup by transcription (search::by_ipa)
+CREATE INDEX IF NOT EXISTS idx_pronunciations_ipa_key ON pronunciations(ipa_key);
+
+-- Mandarin pinyin readings (see pinyin::Pinyin)
+CREATE TABLE IF NOT EXISTS pinyin (
+    id INTEGER PRIMARY KEY,
+    word_id INTEGER NOT NULL,
+    letters TEXT NOT NULL,  -- toneless, e.g. 'nihao'
+    tones TEXT NOT NULL,    -- tone numbers in order, e.g. '33'
+    FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_pinyin_letters ON pinyin(letters);
+
+-- Japanese kana readings, folded to hiragana
+CREATE TABLE IF NOT EXISTS kana (
+    id INTEGER PRIMARY KEY,
+    word_id INTEGER NOT NULL,
+    reading TEXT NOT NULL,
+    FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_kana_reading ON kana(reading);
+
+-- Hangul headwords decomposed into jamo for incremental prefix search
+CREATE TABLE IF NOT EXISTS jamo (
+    word_id INTEGER PRIMARY KEY,
+    jamo TEXT NOT NULL,
+    FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_jamo_jamo ON jamo(jamo);
+
+-- Inflected and alternative forms pointing at their headword
+CREATE TABLE IF NOT EXISTS forms (
+    id INTEGER PRIMARY KEY,
+    word_id INTEGER NOT NULL,
+    form TEXT NOT NULL,
+    tags TEXT,  -- JSON array, e.g. ["past", "participle"]
+    FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_forms_form ON forms(form);
+
+-- Synonyms and antonyms, per sense when Wiktionary gives the sense
+CREATE TABLE IF NOT EXISTS relations (
+    id INTEGER PRIMARY KEY,
+    word_id INTEGER NOT NULL,
+    definition_id INTEGER,  -- NULL for relations of the whole entry
+    kind TEXT NOT NULL,     -- "synonym" or "antonym"
+    target TEXT NOT NULL,
+    sense TEXT,             -- Wiktionary's label for entry-level relations
+    FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE,
+    FOREIGN KEY (definition_id) REFERENCES definitions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_relations_word_id ON relations(word_id);
+
+-- Emoji, punctuation and signs, stored without variation selectors
+CREATE TABLE IF NOT EXISTS symbols (
+    id INTEGER PRIMARY KEY,
+    word_id INTEGER NOT NULL,
+    symbol TEXT NOT NULL,
+    name TEXT,      -- e.g. "slightly smiling face"
+    keywords TEXT,  -- JSON array, e.g. ["face", "smile"]
+    FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_symbols_symbol ON symbols(symbol);
+
+-- Distinct headwords sorted by lowercased key, for autocompletion
+CREATE TABLE IF NOT EXISTS completions (
+    key TEXT NOT NULL,   -- lowercased headword
+    word TEXT NOT NULL,
+    weight INTEGER NOT NULL DEFAULT 0,  -- highest frequency of the headword
+    PRIMARY KEY (key, word)
+) WITHOUT ROWID;
+
+-- Completions by weight under their first one and two characters, so the
+-- best completions of a short prefix are read without ranking every key
+CREATE INDEX IF NOT EXISTS idx_completions_head1
+    ON completions(substr(key, 1, 1), weight DESC, key, word);
+CREATE INDEX IF NOT EXISTS idx_completions_head2
+    ON completions(substr(key, 1, 2), weight DESC, key, word);
 
 -- Etymology
 CREATE TABLE IF NOT EXISTS etymologies (
@@ -77,5 +175,29 @@ CREATE TABLE IF NOT EXISTS translations (
     FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE
 );
 
-CREATE INDEX IF NOT EXISTS idx_translations_word_id ON translations(word_id);
+CREATE INDEX IF NOT EXISTS idx_translations_word_language ON translations(word_id, target_language);
 CREATE INDEX IF NOT EXISTS idx_translations_language ON translations(target_language);
+
+-- Where entries come from and under which license they may be shown
+CREATE TABLE IF NOT EXISTS sources (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    license TEXT NOT NULL,
+    license_url TEXT,
+    page_url TEXT  -- URL of an entry's source page, {word} is replaced by the headword
+);
+
+-- Source of entries not from the primary (first) source, and page revisions
+CREATE TABLE IF NOT EXISTS entry_sources (
+    word_id INTEGER PRIMARY KEY,
+    source_id INTEGER NOT NULL,
+    revision INTEGER,
+    FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE,
+    FOREIGN KEY (source_id) REFERENCES sources(id)
+);
+
+-- Build metadata (key/value)
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
@@ -0,0 +1,432 @@
+//! Pronunciation audio prefetch
+//!
+//! Downloads the audio files referenced by a set of words (e.g. a bookmark
+//! list) into a local cache directory so they play without a connection.
+//!
+//! Downloads are resumable: data is written to `{file}.part` and renamed
+//! once complete, and an interrupted `.part` file is continued with an HTTP
+//! range request on the next run. Files already in the cache are skipped,
+//! so calling [`prefetch`] again after a failure only fetches what is
+//! missing.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use rusqlite::params_from_iter;
+
+use crate::{DictHandle, Result};
+
+/// Timeout for connecting and for each read from the server
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Wikimedia rejects requests without a descriptive User-Agent
+const USER_AGENT: &str = concat!(
+    "dict-app/",
+    env!("CARGO_PKG_VERSION"),
+    " (offline audio prefetch)"
+);
+
+/// Maximum number of bound parameters per SQL `IN (...)` query
+const IDS_PER_QUERY: usize = 500;
+
+/// Progress of a running prefetch
+#[derive(Debug, Clone, Default)]
+pub struct PrefetchProgress {
+    /// Files finished so far (downloaded, already cached or failed)
+    pub files_done: usize,
+    /// Files that need to be checked
+    pub files_total: usize,
+    /// Bytes downloaded so far during this call
+    pub bytes_downloaded: u64,
+}
+
+/// Outcome of a prefetch
+#[derive(Debug, Clone, Default)]
+pub struct PrefetchStats {
+    /// Distinct audio files referenced by the words
+    pub files_total: usize,
+    /// Files that were already in the cache
+    pub already_cached: usize,
+    /// Files downloaded (or completed) by this call
+    pub downloaded: usize,
+    /// Files that could not be downloaded; retry to resume them
+    pub failed: usize,
+    /// Bytes downloaded by this call
+    pub bytes_downloaded: u64,
+}
+
+/// Estimated download size for a set of words
+#[derive(Debug, Clone, Default)]
+pub struct SizeEstimate {
+    /// Distinct audio files referenced by the words
+    pub files_total: usize,
+    /// Files that are already fully cached
+    pub files_cached: usize,
+    /// Bytes still to download for files with a known size
+    pub bytes_remaining: u64,
+    /// Files whose size the server did not report
+    pub files_unknown_size: usize,
+}
+
+/// Download all pronunciation audio for `word_ids` into `cache_dir`
+///
+/// Up to `concurrency` files are downloaded at once. `progress` is called
+/// from the worker threads after each file and as data arrives. Individual
+/// download failures are counted in [`PrefetchStats::failed`] rather than
+/// aborting the whole prefetch.
+pub fn prefetch(
+    handle: &DictHandle,
+    word_ids: &[i64],
+    cache_dir: &Path,
+    concurrency: usize,
+    progress: impl Fn(&PrefetchProgress) + Sync,
+) -> Result<PrefetchStats> {
+    let urls = get_audio_urls(handle, word_ids)?;
+    fs::create_dir_all(cache_dir)?;
+
+    let agent = agent();
+    let files_done = AtomicUsize::new(0);
+    let bytes_downloaded = AtomicU64::new(0);
+    let report = || {
+        progress(&PrefetchProgress {
+            files_done: files_done.load(Ordering::Relaxed),
+            files_total: urls.len(),
+            bytes_downloaded: bytes_downloaded.load(Ordering::Relaxed),
+        })
+    };
+
+    let outcomes = run_pool(&urls, concurrency, |url| {
+        let dest = cached_path(cache_dir, url);
+        let outcome = if dest.exists() {
+            FileOutcome::Cached
+        } else {
+            let on_bytes = |n: u64| {
+                bytes_downloaded.fetch_add(n, Ordering::Relaxed);
+                report();
+            };
+            match download(&agent, url, &dest, &on_bytes) {
+                Ok(()) => FileOutcome::Downloaded,
+                Err(e) => {
+                    log::warn!("Failed to download {}: {}", url, e);
+                    FileOutcome::Failed
+                }
+            }
+        };
+        files_done.fetch_add(1, Ordering::Relaxed);
+        report();
+        outcome
+    });
+
+    let count = |kind: FileOutcome| outcomes.iter().filter(|o| **o == kind).count();
+    Ok(PrefetchStats {
+        files_total: urls.len(),
+        already_cached: count(FileOutcome::Cached),
+        downloaded: count(FileOutcome::Downloaded),
+        failed: count(FileOutcome::Failed),
+        bytes_downloaded: bytes_downloaded.load(Ordering::Relaxed),
+    })
+}
+
+/// Estimate how much [`prefetch`] would download for `word_ids`
+///
+/// Sizes come from `HEAD` requests (at most `concurrency` at once) for files
+/// not yet cached; data already in `.part` files is subtracted.
+pub fn estimate_size(
+    handle: &DictHandle,
+    word_ids: &[i64],
+    cache_dir: &Path,
+    concurrency: usize,
+) -> Result<SizeEstimate> {
+    let urls = get_audio_urls(handle, word_ids)?;
+    let agent = agent();
+
+    let sizes = run_pool(&urls, concurrency, |url| {
+        let dest = cached_path(cache_dir, url);
+        if dest.exists() {
+            return None;
+        }
+        let partial = fs::metadata(partial_path(&dest))
+            .map(|m| m.len())
+            .unwrap_or(0);
+        let length = agent
+            .head(url)
+            .call()
+            .ok()
+            .and_then(|response| response.header("Content-Length")?.parse::<u64>().ok());
+        Some(length.map(|length| length.saturating_sub(partial)))
+    });
+
+    let mut estimate = SizeEstimate {
+        files_total: urls.len(),
+        ..SizeEstimate::default()
+    };
+    for size in sizes {
+        match size {
+            None => estimate.files_cached += 1,
+            Some(None) => estimate.files_unknown_size += 1,
+            Some(Some(bytes)) => estimate.bytes_remaining += bytes,
+        }
+    }
+    Ok(estimate)
+}
+
+/// Path an audio URL is cached under inside `cache_dir`
+///
+/// Uses the URL's file name (Wikimedia Commons names are unique) with
+/// characters outside `[A-Za-z0-9._-]` replaced by `_`.
+pub fn cached_path(cache_dir: &Path, url: &str) -> PathBuf {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let mut safe: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if safe.trim_matches('.').is_empty() {
+        safe = "audio".to_string();
+    }
+    cache_dir.join(safe)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileOutcome {
+    Cached,
+    Downloaded,
+    Failed,
+}
+
+/// Distinct audio URLs of the pronunciations of the given words
+fn get_audio_urls(handle: &DictHandle, word_ids: &[i64]) -> Result<Vec<String>> {
+    let mut urls: Vec<String> = Vec::new();
+    for chunk in word_ids.chunks(IDS_PER_QUERY) {
+        let placeholders = vec!["?"; chunk.len()].join(",");
+        let mut stmt = handle.conn.prepare(&format!(
+            "SELECT DISTINCT audio_url FROM pronunciations
+             WHERE audio_url IS NOT NULL AND audio_url != '' AND word_id IN ({})",
+            placeholders
+        ))?;
+        let rows = stmt.query_map(params_from_iter(chunk), |row| row.get::<_, String>(0))?;
+        for url in rows {
+            urls.push(url?);
+        }
+    }
+    urls.sort();
+    urls.dedup();
+    Ok(urls)
+}
+
+fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout_connect(HTTP_TIMEOUT)
+        .timeout_read(HTTP_TIMEOUT)
+        .user_agent(USER_AGENT)
+        .build()
+}
+
+fn partial_path(dest: &Path) -> PathBuf {
+    let mut name = dest.as_os_str().to_owned();
+    name.push(".part");
+    PathBuf::from(name)
+}
+
+/// Download `url` to `dest`, resuming from an existing `.part` file
+fn download(
+    agent: &ureq::Agent,
+    url: &str,
+    dest: &Path,
+    on_bytes: &dyn Fn(u64),
+) -> std::io::Result<()> {
+    let partial = partial_path(dest);
+    let existing = fs::metadata(&partial).map(|m| m.len()).unwrap_or(0);
+
+    let mut request = agent.get(url);
+    if existing > 0 {
+        request = request.set("Range", &format!("bytes={}-", existing));
+    }
+    let response = match request.call() {
+        Ok(response) => response,
+        // The partial file already holds the whole body
+        Err(ureq::Error::Status(416, _)) if existing > 0 => return fs::rename(&partial, dest),
+        Err(e) => return Err(std::io::Error::other(e)),
+    };
+
+    // Servers that ignore the range send the whole file again
+    let mut file = if response.status() == 206 {
+        OpenOptions::new().append(true).open(&partial)?
+    } else {
+        File::create(&partial)?
+    };
+
+    let mut reader = response.into_reader();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        file.write_all(&buffer[..n])?;
+        on_bytes(n as u64);
+    }
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&partial, dest)
+}
+
+/// Apply `f` to every item using up to `concurrency` threads, keeping order
+fn run_pool<T: Sync, R: Send>(
+    items: &[T],
+    concurrency: usize,
+    f: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+    let workers = concurrency.clamp(1, items.len().max(1));
+
+    let mut results: Vec<(usize, R)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(i) else {
+                            return done;
+                        };
+                        done.push((i, f(item)));
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("audio worker thread panicked"))
+            .collect()
+    });
+
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_database, insert_pronunciation, insert_word};
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    /// Serve `body` over HTTP for every request, honoring `Range: bytes=N-`
+    fn serve(body: &'static [u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = Vec::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    request.push(line.to_ascii_lowercase());
+                }
+                let range_start = request
+                    .iter()
+                    .find_map(|l| l.strip_prefix("range: bytes="))
+                    .and_then(|r| r.trim().trim_end_matches('-').parse::<usize>().ok());
+                let (status, part) = match range_start {
+                    Some(start) => ("206 Partial Content", &body[start..]),
+                    None => ("200 OK", body),
+                };
+                let head = request[0].starts_with("head");
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    part.len()
+                )
+                .unwrap();
+                if !head {
+                    stream.write_all(part).unwrap();
+                }
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    #[test]
+    fn test_cached_path_sanitizes_file_name() {
+        let dir = Path::new("/cache");
+        assert_eq!(
+            cached_path(dir, "https://upload.wikimedia.org/a/ab/En-us-hello.ogg"),
+            dir.join("En-us-hello.ogg")
+        );
+        assert_eq!(
+            cached_path(dir, "https://example.com/x/Fr-bonjour (1).ogg?download"),
+            dir.join("Fr-bonjour__1_.ogg")
+        );
+        assert_eq!(
+            cached_path(dir, "https://example.com/.."),
+            dir.join("audio")
+        );
+    }
+
+    #[test]
+    fn test_prefetch_resumes_and_skips_cached() {
+        let base = serve(b"0123456789abcdef");
+        let dir = tempfile::tempdir().unwrap();
+        let handle = init_database(dir.path().join("test.db").to_str().unwrap()).unwrap();
+
+        let hello = insert_word(&handle.conn, "hello", "noun", "English", "en", 0).unwrap();
+        let world = insert_word(&handle.conn, "world", "noun", "English", "en", 0).unwrap();
+        let none = insert_word(&handle.conn, "none", "noun", "English", "en", 0).unwrap();
+        for (id, url) in [
+            (hello, "hello.ogg"),
+            (hello, "hello.ogg"),
+            (world, "world.ogg"),
+        ] {
+            let url = format!("{}/{}", base, url);
+            insert_pronunciation(&handle.conn, id, Some("/x/"), Some(&url), None).unwrap();
+        }
+        insert_pronunciation(&handle.conn, none, Some("/x/"), None, None).unwrap();
+
+        let cache = dir.path().join("audio");
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("world.ogg.part"), b"0123").unwrap();
+
+        let ids = [hello, world, none];
+        let estimate = estimate_size(&handle, &ids, &cache, 2).unwrap();
+        assert_eq!(estimate.files_total, 2);
+        assert_eq!(estimate.bytes_remaining, 16 + 12);
+
+        let stats = prefetch(&handle, &ids, &cache, 2, |_| {}).unwrap();
+        assert_eq!(stats.files_total, 2);
+        assert_eq!(stats.downloaded, 2);
+        assert_eq!(stats.failed, 0);
+        assert_eq!(stats.bytes_downloaded, 16 + 12);
+        assert_eq!(
+            fs::read(cache.join("world.ogg")).unwrap(),
+            b"0123456789abcdef"
+        );
+        assert!(!cache.join("world.ogg.part").exists());
+
+        let again = prefetch(&handle, &ids, &cache, 2, |_| {}).unwrap();
+        assert_eq!(again.already_cached, 2);
+        assert_eq!(again.bytes_downloaded, 0);
+        assert_eq!(
+            estimate_size(&handle, &ids, &cache, 2)
+                .unwrap()
+                .files_cached,
+            2
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! In-memory cache of full definitions
+//!
+//! Each handle keeps the entries it loaded last, up to
+//! `DictConfig::definition_cache` of them, keyed by word id and the
+//! options they were loaded with. When full, the least recently used
+//! entry is dropped.
+
+use std::collections::HashMap;
+
+use crate::models::{DefinitionOptions, FullDefinition};
+
+#[derive(Debug, Default)]
+pub(crate) struct DefinitionCache {
+    /// Entries and when each was last used
+    entries: HashMap<(i64, DefinitionOptions), (FullDefinition, u64)>,
+    clock: u64,
+}
+
+impl DefinitionCache {
+    pub(crate) fn get(
+        &mut self,
+        word_id: i64,
+        options: &DefinitionOptions,
+    ) -> Option<FullDefinition> {
+        self.clock += 1;
+        let (definition, used) = self.entries.get_mut(&(word_id, options.clone()))?;
+        *used = self.clock;
+        Some(definition.clone())
+    }
+
+    pub(crate) fn contains(&self, word_id: i64, options: &DefinitionOptions) -> bool {
+        self.entries.contains_key(&(word_id, options.clone()))
+    }
+
+    /// Drop the entries of `word_id` and those inlining its definitions
+    /// (form-of senses pointing at it)
+    pub(crate) fn remove_word(&mut self, word_id: i64) {
+        self.entries.retain(|(id, _), (definition, _)| {
+            *id != word_id
+                && !definition
+                    .definitions
+                    .iter()
+                    .filter_map(|d| d.form_of.as_ref())
+                    .any(|form_of| form_of.word_id == Some(word_id))
+        });
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Keep `definition`, dropping the least recently used entries beyond
+    /// `capacity`
+    pub(crate) fn insert(
+        &mut self,
+        word_id: i64,
+        options: &DefinitionOptions,
+        definition: FullDefinition,
+        capacity: usize,
+    ) {
+        if capacity == 0 {
+            return;
+        }
+        self.clock += 1;
+        self.entries
+            .insert((word_id, options.clone()), (definition, self.clock));
+        while self.entries.len() > capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(key) => self.entries.remove(&key),
+                None => break,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_least_recently_used_dropped() {
+        let mut cache = DefinitionCache::default();
+        let options = DefinitionOptions::default();
+        let def = |word: &str| {
+            FullDefinition::new(word.into(), "noun".into(), "English".into(), "en".into())
+        };
+        cache.insert(1, &options, def("a"), 2);
+        cache.insert(2, &options, def("b"), 2);
+        assert_eq!(cache.get(1, &options).unwrap().word, "a");
+        cache.insert(3, &options, def("c"), 2);
+        assert!(cache.contains(1, &options));
+        assert!(!cache.contains(2, &options));
+        assert!(cache.contains(3, &options));
+
+        let brief = DefinitionOptions {
+            inline_form_of: true,
+            ..Default::default()
+        };
+        assert!(cache.get(1, &brief).is_none());
+        cache.insert(4, &options, def("d"), 0);
+        assert!(!cache.contains(4, &options));
+    }
+}
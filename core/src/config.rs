@@ -0,0 +1,334 @@
+//! Per-handle settings
+//!
+//! A [`DictConfig`] is given to [`crate::init_with_config`] and kept on the
+//! [`crate::DictHandle`], so every search and lookup through the handle
+//! uses the same cache, fuzzy matching, preview and ranking settings. It
+//! is (de)serializable so apps can pass it over the FFI as JSON; missing
+//! fields take their defaults.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::PreviewOptions;
+
+/// Settings for a dictionary handle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DictConfig {
+    /// SQLite page cache in KiB; None keeps SQLite's default (about 2 MB)
+    pub page_cache_kib: Option<u32>,
+    /// Prepared statements kept ready on the connection
+    pub statement_cache: usize,
+    /// Full definitions kept in memory for repeat lookups and
+    /// [`crate::db::prefetch_definitions`]; 0 turns the cache off
+    pub definition_cache: usize,
+    pub fuzzy: FuzzyConfig,
+    /// Which search tiers run for a query
+    pub tiers: TierConfig,
+    /// Ranking of full-text matches
+    pub fts: FtsConfig,
+    /// Previews for searches that don't pass their own
+    pub preview: PreviewOptions,
+    pub ranking: RankingStrategy,
+    /// User database (bookmarks, own entries) attached as schema `user`
+    pub user_db: Option<PathBuf>,
+    /// How entries in the user database mix into search results
+    pub overlay: OverlayConfig,
+    /// Hiding or flagging offensive and vulgar entries
+    pub content_filter: ContentFilter,
+    /// Demoting or hiding obsolete and archaic senses
+    pub dated_senses: DatedSenses,
+    /// Target languages of the translations loaded with an entry (as
+    /// stored: language codes, or names where Wiktionary gave no code);
+    /// None loads all of them
+    pub translation_languages: Option<Vec<String>>,
+    /// Accents whose pronunciations come first, most preferred first:
+    /// accent tags ("GenAm") or regions ("US", which takes in
+    /// "General-American" too). Also gives search results an IPA
+    /// (`SearchResult::ipa`)
+    pub preferred_accents: Vec<String>,
+    /// Examples loaded per sense, the first ones stored; None loads all of
+    /// them. Keeps entries with dozens of examples small over FFI
+    pub max_examples_per_sense: Option<usize>,
+    /// Characters replaced in queries before matching, by default curly
+    /// quotes and typographic dashes (see [`crate::normalize::PUNCTUATION`],
+    /// which import applies to headwords)
+    pub query_replacements: Vec<(char, char)>,
+    /// Whether queries have ligatures, fullwidth letters and lookalike
+    /// letters of other scripts replaced (see
+    /// [`crate::normalize::confusables`]), for text pasted from PDFs
+    pub confusables: bool,
+    /// Whether queries missing from the dictionary are looked up by
+    /// built-in suffix rules and irregular forms ("dictionaries",
+    /// "mice"), besides the imported forms table
+    pub lemma_rules: bool,
+    /// Spelling shown where a headword has a US/UK pair ("color" and
+    /// "colour"): search results give the entry of this spelling, and
+    /// results and entries list the other in `also_spelled`. None shows
+    /// both as they are
+    pub spelling: Option<SpellingRegion>,
+}
+
+impl Default for DictConfig {
+    fn default() -> Self {
+        Self {
+            page_cache_kib: None,
+            statement_cache: 16,
+            definition_cache: 0,
+            fuzzy: FuzzyConfig::default(),
+            tiers: TierConfig::default(),
+            fts: FtsConfig::default(),
+            preview: PreviewOptions::default(),
+            ranking: RankingStrategy::default(),
+            user_db: None,
+            overlay: OverlayConfig::default(),
+            content_filter: ContentFilter::default(),
+            dated_senses: DatedSenses::default(),
+            translation_languages: None,
+            preferred_accents: Vec::new(),
+            max_examples_per_sense: None,
+            query_replacements: crate::normalize::PUNCTUATION.to_vec(),
+            confusables: true,
+            lemma_rules: true,
+            spelling: None,
+        }
+    }
+}
+
+/// Typo-tolerant matching
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FuzzyConfig {
+    /// Whether search falls back to fuzzy matches at all
+    pub enabled: bool,
+    /// Largest Levenshtein distance accepted
+    pub max_distance: usize,
+    /// Shortest query (in bytes) that gets fuzzy matches
+    pub min_query_length: usize,
+}
+
+impl Default for FuzzyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_distance: 2,
+            min_query_length: 3,
+        }
+    }
+}
+
+/// Running the search tiers (exact, prefix, full text, fuzzy, ...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TierConfig {
+    pub stop: StopBehavior,
+    /// Candidates each tier may return with `StopBehavior::RunAll` (at
+    /// least the requested page)
+    pub candidate_budget: u32,
+}
+
+impl Default for TierConfig {
+    fn default() -> Self {
+        Self {
+            stop: StopBehavior::default(),
+            candidate_budget: 50,
+        }
+    }
+}
+
+/// When a search stops trying further tiers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopBehavior {
+    /// Once the requested page is full: fastest, but a close fuzzy match
+    /// never shows when there are enough prefix matches
+    #[default]
+    WhenFull,
+    /// Every tier runs, and all candidates are merged by score
+    RunAll,
+}
+
+/// Weighting of full-text matches
+///
+/// FTS5 ranks matches with bm25; `weights` scale how much a match in
+/// each column of the full-text index counts, in column order (`word`
+/// only for now). Columns without a weight get 1.0. The raw rank is reported in
+/// [`crate::SearchResult::fts_rank`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FtsConfig {
+    pub weights: Vec<f64>,
+}
+
+/// How results of equal match quality are ordered
+///
+/// Results always come in bands: exact and phonetic matches, then prefix,
+/// full-text and fuzzy matches. The strategy orders each band.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RankingStrategy {
+    /// By score alone (shorter prefix matches first, FTS rank, ...)
+    #[default]
+    Relevance,
+    /// Most frequent words first, by the imported frequency list
+    Frequency,
+    /// Alphabetically, case-insensitive
+    Alphabetical,
+}
+
+/// Searching the user database alongside the shipped one
+///
+/// A user database with a `words` and a `definitions` table (the shipped
+/// schema, e.g. made with `db::init_database`) has its entries matched by
+/// headword (exact, prefix and fuzzy) and merged into the results as
+/// [`crate::EntryOrigin::User`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OverlayConfig {
+    pub precedence: OverlayPrecedence,
+    /// Subtracted from the scores of user entries (see [`crate::SearchResult::score`]);
+    /// 0.5 puts a user prefix match between the shipped exact and prefix matches
+    pub boost: f64,
+}
+
+impl Default for OverlayConfig {
+    fn default() -> Self {
+        Self {
+            precedence: OverlayPrecedence::default(),
+            boost: 0.5,
+        }
+    }
+}
+
+/// Where user entries go among the shipped ones
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayPrecedence {
+    /// Interleaved by score, with user entries boosted
+    #[default]
+    Boost,
+    /// Every user match before any shipped one
+    UserFirst,
+    /// Boosted, and a user entry hides shipped entries with the same
+    /// headword (case-insensitive)
+    Replace,
+    /// The user database is not searched
+    Off,
+}
+
+/// Filtering entries by the labels of their senses
+///
+/// An entry matches when any of its senses carries one of `tags`. Search
+/// hides or flags matching entries (see [`crate::SearchResult::flagged`]);
+/// random words and the word of the day never pick them while the filter
+/// is on. Only entries of the shipped dictionary are checked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContentFilter {
+    pub mode: ContentFilterMode,
+    /// Sense tags that make an entry match
+    pub tags: Vec<String>,
+}
+
+impl Default for ContentFilter {
+    fn default() -> Self {
+        Self {
+            mode: ContentFilterMode::default(),
+            tags: ["offensive", "vulgar", "derogatory", "slur", "pejorative"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+impl ContentFilter {
+    /// Whether the filter is switched on
+    pub fn is_active(&self) -> bool {
+        self.mode != ContentFilterMode::Off && !self.tags.is_empty()
+    }
+}
+
+/// What happens to entries matching the content filter
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentFilterMode {
+    /// No filtering
+    #[default]
+    Off,
+    /// Kept in search results, with `flagged` set
+    Flag,
+    /// Left out of search results
+    Hide,
+}
+
+/// Senses labelled as no longer in use
+///
+/// Applies to the senses of full definitions and definition pages, and to
+/// search result previews and sense counts. A sense is dated when it
+/// carries one of `tags`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatedSenses {
+    pub mode: DatedSenseMode,
+    /// Sense tags that make a sense dated
+    pub tags: Vec<String>,
+}
+
+impl Default for DatedSenses {
+    fn default() -> Self {
+        Self {
+            mode: DatedSenseMode::default(),
+            tags: ["obsolete", "archaic"].map(String::from).to_vec(),
+        }
+    }
+}
+
+impl DatedSenses {
+    /// Whether dated senses are treated differently at all
+    pub fn is_active(&self) -> bool {
+        self.mode != DatedSenseMode::Keep && !self.tags.is_empty()
+    }
+}
+
+/// What happens to dated senses
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DatedSenseMode {
+    /// Listed in their original order
+    #[default]
+    Keep,
+    /// Listed after the current senses, and previewed only when an entry
+    /// has nothing else
+    Demote,
+    /// Left out
+    Hide,
+}
+
+/// Regional spelling preferred by [`DictConfig::spelling`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpellingRegion {
+    /// American spelling ("color", "center")
+    Us,
+    /// British and Commonwealth spelling ("colour", "centre")
+    Uk,
+}
+
+impl SpellingRegion {
+    /// Sense tags marking a spelling as this region's
+    pub fn tags(self) -> &'static [&'static str] {
+        match self {
+            SpellingRegion::Us => &["US", "American"],
+            SpellingRegion::Uk => &["UK", "British", "Commonwealth"],
+        }
+    }
+
+    /// The region of the other spelling of a pair
+    pub fn other(self) -> Self {
+        match self {
+            SpellingRegion::Us => SpellingRegion::Uk,
+            SpellingRegion::Uk => SpellingRegion::Us,
+        }
+    }
+}
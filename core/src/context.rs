@@ -0,0 +1,142 @@
+//! Picking the sense of a word that fits a sentence
+//!
+//! A simplified Lesk algorithm: each sense is scored by how many content
+//! words it shares with the sentence the word was found in, counting the
+//! sense's gloss, examples and tags. It needs no training data and works
+//! well enough to put the likely meaning first in a reader app.
+
+use std::collections::HashSet;
+
+use crate::models::SenseMatch;
+use crate::{DictHandle, Result};
+
+/// Common English function words that carry no sense information
+pub(crate) const STOP_WORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "an", "and", "any", "are", "as", "at", "be", "been",
+    "but", "by", "can", "could", "did", "do", "does", "for", "from", "had", "has", "have", "he",
+    "her", "him", "his", "how", "i", "if", "in", "into", "is", "it", "its", "may", "me", "more",
+    "my", "no", "not", "of", "on", "one", "or", "other", "our", "she", "so", "some", "such",
+    "than", "that", "the", "their", "them", "then", "there", "these", "they", "this", "those",
+    "to", "up", "us", "was", "we", "were", "what", "when", "which", "who", "will", "with", "would",
+    "you", "your",
+];
+
+/// Minimum length of a word to be compared
+const MIN_TOKEN_LENGTH: usize = 3;
+
+/// Rank the senses of `word` by how well they fit `sentence`
+///
+/// Returns every sense of every entry for `word` (or, if it has none, for
+/// its lemmas, so "banks" works like "bank"), best match first. Senses
+/// sharing no words with the sentence score 0 and keep their dictionary
+/// order after the matching ones.
+pub fn define_in_context(
+    handle: &DictHandle,
+    word: &str,
+    sentence: &str,
+) -> Result<Vec<SenseMatch>> {
+    let mut entries = crate::db::get_words_by_word(handle, word)?;
+    if entries.is_empty() {
+        for (lemma, _) in crate::morph::lemmatize_any(handle, word)? {
+            entries.extend(crate::db::get_words_by_word(handle, &lemma)?);
+        }
+    }
+
+    let mut context = content_tokens(sentence);
+    // The word itself appears in every sense's context and says nothing
+    for entry in &entries {
+        context.remove(&stem(&entry.word.to_lowercase()));
+    }
+    context.remove(&stem(&word.to_lowercase()));
+
+    let mut matches = Vec::new();
+    for entry in entries {
+        for definition in crate::db::get_definitions(handle, entry.id)? {
+            let mut signature = content_tokens(&definition.text);
+            for text in definition.examples.iter().chain(&definition.tags) {
+                signature.extend(content_tokens(text));
+            }
+            let score = context.intersection(&signature).count() as f64;
+            matches.push(SenseMatch {
+                word_id: entry.id,
+                word: entry.word.clone(),
+                pos: entry.pos.clone(),
+                definition,
+                score,
+            });
+        }
+    }
+
+    // Stable, so ties keep dictionary order
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(matches)
+}
+
+/// Lowercased, crudely stemmed content words of `text`
+fn content_tokens(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|t| t.chars().count() >= MIN_TOKEN_LENGTH && !STOP_WORDS.contains(&t.as_str()))
+        .map(|t| stem(&t))
+        .collect()
+}
+
+/// Strip a common English suffix so "deposits" matches "deposit"
+fn stem(token: &str) -> String {
+    for suffix in ["ing", "ed", "es", "s"] {
+        if let Some(stem) = token.strip_suffix(suffix) {
+            if stem.chars().count() >= MIN_TOKEN_LENGTH {
+                return stem.to_string();
+            }
+        }
+    }
+    token.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_database, insert_definition, insert_word};
+
+    #[test]
+    fn test_define_in_context() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let handle = init_database(db_path.to_str().unwrap()).unwrap();
+
+        let bank = insert_word(&handle.conn, "bank", "noun", "English", "en", 0).unwrap();
+        insert_definition(
+            &handle.conn,
+            bank,
+            "The edge of a river or lake",
+            &["We had a picnic on the bank.".to_string()],
+            &[],
+        )
+        .unwrap();
+        insert_definition(
+            &handle.conn,
+            bank,
+            "An institution where money is deposited and lent",
+            &["She opened an account at the bank.".to_string()],
+            &["finance".to_string()],
+        )
+        .unwrap();
+
+        let senses = define_in_context(&handle, "bank", "He deposited money at the bank").unwrap();
+        assert_eq!(senses.len(), 2);
+        assert!(senses[0].definition.text.starts_with("An institution"));
+        assert_eq!(senses[0].score, 2.0);
+
+        let senses = define_in_context(&handle, "bank", "They fished from the river bank").unwrap();
+        assert!(senses[0].definition.text.starts_with("The edge"));
+
+        // Lemmas are used for inflected forms
+        let senses = define_in_context(&handle, "banks", "Banks lend money").unwrap();
+        assert!(senses[0].definition.text.starts_with("An institution"));
+
+        // No overlap keeps dictionary order
+        let senses = define_in_context(&handle, "bank", "Look at that").unwrap();
+        assert!(senses[0].definition.text.starts_with("The edge"));
+        assert_eq!(senses[0].score, 0.0);
+    }
+}
@@ -5,12 +5,22 @@
 //! - Word and definition queries
 //! - FTS5 index management
 
-use std::sync::Arc;
-
-use rusqlite::{params, Connection, OpenFlags};
-
-use crate::models::{Definition, FullDefinition, Pronunciation, Translation};
-use crate::{DictHandle, Result};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+
+use crate::config::DatedSenseMode;
+use crate::models::{
+    Attribution, BrowseOptions, CompactOptions, CompactStats, CompatibilityReport, CountEntry,
+    DatabaseStats, Definition, DefinitionOptions, DefinitionPage, DefinitionParts, EntryMatch,
+    EtymologyEntry, EtymologyGroup, FormOf, FullDefinition, GraphEdge, GraphNode, LetterCount,
+    PhraseFilters, Pronunciation, RelationKind, SearchResult, SourceInfo, TableSize, Translation,
+    WordGraph,
+};
+use crate::normalize::Normalization;
+use crate::{DictConfig, DictHandle, Error, Result};
 
 /// SQL schema for the dictionary database
 const SCHEMA: &str = r#"
@@ -21,11 +31,16 @@ CREATE TABLE IF NOT EXISTS words (
     pos TEXT NOT NULL,
     language TEXT NOT NULL,
     lang_code TEXT NOT NULL DEFAULT '',
-    etymology_num INTEGER DEFAULT 0
+    etymology_num INTEGER DEFAULT 0,
+    frequency INTEGER,  -- corpus count from an external frequency list, NULL if unknown
+    search_key TEXT,  -- normalize::search_key of the headword
+    anagram_key TEXT  -- games::anagram_key of the headword, NULL if not playable
 );
 
 CREATE INDEX IF NOT EXISTS idx_words_word ON words(word);
 CREATE INDEX IF NOT EXISTS idx_words_language ON words(language);
+-- Alphabetical browsing by language (get_words_by_language)
+CREATE INDEX IF NOT EXISTS idx_words_language_word ON words(language, word COLLATE NOCASE);
 
 -- Full-text search using FTS5
 CREATE VIRTUAL TABLE IF NOT EXISTS words_fts USING fts5(
@@ -55,6 +70,10 @@ CREATE TABLE IF NOT EXISTS definitions (
     definition TEXT NOT NULL,
     examples TEXT,  -- JSON array
     tags TEXT,      -- JSON array
+    form_of TEXT,   -- headword a "plural of cat" style gloss points at
+    form_of_id INTEGER,  -- its word id, resolved after import
+    source_id INTEGER,   -- source of senses merged in from another dictionary, NULL for the entry's own
+    sense_id TEXT,       -- stable id of the sense across rebuilds (kaikki's, or a hash)
     FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE
 );
 
@@ -67,11 +86,95 @@ CREATE TABLE IF NOT EXISTS pronunciations (
     ipa TEXT,
     audio_url TEXT,
     accent TEXT,
+    rhyme TEXT,  -- ipa::rhyme_key of the IPA, NULL if it has no vowel
+    ipa_key TEXT,  -- ipa::search_key of the IPA
     FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE
 );
 
 CREATE INDEX IF NOT EXISTS idx_pronunciations_word_id ON pronunciations(word_id);
 
+-- Mandarin pinyin readings (see pinyin::Pinyin)
+CREATE TABLE IF NOT EXISTS pinyin (
+    id INTEGER PRIMARY KEY,
+    word_id INTEGER NOT NULL,
+    letters TEXT NOT NULL,  -- toneless, e.g. 'nihao'
+    tones TEXT NOT NULL,    -- tone numbers in order, e.g. '33'
+    FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_pinyin_letters ON pinyin(letters);
+
+-- Japanese kana readings, folded to hiragana
+CREATE TABLE IF NOT EXISTS kana (
+    id INTEGER PRIMARY KEY,
+    word_id INTEGER NOT NULL,
+    reading TEXT NOT NULL,
+    FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_kana_reading ON kana(reading);
+
+-- Hangul headwords decomposed into jamo for incremental prefix search
+CREATE TABLE IF NOT EXISTS jamo (
+    word_id INTEGER PRIMARY KEY,
+    jamo TEXT NOT NULL,
+    FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_jamo_jamo ON jamo(jamo);
+
+-- Inflected and alternative forms pointing at their headword
+CREATE TABLE IF NOT EXISTS forms (
+    id INTEGER PRIMARY KEY,
+    word_id INTEGER NOT NULL,
+    form TEXT NOT NULL,
+    tags TEXT,  -- JSON array, e.g. ["past", "participle"]
+    FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_forms_form ON forms(form);
+
+-- Synonyms and antonyms, per sense when Wiktionary gives the sense
+CREATE TABLE IF NOT EXISTS relations (
+    id INTEGER PRIMARY KEY,
+    word_id INTEGER NOT NULL,
+    definition_id INTEGER,  -- NULL for relations of the whole entry
+    kind TEXT NOT NULL,     -- "synonym" or "antonym"
+    target TEXT NOT NULL,
+    sense TEXT,             -- Wiktionary's label for entry-level relations
+    FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE,
+    FOREIGN KEY (definition_id) REFERENCES definitions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_relations_word_id ON relations(word_id);
+
+-- Emoji, punctuation and signs, stored without variation selectors
+CREATE TABLE IF NOT EXISTS symbols (
+    id INTEGER PRIMARY KEY,
+    word_id INTEGER NOT NULL,
+    symbol TEXT NOT NULL,
+    name TEXT,      -- e.g. "slightly smiling face"
+    keywords TEXT,  -- JSON array, e.g. ["face", "smile"]
+    FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_symbols_symbol ON symbols(symbol);
+
+-- Distinct headwords sorted by lowercased key, for autocompletion
+CREATE TABLE IF NOT EXISTS completions (
+    key TEXT NOT NULL,   -- lowercased headword
+    word TEXT NOT NULL,
+    weight INTEGER NOT NULL DEFAULT 0,  -- highest frequency of the headword
+    PRIMARY KEY (key, word)
+) WITHOUT ROWID;
+
+-- Completions by weight under their first one and two characters, so the
+-- best completions of a short prefix are read without ranking every key
+CREATE INDEX IF NOT EXISTS idx_completions_head1
+    ON completions(substr(key, 1, 1), weight DESC, key, word);
+CREATE INDEX IF NOT EXISTS idx_completions_head2
+    ON completions(substr(key, 1, 2), weight DESC, key, word);
+
 -- Etymology
 CREATE TABLE IF NOT EXISTS etymologies (
     id INTEGER PRIMARY KEY,
@@ -91,15 +194,53 @@ CREATE TABLE IF NOT EXISTS translations (
     FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE
 );
 
-CREATE INDEX IF NOT EXISTS idx_translations_word_id ON translations(word_id);
+CREATE INDEX IF NOT EXISTS idx_translations_word_language ON translations(word_id, target_language);
 CREATE INDEX IF NOT EXISTS idx_translations_language ON translations(target_language);
+
+-- Where entries come from and under which license they may be shown
+CREATE TABLE IF NOT EXISTS sources (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    license TEXT NOT NULL,
+    license_url TEXT,
+    page_url TEXT  -- URL of an entry's source page, {word} is replaced by the headword
+);
+
+-- Source of entries not from the primary (first) source, and page revisions
+CREATE TABLE IF NOT EXISTS entry_sources (
+    word_id INTEGER PRIMARY KEY,
+    source_id INTEGER NOT NULL,
+    revision INTEGER,
+    FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE,
+    FOREIGN KEY (source_id) REFERENCES sources(id)
+);
+
+-- Build metadata (key/value)
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
 "#;
 
+/// Version of the schema, stored as the database's `user_version`
+///
+/// Raised when a change means older versions of this library can no
+/// longer read a database; columns they would ignore don't need it.
+pub const SCHEMA_VERSION: u32 = 1;
+
 /// Initialize the dictionary database
 ///
 /// Opens the database at the specified path, creating it if necessary,
 /// and ensures the schema is set up correctly.
 pub fn init_database(db_path: &str) -> Result<DictHandle> {
+    init_database_with_config(db_path, DictConfig::default())
+}
+
+/// Initialize the dictionary database with the given settings
+///
+/// Like `init_database`; the user database in `config.user_db`, if any,
+/// is attached as schema `user` and must exist.
+pub fn init_database_with_config(db_path: &str, config: DictConfig) -> Result<DictHandle> {
     let conn = Connection::open_with_flags(
         db_path,
         OpenFlags::SQLITE_OPEN_READ_WRITE
@@ -112,17 +253,170 @@ pub fn init_database(db_path: &str) -> Result<DictHandle> {
 
     // Apply schema
     conn.execute_batch(SCHEMA)?;
+    upgrade_schema(&conn)?;
+    let version: u32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version < SCHEMA_VERSION {
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    }
+    configure(&conn, &config)?;
 
-    Ok(DictHandle {
-        conn: Arc::new(conn),
-    })
+    let mut handle = DictHandle::from_connection(conn);
+    handle.config = config;
+    Ok(handle)
+}
+
+/// Columns added after the first release, as (table, column, type)
+const ADDED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("words", "frequency", "INTEGER"),
+    ("pronunciations", "rhyme", "TEXT"),
+    ("definitions", "form_of", "TEXT"),
+    ("definitions", "form_of_id", "INTEGER"),
+    ("words", "search_key", "TEXT"),
+    ("definitions", "source_id", "INTEGER"),
+    ("definitions", "sense_id", "TEXT"),
+    ("words", "anagram_key", "TEXT"),
+    ("pronunciations", "ipa_key", "TEXT"),
+];
+
+/// Bring a database created by an older version up to the current schema
+///
+/// `CREATE TABLE IF NOT EXISTS` leaves existing tables alone, so columns
+/// added since are created here, along with the indexes over them.
+/// Rhyme and IPA keys of existing pronunciations, search and anagram keys of
+/// existing words and the completions table are backfilled.
+pub(crate) fn upgrade_schema(conn: &Connection) -> Result<()> {
+    for (table, column, ty) in ADDED_COLUMNS {
+        if has_column(conn, table, column)? {
+            continue;
+        }
+        conn.execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {ty}"))?;
+        match *column {
+            "rhyme" => backfill_rhymes(conn)?,
+            "ipa_key" => backfill_ipa_keys(conn)?,
+            "search_key" => backfill_search_keys(conn)?,
+            "anagram_key" => backfill_anagram_keys(conn)?,
+            _ => {}
+        }
+    }
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_pronunciations_rhyme ON pronunciations(rhyme);
+         CREATE INDEX IF NOT EXISTS idx_pronunciations_ipa_key ON pronunciations(ipa_key);
+         CREATE INDEX IF NOT EXISTS idx_words_language_key ON words(language, search_key);
+         CREATE INDEX IF NOT EXISTS idx_words_search_key ON words(search_key);
+         CREATE INDEX IF NOT EXISTS idx_words_anagram_key ON words(anagram_key);
+         CREATE INDEX IF NOT EXISTS idx_definitions_sense_id ON definitions(sense_id);",
+    )?;
+
+    // The completions table is new to databases built before it existed
+    let needs_completions: bool = conn.query_row(
+        "SELECT NOT EXISTS (SELECT 1 FROM completions) AND EXISTS (SELECT 1 FROM words)",
+        [],
+        |row| row.get(0),
+    )?;
+    if needs_completions {
+        rebuild_completions(conn)?;
+    }
+    Ok(())
+}
+
+/// Whether `table` has a column named `column`
+pub(crate) fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    Ok(conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?) WHERE name = ?",
+        params![table, column],
+        |row| row.get(0),
+    )?)
+}
+
+/// Compute rhyme keys for pronunciations that predate the column
+fn backfill_rhymes(conn: &Connection) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut select = tx.prepare("SELECT id, ipa FROM pronunciations WHERE ipa IS NOT NULL")?;
+        let mut update = tx.prepare("UPDATE pronunciations SET rhyme = ? WHERE id = ?")?;
+        let rows = select.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (id, ipa) = row?;
+            update.execute(params![crate::ipa::rhyme_key(&ipa), id])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Compute IPA search keys for pronunciations that predate the column
+fn backfill_ipa_keys(conn: &Connection) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut select = tx.prepare("SELECT id, ipa FROM pronunciations WHERE ipa IS NOT NULL")?;
+        let mut update = tx.prepare("UPDATE pronunciations SET ipa_key = ? WHERE id = ?")?;
+        let rows = select.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (id, ipa) = row?;
+            update.execute(params![crate::ipa::search_key(&ipa), id])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Compute anagram keys for words that predate the column
+fn backfill_anagram_keys(conn: &Connection) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut select = tx.prepare("SELECT id, word FROM words")?;
+        let mut update = tx.prepare("UPDATE words SET anagram_key = ? WHERE id = ?")?;
+        let rows = select.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (id, word) = row?;
+            if let Some(key) = crate::games::anagram_key(&word) {
+                update.execute(params![key, id])?;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Compute search keys for words that predate the column
+fn backfill_search_keys(conn: &Connection) -> Result<()> {
+    let normalization = normalization(conn)?;
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut select = tx.prepare("SELECT id, word FROM words")?;
+        let mut update = tx.prepare("UPDATE words SET search_key = ? WHERE id = ?")?;
+        let rows = select.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (id, word) = row?;
+            update.execute(params![normalization.key(&word), id])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
 }
 
 /// Open an existing database in read-only mode
 ///
 /// Used for search operations where no writes are needed.
 pub fn open_readonly(db_path: &str) -> Result<DictHandle> {
+    open_readonly_with_config(db_path, DictConfig::default())
+}
+
+/// Open an existing database in read-only mode with the given settings
+///
+/// The user database in `config.user_db`, if any, is attached read-only
+/// as schema `user` and must exist.
+pub fn open_readonly_with_config(db_path: &str, config: DictConfig) -> Result<DictHandle> {
     let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    configure(&conn, &config)?;
 
     // Log database stats on open (useful for diagnostics)
     if log::log_enabled!(log::Level::Info) {
@@ -140,13 +434,141 @@ pub fn open_readonly(db_path: &str) -> Result<DictHandle> {
         );
     }
 
-    Ok(DictHandle {
-        conn: Arc::new(conn),
-    })
+    let mut handle = DictHandle::from_connection(conn);
+    handle.config = config;
+    Ok(handle)
+}
+
+/// Whether the SQLite library has the FTS5 module
+///
+/// Probed by creating a table in the connection's temporary schema, which
+/// works on read-only connections too.
+pub(crate) fn fts5_available(conn: &Connection) -> bool {
+    fts5_probe(conn, "x")
+}
+
+/// Whether an FTS5 table with `columns` (and options) can be created
+fn fts5_probe(conn: &Connection, columns: &str) -> bool {
+    conn.execute_batch(&format!(
+        "CREATE VIRTUAL TABLE temp.fts5_probe USING fts5({columns});
+         DROP TABLE temp.fts5_probe;"
+    ))
+    .is_ok()
+}
+
+/// Apply the cache sizes of `config` to a new connection and attach its
+/// user database
+fn configure(conn: &Connection, config: &DictConfig) -> Result<()> {
+    if let Some(kib) = config.page_cache_kib {
+        // Negative sizes are in KiB rather than pages
+        conn.pragma_update(None, "cache_size", -i64::from(kib))?;
+    }
+    conn.set_prepared_statement_cache_capacity(config.statement_cache);
+    if let Some(user_db) = &config.user_db {
+        if !user_db.exists() {
+            return Err(Error::InvalidPath(user_db.display().to_string()));
+        }
+        let path = user_db
+            .to_str()
+            .ok_or_else(|| Error::InvalidPath(user_db.display().to_string()))?;
+        conn.execute("ATTACH DATABASE ? AS user", [path])?;
+    }
+    Ok(())
 }
 
 /// Get the full definition for a word by ID
 pub fn get_full_definition(handle: &DictHandle, word_id: i64) -> Result<Option<FullDefinition>> {
+    get_full_definition_with_options(handle, word_id, &DefinitionOptions::default())
+}
+
+/// Get the complete definition for a word, with options
+///
+/// With `inline_form_of`, senses that only point at another word ("plural
+/// of cat") carry that word's definitions in `form_of`, so the reader
+/// doesn't dead-end on a stub. Only one level is inlined. Parts left out
+/// of `parts` are not queried at all.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip(handle, options))
+)]
+pub fn get_full_definition_with_options(
+    handle: &DictHandle,
+    word_id: i64,
+    options: &DefinitionOptions,
+) -> Result<Option<FullDefinition>> {
+    let capacity = handle.config.definition_cache;
+    if capacity == 0 {
+        return load_full_definition(handle, word_id, options);
+    }
+    if let Some(definition) = handle.definitions.lock().unwrap().get(word_id, options) {
+        return Ok(Some(definition));
+    }
+    let definition = load_full_definition(handle, word_id, options)?;
+    if let Some(definition) = &definition {
+        handle
+            .definitions
+            .lock()
+            .unwrap()
+            .insert(word_id, options, definition.clone(), capacity);
+    }
+    Ok(definition)
+}
+
+/// Load the entries the UI expects to be opened next (e.g. the visible
+/// search results), so opening one doesn't wait on slow storage
+///
+/// Reading the rows pulls their pages into SQLite's and the OS's page
+/// caches; with `DictConfig::definition_cache` set, the definitions are
+/// also kept in memory for [`get_full_definition`]. Unknown ids are
+/// skipped. Returns how many entries were loaded, not counting those
+/// already cached.
+pub fn prefetch_definitions(handle: &DictHandle, word_ids: &[i64]) -> Result<usize> {
+    prefetch_definitions_with_options(handle, word_ids, &DefinitionOptions::default())
+}
+
+/// [`prefetch_definitions`] for lookups made with `options`
+pub fn prefetch_definitions_with_options(
+    handle: &DictHandle,
+    word_ids: &[i64],
+    options: &DefinitionOptions,
+) -> Result<usize> {
+    let capacity = handle.config.definition_cache;
+    let mut loaded = 0;
+    // Ids beyond what the cache holds would only push out the first ones
+    let count = match capacity {
+        0 => word_ids.len(),
+        capacity => word_ids.len().min(capacity),
+    };
+    for &word_id in &word_ids[..count] {
+        if capacity > 0
+            && handle
+                .definitions
+                .lock()
+                .unwrap()
+                .contains(word_id, options)
+        {
+            continue;
+        }
+        let Some(definition) = load_full_definition(handle, word_id, options)? else {
+            continue;
+        };
+        if capacity > 0 {
+            handle
+                .definitions
+                .lock()
+                .unwrap()
+                .insert(word_id, options, definition, capacity);
+        }
+        loaded += 1;
+    }
+    Ok(loaded)
+}
+
+fn load_full_definition(
+    handle: &DictHandle,
+    word_id: i64,
+    options: &DefinitionOptions,
+) -> Result<Option<FullDefinition>> {
     // Get basic word info
     // Note: lang_code may not exist in older database schemas, so we handle both cases
 
@@ -195,37 +617,348 @@ pub fn get_full_definition(handle: &DictHandle, word_id: i64) -> Result<Option<F
 
     let mut full_def = FullDefinition::new(word, pos, language, lang_code);
 
-    // Get definitions
-    full_def.definitions = get_definitions(handle, word_id)?;
+    let parts = options.parts;
+    if parts.contains(DefinitionParts::DEFINITIONS) {
+        full_def.definitions = get_definitions(handle, word_id)?;
+    }
+    let relations = parts.contains(DefinitionParts::RELATIONS);
+    if options.inline_form_of && relations {
+        for definition in &mut full_def.definitions {
+            if let Some(form_of) = definition.form_of.as_mut() {
+                if let Some(target_id) = form_of.word_id {
+                    form_of.definitions = get_definitions(handle, target_id)?;
+                }
+            }
+        }
+    }
+    if options.links && relations {
+        for definition in &mut full_def.definitions {
+            definition.links = crate::text::links(
+                handle,
+                &definition.text,
+                &full_def.lang_code,
+                &full_def.word,
+            )?;
+        }
+    }
+
+    if parts.contains(DefinitionParts::PRONUNCIATIONS) {
+        full_def.pronunciations = get_pronunciations(handle, word_id)?;
+        let scheme = crate::ipa::RespellingScheme::for_language(&full_def.lang_code);
+        if let Some(scheme) = scheme.filter(|_| options.respelling) {
+            for pronunciation in &mut full_def.pronunciations {
+                pronunciation.respelling = pronunciation
+                    .ipa
+                    .as_deref()
+                    .and_then(|ipa| crate::ipa::to_respelling(ipa, scheme));
+            }
+        }
+    }
+    if parts.contains(DefinitionParts::ETYMOLOGY) {
+        full_def.etymology = get_etymology(handle, word_id)?;
+    }
+    if parts.contains(DefinitionParts::TRANSLATIONS) {
+        full_def.translations = get_translations(handle, word_id)?;
+    }
+
+    full_def.attribution = get_attribution(handle, word_id, &full_def.word)?;
+    if handle.config.spelling.is_some() {
+        full_def.also_spelled = crate::spelling::variants(handle, word_id)?;
+    }
+
+    Ok(Some(full_def))
+}
 
-    // Get pronunciations
-    full_def.pronunciations = get_pronunciations(handle, word_id)?;
+/// Source and license of an entry
+///
+/// Entries without a row in `entry_sources` come from the primary source,
+/// the first one recorded. None for databases built before sources were
+/// recorded.
+fn get_attribution(handle: &DictHandle, word_id: i64, word: &str) -> Result<Option<Attribution>> {
+    if !table_exists(&handle.conn, "sources")? {
+        return Ok(None);
+    }
+    let mut stmt = handle.conn.prepare_cached(
+        "SELECT s.name, s.license, s.license_url, s.page_url, e.revision
+         FROM sources s LEFT JOIN entry_sources e ON e.word_id = ?1
+         WHERE s.id = COALESCE(e.source_id, (SELECT MIN(id) FROM sources))",
+    )?;
+    let row = stmt
+        .query_row(params![word_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<i64>>(4)?,
+            ))
+        })
+        .optional()?;
+    Ok(
+        row.map(|(source, license, license_url, page_url, revision)| {
+            let url = page_url.map(|template| {
+                let mut url = template.replace("{word}", &page_title(word));
+                if let Some(revision) = revision {
+                    url.push(if url.contains('?') { '&' } else { '?' });
+                    url.push_str(&format!("oldid={}", revision));
+                }
+                url
+            });
+            Attribution {
+                source,
+                license,
+                license_url,
+                url,
+                revision,
+            }
+        }),
+    )
+}
 
-    // Get etymology
-    full_def.etymology = get_etymology(handle, word_id)?;
+/// A headword as a wiki page title for URLs: spaces become underscores
+/// and characters that would end the path are percent-encoded
+fn page_title(word: &str) -> String {
+    let mut out = String::with_capacity(word.len());
+    for c in word.chars() {
+        match c {
+            ' ' => out.push('_'),
+            '%' => out.push_str("%25"),
+            '?' => out.push_str("%3F"),
+            '#' => out.push_str("%23"),
+            '&' => out.push_str("%26"),
+            '"' => out.push_str("%22"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
 
-    // Get translations
-    full_def.translations = get_translations(handle, word_id)?;
+/// Get an entry of the attached user database by ID
+///
+/// For search results with `EntryOrigin::User`. User entries carry their
+/// definitions and, if the user database has the table, pronunciations;
+/// None if there is no such entry or no user database.
+pub fn get_user_definition(handle: &DictHandle, word_id: i64) -> Result<Option<FullDefinition>> {
+    if !user_table_exists(&handle.conn, "words")? {
+        return Ok(None);
+    }
+    let word_row = handle
+        .conn
+        .query_row(
+            "SELECT word, pos, language, lang_code FROM user.words WHERE id = ?",
+            params![word_id],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            },
+        )
+        .optional()?;
+    let Some((word, pos, language, lang_code)) = word_row else {
+        return Ok(None);
+    };
+    let mut full_def = FullDefinition::new(word, pos, language, lang_code);
 
+    let mut stmt = handle.conn.prepare(
+        "SELECT id, definition, examples, tags FROM user.definitions WHERE word_id = ? ORDER BY id",
+    )?;
+    let rows = stmt.query_map(params![word_id], |row| {
+        let parse = |json: Option<String>| -> Vec<String> {
+            json.and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default()
+        };
+        Ok(Definition {
+            id: row.get(0)?,
+            text: row.get(1)?,
+            examples: parse(row.get(2)?),
+            tags: parse(row.get(3)?),
+            form_of: None,
+            links: Vec::new(),
+            source: None,
+            sense_id: None,
+        })
+    })?;
+    full_def.definitions = rows.collect::<std::result::Result<Vec<_>, _>>()?;
+
+    if user_table_exists(&handle.conn, "pronunciations")? {
+        let mut stmt = handle.conn.prepare(
+            "SELECT id, ipa, audio_url, accent FROM user.pronunciations WHERE word_id = ?",
+        )?;
+        let rows = stmt.query_map(params![word_id], |row| {
+            Ok(Pronunciation {
+                id: row.get(0)?,
+                ipa: row.get(1)?,
+                audio_url: row.get(2)?,
+                accent: row.get(3)?,
+                respelling: None,
+            })
+        })?;
+        full_def.pronunciations = rows.collect::<std::result::Result<Vec<_>, _>>()?;
+    }
     Ok(Some(full_def))
 }
 
 /// Get all definitions for a word
-fn get_definitions(handle: &DictHandle, word_id: i64) -> Result<Vec<Definition>> {
-    let mut stmt = handle
+pub(crate) fn get_definitions(handle: &DictHandle, word_id: i64) -> Result<Vec<Definition>> {
+    // SQLite reads a negative limit as no limit
+    get_definition_range(handle, word_id, 0, -1)
+}
+
+/// Get one page of a word's senses, for lazily loading long entries
+///
+/// Senses come in the order `get_full_definition` lists them; `total`
+/// counts them all, so a list can be sized before the rest is loaded.
+pub fn get_definition_page(
+    handle: &DictHandle,
+    word_id: i64,
+    offset: u32,
+    limit: u32,
+) -> Result<DefinitionPage> {
+    Ok(DefinitionPage {
+        definitions: get_definition_range(handle, word_id, offset, limit as i64)?,
+        offset,
+        total: get_sense_count(handle, word_id)?,
+    })
+}
+
+/// Find `needle` in the senses of one entry, for find-in-page on long
+/// entries
+///
+/// Glosses and examples are searched with case and diacritics folded as
+/// the handle's normalization folds them, senses in the order
+/// `get_definition_page` pages them, so a match's `sense_index` is the
+/// offset of the page to load. Offsets are byte ranges on grapheme
+/// boundaries (see [`crate::graphemes::highlight`]). An empty needle
+/// finds nothing.
+pub fn find_in_entry(handle: &DictHandle, word_id: i64, needle: &str) -> Result<Vec<EntryMatch>> {
+    if needle.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let normalization = handle.normalization();
+    let mut matches = Vec::new();
+    for (index, sense) in get_definitions(handle, word_id)?.iter().enumerate() {
+        let texts = std::iter::once((None, &sense.text)).chain(
+            sense
+                .examples
+                .iter()
+                .enumerate()
+                .map(|(n, example)| (Some(n as u32), example)),
+        );
+        for (example, text) in texts {
+            for range in crate::graphemes::highlight(text, needle, normalization) {
+                matches.push(EntryMatch {
+                    sense_index: index as u32,
+                    definition_id: sense.id,
+                    example,
+                    start: range.start,
+                    end: range.end,
+                });
+            }
+        }
+    }
+    Ok(matches)
+}
+
+/// Number of senses of a word, without dated ones when they are hidden
+pub fn get_sense_count(handle: &DictHandle, word_id: i64) -> Result<u32> {
+    let dated = &handle.config.dated_senses;
+    let tags: &[String] = if dated.is_active() && dated.mode == DatedSenseMode::Hide {
+        &dated.tags
+    } else {
+        &[]
+    };
+    let filter = if tags.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "AND NOT {}",
+            dated_sense_condition("definitions", 2, tags.len())
+        )
+    };
+    let mut values: Vec<&dyn rusqlite::ToSql> = vec![&word_id];
+    values.extend(tags.iter().map(|t| t as &dyn rusqlite::ToSql));
+    handle
         .conn
-        .prepare("SELECT id, definition, examples, tags FROM definitions WHERE word_id = ?")?;
+        .prepare_cached(&format!(
+            "SELECT COUNT(*) FROM definitions WHERE word_id = ?1 {filter}"
+        ))?
+        .query_row(values.as_slice(), |row| row.get(0))
+        .map_err(|e| e.into())
+}
 
-    let rows = stmt.query_map(params![word_id], |row| {
+/// SQL condition: the sense in `table` carries one of `tags` tags, bound
+/// as parameters numbered from `first`
+pub(crate) fn dated_sense_condition(table: &str, first: usize, tags: usize) -> String {
+    let placeholders: Vec<String> = (first..first + tags).map(|n| format!("?{n}")).collect();
+    format!(
+        "EXISTS (SELECT 1 FROM json_each({table}.tags) WHERE value IN ({}))",
+        placeholders.join(",")
+    )
+}
+
+fn get_definition_range(
+    handle: &DictHandle,
+    word_id: i64,
+    offset: u32,
+    limit: i64,
+) -> Result<Vec<Definition>> {
+    // Databases built before form-of links have no such columns
+    let form_of_columns = if has_column(&handle.conn, "definitions", "form_of")? {
+        "form_of, form_of_id"
+    } else {
+        "NULL, NULL"
+    };
+    // Only merged databases tag senses with a source
+    let source_column = if has_column(&handle.conn, "definitions", "source_id")? {
+        "(SELECT name FROM sources WHERE id = definitions.source_id)"
+    } else {
+        "NULL"
+    };
+    let sense_id_column = if has_column(&handle.conn, "definitions", "sense_id")? {
+        "sense_id"
+    } else {
+        "NULL"
+    };
+    // Dated senses are left out, or listed last
+    let dated = &handle.config.dated_senses;
+    let (filter, order, tags): (String, String, &[String]) = match dated.mode {
+        _ if !dated.is_active() => (String::new(), String::new(), &[]),
+        DatedSenseMode::Hide => {
+            let condition = dated_sense_condition("definitions", 4, dated.tags.len());
+            (format!("AND NOT {condition}"), String::new(), &dated.tags)
+        }
+        DatedSenseMode::Demote | DatedSenseMode::Keep => {
+            let condition = dated_sense_condition("definitions", 4, dated.tags.len());
+            (String::new(), format!("{condition}, "), &dated.tags)
+        }
+    };
+    let mut stmt = handle.conn.prepare(&format!(
+        "SELECT id, definition, examples, tags, {form_of_columns}, {source_column}, {sense_id_column}
+         FROM definitions WHERE word_id = ?1 {filter} ORDER BY {order}id LIMIT ?2 OFFSET ?3"
+    ))?;
+
+    let mut values: Vec<&dyn rusqlite::ToSql> = vec![&word_id, &limit, &offset];
+    values.extend(tags.iter().map(|t| t as &dyn rusqlite::ToSql));
+    let rows = stmt.query_map(values.as_slice(), |row| {
         let id: i64 = row.get(0)?;
         let text: String = row.get(1)?;
         let examples_json: Option<String> = row.get(2)?;
         let tags_json: Option<String> = row.get(3)?;
+        let form_of: Option<String> = row.get(4)?;
+        let form_of_id: Option<i64> = row.get(5)?;
+        let source: Option<String> = row.get(6)?;
+        let sense_id: Option<String> = row.get(7)?;
 
         // Parse JSON arrays
-        let examples: Vec<String> = examples_json
+        let mut examples: Vec<String> = examples_json
             .and_then(|s| serde_json::from_str(&s).ok())
             .unwrap_or_default();
+        if let Some(max) = handle.config.max_examples_per_sense {
+            examples.truncate(max);
+        }
         let tags: Vec<String> = tags_json
             .and_then(|s| serde_json::from_str(&s).ok())
             .unwrap_or_default();
@@ -235,6 +968,14 @@ fn get_definitions(handle: &DictHandle, word_id: i64) -> Result<Vec<Definition>>
             text,
             examples,
             tags,
+            form_of: form_of.map(|word| FormOf {
+                word,
+                word_id: form_of_id,
+                definitions: Vec::new(),
+            }),
+            links: Vec::new(),
+            source,
+            sense_id,
         })
     })?;
 
@@ -254,11 +995,16 @@ fn get_pronunciations(handle: &DictHandle, word_id: i64) -> Result<Vec<Pronuncia
             ipa: row.get(1)?,
             audio_url: row.get(2)?,
             accent: row.get(3)?,
+            respelling: None,
         })
     })?;
 
-    rows.collect::<std::result::Result<Vec<_>, _>>()
-        .map_err(|e| e.into())
+    let mut pronunciations = rows.collect::<std::result::Result<Vec<_>, _>>()?;
+    let preferred = &handle.config.preferred_accents;
+    if !preferred.is_empty() {
+        pronunciations.sort_by_key(|p| crate::ipa::accent_rank(p.accent.as_deref(), preferred));
+    }
+    Ok(pronunciations)
 }
 
 /// Get etymology for a word
@@ -274,13 +1020,29 @@ fn get_etymology(handle: &DictHandle, word_id: i64) -> Result<Option<String>> {
     }
 }
 
-/// Get all translations for a word
+/// Get the translations of a word into the handle's target languages
 fn get_translations(handle: &DictHandle, word_id: i64) -> Result<Vec<Translation>> {
-    let mut stmt = handle
-        .conn
-        .prepare("SELECT id, target_language, translation FROM translations WHERE word_id = ?")?;
-
-    let rows = stmt.query_map(params![word_id], |row| {
+    let languages = handle.config.translation_languages.as_deref();
+    let filter = match languages {
+        Some([]) => return Ok(Vec::new()),
+        Some(languages) => format!(
+            "AND target_language IN ({})",
+            vec!["?"; languages.len()].join(",")
+        ),
+        None => String::new(),
+    };
+    let mut stmt = handle.conn.prepare_cached(&format!(
+        "SELECT id, target_language, translation FROM translations WHERE word_id = ? {filter}"
+    ))?;
+
+    let mut values: Vec<&dyn rusqlite::ToSql> = vec![&word_id];
+    values.extend(
+        languages
+            .unwrap_or_default()
+            .iter()
+            .map(|l| l as &dyn rusqlite::ToSql),
+    );
+    let rows = stmt.query_map(values.as_slice(), |row| {
         Ok(Translation {
             id: row.get(0)?,
             target_language: row.get(1)?,
@@ -293,7 +1055,7 @@ fn get_translations(handle: &DictHandle, word_id: i64) -> Result<Vec<Translation
 }
 
 /// Insert a word entry and return its ID
-pub fn insert_word(
+pub(crate) fn insert_word(
     conn: &Connection,
     word: &str,
     pos: &str,
@@ -301,15 +1063,94 @@ pub fn insert_word(
     lang_code: &str,
     etymology_num: i32,
 ) -> Result<i64> {
+    conn.prepare_cached(
+        "INSERT INTO words (word, pos, language, lang_code, etymology_num, search_key, anagram_key)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )?
+    .execute(params![
+        word,
+        pos,
+        language,
+        lang_code,
+        etymology_num,
+        normalization(conn)?.key(word),
+        crate::games::anagram_key(word)
+    ])?;
+    let word_id = conn.last_insert_rowid();
+    if crate::hangul::contains_hangul(word) {
+        set_jamo(conn, word_id, word)?;
+    }
+    add_completion(conn, word)?;
+    Ok(word_id)
+}
+
+/// Make `word` available to `search::complete`
+fn add_completion(conn: &Connection, word: &str) -> Result<()> {
+    conn.prepare_cached("INSERT OR IGNORE INTO completions (key, word) VALUES (?, ?)")?
+        .execute(params![word.to_lowercase(), word])?;
+    Ok(())
+}
+
+/// Drop `word` from the completions once no entry has it as headword
+fn prune_completion(conn: &Connection, word: &str) -> Result<()> {
     conn.execute(
-        "INSERT INTO words (word, pos, language, lang_code, etymology_num) VALUES (?, ?, ?, ?, ?)",
-        params![word, pos, language, lang_code, etymology_num],
+        "DELETE FROM completions WHERE key = ? AND word = ?2 AND NOT EXISTS (SELECT 1 FROM words WHERE word = ?2)",
+        params![word.to_lowercase(), word],
     )?;
-    Ok(conn.last_insert_rowid())
+    Ok(())
+}
+
+/// Fill the completions table from the headwords and their frequencies
+///
+/// Replaces whatever the table held.
+pub(crate) fn rebuild_completions(conn: &Connection) -> Result<()> {
+    let frequency = if has_column(conn, "words", "frequency")? {
+        "MAX(COALESCE(frequency, 0))"
+    } else {
+        "0"
+    };
+    // Within an `Editor`'s transaction the rebuild commits along with it
+    let tx = if conn.is_autocommit() {
+        Some(conn.unchecked_transaction()?)
+    } else {
+        None
+    };
+    conn.execute("DELETE FROM completions", [])?;
+    {
+        let mut select = conn.prepare(&format!(
+            "SELECT word, {frequency} FROM words GROUP BY word"
+        ))?;
+        let mut insert = conn
+            .prepare("INSERT OR REPLACE INTO completions (key, word, weight) VALUES (?, ?, ?)")?;
+        let rows = select.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        for row in rows {
+            let (word, weight) = row?;
+            insert.execute(params![word.to_lowercase(), word, weight])?;
+        }
+    }
+    if let Some(tx) = tx {
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// Store the jamo decomposition of a Hangul headword, replacing any previous
+fn set_jamo(conn: &Connection, word_id: i64, word: &str) -> Result<()> {
+    if crate::hangul::contains_hangul(word) {
+        conn.execute(
+            "INSERT OR REPLACE INTO jamo (word_id, jamo) VALUES (?, ?)",
+            params![word_id, crate::hangul::decompose(word)],
+        )?;
+    } else {
+        conn.execute("DELETE FROM jamo WHERE word_id = ?", [word_id])?;
+    }
+    Ok(())
 }
 
 /// Insert a definition for a word
-pub fn insert_definition(
+pub(crate) fn insert_definition(
     conn: &Connection,
     word_id: i64,
     definition: &str,
@@ -327,64 +1168,291 @@ pub fn insert_definition(
 }
 
 /// Insert a pronunciation for a word
-pub fn insert_pronunciation(
+pub(crate) fn insert_pronunciation(
     conn: &Connection,
     word_id: i64,
     ipa: Option<&str>,
     audio_url: Option<&str>,
     accent: Option<&str>,
 ) -> Result<i64> {
+    let rhyme = ipa.and_then(crate::ipa::rhyme_key);
+    let ipa_key = ipa.and_then(crate::ipa::search_key);
     conn.execute(
-        "INSERT INTO pronunciations (word_id, ipa, audio_url, accent) VALUES (?, ?, ?, ?)",
-        params![word_id, ipa, audio_url, accent],
+        "INSERT INTO pronunciations (word_id, ipa, audio_url, accent, rhyme, ipa_key)
+         VALUES (?, ?, ?, ?, ?, ?)",
+        params![word_id, ipa, audio_url, accent, rhyme, ipa_key],
     )?;
     Ok(conn.last_insert_rowid())
 }
 
-/// Insert an etymology for a word
-pub fn insert_etymology(conn: &Connection, word_id: i64, text: &str) -> Result<i64> {
+/// Set the audio file of a pronunciation
+pub(crate) fn set_pronunciation_audio(
+    conn: &Connection,
+    pronunciation_id: i64,
+    audio_url: &str,
+) -> Result<()> {
     conn.execute(
-        "INSERT INTO etymologies (word_id, etymology_text) VALUES (?, ?)",
-        params![word_id, text],
+        "UPDATE pronunciations SET audio_url = ? WHERE id = ?",
+        params![audio_url, pronunciation_id],
     )?;
-    Ok(conn.last_insert_rowid())
+    Ok(())
 }
 
-/// Insert a translation for a word
-pub fn insert_translation(
+/// Insert an inflected or alternative form of a word
+pub(crate) fn insert_form(
     conn: &Connection,
     word_id: i64,
-    target_language: &str,
-    translation: &str,
+    form: &str,
+    tags: &[String],
 ) -> Result<i64> {
+    let tags_json = serde_json::to_string(tags)?;
     conn.execute(
-        "INSERT INTO translations (word_id, target_language, translation) VALUES (?, ?, ?)",
-        params![word_id, target_language, translation],
+        "INSERT INTO forms (word_id, form, tags) VALUES (?, ?, ?)",
+        params![word_id, form, tags_json],
     )?;
     Ok(conn.last_insert_rowid())
 }
 
-// ============================================================================
-// Update Operations
-// ============================================================================
+/// Record that a definition is an inflection or variant of `target`
+///
+/// The link to the target's word id is filled in by `resolve_form_of`
+/// once all words are imported.
+pub(crate) fn set_form_of(conn: &Connection, definition_id: i64, target: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE definitions SET form_of = ? WHERE id = ?",
+        params![target, definition_id],
+    )?;
+    Ok(())
+}
 
-/// Update a word entry
-pub fn update_word(
-    conn: &Connection,
-    word_id: i64,
-    word: &str,
-    pos: &str,
-    language: &str,
-) -> Result<bool> {
-    let rows = conn.execute(
-        "UPDATE words SET word = ?, pos = ?, language = ? WHERE id = ?",
-        params![word, pos, language, word_id],
+/// Record the stable id of a definition's sense (see `Definition::sense_id`)
+pub(crate) fn set_sense_id(conn: &Connection, definition_id: i64, sense_id: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE definitions SET sense_id = ? WHERE id = ?",
+        params![sense_id, definition_id],
     )?;
-    Ok(rows > 0)
+    Ok(())
 }
 
-/// Update a definition
-pub fn update_definition(
+/// Find a sense by its stable id, for deep links
+///
+/// Returns the word id and definition id of the sense, or None if no
+/// sense has that id.
+pub fn find_sense(handle: &DictHandle, sense_id: &str) -> Result<Option<(i64, i64)>> {
+    if !has_column(&handle.conn, "definitions", "sense_id")? {
+        return Ok(None);
+    }
+    handle
+        .conn
+        .prepare_cached("SELECT word_id, id FROM definitions WHERE sense_id = ? LIMIT 1")?
+        .query_row([sense_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .optional()
+        .map_err(|e| e.into())
+}
+
+/// Point form-of definitions at their target headword in the same language
+///
+/// Returns how many definitions were linked.
+pub(crate) fn resolve_form_of(conn: &Connection) -> Result<u64> {
+    conn.execute(
+        r#"
+        UPDATE definitions
+        SET form_of_id = (
+            SELECT t.id
+            FROM words t, words w
+            WHERE w.id = definitions.word_id
+              AND t.word = definitions.form_of
+              AND t.lang_code = w.lang_code
+              AND t.id != w.id
+            ORDER BY t.id
+            LIMIT 1
+        )
+        WHERE form_of IS NOT NULL
+        "#,
+        [],
+    )?;
+    let linked: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM definitions WHERE form_of_id IS NOT NULL",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(linked as u64)
+}
+
+/// Insert a synonym or antonym of a word
+///
+/// `definition_id` is the sense the relation belongs to, if known; `sense`
+/// is Wiktionary's label for it otherwise.
+pub(crate) fn insert_relation(
+    conn: &Connection,
+    word_id: i64,
+    definition_id: Option<i64>,
+    kind: &str,
+    target: &str,
+    sense: Option<&str>,
+) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO relations (word_id, definition_id, kind, target, sense) VALUES (?, ?, ?, ?, ?)",
+        params![word_id, definition_id, kind, target, sense],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Insert a symbol (emoji, punctuation, sign) pointing at its entry
+///
+/// Variation selectors are stripped from `symbol` so lookups match
+/// either presentation.
+pub(crate) fn insert_symbol(
+    conn: &Connection,
+    word_id: i64,
+    symbol: &str,
+    name: Option<&str>,
+    keywords: &[String],
+) -> Result<i64> {
+    let keywords_json = serde_json::to_string(keywords)?;
+    conn.execute(
+        "INSERT INTO symbols (word_id, symbol, name, keywords) VALUES (?, ?, ?, ?)",
+        params![
+            word_id,
+            crate::symbols::normalize(symbol),
+            name,
+            keywords_json
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Insert a pinyin reading for a word
+///
+/// Returns `None` without inserting if `reading` is not valid pinyin.
+pub(crate) fn insert_pinyin(conn: &Connection, word_id: i64, reading: &str) -> Result<Option<i64>> {
+    let Some(pinyin) = crate::pinyin::parse(reading) else {
+        return Ok(None);
+    };
+    conn.execute(
+        "INSERT INTO pinyin (word_id, letters, tones) VALUES (?, ?, ?)",
+        params![word_id, pinyin.letters, pinyin.tones],
+    )?;
+    Ok(Some(conn.last_insert_rowid()))
+}
+
+/// Insert a kana reading for a word, folded to hiragana
+#[cfg(feature = "kana")]
+pub(crate) fn insert_kana(conn: &Connection, word_id: i64, reading: &str) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO kana (word_id, reading) VALUES (?, ?)",
+        params![word_id, crate::kana::to_hiragana(reading)],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Insert an etymology for a word
+pub(crate) fn insert_etymology(conn: &Connection, word_id: i64, text: &str) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO etymologies (word_id, etymology_text) VALUES (?, ?)",
+        params![word_id, text],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Insert a translation for a word
+pub(crate) fn insert_translation(
+    conn: &Connection,
+    word_id: i64,
+    target_language: &str,
+    translation: &str,
+) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO translations (word_id, target_language, translation) VALUES (?, ?, ?)",
+        params![word_id, target_language, translation],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Record a source of entries and return its ID
+///
+/// A source identical to one already recorded is reused.
+pub(crate) fn insert_source(conn: &Connection, source: &SourceInfo) -> Result<i64> {
+    let existing = conn
+        .query_row(
+            "SELECT id FROM sources WHERE name = ? AND license = ? AND license_url IS ? AND page_url IS ?",
+            params![source.name, source.license, source.license_url, source.page_url],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+    conn.execute(
+        "INSERT INTO sources (name, license, license_url, page_url) VALUES (?, ?, ?, ?)",
+        params![
+            source.name,
+            source.license,
+            source.license_url,
+            source.page_url
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Record the source and source page revision of an entry
+///
+/// Only needed for entries not from the primary source or with a known
+/// revision; see `get_full_definition`.
+pub(crate) fn insert_entry_source(
+    conn: &Connection,
+    word_id: i64,
+    source_id: i64,
+    revision: Option<i64>,
+) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO entry_sources (word_id, source_id, revision) VALUES (?, ?, ?)",
+        params![word_id, source_id, revision],
+    )?;
+    Ok(())
+}
+
+// ============================================================================
+// Update Operations
+// ============================================================================
+
+/// Update a word entry
+pub(crate) fn update_word(
+    conn: &Connection,
+    word_id: i64,
+    word: &str,
+    pos: &str,
+    language: &str,
+) -> Result<bool> {
+    let old_word: Option<String> = conn
+        .query_row("SELECT word FROM words WHERE id = ?", [word_id], |row| {
+            row.get(0)
+        })
+        .optional()?;
+    let rows = conn.execute(
+        "UPDATE words SET word = ?, pos = ?, language = ?, search_key = ?, anagram_key = ?
+         WHERE id = ?",
+        params![
+            word,
+            pos,
+            language,
+            normalization(conn)?.key(word),
+            crate::games::anagram_key(word),
+            word_id
+        ],
+    )?;
+    if rows > 0 {
+        set_jamo(conn, word_id, word)?;
+        if let Some(old_word) = old_word.filter(|w| w != word) {
+            prune_completion(conn, &old_word)?;
+            add_completion(conn, word)?;
+        }
+    }
+    Ok(rows > 0)
+}
+
+/// Update a definition
+pub(crate) fn update_definition(
     conn: &Connection,
     definition_id: i64,
     definition: &str,
@@ -402,22 +1470,25 @@ pub fn update_definition(
 }
 
 /// Update a pronunciation
-pub fn update_pronunciation(
+pub(crate) fn update_pronunciation(
     conn: &Connection,
     pronunciation_id: i64,
     ipa: Option<&str>,
     audio_url: Option<&str>,
     accent: Option<&str>,
 ) -> Result<bool> {
+    let rhyme = ipa.and_then(crate::ipa::rhyme_key);
+    let ipa_key = ipa.and_then(crate::ipa::search_key);
     let rows = conn.execute(
-        "UPDATE pronunciations SET ipa = ?, audio_url = ?, accent = ? WHERE id = ?",
-        params![ipa, audio_url, accent, pronunciation_id],
+        "UPDATE pronunciations SET ipa = ?, audio_url = ?, accent = ?, rhyme = ?, ipa_key = ?
+         WHERE id = ?",
+        params![ipa, audio_url, accent, rhyme, ipa_key, pronunciation_id],
     )?;
     Ok(rows > 0)
 }
 
 /// Update an etymology
-pub fn update_etymology(conn: &Connection, etymology_id: i64, text: &str) -> Result<bool> {
+pub(crate) fn update_etymology(conn: &Connection, etymology_id: i64, text: &str) -> Result<bool> {
     let rows = conn.execute(
         "UPDATE etymologies SET etymology_text = ? WHERE id = ?",
         params![text, etymology_id],
@@ -426,7 +1497,7 @@ pub fn update_etymology(conn: &Connection, etymology_id: i64, text: &str) -> Res
 }
 
 /// Update a translation
-pub fn update_translation(
+pub(crate) fn update_translation(
     conn: &Connection,
     translation_id: i64,
     target_language: &str,
@@ -444,13 +1515,21 @@ pub fn update_translation(
 // ============================================================================
 
 /// Delete a word entry and all associated data (cascades)
-pub fn delete_word(conn: &Connection, word_id: i64) -> Result<bool> {
+pub(crate) fn delete_word(conn: &Connection, word_id: i64) -> Result<bool> {
+    let word: Option<String> = conn
+        .query_row("SELECT word FROM words WHERE id = ?", [word_id], |row| {
+            row.get(0)
+        })
+        .optional()?;
     let rows = conn.execute("DELETE FROM words WHERE id = ?", params![word_id])?;
+    if let Some(word) = word {
+        prune_completion(conn, &word)?;
+    }
     Ok(rows > 0)
 }
 
 /// Delete a definition
-pub fn delete_definition(conn: &Connection, definition_id: i64) -> Result<bool> {
+pub(crate) fn delete_definition(conn: &Connection, definition_id: i64) -> Result<bool> {
     let rows = conn.execute(
         "DELETE FROM definitions WHERE id = ?",
         params![definition_id],
@@ -459,7 +1538,7 @@ pub fn delete_definition(conn: &Connection, definition_id: i64) -> Result<bool>
 }
 
 /// Delete a pronunciation
-pub fn delete_pronunciation(conn: &Connection, pronunciation_id: i64) -> Result<bool> {
+pub(crate) fn delete_pronunciation(conn: &Connection, pronunciation_id: i64) -> Result<bool> {
     let rows = conn.execute(
         "DELETE FROM pronunciations WHERE id = ?",
         params![pronunciation_id],
@@ -468,7 +1547,7 @@ pub fn delete_pronunciation(conn: &Connection, pronunciation_id: i64) -> Result<
 }
 
 /// Delete an etymology
-pub fn delete_etymology(conn: &Connection, etymology_id: i64) -> Result<bool> {
+pub(crate) fn delete_etymology(conn: &Connection, etymology_id: i64) -> Result<bool> {
     let rows = conn.execute(
         "DELETE FROM etymologies WHERE id = ?",
         params![etymology_id],
@@ -477,7 +1556,7 @@ pub fn delete_etymology(conn: &Connection, etymology_id: i64) -> Result<bool> {
 }
 
 /// Delete a translation
-pub fn delete_translation(conn: &Connection, translation_id: i64) -> Result<bool> {
+pub(crate) fn delete_translation(conn: &Connection, translation_id: i64) -> Result<bool> {
     let rows = conn.execute(
         "DELETE FROM translations WHERE id = ?",
         params![translation_id],
@@ -530,18 +1609,236 @@ pub fn get_words_by_word(handle: &DictHandle, word: &str) -> Result<Vec<crate::m
         .map_err(|e| e.into())
 }
 
+/// Every entry for a headword, grouped by etymology
+///
+/// Entries of one language with the same etymology number form a group
+/// carrying their etymology text (the first entry's that has one), so
+/// homographs with unrelated origins are shown apart, as on Wiktionary.
+/// Languages come in the order their first entry was imported, and
+/// groups by etymology number within a language.
+pub fn get_definitions_for_word(
+    handle: &DictHandle,
+    word: &str,
+    options: &DefinitionOptions,
+) -> Result<Vec<EtymologyGroup>> {
+    let mut stmt = handle.conn.prepare_cached(
+        "SELECT id, language, COALESCE(etymology_num, 0) FROM words WHERE word = ?
+         ORDER BY MIN(id) OVER (PARTITION BY language), COALESCE(etymology_num, 0), id",
+    )?;
+    let rows = stmt.query_map(params![word], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, i32>(2)?,
+        ))
+    })?;
+    let words = rows.collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut groups: Vec<EtymologyGroup> = Vec::new();
+    for (word_id, language, etymology_num) in words {
+        let Some(definition) = get_full_definition_with_options(handle, word_id, options)? else {
+            continue;
+        };
+        let group = match groups.last_mut() {
+            Some(group) if group.language == language && group.etymology_num == etymology_num => {
+                group
+            }
+            _ => {
+                groups.push(EtymologyGroup {
+                    language,
+                    etymology_num,
+                    etymology: None,
+                    entries: Vec::new(),
+                });
+                groups.last_mut().unwrap()
+            }
+        };
+        if group.etymology.is_none() {
+            group.etymology = definition.etymology.clone();
+        }
+        group.entries.push(EtymologyEntry {
+            word_id,
+            definition,
+        });
+    }
+    Ok(groups)
+}
+
 /// Get all words for a specific language
+///
+/// Words come in alphabetical order, ignoring ASCII case.
 pub fn get_words_by_language(
     handle: &DictHandle,
     language: &str,
     limit: u32,
     offset: u32,
 ) -> Result<Vec<crate::models::Word>> {
+    get_words_by_language_with_options(handle, language, &BrowseOptions::default(), limit, offset)
+}
+
+/// Get the words of a language matching `options`, for browsing
+///
+/// Words come in alphabetical order, ignoring ASCII case, then by ID.
+pub fn get_words_by_language_with_options(
+    handle: &DictHandle,
+    language: &str,
+    options: &BrowseOptions,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<crate::models::Word>> {
+    let kinds: Vec<String> = options.pos.iter().cloned().collect();
+    browse_words(
+        handle,
+        language,
+        &kinds,
+        options.tag.as_deref(),
+        options.starts_with.as_deref(),
+        limit,
+        offset,
+    )
+}
+
+/// Get the proverbs, idioms and other set phrases of a language, for an
+/// idioms section
+///
+/// Phrases are entries whose part of speech is one of `filters.kinds`;
+/// they come in the order of [`get_words_by_language_with_options`].
+pub fn get_phrases(
+    handle: &DictHandle,
+    language: &str,
+    filters: &PhraseFilters,
+) -> Result<Vec<crate::models::Word>> {
+    if filters.kinds.is_empty() {
+        return Ok(Vec::new());
+    }
+    browse_words(
+        handle,
+        language,
+        &filters.kinds,
+        filters.topic.as_deref(),
+        filters.starts_with.as_deref(),
+        filters.limit,
+        filters.offset,
+    )
+}
+
+/// Words of a language in browsing order; an empty `pos` allows any
+fn browse_words(
+    handle: &DictHandle,
+    language: &str,
+    pos: &[String],
+    tag: Option<&str>,
+    starts_with: Option<&str>,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<crate::models::Word>> {
+    let mut sql =
+        "SELECT id, word, pos, language, etymology_num FROM words w WHERE language = ?".to_string();
+    let mut values: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(language.to_string())];
+    if !pos.is_empty() {
+        sql.push_str(&format!(" AND pos IN ({})", vec!["?"; pos.len()].join(",")));
+        values.extend(
+            pos.iter()
+                .map(|p| Box::new(p.clone()) as Box<dyn rusqlite::ToSql>),
+        );
+    }
+    if let Some(tag) = tag {
+        sql.push_str(" AND ");
+        sql.push_str(&filter_condition(1));
+        values.push(Box::new(tag.to_string()));
+    }
+    if let Some(prefix) = starts_with.filter(|p| !p.is_empty()) {
+        sql.push_str(" AND word >= ? COLLATE NOCASE AND word < ? COLLATE NOCASE");
+        values.push(Box::new(prefix.to_string()));
+        values.push(Box::new(format!("{}\u{10FFFF}", prefix)));
+    }
+    sql.push_str(" ORDER BY word COLLATE NOCASE, id LIMIT ? OFFSET ?");
+    values.push(Box::new(limit));
+    values.push(Box::new(offset));
+
+    let mut stmt = handle.conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(values), |row| {
+        Ok(crate::models::Word {
+            id: row.get(0)?,
+            word: row.get(1)?,
+            pos: row.get(2)?,
+            language: row.get(3)?,
+            etymology_num: row.get(4)?,
+        })
+    })?;
+
+    rows.collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| e.into())
+}
+
+/// Count the words of a language by initial letter, for an A–Z index
+///
+/// Words are filed by the first character of their normalized key (see
+/// [`crate::normalize::search_key`]), so "Éclair" counts under "e".
+/// Letters come in key order; initials that are not letters (digits,
+/// "-" of suffixes) are listed like letters.
+pub fn get_letter_index(handle: &DictHandle, language: &str) -> Result<Vec<LetterCount>> {
+    if !has_column(&handle.conn, "words", "search_key")? {
+        // Databases built before search keys: file every word here
+        let mut stmt = handle
+            .conn
+            .prepare("SELECT word FROM words WHERE language = ?")?;
+        let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+        let rows = stmt.query_map([language], |row| row.get::<_, String>(0))?;
+        for word in rows {
+            if let Some(letter) = crate::normalize::initial(&word?) {
+                *counts.entry(letter.to_string()).or_default() += 1;
+            }
+        }
+        return Ok(counts
+            .into_iter()
+            .map(|(letter, count)| LetterCount { letter, count })
+            .collect());
+    }
+
     let mut stmt = handle.conn.prepare(
-        "SELECT id, word, pos, language, etymology_num FROM words WHERE language = ? LIMIT ? OFFSET ?",
+        "SELECT substr(search_key, 1, 1) AS letter, COUNT(*) FROM words
+         WHERE language = ? AND search_key != ''
+         GROUP BY letter ORDER BY letter",
     )?;
+    let rows = stmt.query_map([language], |row| {
+        Ok(LetterCount {
+            letter: row.get(0)?,
+            count: row.get(1)?,
+        })
+    })?;
+    rows.collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| e.into())
+}
 
-    let rows = stmt.query_map(params![language, limit, offset], |row| {
+/// Get the words of a language filed under `letter` in the A–Z index
+///
+/// `letter` is folded like headwords, so "E" and "é" both list "eclair"
+/// and "Éclair". Words come in key order, then as written.
+pub fn get_words_by_initial(
+    handle: &DictHandle,
+    language: &str,
+    letter: &str,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<crate::models::Word>> {
+    let Some(initial) = handle.normalization.initial(letter) else {
+        return Ok(Vec::new());
+    };
+    let key = if has_column(&handle.conn, "words", "search_key")? {
+        "search_key"
+    } else {
+        // Older databases: lowercase only, no folding
+        "lower(word)"
+    };
+    let mut stmt = handle.conn.prepare(&format!(
+        "SELECT id, word, pos, language, etymology_num FROM words
+         WHERE language = ? AND {key} >= ? AND {key} < ?
+         ORDER BY {key}, word, id LIMIT ? OFFSET ?"
+    ))?;
+    let low = initial.to_string();
+    let high = format!("{}\u{10FFFF}", initial);
+    let rows = stmt.query_map(params![language, low, high, limit, offset], |row| {
         Ok(crate::models::Word {
             id: row.get(0)?,
             word: row.get(1)?,
@@ -550,11 +1847,119 @@ pub fn get_words_by_language(
             etymology_num: row.get(4)?,
         })
     })?;
-
     rows.collect::<std::result::Result<Vec<_>, _>>()
         .map_err(|e| e.into())
 }
 
+/// Get a random word that has at least one definition
+///
+/// Picks a random ID and takes the next defined word at or after it (or the
+/// first one if there is none), which avoids `ORDER BY random()` scanning
+/// the whole table. Words matching an active content filter are never
+/// picked. Returns `None` for an empty database.
+pub fn get_random_word(handle: &DictHandle) -> Result<Option<crate::models::Word>> {
+    let max_id: Option<i64> = handle
+        .conn
+        .query_row("SELECT MAX(id) FROM words", [], |row| row.get(0))?;
+    let Some(max_id) = max_id else {
+        return Ok(None);
+    };
+    let start: i64 =
+        handle
+            .conn
+            .query_row("SELECT abs(random() % ?) + 1", params![max_id], |row| {
+                row.get(0)
+            })?;
+    defined_word_from(handle, start)
+}
+
+/// The word of the day for `day`, counted in days since 1970-01-01 (UTC)
+///
+/// The same for every install of the same database, so users can share
+/// it; like `get_random_word` it always has a definition and never matches
+/// an active content filter.
+pub fn get_word_of_the_day(handle: &DictHandle, day: i64) -> Result<Option<crate::models::Word>> {
+    let max_id: Option<i64> = handle
+        .conn
+        .query_row("SELECT MAX(id) FROM words", [], |row| row.get(0))?;
+    let Some(max_id) = max_id else {
+        return Ok(None);
+    };
+    // SplitMix64, so consecutive days land far apart
+    let mut z = (day as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    defined_word_from(handle, (z % max_id as u64) as i64 + 1)
+}
+
+/// The first defined word with an ID at or after `start`, wrapping around
+fn defined_word_from(handle: &DictHandle, start: i64) -> Result<Option<crate::models::Word>> {
+    let filter = &handle.config.content_filter;
+    let mut sql = "SELECT id, word, pos, language, etymology_num FROM words w
+               WHERE id >= ? AND EXISTS (SELECT 1 FROM definitions WHERE word_id = w.id)"
+        .to_string();
+    let mut tags: Vec<&str> = Vec::new();
+    if filter.is_active() {
+        sql.push_str(&format!(" AND NOT {}", filter_condition(filter.tags.len())));
+        tags = filter.tags.iter().map(String::as_str).collect();
+    }
+    sql.push_str(" ORDER BY id LIMIT 1");
+
+    let mut stmt = handle.conn.prepare(&sql)?;
+    for from in [start, 0] {
+        let params = std::iter::once(&from as &dyn rusqlite::ToSql)
+            .chain(tags.iter().map(|t| t as &dyn rusqlite::ToSql));
+        match stmt.query_row(rusqlite::params_from_iter(params), |row| {
+            Ok(crate::models::Word {
+                id: row.get(0)?,
+                word: row.get(1)?,
+                pos: row.get(2)?,
+                language: row.get(3)?,
+                etymology_num: row.get(4)?,
+            })
+        }) {
+            Ok(word) => return Ok(Some(word)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(None)
+}
+
+/// SQL condition: word `w` has a sense carrying one of the tags bound as
+/// the statement's last `tags` parameters
+pub(crate) fn filter_condition(tags: usize) -> String {
+    format!(
+        "EXISTS (SELECT 1 FROM definitions fd, json_each(fd.tags) ft
+                 WHERE fd.word_id = w.id AND ft.value IN ({}))",
+        vec!["?"; tags].join(",")
+    )
+}
+
+/// The words among `ids` that match the handle's content filter
+pub(crate) fn filtered_words(
+    handle: &DictHandle,
+    ids: &[i64],
+) -> Result<std::collections::HashSet<i64>> {
+    let tags = &handle.config.content_filter.tags;
+    if ids.is_empty() || tags.is_empty() {
+        return Ok(Default::default());
+    }
+    let sql = format!(
+        "SELECT id FROM words w WHERE id IN ({}) AND {}",
+        vec!["?"; ids.len()].join(","),
+        filter_condition(tags.len())
+    );
+    let mut stmt = handle.conn.prepare(&sql)?;
+    let params = ids
+        .iter()
+        .map(|id| id as &dyn rusqlite::ToSql)
+        .chain(tags.iter().map(|t| t as &dyn rusqlite::ToSql));
+    let rows = stmt.query_map(rusqlite::params_from_iter(params), |row| row.get(0))?;
+    Ok(rows.collect::<std::result::Result<_, _>>()?)
+}
+
 /// Get word count for statistics
 pub fn get_word_count(handle: &DictHandle) -> Result<i64> {
     let count: i64 = handle
@@ -573,8 +1978,750 @@ pub fn get_word_count_by_language(handle: &DictHandle, language: &str) -> Result
     Ok(count)
 }
 
+// ============================================================================
+// Metadata and Statistics
+// ============================================================================
+
+/// Set a build metadata value, replacing any existing value for the key
+pub(crate) fn set_meta(conn: &Connection, key: &str, value: &str) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES (?, ?)",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+/// The normalization policy recorded in the `meta` table
+///
+/// Databases recording none, including those built before the policy was
+/// recorded, fold case and diacritics.
+pub(crate) fn normalization(conn: &Connection) -> Result<Normalization> {
+    if !table_exists(conn, "meta")? {
+        return Ok(Normalization::default());
+    }
+    let name: Option<String> = conn
+        .prepare_cached("SELECT value FROM meta WHERE key = 'normalization'")?
+        .query_row([], |row| row.get(0))
+        .optional()?;
+    match name {
+        None => Ok(Normalization::default()),
+        Some(name) => Normalization::from_name(&name)
+            .ok_or_else(|| Error::InvalidInput(format!("unknown normalization policy '{name}'"))),
+    }
+}
+
+/// Get all build metadata
+///
+/// Returns an empty map for databases built before the `meta` table existed.
+pub fn get_metadata(handle: &DictHandle) -> Result<BTreeMap<String, String>> {
+    if !table_exists(&handle.conn, "meta")? {
+        return Ok(BTreeMap::new());
+    }
+
+    let mut stmt = handle.conn.prepare("SELECT key, value FROM meta")?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+    rows.collect::<std::result::Result<BTreeMap<_, _>, _>>()
+        .map_err(|e| e.into())
+}
+
+/// Check whether this library can read the database at `db_path`
+///
+/// Opens the file read-only without setting up a handle, so the app can
+/// check a downloaded dictionary before switching to it and ask for an
+/// app update rather than fail on the first search. Errors only if the
+/// file cannot be read as SQLite at all.
+pub fn check_compatibility(db_path: &str) -> Result<CompatibilityReport> {
+    let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let schema_version: u32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    let mut report = CompatibilityReport {
+        schema_version,
+        supported_schema_version: SCHEMA_VERSION,
+        generator: None,
+        tokenizer: None,
+        required_features: Vec::new(),
+        missing_features: Vec::new(),
+        sqlite_version: rusqlite::version().to_string(),
+        problems: Vec::new(),
+    };
+
+    if !table_exists(&conn, "words")? {
+        report
+            .problems
+            .push("not a dictionary: it has no words table".to_string());
+        return Ok(report);
+    }
+    if schema_version > SCHEMA_VERSION {
+        report.problems.push(format!(
+            "schema version {schema_version} is newer than the supported {SCHEMA_VERSION}"
+        ));
+    }
+    if table_exists(&conn, "meta")? {
+        report.generator = conn
+            .query_row(
+                "SELECT value FROM meta WHERE key = 'generator'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+    }
+    if let Err(e) = normalization(&conn) {
+        report.problems.push(e.to_string());
+    }
+
+    // Definitions keep examples and tags as JSON
+    report.required_features.push("json1".to_string());
+    if conn.query_row("SELECT json('[]')", [], |_| Ok(())).is_err() {
+        report.missing_features.push("json1".to_string());
+        report
+            .problems
+            .push("SQLite lacks the JSON functions".to_string());
+    }
+
+    let fts_sql: Option<String> = conn
+        .query_row(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'words_fts'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(sql) = fts_sql {
+        report.required_features.push("fts5".to_string());
+        report.tokenizer = fts5_tokenizer(&sql);
+        if !fts5_available(&conn) {
+            report.missing_features.push("fts5".to_string());
+        } else if let Some(tokenizer) = &report.tokenizer {
+            let name = tokenizer.split_whitespace().next().unwrap_or_default();
+            report.required_features.push(format!("tokenizer {name}"));
+            let escaped = tokenizer.replace('\'', "\'\'");
+            if !fts5_probe(&conn, &format!("x, tokenize='{escaped}'")) {
+                report.missing_features.push(format!("tokenizer {name}"));
+                report
+                    .problems
+                    .push(format!("SQLite lacks the FTS5 tokenizer {name}"));
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// The `tokenize` option of an FTS5 table's CREATE statement, unquoted
+fn fts5_tokenizer(sql: &str) -> Option<String> {
+    let start = sql.find("tokenize")? + "tokenize".len();
+    let value = sql[start..].trim_start().strip_prefix('=')?.trim_start();
+    let quote = value.chars().next().filter(|c| matches!(c, '\'' | '"'))?;
+    let end = value[1..].find(quote)?;
+    Some(value[1..=end].to_string())
+}
+
+/// Get summary statistics for the database
+///
+/// `top_n` bounds the language, part-of-speech and tag breakdowns; with
+/// 0 they are left empty without being queried. Counting tags scans every definition, so this is intended for tooling
+/// rather than the app's hot path.
+pub fn get_database_stats(handle: &DictHandle, top_n: u32) -> Result<DatabaseStats> {
+    let conn = &handle.conn;
+    let count = |table: &str| -> Result<i64> {
+        let count = conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+            row.get(0)
+        })?;
+        Ok(count)
+    };
+
+    let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+    let page_count: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+
+    Ok(DatabaseStats {
+        metadata: get_metadata(handle)?,
+        words: count("words")?,
+        definitions: count("definitions")?,
+        pronunciations: count("pronunciations")?,
+        etymologies: count("etymologies")?,
+        translations: count("translations")?,
+        languages: query_counts(
+            conn,
+            "SELECT language, COUNT(*) FROM words GROUP BY language ORDER BY 2 DESC, 1 LIMIT ?",
+            top_n,
+        )?,
+        parts_of_speech: query_counts(
+            conn,
+            "SELECT pos, COUNT(*) FROM words GROUP BY pos ORDER BY 2 DESC, 1 LIMIT ?",
+            top_n,
+        )?,
+        top_tags: query_counts(
+            conn,
+            r#"
+            SELECT t.value, COUNT(*)
+            FROM definitions d, json_each(d.tags) t
+            WHERE json_valid(d.tags)
+            GROUP BY t.value
+            ORDER BY 2 DESC, 1
+            LIMIT ?
+            "#,
+            top_n,
+        )?,
+        tables: get_table_sizes(conn),
+        total_bytes: page_size * page_count,
+    })
+}
+
+/// Run a `(label, count)` query bounded by `limit`
+fn query_counts(conn: &Connection, sql: &str, limit: u32) -> Result<Vec<CountEntry>> {
+    if limit == 0 {
+        return Ok(Vec::new());
+    }
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map(params![limit], |row| {
+        Ok(CountEntry {
+            name: row.get(0)?,
+            count: row.get(1)?,
+        })
+    })?;
+
+    rows.collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| e.into())
+}
+
+/// Get per-table storage usage via the `dbstat` virtual table
+///
+/// Returns an empty list if SQLite was built without `dbstat` support.
+fn get_table_sizes(conn: &Connection) -> Vec<TableSize> {
+    let sizes = conn
+        .prepare("SELECT name, SUM(pgsize) FROM dbstat GROUP BY name ORDER BY 2 DESC")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| {
+                Ok(TableSize {
+                    name: row.get(0)?,
+                    bytes: row.get(1)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()
+        });
+
+    match sizes {
+        Ok(sizes) => sizes,
+        Err(e) => {
+            log::debug!("dbstat unavailable, skipping size breakdown: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+// ============================================================================
+// Warm-up
+// ============================================================================
+
+/// Bytes read from each index by `warm_up`, which bounds its IO on large
+/// databases
+const WARM_UP_BYTES: u64 = 4 << 20;
+
+/// Indexes every search reads, as (table, column, query walking the index)
+const WARM_UP_QUERIES: &[(&str, &str, &str)] = &[
+    (
+        "words_fts_data",
+        "block",
+        "SELECT block FROM words_fts_data",
+    ),
+    ("words", "word", "SELECT word FROM words ORDER BY word"),
+    (
+        "words",
+        "search_key",
+        "SELECT search_key FROM words WHERE search_key IS NOT NULL ORDER BY search_key",
+    ),
+    ("completions", "key", "SELECT key, word FROM completions"),
+    (
+        "definitions",
+        "word_id",
+        "SELECT word_id FROM definitions ORDER BY word_id",
+    ),
+];
+
+/// Read the hot indexes so the first search does not wait on the disk
+///
+/// Meant for app startup, behind a splash screen: a freshly opened
+/// database has none of its full-text index in the page cache, which
+/// makes the first search slow. Reads at most `WARM_UP_BYTES` of each
+/// index and returns the total number of bytes read.
+pub fn warm_up(handle: &DictHandle) -> Result<u64> {
+    let mut total = 0;
+    for (table, column, sql) in WARM_UP_QUERIES {
+        if !has_column(&handle.conn, table, column)? {
+            continue;
+        }
+        let mut stmt = handle.conn.prepare(sql)?;
+        let mut rows = stmt.query([])?;
+        let mut read = 0;
+        while read < WARM_UP_BYTES {
+            let Some(row) = rows.next()? else {
+                break;
+            };
+            read += match row.get_ref(0)? {
+                ValueRef::Text(bytes) | ValueRef::Blob(bytes) => bytes.len() as u64,
+                _ => 8,
+            };
+        }
+        total += read;
+    }
+    Ok(total)
+}
+
+// ============================================================================
+// Related-words graph
+// ============================================================================
+
+/// Most entries in a graph from `get_graph_neighborhood`
+const MAX_GRAPH_NODES: usize = 50;
+
+/// Entries related to `word_id` within `depth` steps, for a map of
+/// related words around an entry
+///
+/// Follows the relations of `kinds`: synonyms and antonyms as listed in an
+/// entry, and forms to their headwords, through form-of senses and the
+/// forms table. Relations are followed in the direction they are recorded
+/// ("cats" reaches "cat", not the other way), and only to words that have
+/// an entry in the same language. Stops at `MAX_GRAPH_NODES` entries;
+/// relations among the entries found are all included. An unknown
+/// `word_id` gives an empty graph.
+pub fn get_graph_neighborhood(
+    handle: &DictHandle,
+    word_id: i64,
+    depth: u32,
+    kinds: &[RelationKind],
+) -> Result<WordGraph> {
+    let conn = &handle.conn;
+    let Some(word) = conn
+        .query_row("SELECT word FROM words WHERE id = ?", [word_id], |row| {
+            row.get::<_, String>(0)
+        })
+        .optional()?
+    else {
+        return Ok(WordGraph::default());
+    };
+
+    // Queries for the (id, word) an entry ?1 is related to, per kind
+    let mut steps: Vec<(RelationKind, String)> = Vec::new();
+    if table_exists(conn, "relations")? {
+        for (kind, name) in [
+            (RelationKind::Synonym, "synonym"),
+            (RelationKind::Antonym, "antonym"),
+        ] {
+            if kinds.contains(&kind) {
+                steps.push((
+                    kind,
+                    format!(
+                        "SELECT DISTINCT t.id, t.word FROM relations r
+                         JOIN words w ON w.id = r.word_id
+                         JOIN words t ON t.word = r.target AND t.language = w.language
+                         WHERE r.word_id = ?1 AND r.kind = '{name}' AND t.id != ?1"
+                    ),
+                ));
+            }
+        }
+    }
+    if kinds.contains(&RelationKind::FormOf) {
+        if has_column(conn, "definitions", "form_of_id")? {
+            steps.push((
+                RelationKind::FormOf,
+                "SELECT DISTINCT t.id, t.word FROM definitions d
+                 JOIN words t ON t.id = d.form_of_id
+                 WHERE d.word_id = ?1 AND t.id != ?1"
+                    .to_string(),
+            ));
+        }
+        if table_exists(conn, "forms")? {
+            steps.push((
+                RelationKind::FormOf,
+                "SELECT DISTINCT t.id, t.word FROM words w
+                 JOIN forms f ON f.form = w.word
+                 JOIN words t ON t.id = f.word_id AND t.language = w.language
+                 WHERE w.id = ?1 AND t.id != ?1"
+                    .to_string(),
+            ));
+        }
+    }
+
+    let mut graph = WordGraph {
+        nodes: vec![GraphNode {
+            id: word_id,
+            word,
+            depth: 0,
+        }],
+        edges: Vec::new(),
+    };
+    let mut frontier = vec![word_id];
+    for level in 1..=depth {
+        let mut next = Vec::new();
+        for &from in &frontier {
+            for (kind, sql) in &steps {
+                let related = conn
+                    .prepare_cached(sql)?
+                    .query_map([from], |row| Ok((row.get::<_, i64>(0)?, row.get(1)?)))?
+                    .collect::<std::result::Result<Vec<(i64, String)>, _>>()?;
+                for (to, word) in related {
+                    if !graph.nodes.iter().any(|node| node.id == to) {
+                        if graph.nodes.len() >= MAX_GRAPH_NODES {
+                            continue;
+                        }
+                        graph.nodes.push(GraphNode {
+                            id: to,
+                            word,
+                            depth: level,
+                        });
+                        next.push(to);
+                    }
+                    let edge = GraphEdge {
+                        from,
+                        to,
+                        kind: *kind,
+                    };
+                    if !graph.edges.contains(&edge) {
+                        graph.edges.push(edge);
+                    }
+                }
+            }
+        }
+        frontier = next;
+    }
+    Ok(graph)
+}
+
+/// Up to `k` words whose meanings are closest to `word_id`'s, closest
+/// first, from the `related_semantic` table the preprocessor fills from
+/// gloss embeddings (see `semantic::build_related`)
+///
+/// Needs no model at query time. The preview is the related word's first
+/// definition and the score its cosine distance (0 = same direction).
+/// Databases built without the table return no words.
+pub fn get_semantically_related(
+    handle: &DictHandle,
+    word_id: i64,
+    k: u32,
+) -> Result<Vec<SearchResult>> {
+    if !table_exists(&handle.conn, "related_semantic")? {
+        return Ok(Vec::new());
+    }
+    let mut stmt = handle.conn.prepare_cached(
+        r#"
+        SELECT w.id, w.word, w.pos,
+               COALESCE((SELECT definition FROM definitions
+                         WHERE word_id = w.id ORDER BY id LIMIT 1), ''),
+               r.similarity
+        FROM related_semantic r
+        JOIN words w ON w.id = r.related_id
+        WHERE r.word_id = ?
+        ORDER BY r.rank
+        LIMIT ?
+        "#,
+    )?;
+    let rows = stmt.query_map(params![word_id, k], |row| {
+        let similarity: f64 = row.get(4)?;
+        let mut result = SearchResult::with_score(
+            row.get(0)?,
+            row.get(1)?,
+            row.get(2)?,
+            row.get(3)?,
+            1.0 - similarity,
+        );
+        result.relevance = crate::search::relevance(result.score);
+        Ok(result)
+    })?;
+    Ok(rows.collect::<std::result::Result<_, _>>()?)
+}
+
+// ============================================================================
+// Index building
+// ============================================================================
+
+/// Rows indexed per transaction by `build_indexes`
+const INDEX_CHUNK_SIZE: i64 = 2000;
+
+/// Indexes one (id, text) row selected by a `build_indexes` step
+type IndexRow<'a> = Box<dyn Fn(&Connection, i64, &str) -> Result<()> + 'a>;
+
+/// Headwords with Hangul in them, which need a jamo decomposition
+const HANGUL_GLOB: &str = "*[가-힣ㄱ-ㆎ]*";
+
+/// Fill in the full-text index and derived keys of a database shipped
+/// without them
+///
+/// For dictionaries downloaded as data only, where building the indexes
+/// on the device is the slow part. Indexes the headwords not yet in the
+/// full-text index, then fills in missing search and anagram keys, jamo
+/// of Hangul headwords and rhyme and IPA keys, a few thousand rows per
+/// transaction.
+/// `progress` receives (rows done, rows to do) after each chunk, and
+/// `cancel` is checked before each: setting it stops the build, e.g. on
+/// low battery, and calling this again later resumes where it stopped.
+/// Returns whether everything was built. Needs a writable handle.
+pub fn build_indexes(
+    handle: &DictHandle,
+    progress: impl Fn(u64, u64),
+    cancel: &AtomicBool,
+) -> Result<bool> {
+    let conn = &handle.conn;
+    // Headwords are indexed in id order, so the highest indexed id is how
+    // far an earlier build got
+    let fts_through: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(id), 0) FROM words_fts_docsize",
+        [],
+        |row| row.get(0),
+    )?;
+    let normalization = normalization(conn)?;
+    // Rows to index after the cursor ?1, as (id, text), and how to index
+    // one. Headwords go first: updating a word also updates its FTS row,
+    // which must exist by then
+    let steps: [(String, IndexRow); 4] = [
+        (
+            format!("SELECT id, word FROM words WHERE id > max(?1, {fts_through})"),
+            Box::new(|conn, id, word| {
+                conn.prepare_cached("INSERT INTO words_fts(rowid, word) VALUES (?, ?)")?
+                    .execute(params![id, word])?;
+                Ok(())
+            }),
+        ),
+        (
+            "SELECT id, word FROM words WHERE id > ?1 AND search_key IS NULL".to_string(),
+            Box::new(move |conn, id, word| {
+                conn.prepare_cached(
+                    "UPDATE words SET search_key = ?, anagram_key = ? WHERE id = ?",
+                )?
+                .execute(params![
+                    normalization.key(word),
+                    crate::games::anagram_key(word),
+                    id
+                ])?;
+                Ok(())
+            }),
+        ),
+        (
+            format!(
+                "SELECT id, word FROM words w WHERE id > ?1 AND word GLOB '{HANGUL_GLOB}'
+                   AND NOT EXISTS (SELECT 1 FROM jamo WHERE word_id = w.id)"
+            ),
+            Box::new(set_jamo),
+        ),
+        (
+            "SELECT id, ipa FROM pronunciations
+             WHERE id > ?1 AND ipa IS NOT NULL AND (rhyme IS NULL OR ipa_key IS NULL)"
+                .to_string(),
+            Box::new(|conn, id, ipa| {
+                conn.prepare_cached(
+                    "UPDATE pronunciations SET rhyme = ?, ipa_key = ? WHERE id = ?",
+                )?
+                .execute(params![
+                    crate::ipa::rhyme_key(ipa),
+                    crate::ipa::search_key(ipa),
+                    id
+                ])?;
+                Ok(())
+            }),
+        ),
+    ];
+
+    let mut total = 0;
+    for (select, _) in &steps {
+        let count: i64 =
+            conn.query_row(&format!("SELECT COUNT(*) FROM ({select})"), [0], |row| {
+                row.get(0)
+            })?;
+        total += count as u64;
+    }
+
+    let mut done = 0;
+    for (select, index) in &steps {
+        let chunk = format!("{select} ORDER BY id LIMIT {INDEX_CHUNK_SIZE}");
+        let mut after = 0;
+        loop {
+            if cancel.load(Ordering::Relaxed) {
+                return Ok(false);
+            }
+            let tx = conn.unchecked_transaction()?;
+            let rows = tx
+                .prepare_cached(&chunk)?
+                .query_map([after], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+                })?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            let Some(last) = rows.last().map(|(id, _)| *id) else {
+                break;
+            };
+            for (id, text) in &rows {
+                index(&tx, *id, text)?;
+            }
+            tx.commit()?;
+            after = last;
+            done += rows.len() as u64;
+            progress(done, total);
+        }
+    }
+    Ok(true)
+}
+
+// ============================================================================
+// Compaction
+// ============================================================================
+
+/// Strip content classes from the database and vacuum it
+///
+/// Works on read-only handles too: the changes are made through a separate
+/// writable connection to the same file, and the handle sees them once
+/// this returns. Nothing else may be reading the database meanwhile, and
+/// vacuuming needs temporary disk space about the size of the database.
+/// Classes that were removed completely are added to the `omitted_content`
+/// build metadata, as for a build that left them out.
+pub fn compact(handle: &DictHandle, options: &CompactOptions) -> Result<CompactStats> {
+    let path = handle
+        .conn
+        .path()
+        .filter(|p| !p.is_empty())
+        .ok_or_else(|| Error::InvalidPath("in-memory database".to_string()))?;
+    let mut conn = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?;
+    // Older databases have no meta table to record the removals in
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);",
+    )?;
+
+    let mut stats = CompactStats {
+        bytes_before: database_bytes(&conn)?,
+        ..CompactStats::default()
+    };
+    let mut omitted = Vec::new();
+
+    let tx = conn.transaction()?;
+    if let Some(languages) = &options.translation_languages {
+        if languages.is_empty() {
+            stats.translations_removed = tx.execute("DELETE FROM translations", [])? as u64;
+            omitted.push("translations");
+        } else {
+            let placeholders = vec!["?"; languages.len()].join(",");
+            let keep: Vec<String> = languages.iter().map(|l| l.to_lowercase()).collect();
+            stats.translations_removed = tx.execute(
+                &format!(
+                    "DELETE FROM translations WHERE lower(target_language) NOT IN ({})",
+                    placeholders
+                ),
+                rusqlite::params_from_iter(&keep),
+            )? as u64;
+            set_meta(&tx, "translation_languages", &keep.join(","))?;
+        }
+    }
+    if options.remove_etymologies {
+        stats.etymologies_removed = tx.execute("DELETE FROM etymologies", [])? as u64;
+        omitted.push("etymologies");
+    }
+    if options.remove_audio_urls {
+        stats.audio_urls_removed = tx.execute(
+            "UPDATE pronunciations SET audio_url = NULL WHERE audio_url IS NOT NULL",
+            [],
+        )? as u64;
+        // Rows that only held audio have nothing left to show
+        tx.execute(
+            "DELETE FROM pronunciations WHERE audio_url IS NULL AND COALESCE(ipa, '') = ''",
+            [],
+        )?;
+        omitted.push("audio");
+    }
+    if options.remove_examples {
+        stats.examples_removed = tx.execute(
+            "UPDATE definitions SET examples = '[]' WHERE examples IS NOT NULL AND examples != '[]'",
+            [],
+        )? as u64;
+        omitted.push("examples");
+    }
+    if options.remove_relations && table_exists(&tx, "relations")? {
+        stats.relations_removed = tx.execute("DELETE FROM relations", [])? as u64;
+        omitted.push("relations");
+    }
+
+    if !omitted.is_empty() {
+        let existing: Option<String> = tx
+            .query_row(
+                "SELECT value FROM meta WHERE key = 'omitted_content'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let mut all: Vec<&str> = existing
+            .as_deref()
+            .unwrap_or("")
+            .split(',')
+            .filter(|c| !c.is_empty())
+            .collect();
+        for class in omitted {
+            if !all.contains(&class) {
+                all.push(class);
+            }
+        }
+        set_meta(&tx, "omitted_content", &all.join(","))?;
+    }
+    tx.commit()?;
+
+    conn.execute_batch("VACUUM")?;
+    stats.bytes_after = database_bytes(&conn)?;
+    Ok(stats)
+}
+
+/// Database size in bytes (page_size * page_count)
+pub(crate) fn database_bytes(conn: &Connection) -> Result<i64> {
+    let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+    let page_count: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+    Ok(page_size * page_count)
+}
+
+/// Check whether the attached user database has a table
+///
+/// False when no user database is attached.
+pub(crate) fn user_table_exists(conn: &Connection, name: &str) -> Result<bool> {
+    let attached: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM pragma_database_list WHERE name = 'user')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !attached {
+        return Ok(false);
+    }
+    let exists = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM user.sqlite_master WHERE type = 'table' AND name = ?)",
+        params![name],
+        |row| row.get(0),
+    )?;
+    Ok(exists)
+}
+
+/// Check whether a table exists in the database
+pub(crate) fn table_exists(conn: &Connection, name: &str) -> Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?",
+        params![name],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+/// Files kept next to the database at `db_path` that belong to one build
+/// of it: the headword FST and SQLite's write-ahead log and shared memory
+///
+/// Installing another build over the database must not leave these
+/// behind, or handles would read the old build's keys or log.
+#[cfg(any(feature = "download", feature = "package"))]
+pub(crate) fn sidecar_paths(db_path: &std::path::Path) -> [std::path::PathBuf; 3] {
+    let with_suffix = |suffix: &str| {
+        let mut path = db_path.as_os_str().to_owned();
+        path.push(suffix);
+        std::path::PathBuf::from(path)
+    };
+    [
+        crate::fuzzy::fst_path(db_path),
+        with_suffix("-wal"),
+        with_suffix("-shm"),
+    ]
+}
+
 /// Rebuild the FTS index (useful after bulk operations)
-pub fn rebuild_fts_index(conn: &Connection) -> Result<()> {
+pub(crate) fn rebuild_fts_index(conn: &Connection) -> Result<()> {
     conn.execute_batch(
         r#"
         DELETE FROM words_fts;
@@ -585,7 +2732,7 @@ pub fn rebuild_fts_index(conn: &Connection) -> Result<()> {
 }
 
 /// Optimize the FTS index for better search performance
-pub fn optimize_fts_index(conn: &Connection) -> Result<()> {
+pub(crate) fn optimize_fts_index(conn: &Connection) -> Result<()> {
     conn.execute("INSERT INTO words_fts(words_fts) VALUES('optimize')", [])?;
     Ok(())
 }
@@ -642,6 +2789,87 @@ mod tests {
         assert_eq!(full_def.definitions[0].text, "A procedure for testing");
     }
 
+    #[test]
+    fn test_definition_page() {
+        let (_dir, handle) = setup_test_db();
+        let word_id = insert_word(&handle.conn, "set", "verb", "English", "en", 0).unwrap();
+        for n in 0..5 {
+            insert_definition(&handle.conn, word_id, &format!("Sense {n}"), &[], &[]).unwrap();
+        }
+
+        assert_eq!(get_sense_count(&handle, word_id).unwrap(), 5);
+        let page = get_definition_page(&handle, word_id, 2, 2).unwrap();
+        let texts: Vec<&str> = page.definitions.iter().map(|d| d.text.as_str()).collect();
+        assert_eq!(texts, ["Sense 2", "Sense 3"]);
+        assert_eq!((page.offset, page.total), (2, 5));
+        assert!(page.has_more());
+
+        let last = get_definition_page(&handle, word_id, 4, 2).unwrap();
+        assert_eq!(last.definitions.len(), 1);
+        assert!(!last.has_more());
+    }
+
+    #[test]
+    fn test_find_in_entry() {
+        let (_dir, handle) = setup_test_db();
+        let word_id = insert_word(&handle.conn, "run", "verb", "English", "en", 0).unwrap();
+        insert_definition(&handle.conn, word_id, "To move quickly", &[], &[]).unwrap();
+        let examples = ["She ran a café.".to_string(), "Run a Cafe".to_string()];
+        let sense = insert_definition(
+            &handle.conn,
+            word_id,
+            "To manage a business",
+            &examples,
+            &[],
+        )
+        .unwrap();
+
+        let matches = find_in_entry(&handle, word_id, "cafe").unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].sense_index, 1);
+        assert_eq!(matches[0].definition_id, sense);
+        assert_eq!(matches[0].example, Some(0));
+        assert_eq!(&examples[0][matches[0].start..matches[0].end], "café");
+        assert_eq!(matches[1].example, Some(1));
+
+        let gloss = find_in_entry(&handle, word_id, "QUICK").unwrap();
+        assert_eq!(gloss[0].sense_index, 0);
+        assert_eq!(gloss[0].example, None);
+        assert!(find_in_entry(&handle, word_id, "  ").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_dated_senses() {
+        let (_dir, mut handle) = setup_test_db();
+        let word_id = insert_word(&handle.conn, "thou", "pron", "English", "en", 0).unwrap();
+        let archaic = ["archaic".to_string()];
+        insert_definition(&handle.conn, word_id, "You (singular)", &[], &archaic).unwrap();
+        insert_definition(&handle.conn, word_id, "A thousand", &[], &[]).unwrap();
+        let senses = |handle: &DictHandle| -> Vec<String> {
+            get_full_definition(handle, word_id)
+                .unwrap()
+                .unwrap()
+                .definitions
+                .into_iter()
+                .map(|d| d.text)
+                .collect()
+        };
+        let preview = |handle: &DictHandle| {
+            let results = crate::search::search_words(handle, "thou", 1).unwrap();
+            (results[0].preview.clone(), results[0].sense_count)
+        };
+        assert_eq!(senses(&handle), ["You (singular)", "A thousand"]);
+
+        handle.config.dated_senses.mode = DatedSenseMode::Demote;
+        assert_eq!(senses(&handle), ["A thousand", "You (singular)"]);
+        assert_eq!(preview(&handle), ("A thousand".to_string(), 2));
+
+        handle.config.dated_senses.mode = DatedSenseMode::Hide;
+        assert_eq!(senses(&handle), ["A thousand"]);
+        assert_eq!(get_sense_count(&handle, word_id).unwrap(), 1);
+        assert_eq!(preview(&handle), ("A thousand".to_string(), 1));
+    }
+
     #[test]
     fn test_update_word() {
         let (_dir, handle) = setup_test_db();
@@ -726,6 +2954,103 @@ mod tests {
         assert_eq!(words.len(), 2);
     }
 
+    #[test]
+    fn test_get_definitions_for_word() {
+        let (_dir, handle) = setup_test_db();
+        let entries = [
+            ("noun", "English", 1, Some("From Old English benc.")),
+            ("noun", "English", 2, Some("From Italian banca.")),
+            ("verb", "English", 2, None),
+            ("verb", "English", 1, None),
+            ("noun", "Dutch", 0, Some("From Middle Dutch.")),
+        ];
+        let mut ids = Vec::new();
+        for (pos, language, etymology_num, etymology) in entries {
+            let id = insert_word(&handle.conn, "bank", pos, language, "", etymology_num).unwrap();
+            insert_definition(&handle.conn, id, "...", &[], &[]).unwrap();
+            if let Some(text) = etymology {
+                insert_etymology(&handle.conn, id, text).unwrap();
+            }
+            ids.push(id);
+        }
+
+        let groups =
+            get_definitions_for_word(&handle, "bank", &DefinitionOptions::default()).unwrap();
+        let summary: Vec<(&str, i32, Option<&str>, Vec<i64>)> = groups
+            .iter()
+            .map(|g| {
+                (
+                    g.language.as_str(),
+                    g.etymology_num,
+                    g.etymology.as_deref(),
+                    g.entries.iter().map(|e| e.word_id).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    "English",
+                    1,
+                    Some("From Old English benc."),
+                    vec![ids[0], ids[3]]
+                ),
+                (
+                    "English",
+                    2,
+                    Some("From Italian banca."),
+                    vec![ids[1], ids[2]]
+                ),
+                ("Dutch", 0, Some("From Middle Dutch."), vec![ids[4]]),
+            ]
+        );
+        assert_eq!(groups[0].entries[1].definition.pos, "verb");
+        let json = serde_json::to_value(&groups[0]).unwrap();
+        assert_eq!(json["entries"][0]["word"], "bank");
+        assert_eq!(json["entries"][0]["word_id"], ids[0]);
+        assert!(
+            get_definitions_for_word(&handle, "nothing", &DefinitionOptions::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_prefetch_definitions() {
+        let (_dir, mut handle) = setup_test_db();
+        let mut ids = Vec::new();
+        for word in ["bank", "banker", "banking"] {
+            let id = insert_word(&handle.conn, word, "noun", "English", "en", 0).unwrap();
+            insert_definition(&handle.conn, id, word, &[], &[]).unwrap();
+            ids.push(id);
+        }
+
+        // Without a cache the rows are only read
+        assert_eq!(prefetch_definitions(&handle, &ids).unwrap(), 3);
+        assert_eq!(prefetch_definitions(&handle, &ids).unwrap(), 3);
+
+        handle.config.definition_cache = 2;
+        assert_eq!(prefetch_definitions(&handle, &[ids[0], 999]).unwrap(), 1);
+        assert_eq!(prefetch_definitions(&handle, &ids[..2]).unwrap(), 1);
+        // Only as many ids as the cache holds are loaded
+        assert_eq!(prefetch_definitions(&handle, &ids).unwrap(), 0);
+        handle
+            .conn
+            .execute("UPDATE definitions SET definition = 'changed'", [])
+            .unwrap();
+        let cached = get_full_definition(&handle, ids[0]).unwrap().unwrap();
+        assert_eq!(cached.definitions[0].text, "bank");
+        let brief = DefinitionOptions {
+            parts: DefinitionParts::DEFINITIONS,
+            ..Default::default()
+        };
+        let loaded = get_full_definition_with_options(&handle, ids[0], &brief)
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.definitions[0].text, "changed");
+    }
+
     #[test]
     fn test_get_word_count() {
         let (_dir, handle) = setup_test_db();
@@ -741,6 +3066,153 @@ mod tests {
         assert_eq!(english_count, 2);
     }
 
+    #[test]
+    fn test_get_random_word() {
+        let (_dir, handle) = setup_test_db();
+        assert!(get_random_word(&handle).unwrap().is_none());
+
+        insert_word(&handle.conn, "empty", "noun", "English", "en", 0).unwrap();
+        let word_id =
+            insert_word(&handle.conn, "hello", "interjection", "English", "en", 0).unwrap();
+        insert_definition(&handle.conn, word_id, "A greeting", &[], &[]).unwrap();
+        insert_word(&handle.conn, "undefined", "noun", "English", "en", 0).unwrap();
+
+        for _ in 0..10 {
+            let word = get_random_word(&handle).unwrap().unwrap();
+            assert_eq!(word.word, "hello");
+        }
+
+        let other = insert_word(&handle.conn, "world", "noun", "English", "en", 0).unwrap();
+        insert_definition(&handle.conn, other, "The earth", &[], &[]).unwrap();
+        let today = get_word_of_the_day(&handle, 20_000).unwrap().unwrap();
+        assert_eq!(
+            get_word_of_the_day(&handle, 20_000).unwrap().unwrap().id,
+            today.id
+        );
+        let days: std::collections::HashSet<i64> = (20_000..20_020)
+            .map(|day| get_word_of_the_day(&handle, day).unwrap().unwrap().id)
+            .collect();
+        assert_eq!(days.len(), 2);
+    }
+
+    #[test]
+    fn test_get_words_by_language() {
+        let (_dir, handle) = setup_test_db();
+        for (word, pos, tags) in [
+            ("zebra", "noun", vec![]),
+            ("Apple", "noun", vec![]),
+            ("banana", "noun", vec!["informal".to_string()]),
+            ("bake", "verb", vec![]),
+            ("able", "adj", vec![]),
+        ] {
+            let id = insert_word(&handle.conn, word, pos, "English", "en", 0).unwrap();
+            insert_definition(&handle.conn, id, "...", &[], &tags).unwrap();
+        }
+        insert_word(&handle.conn, "Bach", "name", "German", "de", 0).unwrap();
+
+        let words = |options: &BrowseOptions| -> Vec<String> {
+            get_words_by_language_with_options(&handle, "English", options, 10, 0)
+                .unwrap()
+                .into_iter()
+                .map(|w| w.word)
+                .collect()
+        };
+        assert_eq!(
+            words(&BrowseOptions::default()),
+            ["able", "Apple", "bake", "banana", "zebra"]
+        );
+        let nouns = BrowseOptions {
+            pos: Some("noun".to_string()),
+            ..BrowseOptions::default()
+        };
+        assert_eq!(words(&nouns), ["Apple", "banana", "zebra"]);
+        let b = BrowseOptions {
+            starts_with: Some("B".to_string()),
+            ..BrowseOptions::default()
+        };
+        assert_eq!(words(&b), ["bake", "banana"]);
+        let informal = BrowseOptions {
+            tag: Some("informal".to_string()),
+            ..BrowseOptions::default()
+        };
+        assert_eq!(words(&informal), ["banana"]);
+
+        let page = get_words_by_language(&handle, "English", 2, 1).unwrap();
+        assert_eq!(page[0].word, "Apple");
+        assert_eq!(page.len(), 2);
+    }
+
+    #[test]
+    fn test_get_phrases() {
+        let (_dir, handle) = setup_test_db();
+        for (word, pos, tags) in [
+            ("a stitch in time saves nine", "proverb", vec![]),
+            ("cat", "noun", vec![]),
+            ("all hands on deck", "phrase", vec!["nautical".to_string()]),
+            ("at sea", "prep_phrase", vec!["nautical".to_string()]),
+            ("break a leg", "phrase", vec![]),
+        ] {
+            let id = insert_word(&handle.conn, word, pos, "English", "en", 0).unwrap();
+            insert_definition(&handle.conn, id, "...", &[], &tags).unwrap();
+        }
+
+        let phrases = |filters: &PhraseFilters| -> Vec<String> {
+            get_phrases(&handle, "English", filters)
+                .unwrap()
+                .into_iter()
+                .map(|w| w.word)
+                .collect()
+        };
+        assert_eq!(
+            phrases(&PhraseFilters::default()),
+            [
+                "a stitch in time saves nine",
+                "all hands on deck",
+                "at sea",
+                "break a leg"
+            ]
+        );
+        let nautical = PhraseFilters {
+            topic: Some("nautical".to_string()),
+            ..PhraseFilters::default()
+        };
+        assert_eq!(phrases(&nautical), ["all hands on deck", "at sea"]);
+        let proverbs = PhraseFilters {
+            kinds: vec!["proverb".to_string()],
+            ..PhraseFilters::default()
+        };
+        assert_eq!(phrases(&proverbs), ["a stitch in time saves nine"]);
+        let page = PhraseFilters {
+            limit: 1,
+            offset: 3,
+            ..PhraseFilters::default()
+        };
+        assert_eq!(phrases(&page), ["break a leg"]);
+    }
+
+    #[test]
+    fn test_letter_index() {
+        let (_dir, handle) = setup_test_db();
+        for word in ["eclair", "Éclair", "egg", "apple", "Zebra", "-ism"] {
+            insert_word(&handle.conn, word, "noun", "English", "en", 0).unwrap();
+        }
+        insert_word(&handle.conn, "Ei", "noun", "German", "de", 0).unwrap();
+
+        let index = get_letter_index(&handle, "English").unwrap();
+        let summary: Vec<(&str, u64)> =
+            index.iter().map(|l| (l.letter.as_str(), l.count)).collect();
+        assert_eq!(summary, [("-", 1), ("a", 1), ("e", 3), ("z", 1)]);
+
+        let words: Vec<String> = get_words_by_initial(&handle, "English", "É", 10, 0)
+            .unwrap()
+            .into_iter()
+            .map(|w| w.word)
+            .collect();
+        assert_eq!(words, ["eclair", "Éclair", "egg"]);
+        let page = get_words_by_initial(&handle, "English", "e", 1, 2).unwrap();
+        assert_eq!(page[0].word, "egg");
+    }
+
     #[test]
     fn test_pronunciations() {
         let (_dir, handle) = setup_test_db();
@@ -765,6 +3237,33 @@ mod tests {
         assert_eq!(full_def.pronunciations[0].accent.as_deref(), Some("US"));
     }
 
+    #[test]
+    fn test_preferred_accents() {
+        let (_dir, mut handle) = setup_test_db();
+        let hello = insert_word(&handle.conn, "hello", "interjection", "English", "en", 0).unwrap();
+        for (ipa, accent) in [
+            ("/həˈloʊ/", "US"),
+            ("/həˈləʊ/", "UK"),
+            ("/hɛˈləʊ/", "Scotland"),
+        ] {
+            insert_pronunciation(&handle.conn, hello, Some(ipa), None, Some(accent)).unwrap();
+        }
+        let accents = |handle: &DictHandle| -> Vec<String> {
+            get_full_definition(handle, hello)
+                .unwrap()
+                .unwrap()
+                .pronunciations
+                .into_iter()
+                .filter_map(|p| p.accent)
+                .collect()
+        };
+
+        handle.config.preferred_accents = vec!["UK".to_string()];
+        assert_eq!(accents(&handle), ["UK", "US", "Scotland"]);
+        handle.config.preferred_accents = vec!["Scotland".to_string(), "UK".to_string()];
+        assert_eq!(accents(&handle), ["Scotland", "UK", "US"]);
+    }
+
     #[test]
     fn test_translations() {
         let (_dir, handle) = setup_test_db();
@@ -778,6 +3277,388 @@ mod tests {
 
         let full_def = get_full_definition(&handle, word_id).unwrap().unwrap();
         assert_eq!(full_def.translations.len(), 3);
+
+        let mut handle = handle;
+        handle.config.translation_languages = Some(vec!["fr".to_string(), "de".to_string()]);
+        let full_def = get_full_definition(&handle, word_id).unwrap().unwrap();
+        let mut languages: Vec<&str> = full_def
+            .translations
+            .iter()
+            .map(|t| t.target_language.as_str())
+            .collect();
+        languages.sort();
+        assert_eq!(languages, ["de", "fr"]);
+
+        handle.config.translation_languages = Some(Vec::new());
+        let full_def = get_full_definition(&handle, word_id).unwrap().unwrap();
+        assert!(full_def.translations.is_empty());
+    }
+
+    #[test]
+    fn test_translation_languages() {
+        let (dir, handle) = setup_test_db();
+        let hello = insert_word(&handle.conn, "hello", "interjection", "English", "en", 0).unwrap();
+        for (language, translation) in [
+            ("es", "hola"),
+            ("fr", "bonjour"),
+            ("fr", "salut"),
+            ("de", "hallo"),
+            ("Old English", "wes hāl"),
+        ] {
+            insert_translation(&handle.conn, hello, language, translation).unwrap();
+        }
+        let cat = insert_word(&handle.conn, "cat", "noun", "English", "en", 0).unwrap();
+        insert_translation(&handle.conn, cat, "es", "gato").unwrap();
+
+        // A handle opened for French and Old English readers
+        let config = DictConfig {
+            translation_languages: Some(vec!["fr".to_string(), "Old English".to_string()]),
+            ..DictConfig::default()
+        };
+        let path = dir.path().join("test.db");
+        let reader = open_readonly_with_config(path.to_str().unwrap(), config).unwrap();
+
+        let entry = crate::get_definition(&reader, hello).unwrap();
+        let mut translations: Vec<(&str, &str)> = entry
+            .translations
+            .iter()
+            .map(|t| (t.target_language.as_str(), t.translation.as_str()))
+            .collect();
+        translations.sort();
+        assert_eq!(
+            translations,
+            [
+                ("Old English", "wes hāl"),
+                ("fr", "bonjour"),
+                ("fr", "salut")
+            ]
+        );
+        // Nothing in the chosen languages
+        assert!(crate::get_definition(&reader, cat)
+            .unwrap()
+            .translations
+            .is_empty());
+        // Other handles are unaffected
+        assert_eq!(
+            crate::get_definition(&handle, hello)
+                .unwrap()
+                .translations
+                .len(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_max_examples_per_sense() {
+        let (_dir, mut handle) = setup_test_db();
+        let word_id = insert_word(&handle.conn, "run", "verb", "English", "en", 0).unwrap();
+        let examples = ["I run.", "She runs.", "They ran."].map(String::from);
+        insert_definition(&handle.conn, word_id, "To move fast", &examples, &[]).unwrap();
+
+        let full_def = get_full_definition(&handle, word_id).unwrap().unwrap();
+        assert_eq!(full_def.definitions[0].examples.len(), 3);
+
+        handle.config.max_examples_per_sense = Some(2);
+        let full_def = get_full_definition(&handle, word_id).unwrap().unwrap();
+        assert_eq!(full_def.definitions[0].examples, ["I run.", "She runs."]);
+    }
+
+    #[test]
+    fn test_definition_parts() {
+        let (_dir, handle) = setup_test_db();
+        let word_id =
+            insert_word(&handle.conn, "hello", "interjection", "English", "en", 0).unwrap();
+        insert_definition(&handle.conn, word_id, "A greeting", &[], &[]).unwrap();
+        insert_pronunciation(&handle.conn, word_id, Some("/həˈloʊ/"), None, None).unwrap();
+        insert_translation(&handle.conn, word_id, "es", "hola").unwrap();
+
+        let options = DefinitionOptions {
+            parts: DefinitionParts::DEFINITIONS | DefinitionParts::TRANSLATIONS,
+            ..Default::default()
+        };
+        let def = get_full_definition_with_options(&handle, word_id, &options)
+            .unwrap()
+            .unwrap();
+        assert_eq!(def.word, "hello");
+        assert_eq!(def.definitions.len(), 1);
+        assert_eq!(def.translations.len(), 1);
+        assert!(def.pronunciations.is_empty());
+
+        let full = get_full_definition(&handle, word_id).unwrap().unwrap();
+        assert_eq!(full.pronunciations[0].respelling, None);
+        let options = DefinitionOptions {
+            respelling: true,
+            ..Default::default()
+        };
+        let def = get_full_definition_with_options(&handle, word_id, &options)
+            .unwrap()
+            .unwrap();
+        assert_eq!(def.pronunciations[0].respelling.as_deref(), Some("hə-LOH"));
+
+        assert!(DefinitionParts::default().contains(DefinitionParts::ETYMOLOGY));
+        assert_eq!(DefinitionParts::from_bits(0xff), DefinitionParts::ALL);
+    }
+
+    #[test]
+    fn test_database_stats() {
+        let (_dir, handle) = setup_test_db();
+
+        let hello = insert_word(&handle.conn, "hello", "interjection", "English", "en", 0).unwrap();
+        let world = insert_word(&handle.conn, "world", "noun", "English", "en", 0).unwrap();
+        insert_word(&handle.conn, "monde", "noun", "French", "fr", 0).unwrap();
+        insert_definition(
+            &handle.conn,
+            hello,
+            "A greeting",
+            &[],
+            &["informal".to_string()],
+        )
+        .unwrap();
+        insert_definition(
+            &handle.conn,
+            world,
+            "The earth",
+            &[],
+            &["informal".to_string(), "rare".to_string()],
+        )
+        .unwrap();
+        set_meta(&handle.conn, "source", "test.jsonl").unwrap();
+
+        let stats = get_database_stats(&handle, 10).unwrap();
+        assert_eq!(stats.words, 3);
+        assert_eq!(stats.definitions, 2);
+        assert_eq!(stats.languages[0].name, "English");
+        assert_eq!(stats.languages[0].count, 2);
+        assert_eq!(stats.parts_of_speech[0].name, "noun");
+        assert_eq!(stats.top_tags[0].name, "informal");
+        assert_eq!(stats.top_tags[0].count, 2);
+        assert_eq!(stats.metadata.get("source").unwrap(), "test.jsonl");
+        assert!(stats.total_bytes > 0);
+
+        let stats = get_database_stats(&handle, 0).unwrap();
+        assert_eq!(stats.words, 3);
+        assert!(stats.languages.is_empty());
+        assert!(stats.top_tags.is_empty());
+    }
+
+    #[test]
+    fn test_warm_up() {
+        let (dir, handle) = setup_test_db();
+        let hello = insert_word(&handle.conn, "hello", "interjection", "English", "en", 0).unwrap();
+        insert_definition(&handle.conn, hello, "A greeting", &[], &[]).unwrap();
+
+        let readonly = open_readonly(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let read = warm_up(&readonly).unwrap();
+        // At least the headword from the word, key and completion indexes
+        assert!(read >= 3 * "hello".len() as u64);
+    }
+
+    #[test]
+    fn test_graph_neighborhood() {
+        let (_dir, handle) = setup_test_db();
+        let conn = &handle.conn;
+        let big = insert_word(conn, "big", "adjective", "English", "en", 0).unwrap();
+        let large = insert_word(conn, "large", "adjective", "English", "en", 0).unwrap();
+        let small = insert_word(conn, "small", "adjective", "English", "en", 0).unwrap();
+        let little = insert_word(conn, "little", "adjective", "English", "en", 0).unwrap();
+        let bigger = insert_word(conn, "bigger", "adjective", "English", "en", 0).unwrap();
+        insert_word(conn, "grand", "adjective", "French", "fr", 0).unwrap();
+        insert_form(conn, big, "bigger", &["comparative".to_string()]).unwrap();
+        insert_relation(conn, big, None, "synonym", "large", None).unwrap();
+        insert_relation(conn, big, None, "synonym", "grand", None).unwrap();
+        insert_relation(conn, big, None, "antonym", "small", None).unwrap();
+        insert_relation(conn, small, None, "synonym", "little", None).unwrap();
+        insert_relation(conn, small, None, "antonym", "big", None).unwrap();
+
+        let graph = get_graph_neighborhood(&handle, bigger, 2, RelationKind::ALL).unwrap();
+        let nodes: Vec<(&str, u32)> = graph
+            .nodes
+            .iter()
+            .map(|node| (node.word.as_str(), node.depth))
+            .collect();
+        assert_eq!(
+            nodes,
+            [("bigger", 0), ("big", 1), ("large", 2), ("small", 2)]
+        );
+        assert!(graph.edges.contains(&GraphEdge {
+            from: bigger,
+            to: big,
+            kind: RelationKind::FormOf
+        }));
+        assert!(graph.edges.contains(&GraphEdge {
+            from: big,
+            to: small,
+            kind: RelationKind::Antonym
+        }));
+
+        // One more step reaches "little", and "small" back to "big"
+        let graph = get_graph_neighborhood(&handle, bigger, 3, RelationKind::ALL).unwrap();
+        assert!(graph.nodes.iter().any(|node| node.id == little));
+        assert!(graph.edges.contains(&GraphEdge {
+            from: small,
+            to: big,
+            kind: RelationKind::Antonym
+        }));
+
+        let graph = get_graph_neighborhood(&handle, big, 1, &[RelationKind::Synonym]).unwrap();
+        let ids: Vec<i64> = graph.nodes.iter().map(|node| node.id).collect();
+        assert_eq!(ids, [big, large]);
+        assert_eq!(graph.edges.len(), 1);
+
+        assert!(get_graph_neighborhood(&handle, 999, 2, RelationKind::ALL)
+            .unwrap()
+            .nodes
+            .is_empty());
+    }
+
+    #[test]
+    fn test_build_indexes() {
+        let (_dir, handle) = setup_test_db();
+        let hello = insert_word(&handle.conn, "hello", "interjection", "English", "en", 0).unwrap();
+        insert_pronunciation(&handle.conn, hello, Some("/həˈləʊ/"), None, None).unwrap();
+        let annyeong =
+            insert_word(&handle.conn, "안녕", "interjection", "Korean", "ko", 0).unwrap();
+        // As shipped without indexes
+        handle
+            .conn
+            .execute_batch(
+                "UPDATE words SET search_key = NULL;
+                 INSERT INTO words_fts(words_fts) VALUES('delete-all');
+                 UPDATE pronunciations SET rhyme = NULL, ipa_key = NULL;
+                 DELETE FROM jamo;",
+            )
+            .unwrap();
+
+        let cancel = AtomicBool::new(true);
+        assert!(!build_indexes(&handle, |_, _| {}, &cancel).unwrap());
+
+        let reports = std::cell::RefCell::new(Vec::new());
+        cancel.store(false, Ordering::Relaxed);
+        let done = build_indexes(
+            &handle,
+            |done, total| reports.borrow_mut().push((done, total)),
+            &cancel,
+        );
+        assert!(done.unwrap());
+        assert_eq!(reports.borrow().last(), Some(&(6, 6)));
+        let fts: i64 = handle
+            .conn
+            .query_row(
+                "SELECT rowid FROM words_fts WHERE words_fts MATCH 'hello'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(fts, hello);
+        let jamo: i64 = handle
+            .conn
+            .query_row("SELECT word_id FROM jamo", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(jamo, annyeong);
+        let rhyme: Option<String> = handle
+            .conn
+            .query_row("SELECT rhyme FROM pronunciations", [], |row| row.get(0))
+            .unwrap();
+        assert!(rhyme.is_some());
+
+        // Nothing left to do
+        reports.borrow_mut().clear();
+        assert!(build_indexes(
+            &handle,
+            |done, total| reports.borrow_mut().push((done, total)),
+            &cancel
+        )
+        .unwrap());
+        assert!(reports.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_check_compatibility() {
+        let (dir, handle) = setup_test_db();
+        set_meta(&handle.conn, "generator", "dict-core 0.1.0").unwrap();
+        let path = dir.path().join("test.db");
+        let path = path.to_str().unwrap();
+
+        let report = check_compatibility(path).unwrap();
+        assert!(report.is_compatible(), "{:?}", report.problems);
+        assert_eq!(report.schema_version, SCHEMA_VERSION);
+        assert_eq!(report.generator.as_deref(), Some("dict-core 0.1.0"));
+        assert!(report.required_features.contains(&"fts5".to_string()));
+        assert!(report.missing_features.is_empty());
+
+        // Built by a newer library
+        handle
+            .conn
+            .pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+        let report = check_compatibility(path).unwrap();
+        assert!(!report.is_compatible());
+        assert!(report.problems[0].contains("newer"));
+
+        assert_eq!(
+            fts5_tokenizer("CREATE VIRTUAL TABLE t USING fts5(word, tokenize = 'trigram')"),
+            Some("trigram".to_string())
+        );
+        assert_eq!(
+            fts5_tokenizer("CREATE VIRTUAL TABLE t USING fts5(word)"),
+            None
+        );
+
+        let other = dir.path().join("other.db");
+        Connection::open(&other)
+            .unwrap()
+            .execute_batch("CREATE TABLE notes (text TEXT)")
+            .unwrap();
+        let report = check_compatibility(other.to_str().unwrap()).unwrap();
+        assert!(!report.is_compatible());
+    }
+
+    #[test]
+    fn test_compact() {
+        let (dir, handle) = setup_test_db();
+        let hello = insert_word(&handle.conn, "hello", "interjection", "English", "en", 0).unwrap();
+        let examples = ["Hello, world!".to_string()];
+        let def = insert_definition(&handle.conn, hello, "A greeting", &examples, &[]).unwrap();
+        insert_pronunciation(&handle.conn, hello, Some("/həˈləʊ/"), Some("a.ogg"), None).unwrap();
+        insert_pronunciation(&handle.conn, hello, None, Some("b.ogg"), Some("US")).unwrap();
+        // Long enough that removing it frees pages whatever the layout
+        let etymology = "From hallo. ".repeat(2000);
+        insert_etymology(&handle.conn, hello, &etymology).unwrap();
+        insert_relation(&handle.conn, hello, Some(def), "synonym", "hi", None).unwrap();
+        for (lang, word) in [("fr", "bonjour"), ("de", "hallo"), ("es", "hola")] {
+            insert_translation(&handle.conn, hello, lang, word).unwrap();
+        }
+        set_meta(&handle.conn, "omitted_content", "forms").unwrap();
+
+        // Through a read-only handle, as the app has it open
+        let readonly = open_readonly(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let options = CompactOptions {
+            translation_languages: Some(vec!["FR".to_string(), "de".to_string()]),
+            remove_etymologies: true,
+            remove_audio_urls: true,
+            remove_examples: true,
+            remove_relations: false,
+        };
+        let stats = compact(&readonly, &options).unwrap();
+        assert_eq!(stats.translations_removed, 1);
+        assert_eq!(stats.etymologies_removed, 1);
+        assert_eq!(stats.audio_urls_removed, 2);
+        assert_eq!(stats.examples_removed, 1);
+        assert_eq!(stats.relations_removed, 0);
+        assert!(stats.bytes_after < stats.bytes_before, "{stats:?}");
+
+        let full = get_full_definition(&readonly, hello).unwrap().unwrap();
+        assert_eq!(full.translations.len(), 2);
+        assert!(full.etymology.is_none());
+        assert_eq!(full.pronunciations.len(), 1);
+        assert!(full.pronunciations[0].audio_url.is_none());
+        assert!(full.definitions[0].examples.is_empty());
+        let metadata = get_metadata(&readonly).unwrap();
+        assert_eq!(
+            metadata["omitted_content"],
+            "forms,etymologies,audio,examples"
+        );
+        assert_eq!(metadata["translation_languages"], "fr,de");
     }
 
     #[test]
@@ -0,0 +1,205 @@
+//! A dictionary opened through a builder
+//!
+//! [`Dict`] bundles a [`DictHandle`] with methods for the common
+//! operations, for use from Rust rather than over the FFI:
+//!
+//! ```ignore
+//! use dict_core::{Dict, FuzzyConfig};
+//!
+//! let dict = Dict::open("/path/to/dictionary.db")
+//!     .read_only()
+//!     .with_fuzzy(FuzzyConfig { max_distance: 1, ..FuzzyConfig::default() })
+//!     .attach_user_db("/path/to/user.db")
+//!     .build()?;
+//! for result in dict.search("hello", 10)? {
+//!     println!("{}: {}", result.word, result.preview);
+//! }
+//! ```
+//!
+//! Everything else takes the handle, from [`Dict::handle`]. Unlike the
+//! free functions in the crate root, the methods return errors rather
+//! than logging them.
+
+use std::path::{Path, PathBuf};
+
+use crate::config::{DictConfig, FuzzyConfig, RankingStrategy};
+use crate::editor::Editor;
+use crate::models::{DefinitionOptions, FullDefinition, PreviewOptions, SearchResult};
+use crate::{db, search, DictHandle, Error, Result};
+
+/// An open dictionary
+pub struct Dict {
+    handle: DictHandle,
+}
+
+impl Dict {
+    /// Start opening the dictionary at `path`
+    ///
+    /// By default the database is opened for writing and created if
+    /// missing; see [`DictBuilder::read_only`].
+    pub fn open(path: impl AsRef<Path>) -> DictBuilder {
+        DictBuilder {
+            path: path.as_ref().to_path_buf(),
+            read_only: false,
+            config: DictConfig::default(),
+        }
+    }
+
+    /// The handle, for the functions that take one
+    pub fn handle(&self) -> &DictHandle {
+        &self.handle
+    }
+
+    /// The handle, giving up the facade
+    pub fn into_handle(self) -> DictHandle {
+        self.handle
+    }
+
+    /// Settings the dictionary was opened with
+    pub fn config(&self) -> &DictConfig {
+        self.handle.config()
+    }
+
+    /// Words matching `query`, most relevant first
+    pub fn search(&self, query: &str, limit: u32) -> Result<Vec<SearchResult>> {
+        search::search_words(&self.handle, query, limit)
+    }
+
+    /// A page of the words matching `query`, skipping the first `offset`
+    pub fn search_with_offset(
+        &self,
+        query: &str,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<SearchResult>> {
+        search::search_words_offset(&self.handle, query, limit, offset)
+    }
+
+    /// Headwords completing a typed prefix
+    pub fn complete(&self, prefix: &str, n: u32) -> Result<Vec<String>> {
+        search::complete(&self.handle, prefix, n)
+    }
+
+    /// The full definition of a word, or None if there is no such word
+    pub fn get_definition(&self, word_id: i64) -> Result<Option<FullDefinition>> {
+        db::get_full_definition(&self.handle, word_id)
+    }
+
+    /// The full definition of a word, with options
+    pub fn get_definition_with_options(
+        &self,
+        word_id: i64,
+        options: &DefinitionOptions,
+    ) -> Result<Option<FullDefinition>> {
+        db::get_full_definition_with_options(&self.handle, word_id, options)
+    }
+
+    /// Edit entries in one transaction, see [`crate::editor::edit`]
+    pub fn edit<T>(&self, f: impl FnOnce(&Editor) -> Result<T>) -> Result<T> {
+        crate::editor::edit(&self.handle, f)
+    }
+}
+
+/// Settings for opening a [`Dict`], from [`Dict::open`]
+#[derive(Debug, Clone)]
+pub struct DictBuilder {
+    path: PathBuf,
+    read_only: bool,
+    config: DictConfig,
+}
+
+impl DictBuilder {
+    /// Open an existing database without writing to it, as the apps do
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Replace all settings; later calls change individual ones
+    pub fn with_config(mut self, config: DictConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Typo-tolerant matching settings
+    pub fn with_fuzzy(mut self, fuzzy: FuzzyConfig) -> Self {
+        self.config.fuzzy = fuzzy;
+        self
+    }
+
+    /// How search result previews are built
+    pub fn with_preview(mut self, preview: PreviewOptions) -> Self {
+        self.config.preview = preview;
+        self
+    }
+
+    /// How search results are ordered
+    pub fn with_ranking(mut self, ranking: RankingStrategy) -> Self {
+        self.config.ranking = ranking;
+        self
+    }
+
+    /// Attach a user database (bookmarks, own entries), which must exist
+    pub fn attach_user_db(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.user_db = Some(path.into());
+        self
+    }
+
+    /// Open the dictionary
+    pub fn build(self) -> Result<Dict> {
+        let path = self
+            .path
+            .to_str()
+            .ok_or_else(|| Error::InvalidPath(self.path.display().to_string()))?;
+        let handle = if self.read_only {
+            db::open_readonly_with_config(path, self.config)?
+        } else {
+            db::init_database_with_config(path, self.config)?
+        };
+        Ok(Dict { handle })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dict_builder() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.db");
+
+        let dict = Dict::open(&path).build().unwrap();
+        let hello = dict
+            .edit(|editor| {
+                let id = editor.insert_word("hello", "interjection", "English", "en", 0)?;
+                editor.insert_definition(id, "A greeting", &[], &[])?;
+                Ok(id)
+            })
+            .unwrap();
+        drop(dict);
+
+        let dict = Dict::open(&path)
+            .read_only()
+            .with_fuzzy(FuzzyConfig {
+                enabled: false,
+                ..FuzzyConfig::default()
+            })
+            .with_ranking(RankingStrategy::Alphabetical)
+            .build()
+            .unwrap();
+        assert!(!dict.config().fuzzy.enabled);
+        assert_eq!(dict.search("hello", 10).unwrap()[0].id, hello);
+        assert!(dict.search("helo", 10).unwrap().is_empty());
+        assert_eq!(dict.complete("he", 5).unwrap(), ["hello"]);
+        let full = dict.get_definition(hello).unwrap().unwrap();
+        assert_eq!(full.definitions[0].text, "A greeting");
+        assert!(dict.edit(|editor| editor.delete_word(hello)).is_err());
+
+        let missing = Dict::open(&path)
+            .read_only()
+            .attach_user_db(dir.path().join("user.db"))
+            .build();
+        assert!(matches!(missing, Err(Error::InvalidPath(_))));
+    }
+}
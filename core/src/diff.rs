@@ -0,0 +1,379 @@
+//! Differences between two builds of the same dictionary
+//!
+//! For reviewing what a newer Wiktionary dump changed. Word ids differ
+//! between builds, so entries are matched by headword, part of speech,
+//! language and etymology number, and their senses by the sense id that
+//! stays the same across rebuilds (by text for databases without one).
+//! [`summary`] lists every added, removed and changed entry;
+//! [`entry_changes`] shows what changed in the entries of one headword.
+
+use std::cmp::Ordering;
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::{DictHandle, Result};
+
+/// Whether something is new, gone or different
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// An entry in either build, with its ids in both
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryRef {
+    pub word: String,
+    pub pos: String,
+    pub language: String,
+    /// Id in the old build, None for added entries
+    pub old_id: Option<i64>,
+    /// Id in the new build, None for removed entries
+    pub new_id: Option<i64>,
+}
+
+/// Entries that differ between two builds, from [`summary`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiffSummary {
+    pub added: Vec<EntryRef>,
+    pub removed: Vec<EntryRef>,
+    /// Entries in both builds whose senses differ
+    pub changed: Vec<EntryRef>,
+    /// Number of entries in both builds with the same senses
+    pub unchanged: u64,
+}
+
+/// The content of a sense that is compared
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SenseContent {
+    pub text: String,
+    pub examples: Vec<String>,
+    pub tags: Vec<String>,
+}
+
+/// A sense that was added, removed or edited
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SenseChange {
+    pub kind: ChangeKind,
+    pub sense_id: Option<String>,
+    /// The sense in the old build, None for added senses
+    pub old: Option<SenseContent>,
+    /// The sense in the new build, None for removed senses
+    pub new: Option<SenseContent>,
+}
+
+/// What changed in one entry, from [`entry_changes`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryChange {
+    pub entry: EntryRef,
+    pub kind: ChangeKind,
+    /// Senses that differ, in the new build's order followed by removed
+    /// ones; every sense of an added or removed entry
+    pub senses: Vec<SenseChange>,
+}
+
+/// What identifies an entry across builds, in the order entries are
+/// compared
+type EntryKey = (String, String, String, i32);
+
+/// An entry with its senses flattened into one string for comparison
+struct EntryRow {
+    key: EntryKey,
+    id: i64,
+    content: String,
+}
+
+/// Every entry added, removed or changed from `old` to `new`
+///
+/// Entries are compared by their senses (text, examples and tags); lists
+/// are in headword order. Both databases are read in one pass each.
+pub fn summary(old: &DictHandle, new: &DictHandle) -> Result<DiffSummary> {
+    let mut old_stmt = old.conn.prepare(ENTRIES_SQL)?;
+    let mut new_stmt = new.conn.prepare(ENTRIES_SQL)?;
+    let mut old_rows = old_stmt.query_map([], entry_row)?;
+    let mut new_rows = new_stmt.query_map([], entry_row)?;
+
+    let mut diff = DiffSummary::default();
+    let mut old_row = old_rows.next().transpose()?;
+    let mut new_row = new_rows.next().transpose()?;
+    loop {
+        let order = match (&old_row, &new_row) {
+            (None, None) => break,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(o), Some(n)) => o.key.cmp(&n.key),
+        };
+        match order {
+            Ordering::Less => {
+                let row = old_row.take().expect("compared");
+                diff.removed.push(entry_ref(row.key, Some(row.id), None));
+                old_row = old_rows.next().transpose()?;
+            }
+            Ordering::Greater => {
+                let row = new_row.take().expect("compared");
+                diff.added.push(entry_ref(row.key, None, Some(row.id)));
+                new_row = new_rows.next().transpose()?;
+            }
+            Ordering::Equal => {
+                let (Some(o), Some(n)) = (old_row.take(), new_row.take()) else {
+                    unreachable!("compared");
+                };
+                if o.content == n.content {
+                    diff.unchanged += 1;
+                } else {
+                    diff.changed.push(entry_ref(n.key, Some(o.id), Some(n.id)));
+                }
+                old_row = old_rows.next().transpose()?;
+                new_row = new_rows.next().transpose()?;
+            }
+        }
+    }
+    Ok(diff)
+}
+
+/// What changed from `old` to `new` in the entries with headword `word`
+///
+/// Entries whose senses are the same in both builds are left out.
+pub fn entry_changes(old: &DictHandle, new: &DictHandle, word: &str) -> Result<Vec<EntryChange>> {
+    let old_entries = entries_of(old, word)?;
+    let mut new_entries = entries_of(new, word)?;
+
+    let mut changes = Vec::new();
+    for (key, old_id) in old_entries {
+        let old_senses = senses(old, old_id)?;
+        let Some(index) = new_entries.iter().position(|(k, _)| *k == key) else {
+            changes.push(EntryChange {
+                entry: entry_ref(key, Some(old_id), None),
+                kind: ChangeKind::Removed,
+                senses: old_senses.into_iter().map(removed).collect(),
+            });
+            continue;
+        };
+        let (key, new_id) = new_entries.remove(index);
+        let senses = sense_changes(old_senses, senses(new, new_id)?);
+        if !senses.is_empty() {
+            changes.push(EntryChange {
+                entry: entry_ref(key, Some(old_id), Some(new_id)),
+                kind: ChangeKind::Changed,
+                senses,
+            });
+        }
+    }
+    for (key, new_id) in new_entries {
+        changes.push(EntryChange {
+            entry: entry_ref(key, None, Some(new_id)),
+            kind: ChangeKind::Added,
+            senses: senses(new, new_id)?.into_iter().map(added).collect(),
+        });
+    }
+    Ok(changes)
+}
+
+/// Entries in `EntryKey` order with their senses in one string, fields
+/// separated by unit separators and senses by record separators
+const ENTRIES_SQL: &str = r#"
+SELECT w.id, w.word, w.pos, w.language, COALESCE(w.etymology_num, 0),
+       COALESCE((SELECT group_concat(d.definition || char(31) || COALESCE(d.examples, '')
+                                     || char(31) || COALESCE(d.tags, ''), char(30))
+                 FROM (SELECT definition, examples, tags FROM definitions
+                       WHERE word_id = w.id ORDER BY id) d), '')
+FROM words w
+ORDER BY w.word, w.pos, w.language, COALESCE(w.etymology_num, 0), w.id
+"#;
+
+fn entry_row(row: &rusqlite::Row) -> rusqlite::Result<EntryRow> {
+    Ok(EntryRow {
+        id: row.get(0)?,
+        key: (row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?),
+        content: row.get(5)?,
+    })
+}
+
+fn entry_ref(key: EntryKey, old_id: Option<i64>, new_id: Option<i64>) -> EntryRef {
+    let (word, pos, language, _) = key;
+    EntryRef {
+        word,
+        pos,
+        language,
+        old_id,
+        new_id,
+    }
+}
+
+/// Keys and ids of the entries of `word`, in id order
+fn entries_of(handle: &DictHandle, word: &str) -> Result<Vec<(EntryKey, i64)>> {
+    let mut stmt = handle.conn.prepare_cached(
+        "SELECT id, word, pos, language, COALESCE(etymology_num, 0) FROM words
+         WHERE word = ? ORDER BY id",
+    )?;
+    let rows = stmt.query_map([word], |row| {
+        Ok((
+            (row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?),
+            row.get(0)?,
+        ))
+    })?;
+    Ok(rows.collect::<std::result::Result<_, _>>()?)
+}
+
+/// Senses of an entry with their sense ids, in order
+fn senses(handle: &DictHandle, word_id: i64) -> Result<Vec<(Option<String>, SenseContent)>> {
+    let sense_id = if crate::db::has_column(&handle.conn, "definitions", "sense_id")? {
+        "sense_id"
+    } else {
+        "NULL"
+    };
+    let mut stmt = handle.conn.prepare_cached(&format!(
+        "SELECT definition, examples, tags, {sense_id} FROM definitions
+         WHERE word_id = ? ORDER BY id"
+    ))?;
+    let rows = stmt.query_map(params![word_id], |row| {
+        let parse = |json: Option<String>| -> Vec<String> {
+            json.and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default()
+        };
+        Ok((
+            row.get(3)?,
+            SenseContent {
+                text: row.get(0)?,
+                examples: parse(row.get(1)?),
+                tags: parse(row.get(2)?),
+            },
+        ))
+    })?;
+    Ok(rows.collect::<std::result::Result<_, _>>()?)
+}
+
+/// Senses added, removed or edited between two versions of an entry
+///
+/// Senses pair up by sense id, or by text where either has none; a pair
+/// differing in examples or tags is an edit.
+fn sense_changes(
+    old: Vec<(Option<String>, SenseContent)>,
+    new: Vec<(Option<String>, SenseContent)>,
+) -> Vec<SenseChange> {
+    let mut old: Vec<Option<(Option<String>, SenseContent)>> = old.into_iter().map(Some).collect();
+    let mut changes = Vec::new();
+    for (sense_id, content) in new {
+        let matching = old.iter().position(|o| {
+            o.as_ref()
+                .is_some_and(|(old_id, old_content)| match (old_id, &sense_id) {
+                    (Some(old_id), Some(sense_id)) => old_id == sense_id,
+                    _ => old_content.text == content.text,
+                })
+        });
+        match matching.and_then(|i| old[i].take()) {
+            Some((_, old_content)) if old_content == content => {}
+            Some((_, old_content)) => changes.push(SenseChange {
+                kind: ChangeKind::Changed,
+                sense_id,
+                old: Some(old_content),
+                new: Some(content),
+            }),
+            None => changes.push(added((sense_id, content))),
+        }
+    }
+    changes.extend(old.into_iter().flatten().map(removed));
+    changes
+}
+
+fn added((sense_id, content): (Option<String>, SenseContent)) -> SenseChange {
+    SenseChange {
+        kind: ChangeKind::Added,
+        sense_id,
+        old: None,
+        new: Some(content),
+    }
+}
+
+fn removed((sense_id, content): (Option<String>, SenseContent)) -> SenseChange {
+    SenseChange {
+        kind: ChangeKind::Removed,
+        sense_id,
+        old: Some(content),
+        new: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_database, insert_definition, insert_word, set_sense_id};
+
+    /// (sense id, text) of each sense of a test entry
+    type Senses<'a> = &'a [(&'a str, &'a str)];
+
+    fn build(dir: &std::path::Path, name: &str, entries: &[(&str, &str, Senses)]) -> DictHandle {
+        let handle = init_database(dir.join(name).to_str().unwrap()).unwrap();
+        for (word, pos, senses) in entries {
+            let id = insert_word(&handle.conn, word, pos, "English", "en", 0).unwrap();
+            for (sense_id, text) in *senses {
+                let def = insert_definition(&handle.conn, id, text, &[], &[]).unwrap();
+                set_sense_id(&handle.conn, def, sense_id).unwrap();
+            }
+        }
+        handle
+    }
+
+    #[test]
+    fn test_diff() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = build(
+            dir.path(),
+            "old.db",
+            &[
+                ("cat", "noun", &[("c1", "A feline"), ("c2", "A jazz fan")]),
+                ("dog", "noun", &[("d1", "A canine")]),
+                ("emu", "noun", &[("e1", "A bird")]),
+            ],
+        );
+        let new = build(
+            dir.path(),
+            "new.db",
+            &[
+                ("ant", "noun", &[("a1", "An insect")]),
+                (
+                    "cat",
+                    "noun",
+                    &[("c1", "A small feline"), ("c3", "A person")],
+                ),
+                ("dog", "noun", &[("d1", "A canine")]),
+            ],
+        );
+
+        let diff = summary(&old, &new).unwrap();
+        let words = |entries: &[EntryRef]| -> Vec<String> {
+            entries.iter().map(|e| e.word.clone()).collect()
+        };
+        assert_eq!(words(&diff.added), ["ant"]);
+        assert_eq!(words(&diff.removed), ["emu"]);
+        assert_eq!(words(&diff.changed), ["cat"]);
+        assert_eq!(diff.unchanged, 1);
+        assert!(diff.changed[0].old_id.is_some() && diff.changed[0].new_id.is_some());
+
+        let changes = entry_changes(&old, &new, "cat").unwrap();
+        assert_eq!(changes.len(), 1);
+        let senses: Vec<(ChangeKind, Option<&str>)> = changes[0]
+            .senses
+            .iter()
+            .map(|s| (s.kind, s.sense_id.as_deref()))
+            .collect();
+        assert_eq!(
+            senses,
+            [
+                (ChangeKind::Changed, Some("c1")),
+                (ChangeKind::Added, Some("c3")),
+                (ChangeKind::Removed, Some("c2")),
+            ]
+        );
+        assert_eq!(
+            changes[0].senses[0].new.as_ref().unwrap().text,
+            "A small feline"
+        );
+        assert!(entry_changes(&old, &new, "dog").unwrap().is_empty());
+        let emu = entry_changes(&old, &new, "emu").unwrap();
+        assert_eq!((emu[0].kind, emu[0].senses.len()), (ChangeKind::Removed, 1));
+    }
+}
@@ -0,0 +1,562 @@
+//! Dictionary database download
+//!
+//! Fetches the `{language}-latest.json` manifest the preprocessor publishes
+//! next to each database, then the zstd-compressed database it names, and
+//! installs the decompressed file at the target path. This replaces the
+//! download code each platform used to carry.
+//!
+//! The compressed file is fetched in ranged chunks into `{dest}.zst.part`,
+//! so an interrupted download continues where it stopped on the next call.
+//! The SHA-256 it is expected to have is kept in `{dest}.zst.part.sha256`,
+//! and a partial file from another manifest is discarded, not resumed.
+//! Both the compressed and the decompressed file are checked against the
+//! manifest's SHA-256 sums, and the database only appears at `dest` (by
+//! rename) once it has been verified.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{Error, Result};
+
+/// Timeout for connecting and for each read from the server
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+const USER_AGENT: &str = concat!(
+    "dict-app/",
+    env!("CARGO_PKG_VERSION"),
+    " (dictionary download)"
+);
+
+/// Bytes requested per range request
+const CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Attempts per chunk before the download gives up (and can be resumed)
+const CHUNK_ATTEMPTS: usize = 3;
+
+/// A published database, as described by `{language}-latest.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Language the database covers (e.g. "english")
+    pub language: String,
+    /// Dictionary version (e.g. "42")
+    pub version: String,
+    /// Object key of the compressed database, relative to the base URL
+    pub file: String,
+    /// Size of the compressed database in bytes
+    pub size: u64,
+    /// Size of the decompressed database in bytes
+    pub uncompressed_size: u64,
+    /// Hex-encoded SHA-256 of the compressed database
+    pub sha256: String,
+    /// Hex-encoded SHA-256 of the decompressed database
+    pub uncompressed_sha256: String,
+    /// Unix timestamp of publication
+    #[serde(default)]
+    pub published_at: u64,
+}
+
+/// What a download is doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadPhase {
+    /// Fetching the compressed database
+    Downloading = 0,
+    /// Checksumming the compressed database
+    Verifying = 1,
+    /// Writing (and checksumming) the decompressed database
+    Decompressing = 2,
+}
+
+/// Progress of a running download
+///
+/// Byte counts refer to the compressed file while downloading and
+/// verifying, and to the decompressed file while decompressing.
+#[derive(Debug, Clone, Copy)]
+pub struct DownloadProgress {
+    pub phase: DownloadPhase,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+/// URL of the manifest for `language` under `base_url`
+pub fn manifest_url(base_url: &str, language: &str) -> String {
+    format!(
+        "{}/{}-latest.json",
+        base_url.trim_end_matches('/'),
+        language
+    )
+}
+
+/// Fetch the manifest of the latest database for `language`
+pub fn fetch_manifest(base_url: &str, language: &str) -> Result<Manifest> {
+    fetch_manifest_at(&manifest_url(base_url, language))
+}
+
+/// Fetch a manifest from its full URL
+pub fn fetch_manifest_at(url: &str) -> Result<Manifest> {
+    let response = agent()
+        .get(url)
+        .call()
+        .map_err(|e| Error::Download(e.to_string()))?;
+    Ok(serde_json::from_reader(response.into_reader())?)
+}
+
+/// Download the database described by `manifest` and install it at `dest`
+///
+/// Resumes a previous partial download of the same file. `progress` is
+/// called as data arrives and while verifying and decompressing. A
+/// checksum mismatch discards the downloaded data, so the next call starts
+/// over; any other failure keeps it for resuming. The FST and SQLite log
+/// of the database previously at `dest` are removed, as they belong to
+/// the old build.
+pub fn download_database(
+    base_url: &str,
+    manifest: &Manifest,
+    dest: &Path,
+    mut progress: impl FnMut(&DownloadProgress),
+) -> Result<()> {
+    let url = format!("{}/{}", base_url.trim_end_matches('/'), manifest.file);
+    let compressed = with_suffix(dest, ".zst.part");
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fetch_chunked(
+        &agent(),
+        &url,
+        &compressed,
+        &manifest.sha256,
+        manifest.size,
+        CHUNK_SIZE,
+        &mut progress,
+    )?;
+
+    let actual = sha256_file(&compressed, manifest.size, &mut progress)?;
+    if !actual.eq_ignore_ascii_case(&manifest.sha256) {
+        fs::remove_file(&compressed).ok();
+        fs::remove_file(with_suffix(&compressed, ".sha256")).ok();
+        return Err(Error::ChecksumMismatch {
+            file: manifest.file.clone(),
+            expected: manifest.sha256.clone(),
+            actual,
+        });
+    }
+
+    let decompressed = with_suffix(dest, ".part");
+    let actual = match decompress(&compressed, &decompressed, manifest, &mut progress) {
+        Ok(actual) => actual,
+        Err(e) => {
+            fs::remove_file(&decompressed).ok();
+            return Err(e);
+        }
+    };
+    if !actual.eq_ignore_ascii_case(&manifest.uncompressed_sha256) {
+        fs::remove_file(&decompressed).ok();
+        fs::remove_file(&compressed).ok();
+        fs::remove_file(with_suffix(&compressed, ".sha256")).ok();
+        return Err(Error::ChecksumMismatch {
+            file: dest.display().to_string(),
+            expected: manifest.uncompressed_sha256.clone(),
+            actual,
+        });
+    }
+
+    for stale in crate::db::sidecar_paths(dest) {
+        if stale.exists() {
+            fs::remove_file(&stale)?;
+        }
+    }
+    fs::rename(&decompressed, dest)?;
+    fs::remove_file(&compressed).ok();
+    fs::remove_file(with_suffix(&compressed, ".sha256")).ok();
+    Ok(())
+}
+
+fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout_connect(HTTP_TIMEOUT)
+        .timeout_read(HTTP_TIMEOUT)
+        .user_agent(USER_AGENT)
+        .build()
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Fetch `url` into `partial` until it holds `size` bytes, `chunk` at a time
+///
+/// `sha256` identifies the file being fetched: it is recorded next to
+/// `partial`, and a partial file recorded for another one starts over.
+fn fetch_chunked(
+    agent: &ureq::Agent,
+    url: &str,
+    partial: &Path,
+    sha256: &str,
+    size: u64,
+    chunk: u64,
+    progress: &mut dyn FnMut(&DownloadProgress),
+) -> Result<()> {
+    let stamp = with_suffix(partial, ".sha256");
+    let mut done = fs::metadata(partial).map(|m| m.len()).unwrap_or(0);
+    let same_file = fs::read_to_string(&stamp)
+        .map(|s| s.trim().eq_ignore_ascii_case(sha256))
+        .unwrap_or(false);
+    if done > 0 && (!same_file || done > size) {
+        // Left over from a different file; start again
+        fs::remove_file(partial)?;
+        done = 0;
+    }
+    if !same_file {
+        fs::write(&stamp, sha256)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(partial)?;
+
+    let mut report = |bytes_done| {
+        progress(&DownloadProgress {
+            phase: DownloadPhase::Downloading,
+            bytes_done,
+            bytes_total: size,
+        })
+    };
+    report(done);
+
+    let mut attempts = 0;
+    while done < size {
+        let end = (done + chunk).min(size) - 1;
+        match fetch_range(agent, url, &mut file, done, end, &mut report) {
+            Ok(n) if n == done => {
+                return Err(Error::Download(format!(
+                    "{} ended early at byte {}",
+                    url, done
+                )));
+            }
+            Ok(n) => {
+                done = n;
+                attempts = 0;
+            }
+            Err(e) => {
+                attempts += 1;
+                if attempts >= CHUNK_ATTEMPTS {
+                    return Err(e);
+                }
+                log::warn!("Retrying {} at byte {}: {}", url, done, e);
+                done = file.metadata()?.len();
+            }
+        }
+    }
+    file.sync_all()?;
+
+    if done != size {
+        return Err(Error::Download(format!(
+            "{} is {} bytes, expected {}",
+            url, done, size
+        )));
+    }
+    Ok(())
+}
+
+/// Append bytes `start..=end` of `url` to `file`, returning its new length
+fn fetch_range(
+    agent: &ureq::Agent,
+    url: &str,
+    file: &mut File,
+    start: u64,
+    end: u64,
+    report: &mut dyn FnMut(u64),
+) -> Result<u64> {
+    let response = agent
+        .get(url)
+        .set("Range", &format!("bytes={}-{}", start, end))
+        .call()
+        .map_err(|e| Error::Download(e.to_string()))?;
+
+    // Servers that ignore the range send the whole file again
+    let mut done = start;
+    if response.status() != 206 {
+        file.set_len(0)?;
+        done = 0;
+    }
+
+    let mut reader = response.into_reader();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        file.write_all(&buffer[..n])?;
+        done += n as u64;
+        report(done);
+    }
+    Ok(done)
+}
+
+/// Hex-encoded SHA-256 of a file, reporting progress as it is read
+fn sha256_file(
+    path: &Path,
+    size: u64,
+    progress: &mut dyn FnMut(&DownloadProgress),
+) -> Result<String> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hasher = Sha256::new();
+    let mut done = 0;
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+        done += n as u64;
+        progress(&DownloadProgress {
+            phase: DownloadPhase::Verifying,
+            bytes_done: done,
+            bytes_total: size,
+        });
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Decompress `source` into `dest`, returning the SHA-256 of what was written
+fn decompress(
+    source: &Path,
+    dest: &Path,
+    manifest: &Manifest,
+    progress: &mut dyn FnMut(&DownloadProgress),
+) -> Result<String> {
+    let mut decoder = zstd::stream::read::Decoder::new(File::open(source)?)?;
+    let mut file = File::create(dest)?;
+    let mut hasher = Sha256::new();
+    let mut done = 0;
+    let mut buffer = vec![0u8; 256 * 1024];
+    loop {
+        let n = decoder.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        file.write_all(&buffer[..n])?;
+        hasher.update(&buffer[..n]);
+        done += n as u64;
+        progress(&DownloadProgress {
+            phase: DownloadPhase::Decompressing,
+            bytes_done: done,
+            bytes_total: manifest.uncompressed_size,
+        });
+    }
+    file.sync_all()?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    /// Serve `files` by path over HTTP, honoring `Range: bytes=A-B`
+    ///
+    /// Returns the base URL and the ranges that were requested.
+    fn serve(files: HashMap<String, Vec<u8>>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let seen = ranges.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = Vec::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    request.push(line.trim().to_string());
+                }
+                let path = request[0].split(' ').nth(1).unwrap_or("/");
+                let Some(body) = files.get(path) else {
+                    write!(
+                        stream,
+                        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n"
+                    )
+                    .unwrap();
+                    continue;
+                };
+                let range = request.iter().find_map(|l| {
+                    let (name, value) = l.split_once(':')?;
+                    name.eq_ignore_ascii_case("range")
+                        .then(|| value.trim().trim_start_matches("bytes=").to_string())
+                });
+                let (status, part) = match &range {
+                    Some(range) => {
+                        seen.lock().unwrap().push(range.clone());
+                        let (start, end) = range.split_once('-').unwrap();
+                        let start: usize = start.parse().unwrap();
+                        let end = end.parse::<usize>().map_or(body.len(), |e| e + 1);
+                        ("206 Partial Content", &body[start..end.min(body.len())])
+                    }
+                    None => ("200 OK", &body[..]),
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    part.len()
+                )
+                .unwrap();
+                stream.write_all(part).unwrap();
+            }
+        });
+        (format!("http://{}", addr), ranges)
+    }
+
+    fn sha256_hex(data: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(data))
+    }
+
+    /// A published "database" and the server holding it
+    fn publish(database: &[u8]) -> (String, Arc<Mutex<Vec<String>>>, Vec<u8>) {
+        let compressed = zstd::encode_all(database, 3).unwrap();
+        let manifest = Manifest {
+            language: "english".to_string(),
+            version: "42".to_string(),
+            file: "english-dict-v42.db.zst".to_string(),
+            size: compressed.len() as u64,
+            uncompressed_size: database.len() as u64,
+            sha256: sha256_hex(&compressed),
+            uncompressed_sha256: sha256_hex(database),
+            published_at: 0,
+        };
+        let files = HashMap::from([
+            (
+                "/english-latest.json".to_string(),
+                serde_json::to_vec(&manifest).unwrap(),
+            ),
+            ("/english-dict-v42.db.zst".to_string(), compressed.clone()),
+        ]);
+        let (base, ranges) = serve(files);
+        (base, ranges, compressed)
+    }
+
+    #[test]
+    fn test_download_database() {
+        let database: Vec<u8> = (0..200_000u32).flat_map(|i| i.to_le_bytes()).collect();
+        let (base, _, _) = publish(&database);
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("dicts").join("english.db");
+
+        let manifest = fetch_manifest(&format!("{}/", base), "english").unwrap();
+        assert_eq!(manifest.version, "42");
+
+        let mut phases = Vec::new();
+        let mut last = None;
+        download_database(&base, &manifest, &dest, |p| {
+            if phases.last() != Some(&p.phase) {
+                phases.push(p.phase);
+            }
+            last = Some(*p);
+        })
+        .unwrap();
+
+        assert_eq!(fs::read(&dest).unwrap(), database);
+        assert_eq!(
+            phases,
+            [
+                DownloadPhase::Downloading,
+                DownloadPhase::Verifying,
+                DownloadPhase::Decompressing
+            ]
+        );
+        let last = last.unwrap();
+        assert_eq!(last.bytes_done, database.len() as u64);
+        assert!(!with_suffix(&dest, ".zst.part").exists());
+        assert!(!with_suffix(&dest, ".part").exists());
+    }
+
+    #[test]
+    fn test_fetch_chunked_resumes() {
+        let database: Vec<u8> = (0..50_000u32).flat_map(|i| i.to_be_bytes()).collect();
+        let (base, ranges, compressed) = publish(&database);
+        let dir = tempfile::tempdir().unwrap();
+        let partial = dir.path().join("english.db.zst.part");
+        fs::write(&partial, &compressed[..100]).unwrap();
+        let sha256 = sha256_hex(&compressed);
+        fs::write(with_suffix(&partial, ".sha256"), &sha256).unwrap();
+
+        let url = format!("{}/english-dict-v42.db.zst", base);
+        let size = compressed.len() as u64;
+        fetch_chunked(&agent(), &url, &partial, &sha256, size, 1024, &mut |_| {}).unwrap();
+
+        assert_eq!(fs::read(&partial).unwrap(), compressed);
+        let ranges = ranges.lock().unwrap();
+        assert_eq!(ranges[0], "100-1123");
+        assert_eq!(ranges.len() as u64, (size - 100).div_ceil(1024));
+    }
+
+    #[test]
+    fn test_fetch_chunked_restarts_other_file() {
+        let database: Vec<u8> = (0..50_000u32).flat_map(|i| i.to_be_bytes()).collect();
+        let (base, ranges, compressed) = publish(&database);
+        let dir = tempfile::tempdir().unwrap();
+        let partial = dir.path().join("english.db.zst.part");
+        // A shorter partial file from the previous version's manifest
+        fs::write(&partial, b"older version").unwrap();
+        fs::write(with_suffix(&partial, ".sha256"), sha256_hex(b"older")).unwrap();
+
+        let url = format!("{}/english-dict-v42.db.zst", base);
+        let sha256 = sha256_hex(&compressed);
+        let size = compressed.len() as u64;
+        fetch_chunked(&agent(), &url, &partial, &sha256, size, 1024, &mut |_| {}).unwrap();
+
+        assert_eq!(fs::read(&partial).unwrap(), compressed);
+        assert_eq!(ranges.lock().unwrap()[0], "0-1023");
+        assert_eq!(
+            fs::read_to_string(with_suffix(&partial, ".sha256")).unwrap(),
+            sha256
+        );
+    }
+
+    #[test]
+    fn test_download_removes_stale_sidecars() {
+        let (base, _, _) = publish(b"SQLite format 3\0 v42");
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("english.db");
+        fs::write(&dest, b"SQLite format 3\0 v41").unwrap();
+        for stale in crate::db::sidecar_paths(&dest) {
+            fs::write(stale, b"v41").unwrap();
+        }
+
+        let manifest = fetch_manifest(&base, "english").unwrap();
+        download_database(&base, &manifest, &dest, |_| {}).unwrap();
+
+        assert_eq!(fs::read(&dest).unwrap(), b"SQLite format 3\0 v42");
+        for stale in crate::db::sidecar_paths(&dest) {
+            assert!(!stale.exists(), "{} left behind", stale.display());
+        }
+        assert!(!with_suffix(&dest, ".zst.part.sha256").exists());
+    }
+
+    #[test]
+    fn test_checksum_mismatch_discards_download() {
+        let (base, _, _) = publish(b"SQLite format 3\0");
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("english.db");
+
+        let mut manifest = fetch_manifest(&base, "english").unwrap();
+        manifest.sha256 = sha256_hex(b"something else");
+        let err = download_database(&base, &manifest, &dest, |_| {}).unwrap_err();
+        assert!(matches!(err, Error::ChecksumMismatch { .. }));
+        assert!(!dest.exists());
+        assert!(!with_suffix(&dest, ".zst.part").exists());
+    }
+}
@@ -0,0 +1,360 @@
+//! Editing dictionary entries
+//!
+//! Changes go through an `Editor`, which `edit` hands to a closure and
+//! whose changes are committed together once the closure returns `Ok`, or
+//! rolled back if it fails. The handle must be writable (`init_database`);
+//! on a read-only handle every edit fails. Once committed, the handle's
+//! cached definitions reflect the changes; after adding, renaming or
+//! deleting headwords, `DictHandle::refresh_indexes` brings the headword
+//! FST up to date.
+//!
+//! ```ignore
+//! let handle = dict_core::db::init_database("dictionary.db")?;
+//! let id = dict_core::editor::edit(&handle, |editor| {
+//!     let id = editor.insert_word("hello", "interjection", "English", "en", 0)?;
+//!     editor.insert_definition(id, "A greeting", &[], &[])?;
+//!     Ok(id)
+//! })?;
+//! ```
+
+use rusqlite::Connection;
+
+use crate::db;
+use crate::models::SourceInfo;
+use crate::{DictHandle, Result};
+
+/// Run `f` with an editor of `handle`, committing its changes if it
+/// returns `Ok`
+///
+/// Nothing `f` changed is kept if it returns an error, and the error is
+/// passed on.
+pub fn edit<T>(handle: &DictHandle, f: impl FnOnce(&Editor) -> Result<T>) -> Result<T> {
+    let tx = handle.conn.unchecked_transaction()?;
+    let value = f(&Editor { conn: &tx })?;
+    tx.commit()?;
+    handle.clear_caches();
+    handle
+        .fst_stale
+        .store(true, std::sync::atomic::Ordering::Relaxed);
+    Ok(value)
+}
+
+/// Writes to a dictionary within one transaction, see `edit`
+pub struct Editor<'a> {
+    conn: &'a Connection,
+}
+
+impl Editor<'_> {
+    /// Insert a word entry and return its ID
+    pub fn insert_word(
+        &self,
+        word: &str,
+        pos: &str,
+        language: &str,
+        lang_code: &str,
+        etymology_num: i32,
+    ) -> Result<i64> {
+        db::insert_word(self.conn, word, pos, language, lang_code, etymology_num)
+    }
+
+    /// Insert a definition for a word
+    pub fn insert_definition(
+        &self,
+        word_id: i64,
+        definition: &str,
+        examples: &[String],
+        tags: &[String],
+    ) -> Result<i64> {
+        db::insert_definition(self.conn, word_id, definition, examples, tags)
+    }
+
+    /// Insert a pronunciation for a word
+    pub fn insert_pronunciation(
+        &self,
+        word_id: i64,
+        ipa: Option<&str>,
+        audio_url: Option<&str>,
+        accent: Option<&str>,
+    ) -> Result<i64> {
+        db::insert_pronunciation(self.conn, word_id, ipa, audio_url, accent)
+    }
+
+    /// Set the audio file of a pronunciation
+    pub fn set_pronunciation_audio(&self, pronunciation_id: i64, audio_url: &str) -> Result<()> {
+        db::set_pronunciation_audio(self.conn, pronunciation_id, audio_url)
+    }
+
+    /// Insert an inflected or alternative form of a word
+    pub fn insert_form(&self, word_id: i64, form: &str, tags: &[String]) -> Result<i64> {
+        db::insert_form(self.conn, word_id, form, tags)
+    }
+
+    /// Record that a definition is an inflection or variant of `target`,
+    /// linked to its entry by `resolve_form_of`
+    pub fn set_form_of(&self, definition_id: i64, target: &str) -> Result<()> {
+        db::set_form_of(self.conn, definition_id, target)
+    }
+
+    /// Record the stable id of a definition's sense
+    pub fn set_sense_id(&self, definition_id: i64, sense_id: &str) -> Result<()> {
+        db::set_sense_id(self.conn, definition_id, sense_id)
+    }
+
+    /// Point form-of definitions at their target headword in the same
+    /// language, returning how many are linked
+    pub fn resolve_form_of(&self) -> Result<u64> {
+        db::resolve_form_of(self.conn)
+    }
+
+    /// Insert a synonym or antonym of a word, for the sense `definition_id`
+    /// or the one Wiktionary labels `sense`
+    pub fn insert_relation(
+        &self,
+        word_id: i64,
+        definition_id: Option<i64>,
+        kind: &str,
+        target: &str,
+        sense: Option<&str>,
+    ) -> Result<i64> {
+        db::insert_relation(self.conn, word_id, definition_id, kind, target, sense)
+    }
+
+    /// Insert a symbol (emoji, punctuation, sign) pointing at its entry
+    pub fn insert_symbol(
+        &self,
+        word_id: i64,
+        symbol: &str,
+        name: Option<&str>,
+        keywords: &[String],
+    ) -> Result<i64> {
+        db::insert_symbol(self.conn, word_id, symbol, name, keywords)
+    }
+
+    /// Insert a pinyin reading for a word; `None` if it is not valid pinyin
+    pub fn insert_pinyin(&self, word_id: i64, reading: &str) -> Result<Option<i64>> {
+        db::insert_pinyin(self.conn, word_id, reading)
+    }
+
+    /// Insert a kana reading for a word, folded to hiragana
+    #[cfg(feature = "kana")]
+    pub fn insert_kana(&self, word_id: i64, reading: &str) -> Result<i64> {
+        db::insert_kana(self.conn, word_id, reading)
+    }
+
+    /// Insert an etymology for a word
+    pub fn insert_etymology(&self, word_id: i64, text: &str) -> Result<i64> {
+        db::insert_etymology(self.conn, word_id, text)
+    }
+
+    /// Insert a translation for a word
+    pub fn insert_translation(
+        &self,
+        word_id: i64,
+        target_language: &str,
+        translation: &str,
+    ) -> Result<i64> {
+        db::insert_translation(self.conn, word_id, target_language, translation)
+    }
+
+    /// Record a source of entries and return its ID, reusing an identical one
+    pub fn insert_source(&self, source: &SourceInfo) -> Result<i64> {
+        db::insert_source(self.conn, source)
+    }
+
+    /// Record the source and source page revision of an entry
+    pub fn insert_entry_source(
+        &self,
+        word_id: i64,
+        source_id: i64,
+        revision: Option<i64>,
+    ) -> Result<()> {
+        db::insert_entry_source(self.conn, word_id, source_id, revision)
+    }
+
+    /// Update a word entry
+    pub fn update_word(&self, word_id: i64, word: &str, pos: &str, language: &str) -> Result<bool> {
+        db::update_word(self.conn, word_id, word, pos, language)
+    }
+
+    /// Update a definition
+    pub fn update_definition(
+        &self,
+        definition_id: i64,
+        definition: &str,
+        examples: &[String],
+        tags: &[String],
+    ) -> Result<bool> {
+        db::update_definition(self.conn, definition_id, definition, examples, tags)
+    }
+
+    /// Update a pronunciation
+    pub fn update_pronunciation(
+        &self,
+        pronunciation_id: i64,
+        ipa: Option<&str>,
+        audio_url: Option<&str>,
+        accent: Option<&str>,
+    ) -> Result<bool> {
+        db::update_pronunciation(self.conn, pronunciation_id, ipa, audio_url, accent)
+    }
+
+    /// Update an etymology
+    pub fn update_etymology(&self, etymology_id: i64, text: &str) -> Result<bool> {
+        db::update_etymology(self.conn, etymology_id, text)
+    }
+
+    /// Update a translation
+    pub fn update_translation(
+        &self,
+        translation_id: i64,
+        target_language: &str,
+        translation: &str,
+    ) -> Result<bool> {
+        db::update_translation(self.conn, translation_id, target_language, translation)
+    }
+
+    /// Delete a word entry and all associated data
+    pub fn delete_word(&self, word_id: i64) -> Result<bool> {
+        db::delete_word(self.conn, word_id)
+    }
+
+    /// Delete a definition
+    pub fn delete_definition(&self, definition_id: i64) -> Result<bool> {
+        db::delete_definition(self.conn, definition_id)
+    }
+
+    /// Delete a pronunciation
+    pub fn delete_pronunciation(&self, pronunciation_id: i64) -> Result<bool> {
+        db::delete_pronunciation(self.conn, pronunciation_id)
+    }
+
+    /// Delete an etymology
+    pub fn delete_etymology(&self, etymology_id: i64) -> Result<bool> {
+        db::delete_etymology(self.conn, etymology_id)
+    }
+
+    /// Delete a translation
+    pub fn delete_translation(&self, translation_id: i64) -> Result<bool> {
+        db::delete_translation(self.conn, translation_id)
+    }
+
+    /// Set a build metadata value
+    pub fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+        db::set_meta(self.conn, key, value)
+    }
+
+    /// Fill the completions table from the headwords, replacing its rows
+    pub fn rebuild_completions(&self) -> Result<()> {
+        db::rebuild_completions(self.conn)
+    }
+
+    /// Rebuild the FTS index (useful after bulk operations)
+    pub fn rebuild_fts_index(&self) -> Result<()> {
+        db::rebuild_fts_index(self.conn)
+    }
+
+    /// Optimize the FTS index for better search performance
+    pub fn optimize_fts_index(&self) -> Result<()> {
+        db::optimize_fts_index(self.conn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{get_full_definition, init_database};
+    use crate::search::{complete, search_words};
+    use crate::Error;
+
+    #[test]
+    fn test_edit() {
+        let dir = tempfile::tempdir().unwrap();
+        let handle = init_database(dir.path().join("test.db").to_str().unwrap()).unwrap();
+
+        let hello = edit(&handle, |editor| {
+            let id = editor.insert_word("hello", "interjection", "English", "en", 0)?;
+            editor.insert_definition(id, "A greeting", &[], &[])?;
+            editor.insert_translation(id, "fr", "bonjour")?;
+            editor.rebuild_completions()?;
+            Ok(id)
+        })
+        .unwrap();
+        let full = get_full_definition(&handle, hello).unwrap().unwrap();
+        assert_eq!(full.definitions[0].text, "A greeting");
+        assert_eq!(full.translations[0].translation, "bonjour");
+        assert_eq!(complete(&handle, "hel", 5).unwrap(), ["hello"]);
+
+        // A failed edit leaves nothing behind
+        let result: Result<()> = edit(&handle, |editor| {
+            editor.delete_word(hello)?;
+            editor.insert_word("world", "noun", "English", "en", 0)?;
+            Err(Error::InvalidInput("cancelled".to_string()))
+        });
+        assert!(result.is_err());
+        assert!(get_full_definition(&handle, hello).unwrap().is_some());
+        assert_eq!(complete(&handle, "wor", 5).unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_read_your_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        let mut handle = init_database(path.to_str().unwrap()).unwrap();
+        handle.config.definition_cache = 16;
+        let words: Vec<(i64, i64)> = ["hello", "yellow"]
+            .iter()
+            .map(|word| {
+                edit(&handle, |editor| {
+                    let id = editor.insert_word(word, "noun", "English", "en", 0)?;
+                    Ok((id, editor.insert_definition(id, "old", &[], &[])?))
+                })
+                .unwrap()
+            })
+            .collect();
+        let (hello, sense) = words[0];
+        #[cfg(feature = "fst")]
+        crate::fuzzy::build_fst(path.to_str().unwrap()).unwrap();
+
+        let text = |handle: &DictHandle| {
+            get_full_definition(handle, hello)
+                .unwrap()
+                .unwrap()
+                .definitions[0]
+                .text
+                .clone()
+        };
+        let found = |handle: &DictHandle, query: &str| -> Vec<String> {
+            search_words(handle, query, 5)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.word)
+                .collect()
+        };
+        assert_eq!(text(&handle), "old");
+        assert_eq!(found(&handle, "helo"), ["hello"]);
+
+        edit(&handle, |editor| {
+            editor.update_definition(sense, "new", &[], &[])
+        })
+        .unwrap();
+        assert_eq!(text(&handle), "new");
+
+        // Writes outside an editor are seen once invalidated
+        db::update_definition(&handle.conn, sense, "newer", &[], &[]).unwrap();
+        assert_eq!(text(&handle), "new");
+        handle.invalidate(hello);
+        assert_eq!(text(&handle), "newer");
+
+        // New headwords reach the FST on refresh
+        edit(&handle, |editor| {
+            editor.insert_word("world", "noun", "English", "en", 0)
+        })
+        .unwrap();
+        assert_eq!(complete(&handle, "wor", 5).unwrap(), ["world"]);
+        #[cfg(feature = "fst")]
+        assert_eq!(found(&handle, "wrld"), Vec::<String>::new());
+        handle.refresh_indexes().unwrap();
+        assert_eq!(found(&handle, "wrld"), ["world"]);
+        assert_eq!(complete(&handle, "wor", 5).unwrap(), ["world"]);
+    }
+}
@@ -0,0 +1,224 @@
+//! Analysis of stored example sentences
+//!
+//! [`collocations`] counts the words that appear near a word across every
+//! example sentence in the dictionary, not just the word's own entry, so
+//! learners can see that "decision" is usually *made*, and "rain" *heavy*.
+//! [`filter`] trims the examples of a sense before they are stored.
+
+use std::collections::{HashMap, HashSet};
+
+use rusqlite::params_from_iter;
+
+use crate::models::Collocate;
+use crate::{DictHandle, Result};
+
+/// Most frequent neighbors of `word` in the stored example sentences
+///
+/// Every occurrence of `word` or one of its recorded inflected forms
+/// (case-insensitive) counts the words up to `window` positions before and
+/// after it. Each collocate is reported per position, so "make" two words
+/// before "decision" is counted separately from "make" right after it.
+/// Returns the `n` most frequent, ties in alphabetical order.
+pub fn collocations(
+    handle: &DictHandle,
+    word: &str,
+    window: usize,
+    n: usize,
+) -> Result<Vec<Collocate>> {
+    let targets = word_forms(handle, word)?;
+    if targets.is_empty() || window == 0 {
+        return Ok(Vec::new());
+    }
+
+    // LIKE is case-insensitive for ASCII only, which is enough to narrow
+    // the scan; the exact match happens on the tokens
+    let conditions = vec!["examples LIKE ?"; targets.len()].join(" OR ");
+    let patterns: Vec<String> = targets
+        .iter()
+        .map(|t| format!("%{}%", t.replace(['%', '_'], "")))
+        .collect();
+    let mut stmt = handle.conn.prepare(&format!(
+        "SELECT examples FROM definitions WHERE examples != '[]' AND ({conditions})"
+    ))?;
+    let rows = stmt.query_map(params_from_iter(&patterns), |row| {
+        row.get::<_, Option<String>>(0)
+    })?;
+
+    let mut counts: HashMap<(String, i32), u64> = HashMap::new();
+    for examples_json in rows {
+        let Some(examples_json) = examples_json? else {
+            continue;
+        };
+        let examples: Vec<String> = serde_json::from_str(&examples_json).unwrap_or_default();
+        for example in &examples {
+            let tokens: Vec<String> = crate::text::tokens(example)
+                .into_iter()
+                .map(|(_, t)| t.to_lowercase())
+                .collect();
+            for (i, token) in tokens.iter().enumerate() {
+                if !targets.contains(token) {
+                    continue;
+                }
+                let first = i.saturating_sub(window);
+                let last = (i + window).min(tokens.len() - 1);
+                for (j, neighbor) in tokens.iter().enumerate().take(last + 1).skip(first) {
+                    if j == i || targets.contains(neighbor) {
+                        continue;
+                    }
+                    if !neighbor.chars().any(char::is_alphabetic) {
+                        continue;
+                    }
+                    let offset = j as i32 - i as i32;
+                    *counts.entry((neighbor.clone(), offset)).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+
+    let mut collocates: Vec<Collocate> = counts
+        .into_iter()
+        .map(|((word, offset), count)| Collocate {
+            word,
+            offset,
+            count,
+        })
+        .collect();
+    collocates.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.word.cmp(&b.word))
+            .then(a.offset.cmp(&b.offset))
+    });
+    collocates.truncate(n);
+    Ok(collocates)
+}
+
+/// The examples worth storing out of those given for a sense
+///
+/// Empty examples and, with `max_len`, those longer than that many
+/// characters are dropped. With `dedup`, so are examples that only differ
+/// from an earlier one in case, diacritics, punctuation or spacing.
+pub fn filter<'a>(
+    examples: impl IntoIterator<Item = &'a str>,
+    dedup: bool,
+    max_len: Option<usize>,
+) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut kept = Vec::new();
+    for example in examples {
+        let example = example.trim();
+        if example.is_empty() || max_len.is_some_and(|max| example.chars().count() > max) {
+            continue;
+        }
+        if dedup {
+            let key: String = crate::normalize::search_key(example)
+                .chars()
+                .filter(|c| c.is_alphanumeric())
+                .collect();
+            if !seen.insert(key) {
+                continue;
+            }
+        }
+        kept.push(example.to_string());
+    }
+    kept
+}
+
+/// `word` and the inflected forms recorded for it, lowercased
+fn word_forms(handle: &DictHandle, word: &str) -> Result<HashSet<String>> {
+    let word = word.trim().to_lowercase();
+    let mut forms = HashSet::new();
+    if word.is_empty() {
+        return Ok(forms);
+    }
+
+    if crate::db::table_exists(&handle.conn, "forms")? {
+        let mut stmt = handle.conn.prepare_cached(
+            r#"
+            SELECT f.form
+            FROM forms f
+            JOIN words w ON w.id = f.word_id
+            WHERE w.word = ?
+            "#,
+        )?;
+        let rows = stmt.query_map([&word], |row| row.get::<_, String>(0))?;
+        for form in rows {
+            let form = form?.to_lowercase();
+            // Multi-word forms ("has made") never equal a single token
+            if !form.contains(' ') {
+                forms.insert(form);
+            }
+        }
+    }
+    forms.insert(word);
+    Ok(forms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_database, insert_definition, insert_form, insert_word};
+
+    #[test]
+    fn test_collocations() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let handle = init_database(db_path.to_str().unwrap()).unwrap();
+        let conn = &handle.conn;
+
+        let decision = insert_word(conn, "decision", "noun", "English", "en", 0).unwrap();
+        insert_form(conn, decision, "decisions", &["plural".to_string()]).unwrap();
+        insert_definition(
+            conn,
+            decision,
+            "A choice.",
+            &[
+                "We had to make a decision quickly.".to_string(),
+                "Decisions are hard to make.".to_string(),
+            ],
+            &[],
+        )
+        .unwrap();
+        let choose = insert_word(conn, "choose", "verb", "English", "en", 0).unwrap();
+        insert_definition(
+            conn,
+            choose,
+            "To pick.",
+            &["She had to make a decision alone.".to_string()],
+            &[],
+        )
+        .unwrap();
+
+        let collocates = collocations(&handle, "decision", 2, 3).unwrap();
+        let summary: Vec<(&str, i32, u64)> = collocates
+            .iter()
+            .map(|c| (c.word.as_str(), c.offset, c.count))
+            .collect();
+        assert_eq!(summary[..2], [("a", -1, 2), ("make", -2, 2)]);
+
+        // Window of one sees only direct neighbors, across both examples
+        let collocates = collocations(&handle, "decision", 1, 10).unwrap();
+        let words: Vec<&str> = collocates.iter().map(|c| c.word.as_str()).collect();
+        assert_eq!(words, ["a", "alone", "are", "quickly"]);
+        assert_eq!(collocates[0].count, 2);
+
+        assert!(collocations(&handle, "unknown", 2, 5).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_filter() {
+        let examples = [
+            "The cat sat.",
+            "the cat  sat",
+            "The cát sat!",
+            "",
+            "A cat sat on the mat for a very long time.",
+        ];
+        assert_eq!(
+            filter(examples, true, None),
+            ["The cat sat.", "A cat sat on the mat for a very long time."]
+        );
+        assert_eq!(filter(examples, true, Some(20)), ["The cat sat."]);
+        assert_eq!(filter(examples, false, Some(20)).len(), 3);
+    }
+}
@@ -0,0 +1,643 @@
+//! Database export functionality
+//!
+//! This module converts an existing dictionary database back into
+//! interchange formats:
+//!
+//! - **JSONL** in the kaikki.org shape accepted by the importer, so an
+//!   export can be re-imported
+//! - **CSV** with one row per definition, for spreadsheets and scripts
+//! - **StarDict** (`.ifo`/`.idx`/`.dict`), readable by GoldenDict and other
+//!   desktop dictionary clients
+//!
+//! [`extract_subset`] instead writes a smaller dictionary database holding
+//! some of the entries, e.g. an offline travel pack built on the device.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::db::get_full_definition;
+use crate::models::FullDefinition;
+use crate::{DictHandle, Result};
+
+/// Header row written by [`export_csv`]
+const CSV_HEADER: &str = "word_id,word,pos,language,lang_code,definition,tags,examples";
+
+/// Supported export formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One kaikki.org-style JSON object per word entry
+    Jsonl,
+    /// One CSV row per definition
+    Csv,
+    /// StarDict dictionary files
+    Stardict,
+}
+
+/// Files written by [`export_stardict`]
+#[derive(Debug, Clone)]
+pub struct StardictFiles {
+    /// Dictionary info file
+    pub ifo: PathBuf,
+    /// Sorted headword index
+    pub idx: PathBuf,
+    /// Article data
+    pub dict: PathBuf,
+    /// Number of headwords in the index
+    pub headwords: u64,
+}
+
+/// Entries kept by [`extract_subset`]; unset filters keep everything
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubsetFilters {
+    /// Languages to keep, by name ("Spanish") or code ("es"),
+    /// case-insensitive
+    pub languages: Option<Vec<String>>,
+    /// Parts of speech to keep ("noun", "verb"), case-insensitive
+    pub parts_of_speech: Option<Vec<String>>,
+    /// Frequency levels to keep (see [`crate::lists::LEVEL_SIZE`]), e.g.
+    /// `1..=5` for the 5000 most frequent words; words without a
+    /// frequency count are dropped
+    pub levels: Option<RangeInclusive<u32>>,
+}
+
+/// Result of [`extract_subset`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SubsetStats {
+    /// Word entries in the new database
+    pub words: u64,
+    /// Size of the new database in bytes
+    pub bytes: i64,
+}
+
+/// Export every word entry as JSONL
+///
+/// Each line is an object in the format read by the importer (`word`, `pos`,
+/// `lang`, `senses`, `sounds`, ...). Returns the number of entries written.
+/// `progress` receives (entries_written, total_entries).
+pub fn export_jsonl<W: Write>(
+    handle: &DictHandle,
+    writer: W,
+    progress: impl Fn(u64, u64),
+) -> Result<u64> {
+    let mut writer = BufWriter::new(writer);
+    let count = for_each_entry(handle, "ORDER BY id", progress, |entry| {
+        serde_json::to_writer(&mut writer, &entry_to_json(entry))?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    })?;
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Export every definition as a CSV row
+///
+/// Words without definitions get a single row with an empty definition so
+/// that no entry is lost. Tags and examples are joined with `"; "`.
+/// Returns the number of word entries written.
+pub fn export_csv<W: Write>(
+    handle: &DictHandle,
+    writer: W,
+    progress: impl Fn(u64, u64),
+) -> Result<u64> {
+    let mut writer = BufWriter::new(writer);
+    writeln!(writer, "{}", CSV_HEADER)?;
+
+    let count = for_each_entry(handle, "ORDER BY id", progress, |entry| {
+        let def = &entry.definition;
+        let prefix = [
+            entry.id.to_string(),
+            csv_field(&def.word),
+            csv_field(&def.pos),
+            csv_field(&def.language),
+            csv_field(&def.lang_code),
+        ]
+        .join(",");
+
+        if def.definitions.is_empty() {
+            writeln!(writer, "{},,,", prefix)?;
+        }
+        for sense in &def.definitions {
+            writeln!(
+                writer,
+                "{},{},{},{}",
+                prefix,
+                csv_field(&sense.text),
+                csv_field(&sense.tags.join("; ")),
+                csv_field(&sense.examples.join("; "))
+            )?;
+        }
+        Ok(())
+    })?;
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Export the database as a StarDict dictionary
+///
+/// Writes `{name}.ifo`, `{name}.idx` and `{name}.dict` into `output_dir`.
+/// Entries sharing a headword (different parts of speech or etymologies)
+/// are merged into one article.
+pub fn export_stardict(
+    handle: &DictHandle,
+    output_dir: &Path,
+    name: &str,
+    progress: impl Fn(u64, u64),
+) -> Result<StardictFiles> {
+    std::fs::create_dir_all(output_dir)?;
+    let files = StardictFiles {
+        ifo: output_dir.join(format!("{}.ifo", name)),
+        idx: output_dir.join(format!("{}.idx", name)),
+        dict: output_dir.join(format!("{}.dict", name)),
+        headwords: 0,
+    };
+
+    // Articles are streamed into .dict; only (headword, offset, size) is kept
+    // in memory for the index, which must be sorted at the end.
+    let mut dict = BufWriter::new(File::create(&files.dict)?);
+    let mut index: Vec<(String, u32, u32)> = Vec::new();
+    let mut offset: u64 = 0;
+    let mut current: Option<(String, String)> = None;
+
+    let mut flush_article = |article: Option<(String, String)>,
+                             dict: &mut BufWriter<File>,
+                             index: &mut Vec<(String, u32, u32)>|
+     -> Result<()> {
+        if let Some((headword, text)) = article {
+            let start = u32::try_from(offset).map_err(|_| stardict_too_large())?;
+            let size = u32::try_from(text.len()).map_err(|_| stardict_too_large())?;
+            dict.write_all(text.as_bytes())?;
+            offset += text.len() as u64;
+            index.push((headword, start, size));
+        }
+        Ok(())
+    };
+
+    for_each_entry(handle, "ORDER BY word, id", progress, |entry| {
+        let def = &entry.definition;
+        match current.as_mut() {
+            Some((headword, text)) if *headword == def.word => {
+                text.push_str("\n\n");
+                text.push_str(&stardict_article(def));
+            }
+            _ => {
+                let article = Some((def.word.clone(), stardict_article(def)));
+                flush_article(
+                    std::mem::replace(&mut current, article),
+                    &mut dict,
+                    &mut index,
+                )?;
+            }
+        }
+        Ok(())
+    })?;
+    flush_article(current.take(), &mut dict, &mut index)?;
+    dict.flush()?;
+
+    index.sort_by(|a, b| stardict_cmp(&a.0, &b.0));
+    let mut idx = BufWriter::new(File::create(&files.idx)?);
+    let mut idx_size: u64 = 0;
+    for (headword, start, size) in &index {
+        idx.write_all(headword.as_bytes())?;
+        idx.write_all(&[0])?;
+        idx.write_all(&start.to_be_bytes())?;
+        idx.write_all(&size.to_be_bytes())?;
+        idx_size += headword.len() as u64 + 9;
+    }
+    idx.flush()?;
+
+    let mut ifo = File::create(&files.ifo)?;
+    write!(
+        ifo,
+        "StarDict's dict ifo file\nversion=2.4.2\nbookname={}\nwordcount={}\nidxfilesize={}\nsametypesequence=m\n",
+        name.replace('\n', " "),
+        index.len(),
+        idx_size
+    )?;
+
+    Ok(StardictFiles {
+        headwords: index.len() as u64,
+        ..files
+    })
+}
+
+/// Write the entries of the dictionary matching `filters` to a new
+/// database at `dest_path`
+///
+/// The result is a complete dictionary that can be opened like the one it
+/// came from, with the same schema, build metadata and full-text index
+/// layout, plus a `subset` meta key describing the filters. Entries are
+/// copied with everything attached to them. Fails if `dest_path` already
+/// exists; needs free disk space about the size of the source database
+/// while it runs.
+pub fn extract_subset(
+    handle: &DictHandle,
+    dest_path: &Path,
+    filters: &SubsetFilters,
+) -> Result<SubsetStats> {
+    if dest_path.exists() {
+        return Err(crate::Error::InvalidPath(format!(
+            "{} already exists",
+            dest_path.display()
+        )));
+    }
+    let dest = dest_path
+        .to_str()
+        .ok_or_else(|| crate::Error::InvalidPath(dest_path.display().to_string()))?;
+    handle.conn.execute("VACUUM INTO ?", [dest])?;
+
+    let result = remove_unselected(dest, filters);
+    if result.is_err() {
+        let _ = std::fs::remove_file(dest_path);
+    }
+    result
+}
+
+/// Delete the entries of the copy at `path` that `filters` leave out
+fn remove_unselected(path: &str, filters: &SubsetFilters) -> Result<SubsetStats> {
+    let mut conn = Connection::open(path)?;
+    conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+
+    let has_lang_code = crate::db::has_column(&conn, "words", "lang_code")?;
+    let has_frequency = crate::db::has_column(&conn, "words", "frequency")?;
+    let mut conditions = vec!["1".to_string()];
+    let mut values: Vec<String> = Vec::new();
+    let mut description = Vec::new();
+    if let Some(languages) = &filters.languages {
+        let placeholders = vec!["?"; languages.len()].join(",");
+        conditions.push(if has_lang_code {
+            format!("(lower(language) IN ({placeholders}) OR lower(lang_code) IN ({placeholders}))")
+        } else {
+            format!("lower(language) IN ({placeholders})")
+        });
+        let lowered: Vec<String> = languages.iter().map(|l| l.to_lowercase()).collect();
+        values.extend(lowered.iter().cloned());
+        if has_lang_code {
+            values.extend(lowered);
+        }
+        description.push(format!("languages={}", languages.join(",")));
+    }
+    if let Some(parts_of_speech) = &filters.parts_of_speech {
+        let placeholders = vec!["?"; parts_of_speech.len()].join(",");
+        conditions.push(format!("lower(pos) IN ({placeholders})"));
+        values.extend(parts_of_speech.iter().map(|p| p.to_lowercase()));
+        description.push(format!("pos={}", parts_of_speech.join(",")));
+    }
+    if let Some(levels) = &filters.levels {
+        conditions.push(format!(
+            "level BETWEEN {} AND {}",
+            levels.start(),
+            levels.end()
+        ));
+        description.push(format!("levels={}-{}", levels.start(), levels.end()));
+    }
+
+    let tx = conn.transaction()?;
+    tx.execute(
+        &format!(
+            "CREATE TEMP TABLE subset AS
+             WITH ranked AS (SELECT *, {} AS level FROM words)
+             SELECT id FROM ranked WHERE {}",
+            crate::lists::level_sql(has_frequency),
+            conditions.join(" AND ")
+        ),
+        params_from_iter(&values),
+    )?;
+
+    // Deleting row by row from the full-text index is slow; it is rebuilt
+    // from the remaining words instead
+    let has_fts = crate::db::table_exists(&tx, "words_fts")?;
+    let delete_trigger: Option<String> = tx
+        .query_row(
+            "SELECT sql FROM sqlite_master WHERE type = 'trigger' AND name = 'words_ad'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if delete_trigger.is_some() {
+        tx.execute_batch("DROP TRIGGER words_ad")?;
+    }
+    tx.execute(
+        "DELETE FROM words WHERE id NOT IN (SELECT id FROM temp.subset)",
+        [],
+    )?;
+    if has_fts {
+        tx.execute("INSERT INTO words_fts(words_fts) VALUES ('rebuild')", [])?;
+    }
+    if let Some(sql) = delete_trigger {
+        tx.execute_batch(&sql)?;
+    }
+    if crate::db::has_column(&tx, "definitions", "form_of_id")? {
+        tx.execute(
+            "UPDATE definitions SET form_of_id = NULL
+             WHERE form_of_id IS NOT NULL AND form_of_id NOT IN (SELECT id FROM words)",
+            [],
+        )?;
+    }
+    if crate::db::table_exists(&tx, "completions")? {
+        tx.execute(
+            "DELETE FROM completions WHERE word NOT IN (SELECT word FROM words)",
+            [],
+        )?;
+    }
+    tx.execute("DROP TABLE temp.subset", [])?;
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+        [],
+    )?;
+    crate::db::set_meta(&tx, "subset", &description.join(";"))?;
+    tx.commit()?;
+
+    conn.execute_batch("VACUUM")?;
+    let words: i64 = conn.query_row("SELECT COUNT(*) FROM words", [], |row| row.get(0))?;
+    Ok(SubsetStats {
+        words: words as u64,
+        bytes: crate::db::database_bytes(&conn)?,
+    })
+}
+
+/// A word entry with the columns not carried by `FullDefinition`
+struct ExportEntry {
+    id: i64,
+    etymology_num: i32,
+    definition: FullDefinition,
+}
+
+/// Call `f` for every word entry in the given order
+fn for_each_entry(
+    handle: &DictHandle,
+    order_by: &str,
+    progress: impl Fn(u64, u64),
+    mut f: impl FnMut(&ExportEntry) -> Result<()>,
+) -> Result<u64> {
+    let total: i64 = handle
+        .conn
+        .query_row("SELECT COUNT(*) FROM words", [], |row| row.get(0))?;
+
+    let mut stmt = handle.conn.prepare(&format!(
+        "SELECT id, COALESCE(etymology_num, 0) FROM words {}",
+        order_by
+    ))?;
+    let rows = stmt.query_map(params![], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, i32>(1)?))
+    })?;
+
+    let mut written = 0u64;
+    for row in rows {
+        let (id, etymology_num) = row?;
+        if let Some(definition) = get_full_definition(handle, id)? {
+            f(&ExportEntry {
+                id,
+                etymology_num,
+                definition,
+            })?;
+            written += 1;
+        }
+        if written.is_multiple_of(10_000) {
+            progress(written, total as u64);
+        }
+    }
+    progress(written, total as u64);
+
+    Ok(written)
+}
+
+/// Convert an entry to the kaikki.org JSON shape read by the importer
+fn entry_to_json(entry: &ExportEntry) -> Value {
+    let def = &entry.definition;
+    let mut obj = json!({
+        "word": def.word,
+        "pos": def.pos,
+        "lang": def.language,
+        "lang_code": def.lang_code,
+        "senses": def.definitions.iter().map(|sense| json!({
+            "glosses": [sense.text],
+            "examples": sense.examples.iter().map(|text| json!({"text": text})).collect::<Vec<_>>(),
+            "tags": sense.tags,
+        })).collect::<Vec<_>>(),
+    });
+
+    if entry.etymology_num > 0 {
+        obj["etymology_number"] = json!(entry.etymology_num);
+    }
+    if let Some(etymology) = &def.etymology {
+        obj["etymology_text"] = json!(etymology);
+    }
+    if !def.pronunciations.is_empty() {
+        obj["sounds"] = def
+            .pronunciations
+            .iter()
+            .map(|p| {
+                let mut sound = json!({ "ipa": p.ipa });
+                if let Some(audio) = &p.audio_url {
+                    sound["audio"] = json!(audio);
+                }
+                if let Some(accent) = &p.accent {
+                    sound["tags"] = json!([accent]);
+                }
+                sound
+            })
+            .collect();
+    }
+    if !def.translations.is_empty() {
+        obj["translations"] = def
+            .translations
+            .iter()
+            .map(|t| json!({ "code": t.target_language, "word": t.translation }))
+            .collect();
+    }
+
+    obj
+}
+
+/// Quote a CSV field if it contains a delimiter, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Plain-text StarDict article for one word entry
+fn stardict_article(def: &FullDefinition) -> String {
+    let mut text = def.pos.clone();
+    if let Some(ipa) = def.pronunciations.iter().find_map(|p| p.ipa.as_deref()) {
+        text.push(' ');
+        text.push_str(ipa);
+    }
+    for (i, sense) in def.definitions.iter().enumerate() {
+        text.push_str(&format!("\n{}. {}", i + 1, sense.text));
+        for example in &sense.examples {
+            text.push_str(&format!("\n    \"{}\"", example));
+        }
+    }
+    if let Some(etymology) = &def.etymology {
+        text.push_str("\nEtymology: ");
+        text.push_str(etymology);
+    }
+    text
+}
+
+/// Headword order required by StarDict: ASCII case-insensitive, then bytewise
+fn stardict_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    let fold = |s: &str| {
+        s.bytes()
+            .map(|b| b.to_ascii_lowercase())
+            .collect::<Vec<_>>()
+    };
+    fold(a).cmp(&fold(b)).then_with(|| a.cmp(b))
+}
+
+fn stardict_too_large() -> crate::Error {
+    crate::Error::Io(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        "StarDict output exceeds the 4 GiB offset limit",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{insert_definition, insert_pronunciation, insert_word};
+    use tempfile::tempdir;
+
+    fn sample_db(dir: &Path) -> DictHandle {
+        let path = dir.join("test.db");
+        let handle = crate::db::init_database(path.to_str().unwrap()).unwrap();
+        let conn = &handle.conn;
+        let id = insert_word(conn, "test", "noun", "English", "en", 0).unwrap();
+        insert_definition(
+            conn,
+            id,
+            "A trial, \"exam\"",
+            &["a hard test".to_string()],
+            &["formal".to_string()],
+        )
+        .unwrap();
+        insert_pronunciation(conn, id, Some("/tɛst/"), None, Some("US")).unwrap();
+        let id = insert_word(conn, "test", "verb", "English", "en", 0).unwrap();
+        insert_definition(conn, id, "To try", &[], &[]).unwrap();
+        let id = insert_word(conn, "Apple", "noun", "English", "en", 0).unwrap();
+        insert_definition(conn, id, "A fruit", &[], &[]).unwrap();
+        handle
+    }
+
+    #[test]
+    fn test_extract_subset() {
+        let dir = tempdir().unwrap();
+        let handle = sample_db(dir.path());
+        let conn = &handle.conn;
+        let hola = insert_word(conn, "hola", "intj", "Spanish", "es", 0).unwrap();
+        insert_definition(conn, hola, "hello", &[], &[]).unwrap();
+        conn.execute("UPDATE words SET frequency = 10 WHERE word = 'test'", [])
+            .unwrap();
+
+        let dest = dir.path().join("nouns.db");
+        let filters = SubsetFilters {
+            languages: Some(vec!["EN".to_string()]),
+            parts_of_speech: Some(vec!["noun".to_string()]),
+            ..SubsetFilters::default()
+        };
+        let stats = extract_subset(&handle, &dest, &filters).unwrap();
+        assert_eq!(stats.words, 2);
+        assert!(stats.bytes > 0);
+        assert!(extract_subset(&handle, &dest, &filters).is_err());
+
+        let subset = crate::db::init_database(dest.to_str().unwrap()).unwrap();
+        let results = crate::search::search_words(&subset, "test", 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].pos, "noun");
+        let full = get_full_definition(&subset, results[0].id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(full.pronunciations.len(), 1);
+        assert!(crate::search::search_words(&subset, "hola", 10)
+            .unwrap()
+            .is_empty());
+        let meta = crate::db::get_metadata(&subset).unwrap();
+        assert_eq!(meta["subset"], "languages=EN;pos=noun");
+
+        let frequent = dir.path().join("frequent.db");
+        let filters = SubsetFilters {
+            levels: Some(1..=1),
+            ..SubsetFilters::default()
+        };
+        assert_eq!(
+            extract_subset(&handle, &frequent, &filters).unwrap().words,
+            2
+        );
+    }
+
+    #[test]
+    fn test_export_jsonl_round_trips() {
+        let dir = tempdir().unwrap();
+        let handle = sample_db(dir.path());
+
+        let mut out = Vec::new();
+        let count = export_jsonl(&handle, &mut out, |_, _| {}).unwrap();
+        assert_eq!(count, 3);
+
+        let jsonl = dir.path().join("export.jsonl");
+        std::fs::write(&jsonl, &out).unwrap();
+        let reimported = dir.path().join("reimported.db");
+        let stats = crate::import::import_from_jsonl_with_stats(
+            reimported.to_str().unwrap(),
+            jsonl.to_str().unwrap(),
+            |_, _| {},
+        )
+        .unwrap();
+        assert_eq!(stats.words_imported, 3);
+        assert_eq!(stats.definitions_imported, 3);
+        assert_eq!(stats.pronunciations_imported, 1);
+    }
+
+    #[test]
+    fn test_export_csv_quotes_fields() {
+        let dir = tempdir().unwrap();
+        let handle = sample_db(dir.path());
+
+        let mut out = Vec::new();
+        export_csv(&handle, &mut out, |_, _| {}).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(
+            lines[1],
+            "1,test,noun,English,en,\"A trial, \"\"exam\"\"\",formal,a hard test"
+        );
+        assert_eq!(lines.len(), 4);
+    }
+
+    #[test]
+    fn test_export_stardict_merges_headwords() {
+        let dir = tempdir().unwrap();
+        let handle = sample_db(dir.path());
+
+        let out_dir = dir.path().join("stardict");
+        let files = export_stardict(&handle, &out_dir, "english", |_, _| {}).unwrap();
+        assert_eq!(files.headwords, 2);
+
+        let ifo = std::fs::read_to_string(&files.ifo).unwrap();
+        assert!(ifo.contains("wordcount=2\n"));
+        let idx = std::fs::read(&files.idx).unwrap();
+        assert!(ifo.contains(&format!("idxfilesize={}\n", idx.len())));
+
+        // "Apple" sorts before "test" and the two "test" entries share one article
+        assert!(idx.starts_with(b"Apple\0"));
+        let test_pos = idx.windows(5).position(|w| w == b"test\0").unwrap() + 5;
+        let offset = u32::from_be_bytes(idx[test_pos..test_pos + 4].try_into().unwrap());
+        let size = u32::from_be_bytes(idx[test_pos + 4..test_pos + 8].try_into().unwrap());
+        let dict = std::fs::read(&files.dict).unwrap();
+        let article =
+            std::str::from_utf8(&dict[offset as usize..(offset + size) as usize]).unwrap();
+        assert!(article.starts_with("noun /tɛst/\n1. A trial"));
+        assert!(article.contains("verb\n1. To try"));
+    }
+}
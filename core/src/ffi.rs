@@ -9,10 +9,29 @@
 
 use std::ffi::{CStr, CString};
 use std::os::raw::{c_char, c_int, c_longlong};
+use std::ptr;
 
 use std::sync::Mutex;
 
-use crate::{get_definition, init, search_with_offset, DictHandle};
+use crate::{
+    db, export, get_definition, get_definition_with_options, init, init_with_config, render,
+    search, thesaurus, translate, try_search_with_offset, userdata, CompactOptions,
+    DefinitionOptions, DefinitionParts, DictConfig, DictHandle, Error, RelationKind, SubsetFilters,
+};
+use render::ThemeOptions;
+use userdata::UserData;
+
+#[cfg(feature = "download")]
+use crate::{download, updates};
+
+/// What rendered definitions include: form-of targets, tappable links and
+/// respellings, which the theme decides whether to show
+const RENDER_OPTIONS: DefinitionOptions = DefinitionOptions {
+    inline_form_of: true,
+    links: true,
+    parts: DefinitionParts::ALL,
+    respelling: true,
+};
 
 /// Global handle storage for FFI
 ///
@@ -20,6 +39,20 @@ use crate::{get_definition, init, search_with_offset, DictHandle};
 /// using a handle map with integer keys.
 static HANDLE: Mutex<Option<DictHandle>> = Mutex::new(None);
 
+/// The user database opened by `dict_user_open`, for bookmarks
+static USER: Mutex<Option<UserData>> = Mutex::new(None);
+
+/// Interrupts queries on the handle in `HANDLE`, kept apart from it since
+/// the search to be interrupted holds that lock
+static INTERRUPT: Mutex<Option<rusqlite::InterruptHandle>> = Mutex::new(None);
+
+/// Replace the handle in `HANDLE` (None to close it)
+fn set_handle(handle: Option<DictHandle>) {
+    let mut guard = HANDLE.lock().unwrap();
+    *INTERRUPT.lock().unwrap() = handle.as_ref().map(|h| h.conn.get_interrupt_handle());
+    *guard = handle;
+}
+
 /// Error codes returned by FFI functions
 #[repr(C)]
 pub enum FfiError {
@@ -37,6 +70,28 @@ pub enum FfiError {
     SearchFailed = 5,
     /// JSON serialization failed
     JsonFailed = 6,
+    /// Network or file error while downloading; retrying resumes
+    DownloadFailed = 7,
+    /// Downloaded data did not match the manifest checksum
+    ChecksumMismatch = 8,
+    /// The database's search index is damaged; download it again
+    FtsCorrupt = 9,
+    /// The search was interrupted
+    Interrupted = 10,
+    /// The query could not be parsed
+    InvalidQuery = 11,
+}
+
+impl FfiError {
+    /// The code for a failed search
+    fn from_search(error: &Error) -> Self {
+        match error {
+            Error::FtsCorrupt(_) => FfiError::FtsCorrupt,
+            Error::Interrupted => FfiError::Interrupted,
+            Error::InvalidQuery(_) => FfiError::InvalidQuery,
+            _ => FfiError::SearchFailed,
+        }
+    }
 }
 
 /// Initialize the dictionary database
@@ -61,8 +116,54 @@ pub unsafe extern "C" fn dict_init(db_path: *const c_char) -> c_int {
 
     match init(path) {
         Ok(handle) => {
-            let mut guard = HANDLE.lock().unwrap();
-            *guard = Some(handle);
+            set_handle(Some(handle));
+            FfiError::Success as c_int
+        }
+        Err(e) => {
+            log::error!("Failed to initialize database: {}", e);
+            FfiError::InitFailed as c_int
+        }
+    }
+}
+
+/// Initialize the dictionary database with settings
+///
+/// # Safety
+///
+/// `db_path` and `config_json` must be valid null-terminated C strings.
+/// `config_json` is a JSON `DictConfig`; omitted fields take their defaults.
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code on failure.
+#[no_mangle]
+pub unsafe extern "C" fn dict_init_with_config(
+    db_path: *const c_char,
+    config_json: *const c_char,
+) -> c_int {
+    if db_path.is_null() || config_json.is_null() {
+        return FfiError::NullPointer as c_int;
+    }
+
+    let (path, config_str) = match (
+        CStr::from_ptr(db_path).to_str(),
+        CStr::from_ptr(config_json).to_str(),
+    ) {
+        (Ok(path), Ok(config)) => (path, config),
+        _ => return FfiError::InvalidUtf8 as c_int,
+    };
+
+    let config: DictConfig = match serde_json::from_str(config_str) {
+        Ok(config) => config,
+        Err(e) => {
+            log::error!("Invalid dictionary config: {}", e);
+            return FfiError::JsonFailed as c_int;
+        }
+    };
+
+    match init_with_config(path, config) {
+        Ok(handle) => {
+            set_handle(Some(handle));
             FfiError::Success as c_int
         }
         Err(e) => {
@@ -82,7 +183,9 @@ pub unsafe extern "C" fn dict_init(db_path: *const c_char) -> c_int {
 ///
 /// # Returns
 ///
-/// 0 on success, non-zero error code on failure.
+/// 0 on success, non-zero error code on failure: `FfiError::FtsCorrupt`,
+/// `Interrupted` or `InvalidQuery` where the cause is known, otherwise
+/// `SearchFailed`.
 /// On success, `*out_json` will be set to a JSON array of search results.
 #[no_mangle]
 pub unsafe extern "C" fn dict_search(
@@ -106,7 +209,13 @@ pub unsafe extern "C" fn dict_search(
         None => return FfiError::NotInitialized as c_int,
     };
 
-    let results = search_with_offset(handle, query_str, limit as u32, offset as u32);
+    let results = match try_search_with_offset(handle, query_str, limit as u32, offset as u32) {
+        Ok(results) => results,
+        Err(e) => {
+            log::error!("Search failed: {}", e);
+            return FfiError::from_search(&e) as c_int;
+        }
+    };
 
     // Serialize results to JSON
     let json = match serde_json::to_string(&results) {
@@ -124,6 +233,27 @@ pub unsafe extern "C" fn dict_search(
     FfiError::Success as c_int
 }
 
+/// Stop the query running on the dictionary, from another thread
+///
+/// Does not wait for `HANDLE`, which the running query holds: a search
+/// then fails with `FfiError::Interrupted`. Call it when the user has
+/// typed on, before starting the next search. Does nothing when no query
+/// is running.
+///
+/// # Returns
+///
+/// 0 on success, `NotInitialized` if no dictionary is open.
+#[no_mangle]
+pub extern "C" fn dict_interrupt() -> c_int {
+    match INTERRUPT.lock().unwrap().as_ref() {
+        Some(interrupt) => {
+            interrupt.interrupt();
+            FfiError::Success as c_int
+        }
+        None => FfiError::NotInitialized as c_int,
+    }
+}
+
 /// Get the full definition for a word by ID
 ///
 /// # Safety
@@ -168,180 +298,2012 @@ pub unsafe extern "C" fn dict_get_definition(
     FfiError::Success as c_int
 }
 
-/// Free a string returned by dict_search or dict_get_definition
+/// Find text in the senses of one entry, for find-in-page
 ///
 /// # Safety
 ///
-/// `ptr` must be a pointer returned by a dict_* function, or null.
-#[no_mangle]
-pub unsafe extern "C" fn dict_free_string(ptr: *mut c_char) {
-    if !ptr.is_null() {
-        drop(CString::from_raw(ptr));
-    }
-}
-
-/// Close the dictionary and free resources
+/// - `needle` must be a valid null-terminated C string
+/// - `out_json` must be a valid pointer to store the result
+/// - The caller is responsible for freeing the returned string with `dict_free_string`
 ///
 /// # Returns
 ///
-/// 0 on success.
+/// 0 on success, non-zero error code on failure.
+/// On success, `*out_json` will be set to a JSON array of matches, each
+/// with its `sense_index`, `definition_id`, `example` (absent for the
+/// gloss) and UTF-8 byte `start`/`end` offsets (see `db::find_in_entry`).
 #[no_mangle]
-pub extern "C" fn dict_close() -> c_int {
-    let mut guard = HANDLE.lock().unwrap();
-    *guard = None;
-    FfiError::Success as c_int
+pub unsafe extern "C" fn dict_find_in_entry(
+    word_id: c_longlong,
+    needle: *const c_char,
+    out_json: *mut *mut c_char,
+) -> c_int {
+    if needle.is_null() || out_json.is_null() {
+        return FfiError::NullPointer as c_int;
+    }
+
+    let needle_str = match CStr::from_ptr(needle).to_str() {
+        Ok(s) => s,
+        Err(_) => return FfiError::InvalidUtf8 as c_int,
+    };
+
+    let guard = HANDLE.lock().unwrap();
+    let handle = match guard.as_ref() {
+        Some(h) => h,
+        None => return FfiError::NotInitialized as c_int,
+    };
+
+    let matches = match db::find_in_entry(handle, word_id, needle_str) {
+        Ok(m) => m,
+        Err(e) => {
+            log::error!("Find in entry failed: {}", e);
+            return FfiError::SearchFailed as c_int;
+        }
+    };
+
+    match serde_json::to_string(&matches).map(CString::new) {
+        Ok(Ok(json)) => {
+            *out_json = json.into_raw();
+            FfiError::Success as c_int
+        }
+        _ => FfiError::JsonFailed as c_int,
+    }
 }
 
-/// Get the library version
+/// Get every entry for a headword, grouped by etymology
 ///
 /// # Safety
 ///
-/// Returns a pointer to a static string. Do not free this pointer.
+/// - `word` must be a valid null-terminated C string
+/// - `out_json` must be a valid pointer to store the result
+/// - The caller is responsible for freeing the returned string with `dict_free_string`
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code on failure.
+/// On success, `*out_json` will be set to a JSON array of etymology
+/// groups, each with its `etymology` text and `entries` (full definitions
+/// with their `word_id`); empty for an unknown word.
 #[no_mangle]
-pub extern "C" fn dict_version() -> *const c_char {
-    // Include version from Cargo.toml at compile time
-    static VERSION: &[u8] = concat!(env!("CARGO_PKG_VERSION"), "\0").as_bytes();
-    VERSION.as_ptr() as *const c_char
-}
+pub unsafe extern "C" fn dict_get_definitions_for_word(
+    word: *const c_char,
+    out_json: *mut *mut c_char,
+) -> c_int {
+    if word.is_null() || out_json.is_null() {
+        return FfiError::NullPointer as c_int;
+    }
 
-// ============================================================================
-// JNI bindings for Android
-// ============================================================================
+    let word_str = match CStr::from_ptr(word).to_str() {
+        Ok(s) => s,
+        Err(_) => return FfiError::InvalidUtf8 as c_int,
+    };
 
-#[cfg(target_os = "android")]
-mod android {
-    use std::ptr;
+    let guard = HANDLE.lock().unwrap();
+    let handle = match guard.as_ref() {
+        Some(h) => h,
+        None => return FfiError::NotInitialized as c_int,
+    };
 
-    use jni::objects::{JClass, JString};
-    use jni::sys::{jint, jlong, jstring};
-    use jni::JNIEnv;
+    let groups = match db::get_definitions_for_word(handle, word_str, &DefinitionOptions::default())
+    {
+        Ok(groups) => groups,
+        Err(e) => {
+            log::error!("Loading the entries for {:?} failed: {}", word_str, e);
+            return FfiError::SearchFailed as c_int;
+        }
+    };
 
-    use super::*;
+    match serde_json::to_string(&groups).map(CString::new) {
+        Ok(Ok(s)) => {
+            *out_json = s.into_raw();
+            FfiError::Success as c_int
+        }
+        _ => FfiError::JsonFailed as c_int,
+    }
+}
 
-    // Re-export android_logger for use in this module
-    use android_logger;
+/// Get an entry of the user database by ID
+///
+/// For search results whose `origin` is "user".
+///
+/// # Safety
+///
+/// - `out_json` must be a valid pointer to store the result
+/// - The caller is responsible for freeing the returned string with `dict_free_string`
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code on failure.
+/// On success, `*out_json` will be set to a JSON object with the entry, or
+/// "null" if there is no such entry or no user database.
+#[no_mangle]
+pub unsafe extern "C" fn dict_get_user_definition(
+    word_id: c_longlong,
+    out_json: *mut *mut c_char,
+) -> c_int {
+    if out_json.is_null() {
+        return FfiError::NullPointer as c_int;
+    }
 
-    /// JNI: Initialize the dictionary
-    ///
-    /// Kotlin signature: external fun init(dbPath: String): Int
-    #[no_mangle]
-    pub extern "system" fn Java_org_example_dictapp_DictCore_init(
-        mut env: JNIEnv,
-        _class: JClass,
-        db_path: JString,
-    ) -> jint {
-        let path: String = match env.get_string(&db_path) {
-            Ok(s) => s.into(),
-            Err(_) => return FfiError::InvalidUtf8 as jint,
-        };
+    let guard = HANDLE.lock().unwrap();
+    let handle = match guard.as_ref() {
+        Some(h) => h,
+        None => return FfiError::NotInitialized as c_int,
+    };
 
-        match init(&path) {
-            Ok(handle) => {
-                let mut guard = HANDLE.lock().unwrap();
-                *guard = Some(handle);
-                FfiError::Success as jint
-            }
-            Err(e) => {
-                log::error!("Failed to initialize database: {}", e);
-                FfiError::InitFailed as jint
-            }
+    let definition = match db::get_user_definition(handle, word_id) {
+        Ok(definition) => definition,
+        Err(e) => {
+            log::error!("Failed to get user entry {}: {}", word_id, e);
+            return FfiError::SearchFailed as c_int;
         }
-    }
+    };
 
-    /// JNI: Search for words
-    ///
-    /// Kotlin signature: external fun search(query: String, limit: Int, offset: Int): String
-    #[no_mangle]
-    pub extern "system" fn Java_org_example_dictapp_DictCore_search(
-        mut env: JNIEnv,
-        _class: JClass,
-        query: JString,
-        limit: jint,
-        offset: jint,
-    ) -> jstring {
-        let query_str: String = match env.get_string(&query) {
-            Ok(s) => s.into(),
-            Err(_) => return ptr::null_mut(),
-        };
+    match serde_json::to_string(&definition).map(CString::new) {
+        Ok(Ok(s)) => {
+            *out_json = s.into_raw();
+            FfiError::Success as c_int
+        }
+        _ => FfiError::JsonFailed as c_int,
+    }
+}
 
-        log::debug!(
-            "JNI search: query='{}', limit={}, offset={}",
-            query_str,
-            limit,
-            offset
-        );
+/// Render the full definition for a word as HTML, Markdown or plain text
+///
+/// # Safety
+///
+/// - `theme_json` must be null or a valid null-terminated C string holding
+///   a JSON `ThemeOptions` (null or omitted fields take their defaults)
+/// - `out_text` must be a valid pointer to store the result
+/// - The caller is responsible for freeing the returned string with `dict_free_string`
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code on failure. `format` is 0 for an
+/// HTML fragment, 1 for Markdown and 2 for plain text to read out with a
+/// screen reader (the theme is ignored). If the word is not found,
+/// returns success with `*out_text` set to null.
+#[no_mangle]
+pub unsafe extern "C" fn dict_render_definition(
+    word_id: c_longlong,
+    format: c_int,
+    theme_json: *const c_char,
+    out_text: *mut *mut c_char,
+) -> c_int {
+    if out_text.is_null() {
+        return FfiError::NullPointer as c_int;
+    }
 
-        let guard = HANDLE.lock().unwrap();
-        let handle = match guard.as_ref() {
-            Some(h) => h,
-            None => {
-                log::warn!("JNI search: handle not initialized");
-                return ptr::null_mut();
-            }
+    let theme: ThemeOptions = if theme_json.is_null() {
+        ThemeOptions::default()
+    } else {
+        let theme_str = match CStr::from_ptr(theme_json).to_str() {
+            Ok(s) => s,
+            Err(_) => return FfiError::InvalidUtf8 as c_int,
         };
+        match serde_json::from_str(theme_str) {
+            Ok(theme) => theme,
+            Err(_) => return FfiError::JsonFailed as c_int,
+        }
+    };
 
-        let results = search_with_offset(handle, &query_str, limit as u32, offset as u32);
-
-        log::debug!(
-            "JNI search: query='{}' returned {} results, first IDs: {:?}",
-            query_str,
-            results.len(),
-            results.iter().take(3).map(|r| r.id).collect::<Vec<_>>()
-        );
+    let guard = HANDLE.lock().unwrap();
+    let handle = match guard.as_ref() {
+        Some(h) => h,
+        None => return FfiError::NotInitialized as c_int,
+    };
 
-        let json = match serde_json::to_string(&results) {
-            Ok(j) => j,
-            Err(e) => {
-                log::error!("JNI search: JSON serialization failed: {}", e);
-                return ptr::null_mut();
-            }
-        };
+    let Some(definition) = get_definition_with_options(handle, word_id, &RENDER_OPTIONS) else {
+        *out_text = ptr::null_mut();
+        return FfiError::Success as c_int;
+    };
+    let text = match format {
+        1 => render::definition_markdown(&definition, &theme),
+        2 => render::definition_plaintext(&definition, render::Verbosity::Standard),
+        _ => render::definition_html(&definition, &theme),
+    };
 
-        match env.new_string(&json) {
-            Ok(s) => s.into_raw(),
-            Err(e) => {
-                log::error!("JNI search: failed to create Java string: {:?}", e);
-                ptr::null_mut()
-            }
+    match CString::new(text) {
+        Ok(s) => {
+            *out_text = s.into_raw();
+            FfiError::Success as c_int
         }
+        Err(_) => FfiError::JsonFailed as c_int,
     }
+}
 
-    /// JNI: Get full definition
+/// Get synonyms and antonyms of a word, grouped by sense
+///
+/// # Safety
+///
+/// - `word` and `lang` must be valid null-terminated C strings
+/// - `out_json` must be a valid pointer to store the result
+/// - The caller is responsible for freeing the returned string with `dict_free_string`
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code on failure.
+/// On success, `*out_json` will be set to a JSON array of sense groups
+/// (empty if the word has no synonyms or antonyms).
+#[no_mangle]
+pub unsafe extern "C" fn dict_thesaurus(
+    word: *const c_char,
+    lang: *const c_char,
+    out_json: *mut *mut c_char,
+) -> c_int {
+    if word.is_null() || lang.is_null() || out_json.is_null() {
+        return FfiError::NullPointer as c_int;
+    }
+
+    let (word_str, lang_str) = match (CStr::from_ptr(word).to_str(), CStr::from_ptr(lang).to_str())
+    {
+        (Ok(w), Ok(l)) => (w, l),
+        _ => return FfiError::InvalidUtf8 as c_int,
+    };
+
+    let guard = HANDLE.lock().unwrap();
+    let handle = match guard.as_ref() {
+        Some(h) => h,
+        None => return FfiError::NotInitialized as c_int,
+    };
+
+    let groups = match thesaurus::lookup(handle, word_str, lang_str) {
+        Ok(g) => g,
+        Err(e) => {
+            log::error!("Thesaurus lookup failed: {}", e);
+            return FfiError::SearchFailed as c_int;
+        }
+    };
+
+    let json = match serde_json::to_string(&groups) {
+        Ok(j) => j,
+        Err(_) => return FfiError::JsonFailed as c_int,
+    };
+
+    let c_string = match CString::new(json) {
+        Ok(s) => s,
+        Err(_) => return FfiError::JsonFailed as c_int,
+    };
+
+    *out_json = c_string.into_raw();
+    FfiError::Success as c_int
+}
+
+/// Get candidate translations of a word between two languages, through
+/// English entries where neither is English (see `translate::pivot`)
+///
+/// # Safety
+///
+/// - `word`, `from_lang` and `to_lang` must be valid null-terminated C strings
+/// - `out_json` must be a valid pointer to store the result
+/// - The caller is responsible for freeing the returned string with `dict_free_string`
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code on failure.
+/// On success, `*out_json` will be set to a JSON array of candidates, each
+/// marked `pivoted` when it came through English.
+#[no_mangle]
+pub unsafe extern "C" fn dict_translate_pivot(
+    word: *const c_char,
+    from_lang: *const c_char,
+    to_lang: *const c_char,
+    out_json: *mut *mut c_char,
+) -> c_int {
+    if word.is_null() || from_lang.is_null() || to_lang.is_null() || out_json.is_null() {
+        return FfiError::NullPointer as c_int;
+    }
+
+    let (word_str, from_str, to_str) = match (
+        CStr::from_ptr(word).to_str(),
+        CStr::from_ptr(from_lang).to_str(),
+        CStr::from_ptr(to_lang).to_str(),
+    ) {
+        (Ok(w), Ok(f), Ok(t)) => (w, f, t),
+        _ => return FfiError::InvalidUtf8 as c_int,
+    };
+
+    let guard = HANDLE.lock().unwrap();
+    let handle = match guard.as_ref() {
+        Some(h) => h,
+        None => return FfiError::NotInitialized as c_int,
+    };
+
+    let candidates = match translate::pivot(handle, word_str, from_str, to_str) {
+        Ok(c) => c,
+        Err(e) => {
+            log::error!("Pivot translation failed: {}", e);
+            return FfiError::SearchFailed as c_int;
+        }
+    };
+
+    match serde_json::to_string(&candidates).map(CString::new) {
+        Ok(Ok(json)) => {
+            *out_json = json.into_raw();
+            FfiError::Success as c_int
+        }
+        _ => FfiError::JsonFailed as c_int,
+    }
+}
+
+/// Get the entries related to an entry, as a graph to draw around it
+///
+/// # Safety
+///
+/// - `out_json` must be a valid pointer to store the result
+/// - The caller is responsible for freeing the returned string with `dict_free_string`
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code on failure.
+/// On success, `*out_json` will be set to a JSON object with the `nodes`
+/// within `depth` steps of `word_id` and the `edges` between them, over
+/// all kinds of relation (empty for an unknown word).
+#[no_mangle]
+pub unsafe extern "C" fn dict_graph_neighborhood(
+    word_id: c_longlong,
+    depth: c_int,
+    out_json: *mut *mut c_char,
+) -> c_int {
+    if out_json.is_null() {
+        return FfiError::NullPointer as c_int;
+    }
+
+    let guard = HANDLE.lock().unwrap();
+    let handle = match guard.as_ref() {
+        Some(h) => h,
+        None => return FfiError::NotInitialized as c_int,
+    };
+
+    let depth = depth.max(0) as u32;
+    let graph = match db::get_graph_neighborhood(handle, word_id, depth, RelationKind::ALL) {
+        Ok(g) => g,
+        Err(e) => {
+            log::error!("Graph lookup failed: {}", e);
+            return FfiError::SearchFailed as c_int;
+        }
+    };
+
+    let json = match serde_json::to_string(&graph) {
+        Ok(j) => j,
+        Err(_) => return FfiError::JsonFailed as c_int,
+    };
+
+    let c_string = match CString::new(json) {
+        Ok(s) => s,
+        Err(_) => return FfiError::JsonFailed as c_int,
+    };
+
+    *out_json = c_string.into_raw();
+    FfiError::Success as c_int
+}
+
+/// Get the words closest in meaning to an entry, from the related words
+/// precomputed at build time
+///
+/// # Safety
+///
+/// - `out_json` must be a valid pointer to store the result
+/// - The caller is responsible for freeing the returned string with `dict_free_string`
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code on failure.
+/// On success, `*out_json` will be set to a JSON array of up to `k` search
+/// results, closest first (empty for dictionaries built without them).
+#[no_mangle]
+pub unsafe extern "C" fn dict_semantically_related(
+    word_id: c_longlong,
+    k: c_int,
+    out_json: *mut *mut c_char,
+) -> c_int {
+    if out_json.is_null() {
+        return FfiError::NullPointer as c_int;
+    }
+
+    let guard = HANDLE.lock().unwrap();
+    let handle = match guard.as_ref() {
+        Some(h) => h,
+        None => return FfiError::NotInitialized as c_int,
+    };
+
+    let related = match db::get_semantically_related(handle, word_id, k.max(0) as u32) {
+        Ok(r) => r,
+        Err(e) => {
+            log::error!("Related words lookup failed: {}", e);
+            return FfiError::SearchFailed as c_int;
+        }
+    };
+
+    match serde_json::to_string(&related).map(CString::new) {
+        Ok(Ok(s)) => {
+            *out_json = s.into_raw();
+            FfiError::Success as c_int
+        }
+        _ => FfiError::JsonFailed as c_int,
+    }
+}
+
+/// Complete a typed prefix to headwords, for autocompletion
+///
+/// # Safety
+///
+/// - `prefix` must be a valid null-terminated C string
+/// - `out_words` and `out_count` must be valid pointers to store the result
+/// - The caller is responsible for freeing the array with `dict_free_string_array`
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code on failure.
+/// On success, `*out_words` points to `*out_count` null-terminated strings,
+/// best completion first (null when there are none).
+#[no_mangle]
+pub unsafe extern "C" fn dict_complete(
+    prefix: *const c_char,
+    n: c_int,
+    out_words: *mut *mut *mut c_char,
+    out_count: *mut c_int,
+) -> c_int {
+    if prefix.is_null() || out_words.is_null() || out_count.is_null() {
+        return FfiError::NullPointer as c_int;
+    }
+
+    let prefix_str = match CStr::from_ptr(prefix).to_str() {
+        Ok(s) => s,
+        Err(_) => return FfiError::InvalidUtf8 as c_int,
+    };
+
+    let guard = HANDLE.lock().unwrap();
+    let handle = match guard.as_ref() {
+        Some(h) => h,
+        None => return FfiError::NotInitialized as c_int,
+    };
+
+    let words = match search::complete(handle, prefix_str, n.max(0) as u32) {
+        Ok(w) => w,
+        Err(e) => {
+            log::error!("Completion failed: {}", e);
+            return FfiError::SearchFailed as c_int;
+        }
+    };
+
+    // Headwords never contain NUL, but skip rather than fail if one does
+    let strings: Vec<*mut c_char> = words
+        .into_iter()
+        .filter_map(|w| CString::new(w).ok())
+        .map(CString::into_raw)
+        .collect();
+
+    *out_count = strings.len() as c_int;
+    *out_words = if strings.is_empty() {
+        ptr::null_mut()
+    } else {
+        Box::into_raw(strings.into_boxed_slice()) as *mut *mut c_char
+    };
+    FfiError::Success as c_int
+}
+
+/// Strip content from the open dictionary and vacuum it
+///
+/// Blocks all other dict_* calls until done, which can take a while for a
+/// large database.
+///
+/// # Safety
+///
+/// - `options_json` must be a valid null-terminated C string holding a JSON
+///   `CompactOptions` (omitted fields remove nothing)
+/// - `out_json` must be a valid pointer to store the result
+/// - The caller is responsible for freeing the returned string with `dict_free_string`
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code on failure.
+/// On success, `*out_json` will be set to a JSON `CompactStats`.
+#[no_mangle]
+pub unsafe extern "C" fn dict_compact(
+    options_json: *const c_char,
+    out_json: *mut *mut c_char,
+) -> c_int {
+    if options_json.is_null() || out_json.is_null() {
+        return FfiError::NullPointer as c_int;
+    }
+
+    let options_str = match CStr::from_ptr(options_json).to_str() {
+        Ok(s) => s,
+        Err(_) => return FfiError::InvalidUtf8 as c_int,
+    };
+    let options: CompactOptions = match serde_json::from_str(options_str) {
+        Ok(options) => options,
+        Err(_) => return FfiError::JsonFailed as c_int,
+    };
+
+    let guard = HANDLE.lock().unwrap();
+    let handle = match guard.as_ref() {
+        Some(h) => h,
+        None => return FfiError::NotInitialized as c_int,
+    };
+
+    let stats = match db::compact(handle, &options) {
+        Ok(stats) => stats,
+        Err(e) => {
+            log::error!("Compaction failed: {}", e);
+            return FfiError::SearchFailed as c_int;
+        }
+    };
+
+    match serde_json::to_string(&stats).map(CString::new) {
+        Ok(Ok(s)) => {
+            *out_json = s.into_raw();
+            FfiError::Success as c_int
+        }
+        _ => FfiError::JsonFailed as c_int,
+    }
+}
+
+/// Write the entries of the open dictionary matching some filters to a new
+/// database, e.g. an offline travel pack
+///
+/// Blocks all other dict_* calls until done.
+///
+/// # Safety
+///
+/// - `dest_path` must be a valid null-terminated C string naming a file that
+///   does not exist yet
+/// - `filters_json` must be a valid null-terminated C string holding a JSON
+///   `SubsetFilters` (omitted fields keep everything)
+/// - `out_json` must be a valid pointer to store the result
+/// - The caller is responsible for freeing the returned string with `dict_free_string`
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code on failure.
+/// On success, `*out_json` will be set to a JSON `SubsetStats`.
+#[no_mangle]
+pub unsafe extern "C" fn dict_extract_subset(
+    dest_path: *const c_char,
+    filters_json: *const c_char,
+    out_json: *mut *mut c_char,
+) -> c_int {
+    if dest_path.is_null() || filters_json.is_null() || out_json.is_null() {
+        return FfiError::NullPointer as c_int;
+    }
+
+    let (dest, filters_str) = match (
+        CStr::from_ptr(dest_path).to_str(),
+        CStr::from_ptr(filters_json).to_str(),
+    ) {
+        (Ok(dest), Ok(filters)) => (dest, filters),
+        _ => return FfiError::InvalidUtf8 as c_int,
+    };
+    let filters: SubsetFilters = match serde_json::from_str(filters_str) {
+        Ok(filters) => filters,
+        Err(_) => return FfiError::JsonFailed as c_int,
+    };
+
+    let guard = HANDLE.lock().unwrap();
+    let handle = match guard.as_ref() {
+        Some(h) => h,
+        None => return FfiError::NotInitialized as c_int,
+    };
+
+    let stats = match export::extract_subset(handle, std::path::Path::new(dest), &filters) {
+        Ok(stats) => stats,
+        Err(e) => {
+            log::error!("Subset extraction failed: {}", e);
+            return FfiError::SearchFailed as c_int;
+        }
+    };
+
+    match serde_json::to_string(&stats).map(CString::new) {
+        Ok(Ok(s)) => {
+            *out_json = s.into_raw();
+            FfiError::Success as c_int
+        }
+        _ => FfiError::JsonFailed as c_int,
+    }
+}
+
+/// Read the hot indexes of the open dictionary into the page cache
+///
+/// Call once after `dict_init`, e.g. during the splash screen, so the first
+/// search is not slowed down by disk reads. The IO is bounded.
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code on failure.
+#[no_mangle]
+pub extern "C" fn dict_warm_up() -> c_int {
+    let guard = HANDLE.lock().unwrap();
+    let handle = match guard.as_ref() {
+        Some(h) => h,
+        None => return FfiError::NotInitialized as c_int,
+    };
+
+    match db::warm_up(handle) {
+        Ok(bytes) => {
+            log::debug!("Warm-up read {} bytes", bytes);
+            FfiError::Success as c_int
+        }
+        Err(e) => {
+            log::error!("Warm-up failed: {}", e);
+            FfiError::SearchFailed as c_int
+        }
+    }
+}
+
+/// Load the entries the UI expects to be opened next, e.g. the visible
+/// search results (see `db::prefetch_definitions`)
+///
+/// # Safety
+///
+/// - `word_ids` must point to `count` word ids, or be null when `count` is 0
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code on failure.
+#[no_mangle]
+pub unsafe extern "C" fn dict_prefetch_definitions(
+    word_ids: *const c_longlong,
+    count: c_int,
+) -> c_int {
+    if word_ids.is_null() && count > 0 {
+        return FfiError::NullPointer as c_int;
+    }
+    let word_ids: &[i64] = if count > 0 {
+        std::slice::from_raw_parts(word_ids, count as usize)
+    } else {
+        &[]
+    };
+
+    let guard = HANDLE.lock().unwrap();
+    let handle = match guard.as_ref() {
+        Some(h) => h,
+        None => return FfiError::NotInitialized as c_int,
+    };
+
+    match db::prefetch_definitions(handle, word_ids) {
+        Ok(loaded) => {
+            log::debug!("Prefetched {} definitions", loaded);
+            FfiError::Success as c_int
+        }
+        Err(e) => {
+            log::error!("Prefetch failed: {}", e);
+            FfiError::SearchFailed as c_int
+        }
+    }
+}
+
+/// Report what the SQLite library under the open dictionary supports
+///
+/// # Safety
+///
+/// - `out_json` must be a valid pointer to store the result
+/// - The caller is responsible for freeing the returned string with `dict_free_string`
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code on failure.
+/// On success, `*out_json` will be set to a JSON `Capabilities`, e.g.
+/// `{"fts5":false}` where search falls back to substring matching.
+#[no_mangle]
+pub unsafe extern "C" fn dict_capabilities(out_json: *mut *mut c_char) -> c_int {
+    if out_json.is_null() {
+        return FfiError::NullPointer as c_int;
+    }
+
+    let guard = HANDLE.lock().unwrap();
+    let handle = match guard.as_ref() {
+        Some(h) => h,
+        None => return FfiError::NotInitialized as c_int,
+    };
+
+    match serde_json::to_string(&handle.capabilities()).map(CString::new) {
+        Ok(Ok(s)) => {
+            *out_json = s.into_raw();
+            FfiError::Success as c_int
+        }
+        _ => FfiError::JsonFailed as c_int,
+    }
+}
+
+/// Check whether a dictionary database can be used, without opening it
+///
+/// Doesn't need `dict_init`, so it works on a downloaded file before the
+/// app switches to it.
+///
+/// # Safety
+///
+/// - `db_path` must be a valid null-terminated C string
+/// - `out_json` must be a valid pointer to store the result
+/// - The caller is responsible for freeing the returned string with `dict_free_string`
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code if the file could not be read.
+/// On success, `*out_json` will be set to a JSON `CompatibilityReport`;
+/// a non-empty `problems` list means the app needs an update.
+#[no_mangle]
+pub unsafe extern "C" fn dict_check_db(
+    db_path: *const c_char,
+    out_json: *mut *mut c_char,
+) -> c_int {
+    if db_path.is_null() || out_json.is_null() {
+        return FfiError::NullPointer as c_int;
+    }
+
+    let path = match CStr::from_ptr(db_path).to_str() {
+        Ok(s) => s,
+        Err(_) => return FfiError::InvalidUtf8 as c_int,
+    };
+
+    let report = match db::check_compatibility(path) {
+        Ok(report) => report,
+        Err(e) => {
+            log::error!("Compatibility check failed: {}", e);
+            return FfiError::InitFailed as c_int;
+        }
+    };
+
+    match serde_json::to_string(&report).map(CString::new) {
+        Ok(Ok(s)) => {
+            *out_json = s.into_raw();
+            FfiError::Success as c_int
+        }
+        _ => FfiError::JsonFailed as c_int,
+    }
+}
+
+/// Free an array returned by dict_complete
+///
+/// # Safety
+///
+/// `words` and `count` must be exactly as returned by `dict_complete`;
+/// `words` may be null.
+#[no_mangle]
+pub unsafe extern "C" fn dict_free_string_array(words: *mut *mut c_char, count: c_int) {
+    if words.is_null() {
+        return;
+    }
+    let strings = Box::from_raw(std::ptr::slice_from_raw_parts_mut(words, count as usize));
+    for &s in strings.iter() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Progress callback for `dict_download_database`
+///
+/// Called with the phase (0 = downloading, 1 = verifying, 2 = decompressing),
+/// bytes done and total for that phase, and the caller's `user_data`.
+#[cfg(feature = "download")]
+pub type DictDownloadCallback = Option<
+    unsafe extern "C" fn(
+        phase: c_int,
+        bytes_done: u64,
+        bytes_total: u64,
+        user_data: *mut std::ffi::c_void,
+    ),
+>;
+
+/// Download the latest database for a language and install it
+///
+/// Fetches `{base_url}/{language}-latest.json`, then the database it names,
+/// and writes the verified, decompressed database to `dest_path`. Runs on
+/// the calling thread; call it again after a failure to resume.
+///
+/// # Safety
+///
+/// - `base_url`, `language` and `dest_path` must be valid null-terminated C strings
+/// - `callback` may be null; it is called on the calling thread with `user_data`
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code on failure.
+#[cfg(feature = "download")]
+#[no_mangle]
+pub unsafe extern "C" fn dict_download_database(
+    base_url: *const c_char,
+    language: *const c_char,
+    dest_path: *const c_char,
+    callback: DictDownloadCallback,
+    user_data: *mut std::ffi::c_void,
+) -> c_int {
+    if base_url.is_null() || language.is_null() || dest_path.is_null() {
+        return FfiError::NullPointer as c_int;
+    }
+
+    let (base_str, lang_str, dest_str) = match (
+        CStr::from_ptr(base_url).to_str(),
+        CStr::from_ptr(language).to_str(),
+        CStr::from_ptr(dest_path).to_str(),
+    ) {
+        (Ok(b), Ok(l), Ok(d)) => (b, l, d),
+        _ => return FfiError::InvalidUtf8 as c_int,
+    };
+
+    download_latest(base_str, lang_str, dest_str, |p| {
+        if let Some(callback) = callback {
+            callback(p.phase as c_int, p.bytes_done, p.bytes_total, user_data);
+        }
+    }) as c_int
+}
+
+/// Fetch the manifest and database, mapping failures to an error code
+#[cfg(feature = "download")]
+fn download_latest(
+    base_url: &str,
+    language: &str,
+    dest: &str,
+    progress: impl FnMut(&download::DownloadProgress),
+) -> FfiError {
+    let result = download::fetch_manifest(base_url, language).and_then(|manifest| {
+        download::download_database(base_url, &manifest, std::path::Path::new(dest), progress)
+    });
+    match result {
+        Ok(()) => FfiError::Success,
+        Err(e) => {
+            log::error!("Failed to download {} dictionary: {}", language, e);
+            download_error(&e)
+        }
+    }
+}
+
+/// Error code for a failed download or manifest fetch
+#[cfg(feature = "download")]
+fn download_error(e: &crate::Error) -> FfiError {
+    match e {
+        crate::Error::ChecksumMismatch { .. } => FfiError::ChecksumMismatch,
+        crate::Error::Json(_) => FfiError::JsonFailed,
+        _ => FfiError::DownloadFailed,
+    }
+}
+
+/// Check whether a newer database than the open one has been published
+///
+/// # Safety
+///
+/// - `manifest_url` must be a valid null-terminated C string, the URL of a
+///   `{language}-latest.json` manifest
+/// - `out_json` must be a valid pointer to store the result
+/// - The caller is responsible for freeing the returned string with `dict_free_string`
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code on failure.
+/// On success, `*out_json` will be set to a JSON `AvailableUpdate` (the
+/// installed version and the published manifest with its sizes), or
+/// "null" if the open database is current.
+#[cfg(feature = "download")]
+#[no_mangle]
+pub unsafe extern "C" fn dict_check_update(
+    manifest_url: *const c_char,
+    out_json: *mut *mut c_char,
+) -> c_int {
+    if manifest_url.is_null() || out_json.is_null() {
+        return FfiError::NullPointer as c_int;
+    }
+
+    let url = match CStr::from_ptr(manifest_url).to_str() {
+        Ok(s) => s,
+        Err(_) => return FfiError::InvalidUtf8 as c_int,
+    };
+
+    let json = match check_update(url) {
+        Ok(json) => json,
+        Err(code) => return code as c_int,
+    };
+    match CString::new(json) {
+        Ok(s) => {
+            *out_json = s.into_raw();
+            FfiError::Success as c_int
+        }
+        Err(_) => FfiError::JsonFailed as c_int,
+    }
+}
+
+/// Check the open database for an update, as JSON
+#[cfg(feature = "download")]
+fn check_update(manifest_url: &str) -> std::result::Result<String, FfiError> {
+    // Read the metadata, then release the handle before going to the network
+    let installed = {
+        let guard = HANDLE.lock().unwrap();
+        let handle = guard.as_ref().ok_or(FfiError::NotInitialized)?;
+        crate::db::get_metadata(handle).map_err(|e| {
+            log::error!("Failed to read dictionary metadata: {}", e);
+            FfiError::SearchFailed
+        })?
+    };
+
+    let update = updates::check(manifest_url, &installed).map_err(|e| {
+        log::error!("Update check failed: {}", e);
+        download_error(&e)
+    })?;
+    serde_json::to_string(&update).map_err(|_| FfiError::JsonFailed)
+}
+
+/// Open the user database for bookmarks, creating it if necessary
+///
+/// # Safety
+///
+/// `path` must be a valid null-terminated C string.
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code on failure.
+#[no_mangle]
+pub unsafe extern "C" fn dict_user_open(path: *const c_char) -> c_int {
+    if path.is_null() {
+        return FfiError::NullPointer as c_int;
+    }
+
+    let path = match CStr::from_ptr(path).to_str() {
+        Ok(s) => s,
+        Err(_) => return FfiError::InvalidUtf8 as c_int,
+    };
+
+    match UserData::open(path) {
+        Ok(user) => {
+            *USER.lock().unwrap() = Some(user);
+            FfiError::Success as c_int
+        }
+        Err(e) => {
+            log::error!("Failed to open user database: {}", e);
+            FfiError::InitFailed as c_int
+        }
+    }
+}
+
+/// Close the user database
+///
+/// # Returns
+///
+/// 0 on success.
+#[no_mangle]
+pub extern "C" fn dict_user_close() -> c_int {
+    *USER.lock().unwrap() = None;
+    FfiError::Success as c_int
+}
+
+/// A sense's definition id passed over FFI, where 0 or less stands for
+/// the whole entry
+fn sense_id(definition_id: c_longlong) -> Option<i64> {
+    (definition_id > 0).then_some(definition_id)
+}
+
+/// Bookmark an entry, or one of its senses, with an optional note
+///
+/// # Safety
+///
+/// - `word` must be a valid null-terminated C string
+/// - `note` must be a valid null-terminated C string, or null for none
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code on failure. `definition_id` is the
+/// sense bookmarked, or 0 for the whole entry; `at` is Unix time.
+#[no_mangle]
+pub unsafe extern "C" fn dict_add_bookmark(
+    word_id: c_longlong,
+    word: *const c_char,
+    definition_id: c_longlong,
+    note: *const c_char,
+    at: c_longlong,
+) -> c_int {
+    if word.is_null() {
+        return FfiError::NullPointer as c_int;
+    }
+
+    let word_str = match CStr::from_ptr(word).to_str() {
+        Ok(s) => s,
+        Err(_) => return FfiError::InvalidUtf8 as c_int,
+    };
+    let note_str = if note.is_null() {
+        None
+    } else {
+        match CStr::from_ptr(note).to_str() {
+            Ok(s) => Some(s),
+            Err(_) => return FfiError::InvalidUtf8 as c_int,
+        }
+    };
+
+    let guard = USER.lock().unwrap();
+    let user = match guard.as_ref() {
+        Some(u) => u,
+        None => return FfiError::NotInitialized as c_int,
+    };
+
+    match user.add_bookmark(word_id, word_str, sense_id(definition_id), note_str, at) {
+        Ok(()) => FfiError::Success as c_int,
+        Err(e) => {
+            log::error!("Adding bookmark failed: {}", e);
+            FfiError::SearchFailed as c_int
+        }
+    }
+}
+
+/// Remove the bookmark of an entry, or of one of its senses
+///
+/// # Returns
+///
+/// 0 on success, also when there was no such bookmark; non-zero error
+/// code on failure. `definition_id` is 0 for the whole entry's bookmark.
+#[no_mangle]
+pub extern "C" fn dict_remove_bookmark(word_id: c_longlong, definition_id: c_longlong) -> c_int {
+    let guard = USER.lock().unwrap();
+    let user = match guard.as_ref() {
+        Some(u) => u,
+        None => return FfiError::NotInitialized as c_int,
+    };
+
+    match user.remove_bookmark(word_id, sense_id(definition_id)) {
+        Ok(_) => FfiError::Success as c_int,
+        Err(e) => {
+            log::error!("Removing bookmark failed: {}", e);
+            FfiError::SearchFailed as c_int
+        }
+    }
+}
+
+/// Get the most recent bookmarks, or with a positive `word_id` the
+/// bookmarks of that entry
+///
+/// # Safety
+///
+/// - `out_json` must be a valid pointer to store the result
+/// - The caller is responsible for freeing the returned string with `dict_free_string`
+///
+/// # Returns
+///
+/// 0 on success, non-zero error code on failure.
+/// On success, `*out_json` will be set to a JSON array of bookmarks, each
+/// with its `definition_id` (null for the whole entry) and `note`: up to
+/// `limit` latest first, or an entry's whole-entry bookmark first and then
+/// its senses'.
+#[no_mangle]
+pub unsafe extern "C" fn dict_get_bookmarks(
+    word_id: c_longlong,
+    limit: c_int,
+    out_json: *mut *mut c_char,
+) -> c_int {
+    if out_json.is_null() {
+        return FfiError::NullPointer as c_int;
+    }
+
+    let guard = USER.lock().unwrap();
+    let user = match guard.as_ref() {
+        Some(u) => u,
+        None => return FfiError::NotInitialized as c_int,
+    };
+
+    let bookmarks = if word_id > 0 {
+        user.entry_bookmarks(word_id)
+    } else {
+        user.bookmarks(limit.max(0) as u32)
+    };
+    let bookmarks = match bookmarks {
+        Ok(b) => b,
+        Err(e) => {
+            log::error!("Listing bookmarks failed: {}", e);
+            return FfiError::SearchFailed as c_int;
+        }
+    };
+
+    match serde_json::to_string(&bookmarks).map(CString::new) {
+        Ok(Ok(json)) => {
+            *out_json = json.into_raw();
+            FfiError::Success as c_int
+        }
+        _ => FfiError::JsonFailed as c_int,
+    }
+}
+
+/// Free a string returned by a dict_* function
+///
+/// # Safety
+///
+/// `ptr` must be a pointer returned by a dict_* function, or null.
+#[no_mangle]
+pub unsafe extern "C" fn dict_free_string(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        drop(CString::from_raw(ptr));
+    }
+}
+
+/// Close the dictionary and free resources
+///
+/// # Returns
+///
+/// 0 on success.
+#[no_mangle]
+pub extern "C" fn dict_close() -> c_int {
+    set_handle(None);
+    FfiError::Success as c_int
+}
+
+/// Get the library version
+///
+/// # Safety
+///
+/// Returns a pointer to a static string. Do not free this pointer.
+#[no_mangle]
+pub extern "C" fn dict_version() -> *const c_char {
+    // Include version from Cargo.toml at compile time
+    static VERSION: &[u8] = concat!(env!("CARGO_PKG_VERSION"), "\0").as_bytes();
+    VERSION.as_ptr() as *const c_char
+}
+
+// ============================================================================
+// JNI bindings for Android
+// ============================================================================
+
+#[cfg(target_os = "android")]
+mod android {
+    use std::ptr;
+
+    use jni::objects::{JClass, JLongArray, JObject, JString, JThrowable, JValue};
+    use jni::sys::{jint, jlong, jobjectArray, jstring};
+    use jni::JNIEnv;
+
+    use super::*;
+
+    // Re-export android_logger for use in this module
+    use android_logger;
+
+    /// JNI: Initialize the dictionary
+    ///
+    /// Kotlin signature: external fun init(dbPath: String): Int
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_init(
+        mut env: JNIEnv,
+        _class: JClass,
+        db_path: JString,
+    ) -> jint {
+        let path: String = match env.get_string(&db_path) {
+            Ok(s) => s.into(),
+            Err(_) => return FfiError::InvalidUtf8 as jint,
+        };
+
+        match init(&path) {
+            Ok(handle) => {
+                set_handle(Some(handle));
+                FfiError::Success as jint
+            }
+            Err(e) => {
+                log::error!("Failed to initialize database: {}", e);
+                FfiError::InitFailed as jint
+            }
+        }
+    }
+
+    /// JNI: Initialize the dictionary with a JSON `DictConfig`
+    ///
+    /// Kotlin signature: external fun initWithConfig(dbPath: String, configJson: String): Int
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_initWithConfig(
+        mut env: JNIEnv,
+        _class: JClass,
+        db_path: JString,
+        config_json: JString,
+    ) -> jint {
+        let path: String = match env.get_string(&db_path) {
+            Ok(s) => s.into(),
+            Err(_) => return FfiError::InvalidUtf8 as jint,
+        };
+        let config_str: String = match env.get_string(&config_json) {
+            Ok(s) => s.into(),
+            Err(_) => return FfiError::InvalidUtf8 as jint,
+        };
+
+        let config: DictConfig = match serde_json::from_str(&config_str) {
+            Ok(config) => config,
+            Err(e) => {
+                log::error!("Invalid dictionary config: {}", e);
+                return FfiError::JsonFailed as jint;
+            }
+        };
+
+        match init_with_config(&path, config) {
+            Ok(handle) => {
+                set_handle(Some(handle));
+                FfiError::Success as jint
+            }
+            Err(e) => {
+                log::error!("Failed to initialize database: {}", e);
+                FfiError::InitFailed as jint
+            }
+        }
+    }
+
+    /// Throw a `DictException` carrying `code` into the calling JVM
+    ///
+    /// The JNI function must return right after, without calling into Java.
+    fn throw_dict_exception(env: &mut JNIEnv, code: FfiError, message: &str) {
+        let thrown = env.new_string(message).and_then(|message| {
+            let exception = env.new_object(
+                "org/example/dictapp/DictException",
+                "(ILjava/lang/String;)V",
+                &[JValue::Int(code as jint), JValue::Object(&message)],
+            )?;
+            env.throw(JThrowable::from(exception))
+        });
+        if let Err(e) = thrown {
+            log::error!("Failed to throw DictException: {:?}", e);
+        }
+    }
+
+    /// JNI: Search for words
+    ///
+    /// Throws `DictException` with the `FfiError` code if the search fails.
+    ///
+    /// Kotlin signature: external fun search(query: String, limit: Int, offset: Int): String
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_search(
+        mut env: JNIEnv,
+        _class: JClass,
+        query: JString,
+        limit: jint,
+        offset: jint,
+    ) -> jstring {
+        let query_str: String = match env.get_string(&query) {
+            Ok(s) => s.into(),
+            Err(_) => return ptr::null_mut(),
+        };
+
+        log::debug!(
+            "JNI search: query='{}', limit={}, offset={}",
+            query_str,
+            limit,
+            offset
+        );
+
+        let guard = HANDLE.lock().unwrap();
+        let handle = match guard.as_ref() {
+            Some(h) => h,
+            None => {
+                log::warn!("JNI search: handle not initialized");
+                return ptr::null_mut();
+            }
+        };
+
+        let results = match try_search_with_offset(handle, &query_str, limit as u32, offset as u32)
+        {
+            Ok(results) => results,
+            Err(e) => {
+                log::error!("JNI search: search failed: {}", e);
+                throw_dict_exception(&mut env, FfiError::from_search(&e), &e.to_string());
+                return ptr::null_mut();
+            }
+        };
+
+        log::debug!(
+            "JNI search: query='{}' returned {} results, first IDs: {:?}",
+            query_str,
+            results.len(),
+            results.iter().take(3).map(|r| r.id).collect::<Vec<_>>()
+        );
+
+        let json = match serde_json::to_string(&results) {
+            Ok(j) => j,
+            Err(e) => {
+                log::error!("JNI search: JSON serialization failed: {}", e);
+                return ptr::null_mut();
+            }
+        };
+
+        match env.new_string(&json) {
+            Ok(s) => s.into_raw(),
+            Err(e) => {
+                log::error!("JNI search: failed to create Java string: {:?}", e);
+                ptr::null_mut()
+            }
+        }
+    }
+
+    /// JNI: Render the full definition as HTML (format 0), Markdown (format 1)
+    /// or plain text for TalkBack (format 2)
+    ///
+    /// Kotlin signature: external fun renderDefinition(wordId: Long, format: Int, themeJson: String?): String?
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_renderDefinition(
+        mut env: JNIEnv,
+        _class: JClass,
+        word_id: jlong,
+        format: jint,
+        theme_json: JString,
+    ) -> jstring {
+        let theme: ThemeOptions = if theme_json.is_null() {
+            ThemeOptions::default()
+        } else {
+            let theme_str: String = match env.get_string(&theme_json) {
+                Ok(s) => s.into(),
+                Err(_) => return ptr::null_mut(),
+            };
+            match serde_json::from_str(&theme_str) {
+                Ok(theme) => theme,
+                Err(e) => {
+                    log::error!("JNI renderDefinition: invalid theme: {}", e);
+                    return ptr::null_mut();
+                }
+            }
+        };
+
+        let guard = HANDLE.lock().unwrap();
+        let handle = match guard.as_ref() {
+            Some(h) => h,
+            None => {
+                log::warn!("JNI renderDefinition: handle not initialized");
+                return ptr::null_mut();
+            }
+        };
+
+        let Some(definition) = get_definition_with_options(handle, word_id, &RENDER_OPTIONS) else {
+            return ptr::null_mut();
+        };
+        let text = match format {
+            1 => render::definition_markdown(&definition, &theme),
+            2 => render::definition_plaintext(&definition, render::Verbosity::Standard),
+            _ => render::definition_html(&definition, &theme),
+        };
+
+        match env.new_string(&text) {
+            Ok(s) => s.into_raw(),
+            Err(e) => {
+                log::error!(
+                    "JNI renderDefinition: failed to create Java string: {:?}",
+                    e
+                );
+                ptr::null_mut()
+            }
+        }
+    }
+
+    /// JNI: Get full definition
+    ///
+    /// Kotlin signature: external fun getDefinition(wordId: Long): String?
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_getDefinition(
+        env: JNIEnv,
+        _class: JClass,
+        word_id: jlong,
+    ) -> jstring {
+        log::debug!("JNI getDefinition: called with word_id={}", word_id);
+
+        let guard = HANDLE.lock().unwrap();
+        let handle = match guard.as_ref() {
+            Some(h) => h,
+            None => {
+                log::warn!("JNI getDefinition: handle not initialized");
+                return ptr::null_mut();
+            }
+        };
+
+        let definition = get_definition(handle, word_id);
+
+        log::debug!(
+            "JNI getDefinition: word_id={} -> {}",
+            word_id,
+            if definition.is_some() {
+                "found"
+            } else {
+                "not found"
+            }
+        );
+
+        let json = match serde_json::to_string(&definition) {
+            Ok(j) => j,
+            Err(e) => {
+                log::error!("JNI getDefinition: JSON serialization failed: {}", e);
+                return ptr::null_mut();
+            }
+        };
+
+        match env.new_string(&json) {
+            Ok(s) => s.into_raw(),
+            Err(e) => {
+                log::error!("JNI getDefinition: failed to create Java string: {:?}", e);
+                ptr::null_mut()
+            }
+        }
+    }
+
+    /// JNI: Find text in the senses of one entry
+    ///
+    /// Kotlin signature: external fun findInEntry(wordId: Long, needle: String): String?
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_findInEntry(
+        mut env: JNIEnv,
+        _class: JClass,
+        word_id: jlong,
+        needle: JString,
+    ) -> jstring {
+        let needle: String = match env.get_string(&needle) {
+            Ok(s) => s.into(),
+            Err(_) => return ptr::null_mut(),
+        };
+
+        let guard = HANDLE.lock().unwrap();
+        let handle = match guard.as_ref() {
+            Some(h) => h,
+            None => {
+                log::warn!("JNI findInEntry: handle not initialized");
+                return ptr::null_mut();
+            }
+        };
+
+        let matches = match db::find_in_entry(handle, word_id, &needle) {
+            Ok(m) => m,
+            Err(e) => {
+                log::error!("JNI findInEntry: lookup failed: {}", e);
+                return ptr::null_mut();
+            }
+        };
+
+        let json = match serde_json::to_string(&matches) {
+            Ok(j) => j,
+            Err(e) => {
+                log::error!("JNI findInEntry: JSON serialization failed: {}", e);
+                return ptr::null_mut();
+            }
+        };
+
+        match env.new_string(&json) {
+            Ok(s) => s.into_raw(),
+            Err(e) => {
+                log::error!("JNI findInEntry: failed to create Java string: {:?}", e);
+                ptr::null_mut()
+            }
+        }
+    }
+
+    /// JNI: Get synonyms and antonyms grouped by sense
+    ///
+    /// Kotlin signature: external fun thesaurus(word: String, lang: String): String?
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_thesaurus(
+        mut env: JNIEnv,
+        _class: JClass,
+        word: JString,
+        lang: JString,
+    ) -> jstring {
+        let word_str: String = match env.get_string(&word) {
+            Ok(s) => s.into(),
+            Err(_) => return ptr::null_mut(),
+        };
+        let lang_str: String = match env.get_string(&lang) {
+            Ok(s) => s.into(),
+            Err(_) => return ptr::null_mut(),
+        };
+
+        let guard = HANDLE.lock().unwrap();
+        let handle = match guard.as_ref() {
+            Some(h) => h,
+            None => {
+                log::warn!("JNI thesaurus: handle not initialized");
+                return ptr::null_mut();
+            }
+        };
+
+        let groups = match thesaurus::lookup(handle, &word_str, &lang_str) {
+            Ok(g) => g,
+            Err(e) => {
+                log::error!("JNI thesaurus: lookup failed: {}", e);
+                return ptr::null_mut();
+            }
+        };
+
+        let json = match serde_json::to_string(&groups) {
+            Ok(j) => j,
+            Err(e) => {
+                log::error!("JNI thesaurus: JSON serialization failed: {}", e);
+                return ptr::null_mut();
+            }
+        };
+
+        match env.new_string(&json) {
+            Ok(s) => s.into_raw(),
+            Err(e) => {
+                log::error!("JNI thesaurus: failed to create Java string: {:?}", e);
+                ptr::null_mut()
+            }
+        }
+    }
+
+    /// JNI: Get candidate translations between two languages through English
+    ///
+    /// Kotlin signature: external fun translatePivot(word: String, fromLang: String, toLang: String): String?
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_translatePivot(
+        mut env: JNIEnv,
+        _class: JClass,
+        word: JString,
+        from_lang: JString,
+        to_lang: JString,
+    ) -> jstring {
+        let mut strings = Vec::with_capacity(3);
+        for s in [&word, &from_lang, &to_lang] {
+            match env.get_string(s) {
+                Ok(s) => strings.push(String::from(s)),
+                Err(_) => return ptr::null_mut(),
+            }
+        }
+
+        let guard = HANDLE.lock().unwrap();
+        let handle = match guard.as_ref() {
+            Some(h) => h,
+            None => {
+                log::warn!("JNI translatePivot: handle not initialized");
+                return ptr::null_mut();
+            }
+        };
+
+        let candidates = match translate::pivot(handle, &strings[0], &strings[1], &strings[2]) {
+            Ok(c) => c,
+            Err(e) => {
+                log::error!("JNI translatePivot: lookup failed: {}", e);
+                return ptr::null_mut();
+            }
+        };
+
+        let json = match serde_json::to_string(&candidates) {
+            Ok(j) => j,
+            Err(e) => {
+                log::error!("JNI translatePivot: JSON serialization failed: {}", e);
+                return ptr::null_mut();
+            }
+        };
+
+        match env.new_string(&json) {
+            Ok(s) => s.into_raw(),
+            Err(e) => {
+                log::error!("JNI translatePivot: failed to create Java string: {:?}", e);
+                ptr::null_mut()
+            }
+        }
+    }
+
+    /// JNI: Complete a typed prefix to headwords
+    ///
+    /// Kotlin signature: external fun complete(prefix: String, n: Int): Array<String>?
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_complete(
+        mut env: JNIEnv,
+        _class: JClass,
+        prefix: JString,
+        n: jint,
+    ) -> jobjectArray {
+        let prefix_str: String = match env.get_string(&prefix) {
+            Ok(s) => s.into(),
+            Err(_) => return ptr::null_mut(),
+        };
+
+        let guard = HANDLE.lock().unwrap();
+        let handle = match guard.as_ref() {
+            Some(h) => h,
+            None => {
+                log::warn!("JNI complete: handle not initialized");
+                return ptr::null_mut();
+            }
+        };
+
+        let words = match search::complete(handle, &prefix_str, n.max(0) as u32) {
+            Ok(w) => w,
+            Err(e) => {
+                log::error!("JNI complete: completion failed: {}", e);
+                return ptr::null_mut();
+            }
+        };
+
+        let array =
+            match env.new_object_array(words.len() as jint, "java/lang/String", JObject::null()) {
+                Ok(a) => a,
+                Err(e) => {
+                    log::error!("JNI complete: failed to create array: {:?}", e);
+                    return ptr::null_mut();
+                }
+            };
+        for (i, word) in words.iter().enumerate() {
+            let element = match env.new_string(word) {
+                Ok(s) => s,
+                Err(e) => {
+                    log::error!("JNI complete: failed to create Java string: {:?}", e);
+                    return ptr::null_mut();
+                }
+            };
+            if env
+                .set_object_array_element(&array, i as jint, element)
+                .is_err()
+            {
+                return ptr::null_mut();
+            }
+        }
+        array.into_raw()
+    }
+
+    /// JNI: Download the latest database for a language and install it
+    ///
+    /// `listener` may be null; otherwise its `onProgress(phase: Int,
+    /// bytesDone: Long, bytesTotal: Long)` is called on the calling thread.
+    ///
+    /// Kotlin signature: external fun downloadDatabase(baseUrl: String, language: String, destPath: String, listener: DownloadListener?): Int
+    #[cfg(feature = "download")]
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_downloadDatabase(
+        mut env: JNIEnv,
+        _class: JClass,
+        base_url: JString,
+        language: JString,
+        dest_path: JString,
+        listener: JObject,
+    ) -> jint {
+        let mut strings = Vec::with_capacity(3);
+        for s in [&base_url, &language, &dest_path] {
+            match env.get_string(s) {
+                Ok(s) => strings.push(String::from(s)),
+                Err(_) => return FfiError::InvalidUtf8 as jint,
+            }
+        }
+
+        let mut listener_failed = false;
+        download_latest(&strings[0], &strings[1], &strings[2], |p| {
+            if listener.is_null() || listener_failed {
+                return;
+            }
+            let args = [
+                JValue::Int(p.phase as jint),
+                JValue::Long(p.bytes_done as jlong),
+                JValue::Long(p.bytes_total as jlong),
+            ];
+            if let Err(e) = env.call_method(&listener, "onProgress", "(IJJ)V", &args) {
+                log::error!("JNI downloadDatabase: onProgress failed: {:?}", e);
+                listener_failed = true;
+            }
+        }) as jint
+    }
+
+    /// JNI: Check whether a newer database than the open one has been published
+    ///
+    /// Returns a JSON `AvailableUpdate`, "null" if the database is current,
+    /// or null on failure.
+    ///
+    /// Kotlin signature: external fun checkUpdate(manifestUrl: String): String?
+    #[cfg(feature = "download")]
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_checkUpdate(
+        mut env: JNIEnv,
+        _class: JClass,
+        manifest_url: JString,
+    ) -> jstring {
+        let url: String = match env.get_string(&manifest_url) {
+            Ok(s) => s.into(),
+            Err(_) => return ptr::null_mut(),
+        };
+
+        let json = match check_update(&url) {
+            Ok(json) => json,
+            Err(_) => return ptr::null_mut(),
+        };
+        match env.new_string(&json) {
+            Ok(s) => s.into_raw(),
+            Err(e) => {
+                log::error!("JNI checkUpdate: failed to create Java string: {:?}", e);
+                ptr::null_mut()
+            }
+        }
+    }
+
+    /// JNI: Strip content from the open dictionary and vacuum it
     ///
-    /// Kotlin signature: external fun getDefinition(wordId: Long): String?
+    /// Returns a JSON `CompactStats`, or null on failure.
+    ///
+    /// Kotlin signature: external fun compact(optionsJson: String): String?
     #[no_mangle]
-    pub extern "system" fn Java_org_example_dictapp_DictCore_getDefinition(
-        env: JNIEnv,
+    pub extern "system" fn Java_org_example_dictapp_DictCore_compact(
+        mut env: JNIEnv,
         _class: JClass,
-        word_id: jlong,
+        options_json: JString,
     ) -> jstring {
-        log::debug!("JNI getDefinition: called with word_id={}", word_id);
+        let options_str: String = match env.get_string(&options_json) {
+            Ok(s) => s.into(),
+            Err(_) => return ptr::null_mut(),
+        };
+        let options: CompactOptions = match serde_json::from_str(&options_str) {
+            Ok(options) => options,
+            Err(e) => {
+                log::error!("JNI compact: invalid options: {}", e);
+                return ptr::null_mut();
+            }
+        };
 
         let guard = HANDLE.lock().unwrap();
         let handle = match guard.as_ref() {
             Some(h) => h,
             None => {
-                log::warn!("JNI getDefinition: handle not initialized");
+                log::warn!("JNI compact: handle not initialized");
                 return ptr::null_mut();
             }
         };
 
-        let definition = get_definition(handle, word_id);
+        let stats = match db::compact(handle, &options) {
+            Ok(stats) => stats,
+            Err(e) => {
+                log::error!("JNI compact: compaction failed: {}", e);
+                return ptr::null_mut();
+            }
+        };
+        let json = match serde_json::to_string(&stats) {
+            Ok(j) => j,
+            Err(_) => return ptr::null_mut(),
+        };
 
-        log::debug!(
-            "JNI getDefinition: word_id={} -> {}",
-            word_id,
-            if definition.is_some() {
-                "found"
-            } else {
-                "not found"
+        match env.new_string(&json) {
+            Ok(s) => s.into_raw(),
+            Err(e) => {
+                log::error!("JNI compact: failed to create Java string: {:?}", e);
+                ptr::null_mut()
             }
-        );
+        }
+    }
+
+    /// JNI: Read the hot indexes into the page cache
+    ///
+    /// Kotlin signature: external fun warmUp(): Int
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_warmUp(
+        _env: JNIEnv,
+        _class: JClass,
+    ) -> jint {
+        dict_warm_up()
+    }
+
+    /// JNI: Stop the query running on the dictionary, from another thread
+    ///
+    /// Kotlin signature: external fun interrupt(): Int
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_interrupt(
+        _env: JNIEnv,
+        _class: JClass,
+    ) -> jint {
+        dict_interrupt()
+    }
+
+    /// JNI: Load the entries expected to be opened next
+    ///
+    /// Kotlin signature: external fun prefetchDefinitions(wordIds: LongArray): Int
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_prefetchDefinitions(
+        env: JNIEnv,
+        _class: JClass,
+        word_ids: JLongArray,
+    ) -> jint {
+        let mut ids = match env.get_array_length(&word_ids) {
+            Ok(len) => vec![0; len as usize],
+            Err(_) => return FfiError::NullPointer as jint,
+        };
+        if env.get_long_array_region(&word_ids, 0, &mut ids).is_err() {
+            return FfiError::NullPointer as jint;
+        }
+        unsafe { dict_prefetch_definitions(ids.as_ptr(), ids.len() as c_int) }
+    }
+
+    /// JNI: Report what the SQLite library supports
+    ///
+    /// Returns a JSON `Capabilities`, or null if not initialized.
+    ///
+    /// Kotlin signature: external fun capabilities(): String?
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_capabilities(
+        env: JNIEnv,
+        _class: JClass,
+    ) -> jstring {
+        let guard = HANDLE.lock().unwrap();
+        let Some(handle) = guard.as_ref() else {
+            log::warn!("JNI capabilities: handle not initialized");
+            return ptr::null_mut();
+        };
+        let json = match serde_json::to_string(&handle.capabilities()) {
+            Ok(j) => j,
+            Err(_) => return ptr::null_mut(),
+        };
+
+        match env.new_string(&json) {
+            Ok(s) => s.into_raw(),
+            Err(e) => {
+                log::error!("JNI capabilities: failed to create Java string: {:?}", e);
+                ptr::null_mut()
+            }
+        }
+    }
+
+    /// JNI: Check whether a dictionary database can be used
+    ///
+    /// Returns a JSON `CompatibilityReport`, or null if the file could not
+    /// be read.
+    ///
+    /// Kotlin signature: external fun checkDb(dbPath: String): String?
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_checkDb(
+        mut env: JNIEnv,
+        _class: JClass,
+        db_path: JString,
+    ) -> jstring {
+        let path: String = match env.get_string(&db_path) {
+            Ok(s) => s.into(),
+            Err(_) => return ptr::null_mut(),
+        };
+
+        let report = match db::check_compatibility(&path) {
+            Ok(report) => report,
+            Err(e) => {
+                log::error!("JNI checkDb: check failed: {}", e);
+                return ptr::null_mut();
+            }
+        };
+        let json = match serde_json::to_string(&report) {
+            Ok(j) => j,
+            Err(_) => return ptr::null_mut(),
+        };
+
+        match env.new_string(&json) {
+            Ok(s) => s.into_raw(),
+            Err(e) => {
+                log::error!("JNI checkDb: failed to create Java string: {:?}", e);
+                ptr::null_mut()
+            }
+        }
+    }
+
+    /// JNI: Get an entry of the user database by ID
+    ///
+    /// Kotlin signature: external fun getUserDefinition(wordId: Long): String?
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_getUserDefinition(
+        env: JNIEnv,
+        _class: JClass,
+        word_id: jlong,
+    ) -> jstring {
+        let guard = HANDLE.lock().unwrap();
+        let handle = match guard.as_ref() {
+            Some(h) => h,
+            None => {
+                log::warn!("JNI getUserDefinition: handle not initialized");
+                return ptr::null_mut();
+            }
+        };
 
+        let definition = match db::get_user_definition(handle, word_id) {
+            Ok(definition) => definition,
+            Err(e) => {
+                log::error!("JNI getUserDefinition: lookup failed: {}", e);
+                return ptr::null_mut();
+            }
+        };
         let json = match serde_json::to_string(&definition) {
             Ok(j) => j,
+            Err(_) => return ptr::null_mut(),
+        };
+
+        match env.new_string(&json) {
+            Ok(s) => s.into_raw(),
             Err(e) => {
-                log::error!("JNI getDefinition: JSON serialization failed: {}", e);
+                log::error!(
+                    "JNI getUserDefinition: failed to create Java string: {:?}",
+                    e
+                );
+                ptr::null_mut()
+            }
+        }
+    }
+
+    /// JNI: Open the user database for bookmarks
+    ///
+    /// Kotlin signature: external fun openUserData(path: String): Int
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_openUserData(
+        mut env: JNIEnv,
+        _class: JClass,
+        path: JString,
+    ) -> jint {
+        let path: String = match env.get_string(&path) {
+            Ok(s) => s.into(),
+            Err(_) => return FfiError::InvalidUtf8 as jint,
+        };
+
+        match UserData::open(&path) {
+            Ok(user) => {
+                *USER.lock().unwrap() = Some(user);
+                FfiError::Success as jint
+            }
+            Err(e) => {
+                log::error!("JNI openUserData: failed to open user database: {}", e);
+                FfiError::InitFailed as jint
+            }
+        }
+    }
+
+    /// JNI: Bookmark an entry, or one of its senses (`definitionId` 0 for
+    /// the whole entry)
+    ///
+    /// Kotlin signature: external fun addBookmark(wordId: Long, word: String, definitionId: Long, note: String?, at: Long): Int
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_addBookmark(
+        mut env: JNIEnv,
+        _class: JClass,
+        word_id: jlong,
+        word: JString,
+        definition_id: jlong,
+        note: JString,
+        at: jlong,
+    ) -> jint {
+        let word: String = match env.get_string(&word) {
+            Ok(s) => s.into(),
+            Err(_) => return FfiError::InvalidUtf8 as jint,
+        };
+        let note: Option<String> = if note.is_null() {
+            None
+        } else {
+            match env.get_string(&note) {
+                Ok(s) => Some(s.into()),
+                Err(_) => return FfiError::InvalidUtf8 as jint,
+            }
+        };
+
+        let guard = USER.lock().unwrap();
+        let Some(user) = guard.as_ref() else {
+            log::warn!("JNI addBookmark: user database not open");
+            return FfiError::NotInitialized as jint;
+        };
+
+        match user.add_bookmark(word_id, &word, sense_id(definition_id), note.as_deref(), at) {
+            Ok(()) => FfiError::Success as jint,
+            Err(e) => {
+                log::error!("JNI addBookmark: failed: {}", e);
+                FfiError::SearchFailed as jint
+            }
+        }
+    }
+
+    /// JNI: Remove the bookmark of an entry, or of one of its senses
+    ///
+    /// Kotlin signature: external fun removeBookmark(wordId: Long, definitionId: Long): Int
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_removeBookmark(
+        _env: JNIEnv,
+        _class: JClass,
+        word_id: jlong,
+        definition_id: jlong,
+    ) -> jint {
+        dict_remove_bookmark(word_id, definition_id)
+    }
+
+    /// JNI: Get the most recent bookmarks, or with a positive `wordId` the
+    /// bookmarks of that entry
+    ///
+    /// Kotlin signature: external fun getBookmarks(wordId: Long, limit: Int): String?
+    #[no_mangle]
+    pub extern "system" fn Java_org_example_dictapp_DictCore_getBookmarks(
+        env: JNIEnv,
+        _class: JClass,
+        word_id: jlong,
+        limit: jint,
+    ) -> jstring {
+        let guard = USER.lock().unwrap();
+        let Some(user) = guard.as_ref() else {
+            log::warn!("JNI getBookmarks: user database not open");
+            return ptr::null_mut();
+        };
+
+        let bookmarks = if word_id > 0 {
+            user.entry_bookmarks(word_id)
+        } else {
+            user.bookmarks(limit.max(0) as u32)
+        };
+        let json = match bookmarks.map(|b| serde_json::to_string(&b)) {
+            Ok(Ok(j)) => j,
+            Ok(Err(e)) => {
+                log::error!("JNI getBookmarks: JSON serialization failed: {}", e);
+                return ptr::null_mut();
+            }
+            Err(e) => {
+                log::error!("JNI getBookmarks: lookup failed: {}", e);
                 return ptr::null_mut();
             }
         };
@@ -349,7 +2311,7 @@ mod android {
         match env.new_string(&json) {
             Ok(s) => s.into_raw(),
             Err(e) => {
-                log::error!("JNI getDefinition: failed to create Java string: {:?}", e);
+                log::error!("JNI getBookmarks: failed to create Java string: {:?}", e);
                 ptr::null_mut()
             }
         }
@@ -360,8 +2322,7 @@ mod android {
     /// Kotlin signature: external fun close()
     #[no_mangle]
     pub extern "system" fn Java_org_example_dictapp_DictCore_close(_env: JNIEnv, _class: JClass) {
-        let mut guard = HANDLE.lock().unwrap();
-        *guard = None;
+        set_handle(None);
     }
 
     /// Called when the native library is loaded by System.loadLibrary()
@@ -423,6 +2384,60 @@ mod tests {
                 dict_search(ptr::null(), 10, 0, ptr::null_mut()),
                 FfiError::NullPointer as c_int
             );
+            assert_eq!(
+                dict_thesaurus(ptr::null(), ptr::null(), ptr::null_mut()),
+                FfiError::NullPointer as c_int
+            );
+            assert_eq!(
+                dict_complete(ptr::null(), 10, ptr::null_mut(), ptr::null_mut()),
+                FfiError::NullPointer as c_int
+            );
+            dict_free_string_array(ptr::null_mut(), 0);
+            assert_eq!(
+                dict_get_user_definition(1, ptr::null_mut()),
+                FfiError::NullPointer as c_int
+            );
+            assert_eq!(
+                dict_compact(ptr::null(), ptr::null_mut()),
+                FfiError::NullPointer as c_int
+            );
+            assert_eq!(
+                dict_capabilities(ptr::null_mut()),
+                FfiError::NullPointer as c_int
+            );
+            assert_eq!(
+                dict_check_db(ptr::null(), ptr::null_mut()),
+                FfiError::NullPointer as c_int
+            );
+            assert_eq!(
+                dict_init_with_config(ptr::null(), ptr::null()),
+                FfiError::NullPointer as c_int
+            );
+            assert_eq!(
+                dict_render_definition(1, 0, ptr::null(), ptr::null_mut()),
+                FfiError::NullPointer as c_int
+            );
+            assert_eq!(dict_user_open(ptr::null()), FfiError::NullPointer as c_int);
+            assert_eq!(
+                dict_add_bookmark(1, ptr::null(), 0, ptr::null(), 0),
+                FfiError::NullPointer as c_int
+            );
+            #[cfg(feature = "download")]
+            assert_eq!(
+                dict_check_update(ptr::null(), ptr::null_mut()),
+                FfiError::NullPointer as c_int
+            );
+            #[cfg(feature = "download")]
+            assert_eq!(
+                dict_download_database(
+                    ptr::null(),
+                    ptr::null(),
+                    ptr::null(),
+                    None,
+                    ptr::null_mut()
+                ),
+                FfiError::NullPointer as c_int
+            );
         }
     }
 
@@ -437,6 +2452,50 @@ mod tests {
 
             let result = dict_search(query.as_ptr(), 10, 0, &mut out);
             assert_eq!(result, FfiError::NotInitialized as c_int);
+            assert_eq!(dict_interrupt(), FfiError::NotInitialized as c_int);
+            assert_eq!(dict_warm_up(), FfiError::NotInitialized as c_int);
+            assert_eq!(
+                dict_prefetch_definitions([1i64].as_ptr(), 1),
+                FfiError::NotInitialized as c_int
+            );
+            assert_eq!(
+                dict_get_definitions_for_word(query.as_ptr(), &mut out),
+                FfiError::NotInitialized as c_int
+            );
+            assert_eq!(
+                dict_translate_pivot(query.as_ptr(), query.as_ptr(), query.as_ptr(), &mut out),
+                FfiError::NotInitialized as c_int
+            );
+            assert_eq!(
+                dict_find_in_entry(1, query.as_ptr(), &mut out),
+                FfiError::NotInitialized as c_int
+            );
+            dict_user_close();
+            assert_eq!(
+                dict_add_bookmark(1, query.as_ptr(), 0, ptr::null(), 0),
+                FfiError::NotInitialized as c_int
+            );
+            assert_eq!(
+                dict_remove_bookmark(1, 0),
+                FfiError::NotInitialized as c_int
+            );
+            assert_eq!(
+                dict_get_bookmarks(0, 10, &mut out),
+                FfiError::NotInitialized as c_int
+            );
+            assert_eq!(
+                dict_graph_neighborhood(1, 2, &mut out),
+                FfiError::NotInitialized as c_int
+            );
+            assert_eq!(
+                dict_semantically_related(1, 5, &mut out),
+                FfiError::NotInitialized as c_int
+            );
+            let empty = CString::new("{}").unwrap();
+            assert_eq!(
+                dict_extract_subset(query.as_ptr(), empty.as_ptr(), &mut out),
+                FfiError::NotInitialized as c_int
+            );
         }
     }
 }
@@ -0,0 +1,615 @@
+//! Bounded fuzzy matching with a Levenshtein automaton
+//!
+//! A [`LevenshteinAutomaton`] reads a word one character at a time and
+//! knows, after every character, whether some continuation could still
+//! end within the maximum distance of the query. Walking it over the
+//! sorted headword keys of a [`HeadwordIndex`] shares the work for a
+//! common prefix ("catalog", "catalogue") and skips every key under a
+//! prefix the automaton rejects ("xq..."), so a distance-2 search visits a
+//! small part of the index instead of computing a distance per headword.
+//! A [`Pattern`] ("c?t", "walk*") is walked the same way.
+//!
+//! With the `fst` feature, [`build_fst`] writes the keys to an FST next to
+//! the database (`english.db.fst`), which handles opened later map into
+//! memory instead of reading every key from SQLite. It maps each key to
+//! the highest frequency of its headwords, so prefix search and
+//! completion read their candidates from it too.
+
+use std::collections::BinaryHeap;
+
+use crate::{db, Result};
+
+/// An automaton reading words one character at a time
+pub trait CharAutomaton {
+    type State: Clone;
+
+    /// The state before any character is read
+    fn start(&self) -> Self::State;
+
+    /// The state after reading `c` in `state`
+    fn step(&self, state: &Self::State, c: char) -> Self::State;
+
+    /// Whether the word read is accepted
+    fn is_match(&self, state: &Self::State) -> bool;
+
+    /// Whether any word starting with what was read can be accepted
+    fn can_match(&self, state: &Self::State) -> bool;
+}
+
+/// Accepts the words within `max_distance` edits of a query
+///
+/// States are rows of the Wagner-Fischer matrix, capped at
+/// `max_distance + 1` since larger values never lead to a match.
+#[derive(Debug, Clone)]
+pub struct LevenshteinAutomaton {
+    query: Vec<char>,
+    max_distance: usize,
+}
+
+/// A state of a [`LevenshteinAutomaton`]: distances between the word read
+/// so far and each prefix of the query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State(Vec<usize>);
+
+impl LevenshteinAutomaton {
+    pub fn new(query: &str, max_distance: usize) -> Self {
+        Self {
+            query: query.chars().collect(),
+            max_distance,
+        }
+    }
+
+    /// Distance between the query and the word read, None if it is more
+    /// than the maximum
+    pub fn distance(&self, state: &State) -> Option<usize> {
+        let distance = state.0[self.query.len()];
+        (distance <= self.max_distance).then_some(distance)
+    }
+
+    /// Distance between the query and `word`, None if it is more than the
+    /// maximum
+    pub fn matches(&self, word: &str) -> Option<usize> {
+        let mut state = self.start();
+        for c in word.chars() {
+            state = self.step(&state, c);
+            if !self.can_match(&state) {
+                return None;
+            }
+        }
+        self.distance(&state)
+    }
+}
+
+impl CharAutomaton for LevenshteinAutomaton {
+    type State = State;
+
+    fn start(&self) -> State {
+        let cap = self.max_distance + 1;
+        State((0..=self.query.len()).map(|i| i.min(cap)).collect())
+    }
+
+    fn step(&self, state: &State, c: char) -> State {
+        let cap = self.max_distance + 1;
+        let prev = &state.0;
+        let mut next = Vec::with_capacity(prev.len());
+        next.push((prev[0] + 1).min(cap));
+        for (i, &q) in self.query.iter().enumerate() {
+            let substitution = prev[i] + usize::from(q != c);
+            let distance = substitution.min(prev[i + 1] + 1).min(next[i] + 1);
+            next.push(distance.min(cap));
+        }
+        State(next)
+    }
+
+    fn is_match(&self, state: &State) -> bool {
+        self.distance(state).is_some()
+    }
+
+    fn can_match(&self, state: &State) -> bool {
+        state.0.iter().any(|&d| d <= self.max_distance)
+    }
+}
+
+/// A headword pattern: `?` stands for any one character and `*` for any
+/// run of characters, so "c?t" matches "cat" and "cut", and "walk*"
+/// "walk" and "walking"
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    tokens: Vec<Token>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Char(char),
+    AnyChar,
+    AnyRun,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Self {
+        let tokens = pattern
+            .chars()
+            .map(|c| match c {
+                '?' => Token::AnyChar,
+                '*' => Token::AnyRun,
+                c => Token::Char(c),
+            })
+            .collect();
+        Self { tokens }
+    }
+
+    /// Whether the pattern matches all of `word`
+    pub fn matches(&self, word: &str) -> bool {
+        let state = word
+            .chars()
+            .fold(self.start(), |state, c| self.step(&state, c));
+        self.is_match(&state)
+    }
+
+    /// `positions` with every position after a run of `*` added
+    fn close(&self, mut positions: Vec<usize>) -> Vec<usize> {
+        let mut i = 0;
+        while i < positions.len() {
+            let p = positions[i];
+            if self.tokens.get(p) == Some(&Token::AnyRun) && !positions.contains(&(p + 1)) {
+                positions.push(p + 1);
+            }
+            i += 1;
+        }
+        positions.sort_unstable();
+        positions
+    }
+}
+
+impl CharAutomaton for Pattern {
+    /// Positions in the pattern the word read can have reached
+    type State = Vec<usize>;
+
+    fn start(&self) -> Vec<usize> {
+        self.close(vec![0])
+    }
+
+    fn step(&self, state: &Vec<usize>, c: char) -> Vec<usize> {
+        let mut next = Vec::new();
+        for &p in state {
+            let reached = match self.tokens.get(p) {
+                Some(Token::AnyRun) => p,
+                Some(Token::AnyChar) => p + 1,
+                Some(Token::Char(t)) if *t == c => p + 1,
+                _ => continue,
+            };
+            if !next.contains(&reached) {
+                next.push(reached);
+            }
+        }
+        self.close(next)
+    }
+
+    fn is_match(&self, state: &Vec<usize>) -> bool {
+        state.contains(&self.tokens.len())
+    }
+
+    fn can_match(&self, state: &Vec<usize>) -> bool {
+        !state.is_empty()
+    }
+}
+
+/// The distinct headword keys of a dictionary, sorted, for fuzzy and
+/// pattern matching
+///
+/// Keys are `words.search_key`, or lowercased headwords in databases built
+/// before search keys. Loaded the first time a handle needs them: mapped
+/// from the FST next to the database when there is one, otherwise read
+/// from SQLite.
+pub(crate) enum HeadwordIndex {
+    Sorted(Vec<Box<str>>),
+    #[cfg(feature = "fst")]
+    Fst(fst::Map<memmap2::Mmap>),
+}
+
+impl HeadwordIndex {
+    pub(crate) fn load(conn: &rusqlite::Connection) -> Result<Self> {
+        #[cfg(feature = "fst")]
+        if let Some(path) = conn.path().filter(|p| !p.is_empty()) {
+            let path = fst_path(std::path::Path::new(path));
+            if path.exists() {
+                match open_fst(&path) {
+                    Ok(map) => return Ok(Self::Fst(map)),
+                    Err(e) => log::warn!("Could not open {:?}, reading keys: {}", path, e),
+                }
+            }
+        }
+        Ok(Self::Sorted(sorted_keys(conn)?))
+    }
+
+    /// The first `limit` keys `automaton` accepts, in key order
+    pub(crate) fn search<A: CharAutomaton>(&self, automaton: &A, limit: usize) -> Vec<String> {
+        match self {
+            Self::Sorted(keys) => walk(keys, automaton, limit)
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            #[cfg(feature = "fst")]
+            Self::Fst(map) => {
+                use fst::{IntoStreamer, Streamer};
+                let mut stream = map.search(Utf8(automaton)).into_stream();
+                let mut keys = Vec::new();
+                while keys.len() < limit {
+                    let Some((key, _)) = stream.next() else { break };
+                    // Keys are written from Rust strings
+                    keys.push(String::from_utf8_lossy(key).into_owned());
+                }
+                keys
+            }
+        }
+    }
+
+    /// The `limit` keys starting with `prefix` that `order` ranks first,
+    /// ties broken alphabetically, with their weights: the highest
+    /// frequency of their headwords, or 0 unless the index is an FST
+    ///
+    /// Every key with the prefix is ranked, but only `limit` are kept.
+    pub(crate) fn top_prefixed<K: Ord>(
+        &self,
+        prefix: &str,
+        limit: usize,
+        order: impl Fn(&str, u64) -> K,
+    ) -> Vec<(String, u64)> {
+        // The worst of the best keys so far on top
+        let mut top: BinaryHeap<(K, String, u64)> = BinaryHeap::with_capacity(limit + 1);
+        let mut offer = |key: &str, weight: u64| {
+            let rank = order(key, weight);
+            let better = match top.peek() {
+                _ if top.len() < limit => true,
+                Some((worst, worst_key, _)) => (&rank, key) < (worst, worst_key.as_str()),
+                None => false,
+            };
+            if better {
+                top.push((rank, key.to_string(), weight));
+                if top.len() > limit {
+                    top.pop();
+                }
+            }
+        };
+        match self {
+            Self::Sorted(keys) => {
+                let start = keys.partition_point(|k| &**k < prefix);
+                for key in keys[start..].iter().take_while(|k| k.starts_with(prefix)) {
+                    offer(key, 0);
+                }
+            }
+            #[cfg(feature = "fst")]
+            Self::Fst(map) => {
+                use fst::automaton::{Automaton, Str};
+                use fst::{IntoStreamer, Streamer};
+                let mut stream = map.search(Str::new(prefix).starts_with()).into_stream();
+                while let Some((key, weight)) = stream.next() {
+                    offer(&String::from_utf8_lossy(key), weight);
+                }
+            }
+        }
+        top.into_sorted_vec()
+            .into_iter()
+            .map(|(_, key, weight)| (key, weight))
+            .collect()
+    }
+
+    /// Whether the keys are mapped from an FST rather than read from SQLite
+    pub(crate) fn is_fst(&self) -> bool {
+        match self {
+            Self::Sorted(_) => false,
+            #[cfg(feature = "fst")]
+            Self::Fst(_) => true,
+        }
+    }
+}
+
+/// The first `limit` of `keys` (sorted) that `automaton` accepts
+///
+/// The automaton's states after each character of the current key are
+/// kept, so the next key only steps through the characters after the
+/// prefix they share.
+fn walk<'a, A: CharAutomaton>(keys: &'a [Box<str>], automaton: &A, limit: usize) -> Vec<&'a str> {
+    let mut matches = Vec::new();
+    // States after each character of the key being read, and the byte
+    // offset where each character ends
+    let mut states = vec![automaton.start()];
+    let mut ends = vec![0];
+    let mut prev: &str = "";
+
+    let mut i = 0;
+    while i < keys.len() && matches.len() < limit {
+        let key = &*keys[i];
+        let shared = shared_prefix_chars(prev, key).min(states.len() - 1);
+        states.truncate(shared + 1);
+        ends.truncate(shared + 1);
+
+        let mut dead = false;
+        for (offset, c) in key[ends[shared]..].char_indices() {
+            let state = automaton.step(&states[states.len() - 1], c);
+            ends.push(ends[shared] + offset + c.len_utf8());
+            dead = !automaton.can_match(&state);
+            states.push(state);
+            if dead {
+                break;
+            }
+        }
+
+        if dead {
+            // Nothing under this prefix can match: skip to the next key not
+            // starting with it
+            let prefix = &key[..ends[ends.len() - 1]];
+            i += keys[i..].partition_point(|k| k.starts_with(prefix));
+            states.pop();
+            ends.pop();
+        } else {
+            if automaton.is_match(&states[states.len() - 1]) {
+                matches.push(key);
+            }
+            i += 1;
+        }
+        prev = key;
+    }
+    matches
+}
+
+/// A [`CharAutomaton`] reading UTF-8 bytes, as an FST is searched
+#[cfg(feature = "fst")]
+struct Utf8<'a, A>(&'a A);
+
+#[cfg(feature = "fst")]
+impl<A: CharAutomaton> fst::Automaton for Utf8<'_, A> {
+    /// The character automaton's state and the bytes read of an
+    /// incomplete character; None after invalid UTF-8
+    type State = Option<(A::State, [u8; 4], usize)>;
+
+    fn start(&self) -> Self::State {
+        Some((self.0.start(), [0; 4], 0))
+    }
+
+    fn is_match(&self, state: &Self::State) -> bool {
+        matches!(state, Some((state, _, 0)) if self.0.is_match(state))
+    }
+
+    fn can_match(&self, state: &Self::State) -> bool {
+        matches!(state, Some((state, _, _)) if self.0.can_match(state))
+    }
+
+    fn accept(&self, state: &Self::State, byte: u8) -> Self::State {
+        let (state, mut bytes, len) = state.clone()?;
+        if len == bytes.len() {
+            return None;
+        }
+        bytes[len] = byte;
+        match std::str::from_utf8(&bytes[..=len]) {
+            Ok(c) => {
+                let c = c.chars().next()?;
+                Some((self.0.step(&state, c), [0; 4], 0))
+            }
+            Err(e) if e.error_len().is_none() => Some((state, bytes, len + 1)),
+            Err(_) => None,
+        }
+    }
+}
+
+/// Distinct non-empty headword keys, sorted
+fn sorted_keys(conn: &rusqlite::Connection) -> Result<Vec<Box<str>>> {
+    let column = key_column(conn)?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT DISTINCT {column} FROM words WHERE {column} IS NOT NULL AND {column} != ''"
+    ))?;
+    let keys = stmt.query_map([], |row| row.get::<_, String>(0))?;
+    let mut keys: Vec<Box<str>> = keys
+        .map(|key| key.map(String::into_boxed_str))
+        .collect::<std::result::Result<_, _>>()?;
+    keys.sort_unstable();
+    Ok(keys)
+}
+
+/// Where the headword FST of the database at `db_path` is kept: next to
+/// it, with `.fst` appended ("english.db.fst")
+pub fn fst_path(db_path: &std::path::Path) -> std::path::PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push(".fst");
+    path.into()
+}
+
+/// Write the headword keys of the database at `db_path` to an FST next to
+/// it (see [`fst_path`]), each with the highest frequency of its
+/// headwords, returning the number of keys
+///
+/// Handles opened afterwards search it instead of loading the keys. Build
+/// it last: entries added or renamed later are missing from it until it
+/// is built again.
+#[cfg(feature = "fst")]
+pub fn build_fst(db_path: &str) -> Result<u64> {
+    let conn = rusqlite::Connection::open(db_path)?;
+    let keys = weighted_keys(&conn)?;
+
+    let path = fst_path(std::path::Path::new(db_path));
+    let partial = path.with_extension("fst.partial");
+    let file = std::io::BufWriter::new(std::fs::File::create(&partial)?);
+    let mut builder = fst::MapBuilder::new(file).map_err(fst_error)?;
+    builder
+        .extend_iter(keys.iter().map(|(k, weight)| (k.as_bytes(), *weight)))
+        .map_err(fst_error)?;
+    builder.finish().map_err(fst_error)?;
+    std::fs::rename(&partial, &path)?;
+    Ok(keys.len() as u64)
+}
+
+/// Distinct non-empty headword keys with the highest frequency of their
+/// headwords, sorted
+#[cfg(feature = "fst")]
+fn weighted_keys(conn: &rusqlite::Connection) -> Result<Vec<(String, u64)>> {
+    let column = key_column(conn)?;
+    let frequency = if db::has_column(conn, "words", "frequency")? {
+        "MAX(COALESCE(frequency, 0))"
+    } else {
+        "0"
+    };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {column}, {frequency} FROM words
+         WHERE {column} IS NOT NULL AND {column} != ''
+         GROUP BY {column}"
+    ))?;
+    let keys = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, i64>(1)?.max(0) as u64,
+        ))
+    })?;
+    let mut keys = keys.collect::<std::result::Result<Vec<_>, _>>()?;
+    keys.sort_unstable();
+    Ok(keys)
+}
+
+/// Map the FST at `path`; one written as a set, before weights were
+/// kept, reads as a map with every weight 0
+#[cfg(feature = "fst")]
+fn open_fst(path: &std::path::Path) -> Result<fst::Map<memmap2::Mmap>> {
+    let file = std::fs::File::open(path)?;
+    // Safety: the FST is only replaced by renaming a new file over it, so
+    // the mapped file is never modified
+    let mmap = unsafe { memmap2::Mmap::map(&file)? };
+    fst::Map::new(mmap).map_err(fst_error)
+}
+
+#[cfg(feature = "fst")]
+fn fst_error(e: fst::Error) -> crate::Error {
+    match e {
+        fst::Error::Io(e) => crate::Error::Io(e),
+        e => crate::Error::Io(std::io::Error::other(e)),
+    }
+}
+
+/// Column (or expression) headwords are keyed by in `words`
+pub(crate) fn key_column(conn: &rusqlite::Connection) -> Result<&'static str> {
+    Ok(if db::has_column(conn, "words", "search_key")? {
+        "search_key"
+    } else {
+        "LOWER(word)"
+    })
+}
+
+/// Number of characters `a` and `b` start with in common
+fn shared_prefix_chars(a: &str, b: &str) -> usize {
+    a.chars().zip(b.chars()).take_while(|(x, y)| x == y).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text_distance::levenshtein_distance;
+
+    #[test]
+    fn test_automaton() {
+        let automaton = LevenshteinAutomaton::new("kitten", 2);
+        assert_eq!(automaton.matches("kitten"), Some(0));
+        assert_eq!(automaton.matches("sitten"), Some(1));
+        assert_eq!(automaton.matches("sittin"), Some(2));
+        assert_eq!(automaton.matches("sitting"), None);
+        assert_eq!(automaton.matches("kit"), None);
+        assert_eq!(
+            LevenshteinAutomaton::new("café", 1).matches("cafe"),
+            Some(1)
+        );
+
+        let state = ["x", "q", "z"].iter().fold(automaton.start(), |state, c| {
+            automaton.step(&state, c.chars().next().unwrap())
+        });
+        assert!(!automaton.can_match(&state));
+    }
+
+    #[test]
+    fn test_pattern() {
+        assert!(Pattern::new("c?t").matches("cat"));
+        assert!(!Pattern::new("c?t").matches("ct"));
+        assert!(Pattern::new("walk*").matches("walk"));
+        assert!(Pattern::new("walk*").matches("walking"));
+        assert!(Pattern::new("*ing").matches("walking"));
+        assert!(Pattern::new("w*k*g").matches("walking"));
+        assert!(!Pattern::new("w*k").matches("walking"));
+        assert!(Pattern::new("caf?").matches("café"));
+    }
+
+    fn keys() -> Vec<Box<str>> {
+        let mut keys: Vec<Box<str>> =
+            "helo hello help hell hellos jello shell world xylophone h café"
+                .split(' ')
+                .map(Box::from)
+                .collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    #[test]
+    fn test_index_search() {
+        let index = HeadwordIndex::Sorted(keys());
+        for (query, max_distance) in [("hello", 1), ("hello", 2), ("wrld", 1), ("h", 2)] {
+            let automaton = LevenshteinAutomaton::new(query, max_distance);
+            let expected: Vec<String> = keys()
+                .iter()
+                .filter(|k| levenshtein_distance(query, k) <= max_distance)
+                .map(|k| k.to_string())
+                .collect();
+            assert_eq!(
+                index.search(&automaton, usize::MAX),
+                expected,
+                "{query} {max_distance}"
+            );
+        }
+        assert_eq!(
+            index.search(&Pattern::new("hel*"), usize::MAX),
+            ["hell", "hello", "hellos", "helo", "help"]
+        );
+        assert_eq!(index.search(&Pattern::new("hel*"), 2), ["hell", "hello"]);
+        assert_eq!(index.search(&Pattern::new("caf?"), 10), ["café"]);
+    }
+
+    #[cfg(feature = "fst")]
+    #[test]
+    fn test_build_fst() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db_path = db_path.to_str().unwrap();
+        let handle = crate::db::init_database(db_path).unwrap();
+        for key in keys().iter().map(|k| &**k).chain(["日本語"]) {
+            crate::db::insert_word(&handle.conn, key, "noun", "English", "en", 0).unwrap();
+        }
+        assert!(matches!(
+            HeadwordIndex::load(&handle.conn).unwrap(),
+            HeadwordIndex::Sorted(_)
+        ));
+
+        assert_eq!(build_fst(db_path).unwrap(), 12);
+        assert!(fst_path(std::path::Path::new(db_path)).exists());
+        let index = HeadwordIndex::load(&handle.conn).unwrap();
+        assert!(matches!(index, HeadwordIndex::Fst(_)));
+        let sorted = HeadwordIndex::Sorted(sorted_keys(&handle.conn).unwrap());
+        for automaton in [
+            LevenshteinAutomaton::new("hello", 1),
+            LevenshteinAutomaton::new("cafe", 1),
+            LevenshteinAutomaton::new("日本", 1),
+        ] {
+            assert_eq!(
+                index.search(&automaton, usize::MAX),
+                sorted.search(&automaton, usize::MAX)
+            );
+        }
+        assert_eq!(index.search(&Pattern::new("*ll?"), 10), ["hello", "jello"]);
+
+        let shortest = |key: &str, _| key.len();
+        assert_eq!(
+            index.top_prefixed("hel", 3, shortest),
+            sorted.top_prefixed("hel", 3, shortest)
+        );
+        let keys: Vec<String> = index
+            .top_prefixed("hel", 3, shortest)
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys, ["hell", "helo", "help"]);
+        assert!(index.top_prefixed("xq", 3, shortest).is_empty());
+    }
+}
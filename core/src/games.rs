@@ -0,0 +1,261 @@
+//! Word-game helpers: validity, tile scores and rack solving
+//!
+//! A word is playable when its headword is written in lowercase letters
+//! only, so proper nouns ("Paris"), abbreviations ("e.g.") and phrases are
+//! not. Each playable headword carries an anagram key, its letters without
+//! diacritics in sorted order ("night" and "thing" are both "ghint"),
+//! indexed so [`best_words`] finds the words a rack can make with a few
+//! lookups instead of a scan.
+
+use std::collections::{BTreeMap, HashSet};
+
+use rusqlite::params_from_iter;
+use serde::{Deserialize, Serialize};
+
+use crate::{DictHandle, Error, Result};
+
+/// Longest rack [`best_words`] accepts; every subset of the rack is looked
+/// up, so the work doubles with each letter
+pub const MAX_RACK: usize = 12;
+
+/// Anagram keys looked up per query by [`best_words`]
+const KEYS_PER_QUERY: usize = 500;
+
+/// Tile values of a word game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoringScheme {
+    /// Scrabble, English edition
+    ScrabbleEnglish,
+    /// Scrabble, French edition
+    ScrabbleFrench,
+    /// Words With Friends
+    WordsWithFriends,
+}
+
+impl ScoringScheme {
+    /// Values of the letters a to z
+    fn values(self) -> &'static [u32; 26] {
+        match self {
+            ScoringScheme::ScrabbleEnglish => &SCRABBLE_ENGLISH,
+            ScoringScheme::ScrabbleFrench => &SCRABBLE_FRENCH,
+            ScoringScheme::WordsWithFriends => &WORDS_WITH_FRIENDS,
+        }
+    }
+}
+
+// Tile values of the letters a to z
+#[rustfmt::skip]
+const SCRABBLE_ENGLISH: [u32; 26] = [
+//  a  b  c  d  e  f  g  h  i  j  k  l  m  n  o  p  q   r  s  t  u  v  w  x  y  z
+    1, 3, 3, 2, 1, 4, 2, 4, 1, 8, 5, 1, 3, 1, 1, 3, 10, 1, 1, 1, 1, 4, 4, 8, 4, 10,
+];
+#[rustfmt::skip]
+const SCRABBLE_FRENCH: [u32; 26] = [
+    1, 3, 3, 2, 1, 4, 2, 4, 1, 8, 10, 1, 2, 1, 1, 3, 8, 1, 1, 1, 1, 4, 10, 10, 10, 10,
+];
+#[rustfmt::skip]
+const WORDS_WITH_FRIENDS: [u32; 26] = [
+    1, 4, 4, 2, 1, 4, 3, 3, 1, 10, 5, 2, 4, 2, 1, 4, 10, 1, 1, 1, 2, 5, 4, 8, 3, 10,
+];
+
+/// A word a rack can make, from [`best_words`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameWord {
+    pub word_id: i64,
+    pub word: String,
+    /// Sum of the tile values, without board bonuses
+    pub score: u32,
+}
+
+/// The anagram key of a headword, None if it is not playable
+///
+/// Letters lose their diacritics, so "café" is played as CAFE.
+pub fn anagram_key(word: &str) -> Option<String> {
+    if word.is_empty() || !word.chars().all(|c| c.is_alphabetic() && !c.is_uppercase()) {
+        return None;
+    }
+    let mut letters: Vec<char> = crate::normalize::search_key(word)
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .collect();
+    letters.sort_unstable();
+    Some(letters.into_iter().collect())
+}
+
+/// Tile score of `word` under `scheme`, without board bonuses
+///
+/// Case and diacritics are ignored; letters outside a to z score nothing.
+pub fn score(word: &str, scheme: ScoringScheme) -> u32 {
+    let values = scheme.values();
+    crate::normalize::search_key(word)
+        .bytes()
+        .filter(u8::is_ascii_lowercase)
+        .map(|b| values[(b - b'a') as usize])
+        .sum()
+}
+
+/// Whether `word` can be played in language `lang` (a code such as "en")
+///
+/// True for playable headwords and for inflected forms recorded for an
+/// entry ("cats"), in any case ("CAT" is fine).
+pub fn is_valid_word(handle: &DictHandle, word: &str, lang: &str) -> Result<bool> {
+    let word = word.trim().to_lowercase();
+    let Some(key) = anagram_key(&word) else {
+        return Ok(false);
+    };
+    let mut stmt = handle.conn.prepare_cached(
+        "SELECT EXISTS (SELECT 1 FROM words
+                        WHERE anagram_key = ? AND search_key = ? AND lang_code = ?)",
+    )?;
+    let search_key = handle.normalization.key(&word);
+    if stmt.query_row(rusqlite::params![key, search_key, lang], |row| row.get(0))? {
+        return Ok(true);
+    }
+
+    if !crate::db::table_exists(&handle.conn, "forms")? {
+        return Ok(false);
+    }
+    let mut stmt = handle.conn.prepare_cached(
+        "SELECT EXISTS (SELECT 1 FROM forms f JOIN words w ON w.id = f.word_id
+                        WHERE f.form = ? AND w.lang_code = ?)",
+    )?;
+    Ok(stmt.query_row(rusqlite::params![word, lang], |row| row.get(0))?)
+}
+
+/// The highest-scoring words of language `lang` that can be made from the
+/// letters of `rack`, at most `limit` of them
+///
+/// Words use each tile at most once and have at least two letters; blanks
+/// are not supported. Ties go to longer words, then alphabetical order.
+/// Racks of more than [`MAX_RACK`] letters are rejected.
+pub fn best_words(
+    handle: &DictHandle,
+    rack: &str,
+    lang: &str,
+    scheme: ScoringScheme,
+    limit: usize,
+) -> Result<Vec<GameWord>> {
+    let mut letters: Vec<char> = crate::normalize::search_key(rack)
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .collect();
+    if letters.len() > MAX_RACK {
+        return Err(Error::InvalidInput(format!(
+            "rack has {} letters, at most {MAX_RACK} are supported",
+            letters.len()
+        )));
+    }
+    letters.sort_unstable();
+    let rack_key: String = letters.into_iter().collect();
+
+    let keys = sub_keys(&rack_key);
+    let mut seen = HashSet::new();
+    let mut words = Vec::new();
+    for chunk in keys.chunks(KEYS_PER_QUERY) {
+        let mut stmt = handle.conn.prepare(&format!(
+            "SELECT id, word FROM words WHERE anagram_key IN ({}) AND lang_code = ? ORDER BY id",
+            vec!["?"; chunk.len()].join(",")
+        ))?;
+        let values = chunk.iter().map(String::as_str).chain([lang]);
+        let rows = stmt.query_map(params_from_iter(values), |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (word_id, word) = row?;
+            if seen.insert(word.clone()) {
+                words.push(GameWord {
+                    word_id,
+                    score: score(&word, scheme),
+                    word,
+                });
+            }
+        }
+    }
+
+    words.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| b.word.chars().count().cmp(&a.word.chars().count()))
+            .then_with(|| a.word.cmp(&b.word))
+    });
+    words.truncate(limit);
+    Ok(words)
+}
+
+/// Anagram keys of every sub-multiset of `key`'s letters with at least two
+/// letters
+fn sub_keys(key: &str) -> Vec<String> {
+    let mut counts: BTreeMap<char, usize> = BTreeMap::new();
+    for c in key.chars() {
+        *counts.entry(c).or_insert(0) += 1;
+    }
+    let counts: Vec<(char, usize)> = counts.into_iter().collect();
+
+    let mut keys = vec![String::new()];
+    for (letter, count) in counts {
+        keys = keys
+            .into_iter()
+            .flat_map(|prefix| {
+                (0..=count).map(move |n| {
+                    let mut key = prefix.clone();
+                    key.extend(std::iter::repeat_n(letter, n));
+                    key
+                })
+            })
+            .collect();
+    }
+    keys.retain(|k| k.chars().count() >= 2);
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_database, insert_form, insert_word};
+
+    #[test]
+    fn test_anagram_key_and_score() {
+        assert_eq!(anagram_key("night").as_deref(), Some("ghint"));
+        assert_eq!(anagram_key("café").as_deref(), Some("acef"));
+        assert_eq!(anagram_key("Paris"), None);
+        assert_eq!(anagram_key("ice cream"), None);
+        assert_eq!(score("quiz", ScoringScheme::ScrabbleEnglish), 22);
+        assert_eq!(score("QUIZ", ScoringScheme::WordsWithFriends), 23);
+        assert_eq!(score("kiwi", ScoringScheme::ScrabbleFrench), 22);
+        assert_eq!(sub_keys("aab"), ["ab", "aa", "aab"]);
+    }
+
+    #[test]
+    fn test_words() {
+        let dir = tempfile::tempdir().unwrap();
+        let handle = init_database(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        for word in ["cat", "act", "at", "taco", "Tac", "coat", "a"] {
+            insert_word(&handle.conn, word, "noun", "English", "en", 0).unwrap();
+        }
+        let cat = insert_word(&handle.conn, "cat", "verb", "English", "en", 0).unwrap();
+        insert_form(&handle.conn, cat, "cats", &[]).unwrap();
+        insert_word(&handle.conn, "tac", "noun", "French", "fr", 0).unwrap();
+
+        assert!(is_valid_word(&handle, "CAT", "en").unwrap());
+        assert!(is_valid_word(&handle, "cats", "en").unwrap());
+        assert!(!is_valid_word(&handle, "tac", "en").unwrap());
+        assert!(is_valid_word(&handle, "tac", "fr").unwrap());
+        assert!(!is_valid_word(&handle, "cta", "en").unwrap());
+
+        let words = best_words(&handle, "TAC", "en", ScoringScheme::ScrabbleEnglish, 10).unwrap();
+        let words: Vec<(&str, u32)> = words.iter().map(|w| (w.word.as_str(), w.score)).collect();
+        assert_eq!(words, [("act", 5), ("cat", 5), ("at", 2)]);
+        let words = best_words(&handle, "coat", "en", ScoringScheme::ScrabbleEnglish, 2).unwrap();
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[0].word, "coat");
+        assert!(best_words(
+            &handle,
+            "abcdefghijklm",
+            "en",
+            ScoringScheme::ScrabbleEnglish,
+            5
+        )
+        .is_err());
+    }
+}
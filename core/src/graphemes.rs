@@ -0,0 +1,117 @@
+//! Grapheme-aware text utilities
+//!
+//! What a reader sees as one character can be several `char`s: "é" may be
+//! "e" followed by a combining accent, and a flag is two regional
+//! indicators. Cutting between them leaves a stray accent or half a flag,
+//! so previews are truncated and highlights placed on grapheme cluster
+//! boundaries (Unicode UAX #29) here.
+//!
+//! Highlights match the query the way search does, with case and
+//! diacritics folded (see [`Normalization`]), but are given as byte ranges
+//! of the original text, so "cafe" highlights "Café" whole.
+
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::normalize::Normalization;
+
+/// The longest prefix of `text` made of whole grapheme clusters and at
+/// most `max_len` bytes long
+pub fn truncate(text: &str, max_len: usize) -> &str {
+    if text.len() <= max_len {
+        return text;
+    }
+    let end = text
+        .grapheme_indices(true)
+        .map(|(start, g)| start + g.len())
+        .take_while(|&end| end <= max_len)
+        .last()
+        .unwrap_or(0);
+    &text[..end]
+}
+
+/// Number of grapheme clusters in `text`, the characters a reader counts
+pub fn count(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+/// Byte ranges of `text` where `query` occurs, both folded under
+/// `normalization`, in order and without overlaps
+///
+/// Ranges cover whole grapheme clusters: a match that starts or ends
+/// inside one (the "e" of "æ", folded to "ae") takes in all of it. An
+/// empty query matches nothing.
+pub fn highlight(text: &str, query: &str, normalization: Normalization) -> Vec<Range<usize>> {
+    let query = normalization.key(query.trim());
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    // The folded text, and where each grapheme starts in it and in `text`
+    let mut folded = String::with_capacity(text.len());
+    let mut starts: Vec<(usize, usize)> = Vec::new();
+    for (start, grapheme) in text.grapheme_indices(true) {
+        starts.push((folded.len(), start));
+        folded.push_str(&normalization.key(grapheme));
+    }
+    starts.push((folded.len(), text.len()));
+
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for (begin, matched) in folded.match_indices(&query) {
+        let end = begin + matched.len();
+        let first = starts.partition_point(|&(folded, _)| folded <= begin) - 1;
+        let last = starts.partition_point(|&(folded, _)| folded < end);
+        let range = starts[first].1..starts[last].1;
+        match ranges.last_mut() {
+            Some(previous) if previous.end > range.start => previous.end = range.end,
+            _ => ranges.push(range),
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("short", 100), "short");
+        // "e" and a combining acute accent are one grapheme
+        let decomposed = "cafe\u{301} au lait";
+        assert_eq!(truncate(decomposed, 4), "caf");
+        assert_eq!(truncate(decomposed, 6), "cafe\u{301}");
+        // A flag is two regional indicators of four bytes each
+        assert_eq!(truncate("🇫🇷 France", 6), "");
+        assert_eq!(truncate("🇫🇷 France", 9), "🇫🇷 ");
+        assert_eq!(count("🇫🇷 cafe\u{301}"), 6);
+    }
+
+    /// The parts of `text` highlighted for `query`
+    fn matched<'a>(text: &'a str, query: &str, normalization: Normalization) -> Vec<&'a str> {
+        highlight(text, query, normalization)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect()
+    }
+
+    #[test]
+    fn test_highlight() {
+        let text = "Café, or CAFE: a small restaurant";
+        assert_eq!(matched(text, "cafe", Normalization::Fold), ["Café", "CAFE"]);
+        assert_eq!(matched(text, "cafe", Normalization::Case), ["CAFE"]);
+        assert_eq!(highlight(text, "CAFÉ", Normalization::Case)[0], 0..5);
+
+        let decomposed = "re\u{301}sume\u{301}";
+        assert_eq!(
+            matched(decomposed, "resume", Normalization::Fold),
+            [decomposed]
+        );
+        assert_eq!(matched(decomposed, "sum", Normalization::Fold), ["sum"]);
+
+        // Part of a grapheme that folds to several letters takes all of it
+        assert_eq!(matched("Encyclopædia", "ed", Normalization::Fold), ["æd"]);
+        assert!(highlight("Encyclopædia", " ", Normalization::Fold).is_empty());
+    }
+}
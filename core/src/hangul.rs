@@ -0,0 +1,161 @@
+//! Hangul decomposition for incremental Korean search
+//!
+//! While a word is being typed, the input method shows incomplete
+//! syllables: typing 한국어 passes through ㅎ, 하, 한, 한ㄱ, 한구, 한국, ...
+//! and a final consonant may later move to the next syllable (간 becomes
+//! 가나). Comparing syllables would miss most of these, so both indexed
+//! words and queries are decomposed into sequences of basic jamo, where
+//! every intermediate state is a prefix of the finished word.
+
+/// First precomposed Hangul syllable (가)
+const SYLLABLE_BASE: u32 = 0xAC00;
+
+/// Last precomposed Hangul syllable (힣)
+const SYLLABLE_LAST: u32 = 0xD7A3;
+
+/// Number of syllables per initial consonant (21 vowels x 28 finals)
+const SYLLABLES_PER_INITIAL: u32 = 588;
+
+/// Number of final consonant slots, including none
+const FINALS: u32 = 28;
+
+const INITIAL_JAMO: [char; 19] = [
+    'ㄱ', 'ㄲ', 'ㄴ', 'ㄷ', 'ㄸ', 'ㄹ', 'ㅁ', 'ㅂ', 'ㅃ', 'ㅅ', 'ㅆ', 'ㅇ', 'ㅈ', 'ㅉ', 'ㅊ', 'ㅋ',
+    'ㅌ', 'ㅍ', 'ㅎ',
+];
+
+const VOWEL_JAMO: [char; 21] = [
+    'ㅏ', 'ㅐ', 'ㅑ', 'ㅒ', 'ㅓ', 'ㅔ', 'ㅕ', 'ㅖ', 'ㅗ', 'ㅘ', 'ㅙ', 'ㅚ', 'ㅛ', 'ㅜ', 'ㅝ', 'ㅞ',
+    'ㅟ', 'ㅠ', 'ㅡ', 'ㅢ', 'ㅣ',
+];
+
+/// Final consonants; index 0 is "no final"
+const FINAL_JAMO: [Option<char>; 28] = [
+    None,
+    Some('ㄱ'),
+    Some('ㄲ'),
+    Some('ㄳ'),
+    Some('ㄴ'),
+    Some('ㄵ'),
+    Some('ㄶ'),
+    Some('ㄷ'),
+    Some('ㄹ'),
+    Some('ㄺ'),
+    Some('ㄻ'),
+    Some('ㄼ'),
+    Some('ㄽ'),
+    Some('ㄾ'),
+    Some('ㄿ'),
+    Some('ㅀ'),
+    Some('ㅁ'),
+    Some('ㅂ'),
+    Some('ㅄ'),
+    Some('ㅅ'),
+    Some('ㅆ'),
+    Some('ㅇ'),
+    Some('ㅈ'),
+    Some('ㅊ'),
+    Some('ㅋ'),
+    Some('ㅌ'),
+    Some('ㅍ'),
+    Some('ㅎ'),
+];
+
+/// Whether `c` is a precomposed Hangul syllable or a compatibility jamo
+pub fn is_hangul(c: char) -> bool {
+    matches!(c as u32, SYLLABLE_BASE..=SYLLABLE_LAST | 0x3131..=0x318E)
+}
+
+/// Whether `text` contains any Hangul
+pub fn contains_hangul(text: &str) -> bool {
+    text.chars().any(is_hangul)
+}
+
+/// Decompose Hangul into a sequence of basic jamo
+///
+/// Syllables are split into initial, vowel and final; compound vowels and
+/// consonant clusters are split further (ㅘ into ㅗㅏ, ㄳ into ㄱㅅ) since
+/// they are typed as two keys. Other characters are kept as they are.
+pub fn decompose(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() * 2);
+    for c in text.chars() {
+        let code = c as u32;
+        if (SYLLABLE_BASE..=SYLLABLE_LAST).contains(&code) {
+            let index = code - SYLLABLE_BASE;
+            push_jamo(
+                &mut out,
+                INITIAL_JAMO[(index / SYLLABLES_PER_INITIAL) as usize],
+            );
+            push_jamo(
+                &mut out,
+                VOWEL_JAMO[((index % SYLLABLES_PER_INITIAL) / FINALS) as usize],
+            );
+            if let Some(last) = FINAL_JAMO[(index % FINALS) as usize] {
+                push_jamo(&mut out, last);
+            }
+        } else {
+            push_jamo(&mut out, c);
+        }
+    }
+    out
+}
+
+/// Push a jamo, splitting compound vowels and consonant clusters
+fn push_jamo(out: &mut String, jamo: char) {
+    let parts: &[char] = match jamo {
+        'ㅘ' => &['ㅗ', 'ㅏ'],
+        'ㅙ' => &['ㅗ', 'ㅐ'],
+        'ㅚ' => &['ㅗ', 'ㅣ'],
+        'ㅝ' => &['ㅜ', 'ㅓ'],
+        'ㅞ' => &['ㅜ', 'ㅔ'],
+        'ㅟ' => &['ㅜ', 'ㅣ'],
+        'ㅢ' => &['ㅡ', 'ㅣ'],
+        'ㄳ' => &['ㄱ', 'ㅅ'],
+        'ㄵ' => &['ㄴ', 'ㅈ'],
+        'ㄶ' => &['ㄴ', 'ㅎ'],
+        'ㄺ' => &['ㄹ', 'ㄱ'],
+        'ㄻ' => &['ㄹ', 'ㅁ'],
+        'ㄼ' => &['ㄹ', 'ㅂ'],
+        'ㄽ' => &['ㄹ', 'ㅅ'],
+        'ㄾ' => &['ㄹ', 'ㅌ'],
+        'ㄿ' => &['ㄹ', 'ㅍ'],
+        'ㅀ' => &['ㄹ', 'ㅎ'],
+        'ㅄ' => &['ㅂ', 'ㅅ'],
+        _ => {
+            out.push(jamo);
+            return;
+        }
+    };
+    out.extend(parts);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decompose() {
+        assert_eq!(decompose("한국어"), "ㅎㅏㄴㄱㅜㄱㅇㅓ");
+        assert_eq!(decompose("ㅎㅏㄴ"), "ㅎㅏㄴ");
+        assert_eq!(decompose("화"), "ㅎㅗㅏ");
+        assert_eq!(decompose("닭"), "ㄷㅏㄹㄱ");
+        assert_eq!(decompose("K팝"), "Kㅍㅏㅂ");
+    }
+
+    #[test]
+    fn test_typing_states_are_prefixes() {
+        let word = decompose("한국어");
+        for state in ["ㅎ", "하", "한", "한ㄱ", "한구", "한국", "한국ㅇ", "한국어"] {
+            assert!(word.starts_with(&decompose(state)), "{state}");
+        }
+        // A final consonant that moves to the next syllable
+        assert!(decompose("가나").starts_with(&decompose("간")));
+    }
+
+    #[test]
+    fn test_contains_hangul() {
+        assert!(contains_hangul("한국어"));
+        assert!(contains_hangul("ㅎ"));
+        assert!(!contains_hangul("hangul"));
+    }
+}
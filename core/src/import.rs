@@ -4,22 +4,37 @@
 //! (as exported from Wiktionary via kaikki.org) into the SQLite database.
 //!
 //! Supports both raw JSONL and gzip-compressed JSONL files (.jsonl.gz).
+//!
+//! Progress is reported through a `(current_line, total_lines)` callback,
+//! or as typed [`ImportEvent`]s (phases, progress, per-line warnings and
+//! the final statistics) with [`import_from_jsonl_with_events`], or over a
+//! channel from a background thread with [`spawn_import`].
 
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
+use std::io::Read;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use flate2::read::GzDecoder;
-use rusqlite::Connection;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
 
 use crate::db::{
-    insert_definition, insert_etymology, insert_pronunciation, insert_translation, insert_word,
+    insert_definition, insert_etymology, insert_form, insert_pinyin, insert_pronunciation,
+    insert_relation, insert_translation, insert_word, set_meta,
 };
-use crate::models::{RawSound, RawWordEntry};
-use crate::Result;
+use crate::models::{RawSense, RawSound, RawWordEntry, SourceInfo};
+use crate::normalize::Normalization;
+use crate::{Error, Result};
 
 /// Import statistics returned after processing
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ImportStats {
     /// Total number of lines processed
     pub lines_processed: u64,
@@ -33,12 +48,319 @@ pub struct ImportStats {
     pub etymologies_imported: u64,
     /// Number of translations imported
     pub translations_imported: u64,
+    /// Number of inflected/alternative forms imported
+    pub forms_imported: u64,
+    /// Number of synonyms and antonyms imported
+    pub relations_imported: u64,
+    /// Number of form-of senses ("plural of cat") linked to their target
+    pub form_of_linked: u64,
     /// Number of errors encountered
     pub errors: u64,
-    /// Number of skipped entries (e.g., empty lines)
+    /// Number of skipped entries (e.g., empty lines, or entries left out
+    /// by `ImportOptions::keep_words`)
     pub skipped: u64,
+    /// Bytes read from the input file (compressed bytes for .jsonl.gz),
+    /// not counting the pass that counts lines
+    pub bytes_read: u64,
+    /// Longest time spent inserting one batch of parsed lines
+    pub peak_batch_micros: u64,
+    /// Time spent in each phase
+    pub timings: ImportTimings,
+}
+
+/// Something that happened during an import, for live logs and progress
+/// bars
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ImportEvent {
+    PhaseStarted {
+        phase: ImportPhase,
+    },
+    /// Lines processed so far, out of `total`
+    Progress {
+        lines: u64,
+        total: u64,
+    },
+    /// A line that could not be imported and was skipped; lines are
+    /// numbered among those imported, so from the sample when sampling
+    Warning {
+        line: u64,
+        message: String,
+    },
+    Finished {
+        stats: ImportStats,
+    },
+}
+
+/// Phases of an import, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportPhase {
+    /// Counting the input lines
+    Counting,
+    /// Reading, parsing and writing entries
+    Importing,
+    /// Linking form-of senses and writing build metadata
+    Finalizing,
+}
+
+/// Time spent in each phase of an import
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportTimings {
+    /// Counting the input lines, for progress and random samples
+    pub counting_micros: u64,
+    /// Reading and parsing lines
+    pub parsing_micros: u64,
+    /// Writing entries, including the periodic commits
+    pub inserting_micros: u64,
+    /// Linking form-of senses, writing build metadata and committing
+    pub finalizing_micros: u64,
+}
+
+/// Result of joining an external frequency list onto the words table
+#[derive(Debug, Clone, Default)]
+pub struct FrequencyStats {
+    /// Distinct words in the frequency list
+    pub list_entries: u64,
+    /// Frequency list words that matched at least one headword
+    pub list_entries_matched: u64,
+    /// Lines that could not be parsed as `word,count`
+    pub invalid_lines: u64,
+    /// Total word entries in the database
+    pub words_total: u64,
+    /// Word entries that received a frequency
+    pub words_matched: u64,
+}
+
+impl FrequencyStats {
+    /// Share of word entries that received a frequency, in percent
+    pub fn match_rate(&self) -> f64 {
+        if self.words_total == 0 {
+            0.0
+        } else {
+            self.words_matched as f64 / self.words_total as f64 * 100.0
+        }
+    }
+}
+
+/// Result of merging dictionaries with [`merge_databases`]
+#[derive(Debug, Clone, Default)]
+pub struct MergeStats {
+    /// Number of source databases merged
+    pub databases_merged: u64,
+    /// Entries copied as entries of their own
+    pub words_added: u64,
+    /// Entries folded into an existing entry with the same headword, part
+    /// of speech and language
+    pub words_merged: u64,
+    /// Number of definitions copied
+    pub definitions_added: u64,
+}
+
+/// Options controlling which content is written during import
+///
+/// Content classes that are switched off are skipped while parsing rather
+/// than deleted afterwards, so smaller builds are also faster to produce.
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    /// Import etymology text
+    pub etymologies: bool,
+    /// Import translations
+    pub translations: bool,
+    /// Import example sentences attached to definitions
+    pub examples: bool,
+    /// Skip examples that repeat an earlier one of the same sense apart
+    /// from case, diacritics, punctuation or spacing
+    pub dedup_examples: bool,
+    /// Skip examples longer than this many characters (`None` = keep all)
+    pub max_example_len: Option<usize>,
+    /// Import inflected and alternative forms (used for lemmatization)
+    pub forms: bool,
+    /// Import synonyms and antonyms (used by the thesaurus)
+    pub relations: bool,
+    /// Number of threads used to parse JSON (1 = parse on the import thread)
+    ///
+    /// Inserts always happen on a single connection; parsing is the part of
+    /// the import that scales with cores.
+    pub jobs: usize,
+    /// Only import this many lines of the input (`None` = all)
+    ///
+    /// Used to produce small test databases from the full dump.
+    pub sample: Option<u64>,
+    /// Pick the `sample` lines uniformly at random with this seed instead of
+    /// taking the first ones; the same seed always selects the same lines
+    pub seed: Option<u64>,
+    /// Published version of the dictionary, recorded as `dict_version` in
+    /// the `meta` table so installed copies can be checked for updates
+    pub dict_version: Option<String>,
+    /// Source and license of the imported entries, shown as attribution
+    /// with every entry (see `FullDefinition::attribution`)
+    pub source: SourceInfo,
+    /// Layout of the full-text index, see `FtsProfile`
+    pub fts: FtsProfile,
+    /// How headwords and queries are folded, recorded in the `meta` table
+    pub normalization: Normalization,
+    /// Only import entries whose headword is in this set, or that are an
+    /// inflection or variant of one (a form-of or alt-of sense: "went"
+    /// for "go"); words are lowercase (see [`read_wordlist`]) and compared
+    /// ignoring case. `None` imports every entry
+    pub keep_words: Option<HashSet<String>>,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            etymologies: true,
+            translations: true,
+            examples: true,
+            dedup_examples: true,
+            max_example_len: None,
+            forms: true,
+            relations: true,
+            jobs: 1,
+            sample: None,
+            seed: None,
+            dict_version: None,
+            source: SourceInfo::default(),
+            fts: FtsProfile::Full,
+            normalization: Normalization::Fold,
+            keep_words: None,
+        }
+    }
+}
+
+impl ImportOptions {
+    /// Options for the "lite" build used by the free tier of the app
+    ///
+    /// Keeps headwords, definitions and pronunciations only.
+    pub fn lite() -> Self {
+        Self {
+            etymologies: false,
+            translations: false,
+            examples: false,
+            forms: false,
+            relations: false,
+            ..Self::default()
+        }
+    }
+
+    /// Names of the content classes this configuration leaves out
+    fn omitted_content(&self) -> Vec<&'static str> {
+        let mut omitted = Vec::new();
+        if !self.etymologies {
+            omitted.push("etymologies");
+        }
+        if !self.translations {
+            omitted.push("translations");
+        }
+        if !self.examples {
+            omitted.push("examples");
+        }
+        if !self.forms {
+            omitted.push("forms");
+        }
+        if !self.relations {
+            omitted.push("relations");
+        }
+        omitted
+    }
+
+    /// Description of the sampling mode for the `sample` meta key
+    fn sample_description(&self) -> Option<String> {
+        let size = self.sample?;
+        Some(match self.seed {
+            Some(seed) => format!("{} random (seed {})", size, seed),
+            None => format!("first {}", size),
+        })
+    }
+}
+
+/// Layout of the `words_fts` full-text index
+///
+/// Search only runs prefix queries against the index and orders by rank,
+/// so it needs neither token positions nor a copy of the headwords; the
+/// index reads them from `words` either way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FtsProfile {
+    /// Token positions stored, as the schema creates it
+    #[default]
+    Full,
+    /// Token positions left out (`detail=none`), for a smaller download;
+    /// phrase and NEAR queries fail against it
+    Compact,
+}
+
+impl FtsProfile {
+    /// Name recorded as `fts_profile` in the `meta` table
+    pub fn name(self) -> &'static str {
+        match self {
+            FtsProfile::Full => "full",
+            FtsProfile::Compact => "compact",
+        }
+    }
+}
+
+/// Recreate `words_fts` with the layout of `profile` and the tokenizer of
+/// `normalization`, unless the schema's defaults are wanted, and index the
+/// existing words
+fn create_fts_table(
+    conn: &Connection,
+    profile: FtsProfile,
+    normalization: Normalization,
+) -> Result<()> {
+    if profile == FtsProfile::Full && normalization == Normalization::Fold {
+        return Ok(());
+    }
+    let detail = match profile {
+        FtsProfile::Full => "full",
+        FtsProfile::Compact => "none",
+    };
+    conn.execute_batch(&format!(
+        "DROP TABLE words_fts;
+         CREATE VIRTUAL TABLE words_fts USING fts5(
+             word,
+             content='words',
+             content_rowid='id',
+             detail={detail},
+             tokenize='{}'
+         );
+         INSERT INTO words_fts(words_fts) VALUES('rebuild');",
+        normalization.tokenizer()
+    ))?;
+    Ok(())
 }
 
+/// Tags marking `forms` rows that are not alternative spellings of the word
+const NON_FORM_TAGS: &[&str] = &[
+    "table-tags",
+    "inflection-template",
+    "class",
+    "romanization",
+    "hiragana",
+    "katakana",
+];
+
+/// Last words of glosses that only point at another word, as in
+/// "plural of", "past tense of", "alternative spelling of"
+const FORM_OF_HEADS: &[&str] = &[
+    "form",
+    "plural",
+    "singular",
+    "tense",
+    "participle",
+    "spelling",
+    "comparative",
+    "superlative",
+    "gerund",
+    "inflection",
+    "indicative",
+    "subjunctive",
+    "imperative",
+];
+
+/// Number of lines read and parsed together before inserting
+const PARSE_BATCH_SIZE: usize = 4096;
+
 /// Import dictionary data from a JSONL file
 ///
 /// Each line in the JSONL file should be a valid JSON object representing
@@ -66,16 +388,80 @@ pub fn import_from_jsonl_with_stats(
     db_path: &str,
     jsonl_path: &str,
     progress: impl Fn(u64, u64),
+) -> Result<ImportStats> {
+    import_from_jsonl_with_options(db_path, jsonl_path, &ImportOptions::default(), progress)
+}
+
+/// Import dictionary data from a JSONL file with custom options
+///
+/// Same as `import_from_jsonl_with_stats` but only writes the content
+/// classes enabled in `options`.
+pub fn import_from_jsonl_with_options(
+    db_path: &str,
+    jsonl_path: &str,
+    options: &ImportOptions,
+    progress: impl Fn(u64, u64),
+) -> Result<ImportStats> {
+    import_from_jsonl_with_events(db_path, jsonl_path, options, |event| {
+        if let ImportEvent::Progress { lines, total } = event {
+            progress(lines, total);
+        }
+    })
+}
+
+/// Run [`import_from_jsonl_with_events`] on a new thread
+///
+/// Events arrive on the returned channel until the import ends; the
+/// thread's result is the import's. Dropping the receiver does not stop
+/// the import.
+pub fn spawn_import(
+    db_path: &str,
+    jsonl_path: &str,
+    options: ImportOptions,
+) -> (
+    mpsc::Receiver<ImportEvent>,
+    thread::JoinHandle<Result<ImportStats>>,
+) {
+    let (sender, receiver) = mpsc::channel();
+    let (db_path, jsonl_path) = (db_path.to_string(), jsonl_path.to_string());
+    let handle = thread::spawn(move || {
+        import_from_jsonl_with_events(&db_path, &jsonl_path, &options, |event| {
+            // The receiver may be gone; the import carries on regardless
+            let _ = sender.send(event);
+        })
+    });
+    (receiver, handle)
+}
+
+/// Import dictionary data from a JSONL file, reporting typed events
+///
+/// Same as `import_from_jsonl_with_options`, but `events` also hears when
+/// each phase starts, about every skipped line and, with the statistics,
+/// when the import finished.
+pub fn import_from_jsonl_with_events(
+    db_path: &str,
+    jsonl_path: &str,
+    options: &ImportOptions,
+    mut events: impl FnMut(ImportEvent),
 ) -> Result<ImportStats> {
     let path = Path::new(jsonl_path);
     let is_gzipped = path.extension().map(|ext| ext == "gz").unwrap_or(false);
 
-    // Count total lines for progress reporting
-    let total_lines = if is_gzipped {
-        count_lines_gzipped(jsonl_path)?
-    } else {
-        count_lines(jsonl_path)?
+    // Count total lines for progress reporting and random sampling. Taking
+    // the first N lines needs neither, so skip the full pass over the input.
+    events(ImportEvent::PhaseStarted {
+        phase: ImportPhase::Counting,
+    });
+    let counting_start = Instant::now();
+    let total_lines = {
+        trace_span!("import_count_lines", is_gzipped);
+        match (options.sample, options.seed) {
+            (Some(size), None) => size,
+            _ if is_gzipped => count_lines_gzipped(jsonl_path)?,
+            _ => count_lines(jsonl_path)?,
+        }
     };
+    let counting_micros = counting_start.elapsed().as_micros() as u64;
 
     // Open database with write access
     let conn = Connection::open(db_path)?;
@@ -84,10 +470,29 @@ pub fn import_from_jsonl_with_stats(
     configure_for_import(&conn)?;
 
     // Create schema if needed
-    conn.execute_batch(include_str!("../sql/schema.sql").trim_start_matches('\u{feff}'))?;
+    {
+        trace_span!("import_schema");
+        conn.execute_batch(include_str!("../sql/schema.sql").trim_start_matches('\u{feff}'))?;
+        crate::db::upgrade_schema(&conn)?;
+        record_normalization(&conn, options.normalization)?;
+        create_fts_table(&conn, options.fts, options.normalization)?;
+    }
+
+    // Entries of the primary (first) source need no per-entry row
+    let source_id = crate::db::insert_source(&conn, &options.source)?;
+    let primary_source: i64 =
+        conn.query_row("SELECT MIN(id) FROM sources", [], |row| row.get(0))?;
+    let entry_source = EntrySource {
+        id: source_id,
+        primary: source_id == primary_source,
+    };
 
     // Open JSONL file (handle gzip)
-    let file = File::open(jsonl_path)?;
+    let bytes_read = Rc::new(Cell::new(0));
+    let file = CountingReader {
+        inner: File::open(jsonl_path)?,
+        count: Rc::clone(&bytes_read),
+    };
     let reader: Box<dyn BufRead> = if is_gzipped {
         Box::new(BufReader::new(GzDecoder::new(file)))
     } else {
@@ -96,67 +501,138 @@ pub fn import_from_jsonl_with_stats(
 
     // Begin transaction for better performance
     conn.execute_batch("BEGIN TRANSACTION")?;
+    events(ImportEvent::PhaseStarted {
+        phase: ImportPhase::Importing,
+    });
 
     let mut stats = ImportStats::default();
-
-    for line_result in reader.lines() {
-        stats.lines_processed += 1;
-
-        // Report progress periodically
-        if stats.lines_processed % 1000 == 0 {
-            progress(stats.lines_processed, total_lines);
-        }
-
-        let line = match line_result {
-            Ok(l) => l,
-            Err(_) => {
-                stats.errors += 1;
-                continue;
+    stats.timings.counting_micros = counting_micros;
+    let lines = reader.lines();
+    let (mut lines, total_lines): (Box<dyn Iterator<Item = std::io::Result<String>>>, u64) =
+        match (options.sample, options.seed) {
+            (None, _) => (Box::new(lines), total_lines),
+            (Some(size), None) => (Box::new(lines.take(size as usize)), size),
+            (Some(size), Some(seed)) => {
+                let mut sampler = LineSampler::new(size, total_lines, seed);
+                (
+                    Box::new(lines.filter(move |_| sampler.select())),
+                    size.min(total_lines),
+                )
             }
         };
 
-        // Skip empty lines
-        if line.trim().is_empty() {
-            stats.skipped += 1;
-            continue;
+    loop {
+        let parsing_start = Instant::now();
+        let batch: Vec<std::io::Result<String>> = lines.by_ref().take(PARSE_BATCH_SIZE).collect();
+        if batch.is_empty() {
+            break;
         }
+        trace_span!(
+            "import_batch",
+            first_line = stats.lines_processed + 1,
+            lines = batch.len()
+        );
+        let parsed_batch = parse_batch(&batch, options.jobs);
+        stats.timings.parsing_micros += parsing_start.elapsed().as_micros() as u64;
 
-        // Parse JSON
-        let entry: RawWordEntry = match serde_json::from_str(&line) {
-            Ok(e) => e,
-            Err(e) => {
-                log::debug!("JSON parse error at line {}: {}", stats.lines_processed, e);
-                stats.errors += 1;
-                continue;
+        let inserting_start = Instant::now();
+        for parsed in parsed_batch {
+            stats.lines_processed += 1;
+
+            // Report progress periodically
+            if stats.lines_processed % 1000 == 0 {
+                events(ImportEvent::Progress {
+                    lines: stats.lines_processed,
+                    total: total_lines,
+                });
             }
-        };
 
-        // Import the entry
-        match import_entry_with_stats(&conn, &entry) {
-            Ok(entry_stats) => {
-                stats.words_imported += 1;
-                stats.definitions_imported += entry_stats.definitions;
-                stats.pronunciations_imported += entry_stats.pronunciations;
-                stats.etymologies_imported += entry_stats.etymologies;
-                stats.translations_imported += entry_stats.translations;
+            let entry = match parsed {
+                ParsedLine::Entry(entry) => entry,
+                ParsedLine::Empty => {
+                    stats.skipped += 1;
+                    continue;
+                }
+                ParsedLine::ReadError => {
+                    events(ImportEvent::Warning {
+                        line: stats.lines_processed,
+                        message: "Could not read the line".to_string(),
+                    });
+                    stats.errors += 1;
+                    continue;
+                }
+                ParsedLine::Invalid(e) => {
+                    log::debug!("JSON parse error at line {}: {}", stats.lines_processed, e);
+                    events(ImportEvent::Warning {
+                        line: stats.lines_processed,
+                        message: format!("Invalid JSON: {e}"),
+                    });
+                    stats.errors += 1;
+                    continue;
+                }
+            };
+
+            if let Some(words) = &options.keep_words {
+                if !keeps_entry(words, &entry) {
+                    stats.skipped += 1;
+                    continue;
+                }
             }
-            Err(e) => {
-                log::debug!("Import error at line {}: {}", stats.lines_processed, e);
-                stats.errors += 1;
+
+            // Import the entry
+            match import_entry_with_stats(&conn, &entry, options, entry_source) {
+                Ok(entry_stats) => {
+                    stats.words_imported += 1;
+                    stats.definitions_imported += entry_stats.definitions;
+                    stats.pronunciations_imported += entry_stats.pronunciations;
+                    stats.etymologies_imported += entry_stats.etymologies;
+                    stats.translations_imported += entry_stats.translations;
+                    stats.forms_imported += entry_stats.forms;
+                    stats.relations_imported += entry_stats.relations;
+                }
+                Err(e) => {
+                    log::debug!("Import error at line {}: {}", stats.lines_processed, e);
+                    events(ImportEvent::Warning {
+                        line: stats.lines_processed,
+                        message: e.to_string(),
+                    });
+                    stats.errors += 1;
+                }
             }
-        }
 
-        // Commit periodically to avoid huge transactions
-        if stats.lines_processed % 10000 == 0 {
-            conn.execute_batch("COMMIT; BEGIN TRANSACTION")?;
+            // Commit periodically to avoid huge transactions
+            if stats.lines_processed % 10000 == 0 {
+                conn.execute_batch("COMMIT; BEGIN TRANSACTION")?;
+            }
         }
+        let batch_micros = inserting_start.elapsed().as_micros() as u64;
+        stats.timings.inserting_micros += batch_micros;
+        stats.peak_batch_micros = stats.peak_batch_micros.max(batch_micros);
     }
+    stats.bytes_read = bytes_read.get();
+
+    // Link "plural of cat" senses now that every target is imported
+    events(ImportEvent::PhaseStarted {
+        phase: ImportPhase::Finalizing,
+    });
+    let finalizing_start = Instant::now();
+    stats.form_of_linked = {
+        trace_span!("import_link_form_of");
+        crate::db::resolve_form_of(&conn)?
+    };
+
+    // Record build metadata alongside the data
+    write_build_metadata(&conn, path, options)?;
 
     // Final commit
     conn.execute_batch("COMMIT")?;
+    stats.timings.finalizing_micros = finalizing_start.elapsed().as_micros() as u64;
 
     // Final progress update
-    progress(stats.lines_processed, total_lines);
+    events(ImportEvent::Progress {
+        lines: stats.lines_processed,
+        total: total_lines,
+    });
 
     // Log import statistics
     log::info!(
@@ -167,9 +643,625 @@ pub fn import_from_jsonl_with_stats(
         stats.errors
     );
 
+    events(ImportEvent::Finished {
+        stats: stats.clone(),
+    });
     Ok(stats)
 }
 
+/// Read a word list for `ImportOptions::keep_words`
+///
+/// The list has one headword per line; blank lines and lines starting with
+/// `#` are skipped. Words are lowercased.
+pub fn read_wordlist(path: &str) -> Result<HashSet<String>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut words = HashSet::new();
+    for line in reader.lines() {
+        let line = line?;
+        let word = line.trim();
+        if !word.is_empty() && !word.starts_with('#') {
+            words.insert(word.to_lowercase());
+        }
+    }
+    Ok(words)
+}
+
+/// Whether `entry` is kept by a `keep_words` list: its headword is listed,
+/// or one of its senses is an inflection or variant of a listed word
+fn keeps_entry(words: &HashSet<String>, entry: &RawWordEntry) -> bool {
+    let listed = |word: &str| words.contains(&word.to_lowercase());
+    listed(&entry.word)
+        || entry.senses.iter().any(|sense| {
+            sense
+                .form_of
+                .iter()
+                .chain(&sense.alt_of)
+                .any(|target| listed(&target.word))
+        })
+}
+
+/// Fill `words.frequency` from an external frequency list
+///
+/// The list has one entry per line with the word in the first column and
+/// its count in the last, separated by commas, tabs or spaces (e.g.
+/// `word,count` CSV); a header line is skipped. Headwords are matched
+/// exactly first and case-insensitively otherwise, so "Paris" still picks
+/// up the count listed for "paris".
+/// Databases built before the column existed are migrated in place.
+pub fn apply_frequency_list(db_path: &str, list_path: &str) -> Result<FrequencyStats> {
+    let mut stats = FrequencyStats::default();
+
+    let mut exact: HashMap<String, u64> = HashMap::new();
+    let reader = BufReader::new(File::open(list_path)?);
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_frequency_line(&line) {
+            Some((word, count)) => *exact.entry(word.to_string()).or_insert(0) += count,
+            // Tolerate a header row such as "word,count"
+            None if i == 0 => {}
+            None => stats.invalid_lines += 1,
+        }
+    }
+    stats.list_entries = exact.len() as u64;
+
+    let mut folded: HashMap<String, u64> = HashMap::new();
+    for (word, count) in &exact {
+        *folded.entry(word.to_lowercase()).or_insert(0) += count;
+    }
+
+    let conn = Connection::open(db_path)?;
+    crate::db::upgrade_schema(&conn)?;
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("UPDATE words SET frequency = NULL", [])?;
+    let mut matched_list_words: HashSet<String> = HashSet::new();
+    {
+        let mut select = tx.prepare("SELECT id, word FROM words")?;
+        let mut update = tx.prepare("UPDATE words SET frequency = ? WHERE id = ?")?;
+        let rows = select.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (id, word) = row?;
+            stats.words_total += 1;
+
+            let lowercase = word.to_lowercase();
+            let count = match exact.get(&word) {
+                Some(count) => Some((*count, word)),
+                None => folded.get(&lowercase).map(|count| (*count, lowercase)),
+            };
+            let Some((count, key)) = count else {
+                continue;
+            };
+
+            update.execute(rusqlite::params![count as i64, id])?;
+            stats.words_matched += 1;
+            matched_list_words.insert(key);
+        }
+    }
+
+    // A case-insensitive hit counts for every casing in the list
+    stats.list_entries_matched = exact
+        .keys()
+        .filter(|word| {
+            matched_list_words.contains(*word) || matched_list_words.contains(&word.to_lowercase())
+        })
+        .count() as u64;
+
+    let list_name = Path::new(list_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    set_meta(&tx, "frequency_list", &list_name)?;
+    tx.commit()?;
+
+    // Completions rank by frequency
+    crate::db::rebuild_completions(&conn)?;
+
+    log::info!(
+        "Frequency list applied: {}/{} words matched ({:.1}%)",
+        stats.words_matched,
+        stats.words_total,
+        stats.match_rate()
+    );
+
+    Ok(stats)
+}
+
+/// Parse a frequency list line into its first (word) and last (count) column
+fn parse_frequency_line(line: &str) -> Option<(&str, u64)> {
+    let (rest, count) = line.trim().rsplit_once([',', '\t', ' '])?;
+    let count = count.trim().parse().ok()?;
+    let word = rest.split([',', '\t']).next()?.trim().trim_matches('"');
+    (!word.is_empty()).then_some((word, count))
+}
+
+/// Id of a sense without one from kaikki: a hash of the entry and gloss
+///
+/// FNV-1a, so ids are the same on every platform and Rust version.
+fn sense_hash(entry: &RawWordEntry, gloss: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for part in [&entry.lang_code, &entry.word, &entry.pos, gloss] {
+        for byte in part.bytes().chain([0]) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    format!("{}-{:016x}", entry.lang_code, hash)
+}
+
+/// Tables copied along with their entries, and their columns besides
+/// `word_id`
+const MERGED_TABLES: &[(&str, &[&str])] = &[
+    ("pronunciations", &["ipa", "audio_url", "accent", "rhyme"]),
+    ("translations", &["target_language", "translation"]),
+    ("forms", &["form", "tags"]),
+    ("relations", &["definition_id", "kind", "target", "sense"]),
+    ("kana", &["reading"]),
+    ("pinyin", &["letters", "tones"]),
+    ("symbols", &["symbol", "name", "keywords"]),
+];
+
+/// Merge built dictionaries into the database at `dest_path`
+///
+/// Sources are read in order and left untouched; `dest_path` is created
+/// if needed and may already hold entries. A source entry whose headword,
+/// part of speech and language are already in the destination from an
+/// earlier dictionary is folded into that entry: its senses are appended
+/// (unless the same gloss is there), tagged with their source when it
+/// differs from the entry's (see [`crate::Definition::source`]), and its
+/// pronunciations, translations and other rows are added where new. Other
+/// entries are copied with their source and license (see
+/// [`crate::Attribution`]).
+pub fn merge_databases(dest_path: &str, source_paths: &[&str]) -> Result<MergeStats> {
+    let conn = Connection::open(dest_path)?;
+    configure_for_import(&conn)?;
+    conn.execute_batch(include_str!("../sql/schema.sql").trim_start_matches('\u{feff}'))?;
+    crate::db::upgrade_schema(&conn)?;
+
+    let mut entries: HashMap<(String, String, String), i64> = HashMap::new();
+    {
+        let mut stmt =
+            conn.prepare("SELECT word, pos, language, MIN(id) FROM words GROUP BY 1, 2, 3")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(((row.get(0)?, row.get(1)?, row.get(2)?), row.get(3)?))
+        })?;
+        for row in rows {
+            let (key, id) = row?;
+            entries.insert(key, id);
+        }
+    }
+
+    let mut stats = MergeStats::default();
+    let tx = conn.unchecked_transaction()?;
+    for path in source_paths {
+        let source = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        // Entries of one dictionary are only merged with earlier ones
+        let added = merge_database(&tx, &source, &entries, &mut stats)?;
+        for (key, id) in added {
+            entries.entry(key).or_insert(id);
+        }
+        stats.databases_merged += 1;
+    }
+    crate::db::resolve_form_of(&tx)?;
+    set_meta(&tx, "merged_from", &source_paths.join(","))?;
+    tx.commit()?;
+    crate::db::rebuild_completions(&conn)?;
+
+    log::info!(
+        "Merge complete: {} databases, {} words added, {} merged, {} definitions",
+        stats.databases_merged,
+        stats.words_added,
+        stats.words_merged,
+        stats.definitions_added
+    );
+    Ok(stats)
+}
+
+/// Copy the entries of `source` into `conn`, folding them into `entries`
+/// where they match, and return the entries added
+fn merge_database(
+    conn: &Connection,
+    source: &Connection,
+    entries: &HashMap<(String, String, String), i64>,
+    stats: &mut MergeStats,
+) -> Result<HashMap<(String, String, String), i64>> {
+    use crate::db::{has_column, table_exists};
+
+    // The source's sources, as recorded in the destination
+    let mut source_ids: HashMap<i64, i64> = HashMap::new();
+    if table_exists(source, "sources")? {
+        let mut stmt =
+            source.prepare("SELECT id, name, license, license_url, page_url FROM sources")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                SourceInfo {
+                    name: row.get(1)?,
+                    license: row.get(2)?,
+                    license_url: row.get(3)?,
+                    page_url: row.get(4)?,
+                },
+            ))
+        })?;
+        for row in rows {
+            let (id, info) = row?;
+            source_ids.insert(id, crate::db::insert_source(conn, &info)?);
+        }
+    }
+    // Databases built before sources were recorded are from Wiktionary
+    let source_primary = match source_ids.iter().min_by_key(|(id, _)| **id) {
+        Some((_, dest_id)) => *dest_id,
+        None => crate::db::insert_source(conn, &SourceInfo::default())?,
+    };
+    let primary: i64 = conn.query_row("SELECT MIN(id) FROM sources", [], |row| row.get(0))?;
+
+    let column = |table: &str, column: &str| -> Result<String> {
+        Ok(if has_column(source, table, column)? {
+            column.to_string()
+        } else {
+            "NULL".to_string()
+        })
+    };
+    let frequency = column("words", "frequency")?;
+    let form_of = column("definitions", "form_of")?;
+    let sense_id = column("definitions", "sense_id")?;
+    let has_entry_sources = table_exists(source, "entry_sources")?;
+    let mut tables = Vec::new();
+    for (table, columns) in MERGED_TABLES {
+        if table_exists(source, table)? {
+            let selected = columns
+                .iter()
+                .map(|c| column(table, c))
+                .collect::<Result<Vec<_>>>()?;
+            tables.push((*table, *columns, selected.join(", ")));
+        }
+    }
+
+    let mut added = HashMap::new();
+    let mut words = source.prepare(&format!(
+        "SELECT id, word, pos, language, lang_code, etymology_num, {frequency} FROM words ORDER BY id"
+    ))?;
+    let mut rows = words.query([])?;
+    while let Some(row) = rows.next()? {
+        let source_word: i64 = row.get(0)?;
+        let key: (String, String, String) = (row.get(1)?, row.get(2)?, row.get(3)?);
+        let lang_code: String = row.get(4)?;
+        let etymology_num: i32 = row.get::<_, Option<i32>>(5)?.unwrap_or(0);
+        let word_frequency: Option<i64> = row.get(6)?;
+
+        let (source_id, revision) = if has_entry_sources {
+            source
+                .prepare_cached("SELECT source_id, revision FROM entry_sources WHERE word_id = ?")?
+                .query_row([source_word], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, Option<i64>>(1)?))
+                })
+                .optional()?
+                .map_or((source_primary, None), |(id, revision)| {
+                    (
+                        source_ids.get(&id).copied().unwrap_or(source_primary),
+                        revision,
+                    )
+                })
+        } else {
+            (source_primary, None)
+        };
+
+        let (word_id, sense_source) = match entries.get(&key) {
+            Some(&word_id) => {
+                stats.words_merged += 1;
+                conn.execute(
+                    "UPDATE words SET frequency = COALESCE(MAX(frequency, ?1), frequency, ?1) WHERE id = ?2",
+                    params![word_frequency, word_id],
+                )?;
+                let entry_source: i64 = conn
+                    .query_row(
+                        "SELECT source_id FROM entry_sources WHERE word_id = ?",
+                        [word_id],
+                        |row| row.get(0),
+                    )
+                    .optional()?
+                    .unwrap_or(primary);
+                (word_id, (source_id != entry_source).then_some(source_id))
+            }
+            None => {
+                stats.words_added += 1;
+                let (word, pos, language) = &key;
+                let word_id = insert_word(conn, word, pos, language, &lang_code, etymology_num)?;
+                conn.execute(
+                    "UPDATE words SET frequency = ? WHERE id = ?",
+                    params![word_frequency, word_id],
+                )?;
+                if source_id != primary || revision.is_some() {
+                    crate::db::insert_entry_source(conn, word_id, source_id, revision)?;
+                }
+                added.entry(key).or_insert(word_id);
+                (word_id, None)
+            }
+        };
+
+        // Senses, remembering where each went for sense-level relations
+        let mut definition_ids: HashMap<i64, i64> = HashMap::new();
+        let mut definitions = source.prepare_cached(&format!(
+            "SELECT id, definition, examples, tags, {form_of}, {sense_id} FROM definitions
+             WHERE word_id = ? ORDER BY id"
+        ))?;
+        let mut senses = definitions.query([source_word])?;
+        while let Some(sense) = senses.next()? {
+            let text: String = sense.get(1)?;
+            let existing: Option<i64> = conn
+                .prepare_cached("SELECT id FROM definitions WHERE word_id = ? AND definition = ?")?
+                .query_row(params![word_id, text], |row| row.get(0))
+                .optional()?;
+            let id = match existing {
+                Some(id) => id,
+                None => {
+                    conn.prepare_cached(
+                        "INSERT INTO definitions
+                         (word_id, definition, examples, tags, form_of, source_id, sense_id)
+                         VALUES (?, ?, ?, ?, ?, ?, ?)",
+                    )?
+                    .execute(params![
+                        word_id,
+                        text,
+                        sense.get::<_, Option<String>>(2)?,
+                        sense.get::<_, Option<String>>(3)?,
+                        sense.get::<_, Option<String>>(4)?,
+                        sense_source,
+                        sense.get::<_, Option<String>>(5)?
+                    ])?;
+                    stats.definitions_added += 1;
+                    conn.last_insert_rowid()
+                }
+            };
+            definition_ids.insert(sense.get(0)?, id);
+        }
+
+        // An entry keeps its own etymology
+        let etymology: Option<String> = source
+            .prepare_cached("SELECT etymology_text FROM etymologies WHERE word_id = ? LIMIT 1")?
+            .query_row([source_word], |row| row.get(0))
+            .optional()?;
+        if let Some(etymology) = etymology {
+            conn.prepare_cached(
+                "INSERT INTO etymologies (word_id, etymology_text)
+                 SELECT ?1, ?2 WHERE NOT EXISTS (SELECT 1 FROM etymologies WHERE word_id = ?1)",
+            )?
+            .execute(params![word_id, etymology])?;
+        }
+
+        for (table, columns, selected) in &tables {
+            copy_new_rows(
+                conn,
+                source,
+                table,
+                columns,
+                selected,
+                (source_word, word_id),
+                &definition_ids,
+            )?;
+        }
+    }
+    Ok(added)
+}
+
+/// Copy rows of `table` from one entry to another, skipping rows the
+/// target entry already has
+///
+/// `selected` is the source's select list for `columns`, with NULL for
+/// columns it lacks. Sense-level rows point at the copied senses.
+fn copy_new_rows(
+    conn: &Connection,
+    source: &Connection,
+    table: &str,
+    columns: &[&str],
+    selected: &str,
+    (from, to): (i64, i64),
+    definition_ids: &HashMap<i64, i64>,
+) -> Result<()> {
+    use rusqlite::types::Value;
+
+    let placeholders: Vec<String> = (2..columns.len() + 2).map(|n| format!("?{n}")).collect();
+    let matches: Vec<String> = columns
+        .iter()
+        .zip(&placeholders)
+        .map(|(column, placeholder)| format!("{column} IS {placeholder}"))
+        .collect();
+    let mut insert = conn.prepare_cached(&format!(
+        "INSERT INTO {table} (word_id, {columns}) SELECT ?1, {placeholders}
+         WHERE NOT EXISTS (SELECT 1 FROM {table} WHERE word_id = ?1 AND {matches})",
+        columns = columns.join(", "),
+        placeholders = placeholders.join(", "),
+        matches = matches.join(" AND "),
+    ))?;
+
+    let mut select = source.prepare_cached(&format!(
+        "SELECT {selected} FROM {table} WHERE word_id = ? ORDER BY id"
+    ))?;
+    let mut rows = select.query([from])?;
+    while let Some(row) = rows.next()? {
+        let mut values = vec![Value::Integer(to)];
+        for (i, column) in columns.iter().enumerate() {
+            let value: Value = row.get(i)?;
+            values.push(match (*column, value) {
+                ("definition_id", Value::Integer(id)) => definition_ids
+                    .get(&id)
+                    .map_or(Value::Null, |id| Value::Integer(*id)),
+                (_, value) => value,
+            });
+        }
+        insert.execute(rusqlite::params_from_iter(values))?;
+    }
+    Ok(())
+}
+
+/// Record the normalization policy before any headword is keyed by it
+///
+/// A database keeps one policy; adding to one built with another fails.
+fn record_normalization(conn: &Connection, normalization: Normalization) -> Result<()> {
+    let recorded: Option<String> = conn
+        .query_row(
+            "SELECT value FROM meta WHERE key = 'normalization'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    // Headwords added before the policy was recorded are folded
+    let recorded = match recorded {
+        None if conn.query_row("SELECT EXISTS (SELECT 1 FROM words)", [], |row| row.get(0))? => {
+            Some(Normalization::Fold.name().to_string())
+        }
+        recorded => recorded,
+    };
+    match recorded {
+        Some(name) if name != normalization.name() => Err(Error::InvalidInput(format!(
+            "database is normalized with '{name}', not '{}'",
+            normalization.name()
+        ))),
+        _ => set_meta(conn, "normalization", normalization.name()),
+    }
+}
+
+/// Record where and when the database was built in the `meta` table
+fn write_build_metadata(conn: &Connection, source: &Path, options: &ImportOptions) -> Result<()> {
+    let built_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let source_name = source
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    set_meta(
+        conn,
+        "generator",
+        concat!("dict-core ", env!("CARGO_PKG_VERSION")),
+    )?;
+    set_meta(conn, "source", &source_name)?;
+    set_meta(conn, "built_at", &built_at.to_string())?;
+
+    if let Some(version) = &options.dict_version {
+        set_meta(conn, "dict_version", version)?;
+    }
+    if let Some(sample) = options.sample_description() {
+        set_meta(conn, "sample", &sample)?;
+    }
+
+    if options.fts != FtsProfile::Full {
+        set_meta(conn, "fts_profile", options.fts.name())?;
+    }
+
+    let omitted = options.omitted_content();
+    if !omitted.is_empty() {
+        set_meta(conn, "omitted_content", &omitted.join(","))?;
+    }
+    Ok(())
+}
+
+/// Selects exactly `size` of `total` lines uniformly at random, in order
+///
+/// Selection sampling (Knuth's Algorithm S): each line is kept with
+/// probability `needed / remaining`, so the input is streamed once and
+/// nothing but the selected lines is buffered.
+struct LineSampler {
+    needed: u64,
+    remaining: u64,
+    state: u64,
+}
+
+impl LineSampler {
+    fn new(size: u64, total: u64, seed: u64) -> Self {
+        Self {
+            needed: size.min(total),
+            remaining: total,
+            state: seed,
+        }
+    }
+
+    /// Decide whether the next line is part of the sample
+    fn select(&mut self) -> bool {
+        if self.remaining == 0 {
+            return false;
+        }
+        let selected = self.next_u64() % self.remaining < self.needed;
+        self.remaining -= 1;
+        if selected {
+            self.needed -= 1;
+        }
+        selected
+    }
+
+    /// SplitMix64; statistical quality is plenty for picking test data
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Outcome of parsing a single JSONL line
+enum ParsedLine {
+    Entry(Box<RawWordEntry>),
+    Empty,
+    ReadError,
+    Invalid(serde_json::Error),
+}
+
+/// Parse one line read from the input
+fn parse_line(line: &std::io::Result<String>) -> ParsedLine {
+    match line {
+        Err(_) => ParsedLine::ReadError,
+        Ok(line) if line.trim().is_empty() => ParsedLine::Empty,
+        Ok(line) => match serde_json::from_str(line) {
+            Ok(entry) => ParsedLine::Entry(Box::new(entry)),
+            Err(e) => ParsedLine::Invalid(e),
+        },
+    }
+}
+
+/// Parse a batch of lines, splitting the work across `jobs` threads
+///
+/// Results are returned in input order so that word IDs do not depend on
+/// the number of jobs.
+fn parse_batch(batch: &[std::io::Result<String>], jobs: usize) -> Vec<ParsedLine> {
+    if jobs <= 1 || batch.len() < jobs {
+        return batch.iter().map(parse_line).collect();
+    }
+
+    let chunk_size = batch.len().div_ceil(jobs);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = batch
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(parse_line).collect::<Vec<_>>()))
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("JSONL parser thread panicked"))
+            .collect()
+    })
+}
+
+/// A reader that counts the bytes read through it
+struct CountingReader<R> {
+    inner: R,
+    count: Rc<Cell<u64>>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.set(self.count.get() + n as u64);
+        Ok(n)
+    }
+}
+
 /// Count the number of lines in a file
 fn count_lines(path: &str) -> Result<u64> {
     let file = File::open(path)?;
@@ -206,27 +1298,48 @@ struct EntryStats {
     pronunciations: u64,
     etymologies: u64,
     translations: u64,
+    forms: u64,
+    relations: u64,
+}
+
+/// The source entries of an import are recorded under
+#[derive(Clone, Copy)]
+struct EntrySource {
+    id: i64,
+    /// Whether it is the database's primary source
+    primary: bool,
 }
 
 /// Import a single word entry into the database and return stats
-fn import_entry_with_stats(conn: &Connection, entry: &RawWordEntry) -> Result<EntryStats> {
+fn import_entry_with_stats(
+    conn: &Connection,
+    entry: &RawWordEntry,
+    options: &ImportOptions,
+    source: EntrySource,
+) -> Result<EntryStats> {
     let mut stats = EntryStats {
         definitions: 0,
         pronunciations: 0,
         etymologies: 0,
         translations: 0,
+        forms: 0,
+        relations: 0,
     };
 
     // Insert the word
     let etymology_num = entry.etymology_number.unwrap_or(0);
+    // Stored with plain quotes and dashes, as queries are matched
     let word_id = insert_word(
         conn,
-        &entry.word,
+        &crate::normalize::punctuation(&entry.word),
         &entry.pos,
         &entry.lang,
         &entry.lang_code,
         etymology_num,
     )?;
+    if !source.primary || entry.revision.is_some() {
+        crate::db::insert_entry_source(conn, word_id, source.id, entry.revision)?;
+    }
 
     // Insert definitions from senses
     for sense in &entry.senses {
@@ -243,32 +1356,162 @@ fn import_entry_with_stats(conn: &Connection, entry: &RawWordEntry) -> Result<En
         }
 
         // Collect examples
-        let examples: Vec<String> = sense.examples.iter().map(|e| e.text.clone()).collect();
+        let examples: Vec<String> = if options.examples {
+            crate::examples::filter(
+                sense.examples.iter().map(|e| e.text.as_str()),
+                options.dedup_examples,
+                options.max_example_len,
+            )
+        } else {
+            Vec::new()
+        };
 
-        insert_definition(conn, word_id, definition_text, &examples, &sense.tags)?;
+        let definition_id =
+            insert_definition(conn, word_id, definition_text, &examples, &sense.tags)?;
         stats.definitions += 1;
+        let sense_id = match &sense.id {
+            Some(id) => id.clone(),
+            None => sense_hash(entry, definition_text),
+        };
+        crate::db::set_sense_id(conn, definition_id, &sense_id)?;
+
+        if let Some(target) = form_of_target(sense, definition_text) {
+            if target != entry.word {
+                crate::db::set_form_of(conn, definition_id, &target)?;
+            }
+        }
+
+        if options.relations {
+            stats.relations += insert_relations(conn, word_id, definition_id, sense)?;
+        }
+    }
+
+    // Link symbol entries (emoji, punctuation) so they are found with or
+    // without a variation selector
+    if crate::symbols::is_symbol(&entry.word) {
+        let name = entry.senses.iter().find_map(|s| s.glosses.first());
+        crate::db::insert_symbol(conn, word_id, &entry.word, name.map(String::as_str), &[])?;
     }
 
-    // Insert pronunciations
+    // Insert pronunciations, skipping near-identical transcriptions of one
+    // already kept for the same accent region. A duplicate's audio goes to
+    // the kept row if that has none, otherwise it gets a row of its own.
+    let mut kept: Vec<(i64, &str, Option<&str>, bool)> = Vec::new();
     for sound in &entry.sounds {
         if let Some(ipa) = &sound.ipa {
             let audio_url = get_audio_url(sound);
-            let accent = sound.tags.first().map(|s| s.as_str());
-            insert_pronunciation(conn, word_id, Some(ipa), audio_url.as_deref(), accent)?;
+            let accent = sound_accent(sound);
+            let region = accent.map(|a| crate::ipa::accent_region(a).unwrap_or(a));
+            let duplicate = kept
+                .iter_mut()
+                .find(|(_, k, r, _)| *r == region && crate::ipa::is_near_duplicate(k, ipa));
+            match (duplicate, &audio_url) {
+                (Some(_), None) => continue,
+                (Some((id, _, _, has_audio @ false)), Some(url)) => {
+                    crate::db::set_pronunciation_audio(conn, *id, url)?;
+                    *has_audio = true;
+                    continue;
+                }
+                _ => {}
+            }
+            let id = insert_pronunciation(conn, word_id, Some(ipa), audio_url.as_deref(), accent)?;
+            kept.push((id, ipa, region, audio_url.is_some()));
             stats.pronunciations += 1;
         }
     }
 
+    // Insert Mandarin pinyin readings, which kaikki gives as romanizations
+    // tagged "Pinyin" rather than as IPA
+    let mut readings: HashSet<&str> = HashSet::new();
+    for sound in &entry.sounds {
+        let Some(zh_pron) = &sound.zh_pron else {
+            continue;
+        };
+        if !sound.tags.iter().any(|t| t == "Pinyin") {
+            continue;
+        }
+        for reading in zh_pron.split([',', ';']).map(str::trim) {
+            if readings.insert(reading) {
+                insert_pinyin(conn, word_id, reading)?;
+            }
+        }
+    }
+
+    // Insert Japanese kana readings: the headword itself when written in
+    // kana, and forms tagged as hiragana or katakana
+    #[cfg(feature = "kana")]
+    {
+        let mut readings: HashSet<&str> = HashSet::new();
+        if entry.word.chars().all(crate::kana::is_kana) {
+            readings.insert(&entry.word);
+        }
+        for form in &entry.forms {
+            let is_reading = form.tags.iter().any(|t| t == "hiragana" || t == "katakana");
+            if is_reading && !form.form.is_empty() && form.form.chars().all(crate::kana::is_kana) {
+                readings.insert(&form.form);
+            }
+        }
+        for reading in readings {
+            crate::db::insert_kana(conn, word_id, reading)?;
+        }
+    }
+
+    // Insert inflected and alternative forms, skipping the bookkeeping
+    // rows kaikki mixes in (inflection templates, table headers) and
+    // romanizations, which are not spellings of the word
+    if options.forms {
+        let mut seen: HashSet<&str> = HashSet::new();
+        for form in &entry.forms {
+            let is_noise = form
+                .tags
+                .iter()
+                .any(|t| NON_FORM_TAGS.contains(&t.as_str()));
+            if form.form.is_empty() || form.form == entry.word || is_noise {
+                continue;
+            }
+            if seen.insert(&form.form) {
+                insert_form(conn, word_id, &form.form, &form.tags)?;
+                stats.forms += 1;
+            }
+        }
+    }
+
     // Insert etymology
-    if let Some(etymology_text) = &entry.etymology_text {
+    if let Some(etymology_text) = entry
+        .etymology_text
+        .as_ref()
+        .filter(|_| options.etymologies)
+    {
         if !etymology_text.is_empty() {
             insert_etymology(conn, word_id, etymology_text)?;
             stats.etymologies += 1;
         }
     }
 
+    // Insert synonyms and antonyms given for the entry as a whole
+    if options.relations {
+        for (kind, relations) in [("synonym", &entry.synonyms), ("antonym", &entry.antonyms)] {
+            for relation in relations.iter().filter(|r| !r.word.is_empty()) {
+                insert_relation(
+                    conn,
+                    word_id,
+                    None,
+                    kind,
+                    &relation.word,
+                    relation.sense.as_deref(),
+                )?;
+                stats.relations += 1;
+            }
+        }
+    }
+
     // Insert translations
-    for translation in &entry.translations {
+    let translations = if options.translations {
+        entry.translations.as_slice()
+    } else {
+        &[]
+    };
+    for translation in translations {
         if !translation.word.is_empty() {
             let lang = if translation.code.is_empty() {
                 &translation.lang
@@ -283,7 +1526,71 @@ fn import_entry_with_stats(conn: &Connection, entry: &RawWordEntry) -> Result<En
     Ok(stats)
 }
 
+/// The word a sense is an inflection or variant of, if it is a stub
+///
+/// Kaikki tags most of these with `form_of`/`alt_of`; glosses such as
+/// "past tense of run" without the tag are recognized by their wording.
+fn form_of_target(sense: &RawSense, gloss: &str) -> Option<String> {
+    if let Some(link) = sense
+        .form_of
+        .iter()
+        .chain(&sense.alt_of)
+        .find(|link| !link.word.is_empty())
+    {
+        return Some(link.word.clone());
+    }
+
+    let (head, target) = gloss.rsplit_once(" of ")?;
+    let head = head.trim().to_lowercase();
+    let last_word = head.rsplit(' ').next()?;
+    if !FORM_OF_HEADS.contains(&last_word) || head.split(' ').count() > 6 {
+        return None;
+    }
+    let target = target
+        .split([':', ';', ',', '('])
+        .next()?
+        .trim()
+        .trim_end_matches('.')
+        .trim();
+    (!target.is_empty() && target.split(' ').count() <= 3).then(|| target.to_string())
+}
+
+/// Insert the synonyms and antonyms of one sense, returning how many
+fn insert_relations(
+    conn: &Connection,
+    word_id: i64,
+    definition_id: i64,
+    sense: &RawSense,
+) -> Result<u64> {
+    let mut count = 0;
+    for (kind, relations) in [("synonym", &sense.synonyms), ("antonym", &sense.antonyms)] {
+        for relation in relations.iter().filter(|r| !r.word.is_empty()) {
+            insert_relation(
+                conn,
+                word_id,
+                Some(definition_id),
+                kind,
+                &relation.word,
+                None,
+            )?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
 /// Get the best audio URL from a sound entry
+/// The accent tag of a sound: the first tag naming a known accent, else
+/// the first tag
+fn sound_accent(sound: &RawSound) -> Option<&str> {
+    sound
+        .tags
+        .iter()
+        .find(|t| crate::ipa::accent_region(t).is_some())
+        .or_else(|| sound.tags.first())
+        .map(String::as_str)
+}
+
 fn get_audio_url(sound: &RawSound) -> Option<String> {
     // Prefer OGG, then MP3, then generic audio
     sound
@@ -314,6 +1621,7 @@ mod tests {
             audio: Some("audio.mp3".to_string()),
             ogg_url: Some("audio.ogg".to_string()),
             mp3_url: Some("audio.mp3".to_string()),
+            zh_pron: None,
             tags: vec![],
         };
         assert_eq!(get_audio_url(&sound), Some("audio.ogg".to_string()));
@@ -326,8 +1634,830 @@ mod tests {
             audio: None,
             ogg_url: None,
             mp3_url: Some("audio.mp3".to_string()),
+            zh_pron: None,
             tags: vec![],
         };
         assert_eq!(get_audio_url(&sound), Some("audio.mp3".to_string()));
     }
+
+    #[test]
+    fn test_import_records_words_and_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl_path = dir.path().join("sample.jsonl");
+        let db_path = dir.path().join("sample.db");
+        std::fs::write(
+            &jsonl_path,
+            r#"{"word":"hello","pos":"intj","lang_code":"en","senses":[{"glosses":["A greeting"]}]}"#,
+        )
+        .unwrap();
+
+        let stats = import_from_jsonl_with_stats(
+            db_path.to_str().unwrap(),
+            jsonl_path.to_str().unwrap(),
+            |_, _| {},
+        )
+        .unwrap();
+        assert_eq!(stats.words_imported, 1);
+        assert_eq!(stats.errors, 0);
+        let input_len = std::fs::metadata(&jsonl_path).unwrap().len();
+        assert_eq!(stats.bytes_read, input_len);
+        assert!(stats.peak_batch_micros <= stats.timings.inserting_micros);
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["bytes_read"], input_len);
+        assert!(json["timings"]["finalizing_micros"].is_u64());
+
+        let handle = crate::init(db_path.to_str().unwrap()).unwrap();
+        let metadata = crate::db::get_metadata(&handle).unwrap();
+        assert_eq!(metadata.get("source").unwrap(), "sample.jsonl");
+        assert!(metadata.contains_key("built_at"));
+    }
+
+    #[test]
+    fn test_import_stats_timings_and_counts() {
+        let dir = tempfile::tempdir().unwrap();
+        let lines = [
+            r#"{"word":"cat","pos":"noun","lang_code":"en","senses":[{"glosses":["A feline"]},{"glosses":["A jazz fan"]}],"sounds":[{"ipa":"/kæt/"}],"translations":[{"code":"fr","word":"chat"}]}"#,
+            "",
+            r#"{"word":"dog","pos":"noun","lang_code":"en","senses":[{"glosses":["A canine"]}]}"#,
+            "not json",
+        ];
+        // Gzipped, so bytes_read counts the compressed input
+        let jsonl_path = dir.path().join("sample.jsonl.gz");
+        let mut encoder = flate2::write::GzEncoder::new(
+            std::fs::File::create(&jsonl_path).unwrap(),
+            flate2::Compression::default(),
+        );
+        std::io::Write::write_all(&mut encoder, lines.join("\n").as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let db_path = dir.path().join("sample.db");
+        let start = std::time::Instant::now();
+        let stats = import_from_jsonl_with_stats(
+            db_path.to_str().unwrap(),
+            jsonl_path.to_str().unwrap(),
+            |_, _| {},
+        )
+        .unwrap();
+        let wall_micros = start.elapsed().as_micros() as u64;
+
+        assert_eq!(stats.lines_processed, 4);
+        assert_eq!(stats.words_imported, 2);
+        assert_eq!(stats.definitions_imported, 3);
+        assert_eq!(stats.pronunciations_imported, 1);
+        assert_eq!(stats.translations_imported, 1);
+        assert_eq!(stats.skipped, 1);
+        assert_eq!(stats.errors, 1);
+        assert_eq!(
+            stats.bytes_read,
+            std::fs::metadata(&jsonl_path).unwrap().len()
+        );
+
+        let timings = &stats.timings;
+        assert!(timings.parsing_micros > 0);
+        assert!(timings.inserting_micros > 0);
+        assert!(stats.peak_batch_micros > 0);
+        assert!(stats.peak_batch_micros <= timings.inserting_micros);
+        let phases = timings.counting_micros
+            + timings.parsing_micros
+            + timings.inserting_micros
+            + timings.finalizing_micros;
+        assert!(phases <= wall_micros);
+    }
+
+    #[test]
+    fn test_keep_words() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl_path = dir.path().join("sample.jsonl");
+        std::fs::write(
+            &jsonl_path,
+            concat!(
+                r#"{"word":"go","pos":"verb","senses":[{"glosses":["To move"]}]}"#,
+                "\n",
+                r#"{"word":"went","pos":"verb","senses":[{"glosses":["past of go"],"form_of":[{"word":"go"}]}]}"#,
+                "\n",
+                r#"{"word":"colour","pos":"noun","senses":[{"glosses":["Alternative spelling of color"],"alt_of":[{"word":"color"}]}]}"#,
+                "\n",
+                r#"{"word":"table","pos":"noun","senses":[{"glosses":["Furniture"]}]}"#,
+            ),
+        )
+        .unwrap();
+        let list_path = dir.path().join("words.txt");
+        std::fs::write(&list_path, "# exam words\nGo\n\n  color\n").unwrap();
+        let words = read_wordlist(list_path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            words,
+            HashSet::from(["go".to_string(), "color".to_string()])
+        );
+
+        let db_path = dir.path().join("kept.db");
+        let options = ImportOptions {
+            keep_words: Some(words),
+            ..Default::default()
+        };
+        let stats = import_from_jsonl_with_options(
+            db_path.to_str().unwrap(),
+            jsonl_path.to_str().unwrap(),
+            &options,
+            |_, _| {},
+        )
+        .unwrap();
+        assert_eq!(stats.words_imported, 3);
+        assert_eq!(stats.skipped, 1);
+
+        let conn = Connection::open(&db_path).unwrap();
+        let mut stmt = conn
+            .prepare("SELECT word FROM words ORDER BY word")
+            .unwrap();
+        let kept: Vec<String> = stmt
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap();
+        assert_eq!(kept, ["colour", "go", "went"]);
+    }
+
+    #[test]
+    fn test_import_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl_path = dir.path().join("sample.jsonl");
+        std::fs::write(
+            &jsonl_path,
+            concat!(
+                r#"{"word":"hello","pos":"intj","senses":[{"glosses":["A greeting"]}]}"#,
+                "\n{not json\n",
+                r#"{"word":"world","pos":"noun","senses":[{"glosses":["The Earth"]}]}"#,
+            ),
+        )
+        .unwrap();
+        let jsonl_path = jsonl_path.to_str().unwrap();
+
+        let db_path = dir.path().join("events.db");
+        let mut events = Vec::new();
+        let stats = import_from_jsonl_with_events(
+            db_path.to_str().unwrap(),
+            jsonl_path,
+            &ImportOptions::default(),
+            |event| events.push(event),
+        )
+        .unwrap();
+        assert_eq!(stats.words_imported, 2);
+        let phases: Vec<ImportPhase> = events
+            .iter()
+            .filter_map(|event| match event {
+                ImportEvent::PhaseStarted { phase } => Some(*phase),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            phases,
+            [
+                ImportPhase::Counting,
+                ImportPhase::Importing,
+                ImportPhase::Finalizing
+            ]
+        );
+        assert!(events.iter().any(|event| matches!(
+            event,
+            ImportEvent::Warning { line: 2, message } if message.starts_with("Invalid JSON")
+        )));
+        assert!(matches!(
+            events[events.len() - 2],
+            ImportEvent::Progress { lines: 3, total: 3 }
+        ));
+        let json = serde_json::to_value(events.last().unwrap()).unwrap();
+        assert_eq!(json["event"], "finished");
+        assert_eq!(json["stats"]["errors"], 1);
+
+        let db_path = dir.path().join("spawned.db");
+        let (receiver, thread) = spawn_import(
+            db_path.to_str().unwrap(),
+            jsonl_path,
+            ImportOptions::default(),
+        );
+        let events: Vec<ImportEvent> = receiver.iter().collect();
+        assert_eq!(thread.join().unwrap().unwrap().words_imported, 2);
+        assert!(matches!(
+            events.last(),
+            Some(ImportEvent::Finished { stats }) if stats.words_imported == 2
+        ));
+    }
+
+    #[test]
+    fn test_lite_options_skip_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl_path = dir.path().join("sample.jsonl");
+        let db_path = dir.path().join("lite.db");
+        std::fs::write(
+            &jsonl_path,
+            r#"{"word":"cat","pos":"noun","senses":[{"glosses":["A feline"],"examples":[{"text":"The cat sat."}]}],"etymology_text":"From Old English catt","translations":[{"code":"fr","word":"chat"}],"sounds":[{"ipa":"/kæt/"}]}"#,
+        )
+        .unwrap();
+
+        let stats = import_from_jsonl_with_options(
+            db_path.to_str().unwrap(),
+            jsonl_path.to_str().unwrap(),
+            &ImportOptions::lite(),
+            |_, _| {},
+        )
+        .unwrap();
+        assert_eq!(stats.definitions_imported, 1);
+        assert_eq!(stats.pronunciations_imported, 1);
+        assert_eq!(stats.etymologies_imported, 0);
+        assert_eq!(stats.translations_imported, 0);
+
+        let handle = crate::init(db_path.to_str().unwrap()).unwrap();
+        let def = crate::get_definition(&handle, 1).unwrap();
+        assert!(def.definitions[0].examples.is_empty());
+        assert!(def.etymology.is_none());
+        let metadata = crate::db::get_metadata(&handle).unwrap();
+        assert_eq!(
+            metadata.get("omitted_content").unwrap(),
+            "etymologies,translations,examples,forms,relations"
+        );
+    }
+
+    #[test]
+    fn test_compact_fts_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl_path = dir.path().join("sample.jsonl");
+        std::fs::write(
+            &jsonl_path,
+            concat!(
+                r#"{"word":"ice cream","pos":"noun","senses":[{"glosses":["A frozen dessert"]}]}"#,
+                "\n",
+                r#"{"word":"icing","pos":"noun","senses":[{"glosses":["A sweet glaze"]}]}"#,
+            ),
+        )
+        .unwrap();
+        let size_of = |profile: FtsProfile| {
+            let db_path = dir.path().join(format!("{}.db", profile.name()));
+            let db = db_path.to_str().unwrap();
+            let options = ImportOptions {
+                fts: profile,
+                ..ImportOptions::default()
+            };
+            import_from_jsonl_with_options(db, jsonl_path.to_str().unwrap(), &options, |_, _| {})
+                .unwrap();
+            let handle = crate::init(db).unwrap();
+            let words: Vec<String> = crate::search::search_words(&handle, "cream", 10)
+                .unwrap()
+                .into_iter()
+                .map(|result| result.word)
+                .collect();
+            assert_eq!(words, ["ice cream"]);
+            let metadata = crate::db::get_metadata(&handle).unwrap();
+            assert_eq!(
+                metadata.get("fts_profile").map(String::as_str),
+                (profile != FtsProfile::Full).then_some(profile.name())
+            );
+            handle
+                .conn
+                .query_row("SELECT SUM(length(block)) FROM words_fts_data", [], |row| {
+                    row.get::<_, i64>(0)
+                })
+                .unwrap()
+        };
+        assert!(size_of(FtsProfile::Compact) < size_of(FtsProfile::Full));
+    }
+
+    #[test]
+    fn test_normalization_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl_path = dir.path().join("sample.jsonl");
+        std::fs::write(
+            &jsonl_path,
+            concat!(
+                r#"{"word":"pena","pos":"noun","senses":[{"glosses":["Sorrow"]}]}"#,
+                "\n",
+                r#"{"word":"Peña","pos":"noun","senses":[{"glosses":["Rock"]}]}"#,
+            ),
+        )
+        .unwrap();
+        let db_path = dir.path().join("es.db");
+        let db = db_path.to_str().unwrap();
+        let jsonl = jsonl_path.to_str().unwrap();
+        let options = ImportOptions {
+            normalization: Normalization::Case,
+            ..ImportOptions::default()
+        };
+        import_from_jsonl_with_options(db, jsonl, &options, |_, _| {}).unwrap();
+
+        let handle = crate::init(db).unwrap();
+        assert_eq!(handle.normalization(), Normalization::Case);
+        let metadata = crate::db::get_metadata(&handle).unwrap();
+        assert_eq!(metadata.get("normalization").unwrap(), "case");
+        let keys: Vec<String> = handle
+            .conn
+            .prepare("SELECT search_key FROM words ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap();
+        assert_eq!(keys, ["pena", "peña"]);
+        // The index keeps the diacritics too
+        let matches: i64 = handle
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM words_fts WHERE words_fts MATCH 'pena'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(matches, 1);
+        let exact: Vec<String> = crate::search::search_words(&handle, "peña", 10)
+            .unwrap()
+            .into_iter()
+            .filter(|result| result.score < 0.5)
+            .map(|result| result.word)
+            .collect();
+        assert_eq!(exact, ["Peña"]);
+
+        // One database, one policy
+        let folded =
+            import_from_jsonl_with_options(db, jsonl, &ImportOptions::default(), |_, _| {});
+        assert!(matches!(folded, Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_near_duplicate_pronunciations_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl_path = dir.path().join("sample.jsonl");
+        let db_path = dir.path().join("test.db");
+        std::fs::write(
+            &jsonl_path,
+            r#"{"word":"church","pos":"noun","senses":[{"glosses":["A building"]}],"sounds":[{"ipa":"/ˈʧɜːʧ/","tags":["UK"]},{"ipa":"[ˈt͡ʃɜːt͡ʃ]","tags":["UK"]},{"ipa":"/ˈt͡ʃɝt͡ʃ/","tags":["US"]},{"ipa":"/ˈt͡ʃɝt͡ʃ/","tags":["US"],"ogg_url":"https://example.com/church.ogg"}]}"#,
+        )
+        .unwrap();
+
+        let stats = import_from_jsonl_with_options(
+            db_path.to_str().unwrap(),
+            jsonl_path.to_str().unwrap(),
+            &ImportOptions::default(),
+            |_, _| {},
+        )
+        .unwrap();
+        // The bracketed UK variant is dropped; the US duplicate lends its audio
+        assert_eq!(stats.pronunciations_imported, 2);
+        let handle = crate::init(db_path.to_str().unwrap()).unwrap();
+        let church = crate::get_definition(&handle, 1).unwrap();
+        let us = &church.pronunciations[1];
+        assert_eq!(us.accent.as_deref(), Some("US"));
+        assert_eq!(
+            us.audio_url.as_deref(),
+            Some("https://example.com/church.ogg")
+        );
+    }
+
+    #[test]
+    fn test_pinyin_readings_indexed() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl_path = dir.path().join("sample.jsonl");
+        let db_path = dir.path().join("test.db");
+        std::fs::write(
+            &jsonl_path,
+            r#"{"word":"你好","pos":"intj","lang":"Chinese","lang_code":"zh","senses":[{"glosses":["hello"]}],"sounds":[{"zh_pron":"nǐ hǎo","tags":["Mandarin","Pinyin"]},{"zh_pron":"nǐ hǎo","tags":["Mandarin","Pinyin","standard"]},{"zh_pron":"nei5 hou2","tags":["Cantonese","Jyutping"]}]}"#,
+        )
+        .unwrap();
+
+        import_from_jsonl_with_options(
+            db_path.to_str().unwrap(),
+            jsonl_path.to_str().unwrap(),
+            &ImportOptions::default(),
+            |_, _| {},
+        )
+        .unwrap();
+
+        let handle = crate::init(db_path.to_str().unwrap()).unwrap();
+        let readings: Vec<(String, String)> = handle
+            .conn
+            .prepare("SELECT letters, tones FROM pinyin")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap();
+        assert_eq!(readings, [("nihao".to_string(), "33".to_string())]);
+    }
+
+    #[test]
+    fn test_relations_imported_for_thesaurus() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl_path = dir.path().join("sample.jsonl");
+        let db_path = dir.path().join("test.db");
+        std::fs::write(
+            &jsonl_path,
+            r#"{"word":"happy","pos":"adj","lang":"English","lang_code":"en","senses":[{"glosses":["Feeling joy."],"synonyms":[{"word":"glad"},{"word":"joyful"}],"antonyms":[{"word":"sad"}]}],"synonyms":[{"word":"fortunate","sense":"lucky"}]}"#,
+        )
+        .unwrap();
+
+        let stats = import_from_jsonl_with_options(
+            db_path.to_str().unwrap(),
+            jsonl_path.to_str().unwrap(),
+            &ImportOptions::default(),
+            |_, _| {},
+        )
+        .unwrap();
+        assert_eq!(stats.relations_imported, 4);
+
+        let handle = crate::init(db_path.to_str().unwrap()).unwrap();
+        let groups = crate::thesaurus::lookup(&handle, "happy", "en").unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].sense.as_deref(), Some("Feeling joy."));
+        assert_eq!(groups[0].synonyms, ["glad", "joyful"]);
+        assert_eq!(groups[0].antonyms, ["sad"]);
+        assert_eq!(groups[1].sense.as_deref(), Some("lucky"));
+    }
+
+    #[test]
+    fn test_form_of_links_resolved() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl_path = dir.path().join("sample.jsonl");
+        let db_path = dir.path().join("test.db");
+        // Stubs come before their targets, as they often do in the dump
+        std::fs::write(
+            &jsonl_path,
+            concat!(
+                r#"{"word":"cats","pos":"noun","lang_code":"en","senses":[{"glosses":["plural of cat"],"form_of":[{"word":"cat"}]}]}"#,
+                "\n",
+                r#"{"word":"ran","pos":"verb","lang_code":"en","senses":[{"glosses":["simple past tense of run."]}]}"#,
+                "\n",
+                r#"{"word":"cat","pos":"noun","lang_code":"en","senses":[{"glosses":["A feline."]}]}"#,
+                "\n",
+                r#"{"word":"run","pos":"verb","lang_code":"en","senses":[{"glosses":["To move quickly."]}]}"#,
+                "\n",
+                r#"{"word":"top","pos":"noun","lang_code":"en","senses":[{"glosses":["The highest part of something."]}]}"#,
+            ),
+        )
+        .unwrap();
+
+        let stats = import_from_jsonl_with_options(
+            db_path.to_str().unwrap(),
+            jsonl_path.to_str().unwrap(),
+            &ImportOptions::default(),
+            |_, _| {},
+        )
+        .unwrap();
+        assert_eq!(stats.form_of_linked, 2);
+
+        let handle = crate::init(db_path.to_str().unwrap()).unwrap();
+        let plain = crate::get_definition(&handle, 1).unwrap();
+        let form_of = plain.definitions[0].form_of.as_ref().unwrap();
+        assert_eq!((form_of.word.as_str(), form_of.word_id), ("cat", Some(3)));
+        assert!(form_of.definitions.is_empty());
+
+        let options = crate::DefinitionOptions {
+            inline_form_of: true,
+            ..Default::default()
+        };
+        let ran = crate::get_definition_with_options(&handle, 2, &options).unwrap();
+        let form_of = ran.definitions[0].form_of.as_ref().unwrap();
+        assert_eq!(form_of.word, "run");
+        assert_eq!(form_of.definitions[0].text, "To move quickly.");
+
+        let top = crate::get_definition_with_options(&handle, 5, &options).unwrap();
+        assert!(top.definitions[0].form_of.is_none());
+    }
+
+    #[test]
+    fn test_attribution() {
+        let dir = tempfile::tempdir().unwrap();
+        let wiktionary = dir.path().join("wiktionary.jsonl");
+        let custom = dir.path().join("custom.jsonl");
+        let db_path = dir.path().join("test.db");
+        std::fs::write(
+            &wiktionary,
+            concat!(
+                r#"{"word":"ice cream","pos":"noun","lang_code":"en","senses":[{"glosses":["A frozen dessert."]}],"revision":81234567}"#,
+                "\n",
+                r#"{"word":"cat","pos":"noun","lang_code":"en","senses":[{"glosses":["A feline."]}]}"#,
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            &custom,
+            r#"{"word":"dog","pos":"noun","lang_code":"en","senses":[{"glosses":["A canine."]}]}"#,
+        )
+        .unwrap();
+
+        let db = db_path.to_str().unwrap();
+        import_from_jsonl_with_options(
+            db,
+            wiktionary.to_str().unwrap(),
+            &ImportOptions::default(),
+            |_, _| {},
+        )
+        .unwrap();
+        let options = ImportOptions {
+            source: SourceInfo {
+                name: "House glossary".to_string(),
+                license: "CC0".to_string(),
+                license_url: None,
+                page_url: None,
+            },
+            ..ImportOptions::default()
+        };
+        import_from_jsonl_with_options(db, custom.to_str().unwrap(), &options, |_, _| {}).unwrap();
+
+        let handle = crate::init(db).unwrap();
+        let ice_cream = crate::get_definition(&handle, 1)
+            .unwrap()
+            .attribution
+            .unwrap();
+        assert_eq!(ice_cream.source, "Wiktionary");
+        assert_eq!(ice_cream.license, "CC BY-SA 4.0");
+        assert_eq!(ice_cream.revision, Some(81234567));
+        assert_eq!(
+            ice_cream.url.as_deref(),
+            Some("https://en.wiktionary.org/wiki/ice_cream?oldid=81234567")
+        );
+
+        let cat = crate::get_definition(&handle, 2)
+            .unwrap()
+            .attribution
+            .unwrap();
+        assert_eq!(
+            cat.url.as_deref(),
+            Some("https://en.wiktionary.org/wiki/cat")
+        );
+        assert_eq!(cat.revision, None);
+
+        let dog = crate::get_definition(&handle, 3)
+            .unwrap()
+            .attribution
+            .unwrap();
+        assert_eq!(
+            (dog.source.as_str(), dog.license.as_str()),
+            ("House glossary", "CC0")
+        );
+        assert_eq!(dog.url, None);
+    }
+
+    #[cfg(feature = "kana")]
+    #[test]
+    fn test_kana_readings_indexed() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl_path = dir.path().join("sample.jsonl");
+        let db_path = dir.path().join("test.db");
+        std::fs::write(
+            &jsonl_path,
+            r#"{"word":"有難う","pos":"intj","lang":"Japanese","lang_code":"ja","senses":[{"glosses":["thank you"]}],"forms":[{"form":"ありがとう","tags":["hiragana"]},{"form":"arigatō","tags":["romanization"]}]}"#,
+        )
+        .unwrap();
+
+        import_from_jsonl_with_options(
+            db_path.to_str().unwrap(),
+            jsonl_path.to_str().unwrap(),
+            &ImportOptions::default(),
+            |_, _| {},
+        )
+        .unwrap();
+
+        let handle = crate::init(db_path.to_str().unwrap()).unwrap();
+        let results = crate::search::search_words(&handle, "arigatou", 5).unwrap();
+        assert_eq!(results[0].word, "有難う");
+    }
+
+    #[test]
+    fn test_parallel_parse_preserves_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl_path = dir.path().join("sample.jsonl");
+        let lines: Vec<String> = (0..50)
+            .map(|i| {
+                if i % 10 == 3 {
+                    "not json".to_string()
+                } else {
+                    format!(
+                        r#"{{"word":"word{}","pos":"noun","senses":[{{"glosses":["Gloss {}"]}}]}}"#,
+                        i, i
+                    )
+                }
+            })
+            .collect();
+        std::fs::write(&jsonl_path, lines.join("\n")).unwrap();
+
+        let import = |name: &str, jobs: usize| {
+            let db_path = dir.path().join(name);
+            let options = ImportOptions {
+                jobs,
+                ..ImportOptions::default()
+            };
+            let stats = import_from_jsonl_with_options(
+                db_path.to_str().unwrap(),
+                jsonl_path.to_str().unwrap(),
+                &options,
+                |_, _| {},
+            )
+            .unwrap();
+            (stats, crate::init(db_path.to_str().unwrap()).unwrap())
+        };
+
+        let (sequential_stats, sequential) = import("sequential.db", 1);
+        let (parallel_stats, parallel) = import("parallel.db", 4);
+        assert_eq!(parallel_stats.words_imported, 45);
+        assert_eq!(parallel_stats.errors, 5);
+        assert_eq!(
+            sequential_stats.words_imported,
+            parallel_stats.words_imported
+        );
+
+        for id in [1, 10, 45] {
+            let a = crate::db::get_word(&sequential, id).unwrap().unwrap();
+            let b = crate::db::get_word(&parallel, id).unwrap().unwrap();
+            assert_eq!(a.word, b.word);
+        }
+    }
+
+    #[test]
+    fn test_sample_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl_path = dir.path().join("sample.jsonl");
+        let lines: Vec<String> = (0..100)
+            .map(|i| format!(r#"{{"word":"word{}","pos":"noun"}}"#, i))
+            .collect();
+        std::fs::write(&jsonl_path, lines.join("\n")).unwrap();
+
+        let import = |name: &str, seed: Option<u64>| {
+            let db_path = dir.path().join(name);
+            let options = ImportOptions {
+                sample: Some(10),
+                seed,
+                ..ImportOptions::default()
+            };
+            let stats = import_from_jsonl_with_options(
+                db_path.to_str().unwrap(),
+                jsonl_path.to_str().unwrap(),
+                &options,
+                |_, _| {},
+            )
+            .unwrap();
+            let handle = crate::init(db_path.to_str().unwrap()).unwrap();
+            let words: Vec<String> = (1..=stats.words_imported as i64)
+                .map(|id| crate::db::get_word(&handle, id).unwrap().unwrap().word)
+                .collect();
+            (words, crate::db::get_metadata(&handle).unwrap())
+        };
+
+        let (first, metadata) = import("first.db", None);
+        assert_eq!(first.first().map(String::as_str), Some("word0"));
+        assert_eq!(first.last().map(String::as_str), Some("word9"));
+        assert_eq!(metadata.get("sample").unwrap(), "first 10");
+
+        let (random, metadata) = import("random.db", Some(42));
+        let (again, _) = import("random-again.db", Some(42));
+        assert_eq!(random.len(), 10);
+        assert_eq!(random, again);
+        assert_ne!(random, first);
+        assert_eq!(metadata.get("sample").unwrap(), "10 random (seed 42)");
+    }
+
+    #[test]
+    fn test_apply_frequency_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("freq.db");
+        let handle = crate::db::init_database(db_path.to_str().unwrap()).unwrap();
+        for word in ["the", "Paris", "cat", "zyzzyva"] {
+            insert_word(&handle.conn, word, "noun", "English", "en", 0).unwrap();
+        }
+        drop(handle);
+
+        let list_path = dir.path().join("words.csv");
+        std::fs::write(
+            &list_path,
+            "word,count\nthe,1000\nparis,50\ncat\t20\ndog 5\nbogus\n",
+        )
+        .unwrap();
+
+        let stats =
+            apply_frequency_list(db_path.to_str().unwrap(), list_path.to_str().unwrap()).unwrap();
+        assert_eq!(stats.list_entries, 4);
+        assert_eq!(stats.list_entries_matched, 3);
+        assert_eq!(stats.invalid_lines, 1);
+        assert_eq!(stats.words_total, 4);
+        assert_eq!(stats.words_matched, 3);
+        assert!((stats.match_rate() - 75.0).abs() < f64::EPSILON);
+
+        let conn = Connection::open(&db_path).unwrap();
+        let frequency = |word: &str| -> Option<i64> {
+            conn.query_row(
+                "SELECT frequency FROM words WHERE word = ?",
+                [word],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(frequency("the"), Some(1000));
+        assert_eq!(frequency("Paris"), Some(50));
+        assert_eq!(frequency("zyzzyva"), None);
+    }
+
+    #[test]
+    fn test_sense_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl = dir.path().join("senses.jsonl");
+        let db_path = dir.path().join("test.db");
+        std::fs::write(
+            &jsonl,
+            r#"{"word":"cat","pos":"noun","lang_code":"en","senses":[{"id":"en-cat-en-noun-c1","glosses":["A feline."]},{"glosses":["A jazz musician."]}]}"#,
+        )
+        .unwrap();
+        let build = || {
+            let _ = std::fs::remove_file(&db_path);
+            import_from_jsonl(
+                db_path.to_str().unwrap(),
+                jsonl.to_str().unwrap(),
+                |_, _| {},
+            )
+            .unwrap();
+            let handle = crate::init(db_path.to_str().unwrap()).unwrap();
+            crate::get_definition(&handle, 1)
+                .unwrap()
+                .definitions
+                .into_iter()
+                .map(|d| d.sense_id.unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let ids = build();
+        assert_eq!(ids[0], "en-cat-en-noun-c1");
+        assert!(ids[1].starts_with("en-"));
+        // Hashed ids survive a rebuild
+        assert_eq!(build(), ids);
+
+        let handle = crate::init(db_path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            crate::db::find_sense(&handle, &ids[1]).unwrap(),
+            Some((1, 2))
+        );
+        assert_eq!(crate::db::find_sense(&handle, "en-dog").unwrap(), None);
+    }
+
+    #[test]
+    fn test_merge_databases() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name).to_str().unwrap().to_string();
+        let build = |name: &str, jsonl: &str, source: SourceInfo| {
+            std::fs::write(path(&format!("{name}.jsonl")), jsonl).unwrap();
+            let options = ImportOptions {
+                source,
+                ..ImportOptions::default()
+            };
+            let jsonl_path = path(&format!("{name}.jsonl"));
+            import_from_jsonl_with_options(
+                &path(&format!("{name}.db")),
+                &jsonl_path,
+                &options,
+                |_, _| {},
+            )
+            .unwrap();
+        };
+        build(
+            "wiktionary",
+            r#"{"word":"cat","pos":"noun","lang_code":"en","senses":[{"glosses":["A feline."]}]}"#,
+            SourceInfo::default(),
+        );
+        let glossary = SourceInfo {
+            name: "House glossary".to_string(),
+            license: "CC0".to_string(),
+            license_url: None,
+            page_url: None,
+        };
+        build(
+            "glossary",
+            concat!(
+                r#"{"word":"cat","pos":"noun","lang_code":"en","senses":[{"glosses":["A feline."]},{"glosses":["A jazz musician."]}],"sounds":[{"ipa":"/kæt/"}]}"#,
+                "\n",
+                r#"{"word":"dog","pos":"noun","lang_code":"en","senses":[{"glosses":["A canine."]}]}"#,
+            ),
+            glossary,
+        );
+
+        let merged = path("merged.db");
+        let stats =
+            merge_databases(&merged, &[&path("wiktionary.db"), &path("glossary.db")]).unwrap();
+        assert_eq!(stats.databases_merged, 2);
+        assert_eq!((stats.words_added, stats.words_merged), (2, 1));
+        assert_eq!(stats.definitions_added, 3);
+
+        let handle = crate::init(&merged).unwrap();
+        let cat = crate::get_definition(&handle, 1).unwrap();
+        let senses: Vec<(&str, Option<&str>)> = cat
+            .definitions
+            .iter()
+            .map(|d| (d.text.as_str(), d.source.as_deref()))
+            .collect();
+        assert_eq!(
+            senses,
+            [
+                ("A feline.", None),
+                ("A jazz musician.", Some("House glossary"))
+            ]
+        );
+        assert_eq!(cat.pronunciations.len(), 1);
+        assert_eq!(cat.attribution.unwrap().source, "Wiktionary");
+
+        let dog = crate::get_definition(&handle, 2).unwrap();
+        assert_eq!(dog.definitions[0].source, None);
+        assert_eq!(dog.attribution.unwrap().source, "House glossary");
+    }
 }
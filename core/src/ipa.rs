@@ -0,0 +1,650 @@
+//! IPA transcription utilities
+//!
+//! Wiktionary transcriptions of the same pronunciation vary in notation:
+//! enclosing slashes or brackets, `t͡ʃ` vs `ʧ`, ASCII `g` and `:` instead of
+//! `ɡ` and `ː`, and so on. This module normalizes them and provides
+//! syllable/stress extraction, rhyme keys, search keys and a distance
+//! weighted by phonetic features for looking words up by transcription,
+//! a similarity metric, the regional accent named by a transcription's
+//! tags and respellings for readers who don't know IPA.
+
+/// Primary and secondary stress marks
+const PRIMARY_STRESS: char = 'ˈ';
+const SECONDARY_STRESS: char = 'ˌ';
+
+/// Combining double inverted breve, the canonical tie bar
+const TIE_BAR: char = '\u{0361}';
+
+/// Combining double breve below, an alternative tie bar
+const TIE_BAR_BELOW: char = '\u{035C}';
+
+/// Stress level of a syllable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stress {
+    Unstressed,
+    Primary,
+    Secondary,
+}
+
+/// A syllable of a transcription
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Syllable {
+    /// The syllable's segments, without stress marks
+    pub text: String,
+    pub stress: Stress,
+}
+
+/// Normalize a transcription for comparison
+///
+/// Strips enclosing `/.../` or `[...]`, unifies tie bars and affricate
+/// ligatures (`ʧ` becomes `t͡ʃ`), replaces ASCII look-alikes (`g`, `:`, `'`)
+/// with their IPA characters and collapses whitespace.
+pub fn normalize(ipa: &str) -> String {
+    let trimmed = ipa.trim().trim_matches(['/', '[', ']']).trim();
+    let mut out = String::with_capacity(trimmed.len());
+
+    for c in trimmed.chars() {
+        match c {
+            TIE_BAR_BELOW => out.push(TIE_BAR),
+            'ʧ' => out.push_str("t\u{0361}ʃ"),
+            'ʤ' => out.push_str("d\u{0361}ʒ"),
+            'ʦ' => out.push_str("t\u{0361}s"),
+            'ʣ' => out.push_str("d\u{0361}z"),
+            'ʨ' => out.push_str("t\u{0361}ɕ"),
+            'ʥ' => out.push_str("d\u{0361}ʑ"),
+            'g' => out.push('ɡ'),
+            ':' => out.push('ː'),
+            '\'' => out.push(PRIMARY_STRESS),
+            c if c.is_whitespace() => {
+                if !out.ends_with(' ') {
+                    out.push(' ');
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Split a transcription into syllables
+///
+/// Syllables are delimited by `.`, stress marks, spaces and hyphens. Only
+/// boundaries marked in the transcription are found; unmarked syllable
+/// breaks are not inferred.
+pub fn syllables(ipa: &str) -> Vec<Syllable> {
+    let normalized = normalize(ipa);
+    let mut result = Vec::new();
+    let mut current = String::new();
+    let mut stress = Stress::Unstressed;
+
+    for c in normalized.chars() {
+        let next_stress = match c {
+            PRIMARY_STRESS => Some(Stress::Primary),
+            SECONDARY_STRESS => Some(Stress::Secondary),
+            '.' | ' ' | '-' | '‿' => Some(Stress::Unstressed),
+            _ => None,
+        };
+        match next_stress {
+            Some(next) => {
+                if !current.is_empty() {
+                    result.push(Syllable {
+                        text: std::mem::take(&mut current),
+                        stress,
+                    });
+                }
+                stress = next;
+            }
+            None => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        result.push(Syllable {
+            text: current,
+            stress,
+        });
+    }
+    result
+}
+
+/// Index of the syllable carrying primary stress, if marked
+pub fn primary_stress(ipa: &str) -> Option<usize> {
+    syllables(ipa)
+        .iter()
+        .position(|s| s.stress == Stress::Primary)
+}
+
+/// The rhyming part of a transcription
+///
+/// Everything from the vowel of the last primary-stressed syllable to the
+/// end, without stress marks or boundaries; two words rhyme when their keys
+/// are equal. Without a stress mark the last syllable is used. Returns
+/// `None` if no vowel is found.
+pub fn rhyme_key(ipa: &str) -> Option<String> {
+    let syllables = syllables(ipa);
+    let start = syllables
+        .iter()
+        .rposition(|s| s.stress == Stress::Primary)
+        .or_else(|| syllables.len().checked_sub(1))?;
+
+    let tail: String = syllables[start..].iter().map(|s| s.text.as_str()).collect();
+    let vowel = tail.char_indices().find(|(_, c)| is_vowel(*c))?.0;
+    Some(tail[vowel..].replace(['(', ')'], ""))
+}
+
+/// Similarity of two transcriptions between 0.0 and 1.0
+///
+/// One minus the edit distance between their sound segments (a base symbol
+/// with its diacritics, length marks and tie-barred partner) divided by the
+/// longer length. Stress marks and syllable boundaries are ignored.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let a = segments(a);
+    let b = segments(b);
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(&a, &b) as f64 / longest as f64
+}
+
+/// Key for looking a transcription up: its sound segments without stress
+/// marks, syllable boundaries or spaces, so "/ˈniː.mɒ.nɪk/" and
+/// "[niːmɒnɪk]" share one
+///
+/// Returns `None` for a transcription with no sounds.
+pub fn search_key(ipa: &str) -> Option<String> {
+    let key = segments(ipa).concat();
+    (!key.is_empty()).then_some(key)
+}
+
+/// Cost of two segments differing only in diacritics or length ("e" and
+/// "eː"), or of a consonant close in every feature
+const DIACRITIC_COST: f64 = 0.25;
+
+/// Edit distance between two transcriptions' sound segments, with
+/// substitutions weighted by how far apart the sounds are
+///
+/// Inserting or deleting a segment costs 1, as does swapping a vowel for
+/// a consonant or symbols not in the feature tables. Vowels cost by
+/// height, backness and rounding ("ɪ" for "i" is cheap, "ɑ" for "i" is
+/// not), consonants by place, manner and voicing ("s" for "ʃ" is cheap,
+/// "s" for "m" is not), and segments differing only in diacritics or
+/// length cost a quarter. Stress marks and syllable boundaries are
+/// ignored.
+pub fn feature_distance(a: &str, b: &str) -> f64 {
+    let a = segments(a);
+    let b = segments(b);
+    let mut prev: Vec<f64> = (0..=b.len()).map(|n| n as f64).collect();
+    let mut curr = vec![0.0; b.len() + 1];
+    for (i, sa) in a.iter().enumerate() {
+        curr[0] = (i + 1) as f64;
+        for (j, sb) in b.iter().enumerate() {
+            let substitute = prev[j] + substitution_cost(sa, sb);
+            curr[j + 1] = substitute.min(prev[j + 1] + 1.0).min(curr[j] + 1.0);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+/// Minimum `similarity` for two transcriptions to count as the same
+/// pronunciation; short words must match exactly, long ones may differ in
+/// one segment in ten
+const NEAR_DUPLICATE_SIMILARITY: f64 = 0.9;
+
+/// Whether two transcriptions are near-identical renderings of the same
+/// pronunciation, differing only in notation or a minor detail
+pub fn is_near_duplicate(a: &str, b: &str) -> bool {
+    similarity(a, b) >= NEAR_DUPLICATE_SIMILARITY
+}
+
+/// Wiktionary accent tags and the region they belong to
+const ACCENT_REGIONS: &[(&str, &str)] = &[
+    ("US", "US"),
+    ("General-American", "US"),
+    ("GenAm", "US"),
+    ("GA", "US"),
+    ("UK", "UK"),
+    ("Received-Pronunciation", "UK"),
+    ("RP", "UK"),
+    ("British", "UK"),
+    ("England", "UK"),
+    ("AU", "AU"),
+    ("Australia", "AU"),
+    ("General-Australian", "AU"),
+    ("CA", "CA"),
+    ("Canada", "CA"),
+    ("NZ", "NZ"),
+    ("New-Zealand", "NZ"),
+    ("IE", "IE"),
+    ("Ireland", "IE"),
+    ("ZA", "ZA"),
+    ("South-Africa", "ZA"),
+    ("India", "IN"),
+];
+
+/// The region ("US", "UK", "AU", ...) of a Wiktionary accent tag
+///
+/// Names of standard accents count for their region, so
+/// "Received-Pronunciation" is "UK" and "General-American" is "US".
+/// Case-insensitive; None for tags that are not accents.
+pub fn accent_region(tag: &str) -> Option<&'static str> {
+    ACCENT_REGIONS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(tag))
+        .map(|(_, region)| *region)
+}
+
+/// How far down `preferred` an accent tag comes, `preferred.len()` for
+/// accents not in it and untagged pronunciations
+///
+/// Preferences are accent tags or regions: "US" also matches
+/// "General-American", "GenAm" only itself. Case-insensitive.
+pub fn accent_rank(accent: Option<&str>, preferred: &[String]) -> usize {
+    let Some(accent) = accent else {
+        return preferred.len();
+    };
+    let region = accent_region(accent);
+    preferred
+        .iter()
+        .position(|p| p.eq_ignore_ascii_case(accent) || region.is_some_and(|r| r == p.as_str()))
+        .unwrap_or(preferred.len())
+}
+
+/// Spelling conventions for [`to_respelling`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RespellingScheme {
+    /// Dictionary-style English respelling ("hə-LOH")
+    English,
+    /// Spanish spelling of the sounds ("ka-SA")
+    Spanish,
+}
+
+impl RespellingScheme {
+    /// The scheme for entries of language `lang_code`, if there is one
+    pub fn for_language(lang_code: &str) -> Option<Self> {
+        match lang_code {
+            "en" => Some(RespellingScheme::English),
+            "es" => Some(RespellingScheme::Spanish),
+            _ => None,
+        }
+    }
+
+    /// IPA sequences and their spellings; the longest match wins
+    fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            RespellingScheme::English => ENGLISH_RESPELLING,
+            RespellingScheme::Spanish => SPANISH_RESPELLING,
+        }
+    }
+}
+
+/// English respelling, after the usual dictionary keys
+const ENGLISH_RESPELLING: &[(&str, &str)] = &[
+    ("t\u{0361}ʃ", "ch"),
+    ("d\u{0361}ʒ", "j"),
+    ("aɪ", "eye"),
+    ("aʊ", "ow"),
+    ("eɪ", "ay"),
+    ("ɔɪ", "oy"),
+    ("oʊ", "oh"),
+    ("əʊ", "oh"),
+    ("ɪə", "eer"),
+    ("ɛə", "air"),
+    ("ʊə", "oor"),
+    ("iː", "ee"),
+    ("uː", "oo"),
+    ("ɑː", "ah"),
+    ("ɔː", "aw"),
+    ("ɜː", "ur"),
+    ("æ", "a"),
+    ("ɑ", "ah"),
+    ("ɒ", "o"),
+    ("ɔ", "aw"),
+    ("ɛ", "e"),
+    ("e", "e"),
+    ("ɪ", "i"),
+    ("i", "ee"),
+    ("ʊ", "uu"),
+    ("u", "oo"),
+    ("ʌ", "u"),
+    ("ɜ", "ur"),
+    ("ɝ", "ur"),
+    ("ɚ", "ər"),
+    ("ə", "ə"),
+    ("ʃ", "sh"),
+    ("ʒ", "zh"),
+    ("θ", "th"),
+    ("ð", "dh"),
+    ("ŋ", "ng"),
+    ("j", "y"),
+    ("ɡ", "g"),
+    ("ɹ", "r"),
+    ("ɾ", "t"),
+    ("ɫ", "l"),
+    ("ʍ", "wh"),
+    ("x", "kh"),
+    ("ʔ", ""),
+];
+
+/// Spanish respelling, in Spanish orthography
+const SPANISH_RESPELLING: &[(&str, &str)] = &[
+    ("t\u{0361}ʃ", "ch"),
+    ("ɲ", "ñ"),
+    ("ʎ", "ll"),
+    ("ʝ", "y"),
+    ("ɟ\u{0361}ʝ", "y"),
+    ("x", "j"),
+    ("r", "rr"),
+    ("ɾ", "r"),
+    ("β", "b"),
+    ("ð", "d"),
+    ("ɣ", "g"),
+    ("ɡ", "g"),
+    ("θ", "z"),
+    ("ŋ", "n"),
+    ("j", "i"),
+    ("w", "u"),
+];
+
+/// A transcription respelled for readers who don't know IPA
+///
+/// Syllables are joined with hyphens and the one with primary stress is
+/// written in capitals, so `/həˈloʊ/` becomes "hə-LOH". Sounds missing
+/// from the scheme's table are kept as written; length marks and other
+/// modifiers are dropped. Like [`syllables`], only syllable boundaries
+/// marked in the transcription are used. Returns `None` for a
+/// transcription without any sounds.
+pub fn to_respelling(ipa: &str, scheme: RespellingScheme) -> Option<String> {
+    let table = scheme.table();
+    let mut parts = Vec::new();
+    for syllable in syllables(ipa) {
+        let mut spelled = String::new();
+        let mut rest = syllable.text.as_str();
+        while let Some(c) = rest.chars().next() {
+            let (len, spelling) = table
+                .iter()
+                .filter(|(sound, _)| rest.starts_with(sound))
+                .max_by_key(|(sound, _)| sound.len())
+                .map(|(sound, spelling)| (sound.len(), *spelling))
+                .unwrap_or_else(|| {
+                    let kept = !is_modifier(c) && !matches!(c, '(' | ')' | TIE_BAR);
+                    (c.len_utf8(), if kept { &rest[..c.len_utf8()] } else { "" })
+                });
+            spelled.push_str(spelling);
+            rest = &rest[len..];
+        }
+        if spelled.is_empty() {
+            continue;
+        }
+        if syllable.stress == Stress::Primary {
+            spelled = spelled.to_uppercase();
+        }
+        parts.push(spelled);
+    }
+    (!parts.is_empty()).then(|| parts.join("-"))
+}
+
+/// Split a transcription into sound segments, dropping prosodic marks
+fn segments(ipa: &str) -> Vec<String> {
+    let normalized = normalize(ipa);
+    let mut segments: Vec<String> = Vec::new();
+    let mut join_next = false;
+
+    for c in normalized.chars() {
+        if matches!(
+            c,
+            PRIMARY_STRESS | SECONDARY_STRESS | '.' | ' ' | '-' | '‿' | '(' | ')'
+        ) {
+            continue;
+        }
+        match segments.last_mut() {
+            Some(last) if join_next || is_modifier(c) => {
+                last.push(c);
+                join_next = c == TIE_BAR;
+            }
+            _ => segments.push(c.to_string()),
+        }
+    }
+    segments
+}
+
+/// Levenshtein distance over segments
+fn edit_distance(a: &[String], b: &[String]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, sa) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, sb) in b.iter().enumerate() {
+            let cost = usize::from(sa != sb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+/// Vowels as (symbol, height from close 0 to open 6, backness from front
+/// 0 to back 2, rounded)
+#[rustfmt::skip]
+const VOWELS: &[(char, u8, u8, bool)] = &[
+    ('i', 0, 0, false), ('y', 0, 0, true), ('ɨ', 0, 1, false), ('ʉ', 0, 1, true),
+    ('ɯ', 0, 2, false), ('u', 0, 2, true),
+    ('ɪ', 1, 0, false), ('ʏ', 1, 0, true), ('ʊ', 1, 2, true),
+    ('e', 2, 0, false), ('ø', 2, 0, true), ('ɘ', 2, 1, false), ('ɵ', 2, 1, true),
+    ('ɤ', 2, 2, false), ('o', 2, 2, true),
+    ('ə', 3, 1, false), ('ɚ', 3, 1, false),
+    ('ɛ', 4, 0, false), ('œ', 4, 0, true), ('ɜ', 4, 1, false), ('ɝ', 4, 1, false),
+    ('ɞ', 4, 1, true), ('ʌ', 4, 2, false), ('ɔ', 4, 2, true),
+    ('æ', 5, 0, false), ('ɐ', 5, 1, false),
+    ('a', 6, 0, false), ('ɶ', 6, 0, true), ('ɑ', 6, 2, false), ('ɒ', 6, 2, true),
+];
+
+/// Consonants as (symbol, place from bilabial 0 to glottal 10, manner,
+/// voiced). Manners: plosive 0, nasal 1, trill 2, tap 3, fricative 4,
+/// lateral fricative 5, approximant 6, lateral approximant 7.
+#[rustfmt::skip]
+const CONSONANTS: &[(char, u8, u8, bool)] = &[
+    ('p', 0, 0, false), ('b', 0, 0, true), ('t', 3, 0, false), ('d', 3, 0, true),
+    ('ʈ', 5, 0, false), ('ɖ', 5, 0, true), ('c', 6, 0, false), ('ɟ', 6, 0, true),
+    ('k', 7, 0, false), ('ɡ', 7, 0, true), ('q', 8, 0, false), ('ɢ', 8, 0, true),
+    ('ʔ', 10, 0, false),
+    ('m', 0, 1, true), ('ɱ', 1, 1, true), ('n', 3, 1, true), ('ɳ', 5, 1, true),
+    ('ɲ', 6, 1, true), ('ŋ', 7, 1, true), ('ɴ', 8, 1, true),
+    ('ʙ', 0, 2, true), ('r', 3, 2, true), ('ʀ', 8, 2, true),
+    ('ɾ', 3, 3, true), ('ɽ', 5, 3, true),
+    ('ɸ', 0, 4, false), ('β', 0, 4, true), ('f', 1, 4, false), ('v', 1, 4, true),
+    ('θ', 2, 4, false), ('ð', 2, 4, true), ('s', 3, 4, false), ('z', 3, 4, true),
+    ('ʃ', 4, 4, false), ('ʒ', 4, 4, true), ('ɕ', 4, 4, false), ('ʑ', 4, 4, true),
+    ('ʂ', 5, 4, false), ('ʐ', 5, 4, true), ('ç', 6, 4, false), ('ʝ', 6, 4, true),
+    ('x', 7, 4, false), ('ɣ', 7, 4, true), ('χ', 8, 4, false), ('ʁ', 8, 4, true),
+    ('ħ', 9, 4, false), ('ʕ', 9, 4, true), ('h', 10, 4, false), ('ɦ', 10, 4, true),
+    ('ɬ', 3, 5, false), ('ɮ', 3, 5, true),
+    ('ʋ', 1, 6, true), ('ɹ', 3, 6, true), ('ɻ', 5, 6, true), ('j', 6, 6, true),
+    ('ɥ', 6, 6, true), ('ɰ', 7, 6, true), ('w', 7, 6, true),
+    ('l', 3, 7, true), ('ɫ', 3, 7, true), ('ɭ', 5, 7, true), ('ʎ', 6, 7, true),
+    ('ʟ', 7, 7, true),
+];
+
+/// Cost of substituting segment `b` for `a` in `feature_distance`, from 0
+/// to 1
+fn substitution_cost(a: &str, b: &str) -> f64 {
+    if a == b {
+        return 0.0;
+    }
+    let base = |segment: &str| -> Vec<char> {
+        segment
+            .chars()
+            .filter(|&c| c != TIE_BAR && !is_modifier(c))
+            .collect()
+    };
+    let (a, b) = (base(a), base(b));
+    match (a.as_slice(), b.as_slice()) {
+        _ if a == b => DIACRITIC_COST,
+        ([a], [b]) => sound_cost(*a, *b),
+        // Affricates and other tied segments, by their first and last sounds
+        ([a_first, ..], [b_first, ..]) => {
+            let (a_last, b_last) = (a[a.len() - 1], b[b.len() - 1]);
+            let cost = (sound_cost(*a_first, *b_first) + sound_cost(a_last, b_last)) / 2.0;
+            cost.max(DIACRITIC_COST)
+        }
+        _ => 1.0,
+    }
+}
+
+/// Cost of substituting sound `b` for `a`, from their features
+fn sound_cost(a: char, b: char) -> f64 {
+    if a == b {
+        return 0.0;
+    }
+    let vowel = |c: char| VOWELS.iter().find(|v| v.0 == c);
+    let consonant = |c: char| CONSONANTS.iter().find(|v| v.0 == c);
+    if let (Some(&(_, a_height, a_back, a_round)), Some(&(_, b_height, b_back, b_round))) =
+        (vowel(a), vowel(b))
+    {
+        let height = a_height.abs_diff(b_height) as f64 / 6.0;
+        let back = a_back.abs_diff(b_back) as f64 / 2.0;
+        let round = f64::from(u8::from(a_round != b_round));
+        return (height + back + round) / 3.0;
+    }
+    if let (Some(&(_, a_place, a_manner, a_voiced)), Some(&(_, b_place, b_manner, b_voiced))) =
+        (consonant(a), consonant(b))
+    {
+        let place = a_place.abs_diff(b_place).min(3) as f64 / 3.0;
+        let manner = f64::from(u8::from(a_manner != b_manner));
+        let voicing = f64::from(u8::from(a_voiced != b_voiced));
+        return ((place + manner + voicing) / 3.0).max(DIACRITIC_COST / 2.0);
+    }
+    1.0
+}
+
+/// Whether `c` is an IPA vowel symbol
+fn is_vowel(c: char) -> bool {
+    VOWELS.iter().any(|v| v.0 == c)
+}
+
+/// Whether `c` modifies the preceding segment rather than starting one
+fn is_modifier(c: char) -> bool {
+    matches!(c, '\u{0300}'..='\u{036F}')
+        || matches!(
+            c,
+            'ː' | 'ˑ' | 'ʰ' | 'ʷ' | 'ʲ' | 'ˠ' | 'ˤ' | 'ⁿ' | 'ˡ' | '˞' | 'ʼ'
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("/həˈloʊ/"), "həˈloʊ");
+        assert_eq!(normalize("[ˈʧɜːʧ]"), "ˈt͡ʃɜːt͡ʃ");
+        assert_eq!(normalize("/'gu:s/"), "ˈɡuːs");
+        assert_eq!(normalize("t\u{035C}s"), "t\u{0361}s");
+        assert_eq!(normalize(" /a   b/ "), "a b");
+    }
+
+    #[test]
+    fn test_accent_region() {
+        assert_eq!(accent_region("Received-Pronunciation"), Some("UK"));
+        assert_eq!(accent_region("general-american"), Some("US"));
+        assert_eq!(accent_region("Australia"), Some("AU"));
+        assert_eq!(accent_region("noun"), None);
+    }
+
+    #[test]
+    fn test_accent_rank() {
+        let preferred = ["UK".to_string(), "GenAm".to_string()];
+        assert_eq!(accent_rank(Some("RP"), &preferred), 0);
+        assert_eq!(accent_rank(Some("genam"), &preferred), 1);
+        assert_eq!(accent_rank(Some("General-American"), &preferred), 2);
+        assert_eq!(accent_rank(None, &preferred), 2);
+    }
+
+    #[test]
+    fn test_syllables_and_stress() {
+        let result = syllables("/ˌɪn.fəˈmeɪ.ʃən/");
+        let texts: Vec<&str> = result.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, ["ɪn", "fə", "meɪ", "ʃən"]);
+        assert_eq!(result[0].stress, Stress::Secondary);
+        assert_eq!(result[1].stress, Stress::Unstressed);
+        assert_eq!(result[2].stress, Stress::Primary);
+        assert_eq!(primary_stress("/ˌɪn.fəˈmeɪ.ʃən/"), Some(2));
+        assert_eq!(primary_stress("/kæt/"), None);
+    }
+
+    #[test]
+    fn test_rhyme_key() {
+        assert_eq!(rhyme_key("/kæt/").as_deref(), Some("æt"));
+        assert_eq!(rhyme_key("/kənˈkæt/").as_deref(), Some("æt"));
+        assert_eq!(rhyme_key("/ˌɪn.fəˈmeɪ.ʃən/").as_deref(), Some("eɪʃən"));
+        assert_eq!(rhyme_key("/ˌnɛ.ɡəˈteɪ.ʃən/"), rhyme_key("/ˌɪn.fəˈmeɪ.ʃən/"));
+        assert_eq!(rhyme_key("/pst/"), None);
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("/həˈloʊ/", "[hə.ˈloʊ]"), 1.0);
+        assert_eq!(similarity("/ʧɪp/", "/t͡ʃɪp/"), 1.0);
+        // One of three segments differs: t͡ʃ counts as a single segment
+        let s = similarity("/t͡ʃɪp/", "/ʃɪp/");
+        assert!((s - 2.0 / 3.0).abs() < 1e-9);
+        assert!(similarity("/kæt/", "/dɒɡ/") < 0.5);
+        assert_eq!(similarity("", ""), 1.0);
+    }
+
+    #[test]
+    fn test_search_key() {
+        assert_eq!(search_key("/ˈniː.mɒ.nɪk/").as_deref(), Some("niːmɒnɪk"));
+        assert_eq!(search_key("[niːmɒnɪk]"), search_key("/ˈniː.mɒ.nɪk/"));
+        assert_eq!(search_key("/ʧɪp/").as_deref(), Some("t\u{0361}ʃɪp"));
+        assert_eq!(search_key("/ˈ./"), None);
+    }
+
+    #[test]
+    fn test_feature_distance() {
+        assert_eq!(feature_distance("/ˈkæt/", "[kæt]"), 0.0);
+        // Near vowels and consonants cost less than distant ones
+        assert!(feature_distance("/bit/", "/bɪt/") < feature_distance("/bit/", "/bɑt/"));
+        assert!(feature_distance("/sɪp/", "/ʃɪp/") < feature_distance("/sɪp/", "/mɪp/"));
+        assert!(feature_distance("/pæt/", "/bæt/") < 1.0);
+        assert_eq!(feature_distance("/kæt/", "/kæts/"), 1.0);
+        assert_eq!(feature_distance("/kæt/", "/kæːt/"), DIACRITIC_COST);
+        assert_eq!(
+            feature_distance("/kæt/", "/kit/"),
+            feature_distance("/kit/", "/kæt/")
+        );
+        assert!(feature_distance("/t͡ʃɪp/", "/ʃɪp/") < 1.0);
+        assert_eq!(feature_distance("/kæt/", "/æ/"), 2.0);
+    }
+
+    #[test]
+    fn test_to_respelling() {
+        let en = RespellingScheme::English;
+        assert_eq!(to_respelling("/həˈloʊ/", en).as_deref(), Some("hə-LOH"));
+        assert_eq!(to_respelling("/kæt/", en).as_deref(), Some("kat"));
+        assert_eq!(to_respelling("/ʧɜːʧ/", en).as_deref(), Some("church"));
+        assert_eq!(
+            to_respelling("/ˌɪn.fəˈmeɪ.ʃən/", en).as_deref(),
+            Some("in-fə-MAY-shən")
+        );
+        assert_eq!(to_respelling("/ˈθɪŋ/", en).as_deref(), Some("THING"));
+        let es = RespellingScheme::Spanish;
+        assert_eq!(to_respelling("/ˈka.sa/", es).as_deref(), Some("KA-sa"));
+        assert_eq!(to_respelling("/ˈpe.ɾo/", es).as_deref(), Some("PE-ro"));
+        assert_eq!(to_respelling("/ˈpe.ro/", es).as_deref(), Some("PE-rro"));
+        assert_eq!(to_respelling("//", en), None);
+        assert_eq!(RespellingScheme::for_language("es"), Some(es));
+        assert_eq!(RespellingScheme::for_language("xx"), None);
+    }
+
+    #[test]
+    fn test_is_near_duplicate() {
+        assert!(is_near_duplicate("/ˈʧɜːʧ/", "[ˈt͜ʃɜːt͜ʃ]"));
+        assert!(!is_near_duplicate("/kæt/", "/kɑt/"));
+        assert!(is_near_duplicate(
+            "/ˌɪn.tərˌnæʃ.ə.nəlˈaɪ.zeɪ.ʃən/",
+            "/ˌɪn.tɚˌnæʃ.ə.nəlˈaɪ.zeɪ.ʃən/"
+        ));
+    }
+}
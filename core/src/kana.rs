@@ -0,0 +1,276 @@
+//! Kana and romaji conversion for Japanese lookups
+//!
+//! Readings are indexed in hiragana. Queries in katakana are folded to
+//! hiragana, and Hepburn romaji (plus common Kunrei-shiki and IME spellings
+//! such as `si`, `tu`, `nn`) is converted to hiragana before matching, so
+//! "arigatou" finds ありがとう.
+
+/// Offset between a katakana code point and its hiragana counterpart
+const KATAKANA_OFFSET: u32 = 0x60;
+
+/// Romaji syllables and their hiragana, longest spellings first within
+/// each group so greedy matching picks `sha` over `s` + `ha`
+const ROMAJI: &[(&str, &str)] = &[
+    // Three letters
+    ("kya", "きゃ"),
+    ("kyu", "きゅ"),
+    ("kyo", "きょ"),
+    ("sha", "しゃ"),
+    ("shi", "し"),
+    ("shu", "しゅ"),
+    ("she", "しぇ"),
+    ("sho", "しょ"),
+    ("sya", "しゃ"),
+    ("syu", "しゅ"),
+    ("syo", "しょ"),
+    ("cha", "ちゃ"),
+    ("chi", "ち"),
+    ("chu", "ちゅ"),
+    ("che", "ちぇ"),
+    ("cho", "ちょ"),
+    ("tya", "ちゃ"),
+    ("tyu", "ちゅ"),
+    ("tyo", "ちょ"),
+    ("tsu", "つ"),
+    ("nya", "にゃ"),
+    ("nyu", "にゅ"),
+    ("nyo", "にょ"),
+    ("hya", "ひゃ"),
+    ("hyu", "ひゅ"),
+    ("hyo", "ひょ"),
+    ("mya", "みゃ"),
+    ("myu", "みゅ"),
+    ("myo", "みょ"),
+    ("rya", "りゃ"),
+    ("ryu", "りゅ"),
+    ("ryo", "りょ"),
+    ("gya", "ぎゃ"),
+    ("gyu", "ぎゅ"),
+    ("gyo", "ぎょ"),
+    ("jya", "じゃ"),
+    ("jyu", "じゅ"),
+    ("jyo", "じょ"),
+    ("zya", "じゃ"),
+    ("zyu", "じゅ"),
+    ("zyo", "じょ"),
+    ("bya", "びゃ"),
+    ("byu", "びゅ"),
+    ("byo", "びょ"),
+    ("pya", "ぴゃ"),
+    ("pyu", "ぴゅ"),
+    ("pyo", "ぴょ"),
+    // Two letters
+    ("ka", "か"),
+    ("ki", "き"),
+    ("ku", "く"),
+    ("ke", "け"),
+    ("ko", "こ"),
+    ("sa", "さ"),
+    ("si", "し"),
+    ("su", "す"),
+    ("se", "せ"),
+    ("so", "そ"),
+    ("ta", "た"),
+    ("ti", "ち"),
+    ("tu", "つ"),
+    ("te", "て"),
+    ("to", "と"),
+    ("na", "な"),
+    ("ni", "に"),
+    ("nu", "ぬ"),
+    ("ne", "ね"),
+    ("no", "の"),
+    ("ha", "は"),
+    ("hi", "ひ"),
+    ("fu", "ふ"),
+    ("hu", "ふ"),
+    ("he", "へ"),
+    ("ho", "ほ"),
+    ("fa", "ふぁ"),
+    ("fi", "ふぃ"),
+    ("fe", "ふぇ"),
+    ("fo", "ふぉ"),
+    ("ma", "ま"),
+    ("mi", "み"),
+    ("mu", "む"),
+    ("me", "め"),
+    ("mo", "も"),
+    ("ya", "や"),
+    ("yu", "ゆ"),
+    ("yo", "よ"),
+    ("ra", "ら"),
+    ("ri", "り"),
+    ("ru", "る"),
+    ("re", "れ"),
+    ("ro", "ろ"),
+    ("wa", "わ"),
+    ("wo", "を"),
+    ("ga", "が"),
+    ("gi", "ぎ"),
+    ("gu", "ぐ"),
+    ("ge", "げ"),
+    ("go", "ご"),
+    ("za", "ざ"),
+    ("ji", "じ"),
+    ("zi", "じ"),
+    ("zu", "ず"),
+    ("ze", "ぜ"),
+    ("zo", "ぞ"),
+    ("ja", "じゃ"),
+    ("ju", "じゅ"),
+    ("je", "じぇ"),
+    ("jo", "じょ"),
+    ("da", "だ"),
+    ("di", "ぢ"),
+    ("du", "づ"),
+    ("de", "で"),
+    ("do", "ど"),
+    ("ba", "ば"),
+    ("bi", "び"),
+    ("bu", "ぶ"),
+    ("be", "べ"),
+    ("bo", "ぼ"),
+    ("pa", "ぱ"),
+    ("pi", "ぴ"),
+    ("pu", "ぷ"),
+    ("pe", "ぺ"),
+    ("po", "ぽ"),
+    ("vu", "ゔ"),
+    // Vowels
+    ("a", "あ"),
+    ("i", "い"),
+    ("u", "う"),
+    ("e", "え"),
+    ("o", "お"),
+];
+
+/// Whether `c` is hiragana, katakana or the prolonged sound mark
+pub fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3041}'..='\u{3096}' | '\u{30A1}'..='\u{30FA}' | 'ー' | '・')
+}
+
+/// Fold katakana to hiragana, leaving other characters unchanged
+pub fn to_hiragana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{30A1}'..='\u{30F6}' => char::from_u32(c as u32 - KATAKANA_OFFSET).unwrap_or(c),
+            c => c,
+        })
+        .collect()
+}
+
+/// Convert romaji to hiragana
+///
+/// Handles doubled consonants (`kitte` → きって), syllabic `n` (`n`, `nn`
+/// or `n'`) and macrons for long vowels (`ō` → おう). Returns `None` if
+/// any part of the input is not romaji.
+pub fn romaji_to_hiragana(romaji: &str) -> Option<String> {
+    let input: String = romaji
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'ā' => "aa".to_string(),
+            'ī' => "ii".to_string(),
+            'ū' => "uu".to_string(),
+            'ē' => "ei".to_string(),
+            'ō' | 'ô' => "ou".to_string(),
+            c => c.to_string(),
+        })
+        .collect();
+    let bytes = input.as_bytes();
+    if !input.is_ascii() || input.is_empty() {
+        return None;
+    }
+
+    let mut out = String::with_capacity(input.len() * 3);
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        let next = bytes.get(i + 1).copied();
+
+        if c == b' ' || c == b'-' {
+            i += 1;
+            continue;
+        }
+        if c == b'n' && !next.is_some_and(|n| is_vowel(n) || n == b'y') {
+            out.push('ん');
+            // `nn` and `n'` spell a single ん unless a vowel follows
+            i += if matches!(next, Some(b'\''))
+                || next == Some(b'n') && !next_is_vowel(bytes, i + 2)
+            {
+                2
+            } else {
+                1
+            };
+            continue;
+        }
+        if next == Some(c) && c.is_ascii_alphabetic() && !is_vowel(c) {
+            out.push('っ');
+            i += 1;
+            continue;
+        }
+        // `tch` as in `matcha`
+        if c == b't' && next == Some(b'c') {
+            out.push('っ');
+            i += 1;
+            continue;
+        }
+
+        let rest = &input[i..];
+        let (spelling, kana) = ROMAJI.iter().find(|(r, _)| rest.starts_with(r))?;
+        out.push_str(kana);
+        i += spelling.len();
+    }
+    Some(out)
+}
+
+/// Reduce a query to a hiragana reading, if it is kana or romaji
+pub fn query_reading(query: &str) -> Option<String> {
+    let query = query.trim();
+    if !query.is_empty() && query.chars().all(|c| is_kana(c) || c == ' ') {
+        return Some(to_hiragana(&query.replace(' ', "")));
+    }
+    romaji_to_hiragana(query)
+}
+
+fn is_vowel(c: u8) -> bool {
+    matches!(c, b'a' | b'i' | b'u' | b'e' | b'o')
+}
+
+fn next_is_vowel(bytes: &[u8], i: usize) -> bool {
+    bytes.get(i).is_some_and(|&c| is_vowel(c) || c == b'y')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_romaji_to_hiragana() {
+        assert_eq!(
+            romaji_to_hiragana("arigatou").as_deref(),
+            Some("ありがとう")
+        );
+        assert_eq!(romaji_to_hiragana("arigatō").as_deref(), Some("ありがとう"));
+        assert_eq!(
+            romaji_to_hiragana("Konnichiha").as_deref(),
+            Some("こんにちは")
+        );
+        assert_eq!(romaji_to_hiragana("kitte").as_deref(), Some("きって"));
+        assert_eq!(romaji_to_hiragana("matcha").as_deref(), Some("まっちゃ"));
+        assert_eq!(romaji_to_hiragana("shinbun").as_deref(), Some("しんぶん"));
+        assert_eq!(romaji_to_hiragana("kin'en").as_deref(), Some("きんえん"));
+        assert_eq!(romaji_to_hiragana("kinen").as_deref(), Some("きねん"));
+        assert_eq!(romaji_to_hiragana("honn").as_deref(), Some("ほん"));
+        assert_eq!(romaji_to_hiragana("xyz"), None);
+        assert_eq!(romaji_to_hiragana("café"), None);
+    }
+
+    #[test]
+    fn test_query_reading() {
+        assert_eq!(query_reading("カタカナ").as_deref(), Some("かたかな"));
+        assert_eq!(query_reading("ありがとう").as_deref(), Some("ありがとう"));
+        assert_eq!(query_reading("sushi").as_deref(), Some("すし"));
+        assert_eq!(query_reading("寿司"), None);
+    }
+}
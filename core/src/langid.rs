@@ -0,0 +1,245 @@
+//! Guessing the language of a search query
+//!
+//! Non-Latin scripts mostly identify the language on their own (Hangul is
+//! Korean, kana is Japanese). Latin-script queries are scored against a
+//! small profile of the most frequent character trigrams of each language,
+//! plus letters that only some languages use (ß, ñ, ç). Queries are short,
+//! so the result is a ranked guess, not a verdict.
+
+use crate::models::LanguageGuess;
+
+/// Most frequent character trigrams per language, most frequent first
+///
+/// Spaces mark word boundaries; queries are padded with one on each side.
+const TRIGRAMS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            " th", "the", "he ", "ing", "ng ", "and", " an", "nd ", " to", "ion", "ed ", " of",
+            "of ", "er ", "ent", "tio", " in", "es ", "re ", "ly ", "ter", "her", "hat", "tha",
+            " wh", "ght", "all", "ver", "ous", "ss ",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "en ", "er ", "ch ", "der", "ie ", "ein", "sch", "ich", "nde", "die", " di", " de",
+            "che", " ei", "den", "gen", "ung", " un", "und", "cht", "ber", " ge", "ten", "auf",
+            "zu ", "eit", "lic", "hen", "tz ", "ck ", "ng ",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "es ", " de", "de ", "le ", " le", "ent", "nt ", "ion", "la ", " la", "on ", "re ",
+            "les", " co", "que", "ue ", "eme", " pa", "ait", "des", "our", "ous", "eau", "aux",
+            "oir", " qu", " et", "et ", "eur", "ais",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "de ", " de", "os ", " la", "la ", "el ", " el", "que", "ue ", "as ", "ent", "ión",
+            "ado", "ida", "nte", " co", "con", "do ", "ar ", " en", " qu", "ero", "ien", "aci",
+            "lla", "mie", "dad", "ció", "ía ", "ez ",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "di ", " di", "che", "la ", "to ", "re ", "ell", "lla", "del", "zio", "one", "no ",
+            "are", "ato", "ta ", "ne ", " co", "per", " pe", "gli", " il", "il ", "ere", "tto",
+            "tti", "zza", "ia ", "io ", "ino", "tà ",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "de ", " de", "os ", "ão ", "ção", "do ", " co", "que", "as ", "da ", "com", "est",
+            "men", "nte", " qu", "em ", "ões", "nho", "lha", "ado", " pa", "par", "ra ", "ar ",
+            "ada", "ido", "ei ", "im ", "um ", "ns ",
+        ],
+    ),
+    (
+        "nl",
+        &[
+            "en ", "de ", " de", "het", " he", "van", " va", "an ", "ijk", "ij ", "een", " ee",
+            "er ", "sch", "ver", " ve", "oor", "cht", "aar", "oed", "ie ", "gen", "te ", "ng ",
+            "aan", "ook", "uit", "zij", "eid", "jk ",
+        ],
+    ),
+];
+
+/// Letters used by only some Latin-script languages
+const DISTINCTIVE_LETTERS: &[(char, &[&str])] = &[
+    ('ß', &["de"]),
+    ('ä', &["de"]),
+    ('ö', &["de"]),
+    ('ü', &["de"]),
+    ('ñ', &["es"]),
+    ('¿', &["es"]),
+    ('¡', &["es"]),
+    ('ç', &["fr", "pt"]),
+    ('œ', &["fr"]),
+    ('ë', &["fr", "nl"]),
+    ('î', &["fr"]),
+    ('û', &["fr"]),
+    ('è', &["fr", "it"]),
+    ('ê', &["fr", "pt"]),
+    ('â', &["fr", "pt"]),
+    ('ù', &["fr", "it"]),
+    ('ì', &["it"]),
+    ('ò', &["it"]),
+    ('ã', &["pt"]),
+    ('õ', &["pt"]),
+    ('á', &["es", "pt"]),
+    ('í', &["es", "pt"]),
+    ('ó', &["es", "pt"]),
+    ('ú', &["es", "pt"]),
+];
+
+/// Languages with their probabilities, most likely first
+type Guesses = &'static [(&'static str, f64)];
+
+/// Scripts used by one or a few languages, as (first, last, guesses)
+const SCRIPTS: &[(char, char, Guesses)] = &[
+    ('\u{0370}', '\u{03FF}', &[("el", 1.0)]),
+    ('\u{0590}', '\u{05FF}', &[("he", 1.0)]),
+    ('\u{0600}', '\u{06FF}', &[("ar", 0.8), ("fa", 0.2)]),
+    ('\u{0900}', '\u{097F}', &[("hi", 1.0)]),
+    ('\u{0E00}', '\u{0E7F}', &[("th", 1.0)]),
+    ('\u{0530}', '\u{058F}', &[("hy", 1.0)]),
+    ('\u{10A0}', '\u{10FF}', &[("ka", 1.0)]),
+];
+
+/// Score for a distinctive letter, worth several trigram matches
+const DISTINCTIVE_LETTER_SCORE: f64 = 3.0;
+
+/// Guess the language of `query`
+///
+/// Returns candidate language codes with probabilities that sum to 1,
+/// most likely first. Empty when the query has no letters or is in a
+/// Latin-script language this model doesn't know.
+pub fn detect_language(query: &str) -> Vec<LanguageGuess> {
+    let query = query.trim().to_lowercase();
+    if !query.chars().any(char::is_alphabetic) {
+        return Vec::new();
+    }
+
+    if let Some(guesses) = detect_by_script(&query) {
+        return guesses
+            .iter()
+            .map(|&(code, probability)| LanguageGuess {
+                code: code.to_string(),
+                probability,
+            })
+            .collect();
+    }
+    detect_latin(&query)
+}
+
+/// Languages implied by the script of `query`, if it isn't Latin
+fn detect_by_script(query: &str) -> Option<Guesses> {
+    let letters: Vec<char> = query.chars().filter(|c| c.is_alphabetic()).collect();
+    let any = |range: std::ops::RangeInclusive<char>| letters.iter().any(|c| range.contains(c));
+
+    if letters.iter().any(|&c| crate::hangul::is_hangul(c)) {
+        return Some(&[("ko", 1.0)]);
+    }
+    // Hiragana and katakana
+    if any('\u{3040}'..='\u{30FF}') {
+        return Some(&[("ja", 1.0)]);
+    }
+    // CJK ideographs alone are Chinese or kanji-only Japanese
+    if any('\u{4E00}'..='\u{9FFF}') || any('\u{3400}'..='\u{4DBF}') {
+        return Some(&[("zh", 0.6), ("ja", 0.4)]);
+    }
+    if any('\u{0400}'..='\u{04FF}') {
+        // Letters only Ukrainian uses
+        if letters.iter().any(|c| "іїєґ".contains(*c)) {
+            return Some(&[("uk", 1.0)]);
+        }
+        return Some(&[("ru", 0.8), ("uk", 0.1), ("bg", 0.1)]);
+    }
+    SCRIPTS
+        .iter()
+        .find(|(first, last, _)| any(*first..=*last))
+        .map(|(_, _, guesses)| *guesses)
+}
+
+/// Trigram and letter scores for a Latin-script query, as probabilities
+fn detect_latin(query: &str) -> Vec<LanguageGuess> {
+    let padded: Vec<char> = format!(" {} ", query).chars().collect();
+    let trigrams: Vec<String> = padded.windows(3).map(|w| w.iter().collect()).collect();
+
+    let mut scores: Vec<(&str, f64)> = TRIGRAMS
+        .iter()
+        .map(|(code, profile)| {
+            let mut score = 0.0;
+            for trigram in &trigrams {
+                if let Some(rank) = profile.iter().position(|t| t == trigram) {
+                    // 2 for the most frequent trigram down to just over 1
+                    score += 1.0 + (profile.len() - rank) as f64 / profile.len() as f64;
+                }
+            }
+            for c in query.chars() {
+                if let Some((_, codes)) = DISTINCTIVE_LETTERS.iter().find(|(l, _)| *l == c) {
+                    if codes.contains(code) {
+                        score += DISTINCTIVE_LETTER_SCORE;
+                    }
+                }
+            }
+            (*code, score)
+        })
+        .collect();
+
+    if scores.iter().all(|(_, score)| *score == 0.0) {
+        return Vec::new();
+    }
+
+    // Softmax, so a lead of a few points is a clear preference
+    let total: f64 = scores.iter().map(|(_, score)| score.exp()).sum();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scores
+        .into_iter()
+        .map(|(code, score)| LanguageGuess {
+            code: code.to_string(),
+            probability: score.exp() / total,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn top(query: &str) -> Option<String> {
+        detect_language(query).first().map(|g| g.code.clone())
+    }
+
+    #[test]
+    fn test_detect_by_script() {
+        assert_eq!(top("사랑").as_deref(), Some("ko"));
+        assert_eq!(top("ねこ").as_deref(), Some("ja"));
+        assert_eq!(top("猫").as_deref(), Some("zh"));
+        assert_eq!(top("кошка").as_deref(), Some("ru"));
+        assert_eq!(top("їжак").as_deref(), Some("uk"));
+        assert_eq!(top("γάτα").as_deref(), Some("el"));
+    }
+
+    #[test]
+    fn test_detect_latin() {
+        assert_eq!(top("the weather").as_deref(), Some("en"));
+        assert_eq!(top("Straße").as_deref(), Some("de"));
+        assert_eq!(top("Entschuldigung").as_deref(), Some("de"));
+        assert_eq!(top("mañana").as_deref(), Some("es"));
+        assert_eq!(top("garçon").as_deref(), Some("fr"));
+        assert_eq!(top("coração").as_deref(), Some("pt"));
+
+        let guesses = detect_language("nothing");
+        let total: f64 = guesses.iter().map(|g| g.probability).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!(detect_language("42").is_empty());
+        assert!(detect_language("xyzzy").is_empty());
+    }
+}
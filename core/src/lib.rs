@@ -3,10 +3,51 @@
 //! Core library for the Wiktionary Dictionary App.
 //!
 //! This crate provides:
-//! - SQLite database operations for dictionary storage
-//! - Full-text search (FTS5) and fuzzy search capabilities
-//! - Data models for dictionary entries
-//! - JSONL import functionality for building the database
+//! - SQLite database operations for dictionary storage, and transactional
+//!   editing of entries
+//! - Full-text search (FTS5) and fuzzy search capabilities, with a
+//!   Levenshtein automaton over the sorted headword keys, and wildcard
+//!   patterns (`fuzzy`)
+//! - Lookup by IPA transcription, exact or by phonetic feature distance
+//! - Ranked prefix completion for search-as-you-type
+//! - Per-handle settings for caching, fuzzy matching, previews, ranking,
+//!   filtering offensive entries and demoting dated senses
+//! - Data models for dictionary entries, with per-entry source and license
+//! - Normalized headword keys (case and diacritics folded) and A–Z browsing
+//! - JSONL import functionality for building the database, and merging
+//!   built dictionaries with per-sense provenance
+//! - Export to JSONL, CSV and StarDict, and smaller subsets of a dictionary
+//! - Escaped HTML and Markdown rendering of entries, identical on every platform
+//! - Spell checking with frequency-weighted suggestions
+//! - US or UK spellings shown first, with the other as "also spelled"
+//! - Edit distances and similarity scores (`text_distance`)
+//! - Grapheme-aware truncation and accent-folded match highlighting
+//!   (`graphemes`)
+//! - Tokenizing and linking passages for reading views
+//! - Federated search across dictionaries with query language detection
+//! - Emoji and symbol lookup, with names from Unicode CLDR annotations
+//! - Thesaurus lookup of synonyms and antonyms by sense
+//! - Translations between two non-English languages through English
+//!   entries (`translate`)
+//! - Topic word lists by frequency level for study features (`lists`)
+//! - Word-game validity, tile scores and rack solving (`games`)
+//! - Collocations mined from example sentences
+//! - Added, removed and changed entries between two builds (`diff`)
+//! - Pronunciation audio prefetch for offline use (`audio` feature)
+//! - Resumable, checksum-verified dictionary downloads and update checks
+//!   (`download` feature)
+//! - Offline `.dictpkg` packages of a database with its license files and
+//!   FST, verified and installed atomically (`package` feature)
+//! - Kana/romaji lookup for Japanese entries (`kana` feature)
+//! - A memory-mapped FST of the headword keys written next to the
+//!   database (`fst` feature)
+//! - Concept search over gloss embeddings (`semantic` feature)
+//! - Spans around search tiers, definition loading and import phases,
+//!   with per-query timings (`tracing` feature)
+//! - Privacy-preserving search telemetry hooks for the host app
+//! - View history, flashcards and daily activity in the user database,
+//!   and home screen suggestions drawn from them
+//! - Recall and latency checks for gating dictionary releases
 //! - C FFI exports for cross-platform integration (Android, iOS, WASM)
 //!
 //! ## Usage
@@ -20,18 +61,99 @@
 //!     let definition = get_definition(&handle, result.id);
 //! }
 //! ```
+//!
+//! or, configured through a builder and with errors returned:
+//!
+//! ```ignore
+//! let dict = dict_core::Dict::open("/path/to/dictionary.db").read_only().build()?;
+//! let results = dict.search("hello", 10)?;
+//! ```
+
+/// Enter a `tracing` span until the end of the enclosing block
+///
+/// Expands to nothing without the `tracing` feature, so fields must not
+/// be the only use of a variable.
+macro_rules! trace_span {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($($arg)*).entered();
+    };
+}
 
+#[cfg(feature = "audio")]
+pub mod audio;
+mod cache;
+pub mod config;
+pub mod context;
 pub mod db;
+pub mod dict;
+pub mod diff;
+#[cfg(feature = "download")]
+pub mod download;
+pub mod editor;
+pub mod examples;
+pub mod export;
 pub mod ffi;
+pub mod fuzzy;
+pub mod games;
+pub mod graphemes;
+pub mod hangul;
 pub mod import;
+pub mod ipa;
+#[cfg(feature = "kana")]
+pub mod kana;
+mod langid;
+pub mod lists;
 pub mod models;
+pub mod morph;
+pub mod normalize;
+#[cfg(feature = "package")]
+pub mod package;
+pub mod pinyin;
+pub mod qa;
+pub mod render;
 pub mod search;
+#[cfg(feature = "semantic")]
+pub mod semantic;
+pub mod spell;
+pub mod spelling;
+pub mod suggestions;
+pub mod symbols;
+pub mod telemetry;
+pub mod text;
+pub mod text_distance;
+pub mod thesaurus;
+pub mod translate;
+#[cfg(feature = "download")]
+pub mod updates;
+pub mod userdata;
 
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use thiserror::Error;
 
-pub use import::ImportStats;
-pub use models::{Definition, FullDefinition, Pronunciation, SearchResult, Translation, Word};
+pub use config::{
+    ContentFilter, ContentFilterMode, DatedSenseMode, DatedSenses, DictConfig, FtsConfig,
+    FuzzyConfig, OverlayConfig, OverlayPrecedence, RankingStrategy, SpellingRegion, StopBehavior,
+    TierConfig,
+};
+pub use context::define_in_context;
+pub use dict::{Dict, DictBuilder};
+pub use export::{ExportFormat, SubsetFilters, SubsetStats};
+pub use import::{
+    FrequencyStats, FtsProfile, ImportEvent, ImportOptions, ImportPhase, ImportStats,
+    ImportTimings, MergeStats,
+};
+pub use models::{
+    AccentGroup, Annotation, Attribution, BrowseOptions, Capabilities, Collocate, CompactOptions,
+    CompactStats, CompatibilityReport, DatabaseStats, Definition, DefinitionOptions,
+    DefinitionPage, DefinitionParts, EntryMatch, EntryOrigin, EtymologyEntry, EtymologyGroup,
+    FederatedMode, FederatedResult, FormOf, FullDefinition, GraphEdge, GraphNode, LanguageGuess,
+    LetterCount, Link, MatchKind, PhraseFilters, PreviewOptions, Pronunciation, RelationKind,
+    SearchOptions, SearchPage, SearchResult, SearchTimings, SenseMatch, SourceInfo, ThesaurusSense,
+    TierTiming, TracedSearch, Translation, Word, WordGraph,
+};
+pub use normalize::Normalization;
 
 /// Errors that can occur in dict-core operations
 #[derive(Error, Debug)]
@@ -50,6 +172,36 @@ pub enum Error {
 
     #[error("Invalid database path: {0}")]
     InvalidPath(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    /// The database, usually its full-text index, is damaged; rebuilding
+    /// the index or downloading the dictionary again may fix it
+    #[error("Corrupt search index: {0}")]
+    FtsCorrupt(String),
+
+    /// The query was stopped by `DictHandle::interrupt`
+    #[error("Interrupted")]
+    Interrupted,
+
+    /// The query could not be parsed, e.g. as a full-text expression
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+
+    #[error("Download failed: {0}")]
+    Download(String),
+
+    #[error("Checksum mismatch for {file}: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        file: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// A `.dictpkg` file is malformed or truncated
+    #[error("Invalid package: {0}")]
+    InvalidPackage(String),
 }
 
 /// Result type alias for dict-core operations
@@ -61,6 +213,16 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// It wraps a connection pool to the SQLite database.
 pub struct DictHandle {
     pub(crate) conn: Arc<rusqlite::Connection>,
+    pub(crate) config: DictConfig,
+    pub(crate) capabilities: Capabilities,
+    pub(crate) normalization: Normalization,
+    /// Headword keys for fuzzy search, loaded on first use
+    pub(crate) headwords: RwLock<Option<Arc<fuzzy::HeadwordIndex>>>,
+    /// Headwords were written since the FST sidecar was built, so prefix
+    /// search and completion read SQLite until `refresh_indexes`
+    pub(crate) fst_stale: AtomicBool,
+    /// Definitions loaded recently (see `DictConfig::definition_cache`)
+    pub(crate) definitions: Mutex<cache::DefinitionCache>,
 }
 
 // Safety: rusqlite::Connection with proper configuration is thread-safe for reads
@@ -68,6 +230,143 @@ pub struct DictHandle {
 unsafe impl Send for DictHandle {}
 unsafe impl Sync for DictHandle {}
 
+impl DictHandle {
+    /// Wrap an open connection in a handle
+    // The Send/Sync impls above cover the connection, so the lint does not apply
+    #[allow(clippy::arc_with_non_send_sync)]
+    pub(crate) fn from_connection(conn: rusqlite::Connection) -> Self {
+        let capabilities = Capabilities {
+            fts5: db::fts5_available(&conn),
+        };
+        if !capabilities.fts5 {
+            log::warn!("SQLite has no FTS5, falling back to substring search");
+        }
+        let normalization = db::normalization(&conn).unwrap_or_else(|e| {
+            log::warn!("Could not read the normalization policy: {}", e);
+            Normalization::default()
+        });
+        Self {
+            conn: Arc::new(conn),
+            config: DictConfig::default(),
+            capabilities,
+            normalization,
+            headwords: RwLock::default(),
+            fst_stale: AtomicBool::new(false),
+            definitions: Mutex::default(),
+        }
+    }
+
+    /// The headword keys fuzzy search walks, loaded on first use
+    pub(crate) fn headword_index(&self) -> Result<Arc<fuzzy::HeadwordIndex>> {
+        if let Some(index) = self.headwords.read().unwrap().as_ref() {
+            return Ok(index.clone());
+        }
+        let index = Arc::new(fuzzy::HeadwordIndex::load(&self.conn)?);
+        Ok(self.headwords.write().unwrap().get_or_insert(index).clone())
+    }
+
+    /// The headword keys if they are mapped from an FST that is up to
+    /// date, without reading them from SQLite when there is none
+    pub(crate) fn headword_fst(&self) -> Result<Option<Arc<fuzzy::HeadwordIndex>>> {
+        if self.fst_stale.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        let cached = self.headwords.read().unwrap().clone();
+        let index = match cached {
+            Some(index) => index,
+            None if cfg!(feature = "fst") && self.fst_path().is_some_and(|p| p.exists()) => {
+                self.headword_index()?
+            }
+            None => return Ok(None),
+        };
+        Ok(index.is_fst().then_some(index))
+    }
+
+    /// Where the headword FST of this handle's database is kept, if the
+    /// database is a file
+    fn fst_path(&self) -> Option<std::path::PathBuf> {
+        let path = self.conn.path().filter(|p| !p.is_empty())?;
+        Some(fuzzy::fst_path(std::path::Path::new(path)))
+    }
+
+    /// Forget what this handle keeps in memory about `word_id`, after
+    /// writing to it other than through [`editor::edit`] (which forgets
+    /// everything it may have changed)
+    ///
+    /// Drops the cached definitions of the word and of entries that inline
+    /// it, and the headword keys fuzzy search walks, which are read again
+    /// on first use. A headword FST sidecar is only rebuilt by
+    /// [`Self::refresh_indexes`].
+    pub fn invalidate(&self, word_id: i64) {
+        self.definitions.lock().unwrap().remove_word(word_id);
+        self.clear_headword_index();
+        self.fst_stale.store(true, Ordering::Relaxed);
+    }
+
+    /// Bring the indexes kept outside the database up to date, after
+    /// edits that add, rename or delete headwords
+    ///
+    /// Rebuilds the headword FST sidecar when the dictionary has one, then
+    /// drops everything the handle caches in memory. (Full-text search and
+    /// completions are kept in sync by the writes themselves.) The FST is
+    /// rebuilt from all headwords, so call this once after a batch of
+    /// edits rather than after each. Until then, prefix search and
+    /// completion read SQLite instead of the FST.
+    pub fn refresh_indexes(&self) -> Result<()> {
+        #[cfg(feature = "fst")]
+        if let Some(path) = self.conn.path().filter(|p| !p.is_empty()) {
+            if fuzzy::fst_path(std::path::Path::new(path)).exists() {
+                fuzzy::build_fst(path)?;
+            }
+        }
+        self.fst_stale.store(false, Ordering::Relaxed);
+        self.clear_caches();
+        Ok(())
+    }
+
+    /// Drop the cached definitions and headword keys
+    pub(crate) fn clear_caches(&self) {
+        self.definitions.lock().unwrap().clear();
+        self.clear_headword_index();
+    }
+
+    fn clear_headword_index(&self) {
+        *self.headwords.write().unwrap() = None;
+    }
+
+    /// Settings this handle was opened with
+    pub fn config(&self) -> &DictConfig {
+        &self.config
+    }
+
+    /// What the SQLite library under this handle supports
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    /// How headwords of this dictionary were folded when it was built,
+    /// and so how queries are
+    pub fn normalization(&self) -> Normalization {
+        self.normalization
+    }
+
+    /// Preview settings used by searches on this handle
+    pub fn preview_options(&self) -> &PreviewOptions {
+        &self.config.preview
+    }
+
+    /// Change how searches on this handle build result previews
+    pub fn set_preview_options(&mut self, options: PreviewOptions) {
+        self.config.preview = options;
+    }
+
+    /// Stop the query running on this handle, e.g. from another thread
+    /// when the user has typed on; it fails with `Error::Interrupted`
+    pub fn interrupt(&self) {
+        self.conn.get_interrupt_handle().interrupt();
+    }
+}
+
 /// Initialize the dictionary with a database path
 ///
 /// Opens an existing SQLite database at the specified path in read-only mode.
@@ -91,6 +390,24 @@ pub fn init(db_path: &str) -> Result<DictHandle> {
     db::open_readonly(db_path)
 }
 
+/// Initialize the dictionary with a database path and settings
+///
+/// Like `init`, but the handle uses `config` for its cache sizes, fuzzy
+/// matching, previews and ranking, and attaches `config.user_db` if set.
+///
+/// # Example
+///
+/// ```ignore
+/// let config = DictConfig {
+///     ranking: RankingStrategy::Frequency,
+///     ..DictConfig::default()
+/// };
+/// let handle = dict_core::init_with_config("/path/to/dictionary.db", config)?;
+/// ```
+pub fn init_with_config(db_path: &str, config: DictConfig) -> Result<DictHandle> {
+    db::open_readonly_with_config(db_path, config)
+}
+
 /// Search for words matching a query
 ///
 /// Performs a full-text search using FTS5 and returns matching results
@@ -115,7 +432,26 @@ pub fn init(db_path: &str) -> Result<DictHandle> {
 /// }
 /// ```
 pub fn search(handle: &DictHandle, query: &str, limit: u32) -> Vec<SearchResult> {
-    search::search_words(handle, query, limit).unwrap_or_default()
+    search_with_offset(handle, query, limit, 0)
+}
+
+/// Search for words, reporting why a search failed
+///
+/// Like `search`, but a failure is an error rather than no results: a
+/// damaged index is `Error::FtsCorrupt`, an interrupted search
+/// `Error::Interrupted` and an unusable query `Error::InvalidQuery`.
+pub fn try_search(handle: &DictHandle, query: &str, limit: u32) -> Result<Vec<SearchResult>> {
+    search::search_words(handle, query, limit)
+}
+
+/// `try_search` with offset-based pagination
+pub fn try_search_with_offset(
+    handle: &DictHandle,
+    query: &str,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<SearchResult>> {
+    search::search_words_offset(handle, query, limit, offset)
 }
 
 /// Search for words with offset-based pagination
@@ -139,7 +475,13 @@ pub fn search_with_offset(
     limit: u32,
     offset: u32,
 ) -> Vec<SearchResult> {
-    search::search_words_offset(handle, query, limit, offset).unwrap_or_default()
+    match search::search_words_offset(handle, query, limit, offset) {
+        Ok(results) => results,
+        Err(e) => {
+            log::error!("search: error searching for {:?}: {:?}", query, e);
+            Vec::new()
+        }
+    }
 }
 
 /// Get the full definition for a word by its ID
@@ -180,6 +522,29 @@ pub fn get_definition(handle: &DictHandle, word_id: i64) -> Option<FullDefinitio
     }
 }
 
+/// Get the full definition for a word, with options
+///
+/// Like [`get_definition`]; see [`DefinitionOptions`] for what can be
+/// included, such as the definitions of the word a "plural of cat" sense
+/// points at.
+pub fn get_definition_with_options(
+    handle: &DictHandle,
+    word_id: i64,
+    options: &DefinitionOptions,
+) -> Option<FullDefinition> {
+    match db::get_full_definition_with_options(handle, word_id, options) {
+        Ok(def) => def,
+        Err(e) => {
+            log::error!(
+                "get_definition_with_options: error fetching word_id={}: {:?}",
+                word_id,
+                e
+            );
+            None
+        }
+    }
+}
+
 /// Import JSONL data into the dictionary database
 ///
 /// Parses a JSONL file (one JSON object per line) and imports the entries
@@ -237,6 +602,19 @@ pub fn import_jsonl_with_stats(
     import::import_from_jsonl_with_stats(db_path, jsonl_path, progress)
 }
 
+/// Import JSONL data with custom import options and return statistics
+///
+/// Same as `import_jsonl_with_stats` but only writes the content enabled in
+/// `options`, e.g. `ImportOptions::lite()` for the small free-tier build.
+pub fn import_jsonl_with_options(
+    db_path: &str,
+    jsonl_path: &str,
+    options: &ImportOptions,
+    progress: impl Fn(u64, u64),
+) -> Result<ImportStats> {
+    import::import_from_jsonl_with_options(db_path, jsonl_path, options, progress)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -246,4 +624,74 @@ mod tests {
         let err = Error::NotInitialized;
         assert_eq!(err.to_string(), "Database not initialized");
     }
+
+    #[test]
+    fn test_init_with_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db_path = db_path.to_str().unwrap();
+        {
+            let handle = db::init_database(db_path).unwrap();
+            for (word, frequency) in [("help", 10), ("helper", 500), ("helm", 1)] {
+                let id = db::insert_word(&handle.conn, word, "noun", "English", "en", 0).unwrap();
+                db::insert_definition(&handle.conn, id, "A word.", &[], &[]).unwrap();
+                handle
+                    .conn
+                    .execute(
+                        "UPDATE words SET frequency = ? WHERE id = ?",
+                        [frequency, id],
+                    )
+                    .unwrap();
+            }
+        }
+        let user_db = dir.path().join("user.db");
+        rusqlite::Connection::open(&user_db)
+            .unwrap()
+            .execute_batch("CREATE TABLE bookmarks (word TEXT)")
+            .unwrap();
+
+        // Defaults: shorter prefix matches first, typos matched
+        let handle = init(db_path).unwrap();
+        let words: Vec<String> = search(&handle, "hel", 3)
+            .into_iter()
+            .map(|r| r.word)
+            .collect();
+        assert_eq!(words, ["helm", "help", "helper"]);
+        assert_eq!(search(&handle, "helpr", 3).len(), 3);
+
+        let config: DictConfig = serde_json::from_str(&format!(
+            r#"{{"ranking": "frequency", "fuzzy": {{"enabled": false}}, "page_cache_kib": 512, "user_db": {:?}}}"#,
+            user_db.to_str().unwrap()
+        ))
+        .unwrap();
+        assert_eq!(config.fuzzy.max_distance, 2);
+        let handle = init_with_config(db_path, config).unwrap();
+        // Prefix matches by frequency instead of length
+        let words: Vec<String> = search(&handle, "hel", 3)
+            .into_iter()
+            .map(|r| r.word)
+            .collect();
+        assert_eq!(words, ["helper", "help", "helm"]);
+        // The exact match still comes first
+        let words: Vec<String> = search(&handle, "help", 3)
+            .into_iter()
+            .map(|r| r.word)
+            .collect();
+        assert_eq!(words, ["help", "helper"]);
+        assert!(search(&handle, "helpr", 3).is_empty());
+        let bookmarks: i64 = handle
+            .conn
+            .query_row("SELECT COUNT(*) FROM user.bookmarks", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(bookmarks, 0);
+
+        let missing = DictConfig {
+            user_db: Some(dir.path().join("missing.db")),
+            ..DictConfig::default()
+        };
+        assert!(matches!(
+            init_with_config(db_path, missing),
+            Err(Error::InvalidPath(_))
+        ));
+    }
 }
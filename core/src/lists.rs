@@ -0,0 +1,173 @@
+//! Generated word lists for study features
+//!
+//! [`generate_by_topic`] picks words filed under a topic ("cooking",
+//! "nautical": tags on their senses) for "learn 20 cooking words" style
+//! lists, optionally only words of a frequency level. Entries are keyed
+//! by word id and headword like the user database's flashcards, so a list
+//! can be studied with [`crate::userdata::UserData::add_list`].
+
+use std::ops::RangeInclusive;
+
+use rusqlite::params_from_iter;
+use serde::{Deserialize, Serialize};
+
+use crate::{DictHandle, Result};
+
+/// Words per frequency level: level 1 is the most frequent thousand words,
+/// level 2 the next thousand, and so on
+pub const LEVEL_SIZE: u32 = 1000;
+
+/// SQL for the frequency level of a `words` row, NULL for words without a
+/// frequency count or when the database has no frequency column
+///
+/// A window function, so only usable in a select list.
+pub(crate) fn level_sql(has_frequency: bool) -> String {
+    if !has_frequency {
+        return "NULL".to_string();
+    }
+    // Rank among all words with a count, most frequent first
+    format!(
+        "CASE WHEN frequency IS NOT NULL
+              THEN (ROW_NUMBER() OVER (PARTITION BY frequency IS NULL
+                                       ORDER BY frequency DESC, id) - 1) / {LEVEL_SIZE} + 1
+         END"
+    )
+}
+
+/// A list of words to study
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WordList {
+    /// What the list is about (the topic it was generated from)
+    pub name: String,
+    pub entries: Vec<WordListEntry>,
+}
+
+/// A word of a [`WordList`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WordListEntry {
+    pub word_id: i64,
+    pub word: String,
+    /// Frequency level (see [`LEVEL_SIZE`]), None for words without a
+    /// frequency count
+    pub level: Option<u32>,
+}
+
+/// Up to `size` words picked at random from those with a sense tagged
+/// `topic`
+///
+/// With `levels`, only words whose frequency level (see [`LEVEL_SIZE`])
+/// is in the range are picked, e.g. `1..=3` for the 3000 most frequent
+/// words; dictionaries built without a frequency list have no levels, so
+/// nothing matches. Each headword appears once. Entries come in level
+/// order, then alphabetically.
+pub fn generate_by_topic(
+    handle: &DictHandle,
+    topic: &str,
+    size: u32,
+    levels: Option<RangeInclusive<u32>>,
+) -> Result<WordList> {
+    let mut list = WordList {
+        name: topic.to_string(),
+        entries: Vec::new(),
+    };
+    let has_frequency = crate::db::has_column(&handle.conn, "words", "frequency")?;
+    if size == 0 || (levels.is_some() && !has_frequency) {
+        return Ok(list);
+    }
+
+    let mut sql = format!(
+        "WITH ranked AS (SELECT id, word, {} AS level FROM words)
+         SELECT id, word, level FROM ranked w WHERE {}",
+        level_sql(has_frequency),
+        crate::db::filter_condition(1)
+    );
+    let mut values: Vec<u32> = Vec::new();
+    if let Some(levels) = &levels {
+        sql.push_str(" AND level BETWEEN ? AND ?");
+        values.extend([*levels.start(), *levels.end()]);
+    }
+    sql.push_str(" GROUP BY word ORDER BY random() LIMIT ?");
+    values.push(size);
+
+    let mut stmt = handle.conn.prepare(&sql)?;
+    let params = std::iter::once(&topic as &dyn rusqlite::ToSql)
+        .chain(values.iter().map(|v| v as &dyn rusqlite::ToSql));
+    let rows = stmt.query_map(params_from_iter(params), |row| {
+        Ok(WordListEntry {
+            word_id: row.get(0)?,
+            word: row.get(1)?,
+            level: row.get(2)?,
+        })
+    })?;
+    list.entries = rows.collect::<std::result::Result<_, _>>()?;
+    list.entries.sort_by(|a, b| {
+        (a.level.is_none(), a.level, &a.word).cmp(&(b.level.is_none(), b.level, &b.word))
+    });
+    Ok(list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_database, insert_definition, insert_word};
+
+    #[test]
+    fn test_generate_by_topic() {
+        let dir = tempfile::tempdir().unwrap();
+        let handle = init_database(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let cooking = ["cooking".to_string()];
+        for (word, frequency) in [
+            ("bake", Some(5000)),
+            ("simmer", Some(40)),
+            ("braise", None),
+            ("run", Some(9000)),
+        ] {
+            let id = insert_word(&handle.conn, word, "verb", "English", "en", 0).unwrap();
+            let tags: &[String] = if word == "run" { &[] } else { &cooking };
+            insert_definition(&handle.conn, id, "...", &[], tags).unwrap();
+            handle
+                .conn
+                .execute(
+                    "UPDATE words SET frequency = ? WHERE id = ?",
+                    rusqlite::params![frequency, id],
+                )
+                .unwrap();
+        }
+        // Level 2: a thousand more frequent words ahead of "simmer"
+        for i in 0..LEVEL_SIZE {
+            let id =
+                insert_word(&handle.conn, &format!("w{i}"), "noun", "English", "en", 0).unwrap();
+            handle
+                .conn
+                .execute("UPDATE words SET frequency = 100 WHERE id = ?", [id])
+                .unwrap();
+        }
+
+        let list = generate_by_topic(&handle, "cooking", 10, None).unwrap();
+        assert_eq!(list.name, "cooking");
+        let entries: Vec<(&str, Option<u32>)> = list
+            .entries
+            .iter()
+            .map(|entry| (entry.word.as_str(), entry.level))
+            .collect();
+        assert_eq!(
+            entries,
+            [("bake", Some(1)), ("simmer", Some(2)), ("braise", None)]
+        );
+
+        let easy = generate_by_topic(&handle, "cooking", 10, Some(1..=1)).unwrap();
+        assert_eq!(easy.entries.len(), 1);
+        assert_eq!(easy.entries[0].word, "bake");
+        assert_eq!(
+            generate_by_topic(&handle, "cooking", 2, None)
+                .unwrap()
+                .entries
+                .len(),
+            2
+        );
+        assert!(generate_by_topic(&handle, "nautical", 10, None)
+            .unwrap()
+            .entries
+            .is_empty());
+    }
+}
@@ -3,6 +3,8 @@
 //! This module defines the core data structures used throughout the application
 //! to represent dictionary entries, definitions, pronunciations, and search results.
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 /// A search result entry returned from search queries
@@ -19,9 +21,171 @@ pub struct SearchResult {
     pub pos: String,
     /// Preview text (first definition, truncated)
     pub preview: String,
+    /// Tags of the sense shown in the preview ("archaic", "slang", ...)
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Number of senses in the entry
+    #[serde(default)]
+    pub sense_count: u32,
+    /// Language name of the entry ("English")
+    #[serde(default)]
+    pub language: String,
     /// Relevance score (lower is better, 0 = exact match)
     #[serde(default)]
     pub score: f64,
+    /// How the entry matched the query
+    #[serde(default)]
+    pub match_kind: MatchKind,
+    /// `score` mapped to 0-1, higher is better (1 = exact match)
+    #[serde(default)]
+    pub relevance: f64,
+    /// Set when this entry is one part of a compound query that had no
+    /// entry of its own (e.g. "Donau" for "Donaudampfschiff")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compound_of: Option<String>,
+    /// Database the entry is in; `id` is only unique within it
+    #[serde(default)]
+    pub origin: EntryOrigin,
+    /// The entry matched the content filter (`ContentFilterMode::Flag`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub flagged: bool,
+    /// Raw FTS5 bm25 rank of a full-text match (more negative is better),
+    /// for tuning [`crate::FtsConfig::weights`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fts_rank: Option<f64>,
+    /// IPA of the entry in the most preferred accent, filled when
+    /// [`crate::DictConfig::preferred_accents`] is set, or the
+    /// transcription matched by `search::by_ipa`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ipa: Option<String>,
+    /// Other regional spellings of the headword ("colour" for "color"),
+    /// filled when [`crate::DictConfig::spelling`] is set
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub also_spelled: Vec<String>,
+}
+
+/// Which database a search result came from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryOrigin {
+    /// The shipped dictionary; look up with `get_definition`
+    #[default]
+    Dictionary,
+    /// The attached user database; look up with `db::get_user_definition`
+    User,
+}
+
+/// How a search result matched the query
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    /// The headword, an inflected form of it, or the symbol itself
+    Exact,
+    /// The headword starts with the query
+    Prefix,
+    /// Found by full-text search, or as a part of a compound query
+    #[default]
+    FullText,
+    /// The headword is a near miss of the query (a typo away)
+    Fuzzy,
+    /// Matched by sound: pinyin, kana/romaji reading, rhyme or a near
+    /// IPA transcription
+    Phonetic,
+    /// The headword matches a wildcard pattern
+    Pattern,
+}
+
+/// Time spent in one tier of a search (exact, prefix, fuzzy, ...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TierTiming {
+    pub tier: String,
+    /// Results the tier returned, before merging
+    pub results: usize,
+    pub micros: u64,
+}
+
+/// Per-query timing of a search, tier by tier
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchTimings {
+    pub total_micros: u64,
+    /// Tiers in the order they ran
+    pub tiers: Vec<TierTiming>,
+}
+
+/// Search results along with how long the search took
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracedSearch {
+    pub results: Vec<SearchResult>,
+    pub timings: SearchTimings,
+}
+
+/// Features of the SQLite library a handle runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// FTS5 full-text search is available. Without it (some system SQLite
+    /// builds on old Android devices), searches match substrings instead.
+    pub fts5: bool,
+}
+
+/// Per-search settings for `search::search_words_with_options`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    /// Preview settings, instead of the handle's
+    pub preview: Option<PreviewOptions>,
+    /// Time after which no further tiers are started, in milliseconds.
+    /// Exact matches are always looked up; slower tiers (full text,
+    /// fuzzy, compounds) are skipped once the budget is spent. A tier
+    /// already running is finished, so a search can overrun by one tier.
+    pub time_budget_ms: Option<u64>,
+}
+
+/// Search results, and whether tiers were skipped to stay in budget
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchPage {
+    pub results: Vec<SearchResult>,
+    /// The time budget ran out before every tier ran, so matches may be
+    /// missing; searching again without a budget gives the full list
+    pub truncated: bool,
+}
+
+/// A search result from one of several dictionaries searched together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FederatedResult {
+    /// Index of the dictionary the result came from, in the order given
+    pub source: usize,
+    /// Every dictionary with this headword, part of speech and language,
+    /// `source` first; only grouped results (`FederatedMode::Grouped`)
+    /// list more than one
+    #[serde(default)]
+    pub sources: Vec<usize>,
+    /// Language code of the entry
+    pub lang_code: String,
+    /// The result; its score includes the language boost
+    #[serde(flatten)]
+    pub result: SearchResult,
+}
+
+/// How `search::search_federated_with_mode` treats an entry found in
+/// several dictionaries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FederatedMode {
+    /// One result per dictionary
+    #[default]
+    Separate,
+    /// One result per headword, part of speech and language, from the
+    /// dictionary where it ranks best, listing all in `sources`
+    Grouped,
+}
+
+/// A candidate language for a query, from `search::detect_language`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageGuess {
+    /// Language code, as in `words.lang_code`
+    pub code: String,
+    /// Estimated probability (all guesses for a query sum to 1)
+    pub probability: f64,
 }
 
 /// A word entry from the database
@@ -63,6 +227,80 @@ pub struct FullDefinition {
     pub etymology: Option<String>,
     /// Translations to other languages
     pub translations: Vec<Translation>,
+    /// Where the entry comes from and its license, for showing credit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attribution: Option<Attribution>,
+    /// Other regional spellings of the headword, filled when
+    /// [`crate::DictConfig::spelling`] is set
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub also_spelled: Vec<String>,
+}
+
+/// The entries of a headword that share an origin, see
+/// [`crate::db::get_definitions_for_word`]
+///
+/// Wiktionary numbers the unrelated origins of a spelling ("bank" the
+/// shore and "bank" the institution) as Etymology 1, 2 and so on, with
+/// one entry per part of speech under each.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EtymologyGroup {
+    pub language: String,
+    /// Etymology number, 0 for a word with only one
+    pub etymology_num: i32,
+    /// Etymology text of the group, None if unknown
+    pub etymology: Option<String>,
+    pub entries: Vec<EtymologyEntry>,
+}
+
+/// An entry of an [`EtymologyGroup`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EtymologyEntry {
+    pub word_id: i64,
+    #[serde(flatten)]
+    pub definition: FullDefinition,
+}
+
+/// Source and license of an entry
+///
+/// Wiktionary content is CC BY-SA: apps redistributing it must credit the
+/// source and name the license wherever an entry is shown.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attribution {
+    /// Name of the source ("Wiktionary")
+    pub source: String,
+    /// License the entry is distributed under ("CC BY-SA 4.0")
+    pub license: String,
+    /// Where the license text can be read
+    pub license_url: Option<String>,
+    /// The entry's page at the source
+    pub url: Option<String>,
+    /// Revision of the source page the entry was extracted from, if known
+    pub revision: Option<i64>,
+}
+
+/// A source of imported entries, recorded in the `sources` table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceInfo {
+    /// Name shown in attributions
+    pub name: String,
+    /// License name
+    pub license: String,
+    /// Where the license text can be read
+    pub license_url: Option<String>,
+    /// URL of an entry's page, with `{word}` in place of the headword
+    pub page_url: Option<String>,
+}
+
+impl Default for SourceInfo {
+    /// English Wiktionary, as extracted by kaikki.org
+    fn default() -> Self {
+        Self {
+            name: "Wiktionary".to_string(),
+            license: "CC BY-SA 4.0".to_string(),
+            license_url: Some("https://creativecommons.org/licenses/by-sa/4.0/".to_string()),
+            page_url: Some("https://en.wiktionary.org/wiki/{word}".to_string()),
+        }
+    }
 }
 
 /// A single definition/meaning of a word
@@ -76,6 +314,271 @@ pub struct Definition {
     pub examples: Vec<String>,
     /// Tags/labels (formal, slang, archaic, etc.)
     pub tags: Vec<String>,
+    /// The word this sense is an inflection or variant of ("plural of cat")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub form_of: Option<FormOf>,
+    /// Other headwords mentioned in `text`, when requested (see
+    /// `DefinitionOptions`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<Link>,
+    /// Dictionary the sense comes from, when it is not the entry's own
+    /// (senses merged in by `import::merge_databases`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Id of the sense that stays the same across rebuilds, for deep links
+    /// (see `db::find_sense`): kaikki's sense id, or a hash of the entry
+    /// and gloss where the dump has none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sense_id: Option<String>,
+}
+
+/// A slice of an entry's senses (see `db::get_definition_page`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefinitionPage {
+    pub definitions: Vec<Definition>,
+    /// Index of the first sense in `definitions`
+    pub offset: u32,
+    /// Number of senses in the whole entry
+    pub total: u32,
+}
+
+impl DefinitionPage {
+    /// Whether senses after this page remain
+    pub fn has_more(&self) -> bool {
+        self.offset as usize + self.definitions.len() < self.total as usize
+    }
+}
+
+/// An occurrence of text found in an entry by `db::find_in_entry`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryMatch {
+    /// Index of the sense, as numbered by `db::get_definition_page`
+    pub sense_index: u32,
+    /// Id of the sense's definition
+    pub definition_id: i64,
+    /// Index of the example the text is in, or None for the gloss
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub example: Option<u32>,
+    /// Byte offset of the match in the gloss or example
+    pub start: usize,
+    /// Byte offset just past the match
+    pub end: usize,
+}
+
+/// A headword mentioned in a definition, for a tappable link
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Link {
+    /// Byte offset of the mention in the definition text
+    pub start: usize,
+    /// Byte offset just past the mention
+    pub end: usize,
+    /// The mention as written
+    pub text: String,
+    /// Entry it links to (the first, if the headword has several)
+    pub word_id: i64,
+}
+
+/// Link from a form-of sense to the word it points at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormOf {
+    /// The target headword
+    pub word: String,
+    /// Its word entry, if the dictionary has one
+    pub word_id: Option<i64>,
+    /// The target's definitions, when inlined (see `DefinitionOptions`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub definitions: Vec<Definition>,
+}
+
+/// Options for fetching a full definition
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct DefinitionOptions {
+    /// Include the target's definitions in form-of senses
+    pub inline_form_of: bool,
+    /// Find other headwords mentioned in each definition (`Definition::links`)
+    pub links: bool,
+    /// Parts of the entry to load; the rest are left empty
+    pub parts: DefinitionParts,
+    /// Respell pronunciations (`Pronunciation::respelling`) in languages
+    /// with a respelling scheme (see `ipa::RespellingScheme`)
+    pub respelling: bool,
+}
+
+/// Set of the parts of a `FullDefinition`, combined with `|`
+///
+/// Loading only what a screen shows keeps lookups fast, e.g.
+/// `DefinitionParts::DEFINITIONS` when prefetching a result list. The
+/// headword, part of speech, language and attribution are always loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DefinitionParts(u8);
+
+impl DefinitionParts {
+    pub const NONE: Self = Self(0);
+    pub const DEFINITIONS: Self = Self(1);
+    pub const PRONUNCIATIONS: Self = Self(1 << 1);
+    pub const ETYMOLOGY: Self = Self(1 << 2);
+    pub const TRANSLATIONS: Self = Self(1 << 3);
+    /// Form-of targets and links of senses (see `DefinitionOptions`)
+    pub const RELATIONS: Self = Self(1 << 4);
+    pub const ALL: Self = Self(0b1_1111);
+
+    /// Parts from their bits, ignoring unknown ones (for FFI callers)
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits & Self::ALL.0)
+    }
+
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Whether every part of `other` is in `self`
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl Default for DefinitionParts {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl std::ops::BitOr for DefinitionParts {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// How search result previews are built from definitions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewOptions {
+    /// Longest preview in bytes; longer ones are cut at a word boundary
+    pub max_len: usize,
+    /// Use the first sense that is not dated, if there is one (see
+    /// `DictConfig::dated_senses`; obsolete or archaic by default)
+    pub prefer_current: bool,
+    /// Start the preview with the part of speech ("noun: ...")
+    pub include_pos: bool,
+    /// Drop parenthesized asides such as "(transitive)" or "(chiefly US)"
+    pub strip_parentheticals: bool,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self {
+            max_len: 100,
+            prefer_current: false,
+            include_pos: false,
+            strip_parentheticals: false,
+        }
+    }
+}
+
+/// A word seen near another in example sentences, from
+/// `examples::collocations`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collocate {
+    /// The neighboring word, lowercased
+    pub word: String,
+    /// Its position relative to the word (-1 = just before, 1 = just after)
+    pub offset: i32,
+    /// Number of times it was seen there
+    pub count: u64,
+}
+
+/// A sense ranked by how well it fits a context sentence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SenseMatch {
+    /// Word entry the sense belongs to
+    pub word_id: i64,
+    /// The headword
+    pub word: String,
+    /// Part of speech of the entry
+    pub pos: String,
+    /// The sense itself
+    pub definition: Definition,
+    /// Overlap with the context (higher is better, 0 = no shared words)
+    pub score: f64,
+}
+
+/// A span of text linked to dictionary entries, from `text::annotate`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Annotation {
+    /// Byte offset of the span in the text
+    pub start: usize,
+    /// Byte offset just past the span
+    pub end: usize,
+    /// The span as written
+    pub text: String,
+    /// Headword the span was resolved to (differs from `text` for
+    /// inflected forms and case variants)
+    pub headword: String,
+    /// Matching word entries, in dictionary order
+    pub word_ids: Vec<i64>,
+    /// Whether the span covers several words ("ice cream")
+    pub phrase: bool,
+}
+
+/// Synonyms and antonyms for one sense of a word, from `thesaurus::lookup`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThesaurusSense {
+    /// Word entry the sense belongs to
+    pub word_id: i64,
+    /// Part of speech of the entry
+    pub pos: String,
+    /// The sense: its definition, or the label Wiktionary gives the group
+    /// (`None` for relations that apply to the whole entry)
+    pub sense: Option<String>,
+    /// Words with the same meaning
+    pub synonyms: Vec<String>,
+    /// Words with the opposite meaning
+    pub antonyms: Vec<String>,
+}
+
+/// How two entries of a [`WordGraph`] are related
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationKind {
+    Synonym,
+    Antonym,
+    /// From an inflected or alternative form to its headword ("cats" to
+    /// "cat")
+    FormOf,
+}
+
+impl RelationKind {
+    pub const ALL: &'static [RelationKind] = &[
+        RelationKind::Synonym,
+        RelationKind::Antonym,
+        RelationKind::FormOf,
+    ];
+}
+
+/// An entry in a [`WordGraph`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub id: i64,
+    pub word: String,
+    /// Steps from the entry the graph was built around (0 for itself)
+    pub depth: u32,
+}
+
+/// A relation between two entries of a [`WordGraph`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub from: i64,
+    pub to: i64,
+    pub kind: RelationKind,
+}
+
+/// Entries related to one entry, from `db::get_graph_neighborhood`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WordGraph {
+    /// The entry itself first, then the others by depth
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
 }
 
 /// Pronunciation information for a word
@@ -89,6 +592,20 @@ pub struct Pronunciation {
     pub audio_url: Option<String>,
     /// Regional accent (US, UK, AU, etc.)
     pub accent: Option<String>,
+    /// The IPA respelled for readers who don't know it ("hə-LOH"), when
+    /// asked for with `DefinitionOptions::respelling`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub respelling: Option<String>,
+}
+
+/// Pronunciations of one regional accent, see
+/// [`FullDefinition::pronunciations_by_accent`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccentGroup {
+    /// Region ("US", "UK", "AU", ...), the accent tag as given for accents
+    /// without a known region, or None for untagged pronunciations
+    pub accent: Option<String>,
+    pub pronunciations: Vec<Pronunciation>,
 }
 
 /// A translation of a word to another language
@@ -102,6 +619,166 @@ pub struct Translation {
     pub translation: String,
 }
 
+/// Whether this library can read a dictionary database, from
+/// `db::check_compatibility`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompatibilityReport {
+    /// Schema version of the database, 0 for databases built before
+    /// schemas were versioned (which this library still reads)
+    pub schema_version: u32,
+    /// Newest schema version this library reads
+    pub supported_schema_version: u32,
+    /// What built the database ("dict-core 0.1.0"), if recorded
+    pub generator: Option<String>,
+    /// Tokenizer of the full-text index ("unicode61 remove_diacritics 2")
+    pub tokenizer: Option<String>,
+    /// SQLite features the database uses ("fts5", "json1", tokenizers)
+    pub required_features: Vec<String>,
+    /// Required features this SQLite library lacks. Without "fts5" the
+    /// database still opens and search falls back to substring matching.
+    pub missing_features: Vec<String>,
+    /// Version of the SQLite library in use
+    pub sqlite_version: String,
+    /// Reasons the database cannot be used, empty if it can
+    pub problems: Vec<String>,
+}
+
+impl CompatibilityReport {
+    /// Whether the database can be opened and searched
+    pub fn is_compatible(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Summary statistics for an existing dictionary database
+///
+/// Used by tooling to describe a built database, e.g. when triaging bug
+/// reports against a specific build.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DatabaseStats {
+    /// Build metadata from the `meta` table (empty for older databases)
+    pub metadata: BTreeMap<String, String>,
+    /// Number of word entries
+    pub words: i64,
+    /// Number of definitions
+    pub definitions: i64,
+    /// Number of pronunciations
+    pub pronunciations: i64,
+    /// Number of etymologies
+    pub etymologies: i64,
+    /// Number of translations
+    pub translations: i64,
+    /// Word counts per language, largest first
+    pub languages: Vec<CountEntry>,
+    /// Word counts per part of speech, largest first
+    pub parts_of_speech: Vec<CountEntry>,
+    /// Most frequent definition tags, largest first
+    pub top_tags: Vec<CountEntry>,
+    /// On-disk size per table/index, largest first
+    pub tables: Vec<TableSize>,
+    /// Total database size in bytes (page_size * page_count)
+    pub total_bytes: i64,
+}
+
+/// A labelled count, e.g. the number of words for a language
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CountEntry {
+    /// The label being counted
+    pub name: String,
+    /// Number of occurrences
+    pub count: i64,
+}
+
+/// Storage used by a single table or index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableSize {
+    /// Table or index name
+    pub name: String,
+    /// Size in bytes
+    pub bytes: i64,
+}
+
+/// Filters for browsing the words of a language with
+/// [`crate::db::get_words_by_language_with_options`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BrowseOptions {
+    /// Only this part of speech ("noun")
+    pub pos: Option<String>,
+    /// Only words with a sense carrying this tag ("slang")
+    pub tag: Option<String>,
+    /// Only words starting with this text, ignoring ASCII case
+    pub starts_with: Option<String>,
+}
+
+/// Filters and page for browsing set phrases with [`crate::db::get_phrases`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PhraseFilters {
+    /// Parts of speech that count as phrases
+    pub kinds: Vec<String>,
+    /// Only phrases with a sense carrying this topic tag ("nautical")
+    pub topic: Option<String>,
+    /// Only phrases starting with this text, ignoring ASCII case
+    pub starts_with: Option<String>,
+    pub limit: u32,
+    pub offset: u32,
+}
+
+impl Default for PhraseFilters {
+    fn default() -> Self {
+        Self {
+            kinds: ["phrase", "proverb", "idiom", "prep_phrase"]
+                .map(String::from)
+                .to_vec(),
+            topic: None,
+            starts_with: None,
+            limit: 50,
+            offset: 0,
+        }
+    }
+}
+
+/// Number of words filed under one letter of an A–Z index, see
+/// [`crate::db::get_letter_index`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LetterCount {
+    /// The letter, lowercase and without diacritics ("e" covers "é")
+    pub letter: String,
+    pub count: u64,
+}
+
+/// Content to strip from an installed database with [`crate::db::compact`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompactOptions {
+    /// Keep translations into these target languages only (as stored:
+    /// language codes, or names where Wiktionary gave no code); `None`
+    /// keeps all translations and an empty list removes them all
+    pub translation_languages: Option<Vec<String>>,
+    pub remove_etymologies: bool,
+    /// Clear pronunciation audio URLs, keeping the IPA
+    pub remove_audio_urls: bool,
+    pub remove_examples: bool,
+    /// Remove synonyms and antonyms (the thesaurus)
+    pub remove_relations: bool,
+}
+
+/// What [`crate::db::compact`] removed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompactStats {
+    pub translations_removed: u64,
+    pub etymologies_removed: u64,
+    /// Pronunciations whose audio URL was cleared
+    pub audio_urls_removed: u64,
+    /// Definitions whose examples were cleared
+    pub examples_removed: u64,
+    pub relations_removed: u64,
+    /// Database size before and after, in bytes (page_size * page_count)
+    pub bytes_before: i64,
+    pub bytes_after: i64,
+}
+
 /// Raw word entry from JSONL import
 ///
 /// This structure matches the format of entries in the Wiktionary JSONL export.
@@ -133,6 +810,18 @@ pub struct RawWordEntry {
     /// Translations
     #[serde(default)]
     pub translations: Vec<RawTranslation>,
+    /// Inflected and alternative forms (also carries Japanese kana readings)
+    #[serde(default)]
+    pub forms: Vec<RawForm>,
+    /// Synonyms not attached to a particular sense
+    #[serde(default)]
+    pub synonyms: Vec<RawRelation>,
+    /// Antonyms not attached to a particular sense
+    #[serde(default)]
+    pub antonyms: Vec<RawRelation>,
+    /// Revision id of the source page the entry was extracted from
+    #[serde(default)]
+    pub revision: Option<i64>,
 }
 
 fn default_language() -> String {
@@ -142,6 +831,9 @@ fn default_language() -> String {
 /// A raw sense/definition from JSONL
 #[derive(Debug, Clone, Deserialize)]
 pub struct RawSense {
+    /// Stable id kaikki assigns the sense, in newer dumps
+    #[serde(default)]
+    pub id: Option<String>,
     /// The definition text (may contain wiki markup)
     #[serde(default)]
     pub glosses: Vec<String>,
@@ -154,6 +846,30 @@ pub struct RawSense {
     /// Tags/labels
     #[serde(default)]
     pub tags: Vec<String>,
+    /// Word this sense is an inflection of ("plural of cat")
+    #[serde(default)]
+    pub form_of: Vec<RawRelation>,
+    /// Word this sense is a variant spelling of
+    #[serde(default)]
+    pub alt_of: Vec<RawRelation>,
+    /// Synonyms of this sense
+    #[serde(default)]
+    pub synonyms: Vec<RawRelation>,
+    /// Antonyms of this sense
+    #[serde(default)]
+    pub antonyms: Vec<RawRelation>,
+}
+
+/// Raw link to another word from JSONL (synonym, antonym, form-of target)
+#[derive(Debug, Clone, Deserialize)]
+pub struct RawRelation {
+    /// The related word
+    #[serde(default)]
+    pub word: String,
+    /// Which meaning the relation holds for, when given at entry level
+    /// (e.g. "financial institution")
+    #[serde(default)]
+    pub sense: Option<String>,
 }
 
 /// A raw example from JSONL
@@ -164,6 +880,17 @@ pub struct RawExample {
     pub text: String,
 }
 
+/// Raw alternative form from JSONL
+#[derive(Debug, Clone, Deserialize)]
+pub struct RawForm {
+    /// The form itself
+    #[serde(default)]
+    pub form: String,
+    /// Tags such as "plural" or "hiragana"
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
 /// Raw pronunciation/sound from JSONL
 #[derive(Debug, Clone, Deserialize)]
 pub struct RawSound {
@@ -179,6 +906,9 @@ pub struct RawSound {
     /// MP3 audio URL
     #[serde(default)]
     pub mp3_url: Option<String>,
+    /// Chinese romanization (Pinyin, Jyutping, ...), named by `tags`
+    #[serde(default)]
+    pub zh_pron: Option<String>,
     /// Regional tags
     #[serde(default)]
     pub tags: Vec<String>,
@@ -206,7 +936,18 @@ impl SearchResult {
             word,
             pos,
             preview,
+            tags: Vec::new(),
+            sense_count: 0,
+            language: String::new(),
             score: 0.0,
+            match_kind: MatchKind::default(),
+            relevance: 0.0,
+            compound_of: None,
+            origin: EntryOrigin::Dictionary,
+            flagged: false,
+            fts_rank: None,
+            ipa: None,
+            also_spelled: Vec::new(),
         }
     }
 
@@ -217,7 +958,18 @@ impl SearchResult {
             word,
             pos,
             preview,
+            tags: Vec::new(),
+            sense_count: 0,
+            language: String::new(),
             score,
+            match_kind: MatchKind::default(),
+            relevance: 0.0,
+            compound_of: None,
+            origin: EntryOrigin::Dictionary,
+            flagged: false,
+            fts_rank: None,
+            ipa: None,
+            also_spelled: Vec::new(),
         }
     }
 }
@@ -234,7 +986,49 @@ impl FullDefinition {
             pronunciations: Vec::new(),
             etymology: None,
             translations: Vec::new(),
+            attribution: None,
+            also_spelled: Vec::new(),
+        }
+    }
+
+    /// Pronunciations grouped by regional accent, in order of first
+    /// appearance
+    ///
+    /// Accent tags of the same region are one group ("UK" and
+    /// "Received-Pronunciation" are both "UK"). Near-identical
+    /// transcriptions within a group are dropped unless they have audio,
+    /// which tidies databases built before import deduplicated them.
+    pub fn pronunciations_by_accent(&self) -> Vec<AccentGroup> {
+        let mut groups: Vec<AccentGroup> = Vec::new();
+        for p in &self.pronunciations {
+            let accent = p
+                .accent
+                .as_deref()
+                .map(|a| crate::ipa::accent_region(a).unwrap_or(a).to_string());
+            let index = match groups.iter().position(|g| g.accent == accent) {
+                Some(index) => index,
+                None => {
+                    groups.push(AccentGroup {
+                        accent,
+                        pronunciations: Vec::new(),
+                    });
+                    groups.len() - 1
+                }
+            };
+            let group = &mut groups[index].pronunciations;
+            let duplicate = p.audio_url.is_none()
+                && p.ipa.as_deref().is_some_and(|ipa| {
+                    group.iter().any(|k| {
+                        k.ipa
+                            .as_deref()
+                            .is_some_and(|k| crate::ipa::is_near_duplicate(k, ipa))
+                    })
+                });
+            if !duplicate {
+                group.push(p.clone());
+            }
         }
+        groups
     }
 }
 
@@ -254,6 +1048,37 @@ mod tests {
         assert_eq!(result.word, "hello");
     }
 
+    #[test]
+    fn test_pronunciations_by_accent() {
+        let mut def = FullDefinition::new(
+            "tomato".to_string(),
+            "noun".to_string(),
+            "English".to_string(),
+            "en".to_string(),
+        );
+        let pronunciation = |ipa: &str, accent: Option<&str>, audio: Option<&str>| Pronunciation {
+            id: 0,
+            ipa: Some(ipa.to_string()),
+            audio_url: audio.map(String::from),
+            accent: accent.map(String::from),
+            respelling: None,
+        };
+        def.pronunciations = vec![
+            pronunciation("/təˈmɑːtəʊ/", Some("Received-Pronunciation"), None),
+            pronunciation("/təˈmeɪtoʊ/", Some("General-American"), None),
+            pronunciation("[təˈmɑːtəʊ]", Some("UK"), None),
+            pronunciation("/təˈmeɪtoʊ/", Some("US"), Some("tomato.ogg")),
+            pronunciation("/təˈmɑːtəʊ/", None, None),
+        ];
+
+        let groups = def.pronunciations_by_accent();
+        let summary: Vec<(Option<&str>, usize)> = groups
+            .iter()
+            .map(|g| (g.accent.as_deref(), g.pronunciations.len()))
+            .collect();
+        assert_eq!(summary, [(Some("UK"), 1), (Some("US"), 2), (None, 1)]);
+    }
+
     #[test]
     fn test_raw_word_entry_deserialize() {
         let json = r#"{
@@ -0,0 +1,359 @@
+//! Lightweight lemmatization
+//!
+//! Maps an inflected form to its dictionary headwords ("went" to "go",
+//! "cats" to "cat") using the imported forms table first and simple
+//! per-language suffix rules second. Rule candidates are only returned when
+//! they exist as headwords, so overeager rules cost a lookup, not a wrong
+//! answer. Irregular English forms ("mice", "went") are listed, so they
+//! resolve even in builds without forms. `DictConfig::lemma_rules` turns
+//! the rules and lists off, leaving the forms table.
+
+use rusqlite::params;
+
+use crate::{DictHandle, Result};
+
+/// Suffix rules as (suffix, replacement), per language code
+const SUFFIX_RULES: &[(&str, &[(&str, &str)])] = &[
+    (
+        "en",
+        &[
+            ("ies", "y"),
+            ("ves", "f"),
+            ("ves", "fe"),
+            ("es", ""),
+            ("s", ""),
+            ("ied", "y"),
+            ("ed", "e"),
+            ("ed", ""),
+            ("ing", "e"),
+            ("ing", ""),
+            ("ier", "y"),
+            ("iest", "y"),
+            ("er", ""),
+            ("est", ""),
+        ],
+    ),
+    (
+        "de",
+        &[
+            ("en", ""),
+            ("ern", ""),
+            ("er", ""),
+            ("es", ""),
+            ("e", ""),
+            ("n", ""),
+            ("s", ""),
+            ("st", "en"),
+            ("t", "en"),
+            ("te", "en"),
+            ("ten", "en"),
+        ],
+    ),
+    (
+        "fr",
+        &[
+            ("aux", "al"),
+            ("x", ""),
+            ("s", ""),
+            ("es", ""),
+            ("e", ""),
+            ("ons", "er"),
+            ("ez", "er"),
+            ("ent", "er"),
+            ("ait", "er"),
+            ("ais", "er"),
+            ("é", "er"),
+            ("ée", "er"),
+        ],
+    ),
+    (
+        "es",
+        &[
+            ("es", ""),
+            ("s", ""),
+            ("ando", "ar"),
+            ("iendo", "er"),
+            ("iendo", "ir"),
+            ("ado", "ar"),
+            ("ada", "ar"),
+            ("ido", "er"),
+            ("ido", "ir"),
+        ],
+    ),
+];
+
+/// Irregular forms as (form, lemma), per language code
+const IRREGULAR_FORMS: &[(&str, &[(&str, &str)])] = &[(
+    "en",
+    &[
+        ("analyses", "analysis"),
+        ("ate", "eat"),
+        ("bacteria", "bacterium"),
+        ("been", "be"),
+        ("began", "begin"),
+        ("begun", "begin"),
+        ("best", "good"),
+        ("better", "good"),
+        ("bought", "buy"),
+        ("brought", "bring"),
+        ("cacti", "cactus"),
+        ("came", "come"),
+        ("caught", "catch"),
+        ("children", "child"),
+        ("crises", "crisis"),
+        ("criteria", "criterion"),
+        ("did", "do"),
+        ("done", "do"),
+        ("eaten", "eat"),
+        ("feet", "foot"),
+        ("fungi", "fungus"),
+        ("gave", "give"),
+        ("geese", "goose"),
+        ("given", "give"),
+        ("gone", "go"),
+        ("got", "get"),
+        ("had", "have"),
+        ("has", "have"),
+        ("is", "be"),
+        ("knew", "know"),
+        ("known", "know"),
+        ("lice", "louse"),
+        ("made", "make"),
+        ("men", "man"),
+        ("mice", "mouse"),
+        ("oxen", "ox"),
+        ("people", "person"),
+        ("phenomena", "phenomenon"),
+        ("ran", "run"),
+        ("saw", "see"),
+        ("seen", "see"),
+        ("spoke", "speak"),
+        ("spoken", "speak"),
+        ("taken", "take"),
+        ("taught", "teach"),
+        ("teeth", "tooth"),
+        ("theses", "thesis"),
+        ("thought", "think"),
+        ("took", "take"),
+        ("was", "be"),
+        ("went", "go"),
+        ("were", "be"),
+        ("women", "woman"),
+        ("worse", "bad"),
+        ("worst", "bad"),
+        ("written", "write"),
+        ("wrote", "write"),
+    ],
+)];
+
+/// Minimum length of a lemma produced by a suffix rule
+const MIN_STEM_LENGTH: usize = 2;
+
+/// Find the headwords `word` is an inflected form of, in language `lang`
+///
+/// `lang` is a language code such as "en". Lemmas recorded in the forms
+/// table come first, then suffix-rule candidates that exist as headwords.
+/// The word itself is never returned, so a word that is already a lemma
+/// and nothing else gives an empty list.
+pub fn lemmatize(handle: &DictHandle, word: &str, lang: &str) -> Result<Vec<String>> {
+    let mut lemmas = Vec::new();
+
+    if crate::db::table_exists(&handle.conn, "forms")? {
+        let mut stmt = handle.conn.prepare_cached(
+            r#"
+            SELECT DISTINCT w.word
+            FROM forms f
+            JOIN words w ON w.id = f.word_id
+            WHERE f.form = ? AND w.lang_code = ? AND w.word != ?1
+            ORDER BY w.id
+            "#,
+        )?;
+        let rows = stmt.query_map(params![word, lang], |row| row.get::<_, String>(0))?;
+        for lemma in rows {
+            lemmas.push(lemma?);
+        }
+    }
+
+    if !handle.config.lemma_rules {
+        return Ok(lemmas);
+    }
+    let mut exists = handle
+        .conn
+        .prepare_cached("SELECT 1 FROM words WHERE word = ? AND lang_code = ? LIMIT 1")?;
+    for candidate in rule_candidates(word, lang) {
+        if candidate != word
+            && !lemmas.contains(&candidate)
+            && exists.exists(params![candidate, lang])?
+        {
+            lemmas.push(candidate);
+        }
+    }
+
+    Ok(lemmas)
+}
+
+/// Lemmas of `word` in any language, as (lemma, language code) pairs
+///
+/// Used by search, where the query's language is unknown.
+pub(crate) fn lemmatize_any(handle: &DictHandle, word: &str) -> Result<Vec<(String, String)>> {
+    let mut lemmas: Vec<(String, String)> = Vec::new();
+
+    if crate::db::table_exists(&handle.conn, "forms")? {
+        let mut stmt = handle.conn.prepare_cached(
+            r#"
+            SELECT DISTINCT w.word, w.lang_code
+            FROM forms f
+            JOIN words w ON w.id = f.word_id
+            WHERE f.form = ? AND w.word != ?1
+            ORDER BY w.id
+            "#,
+        )?;
+        let rows = stmt.query_map([word], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        for lemma in rows {
+            lemmas.push(lemma?);
+        }
+    }
+
+    if !handle.config.lemma_rules {
+        return Ok(lemmas);
+    }
+    let mut exists = handle
+        .conn
+        .prepare_cached("SELECT 1 FROM words WHERE word = ? AND lang_code = ? LIMIT 1")?;
+    for (lang, _) in SUFFIX_RULES {
+        for candidate in rule_candidates(word, lang) {
+            let pair = (candidate, lang.to_string());
+            if pair.0 != word && !lemmas.contains(&pair) && exists.exists(params![pair.0, lang])? {
+                lemmas.push(pair);
+            }
+        }
+    }
+
+    Ok(lemmas)
+}
+
+/// Possible lemmas of `word` under the irregular forms and suffix rules
+/// of `lang`
+fn rule_candidates(word: &str, lang: &str) -> Vec<String> {
+    let Some((_, rules)) = SUFFIX_RULES.iter().find(|(l, _)| *l == lang) else {
+        return Vec::new();
+    };
+
+    let mut candidates: Vec<String> = IRREGULAR_FORMS
+        .iter()
+        .filter(|(l, _)| *l == lang)
+        .flat_map(|(_, forms)| forms.iter())
+        .filter(|(form, _)| *form == word)
+        .map(|(_, lemma)| lemma.to_string())
+        .collect();
+    for (suffix, replacement) in *rules {
+        let Some(stem) = word.strip_suffix(suffix) else {
+            continue;
+        };
+        if stem.chars().count() < MIN_STEM_LENGTH {
+            continue;
+        }
+        candidates.push(format!("{}{}", stem, replacement));
+
+        // English doubles a final consonant before -ed/-ing/-er/-est
+        // (running, stopped, bigger)
+        if lang == "en" && replacement.is_empty() && matches!(*suffix, "ed" | "ing" | "er" | "est")
+        {
+            let mut chars = stem.chars().rev();
+            if let (Some(last), Some(prev)) = (chars.next(), chars.next()) {
+                if last == prev && !"aeiou".contains(last) {
+                    candidates.push(stem[..stem.len() - last.len_utf8()].to_string());
+                }
+            }
+        }
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_database, insert_form, insert_word};
+
+    #[test]
+    fn test_rule_candidates() {
+        assert!(rule_candidates("cities", "en").contains(&"city".to_string()));
+        assert!(rule_candidates("running", "en").contains(&"run".to_string()));
+        assert!(rule_candidates("baked", "en").contains(&"bake".to_string()));
+        assert!(rule_candidates("chevaux", "fr").contains(&"cheval".to_string()));
+        assert!(rule_candidates("hablando", "es").contains(&"hablar".to_string()));
+        assert!(rule_candidates("cats", "xx").is_empty());
+    }
+
+    #[test]
+    fn test_irregular_forms() {
+        for (form, lemma) in [
+            ("mice", "mouse"),
+            ("children", "child"),
+            ("feet", "foot"),
+            ("went", "go"),
+            ("better", "good"),
+            ("criteria", "criterion"),
+        ] {
+            assert_eq!(rule_candidates(form, "en")[0], lemma, "{form}");
+        }
+        // Regular rules still apply after the irregular lemma
+        assert!(rule_candidates("better", "en").contains(&"bett".to_string()));
+        assert!(!rule_candidates("mice", "fr").contains(&"mouse".to_string()));
+        // Kept sorted so additions are easy to check for duplicates
+        let forms = IRREGULAR_FORMS[0].1;
+        assert!(forms.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn test_lemmatize() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let handle = init_database(db_path.to_str().unwrap()).unwrap();
+
+        let go = insert_word(&handle.conn, "go", "verb", "English", "en", 0).unwrap();
+        insert_form(&handle.conn, go, "went", &["past".to_string()]).unwrap();
+        insert_word(&handle.conn, "cat", "noun", "English", "en", 0).unwrap();
+        insert_word(&handle.conn, "run", "verb", "English", "en", 0).unwrap();
+        insert_word(&handle.conn, "chat", "noun", "French", "fr", 0).unwrap();
+
+        assert_eq!(lemmatize(&handle, "went", "en").unwrap(), ["go"]);
+        assert_eq!(lemmatize(&handle, "cats", "en").unwrap(), ["cat"]);
+        assert_eq!(lemmatize(&handle, "running", "en").unwrap(), ["run"]);
+        assert_eq!(lemmatize(&handle, "chats", "fr").unwrap(), ["chat"]);
+        // Rules never produce words missing from the dictionary
+        assert!(lemmatize(&handle, "dogs", "en").unwrap().is_empty());
+        // Lemmas are language-specific
+        assert!(lemmatize(&handle, "went", "fr").unwrap().is_empty());
+        assert!(lemmatize(&handle, "cat", "en").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_lemma_rules_flag() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let mut handle = init_database(db_path.to_str().unwrap()).unwrap();
+
+        insert_word(&handle.conn, "dictionary", "noun", "English", "en", 0).unwrap();
+        insert_word(&handle.conn, "mouse", "noun", "English", "en", 0).unwrap();
+        let child = insert_word(&handle.conn, "child", "noun", "English", "en", 0).unwrap();
+        insert_form(&handle.conn, child, "children", &["plural".to_string()]).unwrap();
+
+        let lemmas = |handle: &DictHandle, word: &str| -> Vec<String> {
+            lemmatize_any(handle, word)
+                .unwrap()
+                .into_iter()
+                .map(|(lemma, _)| lemma)
+                .collect()
+        };
+        assert_eq!(lemmas(&handle, "dictionaries"), ["dictionary"]);
+        assert_eq!(lemmas(&handle, "mice"), ["mouse"]);
+        assert_eq!(lemmas(&handle, "children"), ["child"]);
+
+        // Without rules only the forms table is consulted
+        handle.config.lemma_rules = false;
+        assert!(lemmas(&handle, "dictionaries").is_empty());
+        assert!(lemmas(&handle, "mice").is_empty());
+        assert_eq!(lemmas(&handle, "children"), ["child"]);
+    }
+}
@@ -0,0 +1,295 @@
+//! Normalized keys for headwords
+//!
+//! [`search_key`] folds case and Latin diacritics, so "Éclair", "eclair"
+//! and "ÉCLAIR" share a key. Keys are stored with every headword
+//! (`words.search_key`) at insert time and used wherever words are
+//! grouped or matched regardless of spelling details, such as the A–Z
+//! letter index.
+//!
+//! Dictionaries of languages where diacritics tell words apart are built
+//! with [`Normalization::Case`] instead, which only folds case; the policy
+//! is recorded in the `meta` table so queries are folded the same way.
+//!
+//! [`punctuation`] replaces curly quotes and typographic dashes with the
+//! ASCII characters people type, for headwords at import and (through
+//! `DictConfig::query_replacements`) for queries.
+//!
+//! [`confusables`] undoes what copying from PDFs and web pages does to
+//! queries: ligatures ("ﬁ"), fullwidth letters and Cyrillic or Greek
+//! lookalikes mixed into Latin words ("саt" with a Cyrillic "с").
+
+use std::borrow::Cow;
+
+/// Latin letters with diacritics and the plain letters they fold to
+const FOLDS: &[(&str, &str)] = &[
+    ("àáâãäåāăą", "a"),
+    ("çćĉċč", "c"),
+    ("ďđ", "d"),
+    ("èéêëēĕėęě", "e"),
+    ("ĝğġģ", "g"),
+    ("ĥħ", "h"),
+    ("ìíîïĩīĭįı", "i"),
+    ("ĵ", "j"),
+    ("ķ", "k"),
+    ("ĺļľŀł", "l"),
+    ("ñńņňŉ", "n"),
+    ("òóôõöøōŏő", "o"),
+    ("ŕŗř", "r"),
+    ("śŝşšș", "s"),
+    ("ţťŧț", "t"),
+    ("ùúûüũūŭůűų", "u"),
+    ("ŵ", "w"),
+    ("ýÿŷ", "y"),
+    ("źżž", "z"),
+    ("ß", "ss"),
+    ("æ", "ae"),
+    ("œ", "oe"),
+    ("ð", "d"),
+    ("þ", "th"),
+];
+
+/// Typographic quotes, primes and dashes and the ASCII they are typed as
+pub const PUNCTUATION: &[(char, char)] = &[
+    ('\u{2018}', '\''), // ‘
+    ('\u{2019}', '\''), // ’
+    ('\u{201B}', '\''), // ‛
+    ('\u{2032}', '\''), // ′
+    ('\u{201C}', '"'),  // “
+    ('\u{201D}', '"'),  // ”
+    ('\u{201E}', '"'),  // „
+    ('\u{2033}', '"'),  // ″
+    ('\u{2010}', '-'),  // hyphen
+    ('\u{2011}', '-'),  // non-breaking hyphen
+    ('\u{2012}', '-'),  // figure dash
+    ('\u{2013}', '-'),  // –
+    ('\u{2014}', '-'),  // —
+    ('\u{2212}', '-'),  // minus sign
+];
+
+/// Ligatures and the letters they join
+const LIGATURES: &[(char, &str)] = &[
+    ('\u{FB00}', "ff"),
+    ('\u{FB01}', "fi"),
+    ('\u{FB02}', "fl"),
+    ('\u{FB03}', "ffi"),
+    ('\u{FB04}', "ffl"),
+    ('\u{FB05}', "st"),
+    ('\u{FB06}', "st"),
+];
+
+/// Cyrillic and Greek letters that look like Latin ones, from the Unicode
+/// confusables table
+#[rustfmt::skip]
+const LOOKALIKES: &[(char, char)] = &[
+    // Cyrillic
+    ('а', 'a'), ('с', 'c'), ('ԁ', 'd'), ('е', 'e'), ('һ', 'h'), ('і', 'i'),
+    ('ј', 'j'), ('ӏ', 'l'), ('о', 'o'), ('р', 'p'), ('ԛ', 'q'), ('ѕ', 's'),
+    ('ԝ', 'w'), ('х', 'x'), ('у', 'y'),
+    ('А', 'A'), ('В', 'B'), ('С', 'C'), ('Е', 'E'), ('Н', 'H'), ('І', 'I'),
+    ('Ј', 'J'), ('К', 'K'), ('М', 'M'), ('О', 'O'), ('Р', 'P'), ('Ѕ', 'S'),
+    ('Т', 'T'), ('Х', 'X'), ('У', 'Y'),
+    // Greek
+    ('ι', 'i'), ('ν', 'v'), ('ο', 'o'),
+    ('Α', 'A'), ('Β', 'B'), ('Ε', 'E'), ('Η', 'H'), ('Ι', 'I'), ('Κ', 'K'),
+    ('Μ', 'M'), ('Ν', 'N'), ('Ο', 'O'), ('Ρ', 'P'), ('Τ', 'T'), ('Υ', 'Y'),
+    ('Χ', 'X'), ('Ζ', 'Z'),
+];
+
+/// `text` with each character in `map` replaced by its counterpart
+pub fn replace_chars<'a>(text: &'a str, map: &[(char, char)]) -> Cow<'a, str> {
+    let replacement = |c: char| map.iter().find(|(from, _)| *from == c).map(|(_, to)| *to);
+    if !text.chars().any(|c| replacement(c).is_some()) {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(text.chars().map(|c| replacement(c).unwrap_or(c)).collect())
+}
+
+/// `text` with typographic quotes and dashes replaced by ASCII (see
+/// [`PUNCTUATION`]), so "rock ’n’ roll" is stored as "rock 'n' roll"
+///
+/// Symbols are left alone: "–" is an entry of its own.
+pub fn punctuation(text: &str) -> Cow<'_, str> {
+    if crate::symbols::is_symbol(text) {
+        return Cow::Borrowed(text);
+    }
+    replace_chars(text, PUNCTUATION)
+}
+
+/// `text` with ligatures split into their letters ("ﬁle" to "file"),
+/// fullwidth ASCII made plain ("ｃａｔ" to "cat"), and Cyrillic or Greek
+/// lookalikes in words with Latin letters replaced by those letters
+///
+/// Words written wholly in Cyrillic or Greek are left alone, so "сок"
+/// still finds the Russian word.
+pub fn confusables(text: &str) -> Cow<'_, str> {
+    let is_lookalike = |c: char| LOOKALIKES.iter().any(|(from, _)| *from == c);
+    if !text.chars().any(|c| {
+        is_lookalike(c) || fullwidth(c).is_some() || LIGATURES.iter().any(|(l, _)| *l == c)
+    }) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut plain = String::with_capacity(text.len());
+    for c in text.chars() {
+        match LIGATURES.iter().find(|(l, _)| *l == c) {
+            Some((_, letters)) => plain.push_str(letters),
+            None => plain.push(fullwidth(c).unwrap_or(c)),
+        }
+    }
+
+    let mut out = String::with_capacity(plain.len());
+    for (i, word) in plain.split(char::is_whitespace).enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        let latin = word.chars().any(|c| is_latin(c) && !is_lookalike(c));
+        for c in word.chars() {
+            match LOOKALIKES.iter().find(|(from, _)| *from == c) {
+                Some((_, to)) if latin => out.push(*to),
+                _ => out.push(c),
+            }
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// The ASCII character a fullwidth form (U+FF01 to U+FF5E) stands for
+fn fullwidth(c: char) -> Option<char> {
+    match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFF01 + 0x21),
+        _ => None,
+    }
+}
+
+/// Whether `c` is a letter of the Latin script (Basic Latin to Latin
+/// Extended-B)
+fn is_latin(c: char) -> bool {
+    c.is_ascii_alphabetic() || (('\u{00C0}'..='\u{024F}').contains(&c) && c.is_alphabetic())
+}
+
+/// The normalized key of a headword: lowercased, with diacritics of Latin
+/// letters removed and combining marks dropped
+///
+/// Scripts without case or Latin diacritics come through unchanged, so
+/// keys of Chinese or Japanese headwords are the headwords themselves.
+pub fn search_key(text: &str) -> String {
+    let mut key = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if is_combining_mark(c) {
+            continue;
+        }
+        match FOLDS.iter().find(|(from, _)| from.contains(c)) {
+            Some((_, to)) => key.push_str(to),
+            None => key.push(c),
+        }
+    }
+    key
+}
+
+/// The letter a headword is filed under in an A–Z index: the first
+/// character of its key
+pub fn initial(text: &str) -> Option<char> {
+    search_key(text).chars().next()
+}
+
+/// How headwords and queries are folded before they are compared
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Normalization {
+    /// Case and Latin diacritics folded, see [`search_key`]
+    #[default]
+    Fold,
+    /// Case folded only, for languages where "é" and "e" are different
+    /// letters
+    Case,
+}
+
+impl Normalization {
+    /// Name recorded as `normalization` in the `meta` table
+    pub fn name(self) -> &'static str {
+        match self {
+            Normalization::Fold => "fold",
+            Normalization::Case => "case",
+        }
+    }
+
+    /// The policy recorded under `name`, if this library knows it
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "fold" => Some(Normalization::Fold),
+            "case" => Some(Normalization::Case),
+            _ => None,
+        }
+    }
+
+    /// The normalized key of `text` under this policy
+    pub fn key(self, text: &str) -> String {
+        match self {
+            Normalization::Fold => search_key(text),
+            Normalization::Case => text.chars().flat_map(char::to_lowercase).collect(),
+        }
+    }
+
+    /// The letter `text` is filed under in an A–Z index
+    pub fn initial(self, text: &str) -> Option<char> {
+        self.key(text).chars().next()
+    }
+
+    /// FTS5 tokenizer folding tokens the same way
+    pub fn tokenizer(self) -> &'static str {
+        match self {
+            Normalization::Fold => "unicode61",
+            Normalization::Case => "unicode61 remove_diacritics 0",
+        }
+    }
+}
+
+/// Whether `c` is a combining diacritical mark (as in decomposed "é")
+fn is_combining_mark(c: char) -> bool {
+    matches!(c, '\u{0300}'..='\u{036F}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_key() {
+        assert_eq!(search_key("Éclair"), "eclair");
+        assert_eq!(search_key("e\u{0301}clair"), "eclair");
+        assert_eq!(search_key("Straße"), "strasse");
+        assert_eq!(search_key("Œuvre"), "oeuvre");
+        assert_eq!(search_key("Łódź"), "lodz");
+        assert_eq!(search_key("日本"), "日本");
+        assert_eq!(initial("Ñandú"), Some('n'));
+    }
+
+    #[test]
+    fn test_normalization() {
+        assert_eq!(Normalization::Fold.key("Éclair"), "eclair");
+        assert_eq!(Normalization::Case.key("Éclair"), "éclair");
+        assert_eq!(Normalization::Case.initial("Ñandú"), Some('ñ'));
+        for policy in [Normalization::Fold, Normalization::Case] {
+            assert_eq!(Normalization::from_name(policy.name()), Some(policy));
+        }
+        assert_eq!(Normalization::from_name("nfkc"), None);
+    }
+
+    #[test]
+    fn test_confusables() {
+        assert_eq!(confusables("ﬁle"), "file");
+        assert_eq!(confusables("ｃａｔ"), "cat");
+        // Cyrillic "с" and "а" among Latin letters
+        assert_eq!(confusables("\u{0441}\u{0430}t"), "cat");
+        assert_eq!(confusables("Рython or сок"), "Python or сок");
+        assert!(matches!(confusables("plain"), Cow::Borrowed("plain")));
+        assert_eq!(confusables("сок"), "сок");
+    }
+
+    #[test]
+    fn test_punctuation() {
+        assert_eq!(punctuation("rock ’n’ roll"), "rock 'n' roll");
+        assert_eq!(punctuation("“quoted” – well—yes"), "\"quoted\" - well-yes");
+        assert!(matches!(punctuation("plain"), Cow::Borrowed("plain")));
+        assert_eq!(punctuation("–"), "–");
+    }
+}
@@ -0,0 +1,443 @@
+//! Offline dictionary packages
+//!
+//! A `.dictpkg` file carries everything needed to install a dictionary
+//! without a network: the database, its license files and, when built,
+//! the headword FST, described by a manifest with each file's size and
+//! SHA-256. The preprocessor writes them with [`create`] for sideloading
+//! and distribution outside the CDN, and apps install them with
+//! [`install`].
+//!
+//! The container is a simple length-prefixed sequence rather than a zip,
+//! so reading it needs nothing beyond the standard library:
+//!
+//! ```text
+//! "DICTPKG\0"  u32 format
+//! u16 name length, name, u64 data length, data    (manifest.json first)
+//! u16 name length, name, u64 data length, data    (one per manifest file)
+//! ```
+//!
+//! Integers are little-endian. Files follow the manifest in its order.
+//! Installing unpacks into a staging directory inside the target, checks
+//! every file against the manifest, and only then renames them into
+//! place, the database last, so a damaged or truncated package leaves the
+//! target as it was.
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{Error, Result};
+
+/// Start of every package
+const MAGIC: &[u8; 8] = b"DICTPKG\0";
+
+/// Container format written by [`create`] and read by [`install`]
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Name of the entry holding the manifest
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// Largest manifest [`install`] will read
+const MAX_MANIFEST_SIZE: u64 = 1024 * 1024;
+
+/// A package's table of contents
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageManifest {
+    /// Container format version
+    pub format: u32,
+    /// Language the dictionary covers (e.g. "english"), if given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Dictionary version (e.g. "42"), if given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Name of the database among `files`
+    pub database: String,
+    /// Files in the package, in the order they are stored
+    pub files: Vec<PackageFile>,
+    /// Unix timestamp of packaging
+    #[serde(default)]
+    pub created_at: u64,
+}
+
+/// One file in a package
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageFile {
+    /// File name, installed directly in the target directory
+    pub name: String,
+    /// Size in bytes
+    pub size: u64,
+    /// Hex-encoded SHA-256
+    pub sha256: String,
+}
+
+/// Write a package at `dest` holding `database` and the `extras` (license
+/// files, the headword FST), each stored under its file name
+///
+/// The package is written to `{dest}.part` and renamed into place once
+/// complete.
+pub fn create(
+    dest: &Path,
+    database: &Path,
+    extras: &[PathBuf],
+    language: Option<&str>,
+    version: Option<&str>,
+) -> Result<PackageManifest> {
+    let sources: Vec<&Path> = std::iter::once(database)
+        .chain(extras.iter().map(PathBuf::as_path))
+        .collect();
+    let mut files: Vec<PackageFile> = Vec::with_capacity(sources.len());
+    for source in &sources {
+        let name = source
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| Error::InvalidPath(source.display().to_string()))?;
+        check_name(name)?;
+        if files.iter().any(|f| f.name == name) {
+            return Err(Error::InvalidInput(format!(
+                "Two package files are named {}",
+                name
+            )));
+        }
+        let (size, sha256) = sha256_file(source)?;
+        files.push(PackageFile {
+            name: name.to_string(),
+            size,
+            sha256,
+        });
+    }
+    let manifest = PackageManifest {
+        format: FORMAT_VERSION,
+        language: language.map(str::to_string),
+        version: version.map(str::to_string),
+        database: files[0].name.clone(),
+        files,
+        created_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    };
+
+    let partial = with_suffix(dest, ".part");
+    let written = write_package(&partial, &manifest, &sources);
+    if let Err(e) = written {
+        fs::remove_file(&partial).ok();
+        return Err(e);
+    }
+    fs::rename(&partial, dest)?;
+    Ok(manifest)
+}
+
+/// Read the manifest of the package at `path` without unpacking it
+pub fn read_manifest(path: &Path) -> Result<PackageManifest> {
+    let mut reader = BufReader::new(File::open(path)?);
+    read_header(&mut reader)
+}
+
+/// Verify the package at `path` and install its files in `target_dir`
+///
+/// Files already in `target_dir` under the same names are replaced, and
+/// an FST or SQLite log left by a previous build of the database is
+/// removed. On any error, including a checksum mismatch, nothing in
+/// `target_dir` changes.
+pub fn install(path: &Path, target_dir: &Path) -> Result<PackageManifest> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let manifest = read_header(&mut reader)?;
+    fs::create_dir_all(target_dir)?;
+
+    let staging = target_dir.join(format!(".{}.part", manifest.database));
+    if staging.exists() {
+        // Left over from an interrupted install
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir(&staging)?;
+    if let Err(e) = unpack(&mut reader, &manifest, &staging) {
+        fs::remove_dir_all(&staging).ok();
+        return Err(e);
+    }
+
+    // Sidecars first, so the database never appears without them, and
+    // those of a previous build the package doesn't replace removed
+    let database = target_dir.join(&manifest.database);
+    for file in manifest
+        .files
+        .iter()
+        .filter(|f| f.name != manifest.database)
+    {
+        fs::rename(staging.join(&file.name), target_dir.join(&file.name))?;
+    }
+    for stale in crate::db::sidecar_paths(&database) {
+        let listed = manifest
+            .files
+            .iter()
+            .any(|f| stale.file_name() == Some(f.name.as_ref()));
+        if !listed && stale.exists() {
+            fs::remove_file(&stale)?;
+        }
+    }
+    fs::rename(staging.join(&manifest.database), &database)?;
+    fs::remove_dir(&staging).ok();
+    Ok(manifest)
+}
+
+fn write_package(path: &Path, manifest: &PackageManifest, sources: &[&Path]) -> Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(MAGIC)?;
+    out.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    let json = serde_json::to_vec(manifest)?;
+    write_entry_header(&mut out, MANIFEST_NAME, json.len() as u64)?;
+    out.write_all(&json)?;
+    for (file, source) in manifest.files.iter().zip(sources) {
+        write_entry_header(&mut out, &file.name, file.size)?;
+        let copied = std::io::copy(&mut File::open(source)?.take(file.size), &mut out)?;
+        if copied != file.size {
+            return Err(Error::InvalidInput(format!(
+                "{} changed while packaging",
+                source.display()
+            )));
+        }
+    }
+    out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    Ok(())
+}
+
+fn write_entry_header(out: &mut impl Write, name: &str, size: u64) -> Result<()> {
+    let len = u16::try_from(name.len())
+        .map_err(|_| Error::InvalidInput(format!("File name too long: {}", name)))?;
+    out.write_all(&len.to_le_bytes())?;
+    out.write_all(name.as_bytes())?;
+    out.write_all(&size.to_le_bytes())?;
+    Ok(())
+}
+
+/// Read the magic, format and manifest, leaving `reader` at the first file
+fn read_header(reader: &mut impl Read) -> Result<PackageManifest> {
+    let mut magic = [0u8; 8];
+    read_exact(reader, &mut magic)?;
+    if &magic != MAGIC {
+        return Err(Error::InvalidPackage("not a dictionary package".into()));
+    }
+    let mut format = [0u8; 4];
+    read_exact(reader, &mut format)?;
+    let format = u32::from_le_bytes(format);
+    if format != FORMAT_VERSION {
+        return Err(Error::InvalidPackage(format!(
+            "unsupported format {}",
+            format
+        )));
+    }
+
+    let (name, size) = read_entry_header(reader)?;
+    if name != MANIFEST_NAME || size > MAX_MANIFEST_SIZE {
+        return Err(Error::InvalidPackage("missing manifest".into()));
+    }
+    let mut json = vec![0u8; size as usize];
+    read_exact(reader, &mut json)?;
+    let manifest: PackageManifest = serde_json::from_slice(&json)
+        .map_err(|e| Error::InvalidPackage(format!("bad manifest: {}", e)))?;
+
+    for (i, file) in manifest.files.iter().enumerate() {
+        check_name(&file.name)?;
+        // A second copy would overwrite the first while staging
+        if manifest.files[..i].iter().any(|f| f.name == file.name) {
+            return Err(Error::InvalidPackage(format!(
+                "{} is listed twice",
+                file.name
+            )));
+        }
+    }
+    if !manifest.files.iter().any(|f| f.name == manifest.database) {
+        return Err(Error::InvalidPackage(format!(
+            "manifest does not list its database {}",
+            manifest.database
+        )));
+    }
+    Ok(manifest)
+}
+
+fn read_entry_header(reader: &mut impl Read) -> Result<(String, u64)> {
+    let mut len = [0u8; 2];
+    read_exact(reader, &mut len)?;
+    let mut name = vec![0u8; u16::from_le_bytes(len) as usize];
+    read_exact(reader, &mut name)?;
+    let name = String::from_utf8(name)
+        .map_err(|_| Error::InvalidPackage("file name is not UTF-8".into()))?;
+    let mut size = [0u8; 8];
+    read_exact(reader, &mut size)?;
+    Ok((name, u64::from_le_bytes(size)))
+}
+
+/// Like `Read::read_exact`, reporting a short read as a truncated package
+fn read_exact(reader: &mut impl Read, buffer: &mut [u8]) -> Result<()> {
+    reader.read_exact(buffer).map_err(|e| match e.kind() {
+        std::io::ErrorKind::UnexpectedEof => Error::InvalidPackage("truncated".into()),
+        _ => Error::Io(e),
+    })
+}
+
+/// Write each file of `manifest` from `reader` into `staging`, checking
+/// names, sizes and checksums
+fn unpack(reader: &mut impl Read, manifest: &PackageManifest, staging: &Path) -> Result<()> {
+    let mut buffer = vec![0u8; 64 * 1024];
+    for file in &manifest.files {
+        let (name, size) = read_entry_header(reader)?;
+        if name != file.name || size != file.size {
+            return Err(Error::InvalidPackage(format!(
+                "expected {} ({} bytes), found {} ({} bytes)",
+                file.name, file.size, name, size
+            )));
+        }
+
+        let mut out = BufWriter::new(File::create(staging.join(&file.name))?);
+        let mut hasher = Sha256::new();
+        let mut left = size;
+        while left > 0 {
+            let n = buffer.len().min(left as usize);
+            read_exact(reader, &mut buffer[..n])?;
+            hasher.update(&buffer[..n]);
+            out.write_all(&buffer[..n])?;
+            left -= n as u64;
+        }
+        out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+
+        let actual = format!("{:x}", hasher.finalize());
+        if !actual.eq_ignore_ascii_case(&file.sha256) {
+            return Err(Error::ChecksumMismatch {
+                file: file.name.clone(),
+                expected: file.sha256.clone(),
+                actual,
+            });
+        }
+    }
+    if reader.read(&mut buffer[..1])? != 0 {
+        return Err(Error::InvalidPackage("data after the last file".into()));
+    }
+    Ok(())
+}
+
+/// Reject names that would land outside the target directory or clash
+/// with the manifest and staging entries
+fn check_name(name: &str) -> Result<()> {
+    let unsafe_name = name.is_empty()
+        || name.starts_with('.')
+        || name.contains(['/', '\\', '\0'])
+        || name.contains(':')
+        || name == MANIFEST_NAME;
+    if unsafe_name {
+        return Err(Error::InvalidPackage(format!("bad file name {:?}", name)));
+    }
+    Ok(())
+}
+
+fn sha256_file(path: &Path) -> Result<(u64, String)> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hasher = Sha256::new();
+    let size = std::io::copy(&mut reader, &mut hasher)?;
+    Ok((size, format!("{:x}", hasher.finalize())))
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_and_install() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("english.db");
+        fs::write(&db, b"SQLite format 3\0 and the rest").unwrap();
+        let fst = dir.path().join("english.db.fst");
+        fs::write(&fst, b"fst bytes").unwrap();
+        let license = dir.path().join("LICENSE-wiktionary.txt");
+        fs::write(&license, b"CC BY-SA 4.0").unwrap();
+
+        let pkg = dir.path().join("english.dictpkg");
+        let extras = [fst, license];
+        let made = create(&pkg, &db, &extras, Some("english"), Some("42")).unwrap();
+        assert_eq!(made.database, "english.db");
+        assert_eq!(made.files.len(), 3);
+        assert_eq!(read_manifest(&pkg).unwrap(), made);
+        assert!(!with_suffix(&pkg, ".part").exists());
+
+        let target = dir.path().join("installed");
+        let installed = install(&pkg, &target).unwrap();
+        assert_eq!(installed.version.as_deref(), Some("42"));
+        assert_eq!(
+            fs::read(target.join("english.db")).unwrap(),
+            fs::read(&db).unwrap()
+        );
+        assert_eq!(
+            fs::read(target.join("english.db.fst")).unwrap(),
+            b"fst bytes"
+        );
+        assert!(target.join("LICENSE-wiktionary.txt").exists());
+        assert!(!target.join(".english.db.part").exists());
+
+        // A flipped byte in the database fails its checksum and installs nothing
+        let mut bytes = fs::read(&pkg).unwrap();
+        let at = bytes.windows(6).position(|w| w == b"SQLite").unwrap();
+        bytes[at] ^= 0xff;
+        fs::write(&pkg, &bytes).unwrap();
+        let fresh = dir.path().join("fresh");
+        assert!(matches!(
+            install(&pkg, &fresh),
+            Err(Error::ChecksumMismatch { .. })
+        ));
+        assert_eq!(fs::read_dir(&fresh).unwrap().count(), 0);
+
+        // Truncated, or not a package at all
+        bytes[at] ^= 0xff;
+        fs::write(&pkg, &bytes[..bytes.len() - 5]).unwrap();
+        assert!(matches!(
+            install(&pkg, &fresh),
+            Err(Error::InvalidPackage(_))
+        ));
+        assert!(matches!(
+            install(&db, &fresh),
+            Err(Error::InvalidPackage(_))
+        ));
+
+        // A manifest listing a file twice is refused before anything is
+        // unpacked, even with the file stored twice to match
+        let twice = dir.path().join("twice.dictpkg");
+        let mut manifest = made.clone();
+        manifest.files.insert(1, manifest.files[2].clone());
+        let [fst, license] = extras.each_ref().map(PathBuf::as_path);
+        let sources = [db.as_path(), license, fst, license];
+        write_package(&twice, &manifest, &sources).unwrap();
+        match install(&twice, &fresh) {
+            Err(Error::InvalidPackage(message)) => assert!(message.contains("listed twice")),
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(fs::read_dir(&fresh).unwrap().count(), 0);
+
+        // A build without an FST over one with it: the old FST and the
+        // old database's log go, the license stays
+        let bare = dir.path().join("bare.dictpkg");
+        fs::write(&db, b"SQLite format 3\0 next build").unwrap();
+        create(&bare, &db, &[], Some("english"), Some("43")).unwrap();
+        fs::write(target.join("english.db-wal"), b"old log").unwrap();
+        fs::write(target.join("english.db-shm"), b"old index").unwrap();
+        install(&bare, &target).unwrap();
+        assert_eq!(
+            fs::read(target.join("english.db")).unwrap(),
+            b"SQLite format 3\0 next build"
+        );
+        assert!(!target.join("english.db.fst").exists());
+        assert!(!target.join("english.db-wal").exists());
+        assert!(!target.join("english.db-shm").exists());
+        assert!(target.join("LICENSE-wiktionary.txt").exists());
+
+        assert!(check_name("../english.db").is_err());
+        assert!(check_name(".hidden").is_err());
+        assert!(check_name("english.db").is_ok());
+    }
+}
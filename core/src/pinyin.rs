@@ -0,0 +1,130 @@
+//! Hanyu Pinyin parsing for Chinese lookups
+//!
+//! Pinyin is written with tone marks (`nǐ hǎo`) or tone numbers (`ni3hao3`).
+//! Both are reduced to the same [`Pinyin`] key: the letters without tones,
+//! and the tone numbers in order. Keeping the tones apart means neither form
+//! has to be split into syllables, and a query without tones can fall back
+//! to matching the letters alone.
+
+/// Tone-independent form of a pinyin reading
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Pinyin {
+    /// Lowercase letters without tones or separators, `ü` written as `v`
+    /// (e.g. "nihao", "nv")
+    pub letters: String,
+    /// Tone numbers 1-4 in order; neutral tones are omitted (e.g. "33")
+    pub tones: String,
+}
+
+/// Parse pinyin with tone marks or tone numbers
+///
+/// Spaces, apostrophes, hyphens and middle dots are ignored, and so are
+/// neutral tone numbers 5 and 0. Returns `None` if the text contains
+/// anything else, such as Han characters or punctuation, or has no letters.
+pub fn parse(text: &str) -> Option<Pinyin> {
+    let mut letters = String::with_capacity(text.len());
+    let mut tones = String::new();
+
+    for c in text.to_lowercase().chars() {
+        match c {
+            'a'..='z' => letters.push(c),
+            'ü' => letters.push('v'),
+            '1'..='4' if !letters.is_empty() => tones.push(c),
+            '5' | '0' if !letters.is_empty() => {}
+            ' ' | '\'' | '’' | '-' | '·' => {}
+            // Combining tone marks, as left by NFD normalization
+            '\u{0304}' => tones.push('1'),
+            '\u{0301}' => tones.push('2'),
+            '\u{030C}' => tones.push('3'),
+            '\u{0300}' => tones.push('4'),
+            '\u{0308}' if letters.ends_with('u') => {
+                letters.pop();
+                letters.push('v');
+            }
+            c => {
+                let (base, tone) = split_tone_mark(c)?;
+                letters.push(base);
+                tones.push(tone);
+            }
+        }
+    }
+
+    if letters.is_empty() {
+        return None;
+    }
+    Some(Pinyin { letters, tones })
+}
+
+/// Split a precomposed tone-marked vowel into its base letter and tone
+fn split_tone_mark(c: char) -> Option<(char, char)> {
+    let (base, tone) = match c {
+        'ā' => ('a', '1'),
+        'á' => ('a', '2'),
+        'ǎ' => ('a', '3'),
+        'à' => ('a', '4'),
+        'ē' => ('e', '1'),
+        'é' => ('e', '2'),
+        'ě' => ('e', '3'),
+        'è' => ('e', '4'),
+        'ī' => ('i', '1'),
+        'í' => ('i', '2'),
+        'ǐ' => ('i', '3'),
+        'ì' => ('i', '4'),
+        'ō' => ('o', '1'),
+        'ó' => ('o', '2'),
+        'ǒ' => ('o', '3'),
+        'ò' => ('o', '4'),
+        'ū' => ('u', '1'),
+        'ú' => ('u', '2'),
+        'ǔ' => ('u', '3'),
+        'ù' => ('u', '4'),
+        'ǖ' => ('v', '1'),
+        'ǘ' => ('v', '2'),
+        'ǚ' => ('v', '3'),
+        'ǜ' => ('v', '4'),
+        'ḿ' => ('m', '2'),
+        'ń' => ('n', '2'),
+        'ň' => ('n', '3'),
+        'ǹ' => ('n', '4'),
+        _ => return None,
+    };
+    Some((base, tone))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(letters: &str, tones: &str) -> Option<Pinyin> {
+        Some(Pinyin {
+            letters: letters.to_string(),
+            tones: tones.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_marks_and_numbers_agree() {
+        assert_eq!(parse("nǐ hǎo"), key("nihao", "33"));
+        assert_eq!(parse("nǐhǎo"), key("nihao", "33"));
+        assert_eq!(parse("ni3hao3"), key("nihao", "33"));
+        assert_eq!(parse("Ni3 Hao3"), key("nihao", "33"));
+        assert_eq!(parse("nihao"), key("nihao", ""));
+    }
+
+    #[test]
+    fn test_neutral_tone_and_umlaut() {
+        assert_eq!(parse("māma"), key("mama", "1"));
+        assert_eq!(parse("ma1ma5"), key("mama", "1"));
+        assert_eq!(parse("nǚ'ér"), key("nver", "32"));
+        assert_eq!(parse("nv3er2"), key("nver", "32"));
+        assert_eq!(parse("nu\u{0308}\u{030C}"), key("nv", "3"));
+    }
+
+    #[test]
+    fn test_rejects_non_pinyin() {
+        assert_eq!(parse("你好"), None);
+        assert_eq!(parse("hello!"), None);
+        assert_eq!(parse("123"), None);
+        assert_eq!(parse(""), None);
+    }
+}
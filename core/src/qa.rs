@@ -0,0 +1,361 @@
+//! Release checks for a dictionary database
+//!
+//! [`run`] samples headwords from a database, turns each into exact,
+//! prefix, misspelled and paged queries, and reports how often search
+//! found the word and how long it took. Publishers can run it against a
+//! freshly built dictionary and refuse to ship one that misses the
+//! targets in [`QaConfig`].
+
+use std::time::{Duration, Instant};
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::search::search_words_offset;
+use crate::{DictHandle, Result};
+
+/// Misses listed per check in the report; the rest are only counted
+const MAX_REPORTED_MISSES: usize = 10;
+
+/// Shortest headword (in characters) sampled for the prefix check
+const MIN_PREFIX_WORD_LENGTH: usize = 4;
+
+/// Shortest headword (in characters) sampled for the fuzzy check
+const MIN_FUZZY_WORD_LENGTH: usize = 5;
+
+/// A kind of query the suite runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckKind {
+    /// The headword itself; its entry must be among the results
+    Exact,
+    /// The first three quarters of the headword
+    Prefix,
+    /// The headword with its last two letters swapped
+    Fuzzy,
+    /// A prefix query fetched page by page, which must match the same
+    /// results fetched at once
+    Pagination,
+}
+
+/// One check of the suite and the targets it must meet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckSpec {
+    pub kind: CheckKind,
+    /// Share of queries that must find their word (0.0 to 1.0)
+    pub min_recall: f64,
+    /// 95th-percentile latency a single query must stay under
+    pub max_p95_ms: f64,
+}
+
+/// What to run and the targets to hold the database to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QaConfig {
+    /// Headwords to sample, spread evenly over the database
+    pub sample_size: usize,
+    /// Results requested per query (and per page)
+    pub limit: u32,
+    /// Pages fetched by the pagination check
+    pub pages: u32,
+    /// Checks to run, in report order
+    pub checks: Vec<CheckSpec>,
+}
+
+impl Default for QaConfig {
+    fn default() -> Self {
+        let spec = |kind, min_recall, max_p95_ms| CheckSpec {
+            kind,
+            min_recall,
+            max_p95_ms,
+        };
+        Self {
+            sample_size: 200,
+            limit: 20,
+            pages: 3,
+            checks: vec![
+                spec(CheckKind::Exact, 1.0, 20.0),
+                spec(CheckKind::Prefix, 0.9, 50.0),
+                spec(CheckKind::Fuzzy, 0.5, 200.0),
+                spec(CheckKind::Pagination, 1.0, 100.0),
+            ],
+        }
+    }
+}
+
+/// Latency distribution of one check's queries, in milliseconds
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Latency {
+    pub min_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Outcome of one check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckReport {
+    pub kind: CheckKind,
+    /// Queries run; sampled words too short for the check are skipped
+    pub queries: u64,
+    /// Queries that found their word
+    pub hits: u64,
+    /// `hits / queries`, 1.0 when no query ran
+    pub recall: f64,
+    pub latency: Latency,
+    pub min_recall: f64,
+    pub max_p95_ms: f64,
+    /// Whether recall and latency met the targets
+    pub passed: bool,
+    /// The first few queries that missed
+    pub misses: Vec<String>,
+}
+
+/// Outcome of a whole suite
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QaReport {
+    /// Entries in the database
+    pub total_words: u64,
+    /// Headwords actually sampled
+    pub samples: usize,
+    pub checks: Vec<CheckReport>,
+    /// Whether every check passed
+    pub passed: bool,
+}
+
+/// Run the checks in `config` against a database
+///
+/// Sampling is deterministic, so two runs against the same database
+/// issue the same queries and only the latencies differ.
+pub fn run(handle: &DictHandle, config: &QaConfig) -> Result<QaReport> {
+    let total_words: u64 = handle
+        .conn
+        .query_row("SELECT COUNT(*) FROM words", [], |row| row.get(0))?;
+    let samples = sample_words(handle, config.sample_size, total_words)?;
+
+    let mut checks = Vec::with_capacity(config.checks.len());
+    for spec in &config.checks {
+        checks.push(run_check(handle, config, spec, &samples)?);
+    }
+
+    Ok(QaReport {
+        total_words,
+        samples: samples.len(),
+        passed: checks.iter().all(|c| c.passed),
+        checks,
+    })
+}
+
+/// Up to `n` (id, headword) pairs spread evenly over the words table
+fn sample_words(handle: &DictHandle, n: usize, total_words: u64) -> Result<Vec<(i64, String)>> {
+    if n == 0 {
+        return Ok(Vec::new());
+    }
+    let step = (total_words / n as u64).max(1);
+    let mut stmt = handle.conn.prepare(
+        r#"
+        SELECT id, word FROM (SELECT id, word, ROW_NUMBER() OVER (ORDER BY id) - 1 AS n FROM words)
+        WHERE n % ? = 0
+        ORDER BY id
+        LIMIT ?
+        "#,
+    )?;
+    let rows = stmt.query_map(params![step, n as u64], |row| {
+        Ok((row.get(0)?, row.get(1)?))
+    })?;
+    Ok(rows.collect::<std::result::Result<Vec<_>, _>>()?)
+}
+
+fn run_check(
+    handle: &DictHandle,
+    config: &QaConfig,
+    spec: &CheckSpec,
+    samples: &[(i64, String)],
+) -> Result<CheckReport> {
+    let mut timings: Vec<Duration> = Vec::new();
+    let mut queries = 0u64;
+    let mut hits = 0u64;
+    let mut misses = Vec::new();
+
+    for (id, word) in samples {
+        let Some(query) = query_for(spec.kind, word) else {
+            continue;
+        };
+        queries += 1;
+
+        let hit = match spec.kind {
+            CheckKind::Exact => {
+                let results = timed(&mut timings, || {
+                    search_words_offset(handle, &query, config.limit, 0)
+                })?;
+                results.iter().any(|r| r.id == *id)
+            }
+            CheckKind::Prefix | CheckKind::Fuzzy => {
+                let results = timed(&mut timings, || {
+                    search_words_offset(handle, &query, config.limit, 0)
+                })?;
+                results.iter().any(|r| r.word == *word)
+            }
+            CheckKind::Pagination => {
+                let mut paged = Vec::new();
+                for page in 0..config.pages {
+                    let offset = page * config.limit;
+                    paged.extend(timed(&mut timings, || {
+                        search_words_offset(handle, &query, config.limit, offset)
+                    })?);
+                }
+                let all = search_words_offset(handle, &query, config.limit * config.pages, 0)?;
+                paged.iter().map(|r| r.id).eq(all.iter().map(|r| r.id))
+            }
+        };
+
+        if hit {
+            hits += 1;
+        } else if misses.len() < MAX_REPORTED_MISSES {
+            misses.push(query);
+        }
+    }
+
+    let recall = if queries == 0 {
+        1.0
+    } else {
+        hits as f64 / queries as f64
+    };
+    let latency = latency(&mut timings);
+    Ok(CheckReport {
+        kind: spec.kind,
+        queries,
+        hits,
+        recall,
+        passed: recall >= spec.min_recall && latency.p95_ms <= spec.max_p95_ms,
+        latency,
+        min_recall: spec.min_recall,
+        max_p95_ms: spec.max_p95_ms,
+        misses,
+    })
+}
+
+/// The query a check issues for `word`, or None if the word is too short
+fn query_for(kind: CheckKind, word: &str) -> Option<String> {
+    let chars: Vec<char> = word.chars().collect();
+    match kind {
+        CheckKind::Exact => Some(word.to_string()),
+        CheckKind::Prefix | CheckKind::Pagination => {
+            if chars.len() < MIN_PREFIX_WORD_LENGTH {
+                return None;
+            }
+            let keep = (chars.len() * 3).div_ceil(4);
+            Some(chars[..keep].iter().collect())
+        }
+        CheckKind::Fuzzy => {
+            let n = chars.len();
+            if n < MIN_FUZZY_WORD_LENGTH || chars[n - 1] == chars[n - 2] {
+                return None;
+            }
+            let mut typo = chars;
+            typo.swap(n - 1, n - 2);
+            Some(typo.into_iter().collect())
+        }
+    }
+}
+
+/// Run `f`, recording how long it took
+fn timed<T>(timings: &mut Vec<Duration>, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    let start = Instant::now();
+    let result = f();
+    timings.push(start.elapsed());
+    result
+}
+
+/// Distribution of `timings` (nearest-rank percentiles)
+fn latency(timings: &mut [Duration]) -> Latency {
+    if timings.is_empty() {
+        return Latency::default();
+    }
+    timings.sort();
+    let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+    let percentile = |p: usize| ms(timings[(timings.len() * p).div_ceil(100).max(1) - 1]);
+    Latency {
+        min_ms: ms(timings[0]),
+        p50_ms: percentile(50),
+        p95_ms: percentile(95),
+        max_ms: ms(timings[timings.len() - 1]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_database, insert_definition, insert_word};
+
+    #[test]
+    fn test_query_for() {
+        assert_eq!(query_for(CheckKind::Exact, "cat").as_deref(), Some("cat"));
+        assert_eq!(query_for(CheckKind::Prefix, "cat"), None);
+        assert_eq!(
+            query_for(CheckKind::Prefix, "helicopter").as_deref(),
+            Some("helicopt")
+        );
+        assert_eq!(
+            query_for(CheckKind::Fuzzy, "helper").as_deref(),
+            Some("helpre")
+        );
+        assert_eq!(
+            query_for(CheckKind::Fuzzy, "cheer"),
+            Some("chere".to_string())
+        );
+        assert_eq!(query_for(CheckKind::Fuzzy, "grass"), None);
+    }
+
+    #[test]
+    fn test_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let handle = init_database(db_path.to_str().unwrap()).unwrap();
+        for word in [
+            "hello",
+            "helper",
+            "helicopter",
+            "world",
+            "worker",
+            "testing",
+        ] {
+            let id = insert_word(&handle.conn, word, "noun", "English", "en", 0).unwrap();
+            insert_definition(&handle.conn, id, "A word.", &[], &[]).unwrap();
+        }
+
+        let config = QaConfig {
+            sample_size: 3,
+            limit: 1,
+            ..QaConfig::default()
+        };
+        let report = run(&handle, &config).unwrap();
+        assert_eq!(report.total_words, 6);
+        assert_eq!(report.samples, 3);
+        let kinds: Vec<CheckKind> = report.checks.iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                CheckKind::Exact,
+                CheckKind::Prefix,
+                CheckKind::Fuzzy,
+                CheckKind::Pagination
+            ]
+        );
+        let exact = &report.checks[0];
+        assert_eq!((exact.queries, exact.hits), (3, 3));
+        assert!(exact.latency.min_ms <= exact.latency.max_ms);
+
+        // An unreachable target fails the check and the report
+        let config = QaConfig {
+            checks: vec![CheckSpec {
+                kind: CheckKind::Exact,
+                min_recall: 1.0,
+                max_p95_ms: -1.0,
+            }],
+            ..config
+        };
+        let report = run(&handle, &config).unwrap();
+        assert!(!report.checks[0].passed);
+        assert!(!report.passed);
+    }
+}
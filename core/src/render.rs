@@ -0,0 +1,752 @@
+//! Rendering-ready HTML, Markdown and plain text for entries
+//!
+//! Every platform shows entries the same way when they display what
+//! [`definition_html`] or [`definition_markdown`] produce instead of
+//! formatting glosses, labels and examples themselves. All dictionary
+//! text is escaped, so the output is safe to inject into a web view: a
+//! gloss containing `<script>` renders as text. [`definition_plaintext`]
+//! reads an entry out for screen readers and text-to-speech.
+
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Definition, FullDefinition, Link};
+
+/// What to include and how to name things in rendered entries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeOptions {
+    /// Prefix of the CSS classes in HTML output ("dict-" gives
+    /// `dict-headword`, `dict-sense`, ...)
+    pub class_prefix: String,
+    pub show_pronunciations: bool,
+    /// Show respellings next to IPA (see `DefinitionOptions::respelling`)
+    pub show_respellings: bool,
+    pub show_examples: bool,
+    pub show_etymology: bool,
+    pub show_translations: bool,
+    /// Credit the entry's source and license (required when showing
+    /// Wiktionary content)
+    pub show_attribution: bool,
+    /// Target of links to other entries (see `DefinitionOptions::links`);
+    /// `{id}` is replaced by the word id
+    pub link_href: String,
+}
+
+impl Default for ThemeOptions {
+    fn default() -> Self {
+        Self {
+            class_prefix: "dict-".to_string(),
+            show_pronunciations: true,
+            show_respellings: false,
+            show_examples: true,
+            show_etymology: true,
+            show_translations: true,
+            show_attribution: true,
+            link_href: "dict://entry/{id}".to_string(),
+        }
+    }
+}
+
+/// How much of an entry [`definition_plaintext`] reads out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verbosity {
+    /// Headword, part of speech and the first sense
+    Brief,
+    /// Every sense with its labels
+    #[default]
+    Standard,
+    /// Also respellings, examples, form-of targets, the etymology and the
+    /// attribution
+    Full,
+}
+
+/// An entry as an HTML fragment
+///
+/// The fragment is a single `<div>` with classes for styling (see
+/// `ThemeOptions::class_prefix`) and the entry's language in `lang`.
+pub fn definition_html(def: &FullDefinition, options: &ThemeOptions) -> String {
+    let c = |name: &str| escape_html(&format!("{}{}", options.class_prefix, name));
+    let mut out = String::new();
+
+    let _ = write!(
+        out,
+        r#"<div class="{}" lang="{}">"#,
+        c("entry"),
+        escape_html(&def.lang_code)
+    );
+    let _ = write!(
+        out,
+        r#"<h2><span class="{}">{}</span> <span class="{}">{}</span>"#,
+        c("headword"),
+        escape_html(&def.word),
+        c("pos"),
+        escape_html(&def.pos)
+    );
+    if !def.language.is_empty() {
+        let _ = write!(
+            out,
+            r#" <span class="{}">{}</span>"#,
+            c("language"),
+            escape_html(&def.language)
+        );
+    }
+    if !def.also_spelled.is_empty() {
+        let _ = write!(
+            out,
+            r#" <span class="{}">also spelled {}</span>"#,
+            c("also-spelled"),
+            escape_html(&def.also_spelled.join(", "))
+        );
+    }
+    out.push_str("</h2>");
+
+    let ipas: Vec<_> = def
+        .pronunciations
+        .iter()
+        .filter(|p| p.ipa.is_some())
+        .collect();
+    if options.show_pronunciations && !ipas.is_empty() {
+        let _ = write!(out, r#"<ul class="{}">"#, c("pronunciations"));
+        for p in ipas {
+            let _ = write!(
+                out,
+                r#"<li><span class="{}">{}</span>"#,
+                c("ipa"),
+                escape_html(p.ipa.as_deref().unwrap_or_default())
+            );
+            if let Some(respelling) = p.respelling.as_ref().filter(|_| options.show_respellings) {
+                let _ = write!(
+                    out,
+                    r#" <span class="{}">{}</span>"#,
+                    c("respelling"),
+                    escape_html(respelling)
+                );
+            }
+            if let Some(accent) = &p.accent {
+                let _ = write!(
+                    out,
+                    r#" <span class="{}">{}</span>"#,
+                    c("accent"),
+                    escape_html(accent)
+                );
+            }
+            out.push_str("</li>");
+        }
+        out.push_str("</ul>");
+    }
+
+    if !def.definitions.is_empty() {
+        let _ = write!(out, r#"<ol class="{}">"#, c("senses"));
+        for sense in &def.definitions {
+            let _ = write!(out, r#"<li class="{}">"#, c("sense"));
+            sense_html(&mut out, sense, options);
+            out.push_str("</li>");
+        }
+        out.push_str("</ol>");
+    }
+
+    if let Some(etymology) = def.etymology.as_deref().filter(|_| options.show_etymology) {
+        let _ = write!(
+            out,
+            r#"<p class="{}">{}</p>"#,
+            c("etymology"),
+            escape_html(etymology)
+        );
+    }
+
+    if options.show_translations && !def.translations.is_empty() {
+        let _ = write!(out, r#"<dl class="{}">"#, c("translations"));
+        for t in &def.translations {
+            let _ = write!(
+                out,
+                "<dt>{}</dt><dd>{}</dd>",
+                escape_html(&t.target_language),
+                escape_html(&t.translation)
+            );
+        }
+        out.push_str("</dl>");
+    }
+
+    if let Some(attribution) = def
+        .attribution
+        .as_ref()
+        .filter(|_| options.show_attribution)
+    {
+        let link = |text: &str, url: Option<&str>| match url {
+            Some(url) => format!(
+                r#"<a href="{}">{}</a>"#,
+                escape_html(url),
+                escape_html(text)
+            ),
+            None => escape_html(text),
+        };
+        let _ = write!(
+            out,
+            r#"<footer class="{}">From {}, {}</footer>"#,
+            c("attribution"),
+            link(&attribution.source, attribution.url.as_deref()),
+            link(&attribution.license, attribution.license_url.as_deref())
+        );
+    }
+
+    out.push_str("</div>");
+    out
+}
+
+/// The gloss, labels, examples and form-of target of one sense
+fn sense_html(out: &mut String, sense: &Definition, options: &ThemeOptions) {
+    let c = |name: &str| escape_html(&format!("{}{}", options.class_prefix, name));
+
+    if !sense.tags.is_empty() {
+        let _ = write!(
+            out,
+            r#"<span class="{}">{}</span> "#,
+            c("tags"),
+            escape_html(&sense.tags.join(", "))
+        );
+    }
+    let _ = write!(out, r#"<span class="{}">"#, c("gloss"));
+    for (text, link) in link_segments(&sense.text, &sense.links) {
+        match link {
+            Some(link) => {
+                let _ = write!(
+                    out,
+                    r#"<a class="{}" href="{}">{}</a>"#,
+                    c("link"),
+                    escape_html(&href(options, link)),
+                    escape_html(text)
+                );
+            }
+            None => out.push_str(&escape_html(text)),
+        }
+    }
+    out.push_str("</span>");
+
+    if options.show_examples && !sense.examples.is_empty() {
+        let _ = write!(out, r#"<ul class="{}">"#, c("examples"));
+        for example in &sense.examples {
+            let _ = write!(out, "<li>{}</li>", escape_html(example));
+        }
+        out.push_str("</ul>");
+    }
+
+    if let Some(form_of) = &sense.form_of {
+        for target in &form_of.definitions {
+            let _ = write!(
+                out,
+                r#"<p class="{}"><span class="{}">{}</span>: {}</p>"#,
+                c("form-of"),
+                c("headword"),
+                escape_html(&form_of.word),
+                escape_html(&target.text)
+            );
+        }
+    }
+}
+
+/// An entry as Markdown (CommonMark)
+///
+/// Markup characters in dictionary text are backslash-escaped and `<`,
+/// `>` and `&` written as entities, so renderers that allow inline HTML
+/// still show the text as written.
+pub fn definition_markdown(def: &FullDefinition, options: &ThemeOptions) -> String {
+    let mut out = String::new();
+
+    let _ = write!(
+        out,
+        "## {} *{}*",
+        escape_markdown(&def.word),
+        escape_markdown(&def.pos)
+    );
+    if !def.language.is_empty() {
+        let _ = write!(out, " ({})", escape_markdown(&def.language));
+    }
+    if !def.also_spelled.is_empty() {
+        let _ = write!(
+            out,
+            ", also spelled {}",
+            escape_markdown(&def.also_spelled.join(", "))
+        );
+    }
+    out.push_str("\n\n");
+
+    if options.show_pronunciations {
+        let ipas: Vec<String> = def
+            .pronunciations
+            .iter()
+            .filter_map(|p| {
+                let mut ipa = escape_markdown(p.ipa.as_deref()?);
+                if let Some(respelling) = p.respelling.as_ref().filter(|_| options.show_respellings)
+                {
+                    let _ = write!(ipa, " *{}*", escape_markdown(respelling));
+                }
+                Some(match &p.accent {
+                    Some(accent) => format!("{} ({})", ipa, escape_markdown(accent)),
+                    None => ipa,
+                })
+            })
+            .collect();
+        if !ipas.is_empty() {
+            let _ = write!(out, "{}\n\n", ipas.join(", "));
+        }
+    }
+
+    for (n, sense) in def.definitions.iter().enumerate() {
+        let _ = write!(out, "{}. ", n + 1);
+        if !sense.tags.is_empty() {
+            let _ = write!(out, "*({})* ", escape_markdown(&sense.tags.join(", ")));
+        }
+        for (text, link) in link_segments(&sense.text, &sense.links) {
+            match link {
+                Some(link) => {
+                    let _ = write!(
+                        out,
+                        "[{}]({})",
+                        escape_markdown(text),
+                        markdown_destination(&href(options, link))
+                    );
+                }
+                None => out.push_str(&escape_markdown(text)),
+            }
+        }
+        out.push('\n');
+        if options.show_examples {
+            for example in &sense.examples {
+                let _ = writeln!(out, "   > {}", escape_markdown(example));
+            }
+        }
+        if let Some(form_of) = &sense.form_of {
+            for target in &form_of.definitions {
+                let _ = writeln!(
+                    out,
+                    "   - **{}**: {}",
+                    escape_markdown(&form_of.word),
+                    escape_markdown(&target.text)
+                );
+            }
+        }
+    }
+    if !def.definitions.is_empty() {
+        out.push('\n');
+    }
+
+    if let Some(etymology) = def.etymology.as_deref().filter(|_| options.show_etymology) {
+        let _ = write!(out, "**Etymology:** {}\n\n", escape_markdown(etymology));
+    }
+
+    if options.show_translations && !def.translations.is_empty() {
+        let translations: Vec<String> = def
+            .translations
+            .iter()
+            .map(|t| {
+                format!(
+                    "{}: {}",
+                    escape_markdown(&t.target_language),
+                    escape_markdown(&t.translation)
+                )
+            })
+            .collect();
+        let _ = write!(out, "**Translations:** {}\n\n", translations.join("; "));
+    }
+
+    if let Some(attribution) = def
+        .attribution
+        .as_ref()
+        .filter(|_| options.show_attribution)
+    {
+        let link = |text: &str, url: Option<&str>| match url {
+            Some(url) => format!("[{}]({})", escape_markdown(text), markdown_destination(url)),
+            None => escape_markdown(text),
+        };
+        let _ = write!(
+            out,
+            "*From {}, {}*\n\n",
+            link(&attribution.source, attribution.url.as_deref()),
+            link(&attribution.license, attribution.license_url.as_deref())
+        );
+    }
+
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+/// An entry as plain text for screen readers and text-to-speech
+///
+/// One sentence per line, each ending in punctuation so speech pauses
+/// between them: "hello, interjection.", then "1, informal: A
+/// greeting." and so on. IPA is never read out, only respellings (with
+/// `Verbosity::Full`), since speech engines can't pronounce it.
+pub fn definition_plaintext(def: &FullDefinition, verbosity: Verbosity) -> String {
+    let mut lines = vec![sentence(&format!("{}, {}", def.word, def.pos))];
+    if !def.also_spelled.is_empty() {
+        lines.push(sentence(&format!(
+            "Also spelled {}",
+            def.also_spelled.join(", or ")
+        )));
+    }
+    let full = verbosity == Verbosity::Full;
+
+    if full {
+        let respellings: Vec<&str> = def
+            .pronunciations
+            .iter()
+            .filter_map(|p| p.respelling.as_deref())
+            .collect();
+        if !respellings.is_empty() {
+            lines.push(sentence(&format!(
+                "Pronounced {}",
+                respellings.join(", or ")
+            )));
+        }
+    }
+
+    let senses = match verbosity {
+        Verbosity::Brief => &def.definitions[..def.definitions.len().min(1)],
+        _ => &def.definitions[..],
+    };
+    for (n, sense) in senses.iter().enumerate() {
+        let text = sentence(&sense.text);
+        lines.push(match verbosity {
+            Verbosity::Brief => text,
+            _ if sense.tags.is_empty() => format!("{}: {}", n + 1, text),
+            _ => format!("{}, {}: {}", n + 1, sense.tags.join(", "), text),
+        });
+        if !full {
+            continue;
+        }
+        if let Some(form_of) = &sense.form_of {
+            for target in &form_of.definitions {
+                lines.push(sentence(&format!("{}: {}", form_of.word, target.text)));
+            }
+        }
+        for example in &sense.examples {
+            lines.push(sentence(&format!("For example: {example}")));
+        }
+    }
+    if verbosity == Verbosity::Brief && def.definitions.len() > 1 {
+        let more = def.definitions.len() - 1;
+        lines.push(format!(
+            "{more} more {}.",
+            if more == 1 { "sense" } else { "senses" }
+        ));
+    }
+
+    if full {
+        if let Some(etymology) = &def.etymology {
+            lines.push(sentence(&format!("Etymology: {etymology}")));
+        }
+        if let Some(attribution) = &def.attribution {
+            lines.push(sentence(&format!(
+                "From {}, {}",
+                attribution.source, attribution.license
+            )));
+        }
+    }
+    lines.join("\n")
+}
+
+/// `text` on one line, ending in a full stop unless it already ends in
+/// punctuation
+fn sentence(text: &str) -> String {
+    let mut text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !text.ends_with(['.', '!', '?', '…']) {
+        text.push('.');
+    }
+    text
+}
+
+/// Split `text` into plain runs and linked mentions, in order
+///
+/// Links out of bounds, off character boundaries or overlapping an earlier
+/// one are ignored.
+fn link_segments<'a>(text: &'a str, links: &'a [Link]) -> Vec<(&'a str, Option<&'a Link>)> {
+    let mut segments = Vec::new();
+    let mut pos = 0;
+    for link in links {
+        let valid =
+            link.start >= pos && link.start < link.end && text.get(link.start..link.end).is_some();
+        if !valid {
+            continue;
+        }
+        if link.start > pos {
+            segments.push((&text[pos..link.start], None));
+        }
+        segments.push((&text[link.start..link.end], Some(link)));
+        pos = link.end;
+    }
+    if pos < text.len() {
+        segments.push((&text[pos..], None));
+    }
+    segments
+}
+
+/// The link target for `link`
+fn href(options: &ThemeOptions, link: &Link) -> String {
+    options.link_href.replace("{id}", &link.word_id.to_string())
+}
+
+/// A URL as a Markdown link destination
+///
+/// Percent-encodes what would end the destination early.
+fn markdown_destination(url: &str) -> String {
+    url.replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+        .replace('<', "%3C")
+        .replace('>', "%3E")
+}
+
+/// Escape text for HTML element content and quoted attribute values
+pub fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Escape text for inline Markdown, on a single line
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '\n' | '\r' => out.push(' '),
+            '\\' | '`' | '*' | '_' | '[' | ']' | '#' | '|' | '~' | '!' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{FormOf, Pronunciation, Translation};
+
+    fn sample() -> FullDefinition {
+        FullDefinition {
+            word: "cats".to_string(),
+            pos: "noun".to_string(),
+            language: "English".to_string(),
+            lang_code: "en".to_string(),
+            definitions: vec![
+                Definition {
+                    id: 1,
+                    text: "plural of cat".to_string(),
+                    examples: vec![],
+                    tags: vec![],
+                    form_of: Some(FormOf {
+                        word: "cat".to_string(),
+                        word_id: Some(7),
+                        definitions: vec![Definition {
+                            id: 2,
+                            text: "A small feline.".to_string(),
+                            examples: vec![],
+                            tags: vec![],
+                            form_of: None,
+                            links: vec![],
+                            source: None,
+                            sense_id: None,
+                        }],
+                    }),
+                    links: vec![],
+                    source: None,
+                    sense_id: None,
+                },
+                Definition {
+                    id: 3,
+                    text: "<script>alert(1)</script> & *jazz* fans".to_string(),
+                    examples: vec!["Hey, \"cats\"!".to_string()],
+                    tags: vec!["slang".to_string()],
+                    form_of: None,
+                    links: vec![],
+                    source: None,
+                    sense_id: None,
+                },
+            ],
+            pronunciations: vec![Pronunciation {
+                id: 1,
+                ipa: Some("/kæts/".to_string()),
+                audio_url: None,
+                accent: Some("US".to_string()),
+                respelling: None,
+            }],
+            etymology: None,
+            translations: vec![Translation {
+                id: 1,
+                target_language: "fr".to_string(),
+                translation: "chats".to_string(),
+            }],
+            attribution: None,
+            also_spelled: vec![],
+        }
+    }
+
+    #[test]
+    fn test_definition_html() {
+        let html = definition_html(&sample(), &ThemeOptions::default());
+        assert!(html.starts_with(
+            r#"<div class="dict-entry" lang="en"><h2><span class="dict-headword">cats</span>"#
+        ));
+        assert!(html.contains(
+            r#"<span class="dict-ipa">/kæts/</span> <span class="dict-accent">US</span>"#
+        ));
+        assert!(html.contains(r#"<span class="dict-headword">cat</span>: A small feline."#));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt; &amp; *jazz* fans"));
+        assert!(html.contains("<li>Hey, &quot;cats&quot;!</li>"));
+        assert!(html.contains("<dt>fr</dt><dd>chats</dd>"));
+        assert!(!html.contains("<script>"));
+
+        let options = ThemeOptions {
+            class_prefix: "x\"-".to_string(),
+            show_examples: false,
+            show_translations: false,
+            ..ThemeOptions::default()
+        };
+        let html = definition_html(&sample(), &options);
+        assert!(html.starts_with(r#"<div class="x&quot;-entry""#));
+        assert!(!html.contains("Hey"));
+        assert!(!html.contains("chats"));
+    }
+
+    #[test]
+    fn test_attribution() {
+        let mut def = sample();
+        def.attribution = Some(crate::models::Attribution {
+            source: "Wiktionary".to_string(),
+            license: "CC BY-SA 4.0".to_string(),
+            license_url: Some("https://creativecommons.org/licenses/by-sa/4.0/".to_string()),
+            url: Some("https://en.wiktionary.org/wiki/cats".to_string()),
+            revision: None,
+        });
+
+        let html = definition_html(&def, &ThemeOptions::default());
+        assert!(html.ends_with(concat!(
+            r#"<footer class="dict-attribution">From <a href="https://en.wiktionary.org/wiki/cats">Wiktionary</a>, "#,
+            r#"<a href="https://creativecommons.org/licenses/by-sa/4.0/">CC BY-SA 4.0</a></footer></div>"#
+        )));
+        let markdown = definition_markdown(&def, &ThemeOptions::default());
+        assert!(markdown.ends_with(
+            "*From [Wiktionary](https://en.wiktionary.org/wiki/cats), [CC BY-SA 4.0](https://creativecommons.org/licenses/by-sa/4.0/)*\n"
+        ));
+
+        let options = ThemeOptions {
+            show_attribution: false,
+            ..ThemeOptions::default()
+        };
+        assert!(!definition_html(&def, &options).contains("attribution"));
+    }
+
+    #[test]
+    fn test_links() {
+        let mut def = sample();
+        def.definitions.truncate(1);
+        def.definitions[0].form_of = None;
+        def.definitions[0].links = vec![Link {
+            start: 10,
+            end: 13,
+            text: "cat".to_string(),
+            word_id: 7,
+        }];
+
+        let html = definition_html(&def, &ThemeOptions::default());
+        assert!(html.contains(
+            r#"<span class="dict-gloss">plural of <a class="dict-link" href="dict://entry/7">cat</a></span>"#
+        ));
+        let options = ThemeOptions {
+            link_href: "app://x y/{id}".to_string(),
+            ..ThemeOptions::default()
+        };
+        let markdown = definition_markdown(&def, &options);
+        assert!(markdown.contains("1. plural of [cat](app://x%20y/7)\n"));
+
+        // Links that don't fit the text are dropped, not rendered broken
+        def.definitions[0].links[0].end = 99;
+        let html = definition_html(&def, &ThemeOptions::default());
+        assert!(html.contains(r#"<span class="dict-gloss">plural of cat</span>"#));
+    }
+
+    #[test]
+    fn test_definition_markdown() {
+        let markdown = definition_markdown(&sample(), &ThemeOptions::default());
+        assert_eq!(
+            markdown,
+            "## cats *noun* (English)\n\
+             \n\
+             /kæts/ (US)\n\
+             \n\
+             1. plural of cat\n   \
+             - **cat**: A small feline.\n\
+             2. *(slang)* &lt;script&gt;alert(1)&lt;/script&gt; &amp; \\*jazz\\* fans\n   \
+             > Hey, \"cats\"\\!\n\
+             \n\
+             **Translations:** fr: chats\n"
+        );
+    }
+
+    #[test]
+    fn test_respellings() {
+        let mut def = sample();
+        def.pronunciations[0].respelling = Some("KATS".to_string());
+        assert!(!definition_html(&def, &ThemeOptions::default()).contains("KATS"));
+
+        let options = ThemeOptions {
+            show_respellings: true,
+            ..ThemeOptions::default()
+        };
+        assert!(definition_html(&def, &options).contains(
+            r#"<span class="dict-ipa">/kæts/</span> <span class="dict-respelling">KATS</span>"#
+        ));
+        assert!(definition_markdown(&def, &options).contains("/kæts/ *KATS* (US)"));
+    }
+
+    #[test]
+    fn test_also_spelled() {
+        let mut def = sample();
+        def.also_spelled = vec!["catz".to_string()];
+        assert!(definition_html(&def, &ThemeOptions::default()).contains(
+            r#"<span class="dict-language">English</span> <span class="dict-also-spelled">also spelled catz</span></h2>"#
+        ));
+        assert!(definition_markdown(&def, &ThemeOptions::default())
+            .starts_with("## cats *noun* (English), also spelled catz\n"));
+        assert!(definition_plaintext(&def, Verbosity::Brief).contains("Also spelled catz."));
+    }
+
+    #[test]
+    fn test_definition_plaintext() {
+        let mut def = sample();
+        def.pronunciations[0].respelling = Some("KATS".to_string());
+        assert_eq!(
+            definition_plaintext(&def, Verbosity::Standard),
+            "cats, noun.\n\
+             1: plural of cat.\n\
+             2, slang: <script>alert(1)</script> & *jazz* fans."
+        );
+        assert_eq!(
+            definition_plaintext(&def, Verbosity::Brief),
+            "cats, noun.\nplural of cat.\n1 more sense."
+        );
+        let full = definition_plaintext(&def, Verbosity::Full);
+        assert!(full.starts_with("cats, noun.\nPronounced KATS.\n1: plural of cat.\n"));
+        assert!(full.contains("\ncat: A small feline.\n"));
+        assert!(full.contains("\nFor example: Hey, \"cats\"!"));
+        assert!(!full.contains("/kæts/"));
+    }
+}
@@ -4,17 +4,62 @@
 //! - Full-text search using SQLite FTS5
 //! - Prefix matching for autocomplete
 //! - Fuzzy/approximate string matching using Levenshtein distance
-
-use rusqlite::params;
-
-use crate::models::SearchResult;
-use crate::{DictHandle, Result};
-
-/// Maximum Levenshtein distance for fuzzy matches
-const MAX_FUZZY_DISTANCE: usize = 2;
-
-/// Minimum query length for fuzzy matching (to avoid too many false positives)
-const MIN_FUZZY_QUERY_LENGTH: usize = 3;
+//! - Compound splitting for German words without an entry of their own
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use rusqlite::{params, ErrorCode, OptionalExtension};
+
+use crate::config::{
+    ContentFilterMode, DatedSenseMode, OverlayPrecedence, RankingStrategy, SpellingRegion,
+    StopBehavior,
+};
+use crate::fuzzy::{LevenshteinAutomaton, Pattern};
+pub use crate::langid::detect_language;
+use crate::models::{
+    EntryOrigin, FederatedMode, FederatedResult, MatchKind, PreviewOptions, SearchOptions,
+    SearchPage, SearchResult,
+};
+#[cfg(feature = "tracing")]
+use crate::models::{SearchTimings, TierTiming, TracedSearch};
+use crate::telemetry::SearchEvent;
+use crate::text_distance::levenshtein_distance;
+use crate::{DictHandle, Error, Result};
+
+/// Minimum query length (in characters) before compound splitting is tried
+const MIN_COMPOUND_LENGTH: usize = 8;
+
+/// Minimum length of each part of a split compound
+const MIN_COMPOUND_PART_LENGTH: usize = 3;
+
+/// Linking elements (Fugenelemente) that may join German compound parts,
+/// as in Arbeit-s-zimmer or Sonne-n-schein
+const LINKING_ELEMENTS: &[&str] = &["s", "es", "n", "en", "er", "e", "ns"];
+
+/// Score boost for a federated result in the query's detected language,
+/// scaled by the detection probability
+const LANGUAGE_BOOST: f64 = 0.5;
+
+/// Headword keys looked up per query when loading fuzzy and pattern
+/// matches
+const KEYS_PER_QUERY: usize = 500;
+
+/// SQLite virtual machine steps between checks of a search's deadline
+const PROGRESS_STEPS: std::os::raw::c_int = 1000;
+
+/// Score offset that ranks user entries ahead of every shipped match
+/// with `OverlayPrecedence::UserFirst`
+const USER_FIRST_OFFSET: f64 = 100.0;
+
+/// User entries compared against a query for fuzzy matches; user
+/// databases are small, so they are scanned rather than indexed
+const USER_FUZZY_SCAN_LIMIT: u32 = 5000;
+
+/// How far a near match in `by_ipa` may be from the query, in
+/// `ipa::feature_distance` per segment of the query (at least 1 in all)
+const IPA_DISTANCE_PER_SEGMENT: f64 = 0.25;
 
 /// Search for words matching a query using FTS5
 ///
@@ -28,13 +73,345 @@ pub fn search_words(handle: &DictHandle, query: &str, limit: u32) -> Result<Vec<
 /// Search with offset for pagination.
 ///
 /// Fetches up to `limit` results starting at `offset` in the relevance-sorted list.
+/// The order is deterministic: results that rank the same are ordered by
+/// headword (case-insensitive, then as written), then by id, so repeated
+/// searches on the same database return the same list.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip(handle))
+)]
 pub fn search_words_offset(
     handle: &DictHandle,
     query: &str,
     limit: u32,
     offset: u32,
 ) -> Result<Vec<SearchResult>> {
+    search_reported(
+        handle,
+        query,
+        limit,
+        offset,
+        &handle.config.preview,
+        &mut TierTimer::default(),
+    )
+}
+
+/// `search_words_offset` with its own preview settings
+///
+/// Overrides the handle's `PreviewOptions` for this search only, e.g.
+/// for a compact list next to a full-width one.
+pub fn search_words_with_preview(
+    handle: &DictHandle,
+    query: &str,
+    limit: u32,
+    offset: u32,
+    preview: &PreviewOptions,
+) -> Result<Vec<SearchResult>> {
+    search_reported(
+        handle,
+        query,
+        limit,
+        offset,
+        preview,
+        &mut TierTimer::default(),
+    )
+}
+
+/// `search_words_offset` with per-search options
+///
+/// With a time budget, tiers after the exact-match ones are skipped once
+/// it is spent, and one still running then is stopped with what it found
+/// so far. The page is marked truncated, so a slow fuzzy pass on a large
+/// database does not hold up the caller.
+pub fn search_words_with_options(
+    handle: &DictHandle,
+    query: &str,
+    limit: u32,
+    offset: u32,
+    options: &SearchOptions,
+) -> Result<SearchPage> {
+    let mut timer = TierTimer {
+        deadline: options
+            .time_budget_ms
+            .map(|ms| Instant::now() + Duration::from_millis(ms)),
+        conn: Some(&handle.conn),
+        ..TierTimer::default()
+    };
+    let preview = options.preview.as_ref().unwrap_or(&handle.config.preview);
+    let results = search_reported(handle, query, limit, offset, preview, &mut timer)?;
+    Ok(SearchPage {
+        results,
+        truncated: timer.truncated,
+    })
+}
+
+/// `search_words_offset`, also reporting how long each tier took
+///
+/// Tiers that were skipped because earlier ones already filled the page
+/// are not listed.
+#[cfg(feature = "tracing")]
+#[tracing::instrument(level = "debug", skip(handle))]
+pub fn search_words_traced(
+    handle: &DictHandle,
+    query: &str,
+    limit: u32,
+    offset: u32,
+) -> Result<TracedSearch> {
+    let start = Instant::now();
+    let mut timer = TierTimer::default();
+    let results = search_reported(
+        handle,
+        query,
+        limit,
+        offset,
+        &handle.config.preview,
+        &mut timer,
+    )?;
+    Ok(TracedSearch {
+        results,
+        timings: SearchTimings {
+            total_micros: start.elapsed().as_micros() as u64,
+            tiers: timer.tiers,
+        },
+    })
+}
+
+/// `query` trimmed, with the handle's query replacements and confusables
+/// applied unless it is a symbol ("–" looks up the en dash itself)
+fn normalize_query<'a>(handle: &DictHandle, query: &'a str) -> Cow<'a, str> {
     let query = query.trim();
+    if crate::symbols::is_symbol(query) {
+        return Cow::Borrowed(query);
+    }
+    let query = crate::normalize::replace_chars(query, &handle.config.query_replacements);
+    if !handle.config.confusables {
+        return query;
+    }
+    match crate::normalize::confusables(&query) {
+        Cow::Owned(plain) => Cow::Owned(plain),
+        Cow::Borrowed(_) => query,
+    }
+}
+
+/// Run a search, reporting it to the registered telemetry sink if any
+fn search_reported(
+    handle: &DictHandle,
+    query: &str,
+    limit: u32,
+    offset: u32,
+    preview: &PreviewOptions,
+    timer: &mut TierTimer,
+) -> Result<Vec<SearchResult>> {
+    let Some(sink) = crate::telemetry::sink() else {
+        return search_tiers(handle, query, limit, offset, preview, timer).map_err(search_error);
+    };
+
+    let start = Instant::now();
+    timer.origins = Some(HashMap::new());
+    let results =
+        search_tiers(handle, query, limit, offset, preview, timer).map_err(search_error)?;
+    let tier = results
+        .first()
+        .zip(timer.origins.as_ref())
+        .and_then(|(top, origins)| origins.get(&(top.origin, top.id)).copied());
+    sink.record_search(&SearchEvent {
+        query_chars: query.trim().chars().count(),
+        tier,
+        latency: start.elapsed(),
+        result_count: results.len(),
+        offset,
+        query: sink.include_query_text().then(|| query.trim().to_string()),
+    });
+    Ok(results)
+}
+
+/// The error to report for a failed search, telling a damaged index, an
+/// interruption and a bad full-text query apart from other failures
+fn search_error(error: Error) -> Error {
+    let Error::Database(e) = &error else {
+        return error;
+    };
+    match e.sqlite_error_code() {
+        Some(ErrorCode::DatabaseCorrupt) => Error::FtsCorrupt(e.to_string()),
+        Some(ErrorCode::OperationInterrupted) => Error::Interrupted,
+        _ if e.to_string().starts_with("fts5: syntax error") => Error::InvalidQuery(e.to_string()),
+        _ => error,
+    }
+}
+
+/// Bookkeeping for the tiers of one search
+///
+/// With the `tracing` feature each tier gets a span and its time is kept
+/// for `search_words_traced`.
+#[derive(Default)]
+struct TierTimer<'a> {
+    /// Tier that first returned each entry, kept only for telemetry
+    origins: Option<HashMap<(EntryOrigin, i64), &'static str>>,
+    /// Time after which only exact-match tiers are run
+    deadline: Option<Instant>,
+    /// Connection whose statements are stopped at the deadline
+    conn: Option<&'a rusqlite::Connection>,
+    /// A tier was skipped or cut short because the deadline had passed
+    truncated: bool,
+    #[cfg(feature = "tracing")]
+    tiers: Vec<TierTiming>,
+}
+
+impl TierTimer<'_> {
+    fn run(
+        &mut self,
+        tier: &'static str,
+        search: impl FnOnce() -> Result<Vec<SearchResult>>,
+    ) -> Result<Vec<SearchResult>> {
+        let kind = tier_match_kind(tier);
+        let bounded = kind != MatchKind::Exact && self.deadline.is_some();
+        if bounded && self.past_deadline() {
+            self.truncated = true;
+            return Ok(Vec::new());
+        }
+        trace_span!("search_tier", tier);
+        #[cfg(feature = "tracing")]
+        let start = Instant::now();
+
+        // SQLite checks the handler every PROGRESS_STEPS virtual machine
+        // instructions and stops the statement once it returns true
+        let conn = self.conn.filter(|_| bounded);
+        if let (Some(conn), Some(deadline)) = (conn, self.deadline) {
+            conn.progress_handler(PROGRESS_STEPS, Some(move || Instant::now() >= deadline));
+        }
+        let results = search();
+        if let Some(conn) = conn {
+            conn.progress_handler(0, None::<fn() -> bool>);
+        }
+        let mut results = match results {
+            Err(Error::Database(e))
+                if bounded
+                    && e.sqlite_error_code() == Some(ErrorCode::OperationInterrupted)
+                    && self.past_deadline() =>
+            {
+                Vec::new()
+            }
+            results => results?,
+        };
+        if bounded && self.past_deadline() {
+            self.truncated = true;
+        }
+        for result in &mut results {
+            result.match_kind = kind;
+        }
+
+        #[cfg(feature = "tracing")]
+        {
+            let micros = start.elapsed().as_micros() as u64;
+            tracing::debug!(results = results.len(), micros, "search tier done");
+            self.tiers.push(TierTiming {
+                tier: tier.to_string(),
+                results: results.len(),
+                micros,
+            });
+        }
+        if let Some(origins) = &mut self.origins {
+            for result in &results {
+                origins.entry((result.origin, result.id)).or_insert(tier);
+            }
+        }
+        Ok(results)
+    }
+
+    fn past_deadline(&self) -> bool {
+        self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+}
+
+/// Order results by the handle's ranking strategy
+///
+/// Scores are banded by their integer part (exact and phonetic matches,
+/// prefix, full text, then fuzzy by distance) and the strategy orders each
+/// band, falling back to score. Ties are broken by `tie_break`, so the
+/// order never depends on the order tiers returned rows in.
+fn rank(handle: &DictHandle, results: &mut [SearchResult]) -> Result<()> {
+    let by_score = |a: &SearchResult, b: &SearchResult| {
+        a.score.total_cmp(&b.score).then_with(|| tie_break(a, b))
+    };
+    let band = |r: &SearchResult| r.score.floor() as i64;
+    match handle.config.ranking {
+        RankingStrategy::Relevance => results.sort_by(by_score),
+        RankingStrategy::Alphabetical => results.sort_by(|a, b| {
+            band(a)
+                .cmp(&band(b))
+                .then_with(|| a.word.to_lowercase().cmp(&b.word.to_lowercase()))
+                .then_with(|| by_score(a, b))
+        }),
+        RankingStrategy::Frequency => {
+            let mut frequencies = HashMap::new();
+            if crate::db::has_column(&handle.conn, "words", "frequency")? {
+                let mut stmt = handle
+                    .conn
+                    .prepare_cached("SELECT COALESCE(frequency, 0) FROM words WHERE id = ?")?;
+                for result in results.iter() {
+                    if result.origin == EntryOrigin::Dictionary {
+                        let frequency: i64 = stmt.query_row([result.id], |row| row.get(0))?;
+                        frequencies.insert(result.id, frequency);
+                    }
+                }
+            }
+            // User entries have no frequency
+            let frequency = |r: &SearchResult| match r.origin {
+                EntryOrigin::Dictionary => frequencies.get(&r.id).copied().unwrap_or(0),
+                EntryOrigin::User => 0,
+            };
+            results.sort_by(|a, b| {
+                band(a)
+                    .cmp(&band(b))
+                    .then_with(|| frequency(b).cmp(&frequency(a)))
+                    .then_with(|| by_score(a, b))
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Order of results that rank the same: by headword ignoring case, then
+/// as written, shipped entries before user entries, then by id
+fn tie_break(a: &SearchResult, b: &SearchResult) -> std::cmp::Ordering {
+    a.word
+        .to_lowercase()
+        .cmp(&b.word.to_lowercase())
+        .then_with(|| a.word.cmp(&b.word))
+        .then_with(|| (a.origin == EntryOrigin::User).cmp(&(b.origin == EntryOrigin::User)))
+        .then_with(|| a.id.cmp(&b.id))
+}
+
+/// The match kind of results from a search tier
+fn tier_match_kind(tier: &str) -> MatchKind {
+    match tier {
+        "exact" | "symbols" | "lemma" | "user_exact" => MatchKind::Exact,
+        "jamo" | "prefix" | "user_prefix" => MatchKind::Prefix,
+        "pinyin" | "kana" => MatchKind::Phonetic,
+        "fuzzy" | "user_fuzzy" => MatchKind::Fuzzy,
+        _ => MatchKind::FullText,
+    }
+}
+
+/// Map a score (lower is better, from 0) to a relevance in 0-1
+///
+/// Monotonic, so sorting by relevance matches sorting by score: exact
+/// matches get 1.0, prefix matches about 0.5, full-text matches about 0.3
+/// and fuzzy matches 0.25 or less.
+pub(crate) fn relevance(score: f64) -> f64 {
+    1.0 / (1.0 + score.max(0.0))
+}
+
+fn search_tiers(
+    handle: &DictHandle,
+    query: &str,
+    limit: u32,
+    offset: u32,
+    preview: &PreviewOptions,
+    timer: &mut TierTimer,
+) -> Result<Vec<SearchResult>> {
+    let query = normalize_query(handle, query);
+    let query = query.as_ref();
     if query.is_empty() {
         return Ok(Vec::new());
     }
@@ -42,6 +419,16 @@ pub fn search_words_offset(
     // We need to gather enough results to satisfy offset + limit
     let total_needed = offset.saturating_add(limit);
 
+    // How many results the next tier should look for, None to skip it
+    let tiers = &handle.config.tiers;
+    let tier_limit = |results: &Vec<SearchResult>| match tiers.stop {
+        StopBehavior::WhenFull => {
+            let found = results.len() as u32;
+            (found < total_needed).then(|| total_needed - found)
+        }
+        StopBehavior::RunAll => Some(tiers.candidate_budget.max(total_needed)),
+    };
+
     // Normalize query for comparison
     let query_lower = query.to_lowercase();
 
@@ -51,17 +438,58 @@ pub fn search_words_offset(
     // First try exact match, then prefix match, then FTS match
     let mut results = Vec::new();
 
-    // 1. Exact matches (highest priority, score = 0)
-    let exact_results = search_exact(handle, query, total_needed)?;
-    for mut result in exact_results {
-        result.score = 0.0;
-        results.push(result);
+    // 1. Exact matches (highest priority): score 0 as written, 0.1 when
+    //    only case or diacritics differ
+    results.extend(timer.run("exact", || search_exact(handle, query, total_needed))?);
+
+    // Symbols, in either emoji or text presentation (score = 0)
+    for result in timer.run("symbols", || search_symbols(handle, query, total_needed))? {
+        if !results.iter().any(|r| r.id == result.id) {
+            results.push(result);
+        }
     }
 
-    if (results.len() as u32) < total_needed {
-        // 2. Prefix matches (score based on length difference)
-        let remaining = total_needed - results.len() as u32;
-        let prefix_results = search_prefix(handle, query, remaining)?;
+    if results.is_empty() {
+        // Lemmas of an inflected query ("went" finds "go"), score 0.7
+        for result in timer.run("lemma", || search_lemmas(handle, query, total_needed))? {
+            if !results.iter().any(|r| r.id == result.id) {
+                results.push(result);
+            }
+        }
+    }
+
+    if let Some(remaining) = tier_limit(&results) {
+        // 2. Pinyin matches: same tones first (score 0.5), then toneless (0.8)
+        for result in timer.run("pinyin", || search_pinyin(handle, query, remaining))? {
+            if !results.iter().any(|r| r.id == result.id) {
+                results.push(result);
+            }
+        }
+    }
+
+    #[cfg(feature = "kana")]
+    if let Some(remaining) = tier_limit(&results) {
+        // Kana reading matches for kana or romaji queries (score 0.6)
+        for result in timer.run("kana", || search_kana(handle, query, remaining))? {
+            if !results.iter().any(|r| r.id == result.id) {
+                results.push(result);
+            }
+        }
+    }
+
+    if let Some(remaining) = tier_limit(&results) {
+        // 3. Prefix matches, by jamo for Hangul so partially typed
+        //    syllables match (same scoring as plain prefix matches)
+        for result in timer.run("jamo", || search_jamo_prefix(handle, query, remaining))? {
+            if !results.iter().any(|r| r.id == result.id) {
+                results.push(result);
+            }
+        }
+    }
+
+    if let Some(remaining) = tier_limit(&results) {
+        // Plain prefix matches (score based on length difference)
+        let prefix_results = timer.run("prefix", || search_prefix(handle, query, remaining))?;
 
         // Add only results not already in the list
         for mut result in prefix_results {
@@ -74,10 +502,14 @@ pub fn search_words_offset(
         }
     }
 
-    if (results.len() as u32) < total_needed {
-        // 3. FTS matches (score from FTS5 rank)
-        let remaining = total_needed - results.len() as u32;
-        let fts_results = search_fts(handle, &fts_query, remaining)?;
+    if let Some(remaining) = tier_limit(&results) {
+        // 4. FTS matches (score from FTS5 rank), or substring matches where
+        //    SQLite lacks FTS5
+        let fts_results = if handle.capabilities.fts5 {
+            timer.run("fts", || search_fts(handle, &fts_query, remaining))?
+        } else {
+            timer.run("substring", || search_substring(handle, query, remaining))?
+        };
 
         for mut result in fts_results {
             if !results.iter().any(|r| r.id == result.id) {
@@ -88,10 +520,15 @@ pub fn search_words_offset(
         }
     }
 
-    // 4. Fuzzy matches (only if query is long enough and we need more results)
-    if (results.len() as u32) < total_needed && query_lower.len() >= MIN_FUZZY_QUERY_LENGTH {
-        let remaining = total_needed - results.len() as u32;
-        let fuzzy_results = search_fuzzy(handle, &query_lower, remaining)?;
+    // 5. Fuzzy matches (only if query is long enough and we need more results)
+    let fuzzy = &handle.config.fuzzy;
+    let fuzzy_limit = tier_limit(&results)
+        .filter(|_| fuzzy.enabled && query_lower.len() >= fuzzy.min_query_length);
+    if let Some(remaining) = fuzzy_limit {
+        let deadline = timer.deadline;
+        let fuzzy_results = timer.run("fuzzy", || {
+            search_fuzzy(handle, &query_lower, remaining, deadline)
+        })?;
 
         for result in fuzzy_results {
             if !results.iter().any(|r| r.id == result.id) {
@@ -100,40 +537,792 @@ pub fn search_words_offset(
         }
     }
 
-    // Sort by score (lower is better)
-    results.sort_by(|a, b| {
-        a.score
-            .partial_cmp(&b.score)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
+    // 6. German compound parts, only when nothing else matched
+    if results.is_empty() && query.chars().count() >= MIN_COMPOUND_LENGTH {
+        results = timer.run("compound", || {
+            search_compound_parts(handle, query, total_needed)
+        })?;
+    }
+
+    // 7. Entries of the user database, placed by the overlay precedence
+    if handle.config.overlay.precedence != OverlayPrecedence::Off
+        && crate::db::user_table_exists(&handle.conn, "words")?
+    {
+        let user_results = search_user(handle, query, total_needed, timer)?;
+        overlay_user_results(handle, &mut results, user_results);
+    }
+
+    // 8. Regional spellings swapped for the reader's, once per entry
+    if let Some(region) = handle.config.spelling {
+        apply_spelling(handle, &mut results, region)?;
+    }
+
+    // 9. Offensive entries hidden or flagged, as configured, by the entries
+    //    actually shown. Hidden entries are not replaced, so a page can
+    //    come back short.
+    if handle.config.content_filter.is_active() {
+        apply_content_filter(handle, &mut results)?;
+    }
+
+    rank(handle, &mut results)?;
 
     // Apply offset and limit
     let start = std::cmp::min(offset as usize, results.len());
     let end = std::cmp::min(start + limit as usize, results.len());
-    let results = results[start..end].to_vec();
+    let mut results = results[start..end].to_vec();
+    fill_details(handle, &mut results, preview)?;
+
+    Ok(results)
+}
 
+/// Hide or flag results matching the handle's content filter
+fn apply_content_filter(handle: &DictHandle, results: &mut Vec<SearchResult>) -> Result<()> {
+    let ids: Vec<i64> = results
+        .iter()
+        .filter(|r| r.origin == EntryOrigin::Dictionary)
+        .map(|r| r.id)
+        .collect();
+    let matched = crate::db::filtered_words(handle, &ids)?;
+    let is_match =
+        |r: &SearchResult| r.origin == EntryOrigin::Dictionary && matched.contains(&r.id);
+    match handle.config.content_filter.mode {
+        ContentFilterMode::Hide => results.retain(|r| !is_match(r)),
+        ContentFilterMode::Flag => {
+            for result in results.iter_mut() {
+                result.flagged = is_match(result);
+            }
+        }
+        ContentFilterMode::Off => {}
+    }
+    Ok(())
+}
+
+/// Show results in the reader's spelling ("color" for "colour"), keeping
+/// the better score where both spellings matched
+fn apply_spelling(
+    handle: &DictHandle,
+    results: &mut Vec<SearchResult>,
+    region: SpellingRegion,
+) -> Result<()> {
+    for result in results.iter_mut() {
+        if result.origin != EntryOrigin::Dictionary {
+            continue;
+        }
+        if let Some((id, word, pos)) = crate::spelling::preferred_entry(handle, result.id, region)?
+        {
+            result.id = id;
+            result.word = word;
+            result.pos = pos;
+        }
+    }
+    let mut kept: Vec<SearchResult> = Vec::with_capacity(results.len());
+    for result in results.drain(..) {
+        match kept
+            .iter_mut()
+            .find(|k| k.id == result.id && k.origin == result.origin)
+        {
+            Some(existing) if result.score < existing.score => *existing = result,
+            Some(_) => {}
+            None => kept.push(result),
+        }
+    }
+    *results = kept;
+    Ok(())
+}
+
+/// Complete a typed prefix to headwords
+///
+/// Returns up to `n` distinct headwords starting with `prefix`
+/// (case-insensitive), most frequent first, then alphabetically, so
+/// shorter words come before their extensions. Only the words are
+/// returned: no definitions are read, which keeps this fast enough to run
+/// on every keystroke. With a headword FST the candidates are read from
+/// it, matching the prefix against headword keys as fuzzy search does.
+pub fn complete(handle: &DictHandle, prefix: &str, n: u32) -> Result<Vec<String>> {
+    let key = prefix.trim_start().to_lowercase();
+    if key.is_empty() || n == 0 {
+        return Ok(Vec::new());
+    }
+    let upper = format!("{}\u{10FFFF}", key);
+
+    if let Some(index) = handle.headword_fst()? {
+        return complete_from_fst(handle, &index, prefix.trim_start(), n);
+    }
+
+    // Read-only databases built before the completions table fall back to
+    // the (case-sensitive) headword index
+    if !crate::db::table_exists(&handle.conn, "completions")? {
+        let mut stmt = handle.conn.prepare_cached(
+            "SELECT DISTINCT word FROM words WHERE word >= ? AND word < ? ORDER BY word LIMIT ?",
+        )?;
+        let rows = stmt.query_map(params![key, upper, n], |row| row.get(0))?;
+        return Ok(rows.collect::<std::result::Result<Vec<String>, _>>()?);
+    }
+
+    // The completions under the first one or two characters are indexed
+    // by weight, so a short prefix reads its best `n` rows instead of
+    // ranking every key it starts
+    let head_chars = key.chars().count().min(2);
+    let head: String = key.chars().take(head_chars).collect();
+    let mut stmt = handle.conn.prepare_cached(&format!(
+        r#"
+        SELECT word FROM completions
+        WHERE substr(key, 1, {head_chars}) = ? AND key >= ? AND key < ?
+        ORDER BY weight DESC, key, word
+        LIMIT ?
+        "#
+    ))?;
+    let rows = stmt.query_map(params![head, key, upper, n], |row| row.get(0))?;
+    Ok(rows.collect::<std::result::Result<Vec<String>, _>>()?)
+}
+
+/// `complete` reading the candidates from the headword FST, which ranks
+/// keys by the highest frequency of their headwords
+fn complete_from_fst(
+    handle: &DictHandle,
+    index: &crate::fuzzy::HeadwordIndex,
+    prefix: &str,
+    n: u32,
+) -> Result<Vec<String>> {
+    let keys = index.top_prefixed(
+        &handle.normalization.key(prefix),
+        n as usize,
+        |_, weight| std::cmp::Reverse(weight),
+    );
+    if keys.is_empty() {
+        return Ok(Vec::new());
+    }
+    let column = crate::fuzzy::key_column(&handle.conn)?;
+    let frequency = if crate::db::has_column(&handle.conn, "words", "frequency")? {
+        "MAX(COALESCE(frequency, 0))"
+    } else {
+        "0"
+    };
+    let mut stmt = handle.conn.prepare(&format!(
+        "SELECT word, {column}, {frequency} FROM words WHERE {column} IN ({}) GROUP BY word",
+        vec!["?"; keys.len()].join(",")
+    ))?;
+    let position: HashMap<&str, usize> = keys
+        .iter()
+        .enumerate()
+        .map(|(i, (key, _))| (key.as_str(), i))
+        .collect();
+    let rows = stmt.query_map(
+        rusqlite::params_from_iter(keys.iter().map(|(key, _)| key)),
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        },
+    )?;
+    let mut words = Vec::new();
+    for row in rows {
+        let (word, key, weight) = row?;
+        words.push((position[key.as_str()], std::cmp::Reverse(weight), word));
+    }
+    words.sort_unstable();
+    words.truncate(n as usize);
+    Ok(words.into_iter().map(|(_, _, word)| word).collect())
+}
+
+/// Search several dictionaries at once
+///
+/// Each dictionary is searched with `search_words` and the results are
+/// merged by score, tagged with the index of the dictionary they came
+/// from. Entries in the language `detect_language` guesses for the query
+/// are boosted by up to `LANGUAGE_BOOST`, so "chat" lists the French word
+/// ahead of the English one when the query looks French, while an exact
+/// match still beats a prefix match in the other language.
+pub fn search_federated(
+    handles: &[&DictHandle],
+    query: &str,
+    limit: u32,
+) -> Result<Vec<FederatedResult>> {
+    search_federated_with_mode(handles, query, limit, FederatedMode::Separate)
+}
+
+/// Search several dictionaries at once, optionally grouping an entry
+/// found in more than one
+///
+/// Like [`search_federated`]; with `FederatedMode::Grouped` results with
+/// the same headword, part of speech and language code are merged into
+/// the best ranked one, which lists every dictionary in `sources`.
+pub fn search_federated_with_mode(
+    handles: &[&DictHandle],
+    query: &str,
+    limit: u32,
+    mode: FederatedMode,
+) -> Result<Vec<FederatedResult>> {
+    let guesses = detect_language(query);
+
+    let mut merged = Vec::new();
+    for (source, handle) in handles.iter().enumerate() {
+        for mut result in search_words(handle, query, limit)? {
+            // User overlay ids belong to the user database
+            let table = match result.origin {
+                EntryOrigin::Dictionary => "words",
+                EntryOrigin::User => "user.words",
+            };
+            let code: String = handle
+                .conn
+                .prepare_cached(&format!("SELECT lang_code FROM {} WHERE id = ?", table))?
+                .query_row([result.id], |row| row.get(0))?;
+            if let Some(guess) = guesses.iter().find(|g| g.code == code) {
+                result.score -= LANGUAGE_BOOST * guess.probability;
+                result.relevance = relevance(result.score);
+            }
+            merged.push(FederatedResult {
+                source,
+                sources: vec![source],
+                lang_code: code,
+                result,
+            });
+        }
+    }
+
+    // Stable, so ties keep dictionary order
+    merged.sort_by(|a, b| a.result.score.total_cmp(&b.result.score));
+    if mode == FederatedMode::Grouped {
+        let mut groups: HashMap<(String, String, String), usize> = HashMap::new();
+        let mut grouped: Vec<FederatedResult> = Vec::with_capacity(merged.len());
+        for result in merged {
+            let key = (
+                result.result.word.clone(),
+                result.result.pos.clone(),
+                result.lang_code.clone(),
+            );
+            match groups.get(&key) {
+                Some(&i) => {
+                    if !grouped[i].sources.contains(&result.source) {
+                        grouped[i].sources.push(result.source);
+                    }
+                }
+                None => {
+                    groups.insert(key, grouped.len());
+                    grouped.push(result);
+                }
+            }
+        }
+        merged = grouped;
+    }
+    merged.truncate(limit as usize);
+    Ok(merged)
+}
+
+/// A single matching strategy, for callers that need one tier of the
+/// ranked search on its own (e.g. the DICT protocol's MATCH command)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchStrategy {
+    /// Headword equals the query
+    Exact,
+    /// Headword starts with the query
+    Prefix,
+    /// Headword is within a small Levenshtein distance of the query
+    Levenshtein,
+    /// Headword matches the query as a pattern: `?` is any one character
+    /// and `*` any run of characters
+    Pattern,
+}
+
+/// Find words using a single matching strategy
+///
+/// Unlike `search_words`, results from other tiers are not mixed in.
+pub fn match_words(
+    handle: &DictHandle,
+    query: &str,
+    strategy: MatchStrategy,
+    limit: u32,
+) -> Result<Vec<SearchResult>> {
+    let query = normalize_query(handle, query);
+    let query = query.as_ref();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let (mut results, kind) = match strategy {
+        MatchStrategy::Exact => (search_exact(handle, query, limit)?, MatchKind::Exact),
+        MatchStrategy::Prefix => (search_prefix(handle, query, limit)?, MatchKind::Prefix),
+        MatchStrategy::Levenshtein => (
+            search_fuzzy(handle, &query.to_lowercase(), limit, None)?,
+            MatchKind::Fuzzy,
+        ),
+        MatchStrategy::Pattern => (search_pattern(handle, query, limit)?, MatchKind::Pattern),
+    };
+    for result in &mut results {
+        result.match_kind = kind;
+    }
+    fill_details(handle, &mut results, &handle.config.preview)?;
+    Ok(results)
+}
+
+/// Find words that rhyme with `word`
+///
+/// Two words rhyme when one of their pronunciations shares a rhyme key
+/// (see `ipa::rhyme_key`): the sounds from the stressed vowel onward.
+/// Only words in the same language are returned. Databases built before
+/// rhyme keys existed return no results when opened read-only.
+pub fn find_rhymes(handle: &DictHandle, word: &str, limit: u32) -> Result<Vec<SearchResult>> {
+    if !crate::db::has_column(&handle.conn, "pronunciations", "rhyme")? {
+        return Ok(Vec::new());
+    }
+
+    let mut stmt = handle.conn.prepare(
+        r#"
+        WITH keys AS (
+            SELECT DISTINCT p.rhyme, w.language
+            FROM pronunciations p
+            JOIN words w ON w.id = p.word_id
+            WHERE w.word = ? AND p.rhyme IS NOT NULL
+        )
+        SELECT w.id, w.word, w.pos,
+               COALESCE((SELECT definition FROM definitions WHERE word_id = w.id LIMIT 1), '')
+        FROM words w
+        WHERE w.word != ?1
+          AND EXISTS (
+            SELECT 1 FROM pronunciations p
+            JOIN keys k ON k.rhyme = p.rhyme AND k.language = w.language
+            WHERE p.word_id = w.id
+          )
+        ORDER BY w.word, w.id
+        LIMIT ?2
+        "#,
+    )?;
+
+    let rows = stmt.query_map(params![word, limit], row_to_search_result)?;
+    let mut results = rows.collect::<std::result::Result<Vec<_>, _>>()?;
+    for result in &mut results {
+        result.match_kind = MatchKind::Phonetic;
+    }
+    fill_details(handle, &mut results, &handle.config.preview)?;
+    Ok(results)
+}
+
+/// Find words by pronunciation, from an IPA transcription such as
+/// "/ˈniːmɒnɪk/"
+///
+/// Transcriptions are compared by `ipa::search_key`, so enclosing slashes,
+/// stress marks, syllable breaks and notation variants (`ʧ` for `t͡ʃ`,
+/// `:` for `ː`) do not matter. With `fuzzy`, transcriptions of similar
+/// length within a small `ipa::feature_distance` of the query also match,
+/// scored by that distance, so "/ˈnimɒnɪk/" still finds "mnemonic".
+/// Results are best first, once per entry, with `ipa` set to the
+/// transcription that matched. Databases built before IPA keys existed
+/// return no results when opened read-only.
+pub fn by_ipa(
+    handle: &DictHandle,
+    ipa_query: &str,
+    fuzzy: bool,
+    limit: u32,
+) -> Result<Vec<SearchResult>> {
+    if !crate::db::has_column(&handle.conn, "pronunciations", "ipa_key")? {
+        return Ok(Vec::new());
+    }
+    let Some(key) = crate::ipa::search_key(ipa_query) else {
+        return Ok(Vec::new());
+    };
+
+    // Near matches are at most this many segments longer or shorter, and
+    // a segment is at most a few characters
+    let max_distance = (key.chars().count() as f64 * IPA_DISTANCE_PER_SEGMENT).max(1.0);
+    let slack = if fuzzy {
+        max_distance.floor() as i64 * 3
+    } else {
+        0
+    };
+    let length = key.chars().count() as i64;
+    // Exact lookups go through the index; near matches scan
+    let (shortest, longest) = (length - slack, length + slack);
+    let (condition, values): (&str, Vec<&dyn rusqlite::ToSql>) = if fuzzy {
+        (
+            "length(p.ipa_key) BETWEEN ?1 AND ?2",
+            vec![&shortest, &longest],
+        )
+    } else {
+        ("p.ipa_key = ?1", vec![&key])
+    };
+    let mut stmt = handle.conn.prepare_cached(&format!(
+        r#"
+        SELECT w.id, w.word, w.pos,
+               COALESCE((SELECT definition FROM definitions WHERE word_id = w.id LIMIT 1), ''),
+               p.ipa, p.ipa_key
+        FROM pronunciations p
+        JOIN words w ON w.id = p.word_id
+        WHERE {condition}
+        "#
+    ))?;
+    let rows = stmt.query_map(values.as_slice(), |row| {
+        let mut result = row_to_search_result(row)?;
+        result.ipa = row.get(4)?;
+        Ok((result, row.get::<_, String>(5)?))
+    })?;
+
+    let mut results: Vec<SearchResult> = Vec::new();
+    for row in rows {
+        let (mut result, ipa_key) = row?;
+        result.score = if ipa_key == key {
+            0.0
+        } else {
+            crate::ipa::feature_distance(&key, &ipa_key)
+        };
+        if result.score > max_distance {
+            continue;
+        }
+        result.match_kind = if result.score == 0.0 {
+            MatchKind::Exact
+        } else {
+            MatchKind::Phonetic
+        };
+        match results.iter_mut().find(|r| r.id == result.id) {
+            Some(existing) if result.score < existing.score => *existing = result,
+            Some(_) => {}
+            None => results.push(result),
+        }
+    }
+    results.sort_by(|a, b| a.score.total_cmp(&b.score).then_with(|| tie_break(a, b)));
+    results.truncate(limit as usize);
+
+    // `fill_details` puts the preferred accent's IPA in place of the one
+    // that matched
+    let matched: Vec<Option<String>> = results.iter().map(|r| r.ipa.clone()).collect();
+    fill_details(handle, &mut results, &handle.config.preview)?;
+    for (result, ipa) in results.iter_mut().zip(matched) {
+        result.ipa = ipa;
+    }
+    Ok(results)
+}
+
+/// Split a German compound into dictionary words
+///
+/// Greedily takes the longest leading German headword (allowing a linking
+/// element such as the "s" in Arbeitszimmer after it) and splits the rest
+/// the same way, backtracking to shorter parts when the rest cannot be
+/// split. Returns the parts as they are written in the dictionary, e.g.
+/// "Donaudampfschiff" gives `["Donau", "Dampfschiff"]`, or `None` if the
+/// word cannot be split into at least two parts.
+pub fn split_compound(handle: &DictHandle, word: &str) -> Result<Option<Vec<String>>> {
+    let chars: Vec<char> = word.trim().to_lowercase().chars().collect();
+    if chars.len() < 2 * MIN_COMPOUND_PART_LENGTH {
+        return Ok(None);
+    }
+    let mut stmt = handle.conn.prepare_cached(
+        "SELECT word FROM words WHERE word IN (?, ?) AND language = 'German' LIMIT 1",
+    )?;
+    let mut lookup = |part: &[char]| -> Result<Option<String>> {
+        let lower: String = part.iter().collect();
+        let capitalized: String = part[0]
+            .to_uppercase()
+            .chain(part[1..].iter().copied())
+            .collect();
+        match stmt.query_row(params![lower, capitalized], |row| row.get(0)) {
+            Ok(word) => Ok(Some(word)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    };
+
+    let mut parts = Vec::new();
+    let mut failed = HashSet::new();
+    if split_from(&chars, &mut lookup, &mut parts, &mut failed)? && parts.len() >= 2 {
+        Ok(Some(parts))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Split `chars` entirely into dictionary words, appending them to `parts`
+///
+/// `failed` records the lengths of suffixes already known not to split,
+/// so backtracking stays polynomial.
+fn split_from(
+    chars: &[char],
+    lookup: &mut impl FnMut(&[char]) -> Result<Option<String>>,
+    parts: &mut Vec<String>,
+    failed: &mut HashSet<usize>,
+) -> Result<bool> {
+    if chars.is_empty() {
+        return Ok(true);
+    }
+    if failed.contains(&chars.len()) {
+        return Ok(false);
+    }
+    for end in (MIN_COMPOUND_PART_LENGTH..=chars.len()).rev() {
+        let rest = &chars[end..];
+        if !rest.is_empty() && rest.len() < MIN_COMPOUND_PART_LENGTH {
+            continue;
+        }
+        let Some(part) = lookup(&chars[..end])? else {
+            continue;
+        };
+        parts.push(part);
+
+        // Try the rest as is, then after each linking element
+        let mut rests = vec![rest];
+        for linking in LINKING_ELEMENTS {
+            let linking: Vec<char> = linking.chars().collect();
+            if rest.len() >= linking.len() + MIN_COMPOUND_PART_LENGTH && rest.starts_with(&linking)
+            {
+                rests.push(&rest[linking.len()..]);
+            }
+        }
+        for rest in rests {
+            if split_from(rest, lookup, parts, failed)? {
+                return Ok(true);
+            }
+        }
+        parts.pop();
+    }
+    failed.insert(chars.len());
+    Ok(false)
+}
+
+/// Entries for the parts of a German compound query, marked with
+/// `compound_of` and scored after all other tiers in part order
+fn search_compound_parts(
+    handle: &DictHandle,
+    query: &str,
+    limit: u32,
+) -> Result<Vec<SearchResult>> {
+    let Some(parts) = split_compound(handle, query)? else {
+        return Ok(Vec::new());
+    };
+
+    // Only the German entries the split was made from, not other
+    // languages' words spelled the same
+    let mut stmt = handle.conn.prepare_cached(
+        r#"
+        SELECT w.id, w.word, w.pos,
+               COALESCE((SELECT definition FROM definitions WHERE word_id = w.id LIMIT 1), '')
+        FROM words w
+        WHERE w.word = ? AND w.language = 'German'
+        ORDER BY w.id
+        LIMIT ?
+        "#,
+    )?;
+
+    let mut results: Vec<SearchResult> = Vec::new();
+    for (i, part) in parts.iter().enumerate() {
+        for result in stmt.query_map(params![part, limit], row_to_search_result)? {
+            let mut result = result?;
+            if results.len() as u32 >= limit || results.iter().any(|r| r.id == result.id) {
+                continue;
+            }
+            result.score = 6.0 + i as f64 * 0.1;
+            result.compound_of = Some(query.to_string());
+            results.push(result);
+        }
+    }
+    Ok(results)
+}
+
+/// Entries for the lemmas of an inflected query, in the lemma's language
+fn search_lemmas(handle: &DictHandle, query: &str, limit: u32) -> Result<Vec<SearchResult>> {
+    let mut stmt = handle.conn.prepare_cached(
+        r#"
+        SELECT w.id, w.word, w.pos,
+               COALESCE((SELECT definition FROM definitions WHERE word_id = w.id LIMIT 1), '')
+        FROM words w
+        WHERE w.word = ? AND w.lang_code = ?
+        LIMIT ?
+        "#,
+    )?;
+
+    let mut results = Vec::new();
+    for (lemma, lang) in crate::morph::lemmatize_any(handle, query)? {
+        let rows = stmt.query_map(params![lemma, lang, limit], row_to_search_result)?;
+        for result in rows {
+            let mut result = result?;
+            result.score = 0.7;
+            results.push(result);
+        }
+    }
+    results.truncate(limit as usize);
     Ok(results)
 }
 
 /// Search for exact word matches
+///
+/// Headwords equal to the query ignoring case and, unless the dictionary
+/// keeps them apart, diacritics (by their normalized key) match too, so
+/// "polish" finds "Polish" as well; those
+/// written exactly as typed score 0 and the others 0.1, after them.
 fn search_exact(handle: &DictHandle, word: &str, limit: u32) -> Result<Vec<SearchResult>> {
+    // Databases built before search keys only match as written
+    let key_match = if crate::db::has_column(&handle.conn, "words", "search_key")? {
+        "OR w.search_key = ?2"
+    } else {
+        ""
+    };
+    let mut stmt = handle.conn.prepare_cached(&format!(
+        r#"
+        SELECT w.id, w.word, w.pos,
+               COALESCE((SELECT definition FROM definitions WHERE word_id = w.id LIMIT 1), ''),
+               w.word = ?1 AS verbatim
+        FROM words w
+        WHERE w.word = ?1 {key_match}
+        ORDER BY verbatim DESC, w.id
+        LIMIT ?3
+        "#
+    ))?;
+
+    let key = handle.normalization.key(word);
+    let rows = stmt.query_map(params![word, key, limit], |row| {
+        let mut result = row_to_search_result(row)?;
+        result.score = if row.get(4)? { 0.0 } else { 0.1 };
+        Ok(result)
+    })?;
+    rows.collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| e.into())
+}
+
+/// Search emoji, punctuation and signs through the symbols table
+fn search_symbols(handle: &DictHandle, query: &str, limit: u32) -> Result<Vec<SearchResult>> {
+    if !crate::symbols::is_symbol(query) || !crate::db::table_exists(&handle.conn, "symbols")? {
+        return Ok(Vec::new());
+    }
+
     let mut stmt = handle.conn.prepare(
         r#"
-        SELECT w.id, w.word, w.pos, 
+        SELECT DISTINCT w.id, w.word, w.pos,
                COALESCE((SELECT definition FROM definitions WHERE word_id = w.id LIMIT 1), '')
-        FROM words w
-        WHERE w.word = ?
+        FROM symbols s
+        JOIN words w ON w.id = s.word_id
+        WHERE s.symbol = ?
+        ORDER BY w.id
         LIMIT ?
         "#,
     )?;
 
-    let rows = stmt.query_map(params![word, limit], row_to_search_result)?;
+    let rows = stmt.query_map(
+        params![crate::symbols::normalize(query), limit],
+        row_to_search_result,
+    )?;
+    rows.collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| e.into())
+}
+
+/// Search Chinese words by pinyin reading
+///
+/// The query may use tone marks or tone numbers. Readings whose tones
+/// match the query's score 0.5; readings that only match without tones,
+/// or any match for a query without tones, score 0.8.
+fn search_pinyin(handle: &DictHandle, query: &str, limit: u32) -> Result<Vec<SearchResult>> {
+    let Some(pinyin) = crate::pinyin::parse(query) else {
+        return Ok(Vec::new());
+    };
+    if !crate::db::table_exists(&handle.conn, "pinyin")? {
+        return Ok(Vec::new());
+    }
+
+    let mut stmt = handle.conn.prepare(
+        r#"
+        SELECT w.id, w.word, w.pos,
+               COALESCE((SELECT definition FROM definitions WHERE word_id = w.id LIMIT 1), ''),
+               MAX(?2 != '' AND p.tones = ?2) AS toned
+        FROM pinyin p
+        JOIN words w ON w.id = p.word_id
+        WHERE p.letters = ?1
+        GROUP BY w.id
+        ORDER BY toned DESC, w.id
+        LIMIT ?3
+        "#,
+    )?;
+
+    let rows = stmt.query_map(params![pinyin.letters, pinyin.tones, limit], |row| {
+        let mut result = row_to_search_result(row)?;
+        result.score = if row.get::<_, bool>(4)? { 0.5 } else { 0.8 };
+        Ok(result)
+    })?;
+    rows.collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| e.into())
+}
+
+/// Search Japanese words by kana reading
+///
+/// Kana queries are folded to hiragana and romaji queries converted to
+/// hiragana before matching. Matches score 0.6.
+#[cfg(feature = "kana")]
+fn search_kana(handle: &DictHandle, query: &str, limit: u32) -> Result<Vec<SearchResult>> {
+    let Some(reading) = crate::kana::query_reading(query) else {
+        return Ok(Vec::new());
+    };
+    if !crate::db::table_exists(&handle.conn, "kana")? {
+        return Ok(Vec::new());
+    }
+
+    let mut stmt = handle.conn.prepare(
+        r#"
+        SELECT DISTINCT w.id, w.word, w.pos,
+               COALESCE((SELECT definition FROM definitions WHERE word_id = w.id LIMIT 1), '')
+        FROM kana k
+        JOIN words w ON w.id = k.word_id
+        WHERE k.reading = ?
+        ORDER BY w.id
+        LIMIT ?
+        "#,
+    )?;
+
+    let rows = stmt.query_map(params![reading, limit], |row| {
+        let mut result = row_to_search_result(row)?;
+        result.score = 0.6;
+        Ok(result)
+    })?;
     rows.collect::<std::result::Result<Vec<_>, _>>()
         .map_err(|e| e.into())
 }
 
-/// Search for words starting with a prefix
+/// Search Hangul words whose jamo decomposition starts with the query's
+///
+/// Scored like prefix matches, by the number of extra jamo.
+fn search_jamo_prefix(handle: &DictHandle, query: &str, limit: u32) -> Result<Vec<SearchResult>> {
+    if !crate::hangul::contains_hangul(query) || !crate::db::table_exists(&handle.conn, "jamo")? {
+        return Ok(Vec::new());
+    }
+    let prefix = crate::hangul::decompose(query);
+    // Every string starting with `prefix` sorts below this bound
+    let upper = format!("{}\u{10FFFF}", prefix);
+
+    let mut stmt = handle.conn.prepare(
+        r#"
+        SELECT w.id, w.word, w.pos,
+               COALESCE((SELECT definition FROM definitions WHERE word_id = w.id LIMIT 1), ''),
+               j.jamo
+        FROM jamo j
+        JOIN words w ON w.id = j.word_id
+        WHERE j.jamo >= ? AND j.jamo < ?
+        ORDER BY length(j.jamo), j.jamo
+        LIMIT ?
+        "#,
+    )?;
+
+    let prefix_len = prefix.chars().count();
+    let rows = stmt.query_map(params![prefix, upper, limit], |row| {
+        let mut result = row_to_search_result(row)?;
+        let jamo: String = row.get(4)?;
+        let len_diff = jamo.chars().count().saturating_sub(prefix_len);
+        result.score = 1.0 + (len_diff as f64 * 0.1);
+        Ok(result)
+    })?;
+    rows.collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| e.into())
+}
+
+/// Search for words starting with a prefix, shortest first
+///
+/// With a headword FST the prefix is matched against its keys, so it
+/// ignores case (and diacritics, unless the dictionary keeps them apart).
 fn search_prefix(handle: &DictHandle, prefix: &str, limit: u32) -> Result<Vec<SearchResult>> {
+    if let Some(index) = handle.headword_fst()? {
+        let key = handle.normalization.key(prefix);
+        let keys: Vec<(String, f64)> = index
+            .top_prefixed(&key, limit as usize, |key, _| key.chars().count())
+            .into_iter()
+            .map(|(key, _)| (key, 1.0))
+            .collect();
+        return search_keys(handle, &keys, limit, None);
+    }
+
     let pattern = format!("{}%", prefix);
 
     let mut stmt = handle.conn.prepare(
@@ -154,18 +1343,31 @@ fn search_prefix(handle: &DictHandle, prefix: &str, limit: u32) -> Result<Vec<Se
 
 /// Search using FTS5 full-text search
 fn search_fts(handle: &DictHandle, query: &str, limit: u32) -> Result<Vec<SearchResult>> {
-    let mut stmt = handle.conn.prepare(
+    let weights = &handle.config.fts.weights;
+    if let Some(w) = weights.iter().find(|w| !w.is_finite()) {
+        return Err(Error::InvalidInput(format!(
+            "bm25 weight {w} is not finite"
+        )));
+    }
+    // FTS5 `rank` is bm25 with every weight 1.0
+    let rank = if weights.is_empty() {
+        "rank".to_string()
+    } else {
+        let weights: Vec<String> = weights.iter().map(|w| format!("{w:?}")).collect();
+        format!("bm25(words_fts, {})", weights.join(", "))
+    };
+    let mut stmt = handle.conn.prepare_cached(&format!(
         r#"
         SELECT w.id, w.word, w.pos,
                COALESCE((SELECT definition FROM definitions WHERE word_id = w.id LIMIT 1), ''),
-               rank
+               {rank} AS fts_rank
         FROM words_fts fts
         JOIN words w ON fts.rowid = w.id
         WHERE words_fts MATCH ?
-        ORDER BY rank
+        ORDER BY fts_rank
         LIMIT ?
-        "#,
-    )?;
+        "#
+    ))?;
 
     let rows = stmt.query_map(params![query, limit], |row| {
         let id: i64 = row.get(0)?;
@@ -174,123 +1376,418 @@ fn search_fts(handle: &DictHandle, query: &str, limit: u32) -> Result<Vec<Search
         let definition: String = row.get(3)?;
         let rank: f64 = row.get(4)?;
 
-        let preview = truncate_preview(&definition, 100);
-
-        Ok(SearchResult::with_score(id, word, pos, preview, rank))
+        let mut result = SearchResult::with_score(id, word, pos, definition, rank);
+        result.fts_rank = Some(rank);
+        Ok(result)
     })?;
     rows.collect::<std::result::Result<Vec<_>, _>>()
         .map_err(|e| e.into())
 }
 
-/// Search for words with fuzzy/approximate matching using Levenshtein distance
+/// Words containing every word of the query, shortest first
 ///
-/// This function retrieves candidate words and filters them by edit distance.
-/// For performance, it uses prefix-based candidates when possible.
-fn search_fuzzy(handle: &DictHandle, query: &str, limit: u32) -> Result<Vec<SearchResult>> {
-    // Get candidates: words that start with the first character(s) of the query
-    // This significantly reduces the search space
-    let prefix_len = std::cmp::min(2, query.len());
-    let prefix = &query[..prefix_len];
-    let pattern = format!("{}%", prefix);
-
-    let mut stmt = handle.conn.prepare(
+/// Stands in for `search_fts` without FTS5. Scans the words table, so it
+/// is much slower on a large dictionary.
+fn search_substring(handle: &DictHandle, query: &str, limit: u32) -> Result<Vec<SearchResult>> {
+    let patterns: Vec<String> = query
+        .split_whitespace()
+        .map(|term| {
+            let escaped = term
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            format!("%{escaped}%")
+        })
+        .collect();
+    if patterns.is_empty() {
+        return Ok(Vec::new());
+    }
+    let conditions = vec![r"w.word LIKE ? ESCAPE '\'"; patterns.len()].join(" AND ");
+    let mut stmt = handle.conn.prepare(&format!(
         r#"
         SELECT w.id, w.word, w.pos,
                COALESCE((SELECT definition FROM definitions WHERE word_id = w.id LIMIT 1), '')
         FROM words w
-        WHERE LOWER(w.word) LIKE LOWER(?)
-        LIMIT 1000
-        "#,
-    )?;
+        WHERE {conditions}
+        ORDER BY length(w.word), w.id
+        LIMIT ?
+        "#
+    ))?;
+    let limit = i64::from(limit);
+    let mut values: Vec<&dyn rusqlite::ToSql> =
+        patterns.iter().map(|p| p as &dyn rusqlite::ToSql).collect();
+    values.push(&limit);
+    // Unranked, so the tier scores them all as full-text matches of rank 0
+    let rows = stmt.query_map(values.as_slice(), row_to_search_result)?;
+    rows.collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| e.into())
+}
 
-    let candidates = stmt.query_map(params![pattern], row_to_search_result)?;
-
-    // Filter and score by Levenshtein distance
-    let mut fuzzy_results: Vec<SearchResult> = candidates
-        .filter_map(|r| r.ok())
-        .filter_map(|mut result| {
-            let word_lower = result.word.to_lowercase();
-            let distance = levenshtein_distance(query, &word_lower);
-
-            if distance > 0 && distance <= MAX_FUZZY_DISTANCE {
-                // Score is 3.0 (base for fuzzy) + distance
-                result.score = 3.0 + distance as f64;
-                Some(result)
-            } else {
-                None
-            }
+/// Search for words with fuzzy/approximate matching using Levenshtein distance
+///
+/// A Levenshtein automaton for the query's key walks the sorted headword
+/// keys (see [`crate::fuzzy`]), so every headword within the maximum
+/// distance is found, whatever letter it starts with. Matches keep at
+/// least one character of the query, so a two-letter query is not within
+/// distance 2 of every other short word. Closer keys come first; a key
+/// differing only by case or diacritics is an exact match, not a fuzzy
+/// one. Past `deadline`, the words found so far are returned.
+fn search_fuzzy(
+    handle: &DictHandle,
+    query: &str,
+    limit: u32,
+    deadline: Option<Instant>,
+) -> Result<Vec<SearchResult>> {
+    let key = handle.normalization.key(query);
+    let max_distance = handle
+        .config
+        .fuzzy
+        .max_distance
+        .min(key.chars().count().saturating_sub(1));
+    let automaton = LevenshteinAutomaton::new(&key, max_distance);
+    let mut keys: Vec<(String, f64)> = handle
+        .headword_index()?
+        .search(&automaton, usize::MAX)
+        .into_iter()
+        .filter_map(|key| {
+            let distance = automaton.matches(&key)?;
+            // Score is 3.0 (base for fuzzy) + distance
+            (distance > 0).then_some((key, 3.0 + distance as f64))
         })
         .collect();
+    keys.sort_by(|a, b| a.1.total_cmp(&b.1));
+    search_keys(handle, &keys, limit, deadline)
+}
 
-    // Also try candidates that differ by first character (common typos)
-    if fuzzy_results.len() < limit as usize && query.len() >= 2 {
-        // Get some words that might match with a different first letter
-        let suffix = &query[1..];
-        let suffix_pattern = format!("_%{}%", suffix);
+/// Search for headwords matching a pattern, where `?` stands for any one
+/// character and `*` for any run of characters
+///
+/// The pattern is matched against headword keys, so it ignores case (and
+/// diacritics, unless the dictionary keeps them apart). Results come in
+/// alphabetical order of their keys.
+fn search_pattern(handle: &DictHandle, pattern: &str, limit: u32) -> Result<Vec<SearchResult>> {
+    let pattern = Pattern::new(&handle.normalization.key(pattern));
+    let keys: Vec<(String, f64)> = handle
+        .headword_index()?
+        .search(&pattern, limit as usize)
+        .into_iter()
+        .map(|key| (key, 0.0))
+        .collect();
+    search_keys(handle, &keys, limit, None)
+}
 
-        let mut stmt2 = handle.conn.prepare(
+/// Up to `limit` words whose headword key is one of `keys`, keys given
+/// with the score of their words and in the order to return them
+///
+/// Keys are looked up a chunk at a time, and none after `deadline`.
+fn search_keys(
+    handle: &DictHandle,
+    keys: &[(String, f64)],
+    limit: u32,
+    deadline: Option<Instant>,
+) -> Result<Vec<SearchResult>> {
+    let column = crate::fuzzy::key_column(&handle.conn)?;
+    let mut results = Vec::new();
+    for chunk in keys.chunks(KEYS_PER_QUERY) {
+        if results.len() >= limit as usize || deadline.is_some_and(|d| Instant::now() >= d) {
+            break;
+        }
+        let mut stmt = handle.conn.prepare(&format!(
             r#"
             SELECT w.id, w.word, w.pos,
-                   COALESCE((SELECT definition FROM definitions WHERE word_id = w.id LIMIT 1), '')
+                   COALESCE((SELECT definition FROM definitions WHERE word_id = w.id LIMIT 1), ''),
+                   {column}
             FROM words w
-            WHERE LOWER(w.word) LIKE LOWER(?)
-            LIMIT 500
+            WHERE {column} IN ({})
+            ORDER BY w.id
             "#,
+            vec!["?"; chunk.len()].join(",")
+        ))?;
+        let order: HashMap<&str, (usize, f64)> = chunk
+            .iter()
+            .enumerate()
+            .map(|(i, (key, score))| (key.as_str(), (i, *score)))
+            .collect();
+        let rows = stmt.query_map(
+            rusqlite::params_from_iter(chunk.iter().map(|(key, _)| key)),
+            |row| Ok((row_to_search_result(row)?, row.get::<_, String>(4)?)),
         )?;
+        let mut found = Vec::new();
+        for row in rows {
+            let (mut result, key) = row?;
+            let (position, score) = order[key.as_str()];
+            result.score = score;
+            found.push((position, result));
+        }
+        found.sort_by_key(|&(position, _)| position);
+        results.extend(found.into_iter().map(|(_, result)| result));
+    }
+    results.truncate(limit as usize);
+    Ok(results)
+}
 
-        let more_candidates = stmt2.query_map(params![suffix_pattern], row_to_search_result)?;
-
-        for result in more_candidates.filter_map(|r| r.ok()) {
-            if fuzzy_results.iter().any(|r| r.id == result.id) {
-                continue;
-            }
-
-            let word_lower = result.word.to_lowercase();
-            let distance = levenshtein_distance(query, &word_lower);
-
-            if distance > 0 && distance <= MAX_FUZZY_DISTANCE {
-                let mut result = result;
-                result.score = 3.0 + distance as f64;
-                fuzzy_results.push(result);
-            }
+/// Match the query against the headwords of the user database
+///
+/// Exact, prefix and fuzzy matches are scored like their shipped
+/// counterparts, before any overlay boost.
+fn search_user(
+    handle: &DictHandle,
+    query: &str,
+    limit: u32,
+    timer: &mut TierTimer,
+) -> Result<Vec<SearchResult>> {
+    let search = |condition: &str, order: &str, param: &str, limit: u32| -> Result<_> {
+        let mut stmt = handle.conn.prepare_cached(&format!(
+            r#"
+            SELECT w.id, w.word, w.pos,
+                   COALESCE((SELECT definition FROM user.definitions
+                             WHERE word_id = w.id ORDER BY id LIMIT 1), '')
+            FROM user.words w
+            WHERE {condition}
+            ORDER BY {order}
+            LIMIT ?
+            "#
+        ))?;
+        let rows = stmt.query_map(params![param, limit], |row| {
+            let mut result = row_to_search_result(row)?;
+            result.origin = EntryOrigin::User;
+            Ok(result)
+        })?;
+        Ok(rows.collect::<std::result::Result<Vec<_>, _>>()?)
+    };
+
+    let mut results = timer.run("user_exact", || search("w.word = ?", "w.id", query, limit))?;
+
+    let pattern = format!("{}%", query);
+    for mut result in timer.run("user_prefix", || {
+        search("w.word LIKE ?", "length(w.word), w.word", &pattern, limit)
+    })? {
+        if !results.iter().any(|r| r.id == result.id) {
+            let len_diff = result.word.len().saturating_sub(query.len());
+            result.score = 1.0 + (len_diff as f64 * 0.1);
+            results.push(result);
         }
     }
 
-    // Sort by score
-    fuzzy_results.sort_by(|a, b| {
-        a.score
-            .partial_cmp(&b.score)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
-    fuzzy_results.truncate(limit as usize);
+    let fuzzy = &handle.config.fuzzy;
+    let query_lower = query.to_lowercase();
+    if fuzzy.enabled && query_lower.len() >= fuzzy.min_query_length {
+        let candidates = timer.run("user_fuzzy", || {
+            search("w.word != ?", "w.id", query, USER_FUZZY_SCAN_LIMIT)
+        })?;
+        let mut fuzzy_results: Vec<SearchResult> = candidates
+            .into_iter()
+            .filter(|c| !results.iter().any(|r| r.id == c.id))
+            .filter_map(|mut result| {
+                let distance = levenshtein_distance(&query_lower, &result.word.to_lowercase());
+                (distance > 0 && distance <= fuzzy.max_distance).then(|| {
+                    result.score = 3.0 + distance as f64;
+                    result
+                })
+            })
+            .collect();
+        fuzzy_results.sort_by(|a, b| a.score.total_cmp(&b.score));
+        fuzzy_results.truncate(limit as usize);
+        results.extend(fuzzy_results);
+    }
+    Ok(results)
+}
 
-    Ok(fuzzy_results)
+/// Merge user entries into `results` as the overlay config says
+fn overlay_user_results(
+    handle: &DictHandle,
+    results: &mut Vec<SearchResult>,
+    mut user_results: Vec<SearchResult>,
+) {
+    let overlay = &handle.config.overlay;
+    let offset = match overlay.precedence {
+        OverlayPrecedence::UserFirst => USER_FIRST_OFFSET,
+        _ => overlay.boost,
+    };
+    for result in &mut user_results {
+        result.score -= offset;
+    }
+    if overlay.precedence == OverlayPrecedence::Replace {
+        let replaced: HashSet<String> =
+            user_results.iter().map(|r| r.word.to_lowercase()).collect();
+        results.retain(|r| !replaced.contains(&r.word.to_lowercase()));
+    }
+    results.extend(user_results);
 }
 
 /// Convert a database row to a SearchResult
+///
+/// The preview holds the whole first definition until `fill_details`
+/// shapes it for the page of results actually returned.
 fn row_to_search_result(row: &rusqlite::Row) -> rusqlite::Result<SearchResult> {
     let id: i64 = row.get(0)?;
     let word: String = row.get(1)?;
     let pos: String = row.get(2)?;
     let definition: String = row.get(3)?;
 
-    // Truncate preview to reasonable length
-    let preview = truncate_preview(&definition, 100);
+    Ok(SearchResult::new(id, word, pos, definition))
+}
 
-    Ok(SearchResult::new(id, word, pos, preview))
+/// Fill in the previews, tags, sense counts and languages of `results`
+///
+/// Runs only on the page being returned; the tiers carry just the raw
+/// first definition.
+fn fill_details(
+    handle: &DictHandle,
+    results: &mut [SearchResult],
+    options: &PreviewOptions,
+) -> Result<()> {
+    // The previewed sense: the first one, or with `prefer_current` or
+    // dated senses demoted the first not dated if there is one. Hidden
+    // dated senses are neither previewed nor counted.
+    let dated = &handle.config.dated_senses;
+    let prefer_current = options.prefer_current || dated.is_active();
+    let hide = dated.is_active() && dated.mode == DatedSenseMode::Hide;
+    let details_sql = |schema: &str| {
+        let is_dated = |table| crate::db::dated_sense_condition(table, 4, dated.tags.len());
+        format!(
+            r#"
+            SELECT w.language,
+                   (SELECT COUNT(*) FROM {schema}.definitions sd
+                    WHERE word_id = w.id AND NOT (?3 AND {sd_dated})),
+                   d.definition, d.tags
+            FROM {schema}.words w
+            LEFT JOIN {schema}.definitions d ON d.id = COALESCE(
+                (SELECT id FROM {schema}.definitions cd
+                 WHERE word_id = w.id AND ?2 AND NOT {cd_dated}
+                 ORDER BY id LIMIT 1),
+                (SELECT id FROM {schema}.definitions WHERE word_id = w.id AND NOT ?3
+                 ORDER BY id LIMIT 1))
+            WHERE w.id = ?1
+            "#,
+            sd_dated = is_dated("sd"),
+            cd_dated = is_dated("cd"),
+        )
+    };
+    for result in results {
+        let schema = match result.origin {
+            EntryOrigin::Dictionary => "main",
+            EntryOrigin::User => "user",
+        };
+        let mut stmt = handle.conn.prepare_cached(&details_sql(schema))?;
+        let mut values: Vec<&dyn rusqlite::ToSql> = vec![&result.id, &prefer_current, &hide];
+        values.extend(dated.tags.iter().map(|t| t as &dyn rusqlite::ToSql));
+        let details = stmt
+            .query_row(values.as_slice(), |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, u32>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
+            })
+            .optional()?;
+        if let Some((language, sense_count, definition, tags_json)) = details {
+            result.language = language;
+            result.sense_count = sense_count;
+            match definition {
+                Some(definition) => result.preview = definition,
+                // Every sense is hidden
+                None if hide => result.preview.clear(),
+                None => {}
+            }
+            result.tags = tags_json
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default();
+        }
+        result.relevance = relevance(result.score);
+        result.preview = make_preview(&result.preview, &result.pos, options);
+        if !handle.config.preferred_accents.is_empty() && result.origin == EntryOrigin::Dictionary {
+            result.ipa = preferred_ipa(handle, result.id)?;
+        }
+        if handle.config.spelling.is_some() && result.origin == EntryOrigin::Dictionary {
+            result.also_spelled = crate::spelling::variants(handle, result.id)?;
+        }
+    }
+    Ok(())
+}
+
+/// IPA of a word in the accent ranked first by `preferred_accents`,
+/// ties going to the first stored
+fn preferred_ipa(handle: &DictHandle, word_id: i64) -> Result<Option<String>> {
+    let mut stmt = handle.conn.prepare_cached(
+        "SELECT ipa, accent FROM pronunciations WHERE word_id = ? AND ipa IS NOT NULL ORDER BY id",
+    )?;
+    let rows = stmt.query_map([word_id], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+    })?;
+    let preferred = &handle.config.preferred_accents;
+    let mut best: Option<(usize, String)> = None;
+    for row in rows {
+        let (ipa, accent) = row?;
+        let rank = crate::ipa::accent_rank(accent.as_deref(), preferred);
+        if best.as_ref().is_none_or(|(best, _)| rank < *best) {
+            best = Some((rank, ipa));
+        }
+    }
+    Ok(best.map(|(_, ipa)| ipa))
+}
+
+/// Build a preview from a definition
+pub(crate) fn make_preview(definition: &str, pos: &str, options: &PreviewOptions) -> String {
+    let text = if options.strip_parentheticals {
+        strip_parentheticals(definition)
+    } else {
+        definition.to_string()
+    };
+    let text = if options.include_pos && !pos.is_empty() {
+        format!("{}: {}", pos, text)
+    } else {
+        text
+    };
+    truncate_preview(&text, options.max_len)
+}
+
+/// Remove parenthesized text, including nested parentheses
+///
+/// An unbalanced "(" keeps the rest of the text.
+fn strip_parentheticals(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut depth = 0usize;
+    let mut pending = String::new();
+    for c in text.chars() {
+        match c {
+            '(' => {
+                depth += 1;
+                pending.push(c);
+            }
+            ')' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    pending.clear();
+                } else {
+                    pending.push(c);
+                }
+            }
+            _ if depth > 0 => pending.push(c),
+            _ => out.push(c),
+        }
+    }
+    out.push_str(&pending);
+
+    // Collapse the gaps the asides leave ("To run (fast) away" -> "To run away")
+    let collapsed = out.split_whitespace().collect::<Vec<_>>().join(" ");
+    collapsed
+        .replace(" ,", ",")
+        .replace(" .", ".")
+        .replace(" ;", ";")
+        .replace(" :", ":")
 }
 
 /// Truncate definition text for preview
+///
+/// Text is cut between grapheme clusters, so an accent is never split
+/// from its letter.
 fn truncate_preview(text: &str, max_len: usize) -> String {
     if text.len() <= max_len {
         text.to_string()
     } else {
-        // Find a valid UTF-8 character boundary at or before max_len
-        let mut end = max_len;
-        while end > 0 && !text.is_char_boundary(end) {
-            end -= 1;
-        }
-        let truncated = &text[..end];
+        let truncated = crate::graphemes::truncate(text, max_len);
 
         // Try to truncate at word boundary
         if let Some(last_space) = truncated.rfind(' ') {
@@ -312,135 +1809,23 @@ fn prepare_fts_query(query: &str) -> String {
     // This allows "hel" to match "hello"
     let words: Vec<&str> = escaped.split_whitespace().collect();
     if words.is_empty() {
-        return String::new();
-    }
-
-    // Make each word a prefix search
-    words
-        .iter()
-        .map(|w| format!("{}*", w))
-        .collect::<Vec<_>>()
-        .join(" ")
-}
-
-/// Calculate Levenshtein distance between two strings
-///
-/// The Levenshtein distance is the minimum number of single-character edits
-/// (insertions, deletions, or substitutions) required to change one string into another.
-///
-/// Uses the Wagner-Fischer algorithm with O(min(m,n)) space complexity.
-fn levenshtein_distance(a: &str, b: &str) -> usize {
-    let a_chars: Vec<char> = a.chars().collect();
-    let b_chars: Vec<char> = b.chars().collect();
-
-    let m = a_chars.len();
-    let n = b_chars.len();
-
-    // Handle empty strings
-    if m == 0 {
-        return n;
-    }
-    if n == 0 {
-        return m;
-    }
-
-    // Optimize: ensure a is the shorter string for O(min(m,n)) space
-    if m > n {
-        return levenshtein_distance(b, a);
-    }
-
-    // Use two rows instead of full matrix for space efficiency
-    let mut prev_row: Vec<usize> = (0..=m).collect();
-    let mut curr_row: Vec<usize> = vec![0; m + 1];
-
-    for j in 1..=n {
-        curr_row[0] = j;
-
-        for i in 1..=m {
-            let cost = if a_chars[i - 1] == b_chars[j - 1] {
-                0
-            } else {
-                1
-            };
-
-            curr_row[i] = std::cmp::min(
-                std::cmp::min(
-                    prev_row[i] + 1,     // deletion
-                    curr_row[i - 1] + 1, // insertion
-                ),
-                prev_row[i - 1] + cost, // substitution
-            );
-        }
-
-        std::mem::swap(&mut prev_row, &mut curr_row);
-    }
-
-    prev_row[m]
-}
-
-/// Calculate Damerau-Levenshtein distance (allows transpositions)
-///
-/// This is similar to Levenshtein but also considers transposition of two
-/// adjacent characters as a single edit operation.
-#[allow(dead_code)]
-fn damerau_levenshtein_distance(a: &str, b: &str) -> usize {
-    let a_chars: Vec<char> = a.chars().collect();
-    let b_chars: Vec<char> = b.chars().collect();
-
-    let m = a_chars.len();
-    let n = b_chars.len();
-
-    if m == 0 {
-        return n;
-    }
-    if n == 0 {
-        return m;
-    }
-
-    // Need full matrix for transpositions
-    let mut d: Vec<Vec<usize>> = vec![vec![0; n + 1]; m + 1];
-
-    for i in 0..=m {
-        d[i][0] = i;
-    }
-    for j in 0..=n {
-        d[0][j] = j;
-    }
-
-    for i in 1..=m {
-        for j in 1..=n {
-            let cost = if a_chars[i - 1] == b_chars[j - 1] {
-                0
-            } else {
-                1
-            };
-
-            d[i][j] = std::cmp::min(
-                std::cmp::min(
-                    d[i - 1][j] + 1, // deletion
-                    d[i][j - 1] + 1, // insertion
-                ),
-                d[i - 1][j - 1] + cost, // substitution
-            );
-
-            // Transposition
-            if i > 1
-                && j > 1
-                && a_chars[i - 1] == b_chars[j - 2]
-                && a_chars[i - 2] == b_chars[j - 1]
-            {
-                d[i][j] = std::cmp::min(d[i][j], d[i - 2][j - 2] + 1);
-            }
-        }
+        return String::new();
     }
 
-    d[m][n]
+    // Make each word a prefix search
+    words
+        .iter()
+        .map(|w| format!("{}*", w))
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::db::{init_database, insert_definition, insert_word};
+    use crate::db::{
+        init_database, insert_definition, insert_pinyin, insert_pronunciation, insert_word,
+    };
 
     fn setup_test_db() -> (tempfile::TempDir, DictHandle) {
         let dir = tempfile::tempdir().unwrap();
@@ -499,45 +1884,126 @@ mod tests {
     }
 
     #[test]
-    fn test_prepare_fts_query_escapes_special_chars() {
-        // Special chars should be escaped/removed
-        assert_eq!(prepare_fts_query("test*query"), "test* query*");
-        assert_eq!(prepare_fts_query("hello:world"), "hello* world*");
+    fn test_truncate_preview_combining_marks() {
+        // The cut falls inside the accent of the last "é", which goes
+        // along with its letter
+        let text = "Re\u{301}sume\u{301}";
+        assert_eq!(truncate_preview(text, 9), "Re\u{301}sum...");
+    }
+
+    #[test]
+    fn test_make_preview() {
+        let options = PreviewOptions {
+            strip_parentheticals: true,
+            include_pos: true,
+            ..PreviewOptions::default()
+        };
+        assert_eq!(
+            make_preview(
+                "(transitive) To run (fast (very)) away, quickly.",
+                "verb",
+                &options
+            ),
+            "verb: To run away, quickly."
+        );
+        assert_eq!(
+            strip_parentheticals("Unbalanced (aside"),
+            "Unbalanced (aside"
+        );
+        let short = PreviewOptions {
+            max_len: 12,
+            ..PreviewOptions::default()
+        };
+        assert_eq!(
+            make_preview("A greeting said on meeting.", "intj", &short),
+            "A greeting..."
+        );
     }
 
     #[test]
-    fn test_levenshtein_distance() {
-        // Same strings
-        assert_eq!(levenshtein_distance("hello", "hello"), 0);
+    fn test_search_preview_options() {
+        let (_dir, mut handle) = setup_test_db();
+        let thou = insert_word(&handle.conn, "thou", "pron", "English", "en", 0).unwrap();
+        insert_definition(
+            &handle.conn,
+            thou,
+            "(archaic) You (singular).",
+            &[],
+            &["archaic".to_string()],
+        )
+        .unwrap();
+        insert_definition(&handle.conn, thou, "(informal) A thousand.", &[], &[]).unwrap();
+
+        let results = search_words(&handle, "thou", 1).unwrap();
+        assert_eq!(results[0].preview, "(archaic) You (singular).");
+
+        let options = PreviewOptions {
+            prefer_current: true,
+            strip_parentheticals: true,
+            ..PreviewOptions::default()
+        };
+        let results = search_words_with_preview(&handle, "thou", 1, 0, &options).unwrap();
+        assert_eq!(results[0].preview, "A thousand.");
+
+        handle.set_preview_options(PreviewOptions {
+            include_pos: true,
+            ..options
+        });
+        let results = search_words(&handle, "thou", 1).unwrap();
+        assert_eq!(results[0].preview, "pron: A thousand.");
+    }
 
-        // Empty strings
-        assert_eq!(levenshtein_distance("", "hello"), 5);
-        assert_eq!(levenshtein_distance("hello", ""), 5);
-        assert_eq!(levenshtein_distance("", ""), 0);
+    #[test]
+    fn test_match_kind_and_relevance() {
+        let (_dir, handle) = setup_test_db();
+        populate_test_data(&handle);
 
-        // One edit
-        assert_eq!(levenshtein_distance("hello", "helo"), 1); // deletion
-        assert_eq!(levenshtein_distance("hello", "helloo"), 1); // insertion
-        assert_eq!(levenshtein_distance("hello", "hallo"), 1); // substitution
+        let results = search_words(&handle, "help", 3).unwrap();
+        assert_eq!(results[0].match_kind, MatchKind::Exact);
+        assert_eq!(results[0].relevance, 1.0);
+        assert_eq!(results[1].match_kind, MatchKind::Prefix);
+        assert!(results.windows(2).all(|w| w[0].relevance >= w[1].relevance));
 
-        // Two edits
-        assert_eq!(levenshtein_distance("hello", "halo"), 2);
-        assert_eq!(levenshtein_distance("kitten", "sitten"), 1);
-        assert_eq!(levenshtein_distance("kitten", "sittin"), 2);
-        assert_eq!(levenshtein_distance("kitten", "sitting"), 3);
+        let results = search_words(&handle, "worls", 3).unwrap();
+        assert!(!results.is_empty());
+        assert!(results.iter().all(|r| r.match_kind == MatchKind::Fuzzy));
+        assert!(results
+            .iter()
+            .all(|r| r.relevance > 0.0 && r.relevance < 0.5));
 
-        // Completely different
-        assert_eq!(levenshtein_distance("abc", "xyz"), 3);
+        let results = match_words(&handle, "hel", MatchStrategy::Prefix, 5).unwrap();
+        assert!(results.iter().all(|r| r.match_kind == MatchKind::Prefix));
     }
 
     #[test]
-    fn test_damerau_levenshtein_distance() {
-        // Transposition
-        assert_eq!(damerau_levenshtein_distance("ab", "ba"), 1);
-        assert_eq!(damerau_levenshtein_distance("hello", "hlelo"), 1);
+    fn test_search_result_details() {
+        let (_dir, handle) = setup_test_db();
+        let thou = insert_word(&handle.conn, "thou", "pron", "English", "en", 0).unwrap();
+        let archaic = ["archaic".to_string()];
+        insert_definition(&handle.conn, thou, "You (singular).", &[], &archaic).unwrap();
+        insert_definition(&handle.conn, thou, "A thousand.", &[], &[]).unwrap();
+        let thought = insert_word(&handle.conn, "thought", "noun", "English", "en", 0).unwrap();
+        insert_definition(&handle.conn, thought, "An idea.", &[], &[]).unwrap();
+
+        let results = search_words(&handle, "thou", 3).unwrap();
+        assert_eq!(results[0].tags, ["archaic"]);
+        assert_eq!(results[0].sense_count, 2);
+        assert_eq!(results[0].language, "English");
+        assert_eq!(results[1].word, "thought");
+        assert!(results[1].tags.is_empty());
+        assert_eq!(results[1].sense_count, 1);
+
+        // Results serialized before these fields existed still load
+        let old = r#"{"id": 1, "word": "thou", "pos": "pron", "preview": "You."}"#;
+        let result: SearchResult = serde_json::from_str(old).unwrap();
+        assert_eq!((result.sense_count, result.tags.len()), (0, 0));
+    }
 
-        // Compare with standard Levenshtein (which counts transposition as 2)
-        assert_eq!(levenshtein_distance("ab", "ba"), 2);
+    #[test]
+    fn test_prepare_fts_query_escapes_special_chars() {
+        // Special chars should be escaped/removed
+        assert_eq!(prepare_fts_query("test*query"), "test* query*");
+        assert_eq!(prepare_fts_query("hello:world"), "hello* world*");
     }
 
     #[test]
@@ -622,6 +2088,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_match_words_single_strategy() {
+        let (_dir, handle) = setup_test_db();
+        populate_test_data(&handle);
+
+        let exact = match_words(&handle, "help", MatchStrategy::Exact, 10).unwrap();
+        assert_eq!(exact.len(), 1);
+        assert_eq!(exact[0].word, "help");
+
+        let prefix = match_words(&handle, "help", MatchStrategy::Prefix, 10).unwrap();
+        let words: Vec<&str> = prefix.iter().map(|r| r.word.as_str()).collect();
+        assert_eq!(words, ["help", "helper", "helping"]);
+
+        let fuzzy = match_words(&handle, "wokr", MatchStrategy::Levenshtein, 10).unwrap();
+        assert!(fuzzy.iter().any(|r| r.word == "work"));
+        assert!(fuzzy.iter().all(|r| r.word != "helper"));
+
+        let pattern = match_words(&handle, "HELP?NG", MatchStrategy::Pattern, 10).unwrap();
+        assert_eq!(pattern.len(), 1);
+        assert_eq!(pattern[0].word, "helping");
+        assert_eq!(pattern[0].match_kind, MatchKind::Pattern);
+        let pattern = match_words(&handle, "help*", MatchStrategy::Pattern, 2).unwrap();
+        let words: Vec<&str> = pattern.iter().map(|r| r.word.as_str()).collect();
+        assert_eq!(words, ["help", "helper"]);
+    }
+
     #[test]
     fn test_fuzzy_search_typo_tolerance() {
         let (_dir, handle) = setup_test_db();
@@ -637,4 +2129,972 @@ mod tests {
             "Expected to find 'hello' for query 'helo'"
         );
     }
+
+    #[test]
+    fn test_find_rhymes() {
+        let (_dir, handle) = setup_test_db();
+        let words = [
+            ("cat", "English", "/kæt/"),
+            ("hat", "English", "/hæt/"),
+            ("concat", "English", "/kənˈkæt/"),
+            ("catalog", "English", "/ˈkæt.ə.lɒɡ/"),
+            ("dog", "English", "/dɒɡ/"),
+            ("chatte", "French", "/ʃat/"),
+            ("natte", "French", "/nat/"),
+        ];
+        for (word, language, ipa) in words {
+            let word_id = insert_word(&handle.conn, word, "noun", language, "", 0).unwrap();
+            insert_definition(&handle.conn, word_id, "A word", &[], &[]).unwrap();
+            insert_pronunciation(&handle.conn, word_id, Some(ipa), None, None).unwrap();
+        }
+
+        let rhymes = find_rhymes(&handle, "cat", 10).unwrap();
+        let rhymes: Vec<&str> = rhymes.iter().map(|r| r.word.as_str()).collect();
+        assert_eq!(rhymes, ["concat", "hat"]);
+
+        let rhymes = find_rhymes(&handle, "chatte", 10).unwrap();
+        assert_eq!(rhymes.len(), 1);
+        assert_eq!(rhymes[0].word, "natte");
+
+        assert!(find_rhymes(&handle, "missing", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_by_ipa() {
+        let (_dir, handle) = setup_test_db();
+        let words: &[(&str, &[&str])] = &[
+            ("mnemonic", &["/nɪˈmɒn.ɪk/", "/niːˈmɑːnɪk/"]),
+            ("demonic", &["/dɪˈmɒn.ɪk/"]),
+            ("cat", &["/kæt/"]),
+        ];
+        for (word, ipas) in words {
+            let word_id = insert_word(&handle.conn, word, "noun", "English", "en", 0).unwrap();
+            insert_definition(&handle.conn, word_id, "A word", &[], &[]).unwrap();
+            for ipa in ipas.iter() {
+                insert_pronunciation(&handle.conn, word_id, Some(ipa), None, None).unwrap();
+            }
+        }
+
+        let exact = by_ipa(&handle, "[nɪmɒnɪk]", false, 10).unwrap();
+        assert_eq!(exact.len(), 1);
+        assert_eq!(exact[0].word, "mnemonic");
+        assert_eq!(exact[0].ipa.as_deref(), Some("/nɪˈmɒn.ɪk/"));
+        assert_eq!(exact[0].match_kind, MatchKind::Exact);
+        assert!(by_ipa(&handle, "/nimɒnɪk/", false, 10).unwrap().is_empty());
+
+        // Near matches: "i" for "ɪ" is closer than "d" for "n"
+        let near = by_ipa(&handle, "/ˈnimɒnɪk/", true, 10).unwrap();
+        let words: Vec<&str> = near.iter().map(|r| r.word.as_str()).collect();
+        assert_eq!(words, ["mnemonic", "demonic"]);
+        assert_eq!(near[0].match_kind, MatchKind::Phonetic);
+        assert!(near[0].score < near[1].score);
+        assert!(by_ipa(&handle, "//", true, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_preferred_ipa() {
+        let (_dir, mut handle) = setup_test_db();
+        let word_id = insert_word(&handle.conn, "tomato", "noun", "English", "en", 0).unwrap();
+        insert_definition(&handle.conn, word_id, "A red fruit", &[], &[]).unwrap();
+        insert_pronunciation(&handle.conn, word_id, Some("/təˈmɑːtəʊ/"), None, Some("RP")).unwrap();
+        insert_pronunciation(
+            &handle.conn,
+            word_id,
+            Some("/təˈmeɪtoʊ/"),
+            None,
+            Some("GenAm"),
+        )
+        .unwrap();
+
+        let results = search_words(&handle, "tomato", 10).unwrap();
+        assert_eq!(results[0].ipa, None);
+
+        handle.config.preferred_accents = vec!["US".to_string(), "UK".to_string()];
+        let results = search_words(&handle, "tomato", 10).unwrap();
+        assert_eq!(results[0].ipa.as_deref(), Some("/təˈmeɪtoʊ/"));
+    }
+
+    #[test]
+    fn test_pinyin_search() {
+        let (_dir, handle) = setup_test_db();
+        let words = [
+            ("你好", "nǐ hǎo", "Hello"),
+            ("泥蒿", "ní hāo", "A wormwood"),
+            ("妈妈", "māma", "Mother"),
+        ];
+        for (word, reading, definition) in words {
+            let word_id = insert_word(&handle.conn, word, "phrase", "Chinese", "zh", 0).unwrap();
+            insert_definition(&handle.conn, word_id, definition, &[], &[]).unwrap();
+            insert_pinyin(&handle.conn, word_id, reading).unwrap();
+        }
+
+        for query in ["ni3hao3", "nǐhǎo", "ni3 hao3"] {
+            let results = search_words(&handle, query, 10).unwrap();
+            let words: Vec<&str> = results.iter().map(|r| r.word.as_str()).collect();
+            assert_eq!(words, ["你好", "泥蒿"], "query {query}");
+            assert_eq!(results[0].score, 0.5);
+            assert_eq!(results[1].score, 0.8);
+        }
+
+        // Without tones both readings match equally
+        let results = search_words(&handle, "nihao", 10).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.score == 0.8));
+
+        let results = search_words(&handle, "ma1ma", 10).unwrap();
+        assert_eq!(results[0].word, "妈妈");
+        assert_eq!(results[0].score, 0.5);
+    }
+
+    #[cfg(feature = "kana")]
+    #[test]
+    fn test_kana_search() {
+        let (_dir, handle) = setup_test_db();
+        let words = [
+            ("有難う", "ありがとう", "Thank you"),
+            ("寿司", "すし", "Sushi"),
+            ("カメラ", "カメラ", "Camera"),
+        ];
+        for (word, reading, definition) in words {
+            let word_id = insert_word(&handle.conn, word, "noun", "Japanese", "ja", 0).unwrap();
+            insert_definition(&handle.conn, word_id, definition, &[], &[]).unwrap();
+            crate::db::insert_kana(&handle.conn, word_id, reading).unwrap();
+        }
+
+        for (query, expected) in [
+            ("arigatou", "有難う"),
+            ("arigatō", "有難う"),
+            ("ありがとう", "有難う"),
+            ("スシ", "寿司"),
+            ("kamera", "カメラ"),
+        ] {
+            let results = search_words(&handle, query, 10).unwrap();
+            assert_eq!(results[0].word, expected, "query {query}");
+            assert_eq!(results[0].score, 0.6);
+        }
+    }
+
+    #[test]
+    fn test_jamo_prefix_search() {
+        let (_dir, handle) = setup_test_db();
+        for (word, definition) in [
+            ("한국어", "Korean language"),
+            ("한국", "Korea"),
+            ("화장실", "Toilet"),
+        ] {
+            let word_id = insert_word(&handle.conn, word, "noun", "Korean", "ko", 0).unwrap();
+            insert_definition(&handle.conn, word_id, definition, &[], &[]).unwrap();
+        }
+
+        let results = search_words(&handle, "ㅎㅏㄴ", 10).unwrap();
+        let words: Vec<&str> = results.iter().map(|r| r.word.as_str()).collect();
+        assert_eq!(words, ["한국", "한국어"]);
+
+        let results = search_words(&handle, "한국ㅇ", 10).unwrap();
+        assert_eq!(results[0].word, "한국어");
+
+        let results = search_words(&handle, "호", 10).unwrap();
+        assert_eq!(results[0].word, "화장실");
+
+        // Renaming a word updates its jamo
+        crate::db::update_word(&handle.conn, 3, "화분", "noun", "Korean").unwrap();
+        let results = search_words(&handle, "화부", 10).unwrap();
+        assert_eq!(results[0].word, "화분");
+    }
+
+    #[test]
+    fn test_compound_splitting() {
+        let (_dir, handle) = setup_test_db();
+        for (word, definition) in [
+            ("Donau", "Danube"),
+            ("Dampfschiff", "Steamship"),
+            ("Dampf", "Steam"),
+            ("Schiff", "Ship"),
+            ("Arbeit", "Work"),
+            ("Zimmer", "Room"),
+        ] {
+            let word_id = insert_word(&handle.conn, word, "noun", "German", "de", 0).unwrap();
+            insert_definition(&handle.conn, word_id, definition, &[], &[]).unwrap();
+        }
+
+        // The longest parts win
+        assert_eq!(
+            split_compound(&handle, "Donaudampfschiff").unwrap(),
+            Some(vec!["Donau".to_string(), "Dampfschiff".to_string()])
+        );
+        // Linking "s"
+        assert_eq!(
+            split_compound(&handle, "arbeitszimmer").unwrap(),
+            Some(vec!["Arbeit".to_string(), "Zimmer".to_string()])
+        );
+        assert_eq!(split_compound(&handle, "Donauxyzabc").unwrap(), None);
+
+        let results = search_words(&handle, "Donaudampfschiff", 10).unwrap();
+        let words: Vec<&str> = results.iter().map(|r| r.word.as_str()).collect();
+        assert_eq!(words, ["Donau", "Dampfschiff"]);
+        assert!(results
+            .iter()
+            .all(|r| r.compound_of.as_deref() == Some("Donaudampfschiff")));
+
+        // Other languages' entries spelled like a part are not included
+        let word_id = insert_word(&handle.conn, "Donau", "name", "English", "en", 0).unwrap();
+        insert_definition(&handle.conn, word_id, "The Danube", &[], &[]).unwrap();
+        let results = search_words(&handle, "Donaudampfschiff", 10).unwrap();
+        let words: Vec<&str> = results.iter().map(|r| r.word.as_str()).collect();
+        assert_eq!(words, ["Donau", "Dampfschiff"]);
+        assert_eq!(results[0].preview, "Danube");
+
+        // Words with entries of their own are not split
+        let results = search_words(&handle, "Dampfschiff", 10).unwrap();
+        assert_eq!(results[0].word, "Dampfschiff");
+        assert!(results[0].compound_of.is_none());
+    }
+
+    #[test]
+    fn test_lemma_fallback() {
+        let (_dir, handle) = setup_test_db();
+        populate_test_data(&handle);
+        let go = insert_word(&handle.conn, "go", "verb", "English", "en", 0).unwrap();
+        insert_definition(&handle.conn, go, "To move", &[], &[]).unwrap();
+        crate::db::insert_form(&handle.conn, go, "went", &["past".to_string()]).unwrap();
+
+        let results = search_words(&handle, "went", 10).unwrap();
+        assert_eq!(results[0].word, "go");
+        assert_eq!(results[0].score, 0.7);
+
+        let results = search_words(&handle, "workers", 10).unwrap();
+        assert_eq!(results[0].word, "worker");
+
+        // Not used when the query is a headword itself
+        let results = search_words(&handle, "helping", 10).unwrap();
+        assert_eq!(results[0].word, "helping");
+        assert!(results.iter().all(|r| r.score != 0.7));
+    }
+
+    #[test]
+    fn test_search_federated_boosts_detected_language() {
+        let (_en_dir, english) = setup_test_db();
+        let (_fr_dir, french) = setup_test_db();
+        insert_word(&english.conn, "chanson", "noun", "English", "en", 0).unwrap();
+        insert_word(&french.conn, "chanson", "noun", "French", "fr", 0).unwrap();
+
+        assert_eq!(detect_language("chanson")[0].code, "fr");
+        let results = search_federated(&[&english, &french], "chanson", 10).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            (results[0].source, results[0].lang_code.as_str()),
+            (1, "fr")
+        );
+        assert_eq!(results[1].source, 0);
+        assert!(results[0].result.score < results[1].result.score);
+    }
+
+    #[test]
+    fn test_search_federated_grouped() {
+        let (_dir_a, first) = setup_test_db();
+        let (_dir_b, second) = setup_test_db();
+        for handle in [&first, &second] {
+            insert_word(&handle.conn, "house", "noun", "English", "en", 0).unwrap();
+        }
+        insert_word(&second.conn, "house", "verb", "English", "en", 0).unwrap();
+
+        let separate = search_federated(&[&first, &second], "house", 10).unwrap();
+        assert_eq!(separate.len(), 3);
+        assert!(separate.iter().all(|r| r.sources == [r.source]));
+
+        let grouped =
+            search_federated_with_mode(&[&first, &second], "house", 10, FederatedMode::Grouped)
+                .unwrap();
+        let groups: Vec<(&str, Vec<usize>)> = grouped
+            .iter()
+            .map(|r| (r.result.pos.as_str(), r.sources.clone()))
+            .collect();
+        assert_eq!(groups, [("noun", vec![0, 1]), ("verb", vec![1])]);
+    }
+
+    #[test]
+    fn test_search_federated_user_overlay() {
+        let (en_dir, english) = setup_test_db();
+        let (_fr_dir, french) = setup_test_db();
+        insert_word(&english.conn, "chanson", "noun", "English", "en", 0).unwrap();
+        insert_word(&french.conn, "chant", "noun", "French", "fr", 0).unwrap();
+        // Ids 1 and 2 of the user database: 1 is an English word in the
+        // main one, 2 is not there at all
+        let user_path = en_dir.path().join("user.db");
+        let user = init_database(user_path.to_str().unwrap()).unwrap();
+        insert_word(&user.conn, "chanson", "noun", "French", "fr", 0).unwrap();
+        insert_word(&user.conn, "chansonnier", "noun", "French", "fr", 0).unwrap();
+        let config = crate::DictConfig {
+            user_db: Some(user_path),
+            ..crate::DictConfig::default()
+        };
+        let path = en_dir.path().join("test.db");
+        let overlaid =
+            crate::db::open_readonly_with_config(path.to_str().unwrap(), config).unwrap();
+
+        let results = search_federated(&[&overlaid, &french], "chanson", 10).unwrap();
+        let summary: Vec<(&str, EntryOrigin, &str)> = results
+            .iter()
+            .map(|r| {
+                (
+                    r.result.word.as_str(),
+                    r.result.origin,
+                    r.lang_code.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("chanson", EntryOrigin::User, "fr"),
+                ("chanson", EntryOrigin::Dictionary, "en"),
+                ("chansonnier", EntryOrigin::User, "fr"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_complete() {
+        let (_dir, handle) = setup_test_db();
+        populate_test_data(&handle);
+        // A second entry for the same headword completes once
+        insert_word(&handle.conn, "help", "noun", "English", "en", 0).unwrap();
+        let helsinki = insert_word(&handle.conn, "Helsinki", "name", "English", "en", 0).unwrap();
+
+        // Without frequencies, alphabetical by key
+        assert_eq!(
+            complete(&handle, "hel", 3).unwrap(),
+            ["helicopter", "hello", "help"]
+        );
+
+        handle
+            .conn
+            .execute("UPDATE words SET frequency = 500 WHERE word = 'helper'", [])
+            .unwrap();
+        handle
+            .conn
+            .execute(
+                "UPDATE words SET frequency = 90 WHERE word = 'Helsinki'",
+                [],
+            )
+            .unwrap();
+        crate::db::rebuild_completions(&handle.conn).unwrap();
+        assert_eq!(
+            complete(&handle, "HEL", 4).unwrap(),
+            ["helper", "Helsinki", "helicopter", "hello"]
+        );
+
+        crate::db::delete_word(&handle.conn, helsinki).unwrap();
+        assert_eq!(complete(&handle, "hels", 5).unwrap(), Vec::<String>::new());
+        assert!(complete(&handle, "", 5).unwrap().is_empty());
+        assert!(complete(&handle, "hel", 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_complete_ranks_every_key() {
+        let (_dir, handle) = setup_test_db();
+        // More keys under "a" than a scan of the first few thousand would
+        // see, the most frequent sorting last
+        let tx = handle.conn.unchecked_transaction().unwrap();
+        for i in 0..5000 {
+            tx.execute(
+                "INSERT INTO completions (key, word, weight) VALUES (?1, ?1, ?2)",
+                params![format!("a{i:04}"), i % 7],
+            )
+            .unwrap();
+        }
+        for (word, weight) in [("azure", 900), ("Azores", 800), ("abzu", 700)] {
+            tx.execute(
+                "INSERT INTO completions (key, word, weight) VALUES (?, ?, ?)",
+                params![word.to_lowercase(), word, weight],
+            )
+            .unwrap();
+        }
+        tx.commit().unwrap();
+
+        assert_eq!(
+            complete(&handle, "a", 3).unwrap(),
+            ["azure", "Azores", "abzu"]
+        );
+        assert_eq!(complete(&handle, "AZ", 5).unwrap(), ["azure", "Azores"]);
+        assert_eq!(complete(&handle, "azu", 5).unwrap(), ["azure"]);
+        // Among equal weights, alphabetical
+        assert_eq!(
+            complete(&handle, "a4", 3).unwrap(),
+            ["a4003", "a4010", "a4017"]
+        );
+
+        let plan: String = handle
+            .conn
+            .query_row(
+                "EXPLAIN QUERY PLAN SELECT word FROM completions
+                 WHERE substr(key, 1, 1) = 'a' AND key >= 'a' AND key < 'b'
+                 ORDER BY weight DESC, key, word LIMIT 3",
+                [],
+                |row| row.get(3),
+            )
+            .unwrap();
+        assert!(plan.contains("idx_completions_head1"), "{plan}");
+    }
+
+    #[cfg(feature = "fst")]
+    #[test]
+    fn test_prefix_and_complete_from_fst() {
+        let (dir, handle) = setup_test_db();
+        populate_test_data(&handle);
+        insert_word(&handle.conn, "Helsinki", "name", "English", "en", 0).unwrap();
+        handle
+            .conn
+            .execute(
+                "UPDATE words SET frequency = CASE word WHEN 'helper' THEN 500 WHEN 'Helsinki' THEN 90 END",
+                [],
+            )
+            .unwrap();
+        crate::db::rebuild_completions(&handle.conn).unwrap();
+        let words = |results: Vec<SearchResult>| -> Vec<String> {
+            results.into_iter().map(|r| r.word).collect()
+        };
+        let from_sql = words(search_prefix(&handle, "hel", 10).unwrap());
+        let completed = complete(&handle, "HEL", 4).unwrap();
+        assert!(handle.headword_fst().unwrap().is_none());
+
+        crate::fuzzy::build_fst(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        handle.clear_caches();
+        assert!(handle.headword_fst().unwrap().is_some());
+
+        let from_fst = words(search_prefix(&handle, "hel", 10).unwrap());
+        assert_eq!(
+            from_fst,
+            [
+                "help",
+                "hello",
+                "helper",
+                "helping",
+                "Helsinki",
+                "helicopter"
+            ]
+        );
+        assert_eq!(from_fst, from_sql);
+        assert_eq!(
+            words(search_prefix(&handle, "HEL", 2).unwrap()),
+            ["help", "hello"]
+        );
+
+        assert_eq!(complete(&handle, "HEL", 4).unwrap(), completed);
+        assert_eq!(completed, ["helper", "Helsinki", "helicopter", "hello"]);
+        assert!(complete(&handle, "xyz", 3).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_user_overlay() {
+        let (dir, handle) = setup_test_db();
+        populate_test_data(&handle);
+        let user_path = dir.path().join("user.db");
+        let user = init_database(user_path.to_str().unwrap()).unwrap();
+        for (word, definition) in [("help", "My own note"), ("helpmate", "A companion")] {
+            let id = insert_word(&user.conn, word, "noun", "English", "en", 0).unwrap();
+            insert_definition(&user.conn, id, definition, &[], &[]).unwrap();
+        }
+
+        let open = |precedence: OverlayPrecedence| {
+            let mut config = crate::DictConfig {
+                user_db: Some(user_path.clone()),
+                ..crate::DictConfig::default()
+            };
+            config.overlay.precedence = precedence;
+            let path = dir.path().join("test.db");
+            crate::db::open_readonly_with_config(path.to_str().unwrap(), config).unwrap()
+        };
+        let summary = |handle: &DictHandle, query: &str| -> Vec<(String, EntryOrigin)> {
+            search_words(handle, query, 4)
+                .unwrap()
+                .into_iter()
+                .map(|r| (r.word, r.origin))
+                .collect()
+        };
+        let main = |w: &str| (w.to_string(), EntryOrigin::Dictionary);
+        let own = |w: &str| (w.to_string(), EntryOrigin::User);
+
+        // Boosted: the user's exact match first, its prefix match after the
+        // shipped exact match
+        let boosted = open(OverlayPrecedence::Boost);
+        assert_eq!(
+            summary(&boosted, "help"),
+            [own("help"), main("help"), own("helpmate"), main("helper")]
+        );
+        let results = search_words(&boosted, "help", 1).unwrap();
+        assert_eq!(results[0].preview, "My own note");
+        assert_eq!(results[0].match_kind, MatchKind::Exact);
+        let entry = crate::db::get_user_definition(&boosted, results[0].id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.definitions[0].text, "My own note");
+
+        let replaced = open(OverlayPrecedence::Replace);
+        assert_eq!(
+            summary(&replaced, "help"),
+            [
+                own("help"),
+                own("helpmate"),
+                main("helper"),
+                main("helping")
+            ]
+        );
+        let first = open(OverlayPrecedence::UserFirst);
+        assert_eq!(
+            summary(&first, "hel"),
+            [own("help"), own("helpmate"), main("help"), main("hello")]
+        );
+        let off = open(OverlayPrecedence::Off);
+        assert!(summary(&off, "help")
+            .iter()
+            .all(|(_, o)| *o == EntryOrigin::Dictionary));
+
+        // Without a user database nothing changes
+        assert_eq!(summary(&handle, "helpmate"), Vec::new());
+        assert!(crate::db::get_user_definition(&handle, 1)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_tie_break() {
+        let (_dir, mut handle) = setup_test_db();
+        let mut ids = Vec::new();
+        for (word, pos) in [
+            ("Bark", "name"),
+            ("bark", "verb"),
+            ("bark", "noun"),
+            ("barn", "noun"),
+        ] {
+            let id = insert_word(&handle.conn, word, pos, "English", "en", 0).unwrap();
+            insert_definition(&handle.conn, id, "...", &[], &[]).unwrap();
+            ids.push(id);
+        }
+        let mut results = vec![
+            SearchResult::new(ids[2], "bark".into(), "noun".into(), String::new()),
+            SearchResult::new(ids[3], "barn".into(), "noun".into(), String::new()),
+            SearchResult::new(ids[0], "Bark".into(), "name".into(), String::new()),
+            SearchResult::new(ids[1], "bark".into(), "verb".into(), String::new()),
+        ];
+        for strategy in [
+            RankingStrategy::Relevance,
+            RankingStrategy::Alphabetical,
+            RankingStrategy::Frequency,
+        ] {
+            handle.config.ranking = strategy;
+            rank(&handle, &mut results).unwrap();
+            let order: Vec<i64> = results.iter().map(|r| r.id).collect();
+            assert_eq!(order, [ids[0], ids[1], ids[2], ids[3]]);
+            results.reverse();
+        }
+    }
+
+    #[test]
+    fn test_run_all_tiers() {
+        let (_dir, mut handle) = setup_test_db();
+        for word in [
+            "catamaran",
+            "catch-as-catch-can wrestling championship",
+            "cart",
+        ] {
+            let id = insert_word(&handle.conn, word, "noun", "English", "en", 0).unwrap();
+            insert_definition(&handle.conn, id, "...", &[], &[]).unwrap();
+        }
+        let words = |handle: &DictHandle| -> Vec<String> {
+            search_words(handle, "cat", 2)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.word)
+                .collect()
+        };
+
+        // Two prefix matches fill the page, so the fuzzy tier never runs
+        assert_eq!(
+            words(&handle),
+            ["catamaran", "catch-as-catch-can wrestling championship"]
+        );
+
+        handle.config.tiers.stop = StopBehavior::RunAll;
+        assert_eq!(words(&handle), ["catamaran", "cart"]);
+    }
+
+    #[test]
+    fn test_query_punctuation() {
+        let (_dir, mut handle) = setup_test_db();
+        for word in ["rock 'n' roll", "well-being"] {
+            let id = insert_word(&handle.conn, word, "noun", "English", "en", 0).unwrap();
+            insert_definition(&handle.conn, id, "...", &[], &[]).unwrap();
+        }
+        let top = |handle: &DictHandle, query: &str| {
+            search_words(handle, query, 1)
+                .unwrap()
+                .into_iter()
+                .next()
+                .map(|r| (r.word, r.match_kind))
+        };
+
+        assert_eq!(
+            top(&handle, "rock ’n’ roll"),
+            Some(("rock 'n' roll".to_string(), MatchKind::Exact))
+        );
+        assert_eq!(
+            top(&handle, "well–being"),
+            Some(("well-being".to_string(), MatchKind::Exact))
+        );
+
+        handle.config.query_replacements.clear();
+        assert_ne!(
+            top(&handle, "well–being").map(|(_, kind)| kind),
+            Some(MatchKind::Exact)
+        );
+    }
+
+    #[test]
+    fn test_query_confusables() {
+        let (_dir, mut handle) = setup_test_db();
+        for (word, language, code) in [
+            ("office", "English", "en"),
+            ("coat", "English", "en"),
+            ("сок", "Russian", "ru"),
+        ] {
+            let id = insert_word(&handle.conn, word, "noun", language, code, 0).unwrap();
+            insert_definition(&handle.conn, id, "...", &[], &[]).unwrap();
+        }
+        let top = |handle: &DictHandle, query: &str| {
+            search_words(handle, query, 1)
+                .unwrap()
+                .into_iter()
+                .next()
+                .filter(|r| r.match_kind == MatchKind::Exact)
+                .map(|r| r.word)
+        };
+
+        assert_eq!(top(&handle, "oﬃce").as_deref(), Some("office"));
+        // Cyrillic "с" and "о" pasted into a Latin word
+        assert_eq!(top(&handle, "\u{0441}\u{043E}at").as_deref(), Some("coat"));
+        assert_eq!(top(&handle, "сок").as_deref(), Some("сок"));
+
+        handle.config.confusables = false;
+        assert_eq!(top(&handle, "oﬃce"), None);
+    }
+
+    #[test]
+    fn test_case_insensitive_exact() {
+        let (_dir, handle) = setup_test_db();
+        for (word, pos) in [("Polish", "adj"), ("polish", "verb"), ("polished", "adj")] {
+            let id = insert_word(&handle.conn, word, pos, "English", "en", 0).unwrap();
+            insert_definition(&handle.conn, id, "...", &[], &[]).unwrap();
+        }
+        let results = |query: &str| -> Vec<(String, MatchKind)> {
+            search_words(&handle, query, 10)
+                .unwrap()
+                .into_iter()
+                .map(|r| (r.word, r.match_kind))
+                .collect()
+        };
+        let exact = |w: &str| (w.to_string(), MatchKind::Exact);
+
+        assert_eq!(
+            results("polish"),
+            [
+                exact("polish"),
+                exact("Polish"),
+                ("polished".to_string(), MatchKind::Prefix)
+            ]
+        );
+        assert_eq!(results("Polish")[..2], [exact("Polish"), exact("polish")]);
+        assert_eq!(results("POLISH")[..2], [exact("Polish"), exact("polish")]);
+    }
+
+    #[test]
+    fn test_fts_weights() {
+        let (_dir, mut handle) = setup_test_db();
+        for word in ["ice cream", "cream cheese cake"] {
+            let id = insert_word(&handle.conn, word, "noun", "English", "en", 0).unwrap();
+            insert_definition(&handle.conn, id, "...", &[], &[]).unwrap();
+        }
+        let ranks = |handle: &DictHandle| -> Vec<(String, f64)> {
+            search_fts(handle, "cream", 10)
+                .unwrap()
+                .into_iter()
+                .map(|r| (r.word, r.fts_rank.unwrap()))
+                .collect()
+        };
+
+        // The shorter headword ranks first
+        let plain = ranks(&handle);
+        assert_eq!(plain[0].0, "ice cream");
+        assert!(plain.iter().all(|(_, rank)| *rank < 0.0));
+
+        // A heavier column counts its matches more, ranking them better
+        handle.config.fts.weights = vec![2.0];
+        let weighted = ranks(&handle);
+        assert_eq!(weighted[0].0, "ice cream");
+        for ((_, a), (_, b)) in plain.iter().zip(&weighted) {
+            assert!(b < a);
+        }
+
+        handle.config.fts.weights = vec![f64::NAN];
+        assert!(search_fts(&handle, "cream", 10).is_err());
+    }
+
+    #[test]
+    fn test_content_filter() {
+        let (_dir, mut handle) = setup_test_db();
+        let dam = insert_word(&handle.conn, "dam", "noun", "English", "en", 0).unwrap();
+        insert_definition(&handle.conn, dam, "A barrier to water", &[], &[]).unwrap();
+        let damn = insert_word(&handle.conn, "damn", "verb", "English", "en", 0).unwrap();
+        insert_definition(&handle.conn, damn, "To condemn", &[], &[]).unwrap();
+        let tags = ["vulgar".to_string()];
+        insert_definition(&handle.conn, damn, "An expletive", &[], &tags).unwrap();
+
+        let words = |handle: &DictHandle| -> Vec<(String, bool)> {
+            search_words(handle, "dam", 10)
+                .unwrap()
+                .into_iter()
+                .map(|r| (r.word, r.flagged))
+                .collect()
+        };
+        let entry = |w: &str, flagged| (w.to_string(), flagged);
+        assert_eq!(words(&handle), [entry("dam", false), entry("damn", false)]);
+
+        handle.config.content_filter.mode = ContentFilterMode::Flag;
+        assert_eq!(words(&handle), [entry("dam", false), entry("damn", true)]);
+
+        handle.config.content_filter.mode = ContentFilterMode::Hide;
+        assert_eq!(words(&handle), [entry("dam", false)]);
+        for day in 0..20 {
+            let word = crate::db::get_word_of_the_day(&handle, day).unwrap();
+            assert_eq!(word.unwrap().word, "dam");
+            assert_eq!(
+                crate::db::get_random_word(&handle).unwrap().unwrap().id,
+                dam
+            );
+        }
+    }
+
+    #[test]
+    fn test_content_filter_after_spelling() {
+        let (_dir, mut handle) = setup_test_db();
+        let color = insert_word(&handle.conn, "color", "noun", "English", "en", 0).unwrap();
+        insert_definition(&handle.conn, color, "The hue of something", &[], &[]).unwrap();
+        let tags = ["vulgar".to_string()];
+        insert_definition(&handle.conn, color, "An expletive", &[], &tags).unwrap();
+        let colour = insert_word(&handle.conn, "colour", "noun", "English", "en", 0).unwrap();
+        let tags = ["UK".to_string()];
+        let sense = insert_definition(
+            &handle.conn,
+            colour,
+            "British standard spelling of color",
+            &[],
+            &tags,
+        )
+        .unwrap();
+        crate::db::set_form_of(&handle.conn, sense, "color").unwrap();
+        crate::db::resolve_form_of(&handle.conn).unwrap();
+
+        let words = |handle: &DictHandle, query: &str| -> Vec<(String, bool)> {
+            search_words(handle, query, 10)
+                .unwrap()
+                .into_iter()
+                .map(|r| (r.word, r.flagged))
+                .collect()
+        };
+        let entry = |w: &str, flagged| (w.to_string(), flagged);
+
+        // The swapped-in spelling is the one checked
+        handle.config.spelling = Some(SpellingRegion::Us);
+        handle.config.content_filter.mode = ContentFilterMode::Flag;
+        assert_eq!(words(&handle, "colour"), [entry("color", true)]);
+        handle.config.content_filter.mode = ContentFilterMode::Hide;
+        assert!(words(&handle, "colour").is_empty());
+
+        // And the spelling swapped out no longer counts
+        handle.config.spelling = Some(SpellingRegion::Uk);
+        assert_eq!(words(&handle, "color"), [entry("colour", false)]);
+        handle.config.content_filter.mode = ContentFilterMode::Flag;
+        assert_eq!(words(&handle, "color"), [entry("colour", false)]);
+    }
+
+    #[test]
+    fn test_search_errors() {
+        let (_dir, handle) = setup_test_db();
+        populate_test_data(&handle);
+
+        // Garbage in the full-text index's segments
+        handle
+            .conn
+            .execute(
+                "UPDATE words_fts_data SET block = X'00ff00ff' WHERE id > 10",
+                [],
+            )
+            .unwrap();
+        let result = search_words(&handle, "hello world", 10);
+        assert!(matches!(result, Err(Error::FtsCorrupt(_))), "{result:?}");
+
+        let syntax = rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_ERROR),
+            Some("fts5: syntax error near \"\"".to_string()),
+        );
+        assert!(matches!(
+            search_error(syntax.into()),
+            Error::InvalidQuery(_)
+        ));
+        let other = Error::InvalidInput("weights".to_string());
+        assert!(matches!(search_error(other), Error::InvalidInput(_)));
+    }
+
+    #[test]
+    fn test_substring_fallback() {
+        let (_dir, mut handle) = setup_test_db();
+        populate_test_data(&handle);
+        assert!(handle.capabilities().fts5);
+
+        // As on a SQLite build without FTS5
+        handle.capabilities.fts5 = false;
+        let results = search_words(&handle, "copter", 10).unwrap();
+        assert_eq!(results[0].word, "helicopter");
+        assert_eq!(results[0].match_kind, MatchKind::FullText);
+        assert!(results[0].fts_rank.is_none());
+        let words: Vec<String> = search_words(&handle, "orke", 10)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.word)
+            .collect();
+        // "work" is within two edits
+        assert_eq!(words, ["worker", "work"]);
+        // LIKE wildcards in the query are matched literally
+        assert!(search_substring(&handle, "e_p", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_time_budget() {
+        let (_dir, handle) = setup_test_db();
+        populate_test_data(&handle);
+
+        let plain = search_words_offset(&handle, "help", 10, 0).unwrap();
+        let unbounded =
+            search_words_with_options(&handle, "help", 10, 0, &SearchOptions::default()).unwrap();
+        assert!(!unbounded.truncated);
+        assert_eq!(unbounded.results.len(), plain.len());
+
+        let options = SearchOptions {
+            time_budget_ms: Some(60_000),
+            ..SearchOptions::default()
+        };
+        let page = search_words_with_options(&handle, "help", 10, 0, &options).unwrap();
+        assert!(!page.truncated);
+        assert_eq!(page.results.len(), plain.len());
+
+        // A spent budget still looks up exact matches, but nothing else
+        let options = SearchOptions {
+            time_budget_ms: Some(0),
+            ..SearchOptions::default()
+        };
+        let page = search_words_with_options(&handle, "help", 10, 0, &options).unwrap();
+        assert!(page.truncated);
+        let words: Vec<&str> = page.results.iter().map(|r| r.word.as_str()).collect();
+        assert_eq!(words, ["help"]);
+        assert_eq!(page.results[0].preview, "To assist someone");
+    }
+
+    #[test]
+    fn test_time_budget_stops_running_tier() {
+        let (_dir, handle) = setup_test_db();
+        populate_test_data(&handle);
+        let mut timer = TierTimer {
+            deadline: Some(Instant::now() + Duration::from_millis(50)),
+            conn: Some(&handle.conn),
+            ..TierTimer::default()
+        };
+
+        // A statement still running at the deadline is stopped, and the
+        // tier returns nothing instead of failing the search
+        let started = Instant::now();
+        let results = timer
+            .run("fts", || {
+                handle.conn.query_row(
+                    "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n)
+                     SELECT count(*) FROM n",
+                    [],
+                    |row| row.get::<_, i64>(0),
+                )?;
+                Ok(Vec::new())
+            })
+            .unwrap();
+        assert!(results.is_empty());
+        assert!(timer.truncated);
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        // Exact matches are still looked up, and the handler is gone
+        let results = timer
+            .run("exact", || search_exact(&handle, "help", 10))
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        let count: i64 = handle
+            .conn
+            .query_row(
+                "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 100000)
+                 SELECT count(*) FROM n",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 100_000);
+
+        // A tier that overruns without SQLite noticing keeps its results
+        let mut timer = TierTimer {
+            deadline: Some(Instant::now() + Duration::from_millis(10)),
+            conn: Some(&handle.conn),
+            ..TierTimer::default()
+        };
+        let results = timer
+            .run("prefix", || {
+                std::thread::sleep(Duration::from_millis(20));
+                search_prefix(&handle, "hel", 10)
+            })
+            .unwrap();
+        assert!(!results.is_empty());
+        assert!(timer.truncated);
+
+        // The fuzzy tier looks up no keys past its deadline
+        let past = Some(Instant::now());
+        assert!(search_fuzzy(&handle, "helo", 10, past).unwrap().is_empty());
+        assert!(!search_fuzzy(&handle, "helo", 10, None).unwrap().is_empty());
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_search_words_traced() {
+        let (_dir, handle) = setup_test_db();
+        populate_test_data(&handle);
+
+        let traced = search_words_traced(&handle, "help", 3, 0).unwrap();
+        let plain = search_words_offset(&handle, "help", 3, 0).unwrap();
+        let ids: Vec<i64> = traced.results.iter().map(|r| r.id).collect();
+        assert_eq!(ids, plain.iter().map(|r| r.id).collect::<Vec<_>>());
+
+        let tiers: Vec<&str> = traced
+            .timings
+            .tiers
+            .iter()
+            .map(|t| t.tier.as_str())
+            .collect();
+        assert_eq!(tiers[..2], ["exact", "symbols"]);
+        assert!(tiers.contains(&"prefix"));
+        assert_eq!(traced.timings.tiers[0].results, 1);
+        let tier_micros: u64 = traced.timings.tiers.iter().map(|t| t.micros).sum();
+        assert!(traced.timings.total_micros >= tier_micros);
+
+        // The exact match fills a one-result page, so later tiers are skipped
+        let traced = search_words_traced(&handle, "help", 1, 0).unwrap();
+        let tiers: Vec<&str> = traced
+            .timings
+            .tiers
+            .iter()
+            .map(|t| t.tier.as_str())
+            .collect();
+        assert_eq!(tiers, ["exact", "symbols"]);
+    }
 }
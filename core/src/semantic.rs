@@ -0,0 +1,480 @@
+//! Concept search over gloss embeddings
+//!
+//! Glosses are embedded ahead of time with any sentence-embedding model
+//! and the vectors are stored next to the definitions, quantized to one
+//! byte per dimension. At query time the app embeds the query with the same
+//! model and [`search_semantic`] returns the words whose glosses are
+//! closest, so "tiny horse" finds "pony" although no word matches.
+//!
+//! Vectors are supplied as JSONL, one gloss per line:
+//!
+//! ```text
+//! {"text": "A small horse.", "vector": [0.013, -0.094, ...]}
+//! ```
+//!
+//! Lines are matched to definitions by their exact text, so a vector file
+//! computed from a kaikki dump applies to any database built from it.
+//!
+//! [`build_related`] also precomputes each word's nearest neighbors into
+//! the `related_semantic` table, which `db::get_semantically_related`
+//! reads without any model on the device.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use rusqlite::{params, Connection};
+use serde::Deserialize;
+
+use crate::models::SearchResult;
+use crate::{DictHandle, Error, Result};
+
+/// Quantized gloss vectors, one row per embedded definition
+const EMBEDDINGS_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS embeddings (
+    definition_id INTEGER PRIMARY KEY,
+    word_id INTEGER NOT NULL,
+    scale REAL NOT NULL,  -- multiply the int8 components by this
+    vector BLOB NOT NULL, -- int8 components of the unit-length vector
+    FOREIGN KEY (definition_id) REFERENCES definitions(id) ON DELETE CASCADE
+);
+"#;
+
+/// Nearest neighbors of each embedded word, by `rank` (0 = closest)
+const RELATED_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS related_semantic (
+    word_id INTEGER NOT NULL,
+    rank INTEGER NOT NULL,
+    related_id INTEGER NOT NULL,
+    similarity REAL NOT NULL,  -- cosine similarity of the word vectors
+    PRIMARY KEY (word_id, rank),
+    FOREIGN KEY (word_id) REFERENCES words(id) ON DELETE CASCADE,
+    FOREIGN KEY (related_id) REFERENCES words(id) ON DELETE CASCADE
+) WITHOUT ROWID;
+"#;
+
+/// Result of loading a vector file into a database
+#[derive(Debug, Clone, Default)]
+pub struct EmbeddingStats {
+    /// Vectors read from the file
+    pub vectors_read: u64,
+    /// Vectors whose text matched no definition
+    pub vectors_unmatched: u64,
+    /// Lines that were not valid JSON, had no vector or the wrong dimension
+    pub invalid_lines: u64,
+    /// Definitions that received a vector
+    pub definitions_embedded: u64,
+    /// Dimension of the vectors
+    pub dimensions: usize,
+}
+
+/// Result of precomputing related words with [`build_related`]
+#[derive(Debug, Clone, Default)]
+pub struct RelatedStats {
+    /// Words with at least one embedded gloss
+    pub words: u64,
+    /// Rows written to `related_semantic`
+    pub links: u64,
+}
+
+#[derive(Deserialize)]
+struct VectorLine {
+    text: String,
+    vector: Vec<f32>,
+}
+
+/// Load gloss embeddings from a JSONL vector file into a database
+///
+/// Replaces any embeddings stored before. `model` is recorded in the
+/// `embedding_model` meta key so apps can check they embed queries with
+/// the same model.
+pub fn import_embeddings(
+    db_path: &str,
+    vectors_path: &str,
+    model: Option<&str>,
+) -> Result<EmbeddingStats> {
+    let conn = Connection::open(db_path)?;
+    conn.execute_batch(EMBEDDINGS_SCHEMA)?;
+
+    // Definition text -> (definition id, word id)
+    let mut definitions: HashMap<String, Vec<(i64, i64)>> = HashMap::new();
+    {
+        let mut stmt = conn.prepare("SELECT id, word_id, definition FROM definitions")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+        for row in rows {
+            let (id, word_id, text) = row?;
+            definitions.entry(text).or_default().push((id, word_id));
+        }
+    }
+
+    let mut stats = EmbeddingStats::default();
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM embeddings", [])?;
+    {
+        let mut insert = tx.prepare(
+            "INSERT OR REPLACE INTO embeddings (definition_id, word_id, scale, vector) VALUES (?, ?, ?, ?)",
+        )?;
+        let reader = BufReader::new(File::open(vectors_path)?);
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Ok(entry) = serde_json::from_str::<VectorLine>(&line) else {
+                stats.invalid_lines += 1;
+                continue;
+            };
+            if stats.dimensions == 0 {
+                stats.dimensions = entry.vector.len();
+            }
+            let Some((scale, quantized)) =
+                quantize(&entry.vector).filter(|_| entry.vector.len() == stats.dimensions)
+            else {
+                stats.invalid_lines += 1;
+                continue;
+            };
+            stats.vectors_read += 1;
+
+            let Some(targets) = definitions.get(&entry.text) else {
+                stats.vectors_unmatched += 1;
+                continue;
+            };
+            let bytes: Vec<u8> = quantized.iter().map(|&c| c as u8).collect();
+            for (definition_id, word_id) in targets {
+                insert.execute(params![definition_id, word_id, scale, bytes])?;
+                stats.definitions_embedded += 1;
+            }
+        }
+    }
+    crate::db::set_meta(&tx, "embedding_dimensions", &stats.dimensions.to_string())?;
+    if let Some(model) = model {
+        crate::db::set_meta(&tx, "embedding_model", model)?;
+    }
+    tx.commit()?;
+
+    Ok(stats)
+}
+
+/// Find the `k` words whose glosses are closest to `query_embedding`
+///
+/// The query must come from the model the stored vectors were built with.
+/// Each word appears once, with its closest gloss as the preview; the
+/// score is the cosine distance (0 = same direction, lower is better).
+/// Databases without embeddings return no results.
+pub fn search_semantic(
+    handle: &DictHandle,
+    query_embedding: &[f32],
+    k: u32,
+) -> Result<Vec<SearchResult>> {
+    if !crate::db::table_exists(&handle.conn, "embeddings")? {
+        return Ok(Vec::new());
+    }
+    let Some(query) = normalize(query_embedding) else {
+        return Err(Error::InvalidInput("query embedding is zero".to_string()));
+    };
+
+    // Best (similarity, definition id) per word
+    let mut best: HashMap<i64, (f32, i64)> = HashMap::new();
+    let mut stmt = handle
+        .conn
+        .prepare("SELECT definition_id, word_id, scale, vector FROM embeddings")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let definition_id: i64 = row.get(0)?;
+        let word_id: i64 = row.get(1)?;
+        let scale: f32 = row.get::<_, f64>(2)? as f32;
+        let vector = row.get_ref(3)?.as_blob().map_err(rusqlite::Error::from)?;
+        if vector.len() != query.len() {
+            return Err(Error::InvalidInput(format!(
+                "query embedding has {} dimensions, stored vectors have {}",
+                query.len(),
+                vector.len()
+            )));
+        }
+
+        let dot: f32 = vector
+            .iter()
+            .zip(&query)
+            .map(|(&c, q)| (c as i8) as f32 * q)
+            .sum();
+        let similarity = dot * scale;
+        let entry = best.entry(word_id).or_insert((f32::MIN, definition_id));
+        if similarity > entry.0 {
+            *entry = (similarity, definition_id);
+        }
+    }
+
+    let mut ranked: Vec<(i64, f32, i64)> = best
+        .into_iter()
+        .map(|(word_id, (similarity, definition_id))| (word_id, similarity, definition_id))
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    ranked.truncate(k as usize);
+
+    let mut lookup = handle.conn.prepare(
+        r#"
+        SELECT w.word, w.pos, d.definition
+        FROM words w, definitions d
+        WHERE w.id = ? AND d.id = ?
+        "#,
+    )?;
+    let mut results = Vec::with_capacity(ranked.len());
+    for (word_id, similarity, definition_id) in ranked {
+        let (word, pos, preview) = lookup.query_row(params![word_id, definition_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+        let mut result =
+            SearchResult::with_score(word_id, word, pos, preview, f64::from(1.0 - similarity));
+        result.relevance = crate::search::relevance(result.score);
+        results.push(result);
+    }
+    Ok(results)
+}
+
+/// Store the `k` nearest neighbors of every embedded word in the
+/// `related_semantic` table, replacing those stored before
+///
+/// A word's vector is the mean of its gloss vectors, and neighbors are
+/// words of the same language with the highest cosine similarity. Every
+/// pair of words in a language is compared, so this is meant for the
+/// preprocessor, not the device.
+pub fn build_related(db_path: &str, k: u32) -> Result<RelatedStats> {
+    let conn = Connection::open(db_path)?;
+    let mut stats = RelatedStats::default();
+    if !crate::db::table_exists(&conn, "embeddings")? {
+        return Ok(stats);
+    }
+    conn.execute_batch(RELATED_SCHEMA)?;
+
+    // Language -> (word id, summed gloss vectors), in word id order
+    let mut languages: HashMap<String, Vec<(i64, Vec<f32>)>> = HashMap::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT e.word_id, w.language, e.scale, e.vector
+             FROM embeddings e JOIN words w ON w.id = e.word_id
+             ORDER BY e.word_id",
+        )?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let word_id: i64 = row.get(0)?;
+            let language: String = row.get(1)?;
+            let scale = row.get::<_, f64>(2)? as f32;
+            let vector = row.get_ref(3)?.as_blob().map_err(rusqlite::Error::from)?;
+            let words = languages.entry(language).or_default();
+            if words.last().is_none_or(|(id, _)| *id != word_id) {
+                words.push((word_id, vec![0.0; vector.len()]));
+            }
+            let (_, sum) = words.last_mut().expect("pushed above");
+            for (s, &c) in sum.iter_mut().zip(vector) {
+                *s += (c as i8) as f32 * scale;
+            }
+        }
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM related_semantic", [])?;
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO related_semantic (word_id, rank, related_id, similarity) VALUES (?, ?, ?, ?)",
+        )?;
+        for words in languages.values() {
+            let words: Vec<(i64, Vec<f32>)> = words
+                .iter()
+                .filter_map(|(id, sum)| Some((*id, normalize(sum)?)))
+                .collect();
+            stats.words += words.len() as u64;
+            for (id, vector) in &words {
+                let mut neighbors: Vec<(f32, i64)> = words
+                    .iter()
+                    .filter(|(other, _)| other != id)
+                    .map(|(other, v)| (vector.iter().zip(v).map(|(a, b)| a * b).sum(), *other))
+                    .collect();
+                neighbors.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+                neighbors.truncate(k as usize);
+                for (rank, (similarity, other)) in neighbors.into_iter().enumerate() {
+                    insert.execute(params![id, rank as i64, other, f64::from(similarity)])?;
+                    stats.links += 1;
+                }
+            }
+        }
+    }
+    crate::db::set_meta(&tx, "related_semantic_k", &k.to_string())?;
+    tx.commit()?;
+
+    Ok(stats)
+}
+
+/// Scale a vector to unit length; `None` for the zero vector
+fn normalize(vector: &[f32]) -> Option<Vec<f32>> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 || !norm.is_finite() {
+        return None;
+    }
+    Some(vector.iter().map(|x| x / norm).collect())
+}
+
+/// Quantize a vector to int8 after scaling it to unit length
+///
+/// Returns the scale to multiply components by and the components.
+fn quantize(vector: &[f32]) -> Option<(f32, Vec<i8>)> {
+    let unit = normalize(vector)?;
+    let max = unit.iter().fold(0.0f32, |m, x| m.max(x.abs()));
+    let scale = max / 127.0;
+    let components = unit.iter().map(|x| (x / scale).round() as i8).collect();
+    Some((scale, components))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_database, insert_definition, insert_word};
+
+    #[test]
+    fn test_quantize_round_trip() {
+        let (scale, components) = quantize(&[3.0, -4.0, 0.0]).unwrap();
+        let restored: Vec<f32> = components.iter().map(|&c| c as f32 * scale).collect();
+        assert!((restored[0] - 0.6).abs() < 0.01);
+        assert!((restored[1] + 0.8).abs() < 0.01);
+        assert_eq!(restored[2], 0.0);
+        assert!(quantize(&[0.0, 0.0]).is_none());
+    }
+
+    #[test]
+    fn test_import_and_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let handle = init_database(db_path.to_str().unwrap()).unwrap();
+        for (word, gloss) in [
+            ("pony", "A small horse."),
+            ("horse", "A large hoofed mammal."),
+            ("violin", "A bowed string instrument."),
+        ] {
+            let word_id = insert_word(&handle.conn, word, "noun", "English", "en", 0).unwrap();
+            insert_definition(&handle.conn, word_id, gloss, &[], &[]).unwrap();
+        }
+
+        let vectors_path = dir.path().join("vectors.jsonl");
+        std::fs::write(
+            &vectors_path,
+            concat!(
+                "{\"text\": \"A small horse.\", \"vector\": [0.9, 0.5, 0.0]}\n",
+                "{\"text\": \"A large hoofed mammal.\", \"vector\": [0.9, -0.3, 0.1]}\n",
+                "{\"text\": \"A bowed string instrument.\", \"vector\": [0.0, 0.1, 1.0]}\n",
+                "{\"text\": \"Not in the dictionary.\", \"vector\": [1.0, 0.0, 0.0]}\n",
+                "{\"text\": \"Wrong dimension.\", \"vector\": [1.0, 0.0]}\n",
+                "not json\n",
+            ),
+        )
+        .unwrap();
+
+        let stats = import_embeddings(
+            db_path.to_str().unwrap(),
+            vectors_path.to_str().unwrap(),
+            Some("test-model"),
+        )
+        .unwrap();
+        assert_eq!(stats.dimensions, 3);
+        assert_eq!(stats.vectors_read, 4);
+        assert_eq!(stats.vectors_unmatched, 1);
+        assert_eq!(stats.invalid_lines, 2);
+        assert_eq!(stats.definitions_embedded, 3);
+
+        // "tiny horse"
+        let results = search_semantic(&handle, &[0.8, 0.6, 0.0], 2).unwrap();
+        let words: Vec<&str> = results.iter().map(|r| r.word.as_str()).collect();
+        assert_eq!(words, ["pony", "horse"]);
+        assert!(results[0].score < 0.05);
+        assert_eq!(results[0].preview, "A small horse.");
+
+        assert!(matches!(
+            search_semantic(&handle, &[1.0, 0.0], 2),
+            Err(Error::InvalidInput(_))
+        ));
+        let metadata = crate::db::get_metadata(&handle).unwrap();
+        assert_eq!(metadata.get("embedding_model").unwrap(), "test-model");
+
+        assert!(crate::db::get_semantically_related(&handle, 1, 5)
+            .unwrap()
+            .is_empty());
+        let related = build_related(db_path.to_str().unwrap(), 1).unwrap();
+        assert_eq!((related.words, related.links), (3, 3));
+        let pony = results[0].id;
+        let similar = crate::db::get_semantically_related(&handle, pony, 5).unwrap();
+        assert_eq!(similar.len(), 1);
+        assert_eq!(similar[0].word, "horse");
+        assert_eq!(similar[0].preview, "A large hoofed mammal.");
+        assert!(similar[0].score > 0.0 && similar[0].score < 0.5);
+    }
+
+    #[test]
+    fn test_semantically_related() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let handle = init_database(db_path.to_str().unwrap()).unwrap();
+        let mut ids = HashMap::new();
+        for (word, language, gloss) in [
+            ("pony", "English", "A small horse."),
+            ("horse", "English", "A large hoofed mammal."),
+            ("donkey", "English", "A long-eared hoofed mammal."),
+            ("violin", "English", "A bowed string instrument."),
+            ("cheval", "French", "Horse."),
+            ("fiddle", "English", "Unembedded."),
+        ] {
+            let word_id = insert_word(&handle.conn, word, "noun", language, "", 0).unwrap();
+            insert_definition(&handle.conn, word_id, gloss, &[], &[]).unwrap();
+            ids.insert(word, word_id);
+        }
+        let vectors_path = dir.path().join("vectors.jsonl");
+        std::fs::write(
+            &vectors_path,
+            concat!(
+                "{\"text\": \"A small horse.\", \"vector\": [1.0, 0.2, 0.0]}\n",
+                "{\"text\": \"A large hoofed mammal.\", \"vector\": [1.0, 0.0, 0.0]}\n",
+                "{\"text\": \"A long-eared hoofed mammal.\", \"vector\": [0.8, -0.5, 0.1]}\n",
+                "{\"text\": \"A bowed string instrument.\", \"vector\": [0.0, 0.1, 1.0]}\n",
+                "{\"text\": \"Horse.\", \"vector\": [1.0, 0.1, 0.0]}\n",
+            ),
+        )
+        .unwrap();
+        import_embeddings(
+            db_path.to_str().unwrap(),
+            vectors_path.to_str().unwrap(),
+            None,
+        )
+        .unwrap();
+        let related = |word: &str, k: u32| -> Vec<String> {
+            crate::db::get_semantically_related(&handle, ids[word], k)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.word)
+                .collect()
+        };
+
+        // Nothing before the table is built
+        assert!(related("pony", 5).is_empty());
+
+        let stats = build_related(db_path.to_str().unwrap(), 3).unwrap();
+        assert_eq!((stats.words, stats.links), (5, 12));
+
+        // Closest first, within the language, up to k
+        assert_eq!(related("pony", 5), ["horse", "donkey", "violin"]);
+        assert_eq!(related("pony", 1), ["horse"]);
+        let scores: Vec<f64> = crate::db::get_semantically_related(&handle, ids["pony"], 3)
+            .unwrap()
+            .iter()
+            .map(|r| r.score)
+            .collect();
+        assert!(scores.windows(2).all(|w| w[0] <= w[1]));
+
+        // Nothing related: alone in its language, or without a vector
+        assert!(related("cheval", 5).is_empty());
+        assert!(related("fiddle", 5).is_empty());
+        assert!(related("pony", 0).is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+//! Spell checking against the dictionary
+//!
+//! Lets other apps use a dictionary database as a spell-check backend: a
+//! word is spelled correctly when it is a headword or a known inflected
+//! form, and corrections are the closest headwords by edit distance, the
+//! more frequent first when equally close.
+
+use rusqlite::params;
+
+use crate::search::MatchStrategy;
+use crate::{DictHandle, Result};
+
+/// Fuzzy candidates fetched before ranking suggestions
+const SUGGESTION_CANDIDATES: u32 = 100;
+
+/// A misspelled word found by [`check_text`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Misspelling {
+    /// Byte offset of the word in the text
+    pub start: usize,
+    /// Byte offset just past the word
+    pub end: usize,
+    /// The word as written
+    pub word: String,
+    /// Corrections, best first
+    pub suggestions: Vec<String>,
+}
+
+/// Whether `word` is spelled correctly
+///
+/// True for headwords, inflected forms the dictionary records or derives
+/// (see `morph`), and capitalized or upper-case spellings of those ("The",
+/// "HELLO"). Tokens without letters, such as numbers, are always accepted.
+pub fn check(handle: &DictHandle, word: &str) -> Result<bool> {
+    let word = word.trim();
+    if !word.chars().any(char::is_alphabetic) {
+        return Ok(true);
+    }
+    if is_known(handle, word)? {
+        return Ok(true);
+    }
+    let lower = word.to_lowercase();
+    Ok(lower != word && is_known(handle, &lower)?)
+}
+
+/// Suggest up to `n` corrections for `word`
+///
+/// Candidates are headwords within a small edit distance, ordered by
+/// distance, then by corpus frequency when the database has one, then
+/// alphabetically. The case of a capitalized `word` is carried over.
+pub fn suggest(handle: &DictHandle, word: &str, n: usize) -> Result<Vec<String>> {
+    let word = word.trim();
+    let lower = word.to_lowercase();
+    let candidates = crate::search::match_words(
+        handle,
+        &lower,
+        MatchStrategy::Levenshtein,
+        SUGGESTION_CANDIDATES,
+    )?;
+
+    let has_frequency = crate::db::has_column(&handle.conn, "words", "frequency")?;
+    let mut frequency = handle
+        .conn
+        .prepare_cached("SELECT MAX(frequency) FROM words WHERE word = ?")?;
+
+    let mut ranked: Vec<(usize, i64, String)> = Vec::new();
+    for candidate in candidates {
+        if ranked.iter().any(|(_, _, w)| *w == candidate.word) {
+            continue;
+        }
+        let distance =
+            crate::text_distance::levenshtein_distance(&lower, &candidate.word.to_lowercase());
+        let count: i64 = if has_frequency {
+            frequency
+                .query_row(params![candidate.word], |row| row.get::<_, Option<i64>>(0))?
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        ranked.push((distance, count, candidate.word));
+    }
+    ranked.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)).then(a.2.cmp(&b.2)));
+
+    let capitalized = word.chars().next().is_some_and(char::is_uppercase);
+    Ok(ranked
+        .into_iter()
+        .take(n)
+        .map(|(_, _, suggestion)| {
+            if capitalized {
+                capitalize(&suggestion)
+            } else {
+                suggestion
+            }
+        })
+        .collect())
+}
+
+/// Check every word of a document
+///
+/// Splits `text` into words (letters, digits, apostrophes and inner
+/// hyphens) and returns the misspelled ones with up to `n` suggestions
+/// each, in document order. Each distinct word is looked up once.
+pub fn check_text(handle: &DictHandle, text: &str, n: usize) -> Result<Vec<Misspelling>> {
+    let mut verdicts: std::collections::HashMap<&str, Option<Vec<String>>> =
+        std::collections::HashMap::new();
+    let mut misspellings = Vec::new();
+
+    for (start, word) in crate::text::tokens(text) {
+        let verdict = match verdicts.get(word) {
+            Some(verdict) => verdict.clone(),
+            None => {
+                let verdict = if check(handle, word)? {
+                    None
+                } else {
+                    Some(suggest(handle, word, n)?)
+                };
+                verdicts.insert(word, verdict.clone());
+                verdict
+            }
+        };
+        if let Some(suggestions) = verdict {
+            misspellings.push(Misspelling {
+                start,
+                end: start + word.len(),
+                word: word.to_string(),
+                suggestions,
+            });
+        }
+    }
+    Ok(misspellings)
+}
+
+/// Whether `word` is a headword or an inflected form of one
+fn is_known(handle: &DictHandle, word: &str) -> Result<bool> {
+    let mut stmt = handle
+        .conn
+        .prepare_cached("SELECT 1 FROM words WHERE word = ? LIMIT 1")?;
+    if stmt.exists([word])? {
+        return Ok(true);
+    }
+    Ok(!crate::morph::lemmatize_any(handle, word)?.is_empty())
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_database, insert_word};
+
+    fn setup() -> (tempfile::TempDir, DictHandle) {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let handle = init_database(db_path.to_str().unwrap()).unwrap();
+        for (word, frequency) in [
+            ("the", 1000),
+            ("house", 500),
+            ("horse", 300),
+            ("hose", 10),
+            ("cat", 200),
+        ] {
+            let id = insert_word(&handle.conn, word, "noun", "English", "en", 0).unwrap();
+            handle
+                .conn
+                .execute(
+                    "UPDATE words SET frequency = ? WHERE id = ?",
+                    params![frequency, id],
+                )
+                .unwrap();
+        }
+        (dir, handle)
+    }
+
+    #[test]
+    fn test_check() {
+        let (_dir, handle) = setup();
+        assert!(check(&handle, "house").unwrap());
+        assert!(check(&handle, "The").unwrap());
+        assert!(check(&handle, "cats").unwrap());
+        assert!(check(&handle, "42").unwrap());
+        assert!(!check(&handle, "hosue").unwrap());
+    }
+
+    #[test]
+    fn test_suggest_prefers_frequent_words() {
+        let (_dir, handle) = setup();
+        // "hore" is one edit from "horse" and "hose", two from "house"
+        assert_eq!(
+            suggest(&handle, "hore", 3).unwrap(),
+            ["horse", "hose", "house"]
+        );
+        assert_eq!(suggest(&handle, "Hore", 1).unwrap(), ["Horse"]);
+        assert!(suggest(&handle, "xyzzy", 3).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_check_text() {
+        let (_dir, handle) = setup();
+        let text = "The hrse and the cat, the hrse!";
+        let misspellings = check_text(&handle, text, 1).unwrap();
+        assert_eq!(misspellings.len(), 3);
+        assert_eq!(misspellings[0].word, "hrse");
+        assert_eq!(&text[misspellings[0].start..misspellings[0].end], "hrse");
+        assert_eq!(misspellings[0].suggestions, ["horse"]);
+        assert_eq!(misspellings[1].word, "and");
+        assert_eq!(misspellings[2].start, 26);
+    }
+}
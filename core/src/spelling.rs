@@ -0,0 +1,166 @@
+//! US and UK spelling pairs
+//!
+//! Wiktionary gives regional spellings such as "colour" their own entry,
+//! with a sense tagged "UK" or "Commonwealth" pointing at the other
+//! spelling ("British and Commonwealth standard spelling of color"). There
+//! is no separate table of pairs: they are read from these form-of senses,
+//! in either direction, so a pair is found whichever spelling is looked
+//! up. [`crate::DictConfig::spelling`] uses them to show the reader's
+//! spelling first and the other as "also spelled".
+
+use rusqlite::params_from_iter;
+
+use crate::config::SpellingRegion;
+use crate::db::{dated_sense_condition, has_column};
+use crate::{DictHandle, Result};
+
+const REGIONS: [SpellingRegion; 2] = [SpellingRegion::Us, SpellingRegion::Uk];
+
+/// Other regional spellings of the entry `word_id`, in dictionary order
+///
+/// These are the entries with a region-tagged sense pointing at this one
+/// and the targets of this entry's own region-tagged form-of senses, so
+/// "color" and "colour" give each other whichever carries the tags.
+/// Databases built before form-of links have none.
+pub fn variants(handle: &DictHandle, word_id: i64) -> Result<Vec<String>> {
+    if !has_column(&handle.conn, "definitions", "form_of_id")? {
+        return Ok(Vec::new());
+    }
+    let tags: Vec<&str> = REGIONS.iter().flat_map(|r| r.tags()).copied().collect();
+    let sql = format!(
+        r#"
+        SELECT w.word FROM definitions d JOIN words w ON w.id = d.word_id
+        WHERE d.form_of_id = ?1 AND {tagged}
+        UNION
+        SELECT w.word FROM definitions d JOIN words w ON w.id = d.form_of_id
+        WHERE d.word_id = ?1 AND {tagged}
+        "#,
+        tagged = dated_sense_condition("d", 2, tags.len()),
+    );
+    let mut stmt = handle.conn.prepare_cached(&sql)?;
+    let mut values: Vec<&dyn rusqlite::ToSql> = vec![&word_id];
+    values.extend(tags.iter().map(|t| t as &dyn rusqlite::ToSql));
+    let rows = stmt.query_map(params_from_iter(values), |row| row.get::<_, String>(0))?;
+
+    let own: Option<String> = handle
+        .conn
+        .query_row("SELECT word FROM words WHERE id = ?", [word_id], |row| {
+            row.get(0)
+        })
+        .ok();
+    let mut words: Vec<String> = Vec::new();
+    for word in rows {
+        let word = word?;
+        if Some(&word) != own.as_ref() && !words.contains(&word) {
+            words.push(word);
+        }
+    }
+    Ok(words)
+}
+
+/// The entry to show instead of `word_id` for a reader preferring
+/// `region`: its id, headword and part of speech
+///
+/// That is the target of a sense of `word_id` tagged with the other
+/// region ("colour" gives "color" for US readers), or else an entry with
+/// a sense tagged with `region` pointing at `word_id` ("color" gives
+/// "colour" for UK readers). None when the entry is already the reader's
+/// spelling or has no regional pair.
+pub(crate) fn preferred_entry(
+    handle: &DictHandle,
+    word_id: i64,
+    region: SpellingRegion,
+) -> Result<Option<(i64, String, String)>> {
+    if !has_column(&handle.conn, "definitions", "form_of_id")? {
+        return Ok(None);
+    }
+    let preferred = region.tags();
+    let other = region.other().tags();
+    // ?1 is the entry, then the other region's tags, then the preferred
+    let other_tagged = dated_sense_condition("d", 2, other.len());
+    let preferred_tagged = dated_sense_condition("d", 2 + other.len(), preferred.len());
+    let sql = format!(
+        r#"
+        SELECT t.id, t.word, t.pos FROM definitions d JOIN words t ON t.id = d.form_of_id
+        WHERE d.word_id = ?1 AND {other_tagged} AND NOT {preferred_tagged}
+          AND t.id != ?1
+        UNION ALL
+        SELECT w.id, w.word, w.pos FROM definitions d JOIN words w ON w.id = d.word_id
+        WHERE d.form_of_id = ?1 AND {preferred_tagged} AND NOT {other_tagged}
+          AND w.id != ?1
+        LIMIT 1
+        "#
+    );
+    let mut stmt = handle.conn.prepare_cached(&sql)?;
+    let mut values: Vec<&dyn rusqlite::ToSql> = vec![&word_id];
+    values.extend(other.iter().map(|t| t as &dyn rusqlite::ToSql));
+    values.extend(preferred.iter().map(|t| t as &dyn rusqlite::ToSql));
+    let mut rows = stmt.query(params_from_iter(values))?;
+    match rows.next()? {
+        Some(row) => Ok(Some((row.get(0)?, row.get(1)?, row.get(2)?))),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_database, insert_definition, insert_word, resolve_form_of, set_form_of};
+    use crate::search::search_words;
+
+    #[test]
+    fn test_spelling_pairs() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut handle = init_database(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let color = insert_word(&handle.conn, "color", "noun", "English", "en", 0).unwrap();
+        insert_definition(&handle.conn, color, "The hue of something", &[], &[]).unwrap();
+        let colour = insert_word(&handle.conn, "colour", "noun", "English", "en", 0).unwrap();
+        let tags = ["Commonwealth".to_string(), "UK".to_string()];
+        let sense = insert_definition(
+            &handle.conn,
+            colour,
+            "British and Commonwealth standard spelling of color",
+            &[],
+            &tags,
+        )
+        .unwrap();
+        set_form_of(&handle.conn, sense, "color").unwrap();
+        let hue = insert_word(&handle.conn, "hue", "noun", "English", "en", 0).unwrap();
+        insert_definition(&handle.conn, hue, "A color", &[], &[]).unwrap();
+        resolve_form_of(&handle.conn).unwrap();
+
+        assert_eq!(variants(&handle, color).unwrap(), ["colour"]);
+        assert_eq!(variants(&handle, colour).unwrap(), ["color"]);
+        assert!(variants(&handle, hue).unwrap().is_empty());
+
+        let us = SpellingRegion::Us;
+        let uk = SpellingRegion::Uk;
+        assert_eq!(
+            preferred_entry(&handle, colour, us).unwrap().unwrap().0,
+            color
+        );
+        assert_eq!(preferred_entry(&handle, color, us).unwrap(), None);
+        assert_eq!(
+            preferred_entry(&handle, color, uk).unwrap().unwrap().0,
+            colour
+        );
+        assert_eq!(preferred_entry(&handle, colour, uk).unwrap(), None);
+
+        // Search shows the reader's spelling once, listing the other
+        handle.config.spelling = Some(us);
+        let results = search_words(&handle, "colour", 10).unwrap();
+        assert_eq!(results[0].word, "color");
+        assert_eq!(results[0].also_spelled, ["colour"]);
+        assert_eq!(results[0].preview, "The hue of something");
+        assert!(results.iter().filter(|r| r.id == color).count() == 1);
+        let full = crate::db::get_full_definition(&handle, color)
+            .unwrap()
+            .unwrap();
+        assert_eq!(full.also_spelled, ["colour"]);
+
+        handle.config.spelling = None;
+        let results = search_words(&handle, "colour", 10).unwrap();
+        assert_eq!(results[0].word, "colour");
+        assert!(results[0].also_spelled.is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+//! What to show on the home screen
+//!
+//! [`for_home_screen`] mixes the user's due flashcards, recently viewed
+//! entries and a few common words picked at random into one ranked list,
+//! so every platform's home screen suggests the same things.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::userdata::UserData;
+use crate::{DictHandle, Result};
+
+/// Random picks drawn per discovery suggestion; the most frequent win
+const DISCOVER_CANDIDATES: usize = 4;
+
+/// Why an entry is suggested
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionReason {
+    /// A flashcard due for review
+    Due,
+    /// Viewed recently
+    Recent,
+    /// A common word the user may not know yet
+    Discover,
+}
+
+/// A suggested entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub word_id: i64,
+    pub word: String,
+    pub reason: SuggestionReason,
+}
+
+/// Up to `limit` suggestions for the home screen on `today`
+///
+/// Due cards come first, most overdue first, and take at most half the
+/// list; recently viewed entries follow, latest first, taking at most a
+/// quarter. Discovery picks fill the rest: random defined words (never
+/// ones hidden by the content filter), the most frequent of a few
+/// candidates each time, so common words turn up more often. An entry is
+/// suggested once, for its first reason.
+pub fn for_home_screen(
+    handle: &DictHandle,
+    userdata: &UserData,
+    today: i64,
+    limit: usize,
+) -> Result<Vec<Suggestion>> {
+    let mut suggestions = Vec::with_capacity(limit);
+    let mut seen = HashSet::new();
+    let mut add = |suggestions: &mut Vec<Suggestion>, word_id, word, reason| {
+        if suggestions.len() < limit && seen.insert(word_id) {
+            suggestions.push(Suggestion {
+                word_id,
+                word,
+                reason,
+            });
+        }
+    };
+
+    for card in userdata.due_cards(today, limit.div_ceil(2) as u32)? {
+        add(
+            &mut suggestions,
+            card.word_id,
+            card.word,
+            SuggestionReason::Due,
+        );
+    }
+    let recent_quota = suggestions.len() + limit.div_ceil(4);
+    for entry in userdata.recent_history(limit as u32)? {
+        if suggestions.len() >= recent_quota {
+            break;
+        }
+        add(
+            &mut suggestions,
+            entry.word_id,
+            entry.word,
+            SuggestionReason::Recent,
+        );
+    }
+
+    // Give up after a while on small dictionaries that run out of words
+    let mut attempts = 0;
+    while suggestions.len() < limit && attempts < limit * 2 {
+        attempts += 1;
+        let Some((word_id, word)) = frequent_random_word(handle)? else {
+            break;
+        };
+        add(&mut suggestions, word_id, word, SuggestionReason::Discover);
+    }
+    Ok(suggestions)
+}
+
+/// The most frequent of `DISCOVER_CANDIDATES` random defined words
+fn frequent_random_word(handle: &DictHandle) -> Result<Option<(i64, String)>> {
+    let has_frequency = crate::db::has_column(&handle.conn, "words", "frequency")?;
+    let mut best: Option<(i64, i64, String)> = None;
+    for _ in 0..DISCOVER_CANDIDATES {
+        let Some(word) = crate::db::get_random_word(handle)? else {
+            return Ok(None);
+        };
+        let frequency: i64 = if has_frequency {
+            handle.conn.query_row(
+                "SELECT COALESCE(frequency, 0) FROM words WHERE id = ?",
+                [word.id],
+                |row| row.get(0),
+            )?
+        } else {
+            0
+        };
+        if best.as_ref().is_none_or(|(f, _, _)| frequency > *f) {
+            best = Some((frequency, word.id, word.word));
+        }
+    }
+    Ok(best.map(|(_, id, word)| (id, word)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_database, insert_definition, insert_word};
+
+    #[test]
+    fn test_for_home_screen() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let handle = init_database(db_path.to_str().unwrap()).unwrap();
+        let mut ids = Vec::new();
+        for n in 0..50 {
+            let word = ["cat", "dog", "bird"]
+                .get(n)
+                .map_or(format!("word{n}"), |w| w.to_string());
+            let id = insert_word(&handle.conn, &word, "noun", "English", "en", 0).unwrap();
+            insert_definition(&handle.conn, id, "A word", &[], &[]).unwrap();
+            ids.push(id);
+        }
+
+        let user_path = dir.path().join("user.db");
+        let user = UserData::open(user_path.to_str().unwrap()).unwrap();
+        user.add_card(ids[0], "cat", 5).unwrap();
+        user.add_card(ids[1], "dog", 20).unwrap();
+        user.record_view(ids[0], "cat", 100).unwrap();
+        user.record_view(ids[2], "bird", 200).unwrap();
+
+        let suggestions = for_home_screen(&handle, &user, 10, 4).unwrap();
+        assert_eq!(suggestions.len(), 4);
+        assert_eq!(
+            (suggestions[0].word.as_str(), suggestions[0].reason),
+            ("cat", SuggestionReason::Due)
+        );
+        assert_eq!(
+            (suggestions[1].word.as_str(), suggestions[1].reason),
+            ("bird", SuggestionReason::Recent)
+        );
+        assert!(suggestions[2..]
+            .iter()
+            .all(|s| s.reason == SuggestionReason::Discover));
+        let unique: HashSet<i64> = suggestions.iter().map(|s| s.word_id).collect();
+        assert_eq!(unique.len(), 4);
+    }
+}
@@ -0,0 +1,268 @@
+//! Emoji, punctuation and sign lookup
+//!
+//! Symbols get a row in the `symbols` table pointing at their word entry,
+//! so "🙂" or "§" resolves through the normal search path. Kaikki's own
+//! symbol entries are linked at import; [`import_symbols`] adds names and
+//! keywords from Unicode CLDR annotation files (`annotations/en.xml`),
+//! creating entries for symbols the dictionary lacks.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use rusqlite::params;
+
+use crate::db::{init_database, insert_definition, insert_symbol, insert_word, set_meta};
+use crate::Result;
+
+/// Emoji variation selectors (text and emoji presentation)
+const VARIATION_SELECTORS: &[char] = &['\u{FE0E}', '\u{FE0F}'];
+
+/// Result of loading a CLDR annotation file into a database
+#[derive(Debug, Clone, Default)]
+pub struct SymbolStats {
+    /// Symbols with at least a name or keywords in the file
+    pub annotations_read: u64,
+    /// Symbols matched to entries already in the dictionary
+    pub symbols_linked: u64,
+    /// Entries created for symbols the dictionary lacked
+    pub entries_created: u64,
+}
+
+/// Whether `text` is a symbol rather than a word: no letters or digits
+pub fn is_symbol(text: &str) -> bool {
+    let text = text.trim();
+    !text.is_empty() && !text.chars().any(char::is_alphanumeric)
+}
+
+/// `symbol` without variation selectors
+pub fn normalize(symbol: &str) -> String {
+    symbol
+        .trim()
+        .chars()
+        .filter(|c| !VARIATION_SELECTORS.contains(c))
+        .collect()
+}
+
+/// Load symbol names and keywords from a CLDR annotation file
+///
+/// Each symbol's short name (the `type="tts"` annotation) becomes its
+/// definition when the dictionary has no entry for it; symbols already in
+/// the dictionary get the name and keywords added to their `symbols` row.
+/// Running it again with the same file changes nothing.
+pub fn import_symbols(db_path: &str, annotations_path: &str) -> Result<SymbolStats> {
+    let xml = std::fs::read_to_string(annotations_path)?;
+    let annotations = parse_annotations(&xml);
+
+    let handle = init_database(db_path)?;
+    let conn = &handle.conn;
+    let mut stats = SymbolStats::default();
+
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut linked = tx.prepare("SELECT id, word_id FROM symbols WHERE symbol = ?")?;
+        let mut words = tx.prepare("SELECT id FROM words WHERE word IN (?, ?) ORDER BY id")?;
+        let mut update =
+            tx.prepare("UPDATE symbols SET name = COALESCE(name, ?), keywords = ? WHERE id = ?")?;
+
+        for (symbol, annotation) in &annotations {
+            let Some(name) = annotation
+                .name
+                .as_deref()
+                .or(annotation.keywords.first().map(String::as_str))
+            else {
+                continue;
+            };
+            stats.annotations_read += 1;
+            let keywords_json = serde_json::to_string(&annotation.keywords)?;
+
+            let rows: Vec<(i64, i64)> = linked
+                .query_map([symbol], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<std::result::Result<_, _>>()?;
+            if !rows.is_empty() {
+                for (id, _) in rows {
+                    update.execute(params![name, keywords_json, id])?;
+                }
+                stats.symbols_linked += 1;
+                continue;
+            }
+
+            let emoji_form = format!("{}\u{FE0F}", symbol);
+            let word_ids: Vec<i64> = words
+                .query_map(params![symbol, emoji_form], |row| row.get(0))?
+                .collect::<std::result::Result<_, _>>()?;
+            if !word_ids.is_empty() {
+                for word_id in word_ids {
+                    insert_symbol(&tx, word_id, symbol, Some(name), &annotation.keywords)?;
+                }
+                stats.symbols_linked += 1;
+                continue;
+            }
+
+            let word_id = insert_word(&tx, symbol, "symbol", "Translingual", "mul", 0)?;
+            insert_definition(&tx, word_id, name, &[], &[])?;
+            insert_symbol(&tx, word_id, symbol, Some(name), &annotation.keywords)?;
+            stats.entries_created += 1;
+        }
+    }
+
+    let file_name = Path::new(annotations_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    set_meta(&tx, "symbols_source", &file_name)?;
+    tx.commit()?;
+
+    Ok(stats)
+}
+
+/// Name and keywords of one symbol in a CLDR annotation file
+#[derive(Debug, Default)]
+struct Annotation {
+    name: Option<String>,
+    keywords: Vec<String>,
+}
+
+/// Parse the `<annotation>` elements of a CLDR annotation file
+///
+/// Returns (normalized symbol, annotation) pairs in file order.
+fn parse_annotations(xml: &str) -> Vec<(String, Annotation)> {
+    let mut order: Vec<String> = Vec::new();
+    let mut annotations: HashMap<String, Annotation> = HashMap::new();
+
+    let mut rest = xml;
+    while let Some(start) = rest.find("<annotation ") {
+        rest = &rest[start + "<annotation ".len()..];
+        let Some(tag_end) = rest.find('>') else {
+            break;
+        };
+        let attributes = &rest[..tag_end];
+        rest = &rest[tag_end + 1..];
+        let Some(text_end) = rest.find("</annotation>") else {
+            break;
+        };
+        let text = unescape(rest[..text_end].trim());
+        rest = &rest[text_end..];
+
+        let Some(symbol) = attribute(attributes, "cp").map(|cp| normalize(&unescape(cp))) else {
+            continue;
+        };
+        if symbol.is_empty() || text.is_empty() {
+            continue;
+        }
+        let annotation = annotations.entry(symbol.clone()).or_insert_with(|| {
+            order.push(symbol);
+            Annotation::default()
+        });
+        if attribute(attributes, "type") == Some("tts") {
+            annotation.name = Some(text);
+        } else {
+            annotation.keywords = text
+                .split('|')
+                .map(str::trim)
+                .filter(|k| !k.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+    }
+
+    order
+        .into_iter()
+        .filter_map(|symbol| {
+            let annotation = annotations.remove(&symbol)?;
+            Some((symbol, annotation))
+        })
+        .collect()
+}
+
+/// Value of attribute `name` in the attributes of an XML start tag
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attributes;
+    loop {
+        let eq = rest.find('=')?;
+        let key = rest[..eq].trim();
+        let value = rest[eq + 1..].trim_start();
+        let quote = value.chars().next().filter(|q| *q == '"' || *q == '\'')?;
+        let end = value[1..].find(quote)? + 1;
+        if key == name {
+            return Some(&value[1..end]);
+        }
+        rest = &value[end + 1..];
+    }
+}
+
+/// Resolve the XML entities CLDR files use
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANNOTATIONS: &str = r#"<?xml version="1.0" encoding="UTF-8" ?>
+<ldml>
+    <annotations>
+        <annotation cp="🙂">face | slightly smiling face | smile</annotation>
+        <annotation cp="🙂" type="tts">slightly smiling face</annotation>
+        <annotation cp="§">paragraph | section | section sign</annotation>
+        <annotation cp="§" type="tts">section sign</annotation>
+        <annotation cp="&amp;">ampersand | and</annotation>
+        <annotation cp="&amp;" type="tts">ampersand</annotation>
+    </annotations>
+</ldml>
+"#;
+
+    #[test]
+    fn test_is_symbol() {
+        assert!(is_symbol("🙂"));
+        assert!(is_symbol("§"));
+        assert!(is_symbol("?!"));
+        assert!(!is_symbol("cat"));
+        assert!(!is_symbol("42"));
+        assert!(!is_symbol(" "));
+        assert_eq!(normalize("☺\u{FE0F}"), "☺");
+    }
+
+    #[test]
+    fn test_parse_annotations() {
+        let annotations = parse_annotations(ANNOTATIONS);
+        let symbols: Vec<&str> = annotations.iter().map(|(s, _)| s.as_str()).collect();
+        assert_eq!(symbols, ["🙂", "§", "&"]);
+        let (_, smile) = &annotations[0];
+        assert_eq!(smile.name.as_deref(), Some("slightly smiling face"));
+        assert_eq!(smile.keywords, ["face", "slightly smiling face", "smile"]);
+    }
+
+    #[test]
+    fn test_import_symbols() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let handle = init_database(db_path.to_str().unwrap()).unwrap();
+        // Already in the dictionary, as kaikki has it
+        let section = insert_word(&handle.conn, "§", "symbol", "Translingual", "mul", 0).unwrap();
+        insert_definition(&handle.conn, section, "Section (of a text).", &[], &[]).unwrap();
+
+        let path = dir.path().join("en.xml");
+        std::fs::write(&path, ANNOTATIONS).unwrap();
+        let stats = import_symbols(db_path.to_str().unwrap(), path.to_str().unwrap()).unwrap();
+        assert_eq!(stats.annotations_read, 3);
+        assert_eq!(stats.symbols_linked, 1);
+        assert_eq!(stats.entries_created, 2);
+
+        // Idempotent
+        let stats = import_symbols(db_path.to_str().unwrap(), path.to_str().unwrap()).unwrap();
+        assert_eq!(stats.symbols_linked, 3);
+        assert_eq!(stats.entries_created, 0);
+
+        let results = crate::search::search_words(&handle, "🙂\u{FE0F}", 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].preview, "slightly smiling face");
+        let results = crate::search::search_words(&handle, "§", 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, section);
+    }
+}
@@ -0,0 +1,112 @@
+//! Search telemetry hooks for the host app
+//!
+//! The app registers a [`TelemetrySink`] with [`set_sink`] and receives a
+//! [`SearchEvent`] for every search: how long the query was, which tier
+//! produced the top result, how long it took and how many results it
+//! returned. What gets aggregated, sampled or sent anywhere is up to the
+//! app; the query text itself is only included when the sink asks for it.
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// The registered sink, if any
+static SINK: RwLock<Option<Arc<dyn TelemetrySink>>> = RwLock::new(None);
+
+/// Receiver for search telemetry, implemented by the host app
+///
+/// `record_search` runs on the searching thread after the results are
+/// ready, so it should hand the event off rather than do I/O itself.
+pub trait TelemetrySink: Send + Sync {
+    /// Called once per search
+    fn record_search(&self, event: &SearchEvent);
+
+    /// Whether events should carry the query text (off by default)
+    fn include_query_text(&self) -> bool {
+        false
+    }
+}
+
+/// One search, as reported to a [`TelemetrySink`]
+#[derive(Debug, Clone)]
+pub struct SearchEvent {
+    /// Length of the trimmed query, in characters
+    pub query_chars: usize,
+    /// Tier that produced the top result ("exact", "prefix", "fuzzy", ...),
+    /// None when nothing was found
+    pub tier: Option<&'static str>,
+    pub latency: Duration,
+    pub result_count: usize,
+    /// Results skipped for pagination
+    pub offset: u32,
+    /// The trimmed query, only if the sink's `include_query_text` is true
+    pub query: Option<String>,
+}
+
+/// Register the sink that receives search telemetry, replacing any other
+pub fn set_sink(sink: Arc<dyn TelemetrySink>) {
+    *SINK.write().unwrap() = Some(sink);
+}
+
+/// Stop reporting search telemetry
+pub fn clear_sink() {
+    *SINK.write().unwrap() = None;
+}
+
+/// The registered sink, if any
+pub(crate) fn sink() -> Option<Arc<dyn TelemetrySink>> {
+    SINK.read().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_database, insert_definition, insert_word};
+    use std::sync::Mutex;
+
+    /// Keeps the events for one query; other tests search concurrently
+    struct Recorder {
+        query: &'static str,
+        events: Mutex<Vec<SearchEvent>>,
+    }
+
+    impl TelemetrySink for Recorder {
+        fn record_search(&self, event: &SearchEvent) {
+            if event.query.as_deref() == Some(self.query) {
+                self.events.lock().unwrap().push(event.clone());
+            }
+        }
+
+        fn include_query_text(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_search_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let handle = init_database(db_path.to_str().unwrap()).unwrap();
+        for word in ["telemetrics", "telemetry"] {
+            let id = insert_word(&handle.conn, word, "noun", "English", "en", 0).unwrap();
+            insert_definition(&handle.conn, id, "Remote measurement.", &[], &[]).unwrap();
+        }
+
+        let recorder = Arc::new(Recorder {
+            query: "telemetr",
+            events: Mutex::new(Vec::new()),
+        });
+        set_sink(recorder.clone());
+        crate::search::search_words(&handle, " telemetr ", 10).unwrap();
+        crate::search::search_words_offset(&handle, "telemetr", 10, 5).unwrap();
+        clear_sink();
+        crate::search::search_words(&handle, "telemetr", 10).unwrap();
+
+        let events = recorder.events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].query_chars, 8);
+        assert_eq!(events[0].tier, Some("prefix"));
+        assert_eq!(events[0].result_count, 2);
+        assert_eq!((events[1].tier, events[1].result_count), (None, 0));
+        assert_eq!(events[1].offset, 5);
+    }
+}
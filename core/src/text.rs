@@ -0,0 +1,287 @@
+//! Linking running text to dictionary entries
+//!
+//! [`annotate`] is the core of a tap-to-define reading view: it splits a
+//! passage into words, joins multi-word expressions the dictionary has
+//! entries for ("ice cream", "give up"), and resolves each span to word
+//! ids, going through lemmas for inflected forms.
+
+use rusqlite::params;
+
+use crate::models::{Annotation, Link};
+use crate::{DictHandle, Result};
+
+/// Longest multi-word expression looked for, in words
+const MAX_PHRASE_WORDS: usize = 5;
+
+/// Shortest single word linked by `links`, in characters
+const MIN_LINK_LENGTH: usize = 3;
+
+/// Headwords mentioned in a definition, as links to their entries
+///
+/// Like `annotate`, but only spans worth a link: words known to the
+/// dictionary, leaving out function words ("the", "of"), words shorter than
+/// `MIN_LINK_LENGTH` and `own_headword`, the entry being defined.
+pub fn links(handle: &DictHandle, text: &str, lang: &str, own_headword: &str) -> Result<Vec<Link>> {
+    let own_headword = own_headword.to_lowercase();
+    Ok(annotate(handle, text, lang)?
+        .into_iter()
+        .filter(|a| {
+            let word = a.text.to_lowercase();
+            a.phrase
+                || (word.chars().count() >= MIN_LINK_LENGTH
+                    && !crate::context::STOP_WORDS.contains(&word.as_str()))
+        })
+        .filter(|a| a.headword.to_lowercase() != own_headword)
+        .filter_map(|a| {
+            Some(Link {
+                start: a.start,
+                end: a.end,
+                word_id: *a.word_ids.first()?,
+                text: a.text,
+            })
+        })
+        .collect())
+}
+
+/// Split `text` into words and link each to the dictionary
+///
+/// `lang` is the language code of the passage. Spans come back in text
+/// order and cover every word; words the dictionary doesn't know have no
+/// `word_ids`. Multi-word headwords are matched longest first, only across
+/// whitespace, so "ice cream" matches in "the ice cream" but not in
+/// "ice, cream".
+pub fn annotate(handle: &DictHandle, text: &str, lang: &str) -> Result<Vec<Annotation>> {
+    let tokens = tokens(text);
+    let mut annotations = Vec::with_capacity(tokens.len());
+
+    let mut i = 0;
+    while i < tokens.len() {
+        if let Some((last, headword, word_ids)) = find_phrase(handle, text, &tokens, i, lang)? {
+            let start = tokens[i].0;
+            let end = tokens[last].0 + tokens[last].1.len();
+            annotations.push(Annotation {
+                start,
+                end,
+                text: text[start..end].to_string(),
+                headword,
+                word_ids,
+                phrase: true,
+            });
+            i = last + 1;
+            continue;
+        }
+
+        let (start, word) = tokens[i];
+        let (headword, word_ids) =
+            resolve(handle, word, lang)?.unwrap_or_else(|| (word.to_string(), Vec::new()));
+        annotations.push(Annotation {
+            start,
+            end: start + word.len(),
+            text: word.to_string(),
+            headword,
+            word_ids,
+            phrase: false,
+        });
+        i += 1;
+    }
+
+    Ok(annotations)
+}
+
+/// Words of `text` with their byte offsets
+///
+/// Words are runs of letters and digits, keeping inner apostrophes and
+/// hyphens ("don't", "well-known").
+pub(crate) fn tokens(text: &str) -> Vec<(usize, &str)> {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '\'' || c == '’' || c == '-';
+    let mut result = Vec::new();
+    let mut start = None;
+    for (i, c) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        match (start, is_word_char(c)) {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                let run = &text[s..i];
+                let word = run.trim_matches(|c: char| !c.is_alphanumeric());
+                if !word.is_empty() {
+                    let offset = s + run.find(word).unwrap_or(0);
+                    result.push((offset, word));
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+/// Headword and entries for a single word
+///
+/// Tries the word as written, then lowercased, then its lemmas.
+fn resolve(handle: &DictHandle, word: &str, lang: &str) -> Result<Option<(String, Vec<i64>)>> {
+    let lower = word.to_lowercase();
+    let mut candidates = vec![word.to_string()];
+    if lower != word {
+        candidates.push(lower.clone());
+    }
+
+    for candidate in &candidates {
+        let ids = word_ids(handle, candidate, lang)?;
+        if !ids.is_empty() {
+            return Ok(Some((candidate.clone(), ids)));
+        }
+    }
+    for candidate in &candidates {
+        for lemma in crate::morph::lemmatize(handle, candidate, lang)? {
+            let ids = word_ids(handle, &lemma, lang)?;
+            if !ids.is_empty() {
+                return Ok(Some((lemma, ids)));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Longest multi-word headword starting at token `first`
+///
+/// Returns the index of its last token, the headword and its entries.
+fn find_phrase(
+    handle: &DictHandle,
+    text: &str,
+    tokens: &[(usize, &str)],
+    first: usize,
+    lang: &str,
+) -> Result<Option<(usize, String, Vec<i64>)>> {
+    // Tokens that can join the phrase: only whitespace between them
+    let mut last = first;
+    while last + 1 < tokens.len() && last + 1 - first < MAX_PHRASE_WORDS {
+        let gap = &text[tokens[last].0 + tokens[last].1.len()..tokens[last + 1].0];
+        if !gap.chars().all(char::is_whitespace) {
+            break;
+        }
+        last += 1;
+    }
+    if last == first {
+        return Ok(None);
+    }
+
+    let word = tokens[first].1;
+    let lower = word.to_lowercase();
+    if !has_phrase_starting_with(handle, word, lang)?
+        && (lower == word || !has_phrase_starting_with(handle, &lower, lang)?)
+    {
+        return Ok(None);
+    }
+
+    for end in (first + 1..=last).rev() {
+        let phrase = tokens[first..=end]
+            .iter()
+            .map(|(_, w)| *w)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let lower = phrase.to_lowercase();
+        for candidate in [&phrase, &lower] {
+            let ids = word_ids(handle, candidate, lang)?;
+            if !ids.is_empty() {
+                return Ok(Some((end, candidate.clone(), ids)));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Whether any headword in `lang` starts with `word` followed by a space
+fn has_phrase_starting_with(handle: &DictHandle, word: &str, lang: &str) -> Result<bool> {
+    let mut stmt = handle.conn.prepare_cached(
+        "SELECT 1 FROM words WHERE word >= ? AND word < ? AND lang_code = ? LIMIT 1",
+    )?;
+    let low = format!("{} ", word);
+    let high = format!("{} \u{10FFFF}", word);
+    Ok(stmt.exists(params![low, high, lang])?)
+}
+
+fn word_ids(handle: &DictHandle, word: &str, lang: &str) -> Result<Vec<i64>> {
+    let mut stmt = handle
+        .conn
+        .prepare_cached("SELECT id FROM words WHERE word = ? AND lang_code = ? ORDER BY id")?;
+    let ids = stmt
+        .query_map(params![word, lang], |row| row.get(0))?
+        .collect::<std::result::Result<Vec<i64>, _>>()?;
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_database, insert_form, insert_word};
+
+    #[test]
+    fn test_tokens() {
+        let text = "Don't stop, \"well-known\" café — 42!";
+        let words: Vec<&str> = tokens(text).into_iter().map(|(_, w)| w).collect();
+        assert_eq!(words, ["Don't", "stop", "well-known", "café", "42"]);
+        let (offset, word) = tokens(text)[3];
+        assert_eq!(&text[offset..offset + word.len()], "café");
+    }
+
+    #[test]
+    fn test_annotate() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let handle = init_database(db_path.to_str().unwrap()).unwrap();
+
+        let ice = insert_word(&handle.conn, "ice", "noun", "English", "en", 0).unwrap();
+        let ice_cream = insert_word(&handle.conn, "ice cream", "noun", "English", "en", 0).unwrap();
+        let the = insert_word(&handle.conn, "the", "article", "English", "en", 0).unwrap();
+        let go = insert_word(&handle.conn, "go", "verb", "English", "en", 0).unwrap();
+        insert_form(&handle.conn, go, "went", &["past".to_string()]).unwrap();
+        let cat = insert_word(&handle.conn, "cat", "noun", "English", "en", 0).unwrap();
+
+        let text = "The cats went for ice cream, not ice.";
+        let spans = annotate(&handle, text, "en").unwrap();
+        let summary: Vec<(&str, &str, &[i64])> = spans
+            .iter()
+            .map(|s| (s.text.as_str(), s.headword.as_str(), s.word_ids.as_slice()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("The", "the", &[the][..]),
+                ("cats", "cat", &[cat][..]),
+                ("went", "go", &[go][..]),
+                ("for", "for", &[][..]),
+                ("ice cream", "ice cream", &[ice_cream][..]),
+                ("not", "not", &[][..]),
+                ("ice", "ice", &[ice][..]),
+            ]
+        );
+        assert!(spans[4].phrase);
+        assert_eq!(&text[spans[4].start..spans[4].end], "ice cream");
+
+        // Phrases don't cross punctuation
+        let spans = annotate(&handle, "ice, cream", "en").unwrap();
+        assert_eq!(spans.len(), 2);
+        assert!(!spans[0].phrase);
+    }
+
+    #[test]
+    fn test_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let handle = init_database(db_path.to_str().unwrap()).unwrap();
+
+        insert_word(&handle.conn, "the", "article", "English", "en", 0).unwrap();
+        insert_word(&handle.conn, "ox", "noun", "English", "en", 0).unwrap();
+        let cat = insert_word(&handle.conn, "cat", "noun", "English", "en", 0).unwrap();
+        insert_word(&handle.conn, "kitten", "noun", "English", "en", 0).unwrap();
+
+        // Stop words, very short words and the entry's own headword are skipped
+        let text = "The young of the cat, not an ox; a kitten.";
+        let links = links(&handle, text, "en", "Kitten").unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].word_id, cat);
+        assert_eq!(&text[links[0].start..links[0].end], "cat");
+    }
+}
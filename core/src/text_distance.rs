@@ -0,0 +1,237 @@
+//! Edit distances and similarity scores between strings
+//!
+//! Distances count edits of characters (Unicode scalar values), not
+//! bytes. Similarities scale a distance by the longer string's length
+//! into 0-1, 1 meaning equal, so thresholds work the same for short and
+//! long words. Comparisons are exact; lowercase first to ignore case.
+
+/// Calculate Levenshtein distance between two strings
+///
+/// The Levenshtein distance is the minimum number of single-character edits
+/// (insertions, deletions, or substitutions) required to change one string into another.
+///
+/// Uses the Wagner-Fischer algorithm with O(min(m,n)) space complexity.
+pub fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let a_chars: Vec<char> = a.chars().collect();
+    let b_chars: Vec<char> = b.chars().collect();
+
+    let m = a_chars.len();
+    let n = b_chars.len();
+
+    // Handle empty strings
+    if m == 0 {
+        return n;
+    }
+    if n == 0 {
+        return m;
+    }
+
+    // Optimize: ensure a is the shorter string for O(min(m,n)) space
+    if m > n {
+        return levenshtein_distance(b, a);
+    }
+
+    // Use two rows instead of full matrix for space efficiency
+    let mut prev_row: Vec<usize> = (0..=m).collect();
+    let mut curr_row: Vec<usize> = vec![0; m + 1];
+
+    for j in 1..=n {
+        curr_row[0] = j;
+
+        for i in 1..=m {
+            let cost = if a_chars[i - 1] == b_chars[j - 1] {
+                0
+            } else {
+                1
+            };
+
+            curr_row[i] = std::cmp::min(
+                std::cmp::min(
+                    prev_row[i] + 1,     // deletion
+                    curr_row[i - 1] + 1, // insertion
+                ),
+                prev_row[i - 1] + cost, // substitution
+            );
+        }
+
+        std::mem::swap(&mut prev_row, &mut curr_row);
+    }
+
+    prev_row[m]
+}
+
+/// Calculate Damerau-Levenshtein distance (allows transpositions)
+///
+/// This is similar to Levenshtein but also considers transposition of two
+/// adjacent characters as a single edit operation.
+pub fn damerau_levenshtein_distance(a: &str, b: &str) -> usize {
+    let a_chars: Vec<char> = a.chars().collect();
+    let b_chars: Vec<char> = b.chars().collect();
+
+    let m = a_chars.len();
+    let n = b_chars.len();
+
+    if m == 0 {
+        return n;
+    }
+    if n == 0 {
+        return m;
+    }
+
+    // Need full matrix for transpositions
+    let mut d: Vec<Vec<usize>> = vec![vec![0; n + 1]; m + 1];
+
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=m {
+        for j in 1..=n {
+            let cost = if a_chars[i - 1] == b_chars[j - 1] {
+                0
+            } else {
+                1
+            };
+
+            d[i][j] = std::cmp::min(
+                std::cmp::min(
+                    d[i - 1][j] + 1, // deletion
+                    d[i][j - 1] + 1, // insertion
+                ),
+                d[i - 1][j - 1] + cost, // substitution
+            );
+
+            // Transposition
+            if i > 1
+                && j > 1
+                && a_chars[i - 1] == b_chars[j - 2]
+                && a_chars[i - 2] == b_chars[j - 1]
+            {
+                d[i][j] = std::cmp::min(d[i][j], d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[m][n]
+}
+
+/// Similarity of two strings by Levenshtein distance, from 0 (nothing in
+/// common) to 1 (equal)
+pub fn similarity(a: &str, b: &str) -> f64 {
+    normalize(levenshtein_distance(a, b), a, b)
+}
+
+/// Similarity of two strings by Damerau-Levenshtein distance, from 0 to 1;
+/// a swapped pair of letters costs as much as one typo
+pub fn damerau_similarity(a: &str, b: &str) -> f64 {
+    normalize(damerau_levenshtein_distance(a, b), a, b)
+}
+
+/// `distance` scaled by the length of the longer string, as a similarity
+fn normalize(distance: usize, a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        1.0
+    } else {
+        1.0 - distance as f64 / longest as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levenshtein_distance() {
+        // Same strings
+        assert_eq!(levenshtein_distance("hello", "hello"), 0);
+
+        // Empty strings
+        assert_eq!(levenshtein_distance("", "hello"), 5);
+        assert_eq!(levenshtein_distance("hello", ""), 5);
+        assert_eq!(levenshtein_distance("", ""), 0);
+
+        // One edit
+        assert_eq!(levenshtein_distance("hello", "helo"), 1); // deletion
+        assert_eq!(levenshtein_distance("hello", "helloo"), 1); // insertion
+        assert_eq!(levenshtein_distance("hello", "hallo"), 1); // substitution
+
+        // Two edits
+        assert_eq!(levenshtein_distance("hello", "halo"), 2);
+        assert_eq!(levenshtein_distance("kitten", "sitten"), 1);
+        assert_eq!(levenshtein_distance("kitten", "sittin"), 2);
+        assert_eq!(levenshtein_distance("kitten", "sitting"), 3);
+
+        // Completely different
+        assert_eq!(levenshtein_distance("abc", "xyz"), 3);
+    }
+
+    #[test]
+    fn test_damerau_levenshtein_distance() {
+        // Transposition
+        assert_eq!(damerau_levenshtein_distance("ab", "ba"), 1);
+        assert_eq!(damerau_levenshtein_distance("hello", "hlelo"), 1);
+
+        // Compare with standard Levenshtein (which counts transposition as 2)
+        assert_eq!(levenshtein_distance("ab", "ba"), 2);
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("cat", "cat"), 1.0);
+        assert_eq!(similarity("abc", "xyz"), 0.0);
+        assert!((similarity("kitten", "sitting") - (1.0 - 3.0 / 7.0)).abs() < 1e-12);
+        assert_eq!(damerau_similarity("form", "from"), 0.75);
+        assert_eq!(similarity("form", "from"), 0.5);
+    }
+
+    /// Random short strings over a small alphabet, so that edits overlap
+    fn strings(seed: u64, count: usize) -> Vec<String> {
+        let mut state = seed;
+        let mut next = move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as usize
+        };
+        let alphabet: Vec<char> = "abcé ".chars().collect();
+        (0..count)
+            .map(|_| {
+                let len = next() % 7;
+                (0..len)
+                    .map(|_| alphabet[next() % alphabet.len()])
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_distance_properties() {
+        let samples = strings(42, 40);
+        for a in &samples {
+            assert_eq!(levenshtein_distance(a, a), 0);
+            assert_eq!(damerau_levenshtein_distance(a, a), 0);
+            for b in &samples {
+                let lev = levenshtein_distance(a, b);
+                let dam = damerau_levenshtein_distance(a, b);
+                // Symmetric, bounded by the lengths, and transpositions only help
+                assert_eq!(lev, levenshtein_distance(b, a));
+                assert_eq!(dam, damerau_levenshtein_distance(b, a));
+                let (la, lb) = (a.chars().count(), b.chars().count());
+                assert!(la.abs_diff(lb) <= dam && lev <= la.max(lb));
+                assert!(dam <= lev);
+                assert_eq!(lev == 0, a == b);
+                let sim = similarity(a, b);
+                assert!((0.0..=1.0).contains(&sim));
+                assert!(damerau_similarity(a, b) >= sim);
+                for c in samples.iter().take(10) {
+                    assert!(lev <= levenshtein_distance(a, c) + levenshtein_distance(c, b));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Synonyms and antonyms grouped by sense
+//!
+//! Built on the `relations` table, which holds the synonyms and antonyms
+//! Wiktionary lists either under a sense or for the entry as a whole,
+//! the latter often labelled with the meaning they apply to.
+
+use rusqlite::params;
+
+use crate::models::ThesaurusSense;
+use crate::{DictHandle, Result};
+
+/// Synonyms and antonyms of `word` in language `lang`, grouped by sense
+///
+/// `lang` is a language code such as "en". Groups come per entry (noun
+/// before verb, as in the dictionary): first the senses that have
+/// relations, in definition order, then Wiktionary's labelled groups, then
+/// relations for the whole entry. Inflected forms are looked up through
+/// their lemmas. Databases without relations return no groups.
+pub fn lookup(handle: &DictHandle, word: &str, lang: &str) -> Result<Vec<ThesaurusSense>> {
+    if !crate::db::table_exists(&handle.conn, "relations")? {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = entry_ids(handle, word, lang)?;
+    if entries.is_empty() {
+        for lemma in crate::morph::lemmatize(handle, word, lang)? {
+            entries.extend(entry_ids(handle, &lemma, lang)?);
+        }
+    }
+
+    let mut stmt = handle.conn.prepare_cached(
+        r#"
+        SELECT r.definition_id, d.definition, r.sense, r.kind, r.target
+        FROM relations r
+        LEFT JOIN definitions d ON d.id = r.definition_id
+        WHERE r.word_id = ?
+        ORDER BY r.definition_id IS NULL, r.definition_id, r.sense IS NULL, r.sense, r.id
+        "#,
+    )?;
+
+    let mut groups: Vec<ThesaurusSense> = Vec::new();
+    for (word_id, pos) in entries {
+        // Key of the group being filled: the definition id or the label
+        let mut current: Option<(Option<i64>, Option<String>)> = None;
+        let mut rows = stmt.query(params![word_id])?;
+        while let Some(row) = rows.next()? {
+            let definition_id: Option<i64> = row.get(0)?;
+            let definition: Option<String> = row.get(1)?;
+            let label: Option<String> = row.get(2)?;
+            let kind: String = row.get(3)?;
+            let target: String = row.get(4)?;
+
+            let key = (
+                definition_id,
+                label.clone().filter(|_| definition_id.is_none()),
+            );
+            if current.as_ref() != Some(&key) {
+                groups.push(ThesaurusSense {
+                    word_id,
+                    pos: pos.clone(),
+                    sense: definition.or(label),
+                    synonyms: Vec::new(),
+                    antonyms: Vec::new(),
+                });
+                current = Some(key);
+            }
+
+            let group = groups.last_mut().expect("group pushed above");
+            let list = match kind.as_str() {
+                "synonym" => &mut group.synonyms,
+                "antonym" => &mut group.antonyms,
+                _ => continue,
+            };
+            if !list.contains(&target) {
+                list.push(target);
+            }
+        }
+    }
+
+    Ok(groups)
+}
+
+/// (id, part of speech) of the entries for `word` in `lang`
+fn entry_ids(handle: &DictHandle, word: &str, lang: &str) -> Result<Vec<(i64, String)>> {
+    let mut stmt = handle
+        .conn
+        .prepare_cached("SELECT id, pos FROM words WHERE word = ? AND lang_code = ? ORDER BY id")?;
+    let rows = stmt.query_map(params![word, lang], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    Ok(rows.collect::<std::result::Result<Vec<_>, _>>()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_database, insert_definition, insert_relation, insert_word};
+
+    #[test]
+    fn test_lookup_groups_by_sense() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let handle = init_database(db_path.to_str().unwrap()).unwrap();
+        let conn = &handle.conn;
+
+        let big = insert_word(conn, "big", "adj", "English", "en", 0).unwrap();
+        let large = insert_definition(conn, big, "Of great size.", &[], &[]).unwrap();
+        let older = insert_definition(conn, big, "Older, grown up.", &[], &[]).unwrap();
+        insert_relation(conn, big, Some(large), "synonym", "large", None).unwrap();
+        insert_relation(conn, big, Some(large), "synonym", "huge", None).unwrap();
+        insert_relation(conn, big, Some(large), "antonym", "small", None).unwrap();
+        insert_relation(conn, big, Some(older), "synonym", "grown-up", None).unwrap();
+        insert_relation(conn, big, None, "synonym", "important", Some("significant")).unwrap();
+        insert_relation(conn, big, None, "synonym", "great", None).unwrap();
+        insert_relation(conn, big, None, "synonym", "great", None).unwrap();
+
+        let groups = lookup(&handle, "big", "en").unwrap();
+        let summary: Vec<(Option<&str>, &[String], &[String])> = groups
+            .iter()
+            .map(|g| {
+                (
+                    g.sense.as_deref(),
+                    g.synonyms.as_slice(),
+                    g.antonyms.as_slice(),
+                )
+            })
+            .collect();
+        assert_eq!(summary.len(), 4);
+        assert_eq!(summary[0].0, Some("Of great size."));
+        assert_eq!(summary[0].1, ["large", "huge"]);
+        assert_eq!(summary[0].2, ["small"]);
+        assert_eq!(summary[1].0, Some("Older, grown up."));
+        assert_eq!(summary[2].0, Some("significant"));
+        assert_eq!(summary[3].0, None);
+        assert_eq!(summary[3].1, ["great"]);
+
+        // Inflected forms go through their lemma
+        assert_eq!(lookup(&handle, "bigs", "en").unwrap().len(), 4);
+        assert!(lookup(&handle, "big", "fr").unwrap().is_empty());
+    }
+}
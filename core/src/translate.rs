@@ -0,0 +1,163 @@
+//! Translations between two languages through English
+//!
+//! An English Wiktionary database only has translation tables on its
+//! English entries. [`pivot`] goes from a word in one language to the
+//! English entries that list it as a translation, then on to their
+//! translations into the other language: French "chat" reaches German
+//! "Katze" through "cat". Pivoted candidates are guesses, since the
+//! English word can have senses the original lacks ("bank" gives both
+//! "Ufer" and "Bank"), so each carries the English words it came through.
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::{DictHandle, Result};
+
+/// A candidate translation from [`pivot`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PivotTranslation {
+    /// The word in the target language
+    pub translation: String,
+    /// English headwords linking it to the looked-up word (for a direct
+    /// translation, the English word itself)
+    pub via: Vec<String>,
+    /// Whether it was reached through English rather than listed in the
+    /// translation table of the looked-up word (or of its translation)
+    pub pivoted: bool,
+}
+
+/// Candidate translations of `word` from language `from_lang` into
+/// `to_lang`, through the translation tables of English entries
+///
+/// Languages are given as stored with translations: codes such as "fr",
+/// or names where Wiktionary gave no code; English is "en". When either
+/// language is English the lookup is direct and nothing is marked
+/// pivoted. Candidates reached through more English words come first,
+/// then in dictionary order. Translations are matched ignoring ASCII
+/// case.
+pub fn pivot(
+    handle: &DictHandle,
+    word: &str,
+    from_lang: &str,
+    to_lang: &str,
+) -> Result<Vec<PivotTranslation>> {
+    let word = word.trim();
+    if word.is_empty() || from_lang == to_lang {
+        return Ok(Vec::new());
+    }
+    let english = if crate::db::has_column(&handle.conn, "words", "lang_code")? {
+        "w.lang_code = 'en'"
+    } else {
+        "w.language = 'English'"
+    };
+
+    // English entries the word translates into (or is)
+    let mut stmt = if is_english(from_lang) {
+        handle.conn.prepare_cached(&format!(
+            "SELECT w.id, w.word FROM words w WHERE w.word = ?2 AND {english} ORDER BY w.id"
+        ))?
+    } else {
+        handle.conn.prepare_cached(&format!(
+            "SELECT w.id, w.word FROM translations t JOIN words w ON w.id = t.word_id
+             WHERE t.target_language = ?1 AND t.translation = ?2 COLLATE NOCASE AND {english}
+             ORDER BY w.id"
+        ))?
+    };
+    let rows = stmt.query_map(params![from_lang, word], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+    })?;
+    let english_words = rows.collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut candidates: Vec<PivotTranslation> = Vec::new();
+    let mut add = |translation: String, via: &str| {
+        let index = match candidates.iter().position(|c| c.translation == translation) {
+            Some(index) => index,
+            None => {
+                candidates.push(PivotTranslation {
+                    translation,
+                    via: Vec::new(),
+                    pivoted: !is_english(from_lang) && !is_english(to_lang),
+                });
+                candidates.len() - 1
+            }
+        };
+        let candidate = &mut candidates[index];
+        if !candidate.via.iter().any(|v| v == via) {
+            candidate.via.push(via.to_string());
+        }
+    };
+
+    if is_english(to_lang) {
+        for (_, headword) in &english_words {
+            add(headword.clone(), headword);
+        }
+    } else {
+        let mut stmt = handle.conn.prepare_cached(
+            "SELECT translation FROM translations WHERE word_id = ? AND target_language = ? ORDER BY id",
+        )?;
+        for (word_id, headword) in &english_words {
+            let rows = stmt.query_map(params![word_id, to_lang], |row| row.get::<_, String>(0))?;
+            for translation in rows {
+                add(translation?, headword);
+            }
+        }
+    }
+
+    // Stable, so ties keep dictionary order
+    candidates.sort_by_key(|c| std::cmp::Reverse(c.via.len()));
+    Ok(candidates)
+}
+
+fn is_english(lang: &str) -> bool {
+    lang.eq_ignore_ascii_case("en") || lang.eq_ignore_ascii_case("English")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_database, insert_translation, insert_word};
+
+    #[test]
+    fn test_pivot() {
+        let dir = tempfile::tempdir().unwrap();
+        let handle = init_database(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let entries: &[(&str, &[(&str, &str)])] = &[
+            ("cat", &[("fr", "chat"), ("de", "Katze"), ("de", "Kater")]),
+            ("tomcat", &[("fr", "chat"), ("de", "Kater")]),
+            ("chat", &[("fr", "bavarder"), ("de", "plaudern")]),
+        ];
+        for (word, translations) in entries {
+            let id = insert_word(&handle.conn, word, "noun", "English", "en", 0).unwrap();
+            for (lang, translation) in translations.iter() {
+                insert_translation(&handle.conn, id, lang, translation).unwrap();
+            }
+        }
+        // A French entry's own translations are not English ones
+        let french = insert_word(&handle.conn, "chat", "noun", "French", "fr", 0).unwrap();
+        insert_translation(&handle.conn, french, "de", "Mieze").unwrap();
+
+        let candidates = pivot(&handle, "Chat", "fr", "de").unwrap();
+        let summary: Vec<(&str, Vec<&str>)> = candidates
+            .iter()
+            .map(|c| {
+                (
+                    c.translation.as_str(),
+                    c.via.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [("Kater", vec!["cat", "tomcat"]), ("Katze", vec!["cat"])]
+        );
+        assert!(candidates.iter().all(|c| c.pivoted));
+
+        let direct = pivot(&handle, "chat", "en", "de").unwrap();
+        assert_eq!(direct[0].translation, "plaudern");
+        assert!(!direct[0].pivoted);
+        let english = pivot(&handle, "chat", "fr", "en").unwrap();
+        let words: Vec<&str> = english.iter().map(|c| c.translation.as_str()).collect();
+        assert_eq!(words, ["cat", "tomcat"]);
+        assert!(pivot(&handle, "chien", "fr", "de").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,155 @@
+//! Dictionary update checks
+//!
+//! Compares an installed database, as described by its `meta` table (see
+//! [`crate::db::get_metadata`]), with the published manifest, so every app
+//! decides "update available" the same way.
+//!
+//! Builds record the published version as `dict_version`. Databases built
+//! before that only have `built_at`, and are out of date when the manifest
+//! was published later; databases with neither are always out of date.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::download::{self, Manifest};
+use crate::Result;
+
+/// A newer published database than the installed one
+#[derive(Debug, Clone, Serialize)]
+pub struct AvailableUpdate {
+    /// Version of the installed database, if it records one
+    pub installed_version: Option<String>,
+    /// The published database; pass to [`download::download_database`]
+    pub manifest: Manifest,
+}
+
+impl AvailableUpdate {
+    /// Bytes to download
+    pub fn download_size(&self) -> u64 {
+        self.manifest.size
+    }
+
+    /// Bytes the database takes once installed
+    pub fn installed_size(&self) -> u64 {
+        self.manifest.uncompressed_size
+    }
+}
+
+/// Check the manifest at `manifest_url` for a newer database
+///
+/// `installed` is the installed database's metadata. Returns None when the
+/// installed database is current.
+pub fn check(
+    manifest_url: &str,
+    installed: &BTreeMap<String, String>,
+) -> Result<Option<AvailableUpdate>> {
+    let manifest = download::fetch_manifest_at(manifest_url)?;
+    Ok(available_update(manifest, installed))
+}
+
+/// Whether `manifest` is newer than the installed database
+pub fn available_update(
+    manifest: Manifest,
+    installed: &BTreeMap<String, String>,
+) -> Option<AvailableUpdate> {
+    let installed_version = installed.get("dict_version").cloned();
+    let newer = match &installed_version {
+        Some(version) => compare_versions(&manifest.version, version) == Ordering::Greater,
+        None => match installed
+            .get("built_at")
+            .and_then(|t| t.parse::<u64>().ok())
+        {
+            Some(built_at) => manifest.published_at > built_at,
+            None => true,
+        },
+    };
+    newer.then_some(AvailableUpdate {
+        installed_version,
+        manifest,
+    })
+}
+
+/// Compare versions like "42", "2024.06" or "1.10-lite" component-wise
+///
+/// Components are split on `.` and `-`; numeric ones compare as numbers,
+/// others as text, and a number sorts after text. A version with extra
+/// components is newer than its prefix ("1.2.1" > "1.2").
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |v: &str| {
+        v.trim()
+            .trim_start_matches(['v', 'V'])
+            .split(['.', '-'])
+            .map(|part| part.parse::<u64>().map_err(|_| part.to_string()))
+            .collect::<Vec<_>>()
+    };
+    let (a, b) = (parts(a), parts(b));
+    for (x, y) in a.iter().zip(&b) {
+        let ordering = match (x, y) {
+            (Ok(x), Ok(y)) => x.cmp(y),
+            (Ok(_), Err(_)) => Ordering::Greater,
+            (Err(_), Ok(_)) => Ordering::Less,
+            (Err(x), Err(y)) => x.cmp(y),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(version: &str, published_at: u64) -> Manifest {
+        Manifest {
+            language: "english".to_string(),
+            version: version.to_string(),
+            file: format!("english-dict-v{}.db.zst", version),
+            size: 100,
+            uncompressed_size: 400,
+            sha256: String::new(),
+            uncompressed_sha256: String::new(),
+            published_at,
+        }
+    }
+
+    fn metadata(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("10", "9"), Ordering::Greater);
+        assert_eq!(compare_versions("v1.10", "1.9"), Ordering::Greater);
+        assert_eq!(compare_versions("2024.06", "2024.6"), Ordering::Equal);
+        assert_eq!(compare_versions("1.2", "1.2.1"), Ordering::Less);
+        assert_eq!(compare_versions("1.2-beta", "1.2-alpha"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_available_update() {
+        let update =
+            available_update(manifest("43", 2000), &metadata(&[("dict_version", "42")])).unwrap();
+        assert_eq!(update.installed_version.as_deref(), Some("42"));
+        assert_eq!(
+            (update.download_size(), update.installed_size()),
+            (100, 400)
+        );
+
+        // Versions decide, whatever the dates say
+        let current = metadata(&[("dict_version", "43"), ("built_at", "1")]);
+        assert!(available_update(manifest("43", 2000), &current).is_none());
+
+        // Unversioned builds compare dates, and builds without metadata are old
+        let built = metadata(&[("built_at", "1500")]);
+        assert!(available_update(manifest("43", 2000), &built).is_some());
+        assert!(available_update(manifest("43", 1000), &built).is_none());
+        assert!(available_update(manifest("1", 0), &BTreeMap::new()).is_some());
+    }
+}
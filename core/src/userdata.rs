@@ -0,0 +1,654 @@
+//! The user's own data: history, bookmarks, flashcards and activity
+//! statistics
+//!
+//! [`UserData`] opens the user database (the file given as
+//! [`crate::DictConfig::user_db`]) read-write, next to the read-only
+//! dictionary handle, and records what the user does so the app can show
+//! recent entries, bookmarks, due flashcards, streaks and learning
+//! progress. Days are counted from 1970-01-01 in the user's time zone; the
+//! app decides what "today" is.
+//!
+//! Entries are kept by word id and headword: ids only hold for the
+//! dictionary version they were recorded with. Bookmarks and flashcards
+//! can target one sense of an entry by its definition id, which holds
+//! just as long.
+
+use std::ops::RangeInclusive;
+
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::lists::WordList;
+use crate::Result;
+
+/// Tables kept in the user database
+const USER_SCHEMA: &str = r#"
+-- Activity per day (days since 1970-01-01, user's time zone)
+CREATE TABLE IF NOT EXISTS stats (
+    day INTEGER PRIMARY KEY,
+    lookups INTEGER NOT NULL DEFAULT 0,
+    bookmarks INTEGER NOT NULL DEFAULT 0,
+    reviews INTEGER NOT NULL DEFAULT 0
+);
+
+-- Entries viewed, latest view only
+CREATE TABLE IF NOT EXISTS history (
+    word_id INTEGER PRIMARY KEY,
+    word TEXT NOT NULL,
+    viewed_at INTEGER NOT NULL,  -- Unix time
+    views INTEGER NOT NULL DEFAULT 1
+);
+
+CREATE INDEX IF NOT EXISTS idx_history_viewed_at ON history(viewed_at);
+
+-- Flashcards for entries and senses, scheduled by a simplified SM-2
+CREATE TABLE IF NOT EXISTS cards (
+    id INTEGER PRIMARY KEY,
+    word_id INTEGER NOT NULL,
+    word TEXT NOT NULL,
+    due INTEGER NOT NULL,       -- day the card is next shown
+    interval INTEGER NOT NULL DEFAULT 0,  -- days
+    ease REAL NOT NULL DEFAULT 2.5,
+    definition_id INTEGER       -- the sense studied, NULL for the whole entry
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_cards_target
+    ON cards(word_id, COALESCE(definition_id, 0));
+CREATE INDEX IF NOT EXISTS idx_cards_due ON cards(due);
+
+-- Bookmarked entries and senses, with the user's notes
+CREATE TABLE IF NOT EXISTS bookmarks (
+    id INTEGER PRIMARY KEY,
+    word_id INTEGER NOT NULL,
+    word TEXT NOT NULL,
+    definition_id INTEGER,      -- the sense bookmarked, NULL for the whole entry
+    note TEXT,
+    created_at INTEGER NOT NULL  -- Unix time
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_bookmarks_target
+    ON bookmarks(word_id, COALESCE(definition_id, 0));
+CREATE INDEX IF NOT EXISTS idx_bookmarks_created_at ON bookmarks(created_at);
+"#;
+
+/// Lowest ease factor, after repeated lapses
+const MIN_EASE: f64 = 1.3;
+
+/// The user database, open for writing
+pub struct UserData {
+    conn: Connection,
+}
+
+/// What the user did on one day
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DayActivity {
+    /// Days since 1970-01-01
+    pub day: i64,
+    /// Entries looked up
+    pub lookups: u32,
+    /// Bookmarks added
+    pub bookmarks: u32,
+    /// Flashcards reviewed
+    pub reviews: u32,
+}
+
+/// An entry in the view history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub word_id: i64,
+    pub word: String,
+    /// Unix time of the latest view
+    pub viewed_at: i64,
+    pub views: u32,
+}
+
+/// A bookmarked entry, or one sense of it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub word_id: i64,
+    pub word: String,
+    /// Definition id of the bookmarked sense, or None for the whole entry
+    #[serde(default)]
+    pub definition_id: Option<i64>,
+    /// The user's note
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Unix time the bookmark was added
+    pub created_at: i64,
+}
+
+/// A flashcard
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Card {
+    pub word_id: i64,
+    pub word: String,
+    /// Definition id of the sense studied, or None for the whole entry
+    #[serde(default)]
+    pub definition_id: Option<i64>,
+    /// Day the card is next due
+    pub due: i64,
+    /// Days between the last two reviews
+    pub interval: i64,
+    pub ease: f64,
+}
+
+/// How well a flashcard was remembered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Grade {
+    /// Forgotten: shown again tomorrow
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl UserData {
+    /// Open the user database at `path`, creating it if necessary
+    pub fn open(path: &str) -> Result<Self> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_WRITE
+                | OpenFlags::SQLITE_OPEN_CREATE
+                | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        // Cards made when an entry had one, keyed by word id
+        if crate::db::table_exists(&conn, "cards")? && !crate::db::has_column(&conn, "cards", "id")?
+        {
+            migrate_cards(&conn)?;
+        }
+        conn.execute_batch(USER_SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Count an entry looked up on `day`
+    pub fn record_lookup(&self, day: i64) -> Result<()> {
+        self.bump(day, "lookups")
+    }
+
+    /// Count a bookmark added on `day`
+    pub fn record_bookmark(&self, day: i64) -> Result<()> {
+        self.bump(day, "bookmarks")
+    }
+
+    /// Count a flashcard reviewed on `day`
+    pub fn record_review(&self, day: i64) -> Result<()> {
+        self.bump(day, "reviews")
+    }
+
+    fn bump(&self, day: i64, column: &str) -> Result<()> {
+        self.conn
+            .prepare_cached(&format!(
+                "INSERT INTO stats (day, {column}) VALUES (?, 1)
+                 ON CONFLICT(day) DO UPDATE SET {column} = {column} + 1"
+            ))?
+            .execute(params![day])?;
+        Ok(())
+    }
+
+    /// Record a view of an entry at `at` (Unix time)
+    pub fn record_view(&self, word_id: i64, word: &str, at: i64) -> Result<()> {
+        self.conn
+            .prepare_cached(
+                "INSERT INTO history (word_id, word, viewed_at) VALUES (?, ?, ?)
+                 ON CONFLICT(word_id) DO UPDATE SET
+                     word = excluded.word, viewed_at = excluded.viewed_at, views = views + 1",
+            )?
+            .execute(params![word_id, word, at])?;
+        Ok(())
+    }
+
+    /// The most recently viewed entries, latest first
+    pub fn recent_history(&self, limit: u32) -> Result<Vec<HistoryEntry>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT word_id, word, viewed_at, views FROM history
+             ORDER BY viewed_at DESC, word_id LIMIT ?",
+        )?;
+        let rows = stmt.query_map([limit], |row| {
+            Ok(HistoryEntry {
+                word_id: row.get(0)?,
+                word: row.get(1)?,
+                viewed_at: row.get(2)?,
+                views: row.get(3)?,
+            })
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| e.into())
+    }
+
+    /// Bookmark an entry, or with `definition_id` one of its senses, at
+    /// `at` (Unix time)
+    ///
+    /// Bookmarking the same entry or sense again replaces its note and
+    /// keeps when it was added. Counting the bookmark in the day's
+    /// activity is left to `record_bookmark`.
+    pub fn add_bookmark(
+        &self,
+        word_id: i64,
+        word: &str,
+        definition_id: Option<i64>,
+        note: Option<&str>,
+        at: i64,
+    ) -> Result<()> {
+        self.conn
+            .prepare_cached(
+                "INSERT INTO bookmarks (word_id, word, definition_id, note, created_at)
+                 VALUES (?, ?, ?, ?, ?)
+                 ON CONFLICT(word_id, COALESCE(definition_id, 0)) DO UPDATE SET
+                     word = excluded.word, note = excluded.note",
+            )?
+            .execute(params![word_id, word, definition_id, note, at])?;
+        Ok(())
+    }
+
+    /// Remove the bookmark of an entry, or with `definition_id` of one of
+    /// its senses, returning whether there was one
+    pub fn remove_bookmark(&self, word_id: i64, definition_id: Option<i64>) -> Result<bool> {
+        let removed = self
+            .conn
+            .prepare_cached("DELETE FROM bookmarks WHERE word_id = ? AND definition_id IS ?")?
+            .execute(params![word_id, definition_id])?;
+        Ok(removed > 0)
+    }
+
+    /// The most recent bookmarks, latest first
+    pub fn bookmarks(&self, limit: u32) -> Result<Vec<Bookmark>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT word_id, word, definition_id, note, created_at FROM bookmarks
+             ORDER BY created_at DESC, id DESC LIMIT ?",
+        )?;
+        let rows = stmt.query_map([limit], row_to_bookmark)?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| e.into())
+    }
+
+    /// Bookmarks of one entry, for marking it and its senses when shown:
+    /// the whole entry's first, then its senses' by definition id
+    pub fn entry_bookmarks(&self, word_id: i64) -> Result<Vec<Bookmark>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT word_id, word, definition_id, note, created_at FROM bookmarks
+             WHERE word_id = ? ORDER BY definition_id IS NOT NULL, definition_id",
+        )?;
+        let rows = stmt.query_map([word_id], row_to_bookmark)?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| e.into())
+    }
+
+    /// Add a flashcard for an entry, due on `day`; existing cards are kept
+    pub fn add_card(&self, word_id: i64, word: &str, day: i64) -> Result<()> {
+        self.conn
+            .prepare_cached("INSERT OR IGNORE INTO cards (word_id, word, due) VALUES (?, ?, ?)")?
+            .execute(params![word_id, word, day])?;
+        Ok(())
+    }
+
+    /// Add a flashcard studying one sense of an entry, due on `day`
+    ///
+    /// Each sense gets a card of its own, apart from the whole entry's;
+    /// an existing card for the sense is kept.
+    pub fn add_sense_card(
+        &self,
+        word_id: i64,
+        word: &str,
+        definition_id: i64,
+        day: i64,
+    ) -> Result<()> {
+        self.conn
+            .prepare_cached(
+                "INSERT OR IGNORE INTO cards (word_id, word, due, definition_id)
+                 VALUES (?, ?, ?, ?)",
+            )?
+            .execute(params![word_id, word, day, definition_id])?;
+        Ok(())
+    }
+
+    /// Add flashcards for the words of a list, due on `day`, returning how
+    /// many were new
+    pub fn add_list(&self, list: &WordList, day: i64) -> Result<u32> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut added = 0;
+        {
+            let mut insert = tx.prepare_cached(
+                "INSERT OR IGNORE INTO cards (word_id, word, due) VALUES (?, ?, ?)",
+            )?;
+            for entry in &list.entries {
+                added += insert.execute(params![entry.word_id, entry.word, day])? as u32;
+            }
+        }
+        tx.commit()?;
+        Ok(added)
+    }
+
+    /// Cards due on or before `day`, most overdue first
+    pub fn due_cards(&self, day: i64, limit: u32) -> Result<Vec<Card>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT word_id, word, due, interval, ease, definition_id FROM cards
+             WHERE due <= ? ORDER BY due, word_id, id LIMIT ?",
+        )?;
+        let rows = stmt.query_map(params![day, limit], row_to_card)?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| e.into())
+    }
+
+    /// Reschedule the card of an entry, or with `definition_id` of one of
+    /// its senses, after reviewing it on `day`, counting the review
+    ///
+    /// Returns the rescheduled card, or None if there is no such card.
+    pub fn review_card(
+        &self,
+        word_id: i64,
+        definition_id: Option<i64>,
+        grade: Grade,
+        day: i64,
+    ) -> Result<Option<Card>> {
+        let card = self
+            .conn
+            .query_row(
+                "SELECT word_id, word, due, interval, ease, definition_id FROM cards
+                 WHERE word_id = ? AND definition_id IS ?",
+                params![word_id, definition_id],
+                row_to_card,
+            )
+            .optional()?;
+        let Some(mut card) = card else {
+            return Ok(None);
+        };
+
+        let (interval, ease) = match grade {
+            Grade::Again => (1, card.ease - 0.2),
+            Grade::Hard => ((card.interval as f64 * 1.2).ceil() as i64, card.ease - 0.15),
+            Grade::Good => ((card.interval as f64 * card.ease).ceil() as i64, card.ease),
+            Grade::Easy => (
+                (card.interval as f64 * card.ease * 1.3).ceil() as i64,
+                card.ease + 0.15,
+            ),
+        };
+        card.interval = interval.max(1);
+        card.ease = ease.max(MIN_EASE);
+        card.due = day + card.interval;
+        self.conn.execute(
+            "UPDATE cards SET due = ?, interval = ?, ease = ?
+             WHERE word_id = ? AND definition_id IS ?",
+            params![card.due, card.interval, card.ease, word_id, definition_id],
+        )?;
+        self.record_review(day)?;
+        Ok(Some(card))
+    }
+
+    /// Activity on the days in `range`, oldest first
+    ///
+    /// Days without any activity are left out.
+    pub fn get_activity(&self, range: RangeInclusive<i64>) -> Result<Vec<DayActivity>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT day, lookups, bookmarks, reviews FROM stats
+             WHERE day BETWEEN ? AND ? ORDER BY day",
+        )?;
+        let rows = stmt.query_map(params![range.start(), range.end()], |row| {
+            Ok(DayActivity {
+                day: row.get(0)?,
+                lookups: row.get(1)?,
+                bookmarks: row.get(2)?,
+                reviews: row.get(3)?,
+            })
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| e.into())
+    }
+
+    /// Consecutive days with any activity, up to `today`
+    ///
+    /// A streak isn't broken until a whole day is missed: with nothing
+    /// yet today, it counts back from yesterday.
+    pub fn streak(&self, today: i64) -> Result<u32> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT day FROM stats
+             WHERE day <= ? AND lookups + bookmarks + reviews > 0
+             ORDER BY day DESC",
+        )?;
+        let days = stmt.query_map([today], |row| row.get::<_, i64>(0))?;
+        let mut streak = 0;
+        let mut expected: Option<i64> = None;
+        for day in days {
+            let day = day?;
+            let wanted = match expected {
+                Some(wanted) => wanted,
+                None if day >= today - 1 => day,
+                None => break,
+            };
+            if day != wanted {
+                break;
+            }
+            streak += 1;
+            expected = Some(day - 1);
+        }
+        Ok(streak)
+    }
+}
+
+fn row_to_bookmark(row: &rusqlite::Row) -> rusqlite::Result<Bookmark> {
+    Ok(Bookmark {
+        word_id: row.get(0)?,
+        word: row.get(1)?,
+        definition_id: row.get(2)?,
+        note: row.get(3)?,
+        created_at: row.get(4)?,
+    })
+}
+
+/// Rebuild a `cards` table keyed by word id with ids of its own, so an
+/// entry can have a card per sense, keeping the cards and their schedules
+fn migrate_cards(conn: &Connection) -> Result<()> {
+    // Cards made before they could target a sense at all
+    let definition_id = if crate::db::has_column(conn, "cards", "definition_id")? {
+        "definition_id"
+    } else {
+        "NULL"
+    };
+    let tx = conn.unchecked_transaction()?;
+    tx.execute_batch(
+        "DROP INDEX IF EXISTS idx_cards_due;
+         ALTER TABLE cards RENAME TO cards_by_word;",
+    )?;
+    tx.execute_batch(USER_SCHEMA)?;
+    tx.execute_batch(&format!(
+        "INSERT INTO cards (word_id, word, due, interval, ease, definition_id)
+         SELECT word_id, word, due, interval, ease, {definition_id} FROM cards_by_word;
+         DROP TABLE cards_by_word;"
+    ))?;
+    tx.commit()?;
+    Ok(())
+}
+
+fn row_to_card(row: &rusqlite::Row) -> rusqlite::Result<Card> {
+    Ok(Card {
+        word_id: row.get(0)?,
+        word: row.get(1)?,
+        definition_id: row.get(5)?,
+        due: row.get(2)?,
+        interval: row.get(3)?,
+        ease: row.get(4)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_activity_and_streak() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("user.db");
+        let user = UserData::open(path.to_str().unwrap()).unwrap();
+
+        for day in [100, 101, 101, 103, 104] {
+            user.record_lookup(day).unwrap();
+        }
+        user.record_bookmark(101).unwrap();
+        user.record_review(105).unwrap();
+
+        let activity = user.get_activity(101..=104).unwrap();
+        assert_eq!(activity.len(), 3);
+        assert_eq!(
+            activity[0],
+            DayActivity {
+                day: 101,
+                lookups: 2,
+                bookmarks: 1,
+                reviews: 0,
+            }
+        );
+
+        assert_eq!(user.streak(105).unwrap(), 3);
+        // Nothing yet on day 106: the streak still stands
+        assert_eq!(user.streak(106).unwrap(), 3);
+        assert_eq!(user.streak(107).unwrap(), 0);
+        assert_eq!(user.streak(101).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_history_and_cards() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("user.db");
+        let user = UserData::open(path.to_str().unwrap()).unwrap();
+
+        user.record_view(1, "cat", 1000).unwrap();
+        user.record_view(2, "dog", 2000).unwrap();
+        user.record_view(1, "cat", 3000).unwrap();
+        let history = user.recent_history(10).unwrap();
+        assert_eq!(history[0].word, "cat");
+        assert_eq!((history[0].viewed_at, history[0].views), (3000, 2));
+        assert_eq!(history.len(), 2);
+
+        user.add_card(1, "cat", 10).unwrap();
+        user.add_card(2, "dog", 12).unwrap();
+        assert_eq!(user.due_cards(11, 10).unwrap().len(), 1);
+
+        let card = user.review_card(1, None, Grade::Good, 11).unwrap().unwrap();
+        assert_eq!((card.due, card.interval), (12, 1));
+        let card = user.review_card(1, None, Grade::Good, 12).unwrap().unwrap();
+        assert_eq!((card.due, card.interval), (15, 3));
+        let card = user
+            .review_card(1, None, Grade::Again, 15)
+            .unwrap()
+            .unwrap();
+        assert_eq!(card.due, 16);
+        assert!(card.ease < 2.5);
+        assert!(user
+            .review_card(9, None, Grade::Good, 15)
+            .unwrap()
+            .is_none());
+        assert_eq!(user.get_activity(11..=15).unwrap().len(), 3);
+
+        let list = WordList {
+            name: "pets".to_string(),
+            entries: [(2, "dog"), (3, "bird")]
+                .map(|(word_id, word)| crate::lists::WordListEntry {
+                    word_id,
+                    word: word.to_string(),
+                    level: None,
+                })
+                .to_vec(),
+        };
+        assert_eq!(user.add_list(&list, 20).unwrap(), 1);
+        assert_eq!(user.due_cards(20, 10).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_sense_bookmarks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("user.db");
+        let user = UserData::open(path.to_str().unwrap()).unwrap();
+
+        user.add_bookmark(1, "bank", Some(12), Some("river"), 100)
+            .unwrap();
+        user.add_bookmark(1, "bank", None, None, 200).unwrap();
+        user.add_bookmark(1, "bank", Some(11), None, 300).unwrap();
+        user.add_bookmark(2, "cat", None, None, 400).unwrap();
+        // Again: the note is replaced, the time kept
+        user.add_bookmark(1, "bank", Some(12), Some("shore"), 500)
+            .unwrap();
+
+        let bank = user.entry_bookmarks(1).unwrap();
+        let targets: Vec<Option<i64>> = bank.iter().map(|b| b.definition_id).collect();
+        assert_eq!(targets, [None, Some(11), Some(12)]);
+        assert_eq!(bank[2].note.as_deref(), Some("shore"));
+        assert_eq!(bank[2].created_at, 100);
+        assert_eq!(user.bookmarks(2).unwrap()[0].word, "cat");
+
+        assert!(user.remove_bookmark(1, None).unwrap());
+        assert!(!user.remove_bookmark(1, None).unwrap());
+        assert_eq!(user.entry_bookmarks(1).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_sense_cards() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("user.db");
+        let user = UserData::open(path.to_str().unwrap()).unwrap();
+
+        user.add_card(1, "bank", 10).unwrap();
+        user.add_sense_card(1, "bank", 11, 10).unwrap();
+        user.add_sense_card(1, "bank", 12, 10).unwrap();
+        // Again: the card and its schedule are kept
+        user.add_sense_card(1, "bank", 12, 30).unwrap();
+        let targets: Vec<Option<i64>> = user
+            .due_cards(10, 10)
+            .unwrap()
+            .iter()
+            .map(|c| c.definition_id)
+            .collect();
+        assert_eq!(targets, [None, Some(11), Some(12)]);
+
+        // Each sense is scheduled on its own
+        let card = user.review_card(1, Some(12), Grade::Good, 10).unwrap();
+        assert_eq!(card.unwrap().due, 11);
+        let due: Vec<(Option<i64>, i64)> = user
+            .due_cards(20, 10)
+            .unwrap()
+            .iter()
+            .map(|c| (c.definition_id, c.due))
+            .collect();
+        assert_eq!(due, [(None, 10), (Some(11), 10), (Some(12), 11)]);
+        assert!(user
+            .review_card(1, Some(13), Grade::Good, 10)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_cards_migration() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("user.db");
+        // Cards as they were kept when an entry had one
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE cards (
+                 word_id INTEGER PRIMARY KEY,
+                 word TEXT NOT NULL,
+                 due INTEGER NOT NULL,
+                 interval INTEGER NOT NULL DEFAULT 0,
+                 ease REAL NOT NULL DEFAULT 2.5
+             );
+             CREATE INDEX idx_cards_due ON cards(due);
+             INSERT INTO cards (word_id, word, due, interval, ease)
+                 VALUES (1, 'bank', 15, 3, 2.2);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let user = UserData::open(path.to_str().unwrap()).unwrap();
+        let cards = user.due_cards(20, 10).unwrap();
+        assert_eq!(cards.len(), 1);
+        assert_eq!(
+            (cards[0].due, cards[0].interval, cards[0].ease),
+            (15, 3, 2.2)
+        );
+        user.add_sense_card(1, "bank", 12, 20).unwrap();
+        assert_eq!(user.due_cards(20, 10).unwrap().len(), 2);
+
+        // Opening it again leaves it as it is
+        drop(user);
+        let user = UserData::open(path.to_str().unwrap()).unwrap();
+        assert_eq!(user.due_cards(20, 10).unwrap().len(), 2);
+    }
+}
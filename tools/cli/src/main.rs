@@ -0,0 +1,247 @@
+//! `dict` - command-line dictionary lookups
+//!
+//! Queries a dictionary database built by the preprocessor.
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Full entry for a word
+//! dict --db english-dict.db lookup hello
+//!
+//! # Ranked search results
+//! dict --db english-dict.db search helo --limit 5
+//!
+//! # A random word, as JSON
+//! DICT_DB=english-dict.db dict random --json
+//!
+//! # British spellings first ("colour", also spelled "color")
+//! dict --db english-dict.db search color --spelling uk
+//!
+//! # An entry as plain sentences, for a screen reader or `espeak`
+//! dict --db english-dict.db lookup hello --plain
+//!
+//! # Interactive browser
+//! dict --db english-dict.db tui
+//!
+//! # Recall/latency checks; exits with 1 if a target is missed
+//! dict --db english-dict.db qa --samples 500
+//! ```
+
+mod render;
+mod tui;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use dict_core::render::Verbosity;
+use dict_core::{
+    DefinitionOptions, DefinitionParts, DictConfig, DictHandle, FullDefinition, SpellingRegion,
+};
+
+/// Number of suggestions shown when a lookup finds nothing
+const SUGGESTION_LIMIT: u32 = 5;
+
+/// Entries show what "plural of cat" style senses point at
+const DEFINITION_OPTIONS: DefinitionOptions = DefinitionOptions {
+    inline_form_of: true,
+    links: false,
+    parts: DefinitionParts::ALL,
+    respelling: false,
+};
+
+/// Look up words in a Wiktionary dictionary database
+#[derive(Parser, Debug)]
+#[command(name = "dict", author, version)]
+struct Args {
+    /// Dictionary database to query
+    #[arg(long, env = "DICT_DB", global = true)]
+    db: Option<PathBuf>,
+
+    /// Print machine-readable JSON instead of formatted text
+    #[arg(long, global = true, default_value = "false")]
+    json: bool,
+
+    /// Print entries as plain sentences for screen readers and
+    /// text-to-speech
+    #[arg(long, global = true, default_value = "false", conflicts_with = "json")]
+    plain: bool,
+
+    /// Show US or UK spellings of words spelled both ways, listing the
+    /// other as "also spelled"
+    #[arg(long, global = true, value_enum)]
+    spelling: Option<Spelling>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Spelling {
+    Us,
+    Uk,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Show every entry for a word
+    Lookup {
+        /// The word to look up
+        word: String,
+    },
+    /// Search for words matching a query
+    Search {
+        /// Search query (exact, prefix, full-text and fuzzy matches)
+        query: String,
+
+        /// Maximum number of results
+        #[arg(short, long, default_value = "20")]
+        limit: u32,
+
+        /// Number of results to skip
+        #[arg(long, default_value = "0")]
+        offset: u32,
+    },
+    /// Show a random word
+    Random,
+    /// Browse the dictionary interactively
+    Tui,
+    /// Check search recall and latency against release targets
+    Qa {
+        /// Headwords to sample
+        #[arg(long, default_value = "200")]
+        samples: usize,
+    },
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    match run(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("dict: {:#}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Run the selected command, returning whether anything was found
+fn run(args: &Args) -> Result<bool> {
+    let db = args
+        .db
+        .as_ref()
+        .context("No database given; pass --db or set DICT_DB")?;
+    if !db.exists() {
+        anyhow::bail!("Database does not exist: {:?}", db);
+    }
+    let config = DictConfig {
+        spelling: args.spelling.map(|spelling| match spelling {
+            Spelling::Us => SpellingRegion::Us,
+            Spelling::Uk => SpellingRegion::Uk,
+        }),
+        ..Default::default()
+    };
+    let handle = dict_core::init_with_config(db.to_str().context("Invalid database path")?, config)
+        .context("Failed to open database")?;
+
+    match &args.command {
+        Command::Lookup { word } => lookup(&handle, word, args),
+        Command::Search {
+            query,
+            limit,
+            offset,
+        } => {
+            let results = dict_core::search::search_words_offset(&handle, query, *limit, *offset)?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&results)?);
+            } else {
+                render::search_results(&results, query, handle.normalization())?;
+            }
+            Ok(!results.is_empty())
+        }
+        Command::Random => {
+            let Some(word) = dict_core::db::get_random_word(&handle)? else {
+                return Ok(false);
+            };
+            let entries = full_definitions(&handle, &[word.id])?;
+            print_entries(&entries, args)?;
+            Ok(true)
+        }
+        Command::Tui => {
+            tui::run(&handle)?;
+            Ok(true)
+        }
+        Command::Qa { samples } => {
+            let config = dict_core::qa::QaConfig {
+                sample_size: *samples,
+                ..Default::default()
+            };
+            let report = dict_core::qa::run(&handle, &config)?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                render::qa_report(&report)?;
+            }
+            Ok(report.passed)
+        }
+    }
+}
+
+/// Print all entries for a word, or suggestions if there are none
+///
+/// Formatted output groups the entries by etymology; JSON and plain text
+/// list them one after another.
+fn lookup(handle: &DictHandle, word: &str, args: &Args) -> Result<bool> {
+    let groups = dict_core::db::get_definitions_for_word(handle, word, &DEFINITION_OPTIONS)?;
+
+    if !groups.is_empty() {
+        if args.json || args.plain {
+            let entries: Vec<FullDefinition> = groups
+                .into_iter()
+                .flat_map(|group| group.entries)
+                .map(|entry| entry.definition)
+                .collect();
+            print_entries(&entries, args)?;
+        } else {
+            render::etymology_groups(&groups)?;
+        }
+        return Ok(true);
+    }
+
+    let suggestions = dict_core::search::search_words(handle, word, SUGGESTION_LIMIT)?;
+    if args.json {
+        println!("[]");
+    } else {
+        render::not_found(word, &suggestions)?;
+    }
+    Ok(false)
+}
+
+fn full_definitions(handle: &DictHandle, ids: &[i64]) -> Result<Vec<FullDefinition>> {
+    let mut entries = Vec::with_capacity(ids.len());
+    for &id in ids {
+        if let Some(def) =
+            dict_core::db::get_full_definition_with_options(handle, id, &DEFINITION_OPTIONS)?
+        {
+            entries.push(def);
+        }
+    }
+    Ok(entries)
+}
+
+fn print_entries(entries: &[FullDefinition], args: &Args) -> Result<()> {
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(entries)?);
+    } else if args.plain {
+        let texts: Vec<String> = entries
+            .iter()
+            .map(|def| dict_core::render::definition_plaintext(def, Verbosity::Full))
+            .collect();
+        println!("{}", texts.join("\n\n"));
+    } else {
+        render::entries(entries)?;
+    }
+    Ok(())
+}
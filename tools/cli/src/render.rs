@@ -0,0 +1,231 @@
+//! Terminal formatting of entries and search results
+//!
+//! Output goes through `anstream`, which drops the colors when stdout is not
+//! a terminal or `NO_COLOR` is set.
+
+use std::io::Write;
+use std::ops::Range;
+
+use anstyle::{AnsiColor, Style};
+use dict_core::graphemes;
+use dict_core::normalize::Normalization;
+use dict_core::qa::QaReport;
+use dict_core::{EtymologyGroup, FullDefinition, SearchResult};
+
+const HEADWORD: Style = Style::new().bold();
+const POS: Style = AnsiColor::Cyan.on_default().italic();
+const IPA: Style = AnsiColor::Green.on_default();
+const TAG: Style = AnsiColor::Yellow.on_default();
+const DIM: Style = Style::new().dimmed();
+const PASS: Style = AnsiColor::Green.on_default().bold();
+const FAIL: Style = AnsiColor::Red.on_default().bold();
+
+/// Print full entries, one block per part of speech/etymology
+pub fn entries(entries: &[FullDefinition]) -> std::io::Result<()> {
+    let mut out = anstream::stdout().lock();
+    for (i, def) in entries.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        entry(&mut out, def, true)?;
+    }
+    Ok(())
+}
+
+/// Print the entries of a headword under "Etymology 1", "Etymology 2", ...
+/// headings, with the etymology text, where a language has several
+pub fn etymology_groups(groups: &[EtymologyGroup]) -> std::io::Result<()> {
+    let mut out = anstream::stdout().lock();
+    for (i, group) in groups.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        let several = groups
+            .iter()
+            .filter(|g| g.language == group.language)
+            .count()
+            > 1;
+        if several {
+            write!(out, "{DIM}Etymology {}{DIM:#}", group.etymology_num)?;
+            match &group.etymology {
+                Some(etymology) => writeln!(out, "{DIM}:{DIM:#} {}", etymology)?,
+                None => writeln!(out)?,
+            }
+        }
+        for (j, e) in group.entries.iter().enumerate() {
+            if j > 0 {
+                writeln!(out)?;
+            }
+            entry(&mut out, &e.definition, !several)?;
+        }
+    }
+    Ok(())
+}
+
+/// Print one entry, with its etymology unless `with_etymology` is false
+fn entry(out: &mut impl Write, def: &FullDefinition, with_etymology: bool) -> std::io::Result<()> {
+    write!(
+        out,
+        "{HEADWORD}{}{HEADWORD:#} {POS}{}{POS:#}",
+        def.word, def.pos
+    )?;
+    if !def.language.is_empty() {
+        write!(out, " {DIM}({}){DIM:#}", def.language)?;
+    }
+    if !def.also_spelled.is_empty() {
+        write!(
+            out,
+            " {DIM}also spelled{DIM:#} {}",
+            def.also_spelled.join(", ")
+        )?;
+    }
+    writeln!(out)?;
+
+    for p in &def.pronunciations {
+        let Some(ipa) = &p.ipa else { continue };
+        write!(out, "  {IPA}{}{IPA:#}", ipa)?;
+        if let Some(accent) = &p.accent {
+            write!(out, " {DIM}[{}]{DIM:#}", accent)?;
+        }
+        writeln!(out)?;
+    }
+
+    for (n, sense) in def.definitions.iter().enumerate() {
+        write!(out, "  {}. ", n + 1)?;
+        if !sense.tags.is_empty() {
+            write!(out, "{TAG}({}){TAG:#} ", sense.tags.join(", "))?;
+        }
+        writeln!(out, "{}", sense.text)?;
+        for example in &sense.examples {
+            writeln!(out, "       {DIM}\u{201c}{}\u{201d}{DIM:#}", example)?;
+        }
+        if let Some(form_of) = &sense.form_of {
+            for target in &form_of.definitions {
+                writeln!(out, "       {DIM}{}:{DIM:#} {}", form_of.word, target.text)?;
+            }
+        }
+    }
+
+    if let Some(etymology) = def.etymology.as_ref().filter(|_| with_etymology) {
+        writeln!(out, "  {DIM}Etymology:{DIM:#} {}", etymology)?;
+    }
+
+    if !def.translations.is_empty() {
+        let translations: Vec<String> = def
+            .translations
+            .iter()
+            .map(|t| format!("{}: {}", t.target_language, t.translation))
+            .collect();
+        writeln!(
+            out,
+            "  {DIM}Translations:{DIM:#} {}",
+            translations.join("; ")
+        )?;
+    }
+
+    if let Some(attribution) = &def.attribution {
+        writeln!(
+            out,
+            "  {DIM}Source: {}, {}{DIM:#}",
+            attribution.source, attribution.license
+        )?;
+    }
+    Ok(())
+}
+
+/// Print search results as an aligned list, with the parts matching
+/// `query` underlined
+pub fn search_results(
+    results: &[SearchResult],
+    query: &str,
+    normalization: Normalization,
+) -> std::io::Result<()> {
+    let mut out = anstream::stdout().lock();
+    let width = results
+        .iter()
+        .map(|r| graphemes::count(&r.word))
+        .max()
+        .unwrap_or(0);
+    if let Some(compound) = results.first().and_then(|r| r.compound_of.as_deref()) {
+        writeln!(out, "{DIM}'{}' is a compound of:{DIM:#}", compound)?;
+    }
+    for r in results {
+        let padding = width - graphemes::count(&r.word);
+        let ranges = graphemes::highlight(&r.word, query, normalization);
+        highlighted(&mut out, &r.word, &ranges, HEADWORD)?;
+        write!(out, "{:padding$}  {POS}{}{POS:#}  ", "", r.pos)?;
+        if !r.also_spelled.is_empty() {
+            write!(out, "{DIM}[also {}]{DIM:#} ", r.also_spelled.join(", "))?;
+        }
+        if !r.tags.is_empty() {
+            write!(out, "{TAG}({}){TAG:#} ", r.tags.join(", "))?;
+        }
+        let ranges = graphemes::highlight(&r.preview, query, normalization);
+        highlighted(&mut out, &r.preview, &ranges, Style::new())?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Write `text` in `style`, with `ranges` of it underlined as well
+fn highlighted(
+    out: &mut impl Write,
+    text: &str,
+    ranges: &[Range<usize>],
+    style: Style,
+) -> std::io::Result<()> {
+    let matched = style.underline();
+    let mut written = 0;
+    for range in ranges {
+        if written < range.start {
+            write!(out, "{style}{}{style:#}", &text[written..range.start])?;
+        }
+        write!(out, "{matched}{}{matched:#}", &text[range.clone()])?;
+        written = range.end;
+    }
+    if written < text.len() {
+        write!(out, "{style}{}{style:#}", &text[written..])?;
+    }
+    Ok(())
+}
+
+/// Report a failed lookup along with close matches
+pub fn not_found(word: &str, suggestions: &[SearchResult]) -> std::io::Result<()> {
+    let mut out = anstream::stderr().lock();
+    writeln!(out, "No entry for '{}'", word)?;
+    if !suggestions.is_empty() {
+        let words: Vec<&str> = suggestions.iter().map(|s| s.word.as_str()).collect();
+        writeln!(out, "Did you mean: {}?", words.join(", "))?;
+    }
+    Ok(())
+}
+
+/// Print a QA report, one line per check
+pub fn qa_report(report: &QaReport) -> std::io::Result<()> {
+    let mut out = anstream::stdout().lock();
+    writeln!(
+        out,
+        "{DIM}{} headwords sampled from {} entries{DIM:#}",
+        report.samples, report.total_words
+    )?;
+    for check in &report.checks {
+        let (style, verdict) = if check.passed {
+            (PASS, "pass")
+        } else {
+            (FAIL, "FAIL")
+        };
+        let kind = format!("{:?}", check.kind).to_lowercase();
+        writeln!(
+            out,
+            "{style}{verdict}{style:#}  {HEADWORD}{kind:<10}{HEADWORD:#}  recall {:>5.1}% (min {:.0}%)  p95 {:>7.2} ms (max {:.0} ms)",
+            check.recall * 100.0,
+            check.min_recall * 100.0,
+            check.latency.p95_ms,
+            check.max_p95_ms
+        )?;
+        if !check.misses.is_empty() {
+            writeln!(out, "      {DIM}missed: {}{DIM:#}", check.misses.join(", "))?;
+        }
+    }
+    Ok(())
+}
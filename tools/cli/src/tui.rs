@@ -0,0 +1,408 @@
+//! Interactive terminal browser (`dict tui`)
+//!
+//! A search box that re-runs the search on every keystroke, a results list
+//! that loads further pages as the selection reaches its end, and a
+//! definition pane for the selected entry.
+//!
+//! Keys: type to search, Up/Down to move through results, PageUp/PageDown
+//! to scroll the definition, Ctrl-U to clear the query, Esc or Ctrl-C to quit.
+
+use anyhow::Result;
+use dict_core::{DictHandle, FullDefinition, SearchResult};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+/// Number of results fetched per page
+const PAGE_SIZE: u32 = 50;
+
+/// Lines scrolled by PageUp/PageDown in the definition pane
+const SCROLL_STEP: u16 = 10;
+
+/// Run the browser until the user quits
+pub fn run(handle: &DictHandle) -> Result<()> {
+    let mut app = App::new(handle);
+    ratatui::run(|terminal| app.event_loop(terminal))
+}
+
+struct App<'a> {
+    handle: &'a DictHandle,
+    query: String,
+    results: Vec<SearchResult>,
+    /// Whether the last page fetched was full, i.e. more results may follow
+    more_results: bool,
+    list_state: ListState,
+    definition: Option<FullDefinition>,
+    scroll: u16,
+    error: Option<String>,
+}
+
+impl<'a> App<'a> {
+    fn new(handle: &'a DictHandle) -> Self {
+        Self {
+            handle,
+            query: String::new(),
+            results: Vec::new(),
+            more_results: false,
+            list_state: ListState::default(),
+            definition: None,
+            scroll: 0,
+            error: None,
+        }
+    }
+
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.render(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle_key(key) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Apply a key press; returns false when the user asked to quit
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return false,
+            KeyCode::Char('c') if ctrl => return false,
+            KeyCode::Char('u') if ctrl => {
+                self.query.clear();
+                self.search();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.search();
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.search();
+            }
+            KeyCode::Down => self.select_next(),
+            KeyCode::Up => self.select_previous(),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(SCROLL_STEP),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(SCROLL_STEP),
+            _ => {}
+        }
+        true
+    }
+
+    /// Re-run the search for the current query from the first page
+    fn search(&mut self) {
+        self.results.clear();
+        self.more_results = false;
+        self.list_state.select(None);
+        self.definition = None;
+        self.error = None;
+
+        if self.query.trim().is_empty() {
+            return;
+        }
+        self.load_page();
+        if !self.results.is_empty() {
+            self.select(0);
+        }
+    }
+
+    /// Append the next page of results for the current query
+    fn load_page(&mut self) {
+        let offset = self.results.len() as u32;
+        match dict_core::search::search_words_offset(self.handle, &self.query, PAGE_SIZE, offset) {
+            Ok(page) => {
+                // Pages can come back short (overlapping tiers, hidden
+                // entries), so only an empty one ends the list
+                self.more_results = !page.is_empty();
+                self.results.extend(page);
+            }
+            Err(e) => {
+                self.more_results = false;
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+
+    fn select_next(&mut self) {
+        let next = self.list_state.selected().map_or(0, |i| i + 1);
+        if next >= self.results.len() && self.more_results {
+            self.load_page();
+        }
+        if next < self.results.len() {
+            self.select(next);
+        }
+    }
+
+    fn select_previous(&mut self) {
+        if let Some(i) = self.list_state.selected().filter(|&i| i > 0) {
+            self.select(i - 1);
+        }
+    }
+
+    fn select(&mut self, index: usize) {
+        self.list_state.select(Some(index));
+        self.scroll = 0;
+        self.definition = None;
+        match dict_core::db::get_full_definition(self.handle, self.results[index].id) {
+            Ok(definition) => self.definition = definition,
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let [search_area, body_area, help_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [results_area, definition_area] =
+            Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)])
+                .areas(body_area);
+
+        let search = Paragraph::new(self.query.as_str()).block(Block::bordered().title(" Search "));
+        frame.render_widget(search, search_area);
+        frame.set_cursor_position((
+            search_area.x + 1 + self.query.chars().count() as u16,
+            search_area.y + 1,
+        ));
+
+        let items: Vec<ListItem> = self
+            .results
+            .iter()
+            .map(|r| {
+                ListItem::new(Line::from(vec![
+                    Span::raw(r.word.as_str()).bold(),
+                    Span::raw(" "),
+                    Span::raw(r.pos.as_str()).fg(Color::Cyan).italic(),
+                ]))
+            })
+            .collect();
+        let title = if self.more_results {
+            format!(" Results ({}+) ", self.results.len())
+        } else {
+            format!(" Results ({}) ", self.results.len())
+        };
+        let list = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, results_area, &mut self.list_state);
+
+        let definition = Paragraph::new(self.definition_lines())
+            .block(Block::bordered().title(" Definition "))
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0));
+        frame.render_widget(definition, definition_area);
+
+        let help = match &self.error {
+            Some(error) => Line::from(format!(" Error: {}", error)).fg(Color::Red),
+            None => {
+                Line::from(" Up/Down: select  PgUp/PgDn: scroll  Ctrl-U: clear  Esc: quit").dim()
+            }
+        };
+        frame.render_widget(help, help_area);
+    }
+
+    fn definition_lines(&self) -> Vec<Line<'_>> {
+        let Some(def) = &self.definition else {
+            return Vec::new();
+        };
+
+        let mut lines = vec![Line::from(vec![
+            Span::raw(def.word.as_str()).bold(),
+            Span::raw(" "),
+            Span::raw(def.pos.as_str()).fg(Color::Cyan).italic(),
+            Span::raw(format!(" ({})", def.language)).dim(),
+        ])];
+
+        for p in &def.pronunciations {
+            let Some(ipa) = &p.ipa else { continue };
+            let mut spans = vec![Span::raw("  "), Span::raw(ipa.as_str()).fg(Color::Green)];
+            if let Some(accent) = &p.accent {
+                spans.push(Span::raw(format!(" [{}]", accent)).dim());
+            }
+            lines.push(Line::from(spans));
+        }
+        lines.push(Line::default());
+
+        for (n, sense) in def.definitions.iter().enumerate() {
+            let mut spans = vec![Span::raw(format!("{}. ", n + 1))];
+            if !sense.tags.is_empty() {
+                spans.push(Span::raw(format!("({}) ", sense.tags.join(", "))).fg(Color::Yellow));
+            }
+            spans.push(Span::raw(sense.text.as_str()));
+            lines.push(Line::from(spans));
+            for example in &sense.examples {
+                lines.push(Line::from(format!("     \u{201c}{}\u{201d}", example)).dim());
+            }
+        }
+
+        if let Some(etymology) = &def.etymology {
+            lines.push(Line::default());
+            lines.push(Line::from("Etymology").bold());
+            lines.push(Line::from(etymology.as_str()));
+        }
+
+        if !def.translations.is_empty() {
+            lines.push(Line::default());
+            lines.push(Line::from("Translations").bold());
+            for t in &def.translations {
+                lines.push(Line::from(vec![
+                    Span::raw(format!("  {}: ", t.target_language)).dim(),
+                    Span::raw(t.translation.as_str()),
+                ]));
+            }
+        }
+
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A database with "word" and 60 longer words starting with it, more
+    /// than a page of results for "word"
+    fn sample_database(dir: &std::path::Path) -> DictHandle {
+        let entry = |word: &str| {
+            format!(
+                r#"{{"word":"{word}","pos":"noun","lang":"English","lang_code":"en","senses":[{{"glosses":["Gloss of {word}"]}}]}}"#
+            )
+        };
+        let mut lines = vec![entry("word")];
+        lines.extend((0..60).map(|i| entry(&format!("word{i:02}"))));
+        let jsonl = dir.join("sample.jsonl");
+        std::fs::write(&jsonl, lines.join("\n")).unwrap();
+        let db = dir.join("sample.db");
+        dict_core::import::import_from_jsonl(
+            db.to_str().unwrap(),
+            jsonl.to_str().unwrap(),
+            |_, _| {},
+        )
+        .unwrap();
+        dict_core::init(db.to_str().unwrap()).unwrap()
+    }
+
+    fn press(app: &mut App, code: KeyCode) -> bool {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn ctrl(app: &mut App, c: char) -> bool {
+        app.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL))
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            assert!(press(app, KeyCode::Char(c)));
+        }
+    }
+
+    #[test]
+    fn test_typing_searches() {
+        let dir = tempfile::tempdir().unwrap();
+        let handle = sample_database(dir.path());
+        let mut app = App::new(&handle);
+
+        type_text(&mut app, "word");
+        assert_eq!(app.query, "word");
+        assert!(!app.results.is_empty() && app.results.len() <= PAGE_SIZE as usize);
+        assert!(app.more_results);
+        assert_eq!(app.list_state.selected(), Some(0));
+        assert_eq!(app.results[0].word, "word");
+        assert_eq!(app.definition.as_ref().unwrap().word, "word");
+        assert!(app.error.is_none());
+
+        // No match: nothing selected or shown
+        type_text(&mut app, "zzz");
+        assert!(app.results.is_empty());
+        assert_eq!(app.list_state.selected(), None);
+        assert!(app.definition.is_none());
+
+        for _ in 0..3 {
+            assert!(press(&mut app, KeyCode::Backspace));
+        }
+        assert_eq!(app.query, "word");
+        assert_eq!(app.list_state.selected(), Some(0));
+
+        assert!(ctrl(&mut app, 'u'));
+        assert!(app.query.is_empty());
+        assert!(app.results.is_empty());
+        assert!(!app.more_results);
+    }
+
+    #[test]
+    fn test_selection_loads_pages() {
+        let dir = tempfile::tempdir().unwrap();
+        let handle = sample_database(dir.path());
+        let mut app = App::new(&handle);
+        type_text(&mut app, "word");
+
+        // Up at the top stays there
+        assert!(press(&mut app, KeyCode::Up));
+        assert_eq!(app.list_state.selected(), Some(0));
+
+        let first_page = app.results.len();
+        for _ in 1..first_page {
+            assert!(press(&mut app, KeyCode::Down));
+        }
+        assert_eq!(app.results.len(), first_page);
+        let last = first_page - 1;
+        assert_eq!(app.list_state.selected(), Some(last));
+
+        // Moving past the end of the page fetches the next one
+        assert!(press(&mut app, KeyCode::Down));
+        assert_eq!(app.list_state.selected(), Some(last + 1));
+        let total = app.results.len();
+        assert_eq!(total, 61);
+        assert!(app.more_results);
+        let words: std::collections::HashSet<&str> =
+            app.results.iter().map(|r| r.word.as_str()).collect();
+        assert_eq!(words.len(), total, "a result repeats across pages");
+        assert_eq!(
+            app.definition.as_ref().unwrap().word,
+            app.results[last + 1].word
+        );
+
+        // An empty page past the last result ends the list
+        for _ in 0..total {
+            assert!(press(&mut app, KeyCode::Down));
+        }
+        assert_eq!(app.list_state.selected(), Some(total - 1));
+        assert_eq!(app.results.len(), total);
+        assert!(!app.more_results);
+
+        assert!(press(&mut app, KeyCode::Up));
+        assert_eq!(app.list_state.selected(), Some(total - 2));
+        assert_eq!(
+            app.definition.as_ref().unwrap().word,
+            app.results[total - 2].word
+        );
+    }
+
+    #[test]
+    fn test_scroll_and_quit() {
+        let dir = tempfile::tempdir().unwrap();
+        let handle = sample_database(dir.path());
+        let mut app = App::new(&handle);
+        type_text(&mut app, "word");
+
+        assert!(press(&mut app, KeyCode::PageDown));
+        assert!(press(&mut app, KeyCode::PageDown));
+        assert_eq!(app.scroll, 2 * SCROLL_STEP);
+        assert!(press(&mut app, KeyCode::PageUp));
+        assert_eq!(app.scroll, SCROLL_STEP);
+        // A new selection starts at the top of its definition
+        assert!(press(&mut app, KeyCode::Down));
+        assert_eq!(app.scroll, 0);
+
+        // "c" is typed, Ctrl-C and Esc quit
+        assert!(press(&mut app, KeyCode::Char('c')));
+        assert_eq!(app.query, "wordc");
+        assert!(!ctrl(&mut app, 'c'));
+        assert!(!press(&mut app, KeyCode::Esc));
+    }
+}
@@ -0,0 +1,283 @@
+//! DICT protocol server
+//!
+//! Serves one or more dictionary databases over the DICT protocol
+//! (RFC 2229), so existing clients such as `dict(1)` and GoldenDict can
+//! query them over the network.
+//!
+//! # Usage
+//!
+//! ```bash
+//! # Serve a single database on the standard port
+//! dictd --db english-dict.db
+//!
+//! # Several databases, each under its own name
+//! dictd --db en=english-dict.db --db fr=french-dict.db --listen 0.0.0.0:2628
+//!
+//! # Query it
+//! dict -h localhost hello
+//! dict -h localhost -m -s lev helo
+//! ```
+
+mod protocol;
+
+use std::io::Write;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+/// Disconnect clients that send nothing for this long
+const IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// DICT protocol (RFC 2229) server for dictionary databases
+#[derive(Parser, Debug)]
+#[command(name = "dictd", author, version)]
+struct Args {
+    /// Database to serve, as NAME=PATH or PATH (named after the file);
+    /// may be given several times
+    #[arg(long = "db", required = true, value_name = "[NAME=]PATH")]
+    databases: Vec<String>,
+
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:2628")]
+    listen: String,
+
+    /// Clients served at once; others are turned away with 420
+    #[arg(long, default_value_t = 64)]
+    max_clients: usize,
+}
+
+/// A database served under a DICT database name
+pub struct Database {
+    /// Name clients use to select the database
+    pub name: String,
+    /// Short description shown by SHOW DB
+    pub description: String,
+    /// The connection is not safe to use from two client threads at
+    /// once, so each command holds the lock while it queries
+    handle: Mutex<dict_core::DictHandle>,
+}
+
+impl Database {
+    /// The database's handle, locked for the caller's queries
+    pub fn handle(&self) -> MutexGuard<'_, dict_core::DictHandle> {
+        // A client thread that panicked mid-query leaves the handle usable
+        self.handle.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let args = Args::parse();
+
+    let databases = args
+        .databases
+        .iter()
+        .map(|spec| open_database(spec))
+        .collect::<Result<Vec<_>>>()?;
+    let databases = Arc::new(databases);
+
+    let listener = TcpListener::bind(&args.listen)
+        .with_context(|| format!("Failed to bind {}", args.listen))?;
+    log::info!("Serving {} database(s) on {}", databases.len(), args.listen);
+    accept_clients(listener, databases, args.max_clients);
+
+    Ok(())
+}
+
+/// Serve each client connecting to `listener` on its own thread, up to
+/// `max_clients` at once
+fn accept_clients(listener: TcpListener, databases: Arc<Vec<Database>>, max_clients: usize) {
+    let clients = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                log::warn!("Failed to accept connection: {}", e);
+                continue;
+            }
+        };
+        let peer = stream
+            .peer_addr()
+            .map(|addr| addr.to_string())
+            .unwrap_or_default();
+        if clients.fetch_add(1, Ordering::AcqRel) >= max_clients {
+            clients.fetch_sub(1, Ordering::AcqRel);
+            log::warn!("Turning away {}: {} clients connected", peer, max_clients);
+            write!(&stream, "420 server temporarily unavailable\r\n").ok();
+            continue;
+        }
+        let slot = ClientSlot(Arc::clone(&clients));
+        let databases = Arc::clone(&databases);
+
+        std::thread::spawn(move || {
+            let _slot = slot;
+            log::info!("Client connected: {}", peer);
+            stream.set_read_timeout(Some(IDLE_TIMEOUT)).ok();
+            if let Err(e) = protocol::serve(&stream, &databases) {
+                log::debug!("Connection {} ended with error: {}", peer, e);
+            }
+            log::info!("Client disconnected: {}", peer);
+        });
+    }
+}
+
+/// A connected client, counted until its thread ends
+struct ClientSlot(Arc<AtomicUsize>);
+
+impl Drop for ClientSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Open a `[NAME=]PATH` database spec
+fn open_database(spec: &str) -> Result<Database> {
+    let (name, path) = match spec.split_once('=') {
+        Some((name, path)) => (name.to_string(), PathBuf::from(path)),
+        None => {
+            let path = PathBuf::from(spec);
+            let name = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .context("Cannot derive a database name; use NAME=PATH")?
+                .to_string();
+            (name, path)
+        }
+    };
+    if name.is_empty() || name.contains(char::is_whitespace) || name == "*" || name == "!" {
+        anyhow::bail!("Invalid database name '{}'", name);
+    }
+    if !path.exists() {
+        anyhow::bail!("Database does not exist: {:?}", path);
+    }
+
+    let handle = dict_core::init(path.to_str().context("Invalid database path")?)
+        .with_context(|| format!("Failed to open database {:?}", path))?;
+    let metadata = dict_core::db::get_metadata(&handle).unwrap_or_default();
+    let description = match metadata.get("source") {
+        Some(source) => format!("Wiktionary ({})", source),
+        None => "Wiktionary".to_string(),
+    };
+
+    Ok(Database {
+        name,
+        description,
+        handle: Mutex::new(handle),
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpStream;
+
+    /// A database with "hello" and "help", served as "en"
+    pub(crate) fn sample_database(dir: &std::path::Path) -> Database {
+        let jsonl = dir.join("sample.jsonl");
+        std::fs::write(
+            &jsonl,
+            concat!(
+                r#"{"word":"hello","pos":"intj","lang":"English","lang_code":"en","senses":[{"glosses":["A greeting"]}]}"#,
+                "\n",
+                r#"{"word":"help","pos":"verb","lang":"English","lang_code":"en","senses":[{"glosses":["To assist"]}]}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+        let db = dir.join("sample.db");
+        dict_core::import::import_from_jsonl(
+            db.to_str().unwrap(),
+            jsonl.to_str().unwrap(),
+            |_, _| {},
+        )
+        .unwrap();
+        open_database(&format!("en={}", db.display())).unwrap()
+    }
+
+    /// Send `command` and read the response up to its final status line
+    fn request(stream: &TcpStream, reader: &mut impl BufRead, command: &str) -> String {
+        write!(&*stream, "{}\r\n", command).unwrap();
+        let mut response = String::new();
+        loop {
+            let mut line = String::new();
+            assert!(reader.read_line(&mut line).unwrap() > 0);
+            response.push_str(&line);
+            let code = line.get(..3).unwrap_or("");
+            if code.starts_with('2') || code.starts_with('5') {
+                return response;
+            }
+        }
+    }
+
+    #[test]
+    fn test_concurrent_clients() {
+        let dir = tempfile::tempdir().unwrap();
+        let databases = Arc::new(vec![sample_database(dir.path())]);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || accept_clients(listener, databases, 8));
+
+        let clients: Vec<_> = (0..8)
+            .map(|_| {
+                std::thread::spawn(move || {
+                    let stream = TcpStream::connect(addr).unwrap();
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut banner = String::new();
+                    reader.read_line(&mut banner).unwrap();
+                    assert!(banner.starts_with("220 "));
+                    for _ in 0..25 {
+                        let define = request(&stream, &mut reader, "DEFINE en hello");
+                        assert!(define.starts_with("150 "), "{}", define);
+                        assert!(define.contains("A greeting"));
+                        let matches = request(&stream, &mut reader, "MATCH en prefix hel");
+                        assert!(matches.starts_with("152 2 matches"), "{}", matches);
+                    }
+                    assert!(request(&stream, &mut reader, "QUIT").starts_with("221"));
+                })
+            })
+            .collect();
+        for client in clients {
+            client.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_max_clients() {
+        let dir = tempfile::tempdir().unwrap();
+        let databases = Arc::new(vec![sample_database(dir.path())]);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || accept_clients(listener, databases, 1));
+
+        let connect = || {
+            let stream = TcpStream::connect(addr).unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut banner = String::new();
+            reader.read_line(&mut banner).unwrap();
+            (stream, reader, banner)
+        };
+        let (first, mut first_reader, banner) = connect();
+        assert!(banner.starts_with("220 "), "{}", banner);
+        let (_, _, banner) = connect();
+        assert!(banner.starts_with("420 "), "{}", banner);
+
+        // The slot is free again once the first client quits
+        assert!(request(&first, &mut first_reader, "QUIT").starts_with("221"));
+        let mut banner = String::new();
+        for _ in 0..50 {
+            (_, _, banner) = connect();
+            if banner.starts_with("220 ") {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert!(banner.starts_with("220 "), "{}", banner);
+    }
+}
@@ -0,0 +1,619 @@
+//! DICT protocol session handling (RFC 2229)
+//!
+//! Implements DEFINE, MATCH (exact, prefix, lev and wildcard strategies),
+//! SHOW DB/STRAT/INFO/SERVER, OPTION MIME, CLIENT, STATUS, HELP and QUIT.
+//! Text responses are terminated by a line containing a single "." and
+//! lines starting with "." are dot-stuffed.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use dict_core::search::MatchStrategy;
+use dict_core::FullDefinition;
+
+use crate::Database;
+
+/// Maximum number of matches returned per database by MATCH
+const MATCH_LIMIT: u32 = 200;
+
+/// Strategy used when the client asks for the server default (".")
+const DEFAULT_STRATEGY: &str = "lev";
+
+/// Strategies offered by SHOW STRAT, with their descriptions
+const STRATEGIES: [(&str, &str); 4] = [
+    ("exact", "Match headwords exactly"),
+    ("prefix", "Match prefixes"),
+    ("lev", "Match headwords within Levenshtein distance 2"),
+    (
+        "wildcard",
+        "Match headwords with ? for any letter and * for any run",
+    ),
+];
+
+/// Longest command line accepted, including CRLF (RFC 2229 section 2.2)
+const MAX_LINE: u64 = 1024;
+
+/// Counter making each connection's msg-id unique
+static CONNECTIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Serve one client connection until it quits or disconnects
+pub fn serve(stream: &TcpStream, databases: &[Database]) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut session = Session {
+        out: std::io::BufWriter::new(stream),
+        databases,
+        mime: false,
+    };
+
+    let connection = CONNECTIONS.fetch_add(1, Ordering::Relaxed);
+    session.status(
+        220,
+        &format!(
+            "dict-app dictd {} <mime> <{}.{}@dict-app>",
+            env!("CARGO_PKG_VERSION"),
+            std::process::id(),
+            connection
+        ),
+    )?;
+    session.out.flush()?;
+
+    loop {
+        let keep_going = match read_line(&mut reader)? {
+            Line::End => return Ok(()),
+            Line::TooLong => {
+                session.status(500, "line too long")?;
+                true
+            }
+            Line::Command(line) => session.handle(&line)?,
+        };
+        session.out.flush()?;
+        if !keep_going {
+            return Ok(());
+        }
+    }
+}
+
+/// A line read from the client
+#[derive(Debug, PartialEq)]
+enum Line {
+    /// The client closed the connection
+    End,
+    /// A command, without its line ending
+    Command(String),
+    /// A line longer than `MAX_LINE`, which was skipped
+    TooLong,
+}
+
+/// Read the next line, buffering at most `MAX_LINE` bytes of it
+fn read_line(reader: &mut impl BufRead) -> std::io::Result<Line> {
+    let mut line = Vec::new();
+    if reader
+        .by_ref()
+        .take(MAX_LINE)
+        .read_until(b'\n', &mut line)?
+        == 0
+    {
+        return Ok(Line::End);
+    }
+    if !line.ends_with(b"\n") && line.len() as u64 == MAX_LINE {
+        // Skip to the end of the line, a chunk at a time
+        loop {
+            line.clear();
+            let read = reader
+                .by_ref()
+                .take(MAX_LINE)
+                .read_until(b'\n', &mut line)?;
+            if read == 0 || line.ends_with(b"\n") {
+                return Ok(Line::TooLong);
+            }
+        }
+    }
+    let line = String::from_utf8(line)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    Ok(Line::Command(
+        line.trim_end_matches(['\r', '\n']).to_string(),
+    ))
+}
+
+struct Session<'a, W: Write> {
+    out: W,
+    databases: &'a [Database],
+    /// Whether the client asked for MIME headers before each text response
+    mime: bool,
+}
+
+impl<'a, W: Write> Session<'a, W> {
+    /// Handle one command line; returns false after QUIT
+    fn handle(&mut self, line: &str) -> std::io::Result<bool> {
+        let Some(params) = tokenize(line) else {
+            self.status(501, "syntax error, illegal parameters")?;
+            return Ok(true);
+        };
+        let Some(command) = params.first() else {
+            // Blank lines are ignored
+            return Ok(true);
+        };
+        let args: Vec<&str> = params[1..].iter().map(String::as_str).collect();
+        log::debug!("Command: {:?}", params);
+
+        match (command.to_ascii_uppercase().as_str(), args.as_slice()) {
+            ("DEFINE", [database, word]) => self.define(database, word)?,
+            ("MATCH", [database, strategy, word]) => self.match_words(database, strategy, word)?,
+            ("SHOW", [what, rest @ ..]) => match (what.to_ascii_uppercase().as_str(), rest) {
+                ("DB" | "DATABASES", []) => self.show_databases()?,
+                ("STRAT" | "STRATEGIES", []) => self.show_strategies()?,
+                ("INFO", [database]) => self.show_info(database)?,
+                ("SERVER", []) => self.show_server()?,
+                _ => self.status(501, "syntax error, illegal parameters")?,
+            },
+            ("OPTION", [option]) if option.eq_ignore_ascii_case("MIME") => {
+                self.mime = true;
+                self.status(250, "ok - using MIME headers")?;
+            }
+            ("CLIENT", [_, ..]) => self.status(250, "ok")?,
+            ("STATUS", []) => self.status(210, "status [d/m/c = 0/0/0; 0.000r 0.000u 0.000s]")?,
+            ("HELP", []) => self.help()?,
+            ("QUIT", []) => {
+                self.status(221, "bye")?;
+                return Ok(false);
+            }
+            ("AUTH" | "SASLAUTH" | "SASLRESP" | "OPTION", _) => {
+                self.status(502, "command not implemented")?
+            }
+            ("DEFINE" | "MATCH" | "SHOW" | "CLIENT" | "STATUS" | "HELP" | "QUIT", _) => {
+                self.status(501, "syntax error, illegal parameters")?
+            }
+            _ => self.status(500, "unknown command")?,
+        }
+        Ok(true)
+    }
+
+    /// DEFINE database word
+    fn define(&mut self, database: &str, word: &str) -> std::io::Result<()> {
+        let Some(selected) = self.select_databases(database) else {
+            return self.status(
+                550,
+                "invalid database, use \"SHOW DB\" for list of databases",
+            );
+        };
+
+        let mut found: Vec<(&'a Database, String, String)> = Vec::new();
+        for db in selected {
+            let entries = lookup(db, word);
+            if let Some(first) = entries.first() {
+                let headword = first.word.clone();
+                let text = entries
+                    .iter()
+                    .map(format_entry)
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+                found.push((db, headword, text));
+                if database == "!" {
+                    break;
+                }
+            }
+        }
+
+        if found.is_empty() {
+            return self.status(552, "no match");
+        }
+
+        self.status(150, &format!("{} definitions retrieved", found.len()))?;
+        for (db, headword, text) in found {
+            self.status(
+                151,
+                &format!(
+                    "{} {} {}",
+                    quote(&headword),
+                    db.name,
+                    quote(&db.description)
+                ),
+            )?;
+            self.text(&text)?;
+        }
+        self.status(250, "ok")
+    }
+
+    /// MATCH database strategy word
+    fn match_words(&mut self, database: &str, strategy: &str, word: &str) -> std::io::Result<()> {
+        let Some(selected) = self.select_databases(database) else {
+            return self.status(
+                550,
+                "invalid database, use \"SHOW DB\" for list of databases",
+            );
+        };
+        let strategy = if strategy == "." {
+            DEFAULT_STRATEGY
+        } else {
+            strategy
+        };
+        let strategy = match strategy.to_ascii_lowercase().as_str() {
+            "exact" => MatchStrategy::Exact,
+            "prefix" => MatchStrategy::Prefix,
+            "lev" => MatchStrategy::Levenshtein,
+            "wildcard" => MatchStrategy::Pattern,
+            _ => return self.status(551, "invalid strategy, use \"SHOW STRAT\" for a list"),
+        };
+
+        let mut matches: Vec<(&'a str, String)> = Vec::new();
+        for db in selected {
+            let results =
+                match dict_core::search::match_words(&db.handle(), word, strategy, MATCH_LIMIT) {
+                    Ok(results) => results,
+                    Err(e) => {
+                        log::warn!("MATCH failed in {}: {}", db.name, e);
+                        continue;
+                    }
+                };
+            let before = matches.len();
+            for result in results {
+                // Entries for different parts of speech share a headword
+                if !matches[before..].iter().any(|(_, w)| *w == result.word) {
+                    matches.push((&db.name, result.word));
+                }
+            }
+            if database == "!" && matches.len() > before {
+                break;
+            }
+        }
+
+        if matches.is_empty() {
+            return self.status(552, "no match");
+        }
+
+        self.status(152, &format!("{} matches found", matches.len()))?;
+        let lines: Vec<String> = matches
+            .iter()
+            .map(|(db, word)| format!("{} {}", db, quote(word)))
+            .collect();
+        self.text(&lines.join("\n"))?;
+        self.status(250, "ok")
+    }
+
+    fn show_databases(&mut self) -> std::io::Result<()> {
+        if self.databases.is_empty() {
+            return self.status(554, "no databases present");
+        }
+        self.status(110, &format!("{} databases present", self.databases.len()))?;
+        let lines: Vec<String> = self
+            .databases
+            .iter()
+            .map(|db| format!("{} {}", db.name, quote(&db.description)))
+            .collect();
+        self.text(&lines.join("\n"))?;
+        self.status(250, "ok")
+    }
+
+    fn show_strategies(&mut self) -> std::io::Result<()> {
+        self.status(111, &format!("{} strategies available", STRATEGIES.len()))?;
+        let lines: Vec<String> = STRATEGIES
+            .iter()
+            .map(|(name, description)| format!("{} {}", name, quote(description)))
+            .collect();
+        self.text(&lines.join("\n"))?;
+        self.status(250, "ok")
+    }
+
+    fn show_info(&mut self, database: &str) -> std::io::Result<()> {
+        let Some(db) = self.databases.iter().find(|db| db.name == database) else {
+            return self.status(
+                550,
+                "invalid database, use \"SHOW DB\" for list of databases",
+            );
+        };
+
+        let mut info = vec![db.description.clone(), String::new()];
+        let handle = db.handle();
+        if let Ok(count) = dict_core::db::get_word_count(&handle) {
+            info.push(format!("Entries: {}", count));
+        }
+        for (key, value) in dict_core::db::get_metadata(&handle).unwrap_or_default() {
+            info.push(format!("{}: {}", key, value));
+        }
+        info.push(String::new());
+        info.push("Content from Wiktionary (https://www.wiktionary.org/),".to_string());
+        info.push("available under CC BY-SA 4.0 and GFDL.".to_string());
+        drop(handle);
+
+        self.status(112, &format!("information for {}", db.name))?;
+        self.text(&info.join("\n"))?;
+        self.status(250, "ok")
+    }
+
+    fn show_server(&mut self) -> std::io::Result<()> {
+        self.status(114, "server information")?;
+        self.text(&format!(
+            "dict-app dictd {}\nServing {} database(s)",
+            env!("CARGO_PKG_VERSION"),
+            self.databases.len()
+        ))?;
+        self.status(250, "ok")
+    }
+
+    fn help(&mut self) -> std::io::Result<()> {
+        self.status(113, "help text follows")?;
+        self.text(
+            "DEFINE database word         -- look up word in database\n\
+             MATCH database strategy word -- match word in database using strategy\n\
+             SHOW DB                      -- list all accessible databases\n\
+             SHOW STRAT                   -- list available matching strategies\n\
+             SHOW INFO database           -- provide information about the database\n\
+             SHOW SERVER                  -- provide site-specific information\n\
+             OPTION MIME                  -- use MIME headers\n\
+             CLIENT info                  -- identify client to server\n\
+             STATUS                       -- display timing information\n\
+             HELP                         -- display this help information\n\
+             QUIT                         -- terminate connection",
+        )?;
+        self.status(250, "ok")
+    }
+
+    /// Databases addressed by a DEFINE/MATCH database parameter
+    ///
+    /// "*" and "!" address all databases ("!" stops at the first one with
+    /// results); `None` means no database has that name.
+    fn select_databases(&self, name: &str) -> Option<Vec<&'a Database>> {
+        let selected: Vec<&'a Database> = self
+            .databases
+            .iter()
+            .filter(|db| name == "*" || name == "!" || db.name == name)
+            .collect();
+        (!selected.is_empty()).then_some(selected)
+    }
+
+    fn status(&mut self, code: u16, text: &str) -> std::io::Result<()> {
+        write!(self.out, "{} {}\r\n", code, text)
+    }
+
+    /// Write a text response: optional MIME header, dot-stuffed lines, "."
+    fn text(&mut self, text: &str) -> std::io::Result<()> {
+        if self.mime {
+            self.out
+                .write_all(b"Content-type: text/plain; charset=utf-8\r\n\r\n")?;
+        }
+        for line in text.lines() {
+            if line.starts_with('.') {
+                self.out.write_all(b".")?;
+            }
+            write!(self.out, "{}\r\n", line)?;
+        }
+        self.out.write_all(b".\r\n")
+    }
+}
+
+/// Full entries for a headword, falling back to the lowercased form
+fn lookup(db: &Database, word: &str) -> Vec<FullDefinition> {
+    let handle = db.handle();
+    let mut candidates = vec![word.to_string()];
+    let lower = word.to_lowercase();
+    if lower != word {
+        candidates.push(lower);
+    }
+
+    for candidate in candidates {
+        let words = dict_core::db::get_words_by_word(&handle, &candidate).unwrap_or_default();
+        let entries: Vec<FullDefinition> = words
+            .iter()
+            .filter_map(|w| dict_core::get_definition(&handle, w.id))
+            .collect();
+        if !entries.is_empty() {
+            return entries;
+        }
+    }
+    Vec::new()
+}
+
+/// Plain-text rendering of one entry
+fn format_entry(def: &FullDefinition) -> String {
+    let mut text = def.word.clone();
+    if let Some(ipa) = def.pronunciations.iter().find_map(|p| p.ipa.as_deref()) {
+        text.push(' ');
+        text.push_str(ipa);
+    }
+    text.push_str(&format!("\n  {}", def.pos));
+
+    for (n, sense) in def.definitions.iter().enumerate() {
+        text.push_str(&format!("\n  {}. ", n + 1));
+        if !sense.tags.is_empty() {
+            text.push_str(&format!("({}) ", sense.tags.join(", ")));
+        }
+        text.push_str(&sense.text);
+        for example in &sense.examples {
+            text.push_str(&format!("\n       \"{}\"", example));
+        }
+    }
+
+    if let Some(etymology) = &def.etymology {
+        text.push_str(&format!("\n\n  Etymology: {}", etymology));
+    }
+    text
+}
+
+/// Split a command line into parameters
+///
+/// Parameters are separated by spaces or tabs and may be enclosed in single
+/// or double quotes; a backslash escapes the next character. Returns `None`
+/// for an unterminated quote.
+fn tokenize(line: &str) -> Option<Vec<String>> {
+    let mut params = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| *c == ' ' || *c == '\t').is_some() {}
+        let Some(&first) = chars.peek() else {
+            return Some(params);
+        };
+
+        let mut param = String::new();
+        let quote = (first == '"' || first == '\'').then(|| chars.next().unwrap());
+        loop {
+            match chars.next() {
+                None if quote.is_some() => return None,
+                None => break,
+                Some('\\') => param.push(chars.next()?),
+                Some(c) if Some(c) == quote => break,
+                Some(c) if quote.is_none() && (c == ' ' || c == '\t') => break,
+                Some(c) => param.push(c),
+            }
+        }
+        params.push(param);
+    }
+}
+
+/// Quote a string for a status line or text response
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::sample_database;
+
+    #[test]
+    fn test_read_line_is_bounded() {
+        let long = "x".repeat(3000);
+        let input = format!("SHOW DB\r\nMATCH en exact {long}\r\nSTATUS\r\nQUIT");
+        let mut reader = std::io::Cursor::new(input);
+        assert_eq!(
+            read_line(&mut reader).unwrap(),
+            Line::Command("SHOW DB".to_string())
+        );
+        assert_eq!(read_line(&mut reader).unwrap(), Line::TooLong);
+        assert_eq!(
+            read_line(&mut reader).unwrap(),
+            Line::Command("STATUS".to_string())
+        );
+        assert_eq!(
+            read_line(&mut reader).unwrap(),
+            Line::Command("QUIT".to_string())
+        );
+        assert_eq!(read_line(&mut reader).unwrap(), Line::End);
+
+        // 1024 octets with the CRLF is still a command
+        let exact = format!("{}\r\n", "y".repeat(MAX_LINE as usize - 2));
+        let mut reader = std::io::Cursor::new(exact);
+        assert!(matches!(read_line(&mut reader).unwrap(), Line::Command(_)));
+    }
+
+    /// Run `commands` through a session, returning what it wrote
+    fn session_output(databases: &[Database], commands: &[&str]) -> String {
+        let mut session = Session {
+            out: Vec::new(),
+            databases,
+            mime: false,
+        };
+        for command in commands {
+            session.handle(command).unwrap();
+        }
+        String::from_utf8(session.out).unwrap()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("DEFINE en hello").unwrap(),
+            ["DEFINE", "en", "hello"]
+        );
+        assert_eq!(
+            tokenize("define\t* \"ice cream\"").unwrap(),
+            ["define", "*", "ice cream"]
+        );
+        assert_eq!(
+            tokenize("MATCH * '.' o'clock").unwrap(),
+            ["MATCH", "*", ".", "o'clock"]
+        );
+        assert_eq!(
+            tokenize(r#"SHOW INFO "a \"b\"""#).unwrap(),
+            ["SHOW", "INFO", "a \"b\""]
+        );
+        assert!(tokenize("   ").unwrap().is_empty());
+        assert_eq!(tokenize("DEFINE en \"unterminated"), None);
+        assert_eq!(tokenize("DEFINE en trailing\\"), None);
+    }
+
+    #[test]
+    fn test_text_is_dot_stuffed() {
+        let mut session = Session {
+            out: Vec::new(),
+            databases: &[],
+            mime: false,
+        };
+        session.text(".hidden\nplain\n..two").unwrap();
+        assert_eq!(
+            String::from_utf8(session.out).unwrap(),
+            "..hidden\r\nplain\r\n...two\r\n.\r\n"
+        );
+
+        let mut session = Session {
+            out: Vec::new(),
+            databases: &[],
+            mime: true,
+        };
+        session.text("plain").unwrap();
+        assert_eq!(
+            String::from_utf8(session.out).unwrap(),
+            "Content-type: text/plain; charset=utf-8\r\n\r\nplain\r\n.\r\n"
+        );
+    }
+
+    #[test]
+    fn test_status_codes() {
+        let dir = tempfile::tempdir().unwrap();
+        let databases = [sample_database(dir.path())];
+        let status = |command: &str| session_output(&databases, &[command]);
+
+        let define = status("DEFINE en hello");
+        assert!(
+            define.starts_with("150 1 definitions retrieved\r\n"),
+            "{}",
+            define
+        );
+        assert!(define.contains("151 \"hello\" en \"Wiktionary"));
+        assert!(define.contains("A greeting"));
+        assert!(define.ends_with(".\r\n250 ok\r\n"));
+        assert!(status("DEFINE * Hello").starts_with("150 "));
+        assert!(status("DEFINE en nosuchword").starts_with("552 "));
+        assert!(status("DEFINE fr hello").starts_with("550 "));
+        assert!(status("DEFINE en").starts_with("501 "));
+
+        let matches = status("MATCH en prefix hel");
+        assert!(
+            matches.starts_with("152 2 matches found\r\n"),
+            "{}",
+            matches
+        );
+        assert!(matches.contains("en \"hello\"\r\n"));
+        assert!(matches.contains("en \"help\"\r\n"));
+        assert!(matches.ends_with(".\r\n250 ok\r\n"));
+        assert!(status("MATCH ! exact help").starts_with("152 1 matches"));
+        assert!(status("MATCH en . helo").starts_with("152 "));
+        assert!(status("MATCH en exact zzz").starts_with("552 "));
+        assert!(status("MATCH en soundex hel").starts_with("551 "));
+        assert!(status("MATCH fr exact hello").starts_with("550 "));
+
+        assert!(status("SHOW DB").starts_with("110 1 databases present\r\nen "));
+        assert!(status("SHOW STRAT").starts_with("111 4 strategies available"));
+        assert!(status("SHOW INFO en").contains("Entries: 2"));
+        assert!(status("SHOW INFO fr").starts_with("550 "));
+        assert!(status("SHOW SERVER").starts_with("114 "));
+        assert!(status("SHOW NOTHING").starts_with("501 "));
+        assert!(status("OPTION MIME").starts_with("250 "));
+        assert!(status("OPTION OTHER").starts_with("502 "));
+        assert!(status("CLIENT test").starts_with("250 "));
+        assert!(status("STATUS").starts_with("210 "));
+        assert!(status("HELP").starts_with("113 "));
+        assert!(status("FROB").starts_with("500 "));
+        assert!(status("DEFINE en \"open").starts_with("501 "));
+        assert_eq!(status(""), "");
+
+        let mut session = Session {
+            out: Vec::new(),
+            databases: &databases,
+            mime: false,
+        };
+        assert!(!session.handle("QUIT").unwrap());
+        assert_eq!(String::from_utf8(session.out).unwrap(), "221 bye\r\n");
+    }
+}
@@ -0,0 +1,150 @@
+//! `export` subcommand
+//!
+//! Converts a built database back into JSONL, CSV or StarDict using the
+//! exporters in `dict_core::export`, so the preprocessor handles both
+//! directions of conversion.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use dict_core::export::{export_csv, export_jsonl, export_stardict};
+use dict_core::ExportFormat;
+use indicatif::{ProgressBar, ProgressStyle};
+
+use crate::format_number;
+
+/// Arguments for `dict-preprocessor export`
+#[derive(clap::Args, Debug)]
+pub struct ExportArgs {
+    /// SQLite database to export
+    #[arg(long)]
+    db: PathBuf,
+
+    /// Output format
+    #[arg(short, long, value_enum)]
+    format: ExportFormatArg,
+
+    /// Output file for jsonl/csv (default: stdout) or directory for
+    /// stardict (default: current directory)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// StarDict dictionary name, also used as the file name
+    /// (default: database file name without extension)
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Quiet mode - suppress progress bar
+    #[arg(short, long, default_value = "false")]
+    quiet: bool,
+}
+
+/// Export formats accepted on the command line
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum ExportFormatArg {
+    /// kaikki.org-style JSONL, re-importable by the build
+    Jsonl,
+    /// One row per definition
+    Csv,
+    /// StarDict .ifo/.idx/.dict files
+    Stardict,
+}
+
+impl From<ExportFormatArg> for ExportFormat {
+    fn from(format: ExportFormatArg) -> Self {
+        match format {
+            ExportFormatArg::Jsonl => ExportFormat::Jsonl,
+            ExportFormatArg::Csv => ExportFormat::Csv,
+            ExportFormatArg::Stardict => ExportFormat::Stardict,
+        }
+    }
+}
+
+/// Export a database in the requested format
+pub fn run_export(args: &ExportArgs) -> Result<()> {
+    if !args.db.exists() {
+        anyhow::bail!("Database does not exist: {:?}", args.db);
+    }
+    let handle = dict_core::init(args.db.to_str().context("Invalid database path")?)
+        .context("Failed to open database")?;
+
+    let format = ExportFormat::from(args.format);
+
+    // Keep the progress bar from interleaving with data written to stdout
+    let to_stdout = args.output.is_none() && format != ExportFormat::Stardict;
+    let pb = progress_bar(args.quiet || to_stdout)?;
+    let progress = |current: u64, total: u64| {
+        pb.set_length(total);
+        pb.set_position(current);
+    };
+
+    match format {
+        ExportFormat::Jsonl | ExportFormat::Csv => {
+            let writer = open_output(args.output.as_deref())?;
+            let count = if format == ExportFormat::Jsonl {
+                export_jsonl(&handle, writer, progress)?
+            } else {
+                export_csv(&handle, writer, progress)?
+            };
+            pb.finish_and_clear();
+
+            if let Some(path) = &args.output {
+                println!("Exported {} entries to {:?}", format_number(count), path);
+            }
+        }
+        ExportFormat::Stardict => {
+            let output_dir = args.output.clone().unwrap_or_else(|| PathBuf::from("."));
+            let name = match &args.name {
+                Some(name) => name.clone(),
+                None => default_name(&args.db)?,
+            };
+            let files = export_stardict(&handle, &output_dir, &name, progress)?;
+            pb.finish_and_clear();
+
+            println!(
+                "Exported {} headwords to StarDict:",
+                format_number(files.headwords)
+            );
+            println!("  {:?}", files.ifo);
+            println!("  {:?}", files.idx);
+            println!("  {:?}", files.dict);
+        }
+    }
+
+    Ok(())
+}
+
+/// Open the output file, or stdout if none was given
+fn open_output(path: Option<&Path>) -> Result<Box<dyn Write>> {
+    Ok(match path {
+        Some(path) => {
+            Box::new(File::create(path).with_context(|| format!("Failed to create {:?}", path))?)
+        }
+        None => Box::new(std::io::stdout().lock()),
+    })
+}
+
+/// Dictionary name derived from the database file name
+fn default_name(db: &Path) -> Result<String> {
+    db.file_stem()
+        .and_then(|stem| stem.to_str())
+        .map(str::to_string)
+        .context("Cannot derive a dictionary name from the database path; pass --name")
+}
+
+/// Progress bar measuring exported entries, drawn on stderr
+fn progress_bar(hidden: bool) -> Result<ProgressBar> {
+    let pb = if hidden {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(0)
+    };
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} entries ({eta})")?
+            .progress_chars("#>-"),
+    );
+    Ok(pb)
+}
@@ -0,0 +1,176 @@
+//! `inspect` and `stats` subcommands
+//!
+//! Describe an existing database, e.g. to check which build a bug report
+//! was made against or why a build came out larger than expected.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use dict_core::models::CountEntry;
+use dict_core::DatabaseStats;
+use indicatif::HumanBytes;
+
+use crate::format_number;
+
+/// Arguments for `dict-preprocessor inspect`
+#[derive(clap::Args, Debug)]
+pub struct InspectArgs {
+    /// SQLite database to inspect
+    db: PathBuf,
+
+    /// Number of entries to show per breakdown
+    #[arg(long, default_value = "10")]
+    top: u32,
+
+    /// Print machine-readable JSON instead of a table
+    #[arg(long, default_value = "false")]
+    json: bool,
+}
+
+/// Arguments for `dict-preprocessor stats`
+#[derive(clap::Args, Debug)]
+pub struct StatsArgs {
+    /// SQLite database to analyze
+    db: PathBuf,
+
+    /// Number of entries to show per breakdown
+    #[arg(long, default_value = "10")]
+    top: u32,
+
+    /// Print machine-readable JSON instead of a table
+    #[arg(long, default_value = "false")]
+    json: bool,
+}
+
+/// Print metadata, row counts, content breakdowns and the size breakdown
+/// of a database
+pub fn run_inspect(args: &InspectArgs) -> Result<()> {
+    let stats = load_stats(&args.db, args.top)?;
+
+    if args.json {
+        let report = serde_json::json!({
+            "metadata": stats.metadata,
+            "words": stats.words,
+            "definitions": stats.definitions,
+            "pronunciations": stats.pronunciations,
+            "etymologies": stats.etymologies,
+            "translations": stats.translations,
+            "languages": stats.languages,
+            "parts_of_speech": stats.parts_of_speech,
+            "top_tags": stats.top_tags,
+            "tables": stats.tables,
+            "total_bytes": stats.total_bytes,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!("Database: {:?}", args.db);
+    println!();
+    println!("Metadata:");
+    if stats.metadata.is_empty() {
+        println!("  (none - built before build metadata was recorded)");
+    }
+    for (key, value) in &stats.metadata {
+        println!("  {:<20}{}", key, value);
+    }
+    println!();
+    print_counts(&stats);
+    print_breakdowns(&stats);
+    println!();
+    println!("Size:");
+    println!(
+        "  {:<32}{:>12}",
+        "Total",
+        HumanBytes(stats.total_bytes as u64).to_string()
+    );
+    for table in &stats.tables {
+        println!(
+            "  {:<32}{:>12}",
+            table.name,
+            HumanBytes(table.bytes as u64).to_string()
+        );
+    }
+
+    Ok(())
+}
+
+/// Print content distributions of a database
+pub fn run_stats(args: &StatsArgs) -> Result<()> {
+    let stats = load_stats(&args.db, args.top)?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+
+    println!("Database: {:?}", args.db);
+    println!();
+    print_counts(&stats);
+    print_breakdowns(&stats);
+
+    Ok(())
+}
+
+/// Open the database read-only and collect its statistics
+fn load_stats(db: &Path, top_n: u32) -> Result<DatabaseStats> {
+    if !db.exists() {
+        anyhow::bail!("Database does not exist: {:?}", db);
+    }
+
+    let handle = dict_core::init(db.to_str().context("Invalid database path")?)
+        .context("Failed to open database")?;
+    dict_core::db::get_database_stats(&handle, top_n).context("Failed to read statistics")
+}
+
+/// Print the total row count of each content table
+fn print_counts(stats: &DatabaseStats) {
+    println!("Rows:");
+    println!("  Words:              {:>12}", format_count(stats.words));
+    println!(
+        "  Definitions:        {:>12}",
+        format_count(stats.definitions)
+    );
+    println!(
+        "  Pronunciations:     {:>12}",
+        format_count(stats.pronunciations)
+    );
+    println!(
+        "  Etymologies:        {:>12}",
+        format_count(stats.etymologies)
+    );
+    println!(
+        "  Translations:       {:>12}",
+        format_count(stats.translations)
+    );
+}
+
+/// Print the language, part-of-speech and tag breakdowns
+fn print_breakdowns(stats: &DatabaseStats) {
+    print_breakdown("Languages", &stats.languages, stats.words);
+    print_breakdown("Parts of speech", &stats.parts_of_speech, stats.words);
+    print_breakdown("Top tags", &stats.top_tags, stats.definitions);
+}
+
+/// Print a labelled breakdown with each entry's share of `total`
+fn print_breakdown(title: &str, entries: &[CountEntry], total: i64) {
+    println!();
+    println!("{}:", title);
+    for entry in entries {
+        let share = if total > 0 {
+            entry.count as f64 / total as f64 * 100.0
+        } else {
+            0.0
+        };
+        println!(
+            "  {:<32}{:>12} ({:>5.1}%)",
+            entry.name,
+            format_count(entry.count),
+            share
+        );
+    }
+}
+
+fn format_count(n: i64) -> String {
+    format_number(n.max(0) as u64)
+}
@@ -11,23 +11,74 @@
 //! # Gzip-compressed JSONL file
 //! dict-preprocessor --input kaikki-english.jsonl.gz --output english-dict.db
 //!
+//! # Smaller build without etymologies, translations, examples, forms and synonyms
+//! dict-preprocessor --input kaikki-english.jsonl.gz --output english-lite.db --profile lite
+//!
+//! # Smaller full-text index without token positions
+//! dict-preprocessor --input kaikki-english.jsonl.gz --output english-dict.db --fts-index compact
+//!
+//! # Spanish dictionary where diacritics tell words apart
+//! dict-preprocessor --input kaikki-spanish.jsonl.gz --output spanish-dict.db --normalization case
+//!
 //! # Process and upload to CDN
 //! dict-preprocessor --input kaikki-english.jsonl.gz --output english-dict.db --upload --language english
+//!
+//! # Publish a versioned build, update english-latest.json and purge the CDN cache
+//! dict-preprocessor -i kaikki-english.jsonl.gz -o english-dict.db --upload --language english \
+//!     --dict-version 42 --purge digitalocean
+//!
+//! # Small test database from 1000 randomly chosen entries
+//! dict-preprocessor --input kaikki-english.jsonl.gz --output test.db --sample 1000 --seed 42
+//!
+//! # Fill word frequencies from an external list
+//! dict-preprocessor --input kaikki-english.jsonl.gz --output english-dict.db --frequency-list words.csv
+//!
+//! # Add emoji and symbol names from a Unicode CLDR annotation file
+//! dict-preprocessor --input kaikki-english.jsonl.gz --output english-dict.db --symbols annotations/en.xml
+//!
+//! # Store gloss embeddings computed by an external model for concept search
+//! dict-preprocessor --input kaikki-english.jsonl.gz --output english-dict.db \
+//!     --embeddings glosses.vectors.jsonl --embedding-model all-MiniLM-L6-v2
+//!
+//! # Also store the 10 nearest words of each embedded word for "similar words"
+//! dict-preprocessor --input kaikki-english.jsonl.gz --output english-dict.db \
+//!     --embeddings glosses.vectors.jsonl --related-words 10
+//!
+//! # Use 8 threads for parsing and compression
+//! dict-preprocessor --input kaikki-english.jsonl.gz --output english-dict.db --jobs 8
+//!
+//! # Bundle the database, headword FST and license into an offline package
+//! dict-preprocessor --input kaikki-english.jsonl.gz --output english-dict.db --headword-fst \
+//!     --package english.dictpkg --license LICENSE-wiktionary.txt --language english
+//!
+//! # Save import statistics and phase timings as JSON
+//! dict-preprocessor --input kaikki-english.jsonl.gz --output english-dict.db --stats-json stats.json
+//!
+//! # Inspect an existing database
+//! dict-preprocessor inspect english-dict.db
+//! dict-preprocessor stats english-dict.db --top 20 --json
+//!
+//! # Convert a database back to JSONL, CSV or StarDict
+//! dict-preprocessor export --db english-dict.db --format jsonl --output english.jsonl
+//! dict-preprocessor export --db english-dict.db --format stardict --output stardict/
 //! ```
 
+mod export;
+mod inspect;
+mod publish;
+mod upload;
+
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use dict_core::{FtsProfile, ImportOptions, Normalization};
 use indicatif::{HumanBytes, HumanDuration, ProgressBar, ProgressStyle};
-use s3::bucket::Bucket;
-use s3::creds::Credentials;
-use s3::region::Region;
 
 /// Dictionary preprocessor - converts Wiktionary JSONL to SQLite
 #[derive(Parser, Debug)]
@@ -46,15 +97,42 @@ Example usage:
   dict-preprocessor -i kaikki-english.jsonl.gz -o english.db
   dict-preprocessor --input data.jsonl --output dict.db --force
   dict-preprocessor -i data.jsonl -o dict.db --upload --language english
+  dict-preprocessor -i data.jsonl -o dict-lite.db --profile lite
+  dict-preprocessor -i data.jsonl -o test.db --sample 1000 --seed 42
+  dict-preprocessor inspect dict.db
+  dict-preprocessor stats dict.db --json
+  dict-preprocessor export --db dict.db --format csv --output dict.csv
 ")]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    build: BuildArgs,
+}
+
+/// Subcommands operating on an existing database
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print build metadata, row counts and size breakdown of a database
+    Inspect(inspect::InspectArgs),
+    /// Print content statistics (counts by language/POS, top tags)
+    Stats(inspect::StatsArgs),
+    /// Export a database as JSONL, CSV or StarDict
+    Export(export::ExportArgs),
+}
+
+/// Options for the default JSONL -> SQLite build
+#[derive(clap::Args, Debug)]
+struct BuildArgs {
     /// Input JSONL file path (supports .jsonl and .jsonl.gz)
-    #[arg(short, long)]
-    input: PathBuf,
+    #[arg(short, long, required = true)]
+    input: Option<PathBuf>,
 
     /// Output SQLite database path
-    #[arg(short, long)]
-    output: PathBuf,
+    #[arg(short, long, required = true)]
+    output: Option<PathBuf>,
 
     /// Overwrite existing output file
     #[arg(long, default_value = "false")]
@@ -68,9 +146,177 @@ struct Args {
     #[arg(long, default_value = "false")]
     upload: bool,
 
+    /// Upload target URL; the scheme selects the backend (https Spaces URL,
+    /// s3://, r2://, gs://, file://, sftp://). Defaults to $CDN_URL
+    #[arg(long)]
+    upload_url: Option<String>,
+
+    /// Part size in MiB for multipart CDN uploads (minimum 5)
+    #[arg(long, default_value = "64")]
+    part_size_mb: u64,
+
+    /// Retries per request before a CDN upload is abandoned
+    #[arg(long, default_value = "5")]
+    upload_retries: u32,
+
     /// Language code for the database (used in CDN path, e.g., "english")
     #[arg(short, long)]
     language: Option<String>,
+
+    /// Dictionary version; recorded in the database, uploads as
+    /// {language}-dict-v{VERSION}.db.zst and points {language}-latest.json at it
+    #[arg(long)]
+    dict_version: Option<String>,
+
+    /// Purge the uploaded files from this CDN's cache after uploading
+    /// (reads CDN_PURGE_TOKEN and CDN_PURGE_TARGET)
+    #[arg(long, value_enum)]
+    purge: Option<publish::PurgeProvider>,
+
+    /// Number of worker threads for JSON parsing and compression
+    /// (defaults to the number of CPUs)
+    #[arg(short, long)]
+    jobs: Option<usize>,
+
+    /// Only import N entries, to quickly produce a small test database
+    #[arg(long, value_name = "N")]
+    sample: Option<u64>,
+
+    /// Pick the --sample entries at random with this seed instead of
+    /// taking the first N (the same seed gives the same database)
+    #[arg(long, requires = "sample")]
+    seed: Option<u64>,
+
+    /// Only import the headwords listed in FILE (one per line, `#` starts
+    /// a comment) and the entries that are their inflections or variants,
+    /// for small special-purpose builds such as an exam word list
+    #[arg(long, value_name = "FILE")]
+    keep_wordlist: Option<PathBuf>,
+
+    /// CSV/TSV frequency list (word first, count last) joined onto the
+    /// imported words to fill their frequency column
+    #[arg(long, value_name = "FILE")]
+    frequency_list: Option<PathBuf>,
+
+    /// Unicode CLDR annotation file (e.g. `annotations/en.xml`) whose
+    /// emoji and symbol names are added so symbols can be looked up
+    #[arg(long, value_name = "FILE")]
+    symbols: Option<PathBuf>,
+
+    /// JSONL file of gloss embeddings (`{"text": ..., "vector": [...]}` per
+    /// line) to store for semantic search; vectors are computed beforehand
+    /// with a sentence-embedding model
+    #[arg(long, value_name = "FILE")]
+    embeddings: Option<PathBuf>,
+
+    /// Name of the model the embeddings were computed with, recorded so
+    /// apps embed queries with the same one
+    #[arg(long, value_name = "NAME", requires = "embeddings")]
+    embedding_model: Option<String>,
+
+    /// Precompute the K words closest in meaning to each embedded word, so
+    /// apps can show similar words without running a model
+    #[arg(long, value_name = "K", requires = "embeddings")]
+    related_words: Option<u32>,
+
+    /// Also write the headword keys to an FST next to the database
+    /// (`<output>.fst`), which apps map into memory for fuzzy and pattern
+    /// matching instead of loading the keys from SQLite
+    #[arg(long)]
+    headword_fst: bool,
+
+    /// Bundle the database, its headword FST if written, and the --license
+    /// files into an offline `.dictpkg` package at FILE
+    #[arg(long, value_name = "FILE")]
+    package: Option<PathBuf>,
+
+    /// License or attribution file to include in the --package (repeatable)
+    #[arg(long, value_name = "FILE", requires = "package")]
+    license: Vec<PathBuf>,
+
+    /// Write the import statistics, with phase timings and bytes read, to
+    /// this file as JSON
+    #[arg(long, value_name = "FILE")]
+    stats_json: Option<PathBuf>,
+
+    /// Build profile: "full" keeps everything, "lite" drops etymologies,
+    /// translations, examples, inflected forms and synonyms for the free tier
+    #[arg(long, value_enum, default_value = "full")]
+    profile: BuildProfile,
+
+    /// Full-text index layout: "full" stores token positions, "compact"
+    /// leaves them out for a smaller database (prefix search is unaffected)
+    #[arg(long, value_enum, default_value = "full")]
+    fts_index: FtsIndex,
+
+    /// How headwords and queries are folded: "fold" ignores case and
+    /// diacritics, "case" only case, for languages where diacritics tell
+    /// words apart
+    #[arg(long, value_enum, default_value = "fold")]
+    normalization: NormalizationPolicy,
+
+    /// Skip example sentences longer than N characters
+    #[arg(long, value_name = "N")]
+    max_example_len: Option<usize>,
+
+    /// Keep examples that repeat an earlier one of the same sense apart
+    /// from case, diacritics, punctuation or spacing
+    #[arg(long, default_value = "false")]
+    keep_duplicate_examples: bool,
+}
+
+/// Content profile for the built database
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum BuildProfile {
+    /// All imported content
+    Full,
+    /// Headwords, definitions and pronunciations only
+    Lite,
+}
+
+impl BuildProfile {
+    fn import_options(self) -> ImportOptions {
+        match self {
+            BuildProfile::Full => ImportOptions::default(),
+            BuildProfile::Lite => ImportOptions::lite(),
+        }
+    }
+}
+
+/// Full-text index layout for the built database
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum FtsIndex {
+    /// Token positions stored
+    Full,
+    /// Token positions left out
+    Compact,
+}
+
+impl From<FtsIndex> for FtsProfile {
+    fn from(index: FtsIndex) -> Self {
+        match index {
+            FtsIndex::Full => FtsProfile::Full,
+            FtsIndex::Compact => FtsProfile::Compact,
+        }
+    }
+}
+
+/// Normalization policy for the built database
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum NormalizationPolicy {
+    /// Case and diacritics folded
+    Fold,
+    /// Case folded only
+    Case,
+}
+
+impl From<NormalizationPolicy> for Normalization {
+    fn from(policy: NormalizationPolicy) -> Self {
+        match policy {
+            NormalizationPolicy::Fold => Normalization::Fold,
+            NormalizationPolicy::Case => Normalization::Case,
+        }
+    }
 }
 
 #[tokio::main]
@@ -83,37 +329,110 @@ async fn main() -> Result<()> {
 
     let args = Args::parse();
 
+    match args.command {
+        Some(Command::Inspect(inspect_args)) => inspect::run_inspect(&inspect_args),
+        Some(Command::Stats(stats_args)) => inspect::run_stats(&stats_args),
+        Some(Command::Export(export_args)) => export::run_export(&export_args),
+        None => build(&args.build).await,
+    }
+}
+
+/// Import a JSONL file into a new database, optionally uploading it to the CDN
+async fn build(args: &BuildArgs) -> Result<()> {
+    // clap enforces both when no subcommand is given
+    let input = args.input.as_deref().context("--input is required")?;
+    let output = args.output.as_deref().context("--output is required")?;
+
     // Validate input file exists
-    if !args.input.exists() {
-        anyhow::bail!("Input file does not exist: {:?}", args.input);
+    if !input.exists() {
+        anyhow::bail!("Input file does not exist: {:?}", input);
+    }
+
+    if let Some(list) = &args.frequency_list {
+        if !list.exists() {
+            anyhow::bail!("Frequency list does not exist: {:?}", list);
+        }
+    }
+
+    if let Some(list) = &args.keep_wordlist {
+        if !list.exists() {
+            anyhow::bail!("Word list does not exist: {:?}", list);
+        }
+    }
+
+    if let Some(annotations) = &args.symbols {
+        if !annotations.exists() {
+            anyhow::bail!("Symbol annotation file does not exist: {:?}", annotations);
+        }
+    }
+
+    if let Some(vectors) = &args.embeddings {
+        if !vectors.exists() {
+            anyhow::bail!("Embeddings file does not exist: {:?}", vectors);
+        }
     }
 
     // Validate upload args
     if args.upload && args.language.is_none() {
         anyhow::bail!("--language is required when using --upload");
     }
+    let upload_url = match &args.upload_url {
+        Some(url) => url.clone(),
+        None if args.upload => std::env::var("CDN_URL")
+            .context("--upload-url not given and CDN_URL not set in environment")?,
+        None => String::new(),
+    };
+    if args.upload && args.part_size_mb < upload::MIN_PART_SIZE_MB {
+        anyhow::bail!(
+            "--part-size-mb must be at least {}",
+            upload::MIN_PART_SIZE_MB
+        );
+    }
 
     // Check if output exists
-    if args.output.exists() && !args.force {
+    if output.exists() && !args.force {
         anyhow::bail!(
             "Output file already exists: {:?}. Use --force to overwrite.",
-            args.output
+            output
         );
     }
 
     // Remove existing output if force is set
-    if args.output.exists() && args.force {
-        std::fs::remove_file(&args.output).context("Failed to remove existing output file")?;
+    if output.exists() && args.force {
+        std::fs::remove_file(output).context("Failed to remove existing output file")?;
     }
 
     // Get input file size for reporting
-    let input_size = std::fs::metadata(&args.input).map(|m| m.len()).unwrap_or(0);
-
-    println!("Input:  {:?} ({})", args.input, HumanBytes(input_size));
-    println!("Output: {:?}", args.output);
+    let input_size = std::fs::metadata(input).map(|m| m.len()).unwrap_or(0);
+
+    println!("Input:  {:?} ({})", input, HumanBytes(input_size));
+    println!("Output: {:?}", output);
+    println!("Profile: {:?}", args.profile);
+    match (args.sample, args.seed) {
+        (Some(n), Some(seed)) => println!("Sample: {} random entries (seed {})", n, seed),
+        (Some(n), None) => println!("Sample: first {} entries", n),
+        _ => {}
+    }
+    let keep_words = match &args.keep_wordlist {
+        Some(list) => {
+            let words =
+                dict_core::import::read_wordlist(list.to_str().context("Invalid word list path")?)
+                    .context("Failed to read word list")?;
+            println!("Word list: {} headwords from {:?}", words.len(), list);
+            Some(words)
+        }
+        None => None,
+    };
     println!();
 
-    log::info!("Starting import from {:?} to {:?}", args.input, args.output);
+    log::info!("Starting import from {:?} to {:?}", input, output);
+
+    let jobs = args.jobs.unwrap_or_else(|| {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    });
+    let mut timings = StageTimings::default();
 
     let start_time = Instant::now();
 
@@ -145,9 +464,22 @@ async fn main() -> Result<()> {
         pb_clone.set_position(current);
     };
 
-    let stats = dict_core::import_jsonl_with_stats(
-        args.output.to_str().context("Invalid output path")?,
-        args.input.to_str().context("Invalid input path")?,
+    let import_options = ImportOptions {
+        jobs,
+        sample: args.sample,
+        seed: args.seed,
+        dict_version: args.dict_version.clone(),
+        fts: args.fts_index.into(),
+        normalization: args.normalization.into(),
+        dedup_examples: !args.keep_duplicate_examples,
+        max_example_len: args.max_example_len,
+        keep_words,
+        ..args.profile.import_options()
+    };
+    let stats = dict_core::import_jsonl_with_options(
+        output.to_str().context("Invalid output path")?,
+        input.to_str().context("Invalid input path")?,
+        &import_options,
         progress_callback,
     )
     .context("Import failed")?;
@@ -155,11 +487,10 @@ async fn main() -> Result<()> {
     pb.finish_and_clear();
 
     let elapsed = start_time.elapsed();
+    timings.record("Import", elapsed);
 
     // Get output file size
-    let output_size = std::fs::metadata(&args.output)
-        .map(|m| m.len())
-        .unwrap_or(0);
+    let output_size = std::fs::metadata(output).map(|m| m.len()).unwrap_or(0);
 
     // Print statistics
     println!("Import complete!");
@@ -189,6 +520,18 @@ async fn main() -> Result<()> {
         "  Translations:       {:>12}",
         format_number(stats.translations_imported)
     );
+    println!(
+        "  Inflected forms:    {:>12}",
+        format_number(stats.forms_imported)
+    );
+    println!(
+        "  Synonyms/antonyms:  {:>12}",
+        format_number(stats.relations_imported)
+    );
+    println!(
+        "  Form-of links:      {:>12}",
+        format_number(stats.form_of_linked)
+    );
     println!("  Errors:             {:>12}", format_number(stats.errors));
     println!("  Skipped:            {:>12}", format_number(stats.skipped));
     println!();
@@ -200,24 +543,189 @@ async fn main() -> Result<()> {
         let lines_per_sec = stats.lines_processed / elapsed.as_secs();
         println!("  Lines/second:       {:>12}", format_number(lines_per_sec));
     }
+    println!("  Input read:         {:>12}", HumanBytes(stats.bytes_read));
+    let phases = [
+        ("Counting lines", stats.timings.counting_micros),
+        ("Parsing", stats.timings.parsing_micros),
+        ("Inserting", stats.timings.inserting_micros),
+        ("Finalizing", stats.timings.finalizing_micros),
+        ("Slowest batch", stats.peak_batch_micros),
+    ];
+    for (phase, micros) in phases {
+        println!(
+            "  {:<20}{:>12.2?}",
+            format!("{}:", phase),
+            Duration::from_micros(micros)
+        );
+    }
+
+    if let Some(path) = &args.stats_json {
+        let json = serde_json::to_string_pretty(&stats)?;
+        std::fs::write(path, json)
+            .with_context(|| format!("Failed to write statistics to {:?}", path))?;
+    }
 
     log::info!(
         "Successfully imported {} words to {:?} in {:?}",
         stats.words_imported,
-        args.output,
+        output,
         elapsed
     );
 
+    // Join the frequency list onto the imported words
+    if let Some(list) = &args.frequency_list {
+        println!();
+        println!("Applying frequency list {:?}...", list);
+        let stage_start = Instant::now();
+        let freq = dict_core::import::apply_frequency_list(
+            output.to_str().context("Invalid output path")?,
+            list.to_str().context("Invalid frequency list path")?,
+        )
+        .context("Failed to apply frequency list")?;
+        timings.record("Frequency list", stage_start.elapsed());
+
+        println!(
+            "  List entries:       {:>12}",
+            format_number(freq.list_entries)
+        );
+        println!(
+            "  Entries matched:    {:>12}",
+            format_number(freq.list_entries_matched)
+        );
+        println!(
+            "  Invalid lines:      {:>12}",
+            format_number(freq.invalid_lines)
+        );
+        println!(
+            "  Words matched:      {:>12} of {} ({:.1}%)",
+            format_number(freq.words_matched),
+            format_number(freq.words_total),
+            freq.match_rate()
+        );
+    }
+
+    // Name emoji and symbols from CLDR annotations
+    if let Some(annotations) = &args.symbols {
+        println!();
+        println!("Loading symbol annotations {:?}...", annotations);
+        let stage_start = Instant::now();
+        let sym = dict_core::symbols::import_symbols(
+            output.to_str().context("Invalid output path")?,
+            annotations
+                .to_str()
+                .context("Invalid symbol annotation path")?,
+        )
+        .context("Failed to load symbol annotations")?;
+        timings.record("Symbols", stage_start.elapsed());
+
+        println!(
+            "  Symbols annotated:  {:>12}",
+            format_number(sym.annotations_read)
+        );
+        println!(
+            "  Linked to entries:  {:>12}",
+            format_number(sym.symbols_linked)
+        );
+        println!(
+            "  Entries created:    {:>12}",
+            format_number(sym.entries_created)
+        );
+    }
+
+    // Store gloss embeddings for semantic search
+    if let Some(vectors) = &args.embeddings {
+        println!();
+        println!("Loading embeddings {:?}...", vectors);
+        let stage_start = Instant::now();
+        let emb = dict_core::semantic::import_embeddings(
+            output.to_str().context("Invalid output path")?,
+            vectors.to_str().context("Invalid embeddings path")?,
+            args.embedding_model.as_deref(),
+        )
+        .context("Failed to load embeddings")?;
+        timings.record("Embeddings", stage_start.elapsed());
+
+        println!("  Dimensions:         {:>12}", emb.dimensions);
+        println!(
+            "  Vectors read:       {:>12}",
+            format_number(emb.vectors_read)
+        );
+        println!(
+            "  Vectors unmatched:  {:>12}",
+            format_number(emb.vectors_unmatched)
+        );
+        println!(
+            "  Invalid lines:      {:>12}",
+            format_number(emb.invalid_lines)
+        );
+        println!(
+            "  Glosses embedded:   {:>12}",
+            format_number(emb.definitions_embedded)
+        );
+
+        if let Some(k) = args.related_words {
+            println!("Computing {} related words per word...", k);
+            let stage_start = Instant::now();
+            let related = dict_core::semantic::build_related(
+                output.to_str().context("Invalid output path")?,
+                k,
+            )
+            .context("Failed to compute related words")?;
+            timings.record("Related words", stage_start.elapsed());
+            println!("  Words:              {:>12}", format_number(related.words));
+            println!("  Related pairs:      {:>12}", format_number(related.links));
+        }
+    }
+
+    // Headword FST, after every stage that adds entries
+    if args.headword_fst {
+        println!();
+        println!("Writing headword FST...");
+        let stage_start = Instant::now();
+        let keys = dict_core::fuzzy::build_fst(output.to_str().context("Invalid output path")?)
+            .context("Failed to write the headword FST")?;
+        timings.record("Headword FST", stage_start.elapsed());
+        println!("  Keys:               {:>12}", format_number(keys));
+    }
+
+    // Offline package of everything built so far
+    if let Some(package_path) = &args.package {
+        println!();
+        println!("Writing package...");
+        let stage_start = Instant::now();
+        let mut extras = args.license.clone();
+        if args.headword_fst {
+            extras.push(dict_core::fuzzy::fst_path(output));
+        }
+        let manifest = dict_core::package::create(
+            package_path,
+            output,
+            &extras,
+            args.language.as_deref(),
+            args.dict_version.as_deref(),
+        )
+        .context("Failed to write the package")?;
+        timings.record("Package", stage_start.elapsed());
+        let package_size = std::fs::metadata(package_path)
+            .map(|m| m.len())
+            .unwrap_or(0);
+        println!("  Files:              {:>12}", manifest.files.len());
+        println!("  Size:               {:>12}", HumanBytes(package_size));
+        println!("  Written to {}", package_path.display());
+    }
+
     // Upload to CDN if requested
     if args.upload {
         let language = args.language.as_ref().unwrap();
         println!();
         println!("Uploading to CDN...");
 
-        // Compress with zstd
-        let compressed_path = args.output.with_extension("db.zst");
-        println!("  Compressing database...");
-        compress_zstd(&args.output, &compressed_path)?;
+        // Compress with zstd, checksumming both files alongside
+        let compressed_path = output.with_extension("db.zst");
+        println!("  Compressing database ({} threads)...", jobs);
+        let stage_start = Instant::now();
+        let checksums = compress_and_checksum(output, &compressed_path, jobs)?;
+        timings.record("Compress + checksum", stage_start.elapsed());
 
         let compressed_size = std::fs::metadata(&compressed_path)
             .map(|m| m.len())
@@ -229,30 +737,122 @@ async fn main() -> Result<()> {
             (1.0 - compressed_size as f64 / output_size as f64) * 100.0
         );
 
-        // Upload to S3
-        let cdn_key = format!("{}-dict.db.zst", language);
-        println!("  Uploading as '{}'...", cdn_key);
-        upload_to_cdn(&compressed_path, &cdn_key).await?;
+        // Upload to the configured storage backend
+        let cdn_key = match &args.dict_version {
+            Some(version) => format!("{}-dict-v{}.db.zst", language, version),
+            None => format!("{}-dict.db.zst", language),
+        };
+        let object_key = upload::object_key(&upload_url, &cdn_key)?;
+        println!("  Uploading as '{}'...", object_key);
+        let upload_options = upload::UploadOptions {
+            part_size: args.part_size_mb * 1024 * 1024,
+            max_retries: args.upload_retries,
+            quiet: args.quiet,
+        };
+        let stage_start = Instant::now();
+        upload::upload_to_cdn(&upload_url, &compressed_path, &cdn_key, &upload_options).await?;
+        timings.record("Upload", stage_start.elapsed());
+        let mut purge_keys = vec![object_key.clone()];
+
+        // Point clients at the new version
+        if let Some(version) = &args.dict_version {
+            let pointer = publish::LatestPointer {
+                language: language.clone(),
+                version: version.clone(),
+                file: object_key.clone(),
+                size: compressed_size,
+                uncompressed_size: output_size,
+                sha256: checksums.compressed_sha256.clone(),
+                uncompressed_sha256: checksums.uncompressed_sha256.clone(),
+                published_at: publish::unix_now(),
+            };
+            let pointer_key = publish::latest_pointer_key(language);
+            println!("  Updating '{}'...", pointer_key);
+            let stage_start = Instant::now();
+            publish::update_latest_pointer(&upload_url, &pointer, &upload_options).await?;
+            timings.record("Update pointer", stage_start.elapsed());
+            purge_keys.push(pointer_key);
+        }
 
         // Clean up compressed file
         std::fs::remove_file(&compressed_path).ok();
 
+        if let Some(provider) = args.purge {
+            println!("  Purging CDN cache...");
+            let stage_start = Instant::now();
+            publish::purge_cdn(provider, purge_keys).await?;
+            timings.record("Purge CDN", stage_start.elapsed());
+        }
+
         println!("  Upload complete!");
     }
 
+    timings.print();
+
     Ok(())
 }
 
-/// Compress a file using zstd
-fn compress_zstd(input: &Path, output: &Path) -> Result<()> {
+/// Wall-clock time spent in each pipeline stage
+#[derive(Default)]
+struct StageTimings {
+    stages: Vec<(&'static str, Duration)>,
+}
+
+impl StageTimings {
+    fn record(&mut self, stage: &'static str, elapsed: Duration) {
+        self.stages.push((stage, elapsed));
+    }
+
+    fn print(&self) {
+        println!();
+        println!("Stage timings:");
+        for (stage, elapsed) in &self.stages {
+            println!("  {:<20}{:>12.2?}", format!("{}:", stage), elapsed);
+        }
+        let total: Duration = self.stages.iter().map(|(_, elapsed)| *elapsed).sum();
+        println!("  {:<20}{:>12.2?}", "Total:", total);
+    }
+}
+
+/// SHA-256 checksums of a database and its compressed form
+struct Checksums {
+    compressed_sha256: String,
+    uncompressed_sha256: String,
+}
+
+/// Compress a database with zstd while computing checksums in parallel
+///
+/// The compressed output is hashed as it is written, and the uncompressed
+/// input is hashed on a separate thread, so neither needs another pass once
+/// compression finishes.
+fn compress_and_checksum(input: &Path, output: &Path, jobs: usize) -> Result<Checksums> {
+    std::thread::scope(|scope| {
+        let uncompressed = scope.spawn(|| publish::sha256_file(input));
+        let compressed_sha256 = compress_zstd(input, output, jobs)?;
+        let uncompressed_sha256 = uncompressed
+            .join()
+            .map_err(|_| anyhow::anyhow!("Checksum thread panicked"))??;
+
+        Ok(Checksums {
+            compressed_sha256,
+            uncompressed_sha256,
+        })
+    })
+}
+
+/// Compress a file using multithreaded zstd, returning the output's SHA-256
+fn compress_zstd(input: &Path, output: &Path, jobs: usize) -> Result<String> {
     let input_file = File::open(input).context("Failed to open input file for compression")?;
     let mut reader = BufReader::new(input_file);
 
     let output_file = File::create(output).context("Failed to create compressed output file")?;
-    let writer = BufWriter::new(output_file);
+    let writer = publish::HashingWriter::new(BufWriter::new(output_file));
 
     // Use compression level 19 for good compression (max is 22)
     let mut encoder = zstd::Encoder::new(writer, 19)?;
+    if jobs > 1 {
+        encoder.multithread(jobs as u32)?;
+    }
 
     let mut buffer = vec![0u8; 64 * 1024];
     loop {
@@ -263,78 +863,9 @@ fn compress_zstd(input: &Path, output: &Path) -> Result<()> {
         encoder.write_all(&buffer[..bytes_read])?;
     }
 
-    encoder.finish()?;
-    Ok(())
-}
-
-/// Upload a file to the CDN (S3-compatible storage)
-async fn upload_to_cdn(file_path: &Path, key: &str) -> Result<()> {
-    // Read credentials from environment
-    let access_key_id =
-        std::env::var("CDN_ACCESS_KEY_ID").context("CDN_ACCESS_KEY_ID not set in environment")?;
-    let secret_access_key =
-        std::env::var("CDN_ACCESS_KEY").context("CDN_ACCESS_KEY not set in environment")?;
-    let cdn_url = std::env::var("CDN_URL").context("CDN_URL not set in environment")?;
-
-    // Parse CDN URL to extract bucket and region
-    // Expected format: https://{bucket}.{region}.digitaloceanspaces.com
-    let url = url::Url::parse(&cdn_url).context("Invalid CDN_URL")?;
-    let host = url.host_str().context("CDN_URL missing host")?;
-
-    let parts: Vec<&str> = host.split('.').collect();
-    if parts.len() < 3 {
-        anyhow::bail!(
-            "CDN_URL must be in format: https://{{bucket}}.{{region}}.digitaloceanspaces.com"
-        );
-    }
-
-    let bucket_name = parts[0];
-    let region_name = parts[1];
-    let endpoint = format!("https://{}.digitaloceanspaces.com", region_name);
-
-    log::info!(
-        "Uploading to bucket '{}' in region '{}' with key '{}'",
-        bucket_name,
-        region_name,
-        key
-    );
-
-    // Create credentials and region
-    let credentials = Credentials::new(
-        Some(&access_key_id),
-        Some(&secret_access_key),
-        None,
-        None,
-        None,
-    )?;
-
-    let region = Region::Custom {
-        region: region_name.to_string(),
-        endpoint,
-    };
-
-    // Create bucket handle with public-read ACL header
-    let mut bucket = Bucket::new(bucket_name, region, credentials)?.with_path_style();
-    bucket.add_header("x-amz-acl", "public-read");
-
-    // Read file contents
-    let contents = std::fs::read(file_path).context("Failed to read file for upload")?;
-
-    // Upload
-    let response = bucket
-        .put_object_with_content_type(key, &contents, "application/octet-stream")
-        .await?;
-
-    if response.status_code() >= 300 {
-        anyhow::bail!(
-            "Upload failed with status {}: {}",
-            response.status_code(),
-            String::from_utf8_lossy(response.as_slice())
-        );
-    }
-
-    log::info!("Successfully uploaded {} to CDN", key);
-    Ok(())
+    let mut writer = encoder.finish()?;
+    writer.flush()?;
+    Ok(writer.finalize_hex())
 }
 
 /// Format a number with thousand separators
@@ -0,0 +1,266 @@
+//! Post-upload publishing steps
+//!
+//! After a versioned database (e.g. `english-dict-v42.db.zst`) is uploaded,
+//! a small `{language}-latest.json` pointer is rewritten to name it, and the
+//! CDN cache can be purged so clients see the new version immediately.
+//!
+//! The pointer is named per language rather than a single `latest.json`
+//! because several languages are published to the same bucket, and it is
+//! what `dict_core::download::fetch_manifest` requests. It is written at
+//! the root of the target even when the upload URL has a key prefix, and
+//! names the database by its full object key, so clients find both from
+//! the bucket's base URL.
+
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::upload::{self, UploadOptions};
+
+/// Contents of `{language}-latest.json`
+#[derive(Debug, Clone, Serialize)]
+pub struct LatestPointer {
+    /// Language the database covers (e.g. "english")
+    pub language: String,
+    /// Dictionary version (e.g. "42")
+    pub version: String,
+    /// Full object key of the compressed database, prefix included
+    pub file: String,
+    /// Size of the compressed database in bytes
+    pub size: u64,
+    /// Size of the decompressed database in bytes
+    pub uncompressed_size: u64,
+    /// Hex-encoded SHA-256 of the compressed database
+    pub sha256: String,
+    /// Hex-encoded SHA-256 of the decompressed database
+    pub uncompressed_sha256: String,
+    /// Unix timestamp of publication
+    pub published_at: u64,
+}
+
+/// Object key of the pointer file for a language
+pub fn latest_pointer_key(language: &str) -> String {
+    format!("{}-latest.json", language)
+}
+
+/// Upload the pointer to the root of the database's target
+pub async fn update_latest_pointer(
+    upload_url: &str,
+    pointer: &LatestPointer,
+    options: &UploadOptions,
+) -> Result<()> {
+    let json = serde_json::to_vec_pretty(pointer)?;
+
+    // Removed when dropped, whether or not the upload succeeds
+    let dir = tempfile::tempdir().context("Failed to create temporary directory")?;
+    let path = dir.path().join(latest_pointer_key(&pointer.language));
+    std::fs::write(&path, &json).context("Failed to write latest pointer")?;
+
+    upload::upload_to_root(
+        upload_url,
+        &path,
+        &latest_pointer_key(&pointer.language),
+        options,
+    )
+    .await
+}
+
+/// Current Unix timestamp in seconds
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Compute the hex-encoded SHA-256 of a file
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let file = File::open(path).context("Failed to open file for checksum")?;
+    let mut reader = BufReader::new(file);
+    let mut hasher = Sha256::new();
+
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let bytes_read = reader.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Writer adapter that hashes everything written through it
+pub struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// Hex-encoded SHA-256 of all bytes written
+    pub fn finalize_hex(self) -> String {
+        format!("{:x}", self.hasher.finalize())
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// CDN providers whose cache purge API we can call
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum PurgeProvider {
+    /// DigitalOcean Spaces CDN (CDN_PURGE_TARGET = CDN endpoint id)
+    Digitalocean,
+    /// Cloudflare (CDN_PURGE_TARGET = zone id, needs CDN_PUBLIC_URL)
+    Cloudflare,
+}
+
+/// Purge the given object keys from the CDN cache
+///
+/// Reads `CDN_PURGE_TOKEN` (API token) and `CDN_PURGE_TARGET` from the
+/// environment. Cloudflare purges by full URL, so it also needs
+/// `CDN_PUBLIC_URL`, the public base URL the objects are served from.
+pub async fn purge_cdn(provider: PurgeProvider, keys: Vec<String>) -> Result<()> {
+    // The purge APIs are called with the blocking client
+    tokio::task::spawn_blocking(move || purge_cdn_blocking(provider, &keys))
+        .await
+        .context("CDN purge task failed")?
+}
+
+fn purge_cdn_blocking(provider: PurgeProvider, keys: &[String]) -> Result<()> {
+    let token =
+        std::env::var("CDN_PURGE_TOKEN").context("CDN_PURGE_TOKEN not set in environment")?;
+    let target =
+        std::env::var("CDN_PURGE_TARGET").context("CDN_PURGE_TARGET not set in environment")?;
+
+    let request = match provider {
+        PurgeProvider::Digitalocean => ureq::delete(&format!(
+            "https://api.digitalocean.com/v2/cdn/endpoints/{}/cache",
+            target
+        )),
+        PurgeProvider::Cloudflare => ureq::post(&format!(
+            "https://api.cloudflare.com/client/v4/zones/{}/purge_cache",
+            target
+        )),
+    };
+
+    let public_url = match provider {
+        PurgeProvider::Digitalocean => None,
+        PurgeProvider::Cloudflare => {
+            Some(std::env::var("CDN_PUBLIC_URL").context("CDN_PUBLIC_URL not set in environment")?)
+        }
+    };
+    let files = purge_files(keys, public_url.as_deref());
+
+    log::info!("Purging {:?} from {:?} CDN cache", files, provider);
+
+    request
+        .set("Authorization", &format!("Bearer {}", token))
+        .send_json(serde_json::json!({ "files": files }))
+        .context("CDN purge request failed")?;
+
+    Ok(())
+}
+
+/// What a purge request lists: the object keys themselves, or their URLs
+/// under `public_url` for providers that purge by URL
+fn purge_files(keys: &[String], public_url: Option<&str>) -> Vec<String> {
+    match public_url {
+        None => keys.to_vec(),
+        Some(base) => keys
+            .iter()
+            .map(|key| format!("{}/{}", base.trim_end_matches('/'), key))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pointer(file: &str) -> LatestPointer {
+        LatestPointer {
+            language: "english".to_string(),
+            version: "42".to_string(),
+            file: file.to_string(),
+            size: 10,
+            uncompressed_size: 30,
+            sha256: "ab".repeat(32),
+            uncompressed_sha256: "cd".repeat(32),
+            published_at: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn test_pointer_is_a_download_manifest() {
+        let json = serde_json::to_string(&pointer("dicts/english-dict-v42.db.zst")).unwrap();
+        let manifest: dict_core::download::Manifest = serde_json::from_str(&json).unwrap();
+        assert_eq!(manifest.file, "dicts/english-dict-v42.db.zst");
+        assert_eq!(manifest.version, "42");
+        assert_eq!(manifest.uncompressed_size, 30);
+        assert_eq!(
+            dict_core::download::manifest_url("https://cdn.example.com/", "english"),
+            format!("https://cdn.example.com/{}", latest_pointer_key("english"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pointer_written_at_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = url::Url::from_directory_path(dir.path()).unwrap();
+        let options = UploadOptions {
+            part_size: 1024,
+            max_retries: 0,
+            quiet: true,
+        };
+        update_latest_pointer(url.as_str(), &pointer("english-dict-v42.db.zst"), &options)
+            .await
+            .unwrap();
+
+        let written = std::fs::read_to_string(dir.path().join("english-latest.json")).unwrap();
+        let manifest: dict_core::download::Manifest = serde_json::from_str(&written).unwrap();
+        assert_eq!(manifest.file, "english-dict-v42.db.zst");
+    }
+
+    #[test]
+    fn test_purge_keys_are_object_keys() {
+        let db_key = upload::object_key(
+            "s3://bucket/dicts/?region=eu-west-1",
+            "english-dict-v42.db.zst",
+        )
+        .unwrap();
+        assert_eq!(db_key, "dicts/english-dict-v42.db.zst");
+        let keys = vec![db_key, latest_pointer_key("english")];
+
+        assert_eq!(purge_files(&keys, None), keys);
+        assert_eq!(
+            purge_files(&keys, Some("https://cdn.example.com/")),
+            [
+                "https://cdn.example.com/dicts/english-dict-v42.db.zst",
+                "https://cdn.example.com/english-latest.json",
+            ]
+        );
+    }
+}
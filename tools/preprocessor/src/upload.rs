@@ -0,0 +1,323 @@
+//! Publishing built databases to storage
+//!
+//! Each storage target implements the small [`UploadBackend`] trait and is
+//! selected from the upload URL's scheme:
+//!
+//! | URL                                                 | Backend                  |
+//! |-----------------------------------------------------|--------------------------|
+//! | `https://{bucket}.{region}.digitaloceanspaces.com`  | DigitalOcean Spaces      |
+//! | `s3://{bucket}[/prefix]?region=..&endpoint=..`      | Generic S3 (AWS, MinIO)  |
+//! | `r2://{account_id}/{bucket}[/prefix]`               | Cloudflare R2            |
+//! | `gs://{bucket}[/prefix]`                            | Google Cloud Storage     |
+//! | `file:///path/to/dir`                               | Local or mounted dir     |
+//! | `sftp://[user@]host[:port]/path`                    | SFTP via the `sftp` tool |
+//!
+//! S3-compatible backends read credentials from `CDN_ACCESS_KEY_ID` and
+//! `CDN_ACCESS_KEY` (HMAC keys for GCS).
+
+mod local;
+mod s3_compat;
+
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use indicatif::{ProgressBar, ProgressStyle};
+
+pub use self::s3_compat::MIN_PART_SIZE_MB;
+
+/// Delay before the first retry; doubled on each further attempt
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Upper bound for the retry delay
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Options controlling how a file is uploaded
+#[derive(Debug, Clone)]
+pub struct UploadOptions {
+    /// Size of each multipart chunk in bytes
+    pub part_size: u64,
+    /// Number of retries per request before giving up
+    pub max_retries: u32,
+    /// Suppress the progress bar
+    pub quiet: bool,
+}
+
+/// A storage target that built databases can be published to
+#[async_trait]
+pub trait UploadBackend: Send + Sync {
+    /// Human-readable description of the target, for logs
+    fn describe(&self) -> String;
+
+    /// Upload `file_path` so that it is published under `key`
+    async fn upload(&self, file_path: &Path, key: &str, options: &UploadOptions) -> Result<()>;
+
+    /// Full object key `key` is published under, with any key prefix
+    /// taken from the URL
+    fn object_key(&self, key: &str) -> String {
+        key.to_string()
+    }
+
+    /// The same target without the URL's key prefix
+    fn at_root(&self) -> Box<dyn UploadBackend>;
+}
+
+/// Select a backend for an upload URL based on its scheme
+pub fn backend_from_url(raw_url: &str) -> Result<Box<dyn UploadBackend>> {
+    let url =
+        url::Url::parse(raw_url).with_context(|| format!("Invalid upload URL: {}", raw_url))?;
+
+    let backend: Box<dyn UploadBackend> = match url.scheme() {
+        "https" => Box::new(s3_compat::S3Backend::digitalocean_spaces(&url)?),
+        "s3" => Box::new(s3_compat::S3Backend::generic(&url)?),
+        "r2" => Box::new(s3_compat::S3Backend::cloudflare_r2(&url)?),
+        "gs" => Box::new(s3_compat::S3Backend::google_cloud_storage(&url)?),
+        "file" => Box::new(local::FileBackend::from_url(&url)?),
+        "sftp" => Box::new(local::SftpBackend::from_url(&url)?),
+        scheme => anyhow::bail!(
+            "Unsupported upload URL scheme '{}' (expected https, s3, r2, gs, file or sftp)",
+            scheme
+        ),
+    };
+    Ok(backend)
+}
+
+/// Upload a file using the backend selected by `upload_url`
+pub async fn upload_to_cdn(
+    upload_url: &str,
+    file_path: &Path,
+    key: &str,
+    options: &UploadOptions,
+) -> Result<()> {
+    upload_with(backend_from_url(upload_url)?, file_path, key, options).await
+}
+
+/// Upload a file to the root of the target selected by `upload_url`,
+/// ignoring any key prefix in its path
+pub async fn upload_to_root(
+    upload_url: &str,
+    file_path: &Path,
+    key: &str,
+    options: &UploadOptions,
+) -> Result<()> {
+    upload_with(
+        backend_from_url(upload_url)?.at_root(),
+        file_path,
+        key,
+        options,
+    )
+    .await
+}
+
+/// Full object key a file uploaded to `upload_url` as `key` is stored
+/// under, which CDN purges and the latest pointer refer to
+pub fn object_key(upload_url: &str, key: &str) -> Result<String> {
+    Ok(backend_from_url(upload_url)?.object_key(key))
+}
+
+async fn upload_with(
+    backend: Box<dyn UploadBackend>,
+    file_path: &Path,
+    key: &str,
+    options: &UploadOptions,
+) -> Result<()> {
+    log::info!("Upload target: {}", backend.describe());
+    backend.upload(file_path, key, options).await?;
+    log::info!("Successfully uploaded {} to {}", key, backend.describe());
+    Ok(())
+}
+
+/// Progress bar measuring uploaded bytes
+fn progress_bar(total_bytes: u64, quiet: bool) -> Result<ProgressBar> {
+    let pb = if quiet {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(total_bytes)
+    };
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")?
+            .progress_chars("#>-"),
+    );
+    Ok(pb)
+}
+
+/// Join an optional key prefix taken from a URL path with an object key
+fn prefixed_key(prefix: &str, key: &str) -> String {
+    let prefix = prefix.trim_matches('/');
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}/{}", prefix, key)
+    }
+}
+
+/// Run an async operation, retrying failures with exponential backoff
+async fn with_retry<T, F, Fut>(what: &str, max_retries: u32, mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < max_retries => {
+                let delay = backoff_delay(attempt);
+                attempt += 1;
+                log::warn!(
+                    "Failed to {} (attempt {}/{}): {}. Retrying in {:?}",
+                    what,
+                    attempt,
+                    max_retries + 1,
+                    e,
+                    delay
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to {} after {} attempts", what, attempt + 1))
+            }
+        }
+    }
+}
+
+/// Delay before retry number `attempt` (0-based)
+fn backoff_delay(attempt: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// Description and object key for "k" of the backend for `url`
+    fn backend(url: &str) -> (String, String) {
+        let backend = backend_from_url(url).unwrap();
+        (backend.describe(), backend.object_key("k"))
+    }
+
+    fn backend_error(url: &str) -> String {
+        match backend_from_url(url) {
+            Ok(backend) => panic!("{} gave {}", url, backend.describe()),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_backend_from_url() {
+        assert_eq!(
+            backend("https://dicts.nyc3.digitaloceanspaces.com/builds"),
+            (
+                "DigitalOcean Spaces bucket 'dicts' at https://nyc3.digitaloceanspaces.com".into(),
+                "builds/k".into()
+            )
+        );
+        assert_eq!(
+            backend("s3://dicts?region=eu-west-1"),
+            (
+                "S3 bucket 'dicts' at https://s3.eu-west-1.amazonaws.com".into(),
+                "k".into()
+            )
+        );
+        assert_eq!(
+            backend("s3://dicts/builds/?endpoint=http://localhost:9000").0,
+            "S3 bucket 'dicts' at http://localhost:9000"
+        );
+        assert_eq!(
+            backend("r2://account/dicts/builds"),
+            (
+                "Cloudflare R2 bucket 'dicts' at https://account.r2.cloudflarestorage.com".into(),
+                "builds/k".into()
+            )
+        );
+        assert_eq!(
+            backend("gs://dicts"),
+            (
+                "Google Cloud Storage bucket 'dicts' at https://storage.googleapis.com".into(),
+                "k".into()
+            )
+        );
+        assert_eq!(
+            backend("file:///srv/dicts"),
+            ("directory \"/srv/dicts\"".into(), "k".into())
+        );
+        assert_eq!(
+            backend("sftp://deploy@example.com:2222/srv/dicts"),
+            ("SFTP deploy@example.com:/srv/dicts".into(), "k".into())
+        );
+
+        // The root of a prefixed target
+        let root = backend_from_url("r2://account/dicts/builds")
+            .unwrap()
+            .at_root();
+        assert_eq!(root.object_key("k"), "k");
+    }
+
+    #[test]
+    fn test_backend_from_url_rejects() {
+        assert!(backend_error("ftp://example.com/dicts")
+            .starts_with("Unsupported upload URL scheme 'ftp'"));
+        assert!(backend_error("not a url").starts_with("Invalid upload URL"));
+        assert!(backend_error("https://example.com").contains("digitaloceanspaces.com"));
+        assert!(backend_error("r2://account/").contains("r2://{account_id}/{bucket}"));
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let delays: Vec<u64> = (0..8).map(|n| backoff_delay(n).as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 32, 60, 60]);
+        assert_eq!(backoff_delay(u32::MAX), MAX_BACKOFF);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_with_retry_succeeds_after_failures() {
+        let calls = Cell::new(0);
+        let start = tokio::time::Instant::now();
+        let value = with_retry("fetch", 3, || {
+            calls.set(calls.get() + 1);
+            let call = calls.get();
+            async move {
+                if call < 3 {
+                    anyhow::bail!("try {} failed", call);
+                }
+                Ok(call)
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(value, 3);
+        // Waited 1s, then 2s
+        assert_eq!(start.elapsed(), Duration::from_secs(3));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_with_retry_gives_up() {
+        let calls = Cell::new(0);
+        let start = tokio::time::Instant::now();
+        let err = with_retry("fetch", 2, || {
+            calls.set(calls.get() + 1);
+            async { Err::<(), _>(anyhow::anyhow!("unreachable host")) }
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(calls.get(), 3);
+        assert_eq!(err.to_string(), "Failed to fetch after 3 attempts");
+        assert_eq!(err.root_cause().to_string(), "unreachable host");
+        assert_eq!(start.elapsed(), Duration::from_secs(3));
+
+        let calls = Cell::new(0);
+        let result = with_retry("fetch", 0, || {
+            calls.set(calls.get() + 1);
+            async { Err::<(), _>(anyhow::anyhow!("unreachable host")) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 1);
+    }
+}
@@ -0,0 +1,221 @@
+//! Self-hosted targets: local directories and SFTP servers
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+
+use super::{prefixed_key, progress_bar, with_retry, UploadBackend, UploadOptions};
+
+/// A directory on the local filesystem (or a mounted network share)
+#[derive(Clone)]
+pub struct FileBackend {
+    dir: PathBuf,
+}
+
+impl FileBackend {
+    /// `file:///path/to/dir`
+    pub fn from_url(url: &url::Url) -> Result<Self> {
+        let dir = url
+            .to_file_path()
+            .map_err(|_| anyhow::anyhow!("Invalid file:// upload URL: {}", url))?;
+        Ok(Self { dir })
+    }
+}
+
+#[async_trait]
+impl UploadBackend for FileBackend {
+    fn describe(&self) -> String {
+        format!("directory {:?}", self.dir)
+    }
+
+    /// Copies to a temporary name first so readers never see a partial file
+    async fn upload(&self, file_path: &Path, key: &str, options: &UploadOptions) -> Result<()> {
+        let target = self.dir.join(key);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {:?}", parent))?;
+        }
+
+        let file_size = std::fs::metadata(file_path)
+            .context("Failed to stat file for upload")?
+            .len();
+        let pb = progress_bar(file_size, options.quiet)?;
+
+        let mut partial = target.clone().into_os_string();
+        partial.push(".partial");
+        let partial = PathBuf::from(partial);
+        // Copying a database takes a while; keep it off the async workers
+        let source = file_path.to_path_buf();
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            std::fs::copy(&source, &partial)
+                .with_context(|| format!("Failed to copy to {:?}", partial))?;
+            std::fs::rename(&partial, &target)
+                .with_context(|| format!("Failed to move {:?} into place", partial))
+        })
+        .await
+        .context("File copy task failed")??;
+
+        pb.inc(file_size);
+        pb.finish_and_clear();
+        Ok(())
+    }
+
+    /// The directory is the root; keys have no prefix
+    fn at_root(&self) -> Box<dyn UploadBackend> {
+        Box::new(self.clone())
+    }
+}
+
+/// A remote directory reached through the system `sftp` client
+///
+/// Authentication uses the caller's SSH configuration (keys, agent,
+/// `~/.ssh/config`), so no credentials are handled here.
+#[derive(Clone)]
+pub struct SftpBackend {
+    /// `[user@]host`
+    destination: String,
+    port: Option<u16>,
+    /// Remote directory taken from the URL path
+    dir: String,
+}
+
+impl SftpBackend {
+    /// `sftp://[user@]host[:port]/path`
+    pub fn from_url(url: &url::Url) -> Result<Self> {
+        let host = url.host_str().context("SFTP URL missing host")?;
+        let destination = if url.username().is_empty() {
+            host.to_string()
+        } else {
+            format!("{}@{}", url.username(), host)
+        };
+
+        Ok(Self {
+            destination,
+            port: url.port(),
+            dir: url.path().to_string(),
+        })
+    }
+
+    /// Run an `sftp` batch script against the server
+    fn run_batch(&self, script: &str) -> Result<()> {
+        let mut command = Command::new("sftp");
+        command.arg("-b").arg("-");
+        if let Some(port) = self.port {
+            command.arg("-P").arg(port.to_string());
+        }
+        command
+            .arg(&self.destination)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null());
+
+        let mut child = command
+            .spawn()
+            .context("Failed to run sftp (is OpenSSH installed?)")?;
+        child
+            .stdin
+            .take()
+            .context("Failed to open sftp stdin")?
+            .write_all(script.as_bytes())?;
+
+        let status = child.wait()?;
+        if !status.success() {
+            anyhow::bail!("sftp exited with {}", status);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UploadBackend for SftpBackend {
+    fn describe(&self) -> String {
+        format!("SFTP {}:{}", self.destination, self.dir)
+    }
+
+    /// Uploads to a temporary name and renames it into place
+    async fn upload(&self, file_path: &Path, key: &str, options: &UploadOptions) -> Result<()> {
+        let remote = format!("/{}", prefixed_key(&self.dir, key));
+        let partial = format!("{}.partial", remote);
+        let local = file_path.to_str().context("Invalid upload file path")?;
+
+        // sftp renames with the posix-rename extension where the server
+        // has it (OpenSSH does), replacing the target in one step
+        let script = format!("put \"{local}\" \"{partial}\"\nrename \"{partial}\" \"{remote}\"\n");
+
+        let file_size = std::fs::metadata(file_path)
+            .context("Failed to stat file for upload")?
+            .len();
+        let pb = progress_bar(file_size, options.quiet)?;
+
+        with_retry("upload via sftp", options.max_retries, || async {
+            // sftp runs until the upload is done; wait for it off the
+            // async workers
+            let backend = self.clone();
+            let script = script.clone();
+            tokio::task::spawn_blocking(move || backend.run_batch(&script))
+                .await
+                .context("sftp task failed")?
+        })
+        .await?;
+
+        pb.inc(file_size);
+        pb.finish_and_clear();
+        Ok(())
+    }
+
+    /// The remote directory is the root; keys have no prefix
+    fn at_root(&self) -> Box<dyn UploadBackend> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_backend_replaces_target() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let source = source_dir.path().join("english-dict.db.zst");
+        std::fs::write(&source, b"new build").unwrap();
+
+        let target_dir = tempfile::tempdir().unwrap();
+        let url = url::Url::from_directory_path(target_dir.path()).unwrap();
+        let backend = FileBackend::from_url(&url).unwrap();
+        let options = UploadOptions {
+            part_size: 1024,
+            max_retries: 0,
+            quiet: true,
+        };
+
+        // Into a new subdirectory
+        backend
+            .upload(&source, "v42/english-dict.db.zst", &options)
+            .await
+            .unwrap();
+        let target = target_dir.path().join("v42/english-dict.db.zst");
+        assert_eq!(std::fs::read(&target).unwrap(), b"new build");
+
+        // Over an older upload, leaving no partial file behind
+        std::fs::write(&target, b"old build").unwrap();
+        backend
+            .upload(&source, "v42/english-dict.db.zst", &options)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&target).unwrap(), b"new build");
+        let names: Vec<_> = std::fs::read_dir(target_dir.path().join("v42"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["english-dict.db.zst"]);
+
+        // A missing source fails before anything is written
+        assert!(backend
+            .upload(&source_dir.path().join("missing"), "missing", &options)
+            .await
+            .is_err());
+        assert!(!target_dir.path().join("missing.partial").exists());
+    }
+}
@@ -0,0 +1,514 @@
+//! S3-compatible object storage (Spaces, AWS S3, R2, GCS)
+//!
+//! Files are streamed from disk in fixed-size parts using S3 multipart
+//! uploads, so multi-GB databases never have to fit in memory. Every request
+//! is retried with exponential backoff, which lets uploads survive flaky
+//! connections without restarting from scratch.
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use indicatif::ProgressBar;
+use s3::bucket::Bucket;
+use s3::creds::Credentials;
+use s3::region::Region;
+use s3::serde_types::Part;
+
+use super::{prefixed_key, progress_bar, with_retry, UploadBackend, UploadOptions};
+
+/// Smallest part size S3 accepts for all but the last part
+pub const MIN_PART_SIZE_MB: u64 = 5;
+
+/// Content type used for uploaded databases
+const CONTENT_TYPE: &str = "application/octet-stream";
+
+/// An S3-compatible bucket
+#[derive(Clone)]
+pub struct S3Backend {
+    /// Provider name, for logs
+    provider: &'static str,
+    bucket_name: String,
+    region: String,
+    endpoint: String,
+    /// Key prefix taken from the URL path
+    prefix: String,
+    /// Send `x-amz-acl: public-read` (not supported by every provider)
+    public_read: bool,
+}
+
+impl S3Backend {
+    /// `https://{bucket}.{region}.digitaloceanspaces.com[/prefix]`
+    pub fn digitalocean_spaces(url: &url::Url) -> Result<Self> {
+        let host = url.host_str().context("Upload URL missing host")?;
+
+        let parts: Vec<&str> = host.split('.').collect();
+        if parts.len() < 3 {
+            anyhow::bail!(
+                "Spaces URL must be in format: https://{{bucket}}.{{region}}.digitaloceanspaces.com"
+            );
+        }
+
+        Ok(Self {
+            provider: "DigitalOcean Spaces",
+            bucket_name: parts[0].to_string(),
+            region: parts[1].to_string(),
+            endpoint: format!("https://{}.digitaloceanspaces.com", parts[1]),
+            prefix: url.path().to_string(),
+            public_read: true,
+        })
+    }
+
+    /// `s3://{bucket}[/prefix]?region={region}&endpoint={url}&acl=public-read`
+    ///
+    /// Without `endpoint`, the AWS endpoint for the region is used.
+    pub fn generic(url: &url::Url) -> Result<Self> {
+        let bucket_name = url.host_str().context("S3 URL missing bucket")?;
+        let query: std::collections::HashMap<_, _> = url.query_pairs().collect();
+
+        let region = query
+            .get("region")
+            .map(|r| r.to_string())
+            .unwrap_or_else(|| "us-east-1".to_string());
+        let endpoint = query
+            .get("endpoint")
+            .map(|e| e.to_string())
+            .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region));
+
+        Ok(Self {
+            provider: "S3",
+            bucket_name: bucket_name.to_string(),
+            region,
+            endpoint,
+            prefix: url.path().to_string(),
+            public_read: query.get("acl").map(|acl| acl == "public-read") == Some(true),
+        })
+    }
+
+    /// `r2://{account_id}/{bucket}[/prefix]`
+    pub fn cloudflare_r2(url: &url::Url) -> Result<Self> {
+        let account_id = url.host_str().context("R2 URL missing account id")?;
+        let path = url.path().trim_start_matches('/');
+        let (bucket_name, prefix) = path.split_once('/').unwrap_or((path, ""));
+        if bucket_name.is_empty() {
+            anyhow::bail!("R2 URL must be in format: r2://{{account_id}}/{{bucket}}");
+        }
+
+        Ok(Self {
+            provider: "Cloudflare R2",
+            bucket_name: bucket_name.to_string(),
+            region: "auto".to_string(),
+            endpoint: format!("https://{}.r2.cloudflarestorage.com", account_id),
+            prefix: prefix.to_string(),
+            // R2 has no object ACLs; public access is configured per bucket
+            public_read: false,
+        })
+    }
+
+    /// `gs://{bucket}[/prefix]` via the GCS XML (S3 interoperability) API
+    pub fn google_cloud_storage(url: &url::Url) -> Result<Self> {
+        let bucket_name = url.host_str().context("GCS URL missing bucket")?;
+
+        Ok(Self {
+            provider: "Google Cloud Storage",
+            bucket_name: bucket_name.to_string(),
+            region: "auto".to_string(),
+            endpoint: "https://storage.googleapis.com".to_string(),
+            prefix: url.path().to_string(),
+            public_read: false,
+        })
+    }
+
+    /// Create a bucket handle using the CDN_* credentials
+    fn open_bucket(&self) -> Result<Box<Bucket>> {
+        // Read credentials from environment
+        let access_key_id = std::env::var("CDN_ACCESS_KEY_ID")
+            .context("CDN_ACCESS_KEY_ID not set in environment")?;
+        let secret_access_key =
+            std::env::var("CDN_ACCESS_KEY").context("CDN_ACCESS_KEY not set in environment")?;
+
+        let credentials = Credentials::new(
+            Some(&access_key_id),
+            Some(&secret_access_key),
+            None,
+            None,
+            None,
+        )?;
+
+        let region = Region::Custom {
+            region: self.region.clone(),
+            endpoint: self.endpoint.clone(),
+        };
+
+        let mut bucket = Bucket::new(&self.bucket_name, region, credentials)?.with_path_style();
+        if self.public_read {
+            bucket.add_header("x-amz-acl", "public-read");
+        }
+
+        Ok(bucket)
+    }
+}
+
+#[async_trait]
+impl UploadBackend for S3Backend {
+    fn describe(&self) -> String {
+        format!(
+            "{} bucket '{}' at {}",
+            self.provider, self.bucket_name, self.endpoint
+        )
+    }
+
+    /// Files no larger than one part are sent with a single PUT; anything
+    /// larger uses a multipart upload, which is aborted if a part ultimately
+    /// fails.
+    async fn upload(&self, file_path: &Path, key: &str, options: &UploadOptions) -> Result<()> {
+        let bucket = self.open_bucket()?;
+        let key = self.object_key(key);
+
+        let file_size = std::fs::metadata(file_path)
+            .context("Failed to stat file for upload")?
+            .len();
+        let pb = progress_bar(file_size, options.quiet)?;
+
+        if file_size <= options.part_size {
+            let contents = std::fs::read(file_path).context("Failed to read file for upload")?;
+            with_retry("upload", options.max_retries, || async {
+                let response = bucket
+                    .put_object_with_content_type(&key, &contents, CONTENT_TYPE)
+                    .await?;
+                if response.status_code() >= 300 {
+                    anyhow::bail!(
+                        "status {}: {}",
+                        response.status_code(),
+                        String::from_utf8_lossy(response.as_slice())
+                    );
+                }
+                Ok(())
+            })
+            .await?;
+            pb.inc(file_size);
+        } else {
+            upload_multipart(bucket.as_ref(), file_path, &key, options, &pb).await?;
+        }
+
+        pb.finish_and_clear();
+        Ok(())
+    }
+
+    fn object_key(&self, key: &str) -> String {
+        prefixed_key(&self.prefix, key)
+    }
+
+    fn at_root(&self) -> Box<dyn UploadBackend> {
+        Box::new(Self {
+            prefix: String::new(),
+            ..self.clone()
+        })
+    }
+}
+
+/// The requests a multipart upload is made of
+///
+/// Implemented by [`Bucket`]; kept separate so the retry and abort
+/// handling in [`upload_multipart`] can be tested without a server.
+#[async_trait]
+trait MultipartTarget: Sync {
+    async fn initiate(&self, key: &str) -> Result<String>;
+
+    async fn put_part(
+        &self,
+        chunk: Vec<u8>,
+        key: &str,
+        part_number: u32,
+        upload_id: &str,
+    ) -> Result<Part>;
+
+    async fn complete(&self, key: &str, upload_id: &str, parts: Vec<Part>) -> Result<()>;
+
+    async fn abort(&self, key: &str, upload_id: &str) -> Result<()>;
+}
+
+#[async_trait]
+impl MultipartTarget for Bucket {
+    async fn initiate(&self, key: &str) -> Result<String> {
+        Ok(self
+            .initiate_multipart_upload(key, CONTENT_TYPE)
+            .await?
+            .upload_id)
+    }
+
+    async fn put_part(
+        &self,
+        chunk: Vec<u8>,
+        key: &str,
+        part_number: u32,
+        upload_id: &str,
+    ) -> Result<Part> {
+        Ok(self
+            .put_multipart_chunk(chunk, key, part_number, upload_id, CONTENT_TYPE)
+            .await?)
+    }
+
+    async fn complete(&self, key: &str, upload_id: &str, parts: Vec<Part>) -> Result<()> {
+        let response = self
+            .complete_multipart_upload(key, upload_id, parts)
+            .await?;
+        if response.status_code() >= 300 {
+            anyhow::bail!(
+                "status {}: {}",
+                response.status_code(),
+                String::from_utf8_lossy(response.as_slice())
+            );
+        }
+        Ok(())
+    }
+
+    async fn abort(&self, key: &str, upload_id: &str) -> Result<()> {
+        Ok(self.abort_upload(key, upload_id).await?)
+    }
+}
+
+/// Stream a file to the bucket as a multipart upload
+async fn upload_multipart(
+    target: &impl MultipartTarget,
+    file_path: &Path,
+    key: &str,
+    options: &UploadOptions,
+    pb: &ProgressBar,
+) -> Result<()> {
+    let upload_id = with_retry("initiate multipart upload", options.max_retries, || {
+        target.initiate(key)
+    })
+    .await?;
+
+    log::info!(
+        "Started multipart upload of '{}' in {} byte parts",
+        key,
+        options.part_size
+    );
+
+    let result = match upload_parts(target, file_path, key, &upload_id, options, pb).await {
+        Ok(parts) => {
+            with_retry("complete multipart upload", options.max_retries, || {
+                target.complete(key, &upload_id, parts.clone())
+            })
+            .await
+        }
+        Err(e) => Err(e),
+    };
+    if result.is_err() {
+        // Don't leave orphaned parts behind (they are billed as storage),
+        // whether a part or the completion failed
+        if let Err(abort_err) = target.abort(key, &upload_id).await {
+            log::warn!("Failed to abort multipart upload: {}", abort_err);
+        }
+    }
+    result
+}
+
+/// Read the file part by part and upload each one
+async fn upload_parts(
+    target: &impl MultipartTarget,
+    file_path: &Path,
+    key: &str,
+    upload_id: &str,
+    options: &UploadOptions,
+    pb: &ProgressBar,
+) -> Result<Vec<Part>> {
+    let file = File::open(file_path).context("Failed to open file for upload")?;
+    let mut reader = BufReader::new(file);
+    let mut parts = Vec::new();
+    let mut part_number = 1;
+
+    loop {
+        let mut chunk = Vec::with_capacity(options.part_size as usize);
+        (&mut reader)
+            .take(options.part_size)
+            .read_to_end(&mut chunk)
+            .context("Failed to read file for upload")?;
+        if chunk.is_empty() {
+            break;
+        }
+
+        let chunk_len = chunk.len() as u64;
+        let what = format!("upload part {}", part_number);
+        let part = with_retry(&what, options.max_retries, || {
+            target.put_part(chunk.clone(), key, part_number, upload_id)
+        })
+        .await?;
+
+        parts.push(part);
+        pb.inc(chunk_len);
+        part_number += 1;
+    }
+
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records requests; part `fail_part` fails its first `failures` tries,
+    /// and completing fails if `fail_complete` is set
+    #[derive(Default)]
+    struct FakeTarget {
+        fail_part: u32,
+        failures: u32,
+        fail_complete: bool,
+        requests: Mutex<Vec<String>>,
+    }
+
+    impl FakeTarget {
+        fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl MultipartTarget for FakeTarget {
+        async fn initiate(&self, _key: &str) -> Result<String> {
+            self.requests.lock().unwrap().push("initiate".to_string());
+            Ok("upload-1".to_string())
+        }
+
+        async fn put_part(
+            &self,
+            chunk: Vec<u8>,
+            _key: &str,
+            part_number: u32,
+            upload_id: &str,
+        ) -> Result<Part> {
+            assert_eq!(upload_id, "upload-1");
+            let mut requests = self.requests.lock().unwrap();
+            requests.push(format!("part {} ({} bytes)", part_number, chunk.len()));
+            let tries = requests
+                .iter()
+                .filter(|r| r.starts_with(&format!("part {} ", part_number)))
+                .count() as u32;
+            if part_number == self.fail_part && tries <= self.failures {
+                anyhow::bail!("connection reset");
+            }
+            Ok(Part {
+                part_number,
+                etag: format!("etag-{}", part_number),
+            })
+        }
+
+        async fn complete(&self, _key: &str, _upload_id: &str, parts: Vec<Part>) -> Result<()> {
+            self.requests
+                .lock()
+                .unwrap()
+                .push(format!("complete {}", parts.len()));
+            if self.fail_complete {
+                anyhow::bail!("internal error");
+            }
+            Ok(())
+        }
+
+        async fn abort(&self, _key: &str, upload_id: &str) -> Result<()> {
+            self.requests
+                .lock()
+                .unwrap()
+                .push(format!("abort {}", upload_id));
+            Ok(())
+        }
+    }
+
+    fn upload_file(dir: &tempfile::TempDir, size: usize) -> std::path::PathBuf {
+        let path = dir.path().join("dict.db.zst");
+        std::fs::write(&path, vec![7u8; size]).unwrap();
+        path
+    }
+
+    fn options(max_retries: u32) -> UploadOptions {
+        UploadOptions {
+            part_size: 10,
+            max_retries,
+            quiet: true,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_multipart_retries_failed_part() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = upload_file(&dir, 25);
+        let target = FakeTarget {
+            fail_part: 2,
+            failures: 2,
+            ..FakeTarget::default()
+        };
+        let pb = ProgressBar::hidden();
+        upload_multipart(&target, &path, "key", &options(2), &pb)
+            .await
+            .unwrap();
+        assert_eq!(
+            target.requests(),
+            [
+                "initiate",
+                "part 1 (10 bytes)",
+                "part 2 (10 bytes)",
+                "part 2 (10 bytes)",
+                "part 2 (10 bytes)",
+                "part 3 (5 bytes)",
+                "complete 3",
+            ]
+        );
+        assert_eq!(pb.position(), 25);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_multipart_aborts_after_part_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = upload_file(&dir, 25);
+        let target = FakeTarget {
+            fail_part: 2,
+            failures: u32::MAX,
+            ..FakeTarget::default()
+        };
+        let err = upload_multipart(&target, &path, "key", &options(1), &ProgressBar::hidden())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("upload part 2 after 2 attempts"));
+        assert_eq!(
+            target.requests(),
+            [
+                "initiate",
+                "part 1 (10 bytes)",
+                "part 2 (10 bytes)",
+                "part 2 (10 bytes)",
+                "abort upload-1",
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_multipart_aborts_after_complete_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = upload_file(&dir, 15);
+        let target = FakeTarget {
+            fail_complete: true,
+            ..FakeTarget::default()
+        };
+        let err = upload_multipart(&target, &path, "key", &options(1), &ProgressBar::hidden())
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("complete multipart upload after 2 attempts"));
+        assert_eq!(
+            target.requests(),
+            [
+                "initiate",
+                "part 1 (10 bytes)",
+                "part 2 (5 bytes)",
+                "complete 2",
+                "complete 2",
+                "abort upload-1",
+            ]
+        );
+    }
+}